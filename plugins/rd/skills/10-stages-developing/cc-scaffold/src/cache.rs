@@ -0,0 +1,324 @@
+//! Cache of compiled templates.
+//!
+//! Compiling a template parses it and every partial and base template it
+//! includes, resolved against its include directories. Agents often run
+//! `cc-scaffold` over the same templates again and again, so the result is
+//! kept as JSON under `cache/compiled` in [`locator::agents_home`], named
+//! after a hash of the template source, its syntax, its include directories
+//! and the cc-scaffold version. An entry is only used while every include
+//! still resolves to the same file with the same content, so editing a
+//! partial recompiles the templates that use it.
+//!
+//! The cache is only an optimisation: entries that cannot be read or
+//! written are ignored, and setting [`NO_CACHE_ENV`] bypasses it. Only the
+//! [`KEEP`] most recently used entries are kept.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::digest::{self, Sha256};
+use crate::error::{Error, Result};
+use crate::locator;
+use crate::template_engine::{Template, TemplateBackend};
+
+/// Environment variable that, when set to anything but an empty string,
+/// disables the cache.
+pub const NO_CACHE_ENV: &str = "CC_SCAFFOLD_NO_CACHE";
+
+/// Number of entries kept; the least recently used are removed.
+pub const KEEP: usize = 256;
+
+/// A parsed template with the partials and base templates it includes,
+/// directly or through each other.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Compiled {
+    pub template: Template,
+    /// Included files, in the order they were found.
+    pub includes: Vec<Include>,
+    /// Names that did not resolve to a file, left for rendering to report.
+    pub unresolved: Vec<String>,
+}
+
+/// A partial or base template a [`Compiled`] template includes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Include {
+    /// Name it is included by, e.g. `partials/test-header`.
+    pub name: String,
+    pub path: PathBuf,
+    /// Content hash of the file when it was compiled.
+    pub hash: String,
+    pub template: Template,
+}
+
+/// The compiled template cache, `cache/compiled` under
+/// [`locator::agents_home`], unless [`NO_CACHE_ENV`] disables it. Unit
+/// tests never use it, so they leave the user's cache alone.
+pub fn cache_dir() -> Option<PathBuf> {
+    if cfg!(test) || env::var_os(NO_CACHE_ENV).is_some_and(|v| !v.is_empty()) {
+        return None;
+    }
+    Some(locator::agents_home()?.join("cache/compiled"))
+}
+
+/// Compiles `source`, read from `path`, with `backend`, looking includes up
+/// in `dirs`. Uses and fills the cache in [`cache_dir`].
+pub fn compile(
+    backend: &dyn TemplateBackend,
+    path: &Path,
+    source: &str,
+    dirs: &[PathBuf],
+) -> Result<Compiled> {
+    compile_in(cache_dir().as_deref(), backend, path, source, dirs)
+}
+
+/// [`compile`] with the cache in `cache`, or without one.
+pub fn compile_in(
+    cache: Option<&Path>,
+    backend: &dyn TemplateBackend,
+    path: &Path,
+    source: &str,
+    dirs: &[PathBuf],
+) -> Result<Compiled> {
+    let entry = cache.map(|cache| cache.join(format!("{}.json", key(backend, source, dirs))));
+    if let Some(compiled) = entry.as_deref().and_then(|entry| load(entry, dirs)) {
+        return Ok(compiled.with_files(path));
+    }
+    let compiled = resolve(backend, path, source, dirs)?;
+    if let Some(entry) = &entry {
+        store(entry, &compiled);
+    }
+    Ok(compiled)
+}
+
+impl Compiled {
+    /// Reattaches the file names serialization drops, so errors name them.
+    fn with_files(mut self, path: &Path) -> Self {
+        self.template = self.template.with_file(path);
+        for include in &mut self.includes {
+            include.template = include.template.clone().with_file(&include.path);
+        }
+        self
+    }
+}
+
+/// Parses the template and every file it includes.
+fn resolve(
+    backend: &dyn TemplateBackend,
+    path: &Path,
+    source: &str,
+    dirs: &[PathBuf],
+) -> Result<Compiled> {
+    let template = backend
+        .parse(source)
+        .map_err(|err| err.in_file(path))?
+        .with_file(path);
+    let mut includes: Vec<Include> = Vec::new();
+    let mut unresolved = Vec::new();
+    let mut pending: Vec<String> = template.includes().into_iter().map(String::from).collect();
+    while let Some(name) = pending.pop() {
+        if includes.iter().any(|i| i.name == name) || unresolved.contains(&name) {
+            continue;
+        }
+        let Some(path) = locator::locate_include(dirs, &name)? else {
+            unresolved.push(name);
+            continue;
+        };
+        let source = fs::read_to_string(&path).map_err(|err| Error::io(&path, err))?;
+        let partial = backend
+            .parse(&source)
+            .map_err(|err| err.in_file(&path))?
+            .with_file(&path);
+        pending.extend(partial.includes().into_iter().map(String::from));
+        includes.push(Include {
+            name,
+            hash: digest::sha256(source.as_bytes()),
+            path,
+            template: partial,
+        });
+    }
+    Ok(Compiled {
+        template,
+        includes,
+        unresolved,
+    })
+}
+
+/// Name of the cache entry for `source`.
+fn key(backend: &dyn TemplateBackend, source: &str, dirs: &[PathBuf]) -> String {
+    let mut hasher = Sha256::new();
+    let dirs: Vec<_> = dirs.iter().map(|dir| dir.to_string_lossy()).collect();
+    let parts = [
+        env!("CARGO_PKG_VERSION"),
+        backend.name(),
+        &dirs.join("\n"),
+        source,
+    ];
+    for part in parts {
+        hasher.update(&(part.len() as u64).to_be_bytes());
+        hasher.update(part.as_bytes());
+    }
+    digest::hex(&hasher.finish())
+}
+
+/// The entry at `entry`, if it exists and its includes are unchanged,
+/// marked as just used.
+fn load(entry: &Path, dirs: &[PathBuf]) -> Option<Compiled> {
+    let compiled: Compiled = serde_json::from_slice(&fs::read(entry).ok()?).ok()?;
+    let resolves_to = |name: &str| locator::locate_include(dirs, name).ok().flatten();
+    let fresh = compiled.includes.iter().all(|include| {
+        resolves_to(&include.name).as_deref() == Some(include.path.as_path())
+            && fs::read(&include.path).is_ok_and(|bytes| digest::sha256(&bytes) == include.hash)
+    }) && compiled
+        .unresolved
+        .iter()
+        .all(|name| resolves_to(name).is_none());
+    if fresh {
+        let _ = fs::File::options()
+            .write(true)
+            .open(entry)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+    }
+    fresh.then_some(compiled)
+}
+
+/// Writes `compiled` to `entry` through a temporary file, so concurrent
+/// runs never see half an entry, then prunes the cache to [`KEEP`]
+/// entries. Failures are ignored.
+fn store(entry: &Path, compiled: &Compiled) {
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    let (Some(dir), Ok(json)) = (entry.parent(), serde_json::to_vec(compiled)) else {
+        return;
+    };
+    let tmp = entry.with_extension(format!(
+        "{}-{}.tmp",
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let written = fs::create_dir_all(dir)
+        .and_then(|()| fs::write(&tmp, json))
+        .and_then(|()| fs::rename(&tmp, entry));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    prune(dir, KEEP);
+}
+
+/// Removes all but the `keep` most recently used entries in `dir`.
+/// Failures are ignored.
+fn prune(dir: &Path, keep: usize) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = entry.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            Some((entry.metadata().ok()?.modified().ok()?, path))
+        })
+        .collect();
+    if entries.len() <= keep {
+        return;
+    }
+    entries.sort_by_key(|&(modified, _)| std::cmp::Reverse(modified));
+    for (_, path) in entries.into_iter().skip(keep) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template_engine::backend::Native;
+
+    #[test]
+    fn test_compile_reuses_entry_until_includes_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("cache");
+        let templates = dir.path().join("templates");
+        fs::create_dir_all(templates.join("partials")).unwrap();
+        let header = templates.join("partials/header.rs");
+        fs::write(&header, "// header\n").unwrap();
+        let path = templates.join("f-template.rs");
+        let source = "{{> partials/header}}\n{{> partials/footer}}\nfn f() {}\n";
+        let dirs = [templates.clone()];
+
+        let compiled = compile_in(Some(&cache), &Native, &path, source, &dirs).unwrap();
+        assert_eq!(compiled.includes.len(), 1);
+        assert_eq!(compiled.unresolved, ["partials/footer"]);
+        assert_eq!(fs::read_dir(&cache).unwrap().count(), 1);
+        let cached = compile_in(Some(&cache), &Native, &path, source, &dirs).unwrap();
+        assert_eq!(cached, compiled);
+        assert_eq!(cached.template.file(), Some(path.as_path()));
+        assert_eq!(cached.includes[0].template.file(), Some(header.as_path()));
+
+        // Edited and newly added includes invalidate the entry.
+        fs::write(&header, "// {{#if x}}new{{/if}}\n").unwrap();
+        let edited = compile_in(Some(&cache), &Native, &path, source, &dirs).unwrap();
+        assert_ne!(edited.includes[0].template, compiled.includes[0].template);
+        fs::write(templates.join("partials/footer.rs"), "// footer\n").unwrap();
+        let added = compile_in(Some(&cache), &Native, &path, source, &dirs).unwrap();
+        assert_eq!(added.includes.len(), 2);
+        assert!(added.unresolved.is_empty());
+
+        // A corrupt entry is recompiled and replaced.
+        for entry in fs::read_dir(&cache).unwrap() {
+            fs::write(entry.unwrap().path(), "{").unwrap();
+        }
+        let recompiled = compile_in(Some(&cache), &Native, &path, source, &dirs).unwrap();
+        assert_eq!(recompiled, added);
+        assert_eq!(
+            compile_in(Some(&cache), &Native, &path, source, &dirs).unwrap(),
+            added
+        );
+    }
+
+    #[test]
+    fn test_prune_keeps_most_recently_used_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let epoch = SystemTime::UNIX_EPOCH;
+        for (name, age) in [("a", 3), ("b", 1), ("c", 2), ("d", 4)] {
+            let path = dir.path().join(format!("{name}.json"));
+            fs::write(&path, "{}").unwrap();
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(epoch + std::time::Duration::from_secs(100 - age))
+                .unwrap();
+        }
+        fs::write(dir.path().join("e.json.1-0.tmp"), "").unwrap();
+
+        prune(dir.path(), 2);
+        let mut left: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["b.json", "c.json", "e.json.1-0.tmp"]);
+
+        // Loading an entry marks it as used, so pruning keeps it.
+        let templates = dir.path().join("templates");
+        let cache = dir.path().join("cache");
+        let path = templates.join("f-template.rs");
+        compile_in(Some(&cache), &Native, &path, "fn f() {}\n", &[]).unwrap();
+        let entry = fs::read_dir(&cache)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        fs::File::options()
+            .write(true)
+            .open(&entry)
+            .unwrap()
+            .set_modified(epoch)
+            .unwrap();
+        compile_in(Some(&cache), &Native, &path, "fn f() {}\n", &[]).unwrap();
+        assert!(fs::metadata(&entry).unwrap().modified().unwrap() > epoch);
+    }
+}
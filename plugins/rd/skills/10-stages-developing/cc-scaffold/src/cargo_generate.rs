@@ -0,0 +1,437 @@
+//! Compatibility with [cargo-generate] project templates.
+//!
+//! A directory containing `cargo-generate.toml` is a project template: every
+//! file under it is rendered into a destination directory, parsed with the
+//! [`Liquid`] backend: `{{ project-name | upper_camel_case }}`, `{% if %}`,
+//! `{% unless %}`, `{% for %}`, `{% raw %}`, `{% comment %}` and `{%- -%}`
+//! whitespace control.
+//!
+//! `[placeholders]` become variables, and the built-in `project-name`,
+//! `crate_name`, `crate_type`, `authors` and `username` are provided as in
+//! cargo-generate. File and directory names are rendered too, a `.liquid`
+//! suffix is dropped, `[template] ignore` patterns are skipped and `exclude`
+//! patterns copied verbatim. Hooks, `[conditional]` sections, placeholder
+//! regexes and other Liquid tags are not supported; hooks and conditionals
+//! are reported by [`CargoTemplate::unsupported`].
+//!
+//! Hyphens are not valid in native variable names, so `project-name`
+//! becomes `project_name`; `--set project-name=...` is accepted as well.
+//!
+//! [cargo-generate]: https://github.com/cargo-generate/cargo-generate
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::generate::{self, RenderedFile};
+use crate::template_engine::backend::{Liquid, TemplateBackend};
+use crate::template_engine::Context;
+use crate::variables::{self, EnvAccess, VariableKind, VariableSpec};
+
+/// The file marking a directory as a cargo-generate template.
+pub const CONFIG_FILE: &str = "cargo-generate.toml";
+
+/// Whether `dir` is a cargo-generate template.
+pub fn is_template(dir: &Path) -> bool {
+    dir.join(CONFIG_FILE).is_file()
+}
+
+/// A loaded cargo-generate template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CargoTemplate {
+    /// The template directory.
+    pub root: PathBuf,
+    /// Built-in variables followed by the template's placeholders.
+    pub variables: Vec<VariableSpec>,
+    /// cargo-generate features the template uses that are not supported.
+    pub unsupported: Vec<String>,
+    ignore: Vec<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawConfig {
+    #[serde(default)]
+    template: RawTemplate,
+    #[serde(default)]
+    placeholders: BTreeMap<String, RawPlaceholder>,
+    hooks: Option<toml::Value>,
+    conditional: Option<toml::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawTemplate {
+    #[serde(default)]
+    ignore: Vec<String>,
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawPlaceholder {
+    #[serde(rename = "type", default = "default_type")]
+    kind: String,
+    prompt: Option<String>,
+    choices: Option<Vec<String>>,
+    default: Option<toml::Value>,
+}
+
+fn default_type() -> String {
+    "string".to_string()
+}
+
+/// A rendered project: text files to write and files copied as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    pub files: Vec<RenderedFile>,
+    /// `(template file, destination)` pairs for binary and excluded files.
+    pub copies: Vec<(PathBuf, PathBuf)>,
+}
+
+impl CargoTemplate {
+    /// Reads `cargo-generate.toml` in `root`.
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(CONFIG_FILE);
+        let text = fs::read_to_string(&path).map_err(|err| Error::io(&path, err))?;
+        let invalid = |message: String| Error::Manifest {
+            path: path.clone(),
+            message,
+        };
+        let raw: RawConfig = toml::from_str(&text).map_err(|err| invalid(err.to_string()))?;
+        let mut variables = builtin_variables();
+        for (name, placeholder) in raw.placeholders {
+            let kind = match (placeholder.kind.as_str(), placeholder.choices) {
+                ("string", Some(choices)) => VariableKind::Enum(choices),
+                ("string", None) => VariableKind::String,
+                ("bool", _) => VariableKind::Bool,
+                (other, _) => {
+                    return Err(invalid(format!(
+                        "placeholder `{name}` has unsupported type `{other}`"
+                    )))
+                }
+            };
+            let name = variable_name(&name);
+            let mut spec = VariableSpec::new(name.clone(), kind);
+            spec.description = placeholder.prompt;
+            spec.default = placeholder.default.map(|value| match value {
+                toml::Value::String(s) => s,
+                other => other.to_string(),
+            });
+            variables.retain(|v| v.name != name);
+            variables.push(spec);
+        }
+        let mut unsupported = Vec::new();
+        if raw.hooks.is_some() {
+            unsupported.push("hooks".to_string());
+        }
+        if raw.conditional.is_some() {
+            unsupported.push("[conditional] sections".to_string());
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+            variables,
+            unsupported,
+            ignore: raw.template.ignore,
+            include: raw.template.include,
+            exclude: raw.template.exclude,
+        })
+    }
+
+    /// Renders every template file into `destination` with raw `values`,
+    /// reading the environment `env` allows.
+    pub fn render(
+        &self,
+        destination: &Path,
+        values: &BTreeMap<String, String>,
+        env: &EnvAccess,
+    ) -> Result<Project> {
+        let values: BTreeMap<String, String> = values
+            .iter()
+            .map(|(key, value)| (variable_name(key), value.clone()))
+            .collect();
+        let declared: BTreeMap<String, String> = values
+            .iter()
+            .filter(|(key, _)| self.variables.iter().any(|v| &v.name == *key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let mut ctx = variables::bind(&self.variables, &declared, env, "", None)?;
+        for (key, value) in &values {
+            if !declared.contains_key(key) {
+                ctx.set(key.as_str(), value.as_str());
+            }
+        }
+        let mut project = Project {
+            files: Vec::new(),
+            copies: Vec::new(),
+        };
+        for source in self.files()? {
+            let relative = source.strip_prefix(&self.root).expect("walked from root");
+            let relative_text = relative.to_string_lossy().replace('\\', "/");
+            let target = render_liquid(&relative_text, &source, &ctx)?;
+            let target = target.strip_suffix(".liquid").unwrap_or(&target);
+            let dest = destination.join(target);
+            let verbatim = matches_any(&self.exclude, &relative_text)
+                || (!self.include.is_empty() && !matches_any(&self.include, &relative_text));
+            let text = match fs::read(&source).map_err(|err| Error::io(&source, err))? {
+                bytes if verbatim => Err(bytes),
+                bytes => String::from_utf8(bytes).map_err(|err| err.into_bytes()),
+            };
+            match text {
+                Ok(text) => project.files.push(RenderedFile {
+                    template: source.clone(),
+                    destination: dest,
+                    contents: render_liquid(&text, &source, &ctx)?,
+                    inputs: vec![source],
+                }),
+                Err(_) => project.copies.push((source, dest)),
+            }
+        }
+        Ok(project)
+    }
+
+    /// Every file of the template that is not ignored, sorted.
+    fn files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut pending = vec![self.root.clone()];
+        while let Some(dir) = pending.pop() {
+            let entries = fs::read_dir(&dir).map_err(|err| Error::io(&dir, err))?;
+            for entry in entries {
+                let path = entry.map_err(|err| Error::io(&dir, err))?.path();
+                let relative = path.strip_prefix(&self.root).expect("walked from root");
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                if relative == ".git"
+                    || relative == CONFIG_FILE
+                    || matches_any(&self.ignore, &relative)
+                {
+                    continue;
+                }
+                if path.is_dir() {
+                    pending.push(path);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+impl Project {
+    /// Every template file the project was produced from.
+    pub fn inputs(&self) -> Vec<PathBuf> {
+        let mut inputs: Vec<PathBuf> = self
+            .files
+            .iter()
+            .map(|f| f.template.clone())
+            .chain(self.copies.iter().map(|(from, _)| from.clone()))
+            .collect();
+        inputs.sort();
+        inputs
+    }
+
+    /// Writes every file. Nothing is written if any destination exists and
+    /// `overwrite` is not set.
+    pub fn write(&self, overwrite: bool) -> Result<()> {
+        if !overwrite {
+            let existing = self
+                .files
+                .iter()
+                .map(|f| &f.destination)
+                .chain(self.copies.iter().map(|(_, to)| to))
+                .find(|dest| dest.exists());
+            if let Some(dest) = existing {
+                return Err(Error::DestinationExists(dest.clone()));
+            }
+        }
+        for file in &self.files {
+            generate::write(file, true)?;
+        }
+        for (from, to) in &self.copies {
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent).map_err(|err| Error::io(parent, err))?;
+            }
+            fs::copy(from, to).map_err(|err| Error::io(from, err))?;
+        }
+        Ok(())
+    }
+}
+
+/// The variables cargo-generate always defines.
+fn builtin_variables() -> Vec<VariableSpec> {
+    let mut project_name = VariableSpec::new("project_name", VariableKind::String);
+    project_name.description = Some("Name of the generated project".to_string());
+    let mut crate_name = VariableSpec::new("crate_name", VariableKind::String);
+    crate_name.description = Some("Crate name, derived from the project name".to_string());
+    crate_name.default = Some("{{ project_name | snake_case }}".to_string());
+    let mut crate_type = VariableSpec::new(
+        "crate_type",
+        VariableKind::Enum(vec!["bin".to_string(), "lib".to_string()]),
+    );
+    crate_type.default = Some("bin".to_string());
+    let name = git_config("user.name");
+    let email = git_config("user.email");
+    let mut authors = VariableSpec::new("authors", VariableKind::String);
+    authors.default = Some(match (&name, email) {
+        (Some(name), Some(email)) => format!("{name} <{email}>"),
+        (Some(name), None) => name.clone(),
+        _ => String::new(),
+    });
+    let mut username = VariableSpec::new("username", VariableKind::String);
+    username.default = Some(name.unwrap_or_default());
+    vec![project_name, crate_name, crate_type, authors, username]
+}
+
+fn git_config(key: &str) -> Option<String> {
+    let output = Command::new("git").args(["config", key]).output().ok()?;
+    let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
+
+/// Native name of a cargo-generate variable.
+pub fn variable_name(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+/// Parses Liquid `source` from `file` and renders it against `ctx`.
+fn render_liquid(source: &str, file: &Path, ctx: &Context) -> Result<String> {
+    Liquid
+        .parse(source)
+        .map_err(|err| err.in_file(file))?
+        .with_file(file)
+        .render(ctx)
+}
+
+/// Whether `path` (relative, `/`-separated) or one of its parent directories
+/// matches one of `patterns`.
+fn matches_any(patterns: &[String], path: &str) -> bool {
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        let mut prefix = String::new();
+        path.split('/').any(|part| {
+            if !prefix.is_empty() {
+                prefix.push('/');
+            }
+            prefix.push_str(part);
+            glob(pattern, &prefix) || (!pattern.contains('/') && glob(pattern, part))
+        })
+    })
+}
+
+/// Matches `text` against a pattern where `*` matches within one path
+/// component, `**` across components and `?` a single character.
+fn glob(pattern: &str, text: &str) -> bool {
+    fn go(p: &[u8], t: &[u8]) -> bool {
+        match p {
+            [] => t.is_empty(),
+            [b'*', b'*', rest @ ..] => {
+                let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+                (0..=t.len()).any(|i| go(rest, &t[i..]))
+            }
+            [b'*', rest @ ..] => (0..=t.len())
+                .take_while(|&i| i == 0 || t[i - 1] != b'/')
+                .any(|i| go(rest, &t[i..])),
+            [b'?', rest @ ..] => matches!(t, [c, tail @ ..] if *c != b'/' && go(rest, tail)),
+            [c, rest @ ..] => matches!(t, [d, tail @ ..] if c == d && go(rest, tail)),
+        }
+    }
+    go(pattern.as_bytes(), text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_patterns() {
+        let patterns = |p: &[&str]| p.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(matches_any(&patterns(&["target"]), "target/debug/x"));
+        assert!(matches_any(&patterns(&["*.orig"]), "src/main.rs.orig"));
+        assert!(matches_any(&patterns(&[".github/*"]), ".github/ci.yml"));
+        assert!(matches_any(&patterns(&["docs/**/*.png"]), "docs/a/b/c.png"));
+        assert!(!matches_any(&patterns(&["src/*.rs"]), "src/bin/x.rs"));
+        assert!(!matches_any(&patterns(&["target"]), "src/target.rs"));
+    }
+
+    #[test]
+    fn test_render_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("template");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("assets")).unwrap();
+        fs::write(
+            root.join(CONFIG_FILE),
+            "[template]\nignore = [\"target\"]\nexclude = [\"assets\"]\n\n\
+             [placeholders.use-serde]\ntype = \"bool\"\nprompt = \"Serde?\"\ndefault = false\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("Cargo.toml.liquid"),
+            "[package]\nname = \"{{project-name}}\"\n{% if use-serde %}serde = \"1\"\n{% endif %}",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/{{crate_name}}.rs"),
+            "pub struct {{ crate_name | pascal_case }};\n",
+        )
+        .unwrap();
+        fs::write(root.join("assets/logo.txt"), "{{ not rendered }}").unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("target/junk"), "").unwrap();
+
+        let template = CargoTemplate::load(&root).unwrap();
+        assert!(template.variables.iter().any(|v| v.name == "use_serde"));
+        let out = dir.path().join("out");
+        let values = BTreeMap::from([
+            ("project-name".to_string(), "my-tool".to_string()),
+            ("use-serde".to_string(), "true".to_string()),
+        ]);
+        let project = template.render(&out, &values, &EnvAccess::All).unwrap();
+        let files: Vec<_> = project
+            .files
+            .iter()
+            .map(|f| {
+                (
+                    f.destination.strip_prefix(&out).unwrap().to_path_buf(),
+                    f.contents.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            files,
+            [
+                (
+                    PathBuf::from("Cargo.toml"),
+                    "[package]\nname = \"my-tool\"\nserde = \"1\"\n"
+                ),
+                (PathBuf::from("src/my_tool.rs"), "pub struct MyTool;\n"),
+            ]
+        );
+        assert_eq!(
+            project.copies,
+            [(root.join("assets/logo.txt"), out.join("assets/logo.txt"))]
+        );
+
+        project.write(false).unwrap();
+        assert_eq!(
+            fs::read_to_string(out.join("assets/logo.txt")).unwrap(),
+            "{{ not rendered }}"
+        );
+        assert!(matches!(
+            project.write(false),
+            Err(Error::DestinationExists(_))
+        ));
+
+        let err = template
+            .render(&out, &BTreeMap::new(), &EnvAccess::All)
+            .unwrap_err();
+        assert!(matches!(err, Error::MissingVariable(name) if name == "project_name"));
+    }
+}
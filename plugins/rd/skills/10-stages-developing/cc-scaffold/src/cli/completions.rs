@@ -0,0 +1,58 @@
+//! `cc-scaffold completions`.
+//!
+//! The script only registers `cc-scaffold` with the shell: to complete a
+//! word, the shell runs `cc-scaffold` again with [`COMPLETE_ENV`] set, and
+//! `main` answers from the command definitions, reading the manifests for
+//! template identifiers. Completions therefore follow the installed binary
+//! and the templates as they change, without regenerating the script.
+
+use std::env;
+use std::io;
+
+use clap::{Args, ValueEnum};
+use clap_complete::env::{Bash, Elvish, EnvCompleter, Fish, Powershell, Zsh};
+
+use cc_scaffold::{Error, Result};
+
+/// Environment variable that makes `cc-scaffold` print completions for the
+/// shell it names instead of running a command.
+pub const COMPLETE_ENV: &str = "COMPLETE";
+
+#[derive(Debug, Args)]
+pub struct CompletionsArgs {
+    /// Shell to print the script for.
+    shell: Shell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+    Elvish,
+}
+
+pub fn run(args: CompletionsArgs) -> Result<()> {
+    let shell: &dyn EnvCompleter = match args.shell {
+        Shell::Bash => &Bash,
+        Shell::Zsh => &Zsh,
+        Shell::Fish => &Fish,
+        Shell::Powershell => &Powershell,
+        Shell::Elvish => &Elvish,
+    };
+    // The shell calls back into this binary, wherever it is run from.
+    let completer = env::current_exe()
+        .map_err(|err| Error::io("cc-scaffold", err))?
+        .to_string_lossy()
+        .into_owned();
+    shell
+        .write_registration(
+            COMPLETE_ENV,
+            "cc-scaffold",
+            "cc-scaffold",
+            &completer,
+            &mut io::stdout(),
+        )
+        .map_err(|err| Error::io("stdout", err))
+}
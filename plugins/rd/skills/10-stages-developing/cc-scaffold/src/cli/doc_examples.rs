@@ -0,0 +1,38 @@
+//! `cc-scaffold doc-examples`.
+
+use std::path::PathBuf;
+
+use clap::Args;
+
+use cc_scaffold::doc_examples::Survey;
+use cc_scaffold::{Locator, Result};
+
+use super::output::{self, Format};
+
+#[derive(Debug, Args)]
+pub struct DocExamplesArgs {
+    /// Rust files, or directories to search for them. Defaults to `src`
+    /// unless `--templates` is given.
+    paths: Vec<PathBuf>,
+
+    /// Also check the Rust files each template under the template roots
+    /// renders with its sample values.
+    #[arg(long)]
+    templates: bool,
+}
+
+/// Checks that every public item has a doc example and every example that
+/// is not run says why, failing the `5-implementation` gate if not.
+pub fn run(locator: &Locator, mut args: DocExamplesArgs, format: Format) -> Result<()> {
+    if args.paths.is_empty() && !args.templates {
+        args.paths.push(PathBuf::from("src"));
+    }
+    let mut survey = Survey::default();
+    for path in &args.paths {
+        survey.add_path(path)?;
+    }
+    if args.templates {
+        survey.add_templates(locator)?;
+    }
+    output::print_gate(format, survey.stage_report())
+}
@@ -0,0 +1,200 @@
+//! `cc-scaffold fuzz`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+
+use cc_scaffold::config::Config;
+use cc_scaffold::fuzz::{self, Target};
+use cc_scaffold::generate::{self, Preview};
+use cc_scaffold::hooks;
+use cc_scaffold::journal::Journal;
+use cc_scaffold::lockfile;
+use cc_scaffold::{Error, Locator, Result};
+
+use super::new;
+use super::output::{self, Action, FileReport, Format, ProjectReport};
+
+/// What the generated files are reported as coming from.
+const TEMPLATE: &str = "rust/fuzz";
+
+#[derive(Debug, Args)]
+pub struct FuzzArgs {
+    /// Function to fuzz: `name` for a free function, `Type::name` for an
+    /// associated function of an inherent `impl`.
+    function: String,
+
+    /// Rust source file defining the function. Defaults to the file under
+    /// `src/` that does.
+    #[arg(long, value_name = "FILE")]
+    file: Option<PathBuf>,
+
+    /// Overwrite a target of the same name.
+    #[arg(short, long)]
+    force: bool,
+
+    /// Print a unified diff of every file instead of writing them.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Format the target with rustfmt before writing it.
+    #[arg(long)]
+    rustfmt: bool,
+
+    /// Ignore `cc-agents.toml`.
+    #[arg(long)]
+    no_config: bool,
+}
+
+/// Adds a target for the function to the crate's `fuzz/` directory,
+/// creating the fuzz crate if there is none, and writes none of the files
+/// unless all can be written.
+pub fn run(
+    locator: &Locator,
+    mut args: FuzzArgs,
+    format: Format,
+    journal: &mut Journal,
+) -> Result<()> {
+    if !args.no_config {
+        args.rustfmt |= Config::discover(Path::new("."))?.hooks.rustfmt;
+    }
+    let file = match args.file.take() {
+        Some(file) => file,
+        None => defining_file(&args.function)?,
+    };
+    let invalid = |message: String| Error::Fuzz {
+        path: file.clone(),
+        message,
+    };
+    let manifest =
+        hooks::crate_manifest(&file).ok_or_else(|| invalid("it is not in a crate".to_string()))?;
+    let root = manifest.parent().unwrap_or(Path::new(""));
+    let package = package_name(&manifest, |path, message| Error::Fuzz { path, message })?;
+    let src = lockfile::absolute(&root.join("src"));
+    let module = lockfile::absolute(&file)
+        .strip_prefix(&src)
+        .ok()
+        .and_then(fuzz::module_path)
+        .filter(|_| src.join("lib.rs").is_file())
+        .ok_or_else(|| {
+            invalid(
+                "it is not part of the crate's library, which is all a fuzz target can call"
+                    .to_string(),
+            )
+        })?;
+    let source = fs::read_to_string(&file).map_err(|err| Error::io(&file, err))?;
+    let target = Target::parse(&source, &args.function).map_err(invalid)?;
+    let fuzz_dir = root.join("fuzz");
+    let mut files = target.render(locator, &fuzz_dir, &package, &module)?;
+    let mut warnings = target.warnings.clone();
+
+    let mut reports = Vec::with_capacity(files.len());
+    for (file, added_to) in &mut files {
+        if args.rustfmt && hooks::is_rust(&file.destination) {
+            file.contents = hooks::rustfmt(&file.destination, &file.contents)?;
+        }
+        let (action, diff) = match generate::preview(file)? {
+            Preview::Unchanged => (Action::Unchanged, None),
+            Preview::Create(diff) | Preview::Overwrite(diff) if *added_to => {
+                (Action::WouldInject, Some(diff))
+            }
+            Preview::Create(diff) => (Action::WouldCreate, Some(diff)),
+            Preview::Overwrite(diff) => (Action::WouldOverwrite, Some(diff)),
+        };
+        reports.push(FileReport {
+            path: file.destination.clone(),
+            action,
+            diff,
+        });
+    }
+    let blocked = reports
+        .iter()
+        .find(|r| r.action == Action::WouldOverwrite && !args.force);
+    if let Some(report) = blocked {
+        if !args.dry_run {
+            return Err(Error::DestinationExists(report.path.clone()));
+        }
+        warnings.push(format!(
+            "{} exists; writing it requires --force",
+            report.path.display()
+        ));
+    }
+    if !args.dry_run {
+        for ((file, _), report) in files.iter().zip(&mut reports) {
+            report.diff = None;
+            report.action = match report.action {
+                Action::WouldCreate => Action::Created,
+                Action::WouldOverwrite => Action::Overwritten,
+                Action::WouldInject => Action::Injected,
+                action => action,
+            };
+            if report.action != Action::Unchanged {
+                journal.record(&file.destination)?;
+                generate::write(file, true)?;
+            }
+        }
+    }
+
+    if format.is_json() {
+        output::print_json(&ProjectReport {
+            template: TEMPLATE,
+            source: &file,
+            destination: &fuzz_dir,
+            files: &reports,
+            workspace_member: None,
+            warnings: &warnings,
+        });
+        return Ok(());
+    }
+    for warning in &warnings {
+        output::warn(format, warning);
+    }
+    new::print_files(&reports);
+    Ok(())
+}
+
+/// The one file under `src/` that defines `function`.
+fn defining_file(function: &str) -> Result<PathBuf> {
+    let src = Path::new("src");
+    let mut found = fuzz::search(src, function)?;
+    let message = match found.len() {
+        1 => return Ok(found.remove(0)),
+        0 => format!("no function `{function}` found; pass --file to name its file"),
+        _ => format!(
+            "`{function}` is defined in {}; pass --file to choose one",
+            found
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    Err(Error::Fuzz {
+        path: src.to_path_buf(),
+        message,
+    })
+}
+
+/// `package.name` of the crate `manifest`, or the command's `invalid`
+/// error for it and why.
+pub(super) fn package_name(
+    manifest: &Path,
+    invalid: impl Fn(PathBuf, String) -> Error,
+) -> Result<String> {
+    let text = fs::read_to_string(manifest).map_err(|err| Error::io(manifest, err))?;
+    let table: toml::Table = text
+        .parse()
+        .map_err(|err| invalid(manifest.to_path_buf(), format!("it does not parse: {err}")))?;
+    table
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+        .map(String::from)
+        .ok_or_else(|| {
+            invalid(
+                manifest.to_path_buf(),
+                "it has no `package.name`".to_string(),
+            )
+        })
+}
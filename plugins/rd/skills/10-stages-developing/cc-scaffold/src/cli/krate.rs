@@ -0,0 +1,95 @@
+//! `cc-scaffold crate`.
+
+use std::path::{Path, PathBuf};
+
+use clap::{Args, ValueEnum};
+
+use cc_scaffold::config::Config;
+use cc_scaffold::journal::Journal;
+use cc_scaffold::workspace;
+use cc_scaffold::{Error, Locator, Result};
+
+use super::new::{self, NewArgs};
+use super::output::Format;
+
+#[derive(Debug, Args)]
+pub struct CrateArgs {
+    /// Package name of the new crate, e.g. `my-tool`.
+    name: String,
+
+    /// Generate a library, a binary whose logic lives in a library so
+    /// tests, benchmarks and examples can reach it, or an `xtask` crate of
+    /// project automation run with `cargo xtask <task>`.
+    #[arg(long, value_enum, default_value_t)]
+    kind: CrateKind,
+
+    /// Directory to create the crate in. By default `<name>` under the
+    /// directory `cc-agents.toml` configures for the crate template, or
+    /// under the current directory.
+    #[arg(long, value_name = "DIR")]
+    path: Option<PathBuf>,
+
+    /// Set a template variable, e.g. `--set edition=2024` or
+    /// `--set "description=Parses logs"`.
+    #[arg(short, long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// Do not add the crate to the members of the enclosing workspace.
+    #[arg(long)]
+    no_workspace: bool,
+
+    /// Overwrite files that already exist.
+    #[arg(short, long)]
+    force: bool,
+
+    /// Print what would be generated without writing anything.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum CrateKind {
+    #[default]
+    Lib,
+    Bin,
+    Xtask,
+}
+
+impl CrateKind {
+    /// The bundle template the crate is generated from.
+    fn template(self) -> &'static str {
+        match self {
+            Self::Lib => "rust/crate-lib",
+            Self::Bin => "rust/crate-bin",
+            Self::Xtask => "rust/xtask",
+        }
+    }
+}
+
+pub fn run(
+    locator: &Locator,
+    args: CrateArgs,
+    format: Format,
+    journal: &mut Journal,
+) -> Result<()> {
+    if !workspace::is_package_name(&args.name) {
+        return Err(Error::PackageName(args.name));
+    }
+    let template = args.kind.template();
+    let out = match args.path {
+        Some(path) => path,
+        None => Config::discover(Path::new("."))?
+            .output_dir(template)
+            .unwrap_or(Path::new(""))
+            .join(&args.name),
+    };
+    let workspace = if args.no_workspace {
+        None
+    } else {
+        workspace::find(&out)
+    };
+    let mut set = vec![format!("name={}", args.name)];
+    set.extend(args.set);
+    let new_args = NewArgs::for_crate(template, out, set, args.force, args.dry_run, workspace);
+    new::run(locator, new_args, format, journal)
+}
@@ -0,0 +1,78 @@
+//! `cc-scaffold list`.
+
+use clap::Args;
+
+use cc_scaffold::manifest::Filter;
+use cc_scaffold::{Locator, Result};
+
+use super::output::{self, Format, TemplateInfo};
+
+#[derive(Debug, Args)]
+pub struct ListArgs {
+    /// Only templates for this language, e.g. `rust`.
+    #[arg(long = "lang", value_name = "LANGUAGE")]
+    language: Option<String>,
+
+    /// Only templates for this workflow stage, e.g. `5-implementation`,
+    /// `5` or `implementation`.
+    #[arg(long, value_name = "STAGE")]
+    stage: Option<String>,
+
+    /// Only templates with this tag; repeat to require several.
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+}
+
+pub fn run(locator: &Locator, args: ListArgs, format: Format) -> Result<()> {
+    let filter = Filter {
+        language: args.language,
+        stage: args.stage,
+        tags: args.tags,
+    };
+    let manifests: Vec<_> = locator
+        .manifests()?
+        .into_iter()
+        .filter(|m| filter.matches(m))
+        .collect();
+    if format.is_json() {
+        let templates: Vec<_> = manifests.iter().map(TemplateInfo::from).collect();
+        output::print_json(&templates);
+        return Ok(());
+    }
+    if manifests.is_empty() {
+        eprintln!("no templates match");
+        return Ok(());
+    }
+    let id_width = manifests
+        .iter()
+        .map(|m| m.id().len())
+        .max()
+        .unwrap_or(0)
+        .max(2);
+    let stage_width = manifests
+        .iter()
+        .map(|m| m.stage.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let tags_width = manifests
+        .iter()
+        .map(|m| m.tags.join(",").len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!(
+        "{:id_width$}  {:stage_width$}  {:tags_width$}  DESCRIPTION",
+        "ID", "STAGE", "TAGS"
+    );
+    for manifest in &manifests {
+        println!(
+            "{:id_width$}  {:stage_width$}  {:tags_width$}  {}",
+            manifest.id(),
+            manifest.stage,
+            manifest.tags.join(","),
+            manifest.description
+        );
+    }
+    Ok(())
+}
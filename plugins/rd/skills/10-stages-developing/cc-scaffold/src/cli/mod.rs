@@ -0,0 +1,260 @@
+//! Command-line definitions and dispatch.
+
+mod completions;
+mod doc_examples;
+mod fuzz;
+mod krate;
+mod list;
+mod mutants;
+mod new;
+mod openapi;
+mod output;
+mod rollback;
+mod search;
+mod selftest;
+mod templates;
+mod update;
+mod validate;
+mod vectors;
+mod watch;
+
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use clap_complete::CompletionCandidate;
+
+use cc_scaffold::generate;
+use cc_scaffold::journal::{self, Journal};
+use cc_scaffold::remote::{self, RemoteTemplate};
+use cc_scaffold::variables::EnvAccess;
+use cc_scaffold::{locator, verify};
+use cc_scaffold::{Error, LocatedTemplate, Locator, Result};
+
+pub use completions::COMPLETE_ENV;
+pub use output::{print_error, Format};
+
+/// Instantiate 10-stages-developing templates.
+#[derive(Debug, Parser)]
+#[command(name = "cc-scaffold", version, about)]
+pub struct Cli {
+    /// Template root to search instead of the discovered default.
+    #[arg(long, global = true, value_name = "DIR")]
+    templates_dir: Option<PathBuf>,
+
+    /// Output format. `json` prints a single JSON document on stdout,
+    /// including errors, and never prompts.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub format: Format,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Render a template and write it to a destination file.
+    New(new::NewArgs),
+    /// Generate a crate skeleton and add it to the enclosing workspace.
+    Crate(krate::CrateArgs),
+    /// Generate a typed REST client module from an OpenAPI 3 document.
+    FromOpenapi(openapi::FromOpenapiArgs),
+    /// Generate a parametrized test of a function's boundary values and
+    /// inject it into its file's test module.
+    TestVectors(vectors::TestVectorsArgs),
+    /// Generate a cargo-fuzz target calling a function with arbitrary
+    /// arguments.
+    Fuzz(fuzz::FuzzArgs),
+    /// Configure cargo-mutants for a crate, or gate the testing stage on
+    /// the mutants its tests missed.
+    Mutants(mutants::MutantsArgs),
+    /// Check that every public item has a doc example, and that examples
+    /// marked `ignore` or `no_run` say why.
+    DocExamples(doc_examples::DocExamplesArgs),
+    /// List templates that ship a manifest.
+    List(list::ListArgs),
+    /// Find templates by fuzzy-matching names, tags and descriptions.
+    Search(search::SearchArgs),
+    /// Regenerate locked files from newer template versions.
+    Update(update::UpdateArgs),
+    /// Manage the user template registry.
+    Templates(templates::TemplatesArgs),
+    /// Undo the files written by the last (or a given) `new`, `crate` or
+    /// `update`.
+    Rollback(rollback::RollbackArgs),
+    /// Check templates for unknown placeholders, unused variables,
+    /// unbalanced blocks and missing tests or doc comments.
+    Validate(validate::ValidateArgs),
+    /// Render Rust templates into scratch crates and build and test them.
+    Selftest(selftest::SelftestArgs),
+    /// Re-render a template into a preview directory whenever it changes.
+    Watch(watch::WatchArgs),
+    /// Print a shell completion script, to be sourced from the shell's
+    /// startup file.
+    Completions(completions::CompletionsArgs),
+}
+
+pub fn run(cli: Cli) -> Result<()> {
+    let locator = Locator::discover(cli.templates_dir);
+    match cli.command {
+        Command::New(args) => journaled(|journal| new::run(&locator, args, cli.format, journal)),
+        Command::Crate(args) => {
+            journaled(|journal| krate::run(&locator, args, cli.format, journal))
+        }
+        Command::FromOpenapi(args) => {
+            journaled(|journal| openapi::run(&locator, args, cli.format, journal))
+        }
+        Command::TestVectors(args) => {
+            journaled(|journal| vectors::run(&locator, args, cli.format, journal))
+        }
+        Command::Fuzz(args) => journaled(|journal| fuzz::run(&locator, args, cli.format, journal)),
+        Command::Mutants(args) => mutants::run(&locator, args, cli.format),
+        Command::DocExamples(args) => doc_examples::run(&locator, args, cli.format),
+        Command::List(args) => list::run(&locator, args, cli.format),
+        Command::Search(args) => search::run(&locator, args, cli.format),
+        Command::Update(args) => {
+            journaled(|journal| update::run(&locator, args, cli.format, journal))
+        }
+        Command::Templates(args) => templates::run(args, cli.format),
+        Command::Rollback(args) => rollback::run(args, cli.format),
+        Command::Validate(args) => validate::run(&locator, args, cli.format),
+        Command::Selftest(args) => selftest::run(&locator, args, cli.format),
+        Command::Watch(args) => watch::run(&locator, args, cli.format),
+        Command::Completions(args) => completions::run(args),
+    }
+}
+
+/// Template identifiers to complete, from the default template roots, with
+/// their descriptions.
+fn template_candidates() -> Vec<CompletionCandidate> {
+    Locator::discover(None)
+        .manifests()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|m| CompletionCandidate::new(m.id()).help(Some(m.description.into())))
+        .collect()
+}
+
+/// Runs a command that writes files, recording what it changes in the undo
+/// journal so `rollback` can restore it, even if the command fails part way.
+fn journaled(command: impl FnOnce(&mut Journal) -> Result<()>) -> Result<()> {
+    let line: Vec<String> = std::env::args().skip(1).collect();
+    let mut journal = Journal::begin(journal::root().as_deref(), &line.join(" "));
+    let result = command(&mut journal);
+    let finished = journal.finish();
+    result?;
+    finished.map(drop)
+}
+
+/// A template ready to render, with where it came from for the lockfile.
+struct Resolved {
+    template: LocatedTemplate,
+    /// Template file relative to its root, or repository URL for a remote
+    /// template.
+    source: String,
+    /// Commit of a remote template.
+    revision: Option<String>,
+    /// Templates root a local template was found under.
+    root: Option<PathBuf>,
+    /// Remote checkout or user registry the template came from. Template
+    /// files under it are subject to signature checks.
+    untrusted_root: Option<PathBuf>,
+}
+
+impl Resolved {
+    /// What the lockfile records as the source of `file`, one of the
+    /// template's files: where it is under its templates root, or the
+    /// repository URL of a remote template.
+    fn source_of(&self, file: &Path) -> String {
+        if self.revision.is_some() {
+            return self.source.clone();
+        }
+        self.root
+            .as_deref()
+            .and_then(|root| locator::relative_to(root, file))
+            .unwrap_or_else(|| file.display().to_string())
+    }
+}
+
+/// Resolves a local or remote template identifier. A remote template is
+/// fetched at `revision` when one is given, e.g. from the lockfile. Remote
+/// and user-registry templates only read the environment variables their
+/// manifest lists.
+fn resolve(locator: &Locator, id: &str, revision: Option<&str>, refresh: bool) -> Result<Resolved> {
+    let Some(mut remote) = RemoteTemplate::parse(id)? else {
+        let mut template = locator.resolve(id)?;
+        let registry = locator::user_templates_dir().filter(|dir| template.source.starts_with(dir));
+        if registry.is_some() {
+            restrict_env(&mut template);
+        }
+        return Ok(Resolved {
+            source: locator
+                .relative(&template.source)
+                .unwrap_or_else(|| template.source.display().to_string()),
+            root: locator.root_of(&template.source).map(Path::to_path_buf),
+            template,
+            revision: None,
+            untrusted_root: registry,
+        });
+    };
+    if let Some(revision) = revision {
+        remote = remote.at(revision);
+    }
+    let (mut template, revision) = remote::resolve(&remote, refresh)?;
+    restrict_env(&mut template);
+    Ok(Resolved {
+        template,
+        untrusted_root: remote::cache_root().map(|cache| remote.cache_dir(&cache)),
+        source: remote.url,
+        revision: Some(revision),
+        root: None,
+    })
+}
+
+/// Limits `template` to the environment variables its manifest lists.
+fn restrict_env(template: &mut LocatedTemplate) {
+    let listed = template.manifest.as_ref().map(|m| m.env.clone());
+    template.env = EnvAccess::Only(listed.unwrap_or_default());
+}
+
+/// Checks the template files in `inputs` against the hash they are locked
+/// to, if any, and the signatures of those from a remote or the user
+/// registry. With `allow_unverified`, failures are added to `warnings`
+/// instead.
+fn verify(
+    id: &str,
+    resolved: &Resolved,
+    inputs: &[PathBuf],
+    locked: Option<&str>,
+    allow_unverified: bool,
+    warnings: &mut Vec<String>,
+) -> Result<()> {
+    let mut problems = Vec::new();
+    if let Some(locked) = locked {
+        let hash = generate::fingerprint(inputs)?;
+        if hash != locked {
+            problems.push(Error::LockMismatch {
+                template: id.to_string(),
+                locked: locked.to_string(),
+                found: hash,
+            });
+        }
+    }
+    if let Some(root) = &resolved.untrusted_root {
+        let files: Vec<PathBuf> = inputs
+            .iter()
+            .filter(|input| input.starts_with(root))
+            .cloned()
+            .collect();
+        let signers = verify::allowed_signers().filter(|path| path.is_file());
+        if let Err(err) = verify::check(&files, signers.as_deref()) {
+            problems.push(err);
+        }
+    }
+    for problem in problems {
+        if !allow_unverified {
+            return Err(problem);
+        }
+        warnings.push(format!("{problem} (ignored: --allow-unverified)"));
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+//! `cc-scaffold mutants`: `cargo-mutants` configuration and the
+//! `6-expand-tests` gate on its results.
+
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+
+use cc_scaffold::generate::{self, Preview};
+use cc_scaffold::journal::Journal;
+use cc_scaffold::mutants::{self, Outcomes};
+use cc_scaffold::{Error, Locator, Result};
+
+use super::fuzz::package_name;
+use super::new;
+use super::output::{self, Action, FileReport, Format, ProjectReport};
+
+/// What the configuration is reported as coming from.
+const TEMPLATE: &str = "rust/mutants";
+
+#[derive(Debug, Args)]
+pub struct MutantsArgs {
+    #[command(subcommand)]
+    command: MutantsCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum MutantsCommand {
+    /// Write the crate's `.cargo/mutants.toml`, excluding generated files,
+    /// an `xtask` crate and `Debug` implementations.
+    Init {
+        /// Crate to configure.
+        #[arg(long = "crate", value_name = "DIR", default_value = ".")]
+        crate_dir: PathBuf,
+
+        /// Overwrite an existing configuration.
+        #[arg(short, long)]
+        force: bool,
+
+        /// Print a unified diff instead of writing the file.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Check the results of `cargo mutants` and fail if a mutant survived.
+    Report {
+        /// Crate `cargo mutants` ran in.
+        #[arg(long = "crate", value_name = "DIR", default_value = ".")]
+        crate_dir: PathBuf,
+
+        /// Directory holding the results. Defaults to the crate's
+        /// `mutants.out`.
+        #[arg(long, value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
+}
+
+pub fn run(locator: &Locator, args: MutantsArgs, format: Format) -> Result<()> {
+    match args.command {
+        MutantsCommand::Init {
+            crate_dir,
+            force,
+            dry_run,
+        } => super::journaled(|journal| init(locator, &crate_dir, force, dry_run, format, journal)),
+        MutantsCommand::Report { crate_dir, output } => {
+            let output = output.unwrap_or_else(|| crate_dir.join(mutants::OUTPUT));
+            report(&output, format)
+        }
+    }
+}
+
+fn init(
+    locator: &Locator,
+    crate_dir: &Path,
+    force: bool,
+    dry_run: bool,
+    format: Format,
+    journal: &mut Journal,
+) -> Result<()> {
+    let invalid = |path, message| Error::Mutants { path, message };
+    let manifest = crate_dir.join("Cargo.toml");
+    if !manifest.is_file() {
+        return Err(invalid(
+            crate_dir.to_path_buf(),
+            "it has no Cargo.toml".to_string(),
+        ));
+    }
+    let package = package_name(&manifest, invalid)?;
+    let file = mutants::render_config(locator, crate_dir, &package)?;
+
+    let mut warnings = Vec::new();
+    let (mut action, mut diff) = match generate::preview(&file)? {
+        Preview::Unchanged => (Action::Unchanged, None),
+        Preview::Create(diff) => (Action::WouldCreate, Some(diff)),
+        Preview::Overwrite(diff) => (Action::WouldOverwrite, Some(diff)),
+    };
+    if action == Action::WouldOverwrite && !force {
+        if !dry_run {
+            return Err(Error::DestinationExists(file.destination));
+        }
+        warnings.push(format!(
+            "{} exists; writing it requires --force",
+            file.destination.display()
+        ));
+    }
+    if !dry_run && action != Action::Unchanged {
+        journal.record(&file.destination)?;
+        generate::write(&file, true)?;
+        diff = None;
+        action = match action {
+            Action::WouldCreate => Action::Created,
+            _ => Action::Overwritten,
+        };
+    }
+
+    let reports = [FileReport {
+        path: file.destination.clone(),
+        action,
+        diff,
+    }];
+    if format.is_json() {
+        output::print_json(&ProjectReport {
+            template: TEMPLATE,
+            source: &manifest,
+            destination: crate_dir,
+            files: &reports,
+            workspace_member: None,
+            warnings: &warnings,
+        });
+        return Ok(());
+    }
+    for warning in &warnings {
+        output::warn(format, warning);
+    }
+    new::print_files(&reports);
+    Ok(())
+}
+
+fn report(dir: &Path, format: Format) -> Result<()> {
+    output::print_gate(format, Outcomes::load(dir)?.stage_report())
+}
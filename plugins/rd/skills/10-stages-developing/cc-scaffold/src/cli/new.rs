@@ -0,0 +1,732 @@
+//! `cc-scaffold new`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use clap_complete::ArgValueCandidates;
+
+use cc_scaffold::cargo_generate::{self, CargoTemplate};
+use cc_scaffold::config::Config;
+use cc_scaffold::generate::{self, Preview, RenderedFile};
+use cc_scaffold::hooks::{self, Sandbox, TemplateHook};
+use cc_scaffold::inject::{self, Point};
+use cc_scaffold::journal::Journal;
+use cc_scaffold::lockfile::{self, LockedFile, Lockfile};
+use cc_scaffold::parallel;
+use cc_scaffold::prompt;
+use cc_scaffold::variables;
+use cc_scaffold::workspace;
+use cc_scaffold::{Error, Locator, Result};
+
+use super::output::{self, Action, FileReport, Format, NewReport, ProjectReport};
+use super::Resolved;
+
+#[derive(Debug, Args)]
+pub struct NewArgs {
+    /// Template identifier, e.g. `rust/function`, or a remote one such as
+    /// `github:org/repo//templates/rust/actor#v1.0`.
+    #[arg(add = ArgValueCandidates::new(super::template_candidates))]
+    template: String,
+
+    /// File to write the rendered template to, or the directory to generate
+    /// a bundle or cargo-generate project template into. Defaults to the
+    /// directory `cc-agents.toml` configures for the template, with the file
+    /// named after the template's first identifier variable.
+    #[arg(short, long, value_name = "PATH")]
+    out: Option<PathBuf>,
+
+    /// Set a template variable, e.g. `--set function_name=parse`. Without a
+    /// manifest, any placeholder can be set, including prose ones:
+    /// `--set "Description of param1=Input text"`.
+    #[arg(short, long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// Rust edition to generate code for, for templates with an `edition`
+    /// variable; the same as `--set edition=EDITION`. Defaults to the
+    /// edition of the crate the destination is in.
+    #[arg(long, value_name = "EDITION")]
+    edition: Option<String>,
+
+    /// Inject the rendered snippet into the existing destination instead of
+    /// replacing it, at POINT: `auto` (the default: before the test module,
+    /// merging tests into it), `end`, `anchor:<name>` (above a
+    /// `// cc-agents:insert <name>` comment), `enum:<Name>`,
+    /// `struct:<Name>`, `impl:<Type>`, `trait:<Name>` or `mod:<name>`.
+    #[arg(
+        long,
+        value_name = "POINT",
+        num_args = 0..=1,
+        default_missing_value = "auto"
+    )]
+    inject: Option<String>,
+
+    /// Overwrite the destination if it already exists.
+    #[arg(short, long)]
+    force: bool,
+
+    /// Print a unified diff against the destination (or the whole file, if
+    /// it does not exist yet) instead of writing it.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Fetch a remote template again even if the cached copy is recent.
+    #[arg(long)]
+    refresh: bool,
+
+    /// Render even if the template does not match its lockfile hash or its
+    /// signatures do not verify.
+    #[arg(long)]
+    allow_unverified: bool,
+
+    /// Format generated Rust files with rustfmt before writing them. Fails,
+    /// writing nothing, if the output does not parse.
+    #[arg(long)]
+    rustfmt: bool,
+
+    /// Run `cargo check` on the crate the output was written into, failing
+    /// if it does not compile.
+    #[arg(long)]
+    check: bool,
+
+    /// Run the commands the template's manifest declares as hooks, in a
+    /// sandbox confined to the project. Without this they are skipped.
+    #[arg(long)]
+    allow_hooks: bool,
+
+    /// Do not read or update `cc-agents.lock`.
+    #[arg(long)]
+    no_lock: bool,
+
+    /// Ignore `cc-agents.toml`.
+    #[arg(long)]
+    no_config: bool,
+
+    /// Never prompt; fail if a required variable is missing. Prompting is
+    /// also skipped when stdin is not a terminal or with `--format json`.
+    #[arg(long)]
+    no_interactive: bool,
+
+    /// Workspace `Cargo.toml` a generated crate joins as a member.
+    #[arg(skip)]
+    workspace: Option<PathBuf>,
+}
+
+impl NewArgs {
+    /// Arguments generating crate template `template` into `out` for
+    /// `cc-scaffold crate`, adding it to `workspace` if given.
+    pub(super) fn for_crate(
+        template: &str,
+        out: PathBuf,
+        set: Vec<String>,
+        force: bool,
+        dry_run: bool,
+        workspace: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            template: template.to_string(),
+            out: Some(out),
+            set,
+            edition: None,
+            inject: None,
+            force,
+            dry_run,
+            refresh: false,
+            allow_unverified: false,
+            rustfmt: false,
+            check: false,
+            allow_hooks: false,
+            no_lock: false,
+            no_config: false,
+            no_interactive: false,
+            workspace,
+        }
+    }
+
+    /// Destination, once [`run`] has filled it in from the config if
+    /// `--out` was omitted.
+    fn out(&self) -> &Path {
+        self.out.as_deref().unwrap_or(Path::new("."))
+    }
+}
+
+pub fn run(
+    locator: &Locator,
+    mut args: NewArgs,
+    format: Format,
+    journal: &mut Journal,
+) -> Result<()> {
+    let config = if args.no_config {
+        Config::default()
+    } else {
+        Config::discover(Path::new("."))?
+    };
+    args.rustfmt |= config.hooks.rustfmt;
+    args.check |= config.hooks.check;
+    // Without --out, output goes into the configured directory; a single
+    // file is named once its values are known.
+    let named = args.out.is_some();
+    let lock_dir = match &args.out {
+        Some(out) => out.parent().unwrap_or(Path::new(".")).to_path_buf(),
+        None => {
+            let dir = config
+                .output_dir(&args.template)
+                .ok_or_else(|| Error::NoDestination(args.template.clone()))?
+                .to_path_buf();
+            args.out = Some(dir.clone());
+            dir
+        }
+    };
+    let lock_path = lockfile::locate(&lock_dir);
+    let mut lock = if args.no_lock {
+        Lockfile::default()
+    } else {
+        Lockfile::load(&lock_path)?
+    };
+    let locked = lock.template(&args.template).cloned();
+    let resolved = super::resolve(
+        locator,
+        &args.template,
+        locked.as_ref().and_then(|l| l.revision.as_deref()),
+        args.refresh,
+    )?;
+    let template = &resolved.template;
+    let mut values = args
+        .set
+        .iter()
+        .map(|arg| generate::parse_assignment(arg))
+        .collect::<Result<BTreeMap<_, _>>>()?;
+    if cargo_generate::is_template(&template.source) {
+        return project(&args, &resolved, values, format, journal);
+    }
+    let mut warnings = Vec::new();
+    match &template.manifest {
+        Some(manifest) => {
+            let has_edition = manifest.variable("edition").is_some();
+            match &args.edition {
+                Some(edition) if has_edition => {
+                    values.insert("edition".to_string(), edition.clone());
+                }
+                Some(_) => warnings.push(format!(
+                    "{} has no edition variable; ignored --edition",
+                    args.template
+                )),
+                None => {}
+            }
+            config.apply(manifest, &mut values);
+            if has_edition && !values.contains_key("edition") {
+                if let Some(edition) = hooks::crate_edition(args.out()) {
+                    values.insert("edition".to_string(), edition);
+                }
+            }
+            if !args.no_interactive && !format.is_json() && io::stdin().is_terminal() {
+                prompt::fill_missing(
+                    &manifest.variables,
+                    &mut values,
+                    &template.env,
+                    &mut io::stdin().lock(),
+                    &mut io::stderr(),
+                )?;
+            }
+            // Recorded in the lockfile with the rest, so `update` renders
+            // the same migration version rather than the time it runs.
+            variables::pin_clock_defaults(&manifest.variables, &mut values, &template.env);
+        }
+        None => warnings.push(format!(
+            "{} has no manifest; values are substituted without validation",
+            template.source.display()
+        )),
+    }
+    if template
+        .manifest
+        .as_ref()
+        .is_some_and(|m| !m.files.is_empty())
+    {
+        let lock = Lock {
+            file: lock,
+            path: &lock_path,
+        };
+        return bundle(&args, &resolved, lock, values, warnings, format, journal);
+    }
+    if !named {
+        args.out = Some(lock_dir.join(generate::default_file_name(template, &values)));
+    }
+    let mut file = generate::render(template, args.out(), &values)?;
+    super::verify(
+        &args.template,
+        &resolved,
+        &file.inputs,
+        locked.as_ref().map(|l| l.hash.as_str()),
+        args.allow_unverified,
+        &mut warnings,
+    )?;
+    let dest = file.destination.display().to_string();
+    if let Some(spec) = &args.inject {
+        let invalid = |message| Error::Inject {
+            path: file.destination.clone(),
+            message,
+        };
+        let point = Point::parse(spec).map_err(invalid)?;
+        let existing = match fs::read_to_string(&file.destination) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(invalid(
+                    "it does not exist; create it without --inject".to_string(),
+                ))
+            }
+            other => other.map_err(|err| Error::io(&file.destination, err))?,
+        };
+        file.contents = inject::inject(&existing, &file.contents, &point).map_err(invalid)?;
+    }
+    let injecting = args.inject.is_some();
+    let merging = !injecting && merge_regions(&mut file, &mut warnings)?;
+    let rustfmt = args.rustfmt && hooks::is_rust(&file.destination);
+    if rustfmt {
+        file.contents = hooks::rustfmt(&file.destination, &file.contents)?;
+    } else if args.rustfmt {
+        warnings.push(format!("{dest} is not a Rust file; skipped rustfmt"));
+    }
+
+    let (action, diff) = if args.dry_run {
+        match generate::preview(&file)? {
+            Preview::Create(diff) => (Action::WouldCreate, Some(diff)),
+            Preview::Overwrite(diff) if injecting => (Action::WouldInject, Some(diff)),
+            Preview::Overwrite(diff) if merging => (Action::WouldMerge, Some(diff)),
+            Preview::Overwrite(diff) => {
+                if !args.force {
+                    warnings.push(format!("{dest} exists; writing it requires --force"));
+                }
+                (Action::WouldOverwrite, Some(diff))
+            }
+            Preview::Unchanged => (Action::Unchanged, None),
+        }
+    } else {
+        let existed = file.destination.exists();
+        let partial = injecting || merging;
+        let unchanged = partial && generate::preview(&file)? == Preview::Unchanged;
+        journal.record(&file.destination)?;
+        generate::write(&file, args.force || partial)?;
+        let action = match (existed, injecting) {
+            _ if unchanged => Action::Unchanged,
+            (_, true) => Action::Injected,
+            _ if merging => Action::Merged,
+            (true, false) => Action::Overwritten,
+            (false, false) => Action::Created,
+        };
+        // Only part of an injected-into file is generated, so it is not
+        // pinned to the template.
+        if !args.no_lock && !injecting {
+            lock.record(LockedFile {
+                path: lockfile::relative_path(&lock_path, &file.destination),
+                template: args.template.clone(),
+                source: resolved.source_of(&file.template),
+                revision: resolved.revision.clone(),
+                hash: file.fingerprint()?,
+                output: generate::output_hash(&file.contents),
+                bundle: None,
+                rustfmt,
+                values,
+            });
+            journal.record(&lock_path)?;
+            lock.save(&lock_path)?;
+        }
+        (action, None)
+    };
+    check(&args, &file.destination, &mut warnings)?;
+    if let Some(manifest) = &template.manifest {
+        let root = lock_path.parent().unwrap_or(Path::new("."));
+        run_hooks(
+            &args,
+            &manifest.hooks,
+            root,
+            &file.destination,
+            &mut warnings,
+        )?;
+    }
+
+    if format.is_json() {
+        output::print_json(&NewReport {
+            template: &args.template,
+            source: &file.template,
+            destination: &file.destination,
+            action,
+            contents: &file.contents,
+            diff: diff.as_deref(),
+            warnings: &warnings,
+        });
+        return Ok(());
+    }
+    for warning in &warnings {
+        output::warn(format, warning);
+    }
+    if let Some(diff) = &diff {
+        print!("{diff}");
+    }
+    print_action(action, &dest);
+    Ok(())
+}
+
+/// Reports what was done to `path` in text mode: changes on stdout,
+/// previews and no-ops on stderr.
+pub(super) fn print_action(action: Action, path: &str) {
+    match action {
+        Action::Created => println!("created {path}"),
+        Action::Overwritten => println!("overwrote {path}"),
+        Action::Injected => println!("injected into {path}"),
+        Action::Merged => println!("regenerated the managed regions of {path}"),
+        Action::WouldCreate => eprintln!("would create {path}"),
+        Action::WouldOverwrite => eprintln!("would overwrite {path}"),
+        Action::WouldInject => eprintln!("would inject into {path}"),
+        Action::WouldMerge => eprintln!("would regenerate the managed regions of {path}"),
+        Action::Unchanged => eprintln!("{path} is up to date"),
+    }
+}
+
+/// Keeps the hand-written code of an existing destination when both it and
+/// `file` have managed regions, by regenerating only those regions. That
+/// needs no --force.
+fn merge_regions(file: &mut RenderedFile, warnings: &mut Vec<String>) -> Result<bool> {
+    let Some(missing) = file.merge_regions()? else {
+        return Ok(false);
+    };
+    for name in missing {
+        warnings.push(format!(
+            "{} has no managed region `{name}`; it was not generated",
+            file.destination.display()
+        ));
+    }
+    Ok(true)
+}
+
+/// The lockfile a run records generated files in, and where it is saved.
+struct Lock<'a> {
+    file: Lockfile,
+    path: &'a Path,
+}
+
+/// Generates every file of a bundle template under the output directory,
+/// or injects it into the existing file for entries with an `inject`
+/// point, writing none of them unless all can be written.
+fn bundle(
+    args: &NewArgs,
+    resolved: &Resolved,
+    lock: Lock<'_>,
+    values: BTreeMap<String, String>,
+    mut warnings: Vec<String>,
+    format: Format,
+    journal: &mut Journal,
+) -> Result<()> {
+    let Lock {
+        file: mut lock,
+        path: lock_path,
+    } = lock;
+    if args.inject.is_some() {
+        return Err(Error::Inject {
+            path: args.out().to_path_buf(),
+            message: format!(
+                "`{}` generates several files; generate it without --inject",
+                args.template
+            ),
+        });
+    }
+    let locked = lock.template(&args.template).cloned();
+    let files = generate::render_bundle(&resolved.template, args.out(), &values)?;
+    let files = match &resolved.template.manifest {
+        Some(manifest) => generate::inject_bundled(manifest, &values, files)?,
+        None => files.into_iter().map(|file| (file, false)).collect(),
+    };
+    if let Some((first, _)) = files.first() {
+        super::verify(
+            &args.template,
+            resolved,
+            &first.inputs,
+            locked.as_ref().map(|l| l.hash.as_str()),
+            args.allow_unverified,
+            &mut warnings,
+        )?;
+    }
+
+    let prepared = parallel::map(files, |(mut file, injected)| {
+        let mut warnings = Vec::new();
+        let merging = !injected && merge_regions(&mut file, &mut warnings)?;
+        if args.rustfmt && hooks::is_rust(&file.destination) {
+            file.contents = hooks::rustfmt(&file.destination, &file.contents)?;
+        }
+        let (action, diff) = match generate::preview(&file)? {
+            Preview::Create(diff) => (Action::WouldCreate, Some(diff)),
+            Preview::Overwrite(diff) if injected => (Action::WouldInject, Some(diff)),
+            Preview::Overwrite(diff) if merging => (Action::WouldMerge, Some(diff)),
+            Preview::Overwrite(diff) => (Action::WouldOverwrite, Some(diff)),
+            Preview::Unchanged => (Action::Unchanged, None),
+        };
+        let report = FileReport {
+            path: file.destination.clone(),
+            action,
+            diff,
+        };
+        Ok(((file, injected), report, warnings))
+    })?;
+    let mut files = Vec::with_capacity(prepared.len());
+    let mut reports = Vec::with_capacity(prepared.len());
+    for (file, report, file_warnings) in prepared {
+        files.push(file);
+        reports.push(report);
+        warnings.extend(file_warnings);
+    }
+    let blocked = reports
+        .iter()
+        .find(|r| r.action == Action::WouldOverwrite && !args.force);
+    if let Some(report) = blocked {
+        if !args.dry_run {
+            return Err(Error::DestinationExists(report.path.clone()));
+        }
+        warnings.push(format!(
+            "{} exists; writing it requires --force",
+            report.path.display()
+        ));
+    }
+    if !args.dry_run {
+        for report in &mut reports {
+            report.diff = None;
+            report.action = match report.action {
+                Action::WouldCreate => Action::Created,
+                Action::WouldInject => Action::Injected,
+                Action::WouldMerge => Action::Merged,
+                Action::WouldOverwrite => Action::Overwritten,
+                action => action,
+            };
+        }
+        let changed = files
+            .iter()
+            .zip(&reports)
+            .filter(|(_, report)| report.action != Action::Unchanged)
+            .map(|((file, _), _)| file)
+            .collect::<Vec<_>>();
+        for file in &changed {
+            journal.record(&file.destination)?;
+        }
+        parallel::map(changed, |file| generate::write(file, true))?;
+        if !args.no_lock {
+            // As with --inject, files only added to are not pinned.
+            for (file, _) in files.iter().filter(|(_, injected)| !injected) {
+                lock.record(LockedFile {
+                    path: lockfile::relative_path(lock_path, &file.destination),
+                    template: args.template.clone(),
+                    source: resolved.source_of(&file.template),
+                    revision: resolved.revision.clone(),
+                    hash: file.fingerprint()?,
+                    output: generate::output_hash(&file.contents),
+                    bundle: Some(lockfile::relative_path(lock_path, args.out())),
+                    rustfmt: args.rustfmt && hooks::is_rust(&file.destination),
+                    values: values.clone(),
+                });
+            }
+        }
+        if !args.no_lock {
+            journal.record(lock_path)?;
+            lock.save(lock_path)?;
+        }
+    }
+    let member = match &args.workspace {
+        Some(manifest) => workspace::member_to_add(manifest, args.out())?,
+        None => None,
+    };
+    if let (Some(manifest), Some(member)) = (&args.workspace, &member) {
+        if !args.dry_run {
+            journal.record(manifest)?;
+            workspace::add_member(manifest, member)?;
+        }
+    }
+    check(args, args.out(), &mut warnings)?;
+    if let Some(manifest) = &resolved.template.manifest {
+        let root = lock_path.parent().unwrap_or(Path::new("."));
+        run_hooks(args, &manifest.hooks, root, args.out(), &mut warnings)?;
+    }
+
+    if format.is_json() {
+        output::print_json(&ProjectReport {
+            template: &args.template,
+            source: &resolved.template.source,
+            destination: args.out(),
+            files: &reports,
+            workspace_member: member.as_deref(),
+            warnings: &warnings,
+        });
+        return Ok(());
+    }
+    for warning in &warnings {
+        output::warn(format, warning);
+    }
+    print_files(&reports);
+    if let (Some(manifest), Some(member)) = (&args.workspace, &member) {
+        let manifest = manifest.display();
+        if args.dry_run {
+            eprintln!("would add {member} to the workspace members in {manifest}");
+        } else {
+            println!("added {member} to the workspace members in {manifest}");
+        }
+    }
+    Ok(())
+}
+
+/// Prints each file's diff, if any, and what was done to it.
+pub(super) fn print_files(files: &[FileReport]) {
+    for file in files {
+        if let Some(diff) = &file.diff {
+            print!("{diff}");
+        }
+        print_action(file.action, &file.path.display().to_string());
+    }
+}
+
+/// Generates a cargo-generate project template into the output directory.
+/// Projects are not recorded in the lockfile.
+fn project(
+    args: &NewArgs,
+    resolved: &Resolved,
+    values: BTreeMap<String, String>,
+    format: Format,
+    journal: &mut Journal,
+) -> Result<()> {
+    let template = CargoTemplate::load(&resolved.template.source)?;
+    let mut values: BTreeMap<String, String> = values
+        .into_iter()
+        .map(|(key, value)| (cargo_generate::variable_name(&key), value))
+        .collect();
+    let mut warnings: Vec<String> = template
+        .unsupported
+        .iter()
+        .map(|feature| format!("cargo-generate {feature} are not supported and were skipped"))
+        .collect();
+    if !args.no_interactive && !format.is_json() && io::stdin().is_terminal() {
+        prompt::fill_missing(
+            &template.variables,
+            &mut values,
+            &resolved.template.env,
+            &mut io::stdin().lock(),
+            &mut io::stderr(),
+        )?;
+    }
+    let mut project = template.render(args.out(), &values, &resolved.template.env)?;
+    super::verify(
+        &args.template,
+        resolved,
+        &project.inputs(),
+        None,
+        args.allow_unverified,
+        &mut warnings,
+    )?;
+
+    if args.rustfmt {
+        for file in project
+            .files
+            .iter_mut()
+            .filter(|f| hooks::is_rust(&f.destination))
+        {
+            file.contents = hooks::rustfmt(&file.destination, &file.contents)?;
+        }
+    }
+
+    let mut files = Vec::new();
+    for file in &project.files {
+        let (action, diff) = match generate::preview(file)? {
+            Preview::Create(diff) => (Action::WouldCreate, Some(diff)),
+            Preview::Overwrite(diff) => (Action::WouldOverwrite, Some(diff)),
+            Preview::Unchanged => (Action::Unchanged, None),
+        };
+        files.push(FileReport {
+            path: file.destination.clone(),
+            action,
+            diff,
+        });
+    }
+    for (_, to) in &project.copies {
+        let action = if to.exists() {
+            Action::WouldOverwrite
+        } else {
+            Action::WouldCreate
+        };
+        files.push(FileReport {
+            path: to.clone(),
+            action,
+            diff: None,
+        });
+    }
+    if !args.dry_run {
+        for file in &files {
+            journal.record(&file.path)?;
+        }
+        project.write(args.force)?;
+        for file in &mut files {
+            file.diff = None;
+            file.action = match file.action {
+                Action::WouldCreate => Action::Created,
+                Action::Unchanged => Action::Unchanged,
+                _ => Action::Overwritten,
+            };
+        }
+    }
+    check(args, args.out(), &mut warnings)?;
+
+    if format.is_json() {
+        output::print_json(&ProjectReport {
+            template: &args.template,
+            source: &template.root,
+            destination: args.out(),
+            files: &files,
+            workspace_member: None,
+            warnings: &warnings,
+        });
+        return Ok(());
+    }
+    for warning in &warnings {
+        output::warn(format, warning);
+    }
+    print_files(&files);
+    Ok(())
+}
+
+/// Runs `cargo check` for `--check` on the crate containing `path`.
+fn check(args: &NewArgs, path: &Path, warnings: &mut Vec<String>) -> Result<()> {
+    if !args.check {
+        return Ok(());
+    }
+    if args.dry_run {
+        warnings.push("cargo check is skipped with --dry-run".to_string());
+    } else if !hooks::cargo_check(path)? {
+        warnings.push(format!(
+            "{} is not inside a Cargo crate; skipped cargo check",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Runs the template's own `hooks` for `--allow-hooks`, or warns that they
+/// were skipped.
+fn run_hooks(
+    args: &NewArgs,
+    hooks: &[TemplateHook],
+    root: &Path,
+    destination: &Path,
+    warnings: &mut Vec<String>,
+) -> Result<()> {
+    if hooks.is_empty() || args.dry_run {
+        return Ok(());
+    }
+    if !args.allow_hooks {
+        let commands: Vec<_> = hooks.iter().map(TemplateHook::display).collect();
+        warnings.push(format!(
+            "{} declares hooks ({}); pass --allow-hooks to run them",
+            args.template,
+            commands.join("; ")
+        ));
+        return Ok(());
+    }
+    let sandbox = Sandbox::new(&lockfile::absolute(root));
+    let destination = lockfile::absolute(destination);
+    hooks
+        .iter()
+        .try_for_each(|hook| sandbox.run(hook, &destination))
+}
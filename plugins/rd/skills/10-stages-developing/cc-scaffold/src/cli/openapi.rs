@@ -0,0 +1,146 @@
+//! `cc-scaffold from-openapi`.
+
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+
+use cc_scaffold::config::Config;
+use cc_scaffold::generate::{self, Preview};
+use cc_scaffold::hooks;
+use cc_scaffold::journal::Journal;
+use cc_scaffold::openapi::{self, Api};
+use cc_scaffold::{Error, Locator, Result, VariableKind, VariableSpec};
+
+use super::new;
+use super::output::{self, Action, FileReport, Format, ProjectReport};
+
+/// What the generated files are reported as coming from.
+const TEMPLATE: &str = "rust/openapi-client";
+
+#[derive(Debug, Args)]
+pub struct FromOpenapiArgs {
+    /// OpenAPI 3 document, in YAML or JSON.
+    spec: PathBuf,
+
+    /// Module directory to generate the client into. Defaults to
+    /// `src/<name>`, named after the document's file.
+    #[arg(short, long, value_name = "DIR")]
+    out: Option<PathBuf>,
+
+    /// Name of the client struct. Defaults to the module name in PascalCase
+    /// followed by `Client`.
+    #[arg(long, value_name = "NAME", value_parser = client_name)]
+    client_name: Option<String>,
+
+    /// Overwrite files that already exist.
+    #[arg(short, long)]
+    force: bool,
+
+    /// Print a unified diff of every file instead of writing them.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Format the generated files with rustfmt before writing them.
+    #[arg(long)]
+    rustfmt: bool,
+
+    /// Ignore `cc-agents.toml`.
+    #[arg(long)]
+    no_config: bool,
+}
+
+fn client_name(value: &str) -> std::result::Result<String, String> {
+    VariableSpec::new("client_name", VariableKind::Identifier).parse_value(value)?;
+    Ok(value.to_string())
+}
+
+/// Generates the client, writing none of its files unless all can be
+/// written. The files are not recorded in the lockfile: `update` cannot
+/// regenerate them, running `from-openapi` again does.
+pub fn run(
+    locator: &Locator,
+    mut args: FromOpenapiArgs,
+    format: Format,
+    journal: &mut Journal,
+) -> Result<()> {
+    if !args.no_config {
+        args.rustfmt |= Config::discover(Path::new("."))?.hooks.rustfmt;
+    }
+    let api = Api::load(&args.spec)?;
+    let out = match args.out.take() {
+        Some(out) => out,
+        None => {
+            let stem = args.spec.file_stem().unwrap_or_default().to_string_lossy();
+            Path::new("src").join(openapi::field_name(&stem).trim_start_matches("r#"))
+        }
+    };
+    let client_name = match args.client_name.take() {
+        Some(name) => name,
+        None => {
+            let module = out.file_name().unwrap_or_default().to_string_lossy();
+            format!("{}Client", openapi::type_name(&module))
+        }
+    };
+    let mut files = api.render(locator, &out, &client_name)?;
+    let mut warnings = api.warnings.clone();
+
+    let mut reports = Vec::with_capacity(files.len());
+    for file in &mut files {
+        if args.rustfmt && hooks::is_rust(&file.destination) {
+            file.contents = hooks::rustfmt(&file.destination, &file.contents)?;
+        }
+        let (action, diff) = match generate::preview(file)? {
+            Preview::Create(diff) => (Action::WouldCreate, Some(diff)),
+            Preview::Overwrite(diff) => (Action::WouldOverwrite, Some(diff)),
+            Preview::Unchanged => (Action::Unchanged, None),
+        };
+        reports.push(FileReport {
+            path: file.destination.clone(),
+            action,
+            diff,
+        });
+    }
+    let blocked = reports
+        .iter()
+        .find(|r| r.action == Action::WouldOverwrite && !args.force);
+    if let Some(report) = blocked {
+        if !args.dry_run {
+            return Err(Error::DestinationExists(report.path.clone()));
+        }
+        warnings.push(format!(
+            "{} exists; writing it requires --force",
+            report.path.display()
+        ));
+    }
+    if !args.dry_run {
+        for (file, report) in files.iter().zip(&mut reports) {
+            report.diff = None;
+            report.action = match report.action {
+                Action::WouldCreate => Action::Created,
+                Action::WouldOverwrite => Action::Overwritten,
+                action => action,
+            };
+            if report.action != Action::Unchanged {
+                journal.record(&file.destination)?;
+                generate::write(file, true)?;
+            }
+        }
+    }
+
+    if format.is_json() {
+        output::print_json(&ProjectReport {
+            template: TEMPLATE,
+            source: &args.spec,
+            destination: &out,
+            files: &reports,
+            workspace_member: None,
+            warnings: &warnings,
+        });
+        return Ok(());
+    }
+    for warning in &warnings {
+        output::warn(format, warning);
+    }
+    new::print_files(&reports);
+    Ok(())
+}
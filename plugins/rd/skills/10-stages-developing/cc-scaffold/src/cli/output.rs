@@ -0,0 +1,251 @@
+//! Machine-readable output for `--format json`.
+//!
+//! Every command prints exactly one JSON document to stdout, except
+//! `watch`, which prints one line of JSON per round. Failures are
+//! reported as `{"error": "...", "code": "..."}` with a non-zero exit
+//! status, plus the `file`, `span` and `suggestion` when there are any, so
+//! callers never have to scrape stderr; a failed `--rustfmt` or `--check` adds the
+//! `hook`, its parsed `diagnostics` and its raw `output`, a failed
+//! `validate` lists its `problems`, a failed `selftest` its `templates`
+//! and a failed stage gate its `report`.
+
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use cc_scaffold::stage::StageReport;
+use cc_scaffold::{Error, Manifest, Result, VariableSpec};
+
+/// How commands report their results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Format {
+    /// Human-oriented text.
+    #[default]
+    Text,
+    /// A single JSON document on stdout.
+    Json,
+}
+
+impl Format {
+    pub fn is_json(self) -> bool {
+        self == Format::Json
+    }
+}
+
+/// Prints `value` as pretty JSON on stdout.
+pub fn print_json<T: Serialize>(value: &T) {
+    let json = serde_json::to_string_pretty(value).expect("output types serialize");
+    println!("{json}");
+}
+
+/// Prints `value` as JSON on a single line of stdout, for commands that
+/// report more than once.
+pub fn print_json_line<T: Serialize>(value: &T) {
+    let json = serde_json::to_string(value).expect("output types serialize");
+    println!("{json}");
+}
+
+/// Reports a failed command: in text mode its message and any suggestion,
+/// and in JSON its [`ErrorReport`](cc_scaffold::error::ErrorReport). A
+/// failed hook also carries its parsed `diagnostics`.
+pub fn print_error(format: Format, err: &Error) {
+    let report = err.report();
+    if !format.is_json() {
+        eprintln!("error: {}", report.message);
+        if let Some(suggestion) = &report.suggestion {
+            eprintln!("help: {suggestion}");
+        }
+        return;
+    }
+    let mut json = serde_json::to_value(&report).expect("reports serialize");
+    let extra = match err {
+        Error::Hook {
+            hook,
+            path,
+            diagnostics,
+            output,
+        } => serde_json::json!({
+            "error": format!("{hook} failed for {}", path.display()),
+            "hook": hook,
+            "path": path,
+            "diagnostics": diagnostics,
+            "output": output,
+        }),
+        Error::Lint { problems } => serde_json::json!({ "problems": problems }),
+        Error::SelfTest { outcomes } => serde_json::json!({ "templates": outcomes }),
+        Error::Gate { report } => serde_json::json!({ "report": report }),
+        _ => serde_json::json!({}),
+    };
+    if let (Some(json), serde_json::Value::Object(extra)) = (json.as_object_mut(), extra) {
+        json.extend(extra);
+    }
+    print_json(&json);
+}
+
+/// Reports a stage gate: in text mode each problem as
+/// `file:line:column: message`, its warnings and, if it passed, its
+/// summary, and in JSON the report. A gate that did not pass is an
+/// [`Error::Gate`].
+pub fn print_gate(format: Format, report: StageReport) -> Result<()> {
+    if !format.is_json() {
+        for problem in &report.problems {
+            println!(
+                "{}:{}:{}: {}",
+                problem.file.display(),
+                problem.line,
+                problem.column,
+                problem.message
+            );
+        }
+        for warning in &report.warnings {
+            warn(format, warning);
+        }
+    }
+    if !report.passed {
+        return Err(Error::Gate {
+            report: Box::new(report),
+        });
+    }
+    if format.is_json() {
+        print_json(&report);
+    } else {
+        eprintln!("{report}");
+    }
+    Ok(())
+}
+
+/// Prints a warning in text mode. JSON output collects warnings in the
+/// command's report instead.
+pub fn warn(format: Format, message: &str) {
+    if !format.is_json() {
+        eprintln!("warning: {message}");
+    }
+}
+
+/// A template as listed by `cc-scaffold list`.
+#[derive(Debug, Serialize)]
+pub struct TemplateInfo<'a> {
+    pub id: String,
+    pub name: &'a str,
+    pub language: &'a str,
+    pub stage: &'a str,
+    pub description: &'a str,
+    pub tags: &'a [String],
+    pub file: &'a Path,
+    pub manifest: &'a Path,
+    pub variables: Vec<VariableInfo<'a>>,
+}
+
+impl<'a> From<&'a Manifest> for TemplateInfo<'a> {
+    fn from(manifest: &'a Manifest) -> Self {
+        Self {
+            id: manifest.id(),
+            name: &manifest.name,
+            language: &manifest.language,
+            stage: &manifest.stage,
+            description: &manifest.description,
+            tags: &manifest.tags,
+            file: &manifest.file,
+            manifest: &manifest.path,
+            variables: manifest.variables.iter().map(VariableInfo::from).collect(),
+        }
+    }
+}
+
+/// A declared template variable.
+#[derive(Debug, Serialize)]
+pub struct VariableInfo<'a> {
+    pub name: &'a str,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub required: bool,
+    pub default: Option<&'a str>,
+    pub description: Option<&'a str>,
+    pub placeholder: Option<&'a str>,
+}
+
+impl<'a> From<&'a VariableSpec> for VariableInfo<'a> {
+    fn from(spec: &'a VariableSpec) -> Self {
+        Self {
+            name: &spec.name,
+            kind: spec.kind.to_string(),
+            required: spec.is_required(),
+            default: spec.default.as_deref(),
+            description: spec.description.as_deref(),
+            placeholder: spec.placeholder.as_deref(),
+        }
+    }
+}
+
+/// What `cc-scaffold new` did, or would do with `--dry-run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Created,
+    Overwritten,
+    WouldCreate,
+    WouldOverwrite,
+    /// A snippet was injected into the existing destination.
+    Injected,
+    WouldInject,
+    /// Only the managed regions of the existing destination were replaced.
+    Merged,
+    WouldMerge,
+    Unchanged,
+}
+
+/// Result of `cc-scaffold new`.
+#[derive(Debug, Serialize)]
+pub struct NewReport<'a> {
+    pub template: &'a str,
+    pub source: &'a Path,
+    pub destination: &'a Path,
+    pub action: Action,
+    pub contents: &'a str,
+    pub diff: Option<&'a str>,
+    pub warnings: &'a [String],
+}
+
+/// One file of a generated project.
+#[derive(Debug, Serialize)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub action: Action,
+    pub diff: Option<String>,
+}
+
+/// Result of `cc-scaffold new` with a cargo-generate project template.
+#[derive(Debug, Serialize)]
+pub struct ProjectReport<'a> {
+    pub template: &'a str,
+    pub source: &'a Path,
+    pub destination: &'a Path,
+    pub files: &'a [FileReport],
+    /// Member added to the enclosing workspace's `Cargo.toml`, for
+    /// `cc-scaffold crate`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace_member: Option<&'a str>,
+    pub warnings: &'a [String],
+}
+
+/// What `cc-scaffold update` did to one locked file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateAction {
+    Updated,
+    WouldUpdate,
+    UpToDate,
+    /// The file was edited since it was generated and `--force` was not given.
+    Skipped,
+}
+
+/// One entry of the result of `cc-scaffold update`.
+#[derive(Debug, Serialize)]
+pub struct UpdateReport {
+    pub path: PathBuf,
+    pub template: String,
+    pub action: UpdateAction,
+    pub revision: Option<String>,
+    pub diff: Option<String>,
+}
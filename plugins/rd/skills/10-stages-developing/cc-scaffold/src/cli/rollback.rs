@@ -0,0 +1,81 @@
+//! `cc-scaffold rollback`.
+
+use clap::Args;
+
+use cc_scaffold::journal;
+use cc_scaffold::{locator, Error, Result};
+
+use super::output::{self, Format};
+
+#[derive(Debug, Args)]
+pub struct RollbackArgs {
+    /// Operation to undo, as shown by `--list`; the last one when omitted.
+    id: Option<String>,
+
+    /// List recorded operations, newest first, instead of undoing one.
+    #[arg(long, conflicts_with = "id")]
+    list: bool,
+
+    /// Undo the operation even if files it wrote were changed since,
+    /// discarding those changes.
+    #[arg(short, long)]
+    force: bool,
+
+    /// Show what would be restored and removed without changing anything.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub fn run(args: RollbackArgs, format: Format) -> Result<()> {
+    let Some(root) = journal::root() else {
+        return Err(Error::Rollback {
+            target: "the last operation".to_string(),
+            message: format!(
+                "no home directory for the journal; set {}",
+                locator::HOME_ENV
+            ),
+        });
+    };
+    if args.list {
+        let operations = journal::operations(&root)?;
+        if format.is_json() {
+            output::print_json(&operations);
+            return Ok(());
+        }
+        for operation in &operations {
+            let files = operation.changes.len();
+            let plural = if files == 1 { "" } else { "s" };
+            println!(
+                "{}  {files} file{plural}  {}",
+                operation.id, operation.command
+            );
+        }
+        return Ok(());
+    }
+    let rollback = journal::rollback(&root, args.id.as_deref(), args.force, args.dry_run)?;
+    if format.is_json() {
+        output::print_json(&rollback);
+        return Ok(());
+    }
+    for path in &rollback.restored {
+        if args.dry_run {
+            eprintln!("would restore {}", path.display());
+        } else {
+            println!("restored {}", path.display());
+        }
+    }
+    for path in &rollback.removed {
+        if args.dry_run {
+            eprintln!("would remove {}", path.display());
+        } else {
+            println!("removed {}", path.display());
+        }
+    }
+    if !args.dry_run {
+        println!(
+            "rolled back `{}` ({})",
+            rollback.operation.command, rollback.operation.id
+        );
+    }
+    Ok(())
+}
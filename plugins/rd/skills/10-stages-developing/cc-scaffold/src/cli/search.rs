@@ -0,0 +1,56 @@
+//! `cc-scaffold search`.
+
+use clap::Args;
+
+use cc_scaffold::{search, Locator, Result};
+
+use super::output::{self, Format, TemplateInfo};
+
+#[derive(Debug, Args)]
+pub struct SearchArgs {
+    /// Words to look for in template names, tags and descriptions.
+    #[arg(required = true, num_args = 1..)]
+    query: Vec<String>,
+
+    /// Show at most this many results.
+    #[arg(long, default_value_t = 10)]
+    limit: usize,
+}
+
+pub fn run(locator: &Locator, args: SearchArgs, format: Format) -> Result<()> {
+    let manifests = locator.manifests()?;
+    let mut hits = search::search(&manifests, &args.query.join(" "));
+    hits.truncate(args.limit);
+    if format.is_json() {
+        let results: Vec<_> = hits
+            .iter()
+            .map(|hit| {
+                serde_json::json!({
+                    "score": hit.score,
+                    "template": TemplateInfo::from(hit.manifest),
+                })
+            })
+            .collect();
+        output::print_json(&results);
+        return Ok(());
+    }
+    if hits.is_empty() {
+        eprintln!("no templates match");
+        return Ok(());
+    }
+    let id_width = hits
+        .iter()
+        .map(|h| h.manifest.id().len())
+        .max()
+        .unwrap_or(0)
+        .max(2);
+    println!("{:id_width$}  DESCRIPTION", "ID");
+    for hit in &hits {
+        println!(
+            "{:id_width$}  {}",
+            hit.manifest.id(),
+            hit.manifest.description
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+//! `cc-scaffold selftest`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use clap_complete::ArgValueCandidates;
+
+use cc_scaffold::selftest::{self, Verdict};
+use cc_scaffold::{Error, Locator, Result};
+
+use super::output::{self, Format};
+
+#[derive(Debug, Args)]
+pub struct SelftestArgs {
+    /// Templates to test, e.g. `rust/function`; every Rust template when
+    /// omitted.
+    #[arg(value_name = "TEMPLATE", add = ArgValueCandidates::new(super::template_candidates))]
+    templates: Vec<String>,
+
+    /// Build the scratch crates in DIR and keep them, instead of in a
+    /// temporary directory that is removed afterwards. Scratch crates
+    /// already there are replaced.
+    #[arg(long, value_name = "DIR")]
+    work_dir: Option<PathBuf>,
+}
+
+pub fn run(locator: &Locator, args: SelftestArgs, format: Format) -> Result<()> {
+    let ids: Vec<String> = if args.templates.is_empty() {
+        locator
+            .manifests()?
+            .iter()
+            .filter(|m| m.language == "rust")
+            .map(|m| m.id())
+            .collect()
+    } else {
+        args.templates
+    };
+    let keep = args.work_dir.is_some();
+    let work = args.work_dir.unwrap_or_else(|| {
+        std::env::temp_dir().join(format!("cc-scaffold-selftest-{}", std::process::id()))
+    });
+    let result = test_all(locator, &ids, &work, format);
+    if !keep {
+        let _ = fs::remove_dir_all(&work);
+    }
+    result
+}
+
+fn test_all(locator: &Locator, ids: &[String], work: &Path, format: Format) -> Result<()> {
+    let target_dir = work.join("target");
+    let mut outcomes = Vec::with_capacity(ids.len());
+    for id in ids {
+        let template = locator.resolve(id)?;
+        let outcome = selftest::run(&template, &work.join(id.replace('/', "-")), &target_dir)?;
+        if !format.is_json() {
+            match &outcome.verdict {
+                Verdict::Passed => println!("ok       {id}"),
+                Verdict::Skipped { reason } => println!("skipped  {id} ({reason})"),
+                Verdict::Failed { step, output, .. } => {
+                    println!("FAILED   {id} ({step})");
+                    for line in output.lines() {
+                        println!("    {line}");
+                    }
+                }
+            }
+        }
+        outcomes.push(outcome);
+    }
+    if outcomes
+        .iter()
+        .any(|o| matches!(o.verdict, Verdict::Failed { .. }))
+    {
+        return Err(Error::SelfTest { outcomes });
+    }
+    if format.is_json() {
+        output::print_json(&outcomes);
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+//! `cc-scaffold templates`: managing the user template registry.
+
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+
+use cc_scaffold::{locator, registry, Error, Result};
+
+use super::output::{self, Format};
+
+#[derive(Debug, Args)]
+pub struct TemplatesArgs {
+    #[command(subcommand)]
+    command: TemplatesCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum TemplatesCommand {
+    /// Copy a manifest, template file or templates directory into the user
+    /// registry (`$CC_AGENTS_HOME/templates` or `~/.cc-agents/templates`).
+    Add {
+        /// Manifest, template file or directory laid out as `<language>/...`.
+        path: PathBuf,
+
+        /// Replace files already in the registry.
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+pub fn run(args: TemplatesArgs, format: Format) -> Result<()> {
+    match args.command {
+        TemplatesCommand::Add { path, force } => add(&path, force, format),
+    }
+}
+
+fn add(path: &Path, force: bool, format: Format) -> Result<()> {
+    let Some(registry) = locator::user_templates_dir() else {
+        return Err(Error::Registry {
+            path: path.to_path_buf(),
+            message: format!("no home directory; set {}", locator::HOME_ENV),
+        });
+    };
+    let written = registry::add(path, &registry, force)?;
+    if format.is_json() {
+        output::print_json(&serde_json::json!({
+            "registry": registry,
+            "files": written,
+        }));
+        return Ok(());
+    }
+    for file in &written {
+        println!("added {}", file.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,191 @@
+//! `cc-scaffold update`.
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use clap::Args;
+use clap_complete::ArgValueCandidates;
+
+use cc_scaffold::generate::{self, Preview};
+use cc_scaffold::hooks;
+use cc_scaffold::journal::Journal;
+use cc_scaffold::lockfile::{self, Lockfile};
+use cc_scaffold::{Error, Locator, Result};
+
+use super::output::{self, Format, UpdateAction, UpdateReport};
+use super::Resolved;
+
+#[derive(Debug, Args)]
+pub struct UpdateArgs {
+    /// Templates to update; every locked template when omitted.
+    #[arg(add = ArgValueCandidates::new(super::template_candidates))]
+    templates: Vec<String>,
+
+    /// Show what would change without writing files or the lockfile.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Accept template versions whose signatures do not verify.
+    #[arg(long)]
+    allow_unverified: bool,
+
+    /// Regenerate files even if they were edited since they were generated.
+    #[arg(short, long)]
+    force: bool,
+}
+
+pub fn run(
+    locator: &Locator,
+    args: UpdateArgs,
+    format: Format,
+    journal: &mut Journal,
+) -> Result<()> {
+    let cwd = std::env::current_dir().map_err(|err| Error::io(".", err))?;
+    let lock_path = lockfile::locate(&cwd);
+    if !lock_path.is_file() {
+        return Err(Error::Lockfile {
+            path: lock_path,
+            message: "not found; it is created by `cc-scaffold new`".to_string(),
+        });
+    }
+    let mut lock = Lockfile::load(&lock_path)?;
+    for id in &args.templates {
+        if lock.template(id).is_none() {
+            return Err(Error::Lockfile {
+                path: lock_path,
+                message: format!("no file is generated from `{id}`"),
+            });
+        }
+    }
+    let root = lock_path.parent().unwrap_or(&cwd).to_path_buf();
+    // Each template is resolved at its newest version once, however many
+    // files it generated.
+    let mut latest: BTreeMap<String, Resolved> = BTreeMap::new();
+    let mut reports = Vec::new();
+    for entry in &mut lock.files {
+        if !args.templates.is_empty() && !args.templates.contains(&entry.template) {
+            continue;
+        }
+        if !latest.contains_key(&entry.template) {
+            let resolved = super::resolve(locator, &entry.template, None, true)?;
+            latest.insert(entry.template.clone(), resolved);
+        }
+        let resolved = &latest[&entry.template];
+        let dest = root.join(&entry.path);
+        let mut warnings = Vec::new();
+        let mut file = match &entry.bundle {
+            Some(bundle) => {
+                let files =
+                    generate::render_bundle(&resolved.template, &root.join(bundle), &entry.values)?;
+                match files.into_iter().find(|f| f.destination == dest) {
+                    Some(file) => file,
+                    None => {
+                        output::warn(
+                            format,
+                            &format!(
+                                "{} is no longer generated by `{}`; skipped it",
+                                entry.path.display(),
+                                entry.template
+                            ),
+                        );
+                        reports.push(UpdateReport {
+                            path: entry.path.clone(),
+                            template: entry.template.clone(),
+                            action: UpdateAction::Skipped,
+                            revision: resolved.revision.clone(),
+                            diff: None,
+                        });
+                        continue;
+                    }
+                }
+            }
+            None => generate::render(&resolved.template, &dest, &entry.values)?,
+        };
+        if let Some(missing) = file.merge_regions()? {
+            for name in missing {
+                warnings.push(format!(
+                    "{} has no managed region `{name}`; it was not generated",
+                    entry.path.display()
+                ));
+            }
+        }
+        if entry.rustfmt {
+            file.contents = hooks::rustfmt(&dest, &file.contents)?;
+        }
+        super::verify(
+            &entry.template,
+            resolved,
+            &file.inputs,
+            None,
+            args.allow_unverified,
+            &mut warnings,
+        )?;
+        for warning in &warnings {
+            output::warn(format, warning);
+        }
+        let hash = file.fingerprint()?;
+        let mut report = UpdateReport {
+            path: entry.path.clone(),
+            template: entry.template.clone(),
+            action: UpdateAction::UpToDate,
+            revision: resolved.revision.clone(),
+            diff: None,
+        };
+        if hash == entry.hash && resolved.revision == entry.revision {
+            reports.push(report);
+            continue;
+        }
+        // Only managed regions count as generated, so editing elsewhere in
+        // the file does not stop it from being updated.
+        let edited = fs::read_to_string(&dest)
+            .map(|current| generate::output_hash(&current) != entry.output)
+            .unwrap_or(false);
+        if edited && !args.force {
+            output::warn(
+                format,
+                &format!(
+                    "{} was edited since it was generated; pass --force to regenerate it",
+                    entry.path.display()
+                ),
+            );
+            report.action = UpdateAction::Skipped;
+        } else if args.dry_run {
+            report.action = UpdateAction::WouldUpdate;
+            report.diff = match generate::preview(&file)? {
+                Preview::Create(diff) | Preview::Overwrite(diff) => Some(diff),
+                Preview::Unchanged => None,
+            };
+        } else {
+            journal.record(&dest)?;
+            generate::write(&file, true)?;
+            entry.source = resolved.source_of(&file.template);
+            entry.revision.clone_from(&resolved.revision);
+            entry.hash = hash;
+            entry.output = generate::output_hash(&file.contents);
+            report.action = UpdateAction::Updated;
+        }
+        reports.push(report);
+    }
+    if !args.dry_run {
+        journal.record(&lock_path)?;
+        lock.save(&lock_path)?;
+    }
+
+    if format.is_json() {
+        output::print_json(&reports);
+        return Ok(());
+    }
+    for report in &reports {
+        let path = report.path.display();
+        if let Some(diff) = &report.diff {
+            print!("{diff}");
+        }
+        match report.action {
+            UpdateAction::Updated => println!("updated {path}"),
+            UpdateAction::WouldUpdate => eprintln!("would update {path}"),
+            UpdateAction::UpToDate => eprintln!("{path} is up to date"),
+            UpdateAction::Skipped => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! `cc-scaffold validate`.
+
+use clap::Args;
+use clap_complete::ArgValueCandidates;
+
+use cc_scaffold::lint::{self, Problem};
+use cc_scaffold::{manifest, Error, Locator, Manifest, Result};
+
+use super::output::{self, Format};
+
+#[derive(Debug, Args)]
+pub struct ValidateArgs {
+    /// Templates to check, e.g. `rust/function`; every manifest under the
+    /// template roots when omitted.
+    #[arg(value_name = "TEMPLATE", add = ArgValueCandidates::new(super::template_candidates))]
+    templates: Vec<String>,
+}
+
+pub fn run(locator: &Locator, args: ValidateArgs, format: Format) -> Result<()> {
+    let mut problems = Vec::new();
+    let mut manifests = Vec::new();
+    if args.templates.is_empty() {
+        for root in locator.roots() {
+            for path in manifest::find(root)? {
+                match Manifest::load(&path) {
+                    Ok(manifest) => manifests.push(manifest),
+                    Err(err) => problems.push(Problem::from_error(&path, &err)),
+                }
+            }
+        }
+    } else {
+        for id in &args.templates {
+            let template = locator.resolve(id)?;
+            match template.manifest {
+                Some(manifest) => manifests.push(manifest),
+                None => problems.push(Problem::from_error(
+                    &template.source,
+                    &Error::Manifest {
+                        path: template.source.clone(),
+                        message: "template has no manifest to validate".to_string(),
+                    },
+                )),
+            }
+        }
+    }
+    for manifest in &manifests {
+        problems.extend(lint::check(manifest, &locator.include_dirs_for(manifest)));
+    }
+    if !problems.is_empty() {
+        if !format.is_json() {
+            for problem in &problems {
+                println!("{problem}");
+            }
+        }
+        return Err(Error::Lint { problems });
+    }
+    if format.is_json() {
+        output::print_json(&serde_json::json!({
+            "templates": manifests.len(),
+            "problems": problems,
+        }));
+    } else {
+        let plural = if manifests.len() == 1 { "" } else { "s" };
+        eprintln!("{} template{plural} checked, no problems", manifests.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+//! `cc-scaffold test-vectors`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+
+use cc_scaffold::config::Config;
+use cc_scaffold::generate::{self, Preview};
+use cc_scaffold::hooks;
+use cc_scaffold::inject::{self, Point};
+use cc_scaffold::journal::Journal;
+use cc_scaffold::vectors::Table;
+use cc_scaffold::{Error, Locator, Result};
+
+use super::new;
+use super::output::{self, Action, Format, NewReport};
+
+/// What the generated test is reported as coming from.
+const TEMPLATE: &str = "rust/test-vectors";
+
+#[derive(Debug, Args)]
+pub struct TestVectorsArgs {
+    /// Rust source file defining the function; the test is injected into
+    /// its `mod tests`, which is added if it has none.
+    file: PathBuf,
+
+    /// Function to test: `name` for a free function, `Type::name` for an
+    /// associated function of an inherent `impl`.
+    function: String,
+
+    /// Print a unified diff of the file instead of writing it.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Format the file with rustfmt before writing it.
+    #[arg(long)]
+    rustfmt: bool,
+
+    /// Ignore `cc-agents.toml`.
+    #[arg(long)]
+    no_config: bool,
+}
+
+/// Injects the test into the file. One generated before is left as it is,
+/// with the expectations filled in since.
+pub fn run(
+    locator: &Locator,
+    mut args: TestVectorsArgs,
+    format: Format,
+    journal: &mut Journal,
+) -> Result<()> {
+    if !args.no_config {
+        args.rustfmt |= Config::discover(Path::new("."))?.hooks.rustfmt;
+    }
+    let invalid = |message| Error::Inject {
+        path: args.file.clone(),
+        message,
+    };
+    let existing = fs::read_to_string(&args.file).map_err(|err| Error::io(&args.file, err))?;
+    let mut table = Table::parse(&existing, &args.function).map_err(invalid)?;
+    let mut file = table.render(locator, &args.file)?;
+    if table.test_exists {
+        table.warnings.push(format!(
+            "{} already has `{}`; remove it to generate it again",
+            args.file.display(),
+            table.test_name
+        ));
+        file.contents = existing;
+    } else {
+        file.contents = inject::inject(&existing, &file.contents, &Point::Auto).map_err(invalid)?;
+        if args.rustfmt {
+            file.contents = hooks::rustfmt(&file.destination, &file.contents)?;
+        }
+    }
+
+    let (action, diff) = match generate::preview(&file)? {
+        Preview::Unchanged => (Action::Unchanged, None),
+        Preview::Create(diff) | Preview::Overwrite(diff) if args.dry_run => {
+            (Action::WouldInject, Some(diff))
+        }
+        Preview::Create(_) | Preview::Overwrite(_) => {
+            journal.record(&file.destination)?;
+            generate::write(&file, true)?;
+            (Action::Injected, None)
+        }
+    };
+
+    if format.is_json() {
+        output::print_json(&NewReport {
+            template: TEMPLATE,
+            source: &file.template,
+            destination: &file.destination,
+            action,
+            contents: &file.contents,
+            diff: diff.as_deref(),
+            warnings: &table.warnings,
+        });
+        return Ok(());
+    }
+    for warning in &table.warnings {
+        output::warn(format, warning);
+    }
+    if let Some(diff) = &diff {
+        print!("{diff}");
+    }
+    new::print_action(action, &file.destination.display().to_string());
+    Ok(())
+}
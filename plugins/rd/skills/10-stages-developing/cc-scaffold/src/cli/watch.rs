@@ -0,0 +1,138 @@
+//! `cc-scaffold watch`.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use clap::Args;
+use clap_complete::ArgValueCandidates;
+
+use cc_scaffold::selftest::{self, Step, Verdict};
+use cc_scaffold::watch::{self, Round, Stamps};
+use cc_scaffold::{Locator, Result};
+
+use super::output::{self, Format};
+
+/// How often the watched files are looked at.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    /// Template to watch, e.g. `rust/function`.
+    #[arg(add = ArgValueCandidates::new(super::template_candidates))]
+    template: String,
+
+    /// TOML file of variable values, overriding the template's samples.
+    /// It is watched along with the template.
+    #[arg(long, value_name = "FILE")]
+    vars: Option<PathBuf>,
+
+    /// Preview directory, under the system temporary directory when
+    /// omitted. Files the template renders there are overwritten.
+    #[arg(short, long, value_name = "DIR")]
+    out: Option<PathBuf>,
+
+    /// Run `cargo check` on the output of every render. A Rust template is
+    /// then previewed as a scratch crate, as for `selftest`.
+    #[arg(long)]
+    check: bool,
+}
+
+pub fn run(locator: &Locator, args: WatchArgs, format: Format) -> Result<()> {
+    // A template that does not resolve at all is an error; one that breaks
+    // while it is edited is reported and watched until it is fixed.
+    let template = locator.resolve(&args.template)?;
+    let dir = args.out.clone().unwrap_or_else(|| {
+        env::temp_dir()
+            .join("cc-scaffold-watch")
+            .join(args.template.replace('/', "-"))
+    });
+    let target_dir = dir.join("target");
+    let mut inputs = vec![template.source.clone()];
+    inputs.extend(template.manifest.as_ref().map(|m| m.path.clone()));
+    let mut written = Vec::new();
+    if !format.is_json() {
+        eprintln!(
+            "watching {} into {}; press Ctrl-C to stop",
+            args.template,
+            dir.display()
+        );
+    }
+    loop {
+        let check = args.check.then_some(target_dir.as_path());
+        let round = render(locator, &args, &dir, check, &written);
+        if let Ok(round) = &round {
+            written.clone_from(&round.files);
+            inputs.clone_from(&round.inputs);
+        }
+        let mut watched = inputs.clone();
+        watched.extend(args.vars.clone());
+        // Stamped before reporting, so that whatever is edited once the
+        // report is out starts another round.
+        let stamps = Stamps::take(&watched);
+        match round {
+            Ok(round) => report(&round, &dir, format),
+            // Nothing was rendered, so the previous round's files and
+            // inputs stand.
+            Err(err) => report(
+                &Round {
+                    template: args.template.clone(),
+                    files: Vec::new(),
+                    inputs: inputs.clone(),
+                    verdict: Verdict::Failed {
+                        step: Step::Render,
+                        output: err.to_string(),
+                        diagnostics: Vec::new(),
+                    },
+                },
+                &dir,
+                format,
+            ),
+        }
+        while !stamps.changed() {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Resolves the template and its values afresh, since either may have
+/// changed, and renders it.
+fn render(
+    locator: &Locator,
+    args: &WatchArgs,
+    dir: &Path,
+    check: Option<&Path>,
+    previous: &[PathBuf],
+) -> Result<Round> {
+    let template = locator.resolve(&args.template)?;
+    let mut values = template
+        .manifest
+        .as_ref()
+        .map(selftest::sample_values)
+        .unwrap_or_default();
+    if let Some(vars) = &args.vars {
+        values.extend(watch::load_values(vars)?);
+    }
+    watch::render(&template, &values, dir, check, previous)
+}
+
+fn report(round: &Round, dir: &Path, format: Format) {
+    if format.is_json() {
+        output::print_json_line(round);
+        return;
+    }
+    let id = &round.template;
+    let plural = if round.files.len() == 1 { "" } else { "s" };
+    let rendered = format!("{} file{plural} in {}", round.files.len(), dir.display());
+    match &round.verdict {
+        Verdict::Passed => println!("ok       {id} ({rendered})"),
+        Verdict::Skipped { reason } => println!("skipped  {id} ({rendered}; {reason})"),
+        Verdict::Failed { step, output, .. } => {
+            println!("FAILED   {id} ({step})");
+            for line in output.lines() {
+                println!("    {line}");
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! `cc-agents.toml`: per-project defaults for `cc-scaffold new`.
+//!
+//! ```toml
+//! # Defaults for variables a template declares.
+//! [variables]
+//! author = "Jane Doe <jane@example.com>"
+//! license = "MIT"
+//! module_prefix = "crate::core"
+//!
+//! # Where files go without --out, by template identifier or language;
+//! # the more specific key wins.
+//! [output]
+//! rust = "src"
+//! "rust/crate-lib" = "crates"
+//!
+//! # Checks to run as if their flags were given. A template's own hooks
+//! # run only with --allow-hooks on the command line.
+//! [hooks]
+//! rustfmt = true
+//! check = false
+//! ```
+//!
+//! Strings in `[variables]` and `[output]` can refer to the environment
+//! with `${VAR}` or `${VAR:-fallback}`, e.g.
+//! `author = "${GIT_AUTHOR_NAME:-Jane Doe}"`; an unset variable without a
+//! fallback is an error.
+//!
+//! Values given with `--set` win over the config, which wins over prompts
+//! and the manifest's defaults. The nearest `cc-agents.toml` in the current
+//! directory or its ancestors applies, with relative output directories
+//! resolved against the directory holding it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::manifest::Manifest;
+use crate::variables;
+
+/// File name of the project configuration.
+pub const CONFIG_FILE: &str = "cc-agents.toml";
+
+/// A parsed `cc-agents.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Default variable values, as they would be given to `--set`.
+    pub variables: BTreeMap<String, String>,
+    /// Output directories by template identifier or language, resolved
+    /// against the config's directory.
+    pub output: BTreeMap<String, PathBuf>,
+    pub hooks: Hooks,
+    /// Path of the config file; empty for the default configuration.
+    pub path: PathBuf,
+}
+
+/// Post-generation checks enabled for every run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    #[serde(default)]
+    pub rustfmt: bool,
+    #[serde(default)]
+    pub check: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    #[serde(default)]
+    variables: BTreeMap<String, toml::Value>,
+    #[serde(default)]
+    output: BTreeMap<String, String>,
+    #[serde(default)]
+    hooks: Hooks,
+}
+
+impl Config {
+    /// Loads the nearest `cc-agents.toml` in `start` or its ancestors, or
+    /// the default configuration if there is none.
+    pub fn discover(start: &Path) -> Result<Self> {
+        let found = crate::lockfile::absolute(start)
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|path| path.is_file());
+        match found {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Reads and validates the config at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+        Self::parse(&text, path)
+    }
+
+    /// Parses config `text` as if it were read from `path`.
+    pub fn parse(text: &str, path: &Path) -> Result<Self> {
+        let invalid = |message: String| Error::Config {
+            path: path.to_path_buf(),
+            message,
+        };
+        let raw: RawConfig = toml::from_str(text).map_err(|err| invalid(err.to_string()))?;
+        let variables = variable_values(raw.variables).map_err(invalid)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut output = BTreeMap::new();
+        for (key, out) in raw.output {
+            let out = variables::expand_env(&out)
+                .map_err(|err| invalid(format!("output `{key}`: {err}")))?;
+            output.insert(key, dir.join(out));
+        }
+        Ok(Self {
+            variables,
+            output,
+            hooks: raw.hooks,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Adds the configured values of variables `manifest` declares to
+    /// `values`, keeping those already set. Others are left out, since an
+    /// undeclared value would be substituted wherever its name appears.
+    pub fn apply(&self, manifest: &Manifest, values: &mut BTreeMap<String, String>) {
+        for (name, value) in &self.variables {
+            if manifest.variable(name).is_some() && !values.contains_key(name) {
+                values.insert(name.clone(), value.clone());
+            }
+        }
+    }
+
+    /// The output directory configured for template `id`: under its full
+    /// identifier, else under its language.
+    pub fn output_dir(&self, id: &str) -> Option<&Path> {
+        let language = id.rsplit_once('/').map(|(language, _)| language);
+        self.output
+            .get(id)
+            .or_else(|| language.and_then(|language| self.output.get(language)))
+            .map(PathBuf::as_path)
+    }
+}
+
+/// Converts `raw` variable values, as found in `[variables]` or a
+/// `watch --vars` file, to the strings they would be given to `--set` as,
+/// expanding environment references in strings.
+pub(crate) fn variable_values(
+    raw: BTreeMap<String, toml::Value>,
+) -> std::result::Result<BTreeMap<String, String>, String> {
+    let mut variables = BTreeMap::new();
+    for (name, value) in raw {
+        let value = match value {
+            toml::Value::String(s) => {
+                variables::expand_env(&s).map_err(|err| format!("variable `{name}`: {err}"))?
+            }
+            toml::Value::Integer(n) => n.to_string(),
+            toml::Value::Boolean(b) => b.to_string(),
+            other => {
+                return Err(format!(
+                    "variable `{name}` must be a string, integer or boolean, not {}",
+                    other.type_str()
+                ))
+            }
+        };
+        variables.insert(name, value);
+    }
+    Ok(variables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+[variables]
+author = "Jane"
+tracing = true
+retries = 3
+
+[output]
+rust = "src"
+"rust/crate-lib" = "crates"
+
+[hooks]
+rustfmt = true
+"#;
+
+    fn parse(text: &str) -> Result<Config> {
+        Config::parse(text, Path::new("project/cc-agents.toml"))
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = parse(CONFIG).unwrap();
+        assert_eq!(config.variables["tracing"], "true");
+        assert_eq!(config.variables["retries"], "3");
+        assert!(config.hooks.rustfmt && !config.hooks.check);
+        // Running a template's own hooks is never up to a config file.
+        let err = parse("[hooks]\nallow = true\n").unwrap_err();
+        assert!(err.to_string().contains("unknown field `allow`"), "{err}");
+        assert_eq!(
+            config.output_dir("rust/function"),
+            Some(Path::new("project/src"))
+        );
+        assert_eq!(
+            config.output_dir("rust/crate-lib"),
+            Some(Path::new("project/crates"))
+        );
+        assert_eq!(config.output_dir("go/function"), None);
+
+        let config = parse("[variables]\nauthor = \"${CC_SCAFFOLD_UNSET:-Jane}\"\n").unwrap();
+        assert_eq!(config.variables["author"], "Jane");
+        let err = parse("[output]\nrust = \"${CC_SCAFFOLD_UNSET}\"\n").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("output `rust`: environment variable"),
+            "{err}"
+        );
+
+        let err = parse("[variables]\ntags = [\"a\"]\n").unwrap_err();
+        assert!(err.to_string().contains("must be a string"), "{err}");
+        assert!(parse("[hooks]\nlint = true\n").is_err());
+    }
+
+    #[test]
+    fn test_apply_only_declared_variables() {
+        let manifest = Manifest::parse(
+            "name = \"function\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\ndescription = \"d\"\n\
+             file = \"f.rs\"\n\n[[variables]]\nname = \"author\"\n\n\
+             [[variables]]\nname = \"tracing\"\ntype = \"bool\"\ndefault = false\n",
+            Path::new("templates/rust/function.template.toml"),
+        )
+        .unwrap();
+        let mut values = BTreeMap::from([("tracing".to_string(), "false".to_string())]);
+        parse(CONFIG).unwrap().apply(&manifest, &mut values);
+        assert_eq!(values["author"], "Jane");
+        assert_eq!(values["tracing"], "false");
+        assert!(!values.contains_key("retries"));
+    }
+}
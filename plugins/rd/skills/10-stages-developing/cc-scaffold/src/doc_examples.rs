@@ -0,0 +1,680 @@
+//! Doc-example conformance, for `cc-scaffold doc-examples`.
+//!
+//! Every public item of the Rust files a [`Survey`] is given must have a
+//! `///` doc comment with an example `cargo test` runs: a fenced block that
+//! is Rust, plain or with rustdoc attributes such as `should_panic`. A
+//! `compile_fail` block does not count, since it shows what does not work.
+//!
+//! An `ignore` or `no_run` block counts, but has to say why it is not run,
+//! in a `//` comment as its first line:
+//!
+//! ```text
+//! /// ```no_run
+//! /// // Connects to the configured server.
+//! /// let client = Client::connect(&config)?;
+//! /// ```
+//! ```
+//!
+//! One that does not is reported in any doc comment, a module's `//!`
+//! included. Files are parsed with `syn`, so an item is public only with a
+//! plain `pub`, or `#[macro_export]` for a `macro_rules!` macro, and items
+//! under `#[cfg(test)]` are skipped. Template files do not parse until
+//! rendered: [`Survey::add_templates`] renders each with its sample values
+//! and checks the output, reporting each problem against the template file
+//! at the line of the rendering its message names. [`Survey::stage_report`] makes the problems the
+//! `5-implementation` stage's `doc-examples` gate.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+use syn::{Attribute, Expr, Fields, ForeignItem, ImplItem, Item, Lit, Meta, TraitItem, Visibility};
+
+use crate::error::{Error, Result};
+use crate::generate;
+use crate::hooks::{self, Diagnostic};
+use crate::locator::{LocatedTemplate, Locator};
+use crate::selftest;
+use crate::stage::{self, StageReport};
+use crate::variables::EnvAccess;
+
+/// Rustdoc attributes of a fenced block that still leave it Rust code
+/// `cargo test` runs.
+const RUNNABLE: &[&str] = &["rust", "should_panic", "test_harness", "standalone_crate"];
+
+/// The public items of some Rust files, by how their examples stand.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Survey {
+    /// Items with an example.
+    pub documented: usize,
+    /// Items without a doc comment.
+    pub undocumented: Vec<Diagnostic>,
+    /// Items whose doc comment has no example.
+    pub missing_example: Vec<Diagnostic>,
+    /// `ignore` and `no_run` examples that do not say why.
+    pub unjustified: Vec<Diagnostic>,
+    /// Files that are not Rust `syn` can parse, where parsing stopped.
+    pub unparsed: Vec<Diagnostic>,
+}
+
+/// What a fenced block in a doc comment holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block {
+    /// Rust that is compiled and run.
+    Runnable,
+    /// Rust marked with this attribute, `ignore` or `no_run`.
+    NotRun(&'static str),
+    /// Rust that must fail to compile.
+    CompileFail,
+    /// Anything else, e.g. `text` or `toml`.
+    Other,
+}
+
+impl Block {
+    /// The block a fence with `info` opens, e.g. `rust,no_run`.
+    fn parse(info: &str) -> Self {
+        let mut block = Block::Runnable;
+        let tokens = info
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty());
+        for token in tokens {
+            match token {
+                "ignore" => block = Block::NotRun("ignore"),
+                "no_run" => block = Block::NotRun("no_run"),
+                "compile_fail" => block = Block::CompileFail,
+                token if token.starts_with("ignore-") => block = Block::NotRun("ignore"),
+                token if RUNNABLE.contains(&token) || token.starts_with("edition") => {}
+                _ => return Block::Other,
+            }
+        }
+        block
+    }
+}
+
+/// A fenced block of a doc comment.
+struct Fence {
+    /// Line opening it, from 1.
+    line: usize,
+    column: usize,
+    block: Block,
+    /// Whether its first line is a `//` comment.
+    justified: bool,
+}
+
+impl Survey {
+    /// Checks every Rust file at `path`, a file or a directory searched
+    /// recursively, without `target/` and hidden directories.
+    pub fn add_path(&mut self, path: &Path) -> Result<()> {
+        if !path.is_dir() {
+            let source = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+            self.add_source(path, &source);
+            return Ok(());
+        }
+        let entries = fs::read_dir(path).map_err(|err| Error::io(path, err))?;
+        let mut paths = Vec::new();
+        for entry in entries {
+            paths.push(entry.map_err(|err| Error::io(path, err))?.path());
+        }
+        paths.sort();
+        for path in paths {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if path.is_dir() {
+                if !name.starts_with('.') && name != "target" {
+                    self.add_path(&path)?;
+                }
+            } else if hooks::is_rust(&path) {
+                self.add_path(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the Rust files of every template with a manifest under
+    /// `locator`'s roots, rendered with its sample values, except snippets
+    /// injected into another file. Problems are reported against the
+    /// template file, at lines of the rendering their message names, and
+    /// once when manifests sharing a file render the same problem.
+    pub fn add_templates(&mut self, locator: &Locator) -> Result<()> {
+        let mut checked = BTreeSet::new();
+        for manifest in locator.manifests()? {
+            if manifest.files.is_empty() && !hooks::is_rust(&manifest.file) {
+                continue;
+            }
+            // Snippets injected into another file are not Rust of their own.
+            let injected: Vec<PathBuf> = manifest
+                .files
+                .iter()
+                .filter(|file| file.inject.is_some())
+                .flat_map(|file| std::iter::once(&file.template).chain(file.editions.values()))
+                .cloned()
+                .collect();
+            let values = selftest::sample_values(&manifest);
+            let output = selftest::crate_output(&manifest, Path::new(""));
+            let template = LocatedTemplate {
+                source: manifest.file.clone(),
+                include_dirs: locator.include_dirs_for(&manifest),
+                manifest: Some(manifest),
+                env: EnvAccess::All,
+            };
+            for file in generate::render_bundle(&template, &output, &values)? {
+                if !hooks::is_rust(&file.destination) || injected.contains(&file.template) {
+                    continue;
+                }
+                let mut rendered = Survey::default();
+                rendered.add_source(&file.template, &file.contents);
+                let first = checked.insert((file.template, file.destination.clone()));
+                self.add_rendering(rendered, &file.destination, first);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds `rendered`, the survey of a template file rendered to
+    /// `destination`, saying so in each message and leaving out problems
+    /// already reported. Its examples are counted only if `first`.
+    fn add_rendering(&mut self, rendered: Survey, destination: &Path, first: bool) {
+        if first {
+            self.documented += rendered.documented;
+        }
+        let merge = |into: &mut Vec<Diagnostic>, diagnostics: Vec<Diagnostic>| {
+            for mut diagnostic in diagnostics {
+                diagnostic.message = format!(
+                    "in its rendering to {}: {}",
+                    destination.display(),
+                    diagnostic.message
+                );
+                if !into.contains(&diagnostic) {
+                    into.push(diagnostic);
+                }
+            }
+        };
+        merge(&mut self.undocumented, rendered.undocumented);
+        merge(&mut self.missing_example, rendered.missing_example);
+        merge(&mut self.unjustified, rendered.unjustified);
+        merge(&mut self.unparsed, rendered.unparsed);
+    }
+
+    /// Checks `source`, the contents of `file`.
+    pub fn add_source(&mut self, file: &Path, source: &str) {
+        let lines: Vec<&str> = source.lines().collect();
+        match syn::parse_file(source) {
+            Ok(syntax) => {
+                let mut walk = Walk {
+                    survey: self,
+                    file,
+                    lines: &lines,
+                };
+                walk.fences(&syntax.attrs);
+                walk.items(&syntax.items);
+            }
+            Err(err) => {
+                let start = err.span().start();
+                self.unparsed.push(Diagnostic {
+                    file: file.to_path_buf(),
+                    line: start.line,
+                    column: start.column + 1,
+                    message: format!("cannot parse it as Rust: {err}"),
+                });
+            }
+        }
+        // The survey keeps no spans, and the source they point into would
+        // otherwise stay in memory for every file checked.
+        proc_macro2::extra::invalidate_current_thread_spans();
+    }
+
+    /// The `5-implementation` gate: passed if every public item has an
+    /// example and every example that is not run says why.
+    pub fn stage_report(&self) -> StageReport {
+        let counts = BTreeMap::from([
+            ("documented".to_string(), self.documented),
+            ("undocumented".to_string(), self.undocumented.len()),
+            ("missing_example".to_string(), self.missing_example.len()),
+            ("unjustified".to_string(), self.unjustified.len()),
+            ("unparsed".to_string(), self.unparsed.len()),
+        ]);
+        let mut problems: Vec<Diagnostic> = self
+            .undocumented
+            .iter()
+            .chain(&self.missing_example)
+            .chain(&self.unjustified)
+            .chain(&self.unparsed)
+            .cloned()
+            .collect();
+        problems.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+        StageReport::new(stage::IMPLEMENTATION, "doc-examples", counts, problems)
+    }
+}
+
+/// One file's items being added to a [`Survey`].
+struct Walk<'a> {
+    survey: &'a mut Survey,
+    file: &'a Path,
+    lines: &'a [&'a str],
+}
+
+impl Walk<'_> {
+    fn items(&mut self, items: &[Item]) {
+        for item in items {
+            match item {
+                Item::Fn(f) if is_test(&f.attrs) => {}
+                Item::Fn(f) => self.item(&f.attrs, public(&f.vis), "function", &f.sig.ident),
+                Item::Struct(s) if is_test(&s.attrs) => {}
+                Item::Struct(s) => {
+                    self.item(&s.attrs, public(&s.vis), "struct", &s.ident);
+                    self.fields(&s.fields);
+                }
+                Item::Enum(e) if is_test(&e.attrs) => {}
+                Item::Enum(e) => {
+                    self.item(&e.attrs, public(&e.vis), "enum", &e.ident);
+                    for variant in &e.variants {
+                        self.fences(&variant.attrs);
+                        self.fields(&variant.fields);
+                    }
+                }
+                Item::Union(u) if is_test(&u.attrs) => {}
+                Item::Union(u) => {
+                    self.item(&u.attrs, public(&u.vis), "union", &u.ident);
+                    for field in &u.fields.named {
+                        self.fences(&field.attrs);
+                    }
+                }
+                Item::Trait(t) if is_test(&t.attrs) => {}
+                Item::Trait(t) => {
+                    self.item(&t.attrs, public(&t.vis), "trait", &t.ident);
+                    for item in &t.items {
+                        if let Some(attrs) = trait_item_attrs(item) {
+                            self.fences(attrs);
+                        }
+                    }
+                }
+                Item::Type(t) if is_test(&t.attrs) => {}
+                Item::Type(t) => self.item(&t.attrs, public(&t.vis), "type alias", &t.ident),
+                Item::Const(c) if is_test(&c.attrs) => {}
+                Item::Const(c) => self.item(&c.attrs, public(&c.vis), "constant", &c.ident),
+                Item::Static(s) if is_test(&s.attrs) => {}
+                Item::Static(s) => self.item(&s.attrs, public(&s.vis), "static", &s.ident),
+                Item::Mod(m) if is_test(&m.attrs) => {}
+                Item::Mod(m) => {
+                    self.item(&m.attrs, public(&m.vis), "module", &m.ident);
+                    if let Some((_, items)) = &m.content {
+                        self.items(items);
+                    }
+                }
+                Item::Macro(m) if is_test(&m.attrs) => {}
+                Item::Macro(m) => {
+                    let exported = m.mac.path.is_ident("macro_rules")
+                        && m.attrs.iter().any(|a| a.path().is_ident("macro_export"));
+                    match &m.ident {
+                        Some(ident) if exported => {
+                            self.item(&m.attrs, Some(m.mac.path.span()), "macro", ident);
+                        }
+                        _ => {
+                            self.fences(&m.attrs);
+                        }
+                    }
+                }
+                Item::Impl(i) if is_test(&i.attrs) => {}
+                Item::Impl(i) => {
+                    self.fences(&i.attrs);
+                    for item in &i.items {
+                        match item {
+                            ImplItem::Fn(f) if !is_test(&f.attrs) => {
+                                self.item(&f.attrs, public(&f.vis), "function", &f.sig.ident);
+                            }
+                            ImplItem::Const(c) if !is_test(&c.attrs) => {
+                                self.item(&c.attrs, public(&c.vis), "constant", &c.ident);
+                            }
+                            ImplItem::Type(t) if !is_test(&t.attrs) => {
+                                self.item(&t.attrs, public(&t.vis), "type alias", &t.ident);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Item::ForeignMod(f) if is_test(&f.attrs) => {}
+                Item::ForeignMod(f) => {
+                    self.fences(&f.attrs);
+                    for item in &f.items {
+                        match item {
+                            ForeignItem::Fn(f) => {
+                                self.item(&f.attrs, public(&f.vis), "function", &f.sig.ident);
+                            }
+                            ForeignItem::Static(s) => {
+                                self.item(&s.attrs, public(&s.vis), "static", &s.ident);
+                            }
+                            ForeignItem::Type(t) => {
+                                self.item(&t.attrs, public(&t.vis), "type", &t.ident);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Checks the fields of a struct or enum variant for examples that do
+    /// not say why they are not run.
+    fn fields(&mut self, fields: &Fields) {
+        for field in fields {
+            self.fences(&field.attrs);
+        }
+    }
+
+    /// Counts the item with `attrs` if it is public, declared at `public`,
+    /// and checks its doc comment's examples.
+    fn item(&mut self, attrs: &[Attribute], public: Option<Span>, kind: &str, name: &syn::Ident) {
+        let fences = self.fences(attrs);
+        let Some(span) = public.filter(|_| !is_hidden(attrs)) else {
+            return;
+        };
+        let start = span.start();
+        let diagnostic = |message: String| Diagnostic {
+            file: self.file.to_path_buf(),
+            line: start.line,
+            column: start.column + 1,
+            message,
+        };
+        let item = format!("public {kind} `{name}`");
+        if !attrs.iter().any(|attr| doc(attr).is_some()) {
+            let diagnostic = diagnostic(format!("{item} has no doc comment"));
+            self.survey.undocumented.push(diagnostic);
+        } else if fences
+            .iter()
+            .any(|fence| matches!(fence.block, Block::Runnable | Block::NotRun(_)))
+        {
+            self.survey.documented += 1;
+        } else {
+            let diagnostic = diagnostic(format!("the doc comment of {item} has no example"));
+            self.survey.missing_example.push(diagnostic);
+        }
+    }
+
+    /// The fenced blocks of the doc comment in `attrs`, in order, with the
+    /// `ignore` and `no_run` ones that do not say why reported. A block
+    /// left open ends with the comment.
+    fn fences(&mut self, attrs: &[Attribute]) -> Vec<Fence> {
+        let mut fences = Vec::new();
+        let mut open: Option<Fence> = None;
+        let mut first_line = false;
+        for attr in attrs {
+            let Some(Some(text)) = doc(attr) else {
+                continue;
+            };
+            let start = attr.span().start();
+            for (offset, text) in text.lines().enumerate() {
+                let trimmed = text.trim();
+                let line = start.line + offset;
+                if let Some(info) = trimmed.strip_prefix("```") {
+                    match open.take() {
+                        Some(fence) => fences.push(fence),
+                        None => {
+                            let column = self
+                                .lines
+                                .get(line - 1)
+                                .and_then(|source| source.find("```"))
+                                .map_or(start.column + 1, |index| index + 1);
+                            open = Some(Fence {
+                                line,
+                                column,
+                                block: Block::parse(info),
+                                justified: false,
+                            });
+                            first_line = true;
+                        }
+                    }
+                } else if let Some(fence) = open.as_mut().filter(|_| first_line) {
+                    if !trimmed.is_empty() {
+                        fence.justified = trimmed.starts_with("//");
+                        first_line = false;
+                    }
+                }
+            }
+        }
+        fences.extend(open);
+        for fence in &fences {
+            if let (Block::NotRun(attribute), false) = (fence.block, fence.justified) {
+                self.survey.unjustified.push(Diagnostic {
+                    file: self.file.to_path_buf(),
+                    line: fence.line,
+                    column: fence.column,
+                    message: format!(
+                        "`{attribute}` example does not say why it is not run: \
+                         start it with a `//` comment giving the reason"
+                    ),
+                });
+            }
+        }
+        fences
+    }
+}
+
+/// Where `vis` says `pub`, if it is plain `pub`.
+fn public(vis: &Visibility) -> Option<Span> {
+    match vis {
+        Visibility::Public(token) => Some(token.span),
+        _ => None,
+    }
+}
+
+/// The text of `attr` if it is a doc comment: `None` if it is not one,
+/// `Some(None)` if its text is not a string literal, e.g.
+/// `#[doc = include_str!("README.md")]`.
+fn doc(attr: &Attribute) -> Option<Option<String>> {
+    let Meta::NameValue(meta) = &attr.meta else {
+        return None;
+    };
+    if !meta.path.is_ident("doc") {
+        return None;
+    }
+    Some(match &meta.value {
+        Expr::Lit(expr) => match &expr.lit {
+            Lit::Str(text) => Some(text.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Whether `attrs` has a `#[doc(hidden)]`.
+fn is_hidden(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| match &attr.meta {
+        Meta::List(list) if list.path.is_ident("doc") => list
+            .tokens
+            .to_string()
+            .split(',')
+            .any(|token| token.trim() == "hidden"),
+        _ => false,
+    })
+}
+
+/// Whether `attrs` has a `#[cfg(test)]`.
+fn is_test(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| match &attr.meta {
+        Meta::List(list) if list.path.is_ident("cfg") => list.tokens.to_string() == "test",
+        _ => false,
+    })
+}
+
+/// The attributes of a trait's item, if it has any.
+fn trait_item_attrs(item: &TraitItem) -> Option<&[Attribute]> {
+    match item {
+        TraitItem::Const(c) => Some(&c.attrs),
+        TraitItem::Fn(f) => Some(&f.attrs),
+        TraitItem::Type(t) => Some(&t.attrs),
+        TraitItem::Macro(m) => Some(&m.attrs),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn survey(source: &str) -> Survey {
+        let mut survey = Survey::default();
+        survey.add_source(Path::new("src/lib.rs"), source);
+        survey
+    }
+
+    fn lines(diagnostics: &[Diagnostic]) -> Vec<usize> {
+        diagnostics.iter().map(|d| d.line).collect()
+    }
+
+    #[test]
+    fn test_survey_requires_an_example_per_public_item() {
+        let survey = survey(
+            "/// Doubles it.\n///\n/// ```\n/// assert_eq!(double(2), 4);\n/// ```\n\
+             #[must_use]\npub fn double(x: u32) -> u32 { x * 2 }\n\n\
+             /// Halves it.\n///\n/// ```text\n/// 4 -> 2\n/// ```\npub const fn half(x: u32) -> u32 { x / 2 }\n\n\
+             pub struct Config;\n\n\
+             /// Rejected.\n///\n/// ```compile_fail\n/// let x: u8 = 256;\n/// ```\npub static LIMIT: u8 = 255;\n\n\
+             #[doc(hidden)]\npub fn internal() {}\n\nfn private() {}\n",
+        );
+        assert_eq!(survey.documented, 1);
+        assert_eq!(lines(&survey.missing_example), [14, 23]);
+        assert_eq!(
+            survey.missing_example[0].message,
+            "the doc comment of public function `half` has no example"
+        );
+        assert_eq!(
+            survey.undocumented[0].message,
+            "public struct `Config` has no doc comment"
+        );
+        assert!(survey.unjustified.is_empty());
+    }
+
+    #[test]
+    fn test_survey_sees_items_a_line_scan_does_not() {
+        let survey = survey(
+            "pub mod api {\n    pub extern \"C\" fn callback() {}\n\n    pub union Bits {\n        \
+             pub word: u32,\n    }\n}\n\n#[macro_export]\nmacro_rules! checked {\n    () => {};\n}\n\n\
+             macro_rules! local {\n    () => {};\n}\n\n\
+             pub(crate) fn internal() {}\npub(super) struct Parent;\nfn private() {}\n",
+        );
+        let messages: Vec<&str> = survey
+            .undocumented
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "public module `api` has no doc comment",
+                "public function `callback` has no doc comment",
+                "public union `Bits` has no doc comment",
+                "public macro `checked` has no doc comment",
+            ]
+        );
+        assert_eq!(lines(&survey.undocumented), [1, 2, 4, 10]);
+        assert_eq!(survey.undocumented[1].column, 5);
+    }
+
+    #[test]
+    fn test_survey_skips_strings_and_test_code() {
+        let survey = survey(
+            "/// Renders.\n///\n/// ```\n/// assert!(render().contains(\"pub\"));\n/// ```\n\
+             pub fn render() -> &'static str {\n    \"pub fn generated() {}\\n\\npub struct Widget;\"\n}\n\n\
+             #[cfg(test)]\nmod tests {\n    pub fn helper() {}\n}\n\n\
+             #[cfg(test)]\npub fn fixture() {}\n",
+        );
+        assert_eq!(survey.documented, 1);
+        assert!(survey.stage_report().passed, "{:?}", survey);
+    }
+
+    #[test]
+    fn test_survey_reports_files_it_cannot_parse() {
+        let survey = survey("pub fn {{ name }}() {}\n");
+        assert_eq!(survey.unparsed.len(), 1);
+        assert_eq!(survey.unparsed[0].line, 1);
+        assert!(survey.unparsed[0]
+            .message
+            .starts_with("cannot parse it as Rust"));
+        let report = survey.stage_report();
+        assert!(!report.passed);
+        assert_eq!(report.counts["unparsed"], 1);
+    }
+
+    #[test]
+    fn test_survey_flags_unexplained_examples_that_are_not_run() {
+        let survey = survey(
+            "//! Client.\n//!\n//! ```ignore\n//! client.run();\n//! ```\n\n\
+             /// Connects.\n///\n/// ```no_run\n///\n/// // Opens a socket.\n/// connect();\n/// ```\n\
+             pub fn connect() {}\n\n\
+             impl Client {\n    /// Sends.\n    ///\n    /// ```rust,no_run\n    /// send();\n    /// ```\n    pub fn send() {}\n}\n",
+        );
+        assert_eq!(survey.documented, 2);
+        assert_eq!(lines(&survey.unjustified), [3, 19]);
+        assert_eq!(survey.unjustified[1].column, 9);
+        assert!(survey.unjustified[0]
+            .message
+            .starts_with("`ignore` example"));
+
+        let report = survey.stage_report();
+        assert!(!report.passed);
+        assert_eq!(report.counts["unjustified"], 2);
+        assert_eq!(report.problems.len(), 2);
+    }
+
+    #[test]
+    fn test_add_path_checks_rust_files_outside_target() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+        fs::write(dir.path().join("src/nested/mod.rs"), "pub fn a() {}\n").unwrap();
+        fs::write(dir.path().join("src/notes.md"), "pub fn b() {}\n").unwrap();
+        fs::write(dir.path().join("target/debug/out.rs"), "pub fn c() {}\n").unwrap();
+
+        let mut survey = Survey::default();
+        survey.add_path(dir.path()).unwrap();
+        assert_eq!(survey.undocumented.len(), 1);
+        assert!(survey.undocumented[0].file.ends_with("src/nested/mod.rs"));
+        assert!(survey.stage_report().to_string().starts_with(
+            "5-implementation gate `doc-examples` failed: 0 documented, 0 missing_example, 1 undocumented"
+        ));
+    }
+
+    #[test]
+    fn test_add_templates_reports_each_rendered_problem_once() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("rust")).unwrap();
+        fs::write(
+            dir.path().join("rust/widget.template.toml"),
+            "name = \"widget\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+             description = \"d\"\nfile = \"widget-template.rs\"\n\n\
+             [[variables]]\nname = \"name\"\ntype = \"identifier\"\nsample = \"Widget\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("rust/widget-template.rs"),
+            "/// A widget.\npub struct {{ name }};\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("rust/gadget.template.toml"),
+            "name = \"gadget\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+             description = \"d\"\nfile = \"widget-template.rs\"\nextends = \"widget\"\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("rust/notes.template.toml"), "name = \"notes\"\nlanguage = \"text\"\nstage = \"0-setup\"\ndescription = \"d\"\nfile = \"notes.md\"\n").unwrap();
+        fs::write(dir.path().join("rust/notes.md"), "pub fn {{ x }}\n").unwrap();
+
+        let mut survey = Survey::default();
+        survey
+            .add_templates(&Locator::new(vec![dir.path().to_path_buf()]))
+            .unwrap();
+        assert!(survey.unparsed.is_empty(), "{:?}", survey.unparsed);
+        assert_eq!(lines(&survey.missing_example), [2]);
+        assert_eq!(
+            survey.missing_example[0].message,
+            "in its rendering to src/lib.rs: \
+             the doc comment of public struct `Widget` has no example"
+        );
+        assert!(survey.missing_example[0]
+            .file
+            .ends_with("rust/widget-template.rs"));
+    }
+}
@@ -0,0 +1,358 @@
+//! Error type shared by the library and the CLI.
+//!
+//! Every error has a stable [`code`](Error::code) naming its class, such as
+//! `missing_variable` or `destination_exists`, so callers can react to it
+//! without matching on the message. [`Error::report`] gathers the code, the
+//! message, the file and position involved and a suggested fix, which
+//! `--format json` prints.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::hooks::Diagnostic;
+use crate::lint::Problem;
+use crate::selftest::{Outcome, Verdict};
+use crate::stage::StageReport;
+use crate::template_engine::Span;
+use crate::variables::ValidationError;
+
+/// Convenience alias used throughout the crate.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Everything that can go wrong while locating, rendering, or writing a template.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// No template matched the requested identifier.
+    #[error("template `{id}` not found (searched: {searched})")]
+    TemplateNotFound { id: String, searched: String },
+
+    /// The identifier matched more than one file and cannot be resolved unambiguously.
+    #[error("template `{id}` is ambiguous: {candidates}")]
+    AmbiguousTemplate { id: String, candidates: String },
+
+    /// The destination exists and `--force` was not given.
+    #[error("destination {} already exists", .0.display())]
+    DestinationExists(PathBuf),
+
+    /// A `--set` argument was not of the form `key=value`.
+    #[error("invalid variable assignment `{0}`, expected key=value")]
+    InvalidAssignment(String),
+
+    /// `cc-agents.toml`, or a `watch --vars` file, is malformed.
+    #[error("invalid config {}: {message}", path.display())]
+    Config { path: PathBuf, message: String },
+
+    /// `--out` was omitted and the config has no output directory for the
+    /// template.
+    #[error("no destination for `{0}`")]
+    NoDestination(String),
+
+    /// A template manifest is malformed.
+    #[error("invalid manifest {}: {message}", path.display())]
+    Manifest { path: PathBuf, message: String },
+
+    /// A path given to `cc-scaffold templates add` cannot be registered.
+    #[error("cannot add {}: {message}", path.display())]
+    Registry { path: PathBuf, message: String },
+
+    /// The document given to `cc-scaffold from-openapi` is malformed or
+    /// cannot be turned into a client.
+    #[error("invalid OpenAPI document {}: {message}", path.display())]
+    OpenApi { path: PathBuf, message: String },
+
+    /// The function given to `cc-scaffold fuzz` cannot be called from a
+    /// fuzz target, or the fuzz crate's `Cargo.toml` is malformed.
+    #[error("cannot generate a fuzz target from {}: {message}", path.display())]
+    Fuzz { path: PathBuf, message: String },
+
+    /// The crate given to `cc-scaffold mutants` has no usable `Cargo.toml`,
+    /// or the results `cargo mutants` wrote are missing or malformed.
+    #[error("cannot check mutants with {}: {message}", path.display())]
+    Mutants { path: PathBuf, message: String },
+
+    /// A workflow stage's gate found problems.
+    #[error("{report}")]
+    Gate { report: Box<StageReport> },
+
+    /// A remote template identifier is malformed or could not be fetched.
+    #[error("remote template `{spec}`: {message}")]
+    Remote { spec: String, message: String },
+
+    /// `cc-agents.lock` is malformed or could not be written.
+    #[error("invalid lockfile {}: {message}", path.display())]
+    Lockfile { path: PathBuf, message: String },
+
+    /// A template no longer matches the version recorded in the lockfile.
+    #[error(
+        "template `{template}` has changed since it was locked (locked {locked}, found {found})"
+    )]
+    LockMismatch {
+        template: String,
+        locked: String,
+        found: String,
+    },
+
+    /// A template file's signature is missing or does not verify.
+    #[error("cannot verify {}: {message}", path.display())]
+    Unverified { path: PathBuf, message: String },
+
+    /// A snippet could not be injected into an existing file.
+    #[error("cannot inject into {}: {message}", path.display())]
+    Inject { path: PathBuf, message: String },
+
+    /// A generated crate could not be added to its workspace.
+    #[error("cannot update workspace {}: {message}", path.display())]
+    Workspace { path: PathBuf, message: String },
+
+    /// `cc-scaffold crate` was given a name Cargo does not accept.
+    #[error("invalid package name `{0}`: use ASCII letters, digits, `-` and `_`, starting with a letter")]
+    PackageName(String),
+
+    /// The managed regions of a file or template are malformed.
+    #[error("cannot merge managed regions of {}: {message}", path.display())]
+    Regions { path: PathBuf, message: String },
+
+    /// An operation in the undo journal could not be rolled back.
+    #[error("cannot roll back {target}: {message}")]
+    Rollback { target: String, message: String },
+
+    /// `cc-scaffold validate` found problems in templates.
+    #[error(
+        "{} problem{} found in templates",
+        problems.len(),
+        if problems.len() == 1 { "" } else { "s" }
+    )]
+    Lint { problems: Vec<Problem> },
+
+    /// Templates failed `cc-scaffold selftest`.
+    #[error(
+        "{} of {} templates failed the self-test: {}",
+        failed(outcomes).len(),
+        outcomes.len(),
+        failed(outcomes).join(", ")
+    )]
+    SelfTest { outcomes: Vec<Outcome> },
+
+    /// A post-generation hook failed or was refused.
+    #[error("{hook} failed for {}:\n{output}", path.display())]
+    Hook {
+        hook: String,
+        path: PathBuf,
+        diagnostics: Vec<Diagnostic>,
+        output: String,
+    },
+
+    /// A required variable was given no value and has no default.
+    #[error("missing value for required variable `{0}`")]
+    MissingVariable(String),
+
+    /// A default derived from other variables could not be rendered.
+    #[error("default for `{variable}` cannot be derived: {message}")]
+    DerivedDefault { variable: String, message: String },
+
+    /// A supplied value does not match the variable's declared type.
+    #[error(transparent)]
+    Validation(Box<ValidationError>),
+
+    /// A template could not be parsed or rendered.
+    #[error("{}{}:{}: {message}", file_prefix(file.as_deref()), span.line, span.column)]
+    Template {
+        file: Option<PathBuf>,
+        span: Span,
+        message: String,
+    },
+
+    /// Filesystem failure, annotated with the path involved.
+    #[error("{}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// An [`Error`] as reported to machines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorReport {
+    /// Class of the error; see [`Error::code`].
+    pub code: &'static str,
+    /// The error's message, as displayed.
+    #[serde(rename = "error")]
+    pub message: String,
+    /// File the error is about, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// Position in `file`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    /// What to do about it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl Error {
+    /// Stable name of the class of error, in snake case.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::TemplateNotFound { .. } => "template_not_found",
+            Error::AmbiguousTemplate { .. } => "ambiguous_template",
+            Error::DestinationExists(_) => "destination_exists",
+            Error::InvalidAssignment(_) => "invalid_assignment",
+            Error::Config { .. } => "invalid_config",
+            Error::NoDestination(_) => "no_destination",
+            Error::Manifest { .. } => "invalid_manifest",
+            Error::Registry { .. } => "registry",
+            Error::OpenApi { .. } => "invalid_openapi",
+            Error::Fuzz { .. } => "fuzz",
+            Error::Mutants { .. } => "mutants",
+            Error::Gate { .. } => "gate_failed",
+            Error::Remote { .. } => "remote",
+            Error::Lockfile { .. } => "invalid_lockfile",
+            Error::LockMismatch { .. } => "lock_mismatch",
+            Error::Unverified { .. } => "unverified",
+            Error::Inject { .. } => "inject",
+            Error::Workspace { .. } => "workspace",
+            Error::PackageName(_) => "invalid_package_name",
+            Error::Regions { .. } => "regions",
+            Error::Rollback { .. } => "rollback",
+            Error::Lint { .. } => "template_problems",
+            Error::SelfTest { .. } => "selftest_failed",
+            Error::Hook { .. } => "hook_failed",
+            Error::MissingVariable(_) => "missing_variable",
+            Error::DerivedDefault { .. } => "derived_default",
+            Error::Validation(_) => "invalid_value",
+            Error::Template { .. } => "template",
+            Error::Io { .. } => "io",
+        }
+    }
+
+    /// The file the error is about, if any.
+    pub fn file(&self) -> Option<&Path> {
+        match self {
+            Error::DestinationExists(path)
+            | Error::Config { path, .. }
+            | Error::Manifest { path, .. }
+            | Error::Registry { path, .. }
+            | Error::OpenApi { path, .. }
+            | Error::Fuzz { path, .. }
+            | Error::Mutants { path, .. }
+            | Error::Lockfile { path, .. }
+            | Error::Unverified { path, .. }
+            | Error::Inject { path, .. }
+            | Error::Workspace { path, .. }
+            | Error::Regions { path, .. }
+            | Error::Hook { path, .. }
+            | Error::Io { path, .. } => Some(path),
+            Error::Validation(err) => err.file.as_deref(),
+            Error::Template { file, .. } => file.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Where in [`file`](Self::file) the error is.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Error::Validation(err) => err.span,
+            Error::Template { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+
+    /// A fix to try, for errors that have an obvious one.
+    pub fn suggestion(&self) -> Option<String> {
+        let suggestion = match self {
+            Error::TemplateNotFound { .. } => {
+                "run `cc-scaffold list` to see the templates, or `cc-scaffold search` to find one"
+                    .to_string()
+            }
+            Error::AmbiguousTemplate { .. } => {
+                "use one of the candidates' full identifiers".to_string()
+            }
+            Error::DestinationExists(_) => {
+                "pass --force to overwrite it, or --dry-run to see the changes".to_string()
+            }
+            Error::NoDestination(_) => {
+                "pass --out, or set a directory under [output] in cc-agents.toml".to_string()
+            }
+            Error::LockMismatch { template, .. } => format!(
+                "run `cc-scaffold update {template}` to accept the new version, \
+                 or pass --allow-unverified"
+            ),
+            Error::Unverified { .. } => "pass --allow-unverified to use it anyway".to_string(),
+            Error::MissingVariable(name) => format!("pass --set {name}=VALUE"),
+            Error::Gate { report } if report.gate == "mutants" => {
+                "add a test that fails for each surviving mutant, or exclude its code \
+                 in .cargo/mutants.toml with the reason"
+                    .to_string()
+            }
+            _ => return None,
+        };
+        Some(suggestion)
+    }
+
+    /// The error with its code, location and suggestion.
+    pub fn report(&self) -> ErrorReport {
+        ErrorReport {
+            code: self.code(),
+            message: self.to_string(),
+            file: self.file().map(Path::to_path_buf),
+            span: self.span(),
+            suggestion: self.suggestion(),
+        }
+    }
+
+    /// Wraps an [`io::Error`] with the path that triggered it.
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Builds a [`Error::Template`] at `span` with no file attached yet.
+    pub fn template(span: Span, message: impl Into<String>) -> Self {
+        Error::Template {
+            file: None,
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// Attaches the template file to a [`Error::Template`] that lacks one.
+    pub fn in_file(self, path: &Path) -> Self {
+        match self {
+            Error::Template {
+                file: None,
+                span,
+                message,
+            } => Error::Template {
+                file: Some(path.to_path_buf()),
+                span,
+                message,
+            },
+            other => other,
+        }
+    }
+}
+
+impl From<ValidationError> for Error {
+    fn from(err: ValidationError) -> Self {
+        Error::Validation(Box::new(err))
+    }
+}
+
+fn file_prefix(file: Option<&Path>) -> String {
+    file.map(|f| format!("{}:", f.display()))
+        .unwrap_or_default()
+}
+
+/// Templates of `outcomes` that failed.
+fn failed(outcomes: &[Outcome]) -> Vec<&str> {
+    outcomes
+        .iter()
+        .filter(|o| matches!(o.verdict, Verdict::Failed { .. }))
+        .map(|o| o.template.as_str())
+        .collect()
+}
@@ -0,0 +1,635 @@
+//! libFuzzer targets generated from a function's signature, for
+//! `cc-scaffold fuzz`.
+//!
+//! [`Target::parse`] finds a public function in a Rust source file, as
+//! [`Table::parse`](crate::vectors::Table::parse) does, and gives each of
+//! its parameters a field of the target's input, which `arbitrary` builds
+//! from the fuzzer's bytes. Fields own their values: a `&str` parameter is
+//! a `String` field, `&[T]` a `Vec<T>`, `&Path` a `PathBuf` and any other
+//! `&T` or `&mut T` a `T`, lent to the call. A function taking only a
+//! `&[u8]` is passed the bytes themselves.
+//!
+//! [`Target::render`] renders the `fuzz/` crate `cargo fuzz` works with:
+//! its `Cargo.toml`, the target in `fuzz_targets/` and a seed corpus in
+//! `corpus/<target>/`. A crate that has one already gets the target added
+//! to its `Cargo.toml` as another `[[bin]]`.
+//!
+//! A parameter of a type `arbitrary` has no implementation for is reported
+//! in [`Target::warnings`]: the target builds once the type implements
+//! `Arbitrary`. Generic, `impl Trait` and borrowed-within types, methods
+//! taking `self`, and async and unsafe functions are rejected.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use syn::{Expr, GenericArgument, Lit, Pat, PathArguments, ReturnType, Safety, Type, Visibility};
+
+use crate::error::{Error, Result};
+use crate::generate::{self, RenderedFile};
+use crate::hooks;
+use crate::locator::Locator;
+use crate::template_engine::filters;
+use crate::template_engine::{Context, Value};
+use crate::vectors::{self, Pass};
+
+const CARGO_TEMPLATE: &str = "rust/fuzz/cargo";
+const BIN_TEMPLATE: &str = "rust/fuzz/bin";
+const TARGET_TEMPLATE: &str = "rust/fuzz/target";
+
+/// What `cargo fuzz` writes and nobody commits.
+const GITIGNORE: &str = "target\nartifacts\ncoverage\n";
+
+/// Names the generated target binds besides the parameters.
+const RESERVED_NAMES: &[&str] = &["data", "input"];
+
+/// Types `arbitrary` implements `Arbitrary` for, given arguments that
+/// implement it.
+const ARBITRARY_TYPES: &[&str] = &[
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "i128",
+    "isize",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "usize",
+    "f32",
+    "f64",
+    "bool",
+    "char",
+    "String",
+    "Vec",
+    "VecDeque",
+    "LinkedList",
+    "BinaryHeap",
+    "HashMap",
+    "HashSet",
+    "BTreeMap",
+    "BTreeSet",
+    "Option",
+    "Result",
+    "Box",
+    "Rc",
+    "Arc",
+    "Cell",
+    "RefCell",
+    "PathBuf",
+    "OsString",
+    "CString",
+    "Duration",
+    "Wrapping",
+    "IpAddr",
+    "Ipv4Addr",
+    "Ipv6Addr",
+    "SocketAddr",
+    "NonZeroU8",
+    "NonZeroU16",
+    "NonZeroU32",
+    "NonZeroU64",
+    "NonZeroUsize",
+];
+
+/// A fuzz target calling one function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    /// The function as its module calls it: `name`, or `Type::name`.
+    pub function: String,
+    /// Name of the target, which `cargo fuzz run` takes.
+    pub name: String,
+    /// One per parameter, in order.
+    pub fields: Vec<Field>,
+    /// Whether the function returns a value, which the target drops.
+    pub returns: bool,
+    /// Parameters whose types need an `Arbitrary` implementation.
+    pub warnings: Vec<String>,
+}
+
+/// The field of the target's input holding one parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    /// Owned type of the field.
+    pub ty: String,
+    /// [`Pass::Value`], [`Pass::Ref`] or [`Pass::Mut`].
+    pub pass: Pass,
+}
+
+impl Target {
+    /// The target for `function` of the Rust file `source`: `name` for a
+    /// function at its top level, `Type::name` for one in an inherent
+    /// `impl` there.
+    pub fn parse(source: &str, function: &str) -> Result<Self, String> {
+        let file = syn::parse_file(source).map_err(|err| format!("it does not parse: {err}"))?;
+        let (owner, name) = match function.rsplit_once("::") {
+            Some((owner, name)) => (Some(owner), name),
+            None => (None, function),
+        };
+        let (sig, impl_generics, vis) = vectors::find(&file.items, owner, name)
+            .ok_or_else(|| format!("no function `{function}` found"))?;
+        if !matches!(vis, Visibility::Public(_)) {
+            return Err(format!(
+                "`{function}` is not `pub`; the fuzz crate calls it from outside the crate"
+            ));
+        }
+        if sig.receiver().is_some() {
+            return Err(format!(
+                "`{function}` takes `self`; only functions without one can be fuzzed"
+            ));
+        }
+        if matches!(sig.safety, Safety::Unsafe(_)) {
+            return Err(format!(
+                "`{function}` is unsafe; arbitrary arguments may break what it requires of them"
+            ));
+        }
+        if sig.asyncness.is_some() {
+            return Err(format!(
+                "`{function}` is async; a fuzz target has no runtime to await it on"
+            ));
+        }
+        if sig.inputs.is_empty() {
+            return Err(format!(
+                "`{function}` takes no parameters, so there is nothing to fuzz"
+            ));
+        }
+        let mut builder = Builder {
+            generics: vectors::type_params(impl_generics)
+                .chain(vectors::type_params(Some(&sig.generics)))
+                .collect(),
+            param: String::new(),
+            warnings: Vec::new(),
+        };
+        let mut fields = Vec::with_capacity(sig.inputs.len());
+        for (index, input) in sig.inputs.iter().enumerate() {
+            let syn::FnArg::Typed(input) = input else {
+                continue;
+            };
+            let name = binding(&input.pat, index);
+            builder.param = name.clone();
+            let (ty, pass) = builder.field(&input.ty)?;
+            fields.push(Field { name, ty, pass });
+        }
+        let returns = match &sig.output {
+            ReturnType::Default => false,
+            ReturnType::Type(_, ty) => !matches!(&**ty, Type::Tuple(t) if t.elems.is_empty()),
+        };
+        Ok(Self {
+            function: function.to_string(),
+            name: filters::apply("snake_case", &function.replace("::", "_")),
+            fields,
+            returns,
+            warnings: builder.warnings,
+        })
+    }
+
+    /// Whether the function takes only a `&[u8]`, which the target passes
+    /// the fuzzer's bytes.
+    pub fn takes_bytes(&self) -> bool {
+        matches!(self.fields.as_slice(), [field] if field.pass == Pass::Ref && field.ty == "Vec<u8>")
+    }
+
+    /// Renders the files of the fuzz crate in `fuzz_dir` for the function
+    /// of module `module` (`""` for the crate root) of package `package`,
+    /// each with whether it adds to an existing file. The `Cargo.toml`
+    /// already there, if any, gets the target's `[[bin]]`; the other files
+    /// of the fuzz crate are left to it.
+    pub fn render(
+        &self,
+        locator: &Locator,
+        fuzz_dir: &Path,
+        package: &str,
+        module: &str,
+    ) -> Result<Vec<(RenderedFile, bool)>> {
+        let krate = package.replace('-', "_");
+        let path = [krate.as_str(), module, &self.function]
+            .into_iter()
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>()
+            .join("::");
+        let args: Vec<String> = match self.takes_bytes() {
+            true => vec!["data".to_string()],
+            false => self
+                .fields
+                .iter()
+                .map(|field| match field.pass {
+                    Pass::Ref | Pass::Path => format!("&{}", field.name),
+                    Pass::Mut => format!("&mut {}", field.name),
+                    Pass::Value => field.name.clone(),
+                })
+                .collect(),
+        };
+        let bindings: Vec<String> = self
+            .fields
+            .iter()
+            .map(|field| match field.pass {
+                Pass::Mut => format!("mut {}", field.name),
+                _ => field.name.clone(),
+            })
+            .collect();
+        let fields = self.fields.iter().map(|field| {
+            Value::Map(BTreeMap::from([
+                ("name".to_string(), Value::from(field.name.as_str())),
+                ("ty".to_string(), Value::from(field.ty.as_str())),
+            ]))
+        });
+        let mut ctx = Context::new();
+        ctx.set("package", package);
+        ctx.set("path", path.as_str());
+        ctx.set("target", self.name.as_str());
+        ctx.set("bytes", self.takes_bytes());
+        ctx.set("fields", Value::List(fields.collect()));
+        ctx.set("bindings", bindings.join(", "));
+        ctx.set("call", format!("{path}({})", args.join(", ")));
+        ctx.set("returns", self.returns);
+
+        let manifest = fuzz_dir.join("Cargo.toml");
+        let bin_template = locator.resolve(BIN_TEMPLATE)?;
+        let bin = generate::render_file(&bin_template.source, &manifest, &ctx)?;
+        let mut files = Vec::new();
+        match fs::read_to_string(&manifest) {
+            Ok(existing) => {
+                let mut file = bin;
+                file.contents = add_bin(&manifest, &existing, &self.name, &file.contents)?;
+                files.push((file, true));
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                let template = locator.resolve(CARGO_TEMPLATE)?;
+                let mut file = generate::render_file(&template.source, &manifest, &ctx)?;
+                file.contents.push('\n');
+                file.contents.push_str(&bin.contents);
+                file.inputs.extend(bin.inputs);
+                files.push((file, false));
+                files.push((
+                    RenderedFile {
+                        template: template.source.clone(),
+                        destination: fuzz_dir.join(".gitignore"),
+                        contents: GITIGNORE.to_string(),
+                        inputs: Vec::new(),
+                    },
+                    false,
+                ));
+            }
+            Err(err) => return Err(Error::io(&manifest, err)),
+        }
+        let template = locator.resolve(TARGET_TEMPLATE)?;
+        let target = fuzz_dir
+            .join("fuzz_targets")
+            .join(format!("{}.rs", self.name));
+        let target = generate::render_file(&template.source, &target, &ctx)?;
+        let seed = RenderedFile {
+            template: template.source.clone(),
+            destination: fuzz_dir.join("corpus").join(&self.name).join("empty"),
+            contents: String::new(),
+            inputs: Vec::new(),
+        };
+        files.push((target, false));
+        files.push((seed, false));
+        Ok(files)
+    }
+}
+
+/// `manifest` with the `[[bin]]` section `bin` for `target` appended,
+/// unless it has one for it already.
+fn add_bin(path: &Path, manifest: &str, target: &str, bin: &str) -> Result<String> {
+    let table: toml::Table = manifest.parse().map_err(|err| Error::Fuzz {
+        path: path.to_path_buf(),
+        message: format!("it does not parse: {err}"),
+    })?;
+    let bins = table.get("bin").and_then(|bins| bins.as_array());
+    let listed = bins.into_iter().flatten().any(|bin| {
+        bin.get("name")
+            .and_then(|name| name.as_str())
+            .is_some_and(|name| name == target)
+    });
+    if listed {
+        return Ok(manifest.to_string());
+    }
+    let mut manifest = manifest.trim_end().to_string();
+    manifest.push_str("\n\n");
+    manifest.push_str(bin);
+    Ok(manifest)
+}
+
+/// The path from the crate root of the module `file` defines, `file`
+/// being relative to `src/`: `""` for `lib.rs`, `a::b` for `a/b.rs` or
+/// `a/b/mod.rs`. `None` for a file of a binary, which nothing can call
+/// into.
+pub fn module_path(file: &Path) -> Option<String> {
+    let mut segments: Vec<String> = Vec::new();
+    for component in file.components() {
+        let Component::Normal(segment) = component else {
+            return None;
+        };
+        segments.push(segment.to_string_lossy().into_owned());
+    }
+    let last = segments.pop()?;
+    if segments.first().is_some_and(|first| first == "bin") {
+        return None;
+    }
+    match last.as_str() {
+        "main.rs" => return None,
+        "lib.rs" if segments.is_empty() => {}
+        "mod.rs" => {}
+        _ => segments.push(last.strip_suffix(".rs")?.to_string()),
+    }
+    Some(segments.join("::"))
+}
+
+/// The Rust files under `src` that define `function`, in path order.
+/// Files that do not parse are skipped.
+pub fn search(src: &Path, function: &str) -> Result<Vec<PathBuf>> {
+    let (owner, name) = match function.rsplit_once("::") {
+        Some((owner, name)) => (Some(owner), name),
+        None => (None, function),
+    };
+    let mut found = Vec::new();
+    let mut pending = vec![src.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir).map_err(|err| Error::io(&dir, err))?;
+        for entry in entries {
+            let path = entry.map_err(|err| Error::io(&dir, err))?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if hooks::is_rust(&path) {
+                let source = fs::read_to_string(&path).map_err(|err| Error::io(&path, err))?;
+                let defines = syn::parse_file(&source)
+                    .is_ok_and(|file| vectors::find(&file.items, owner, name).is_some());
+                if defines {
+                    found.push(path);
+                }
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+/// The field parameter `index` is held in: the parameter's own name, if
+/// it has one the target does not use for itself.
+fn binding(pat: &Pat, index: usize) -> String {
+    let name = match pat {
+        Pat::Ident(pat) => pat.ident.to_string(),
+        _ => format!("arg{}", index + 1),
+    };
+    if RESERVED_NAMES.contains(&name.as_str()) {
+        format!("{name}_")
+    } else {
+        name
+    }
+}
+
+/// Works out the fields of the parameters of one signature.
+struct Builder {
+    /// Type parameters in scope, which have no values.
+    generics: Vec<String>,
+    /// The parameter whose field is being worked out.
+    param: String,
+    warnings: Vec<String>,
+}
+
+impl Builder {
+    /// The owned type of the field for a parameter of type `ty`, and how
+    /// it is passed.
+    fn field(&mut self, ty: &Type) -> Result<(String, Pass), String> {
+        let Type::Reference(reference) = ty else {
+            return Ok((self.owned(ty)?, Pass::Value));
+        };
+        let pass = match reference.mutability {
+            Some(_) => Pass::Mut,
+            None => Pass::Ref,
+        };
+        let ty = match &*reference.elem {
+            Type::Path(path) if pass == Pass::Ref && vectors::is(path, "str") => {
+                "String".to_string()
+            }
+            Type::Path(path) if pass == Pass::Ref && vectors::is(path, "Path") => {
+                "std::path::PathBuf".to_string()
+            }
+            Type::Slice(slice) => format!("Vec<{}>", self.owned(&slice.elem)?),
+            elem => self.owned(elem)?,
+        };
+        Ok((ty, pass))
+    }
+
+    /// `ty` as written in the input struct, which owns what it holds.
+    fn owned(&mut self, ty: &Type) -> Result<String, String> {
+        match ty {
+            Type::Paren(ty) => self.owned(&ty.elem),
+            Type::Group(ty) => self.owned(&ty.elem),
+            Type::Tuple(tuple) => {
+                let elems = tuple
+                    .elems
+                    .iter()
+                    .map(|elem| self.owned(elem))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(match elems.len() {
+                    1 => format!("({},)", elems[0]),
+                    _ => format!("({})", elems.join(", ")),
+                })
+            }
+            Type::Array(array) => {
+                let Expr::Lit(len) = &array.len else {
+                    return Err(format!(
+                        "`{}` is an array whose length is not a literal",
+                        self.param
+                    ));
+                };
+                let Lit::Int(len) = &len.lit else {
+                    return Err(format!(
+                        "`{}` has a type a fuzz input cannot hold",
+                        self.param
+                    ));
+                };
+                Ok(format!(
+                    "[{}; {}]",
+                    self.owned(&array.elem)?,
+                    len.base10_digits()
+                ))
+            }
+            Type::Path(path) if path.qself.is_none() => self.path(&path.path),
+            Type::Reference(_) => Err(format!(
+                "`{}` borrows within its type, which a fuzz input cannot own",
+                self.param
+            )),
+            Type::ImplTrait(_) => Err(format!(
+                "`{}` is an `impl Trait`, which a fuzz input cannot hold",
+                self.param
+            )),
+            Type::TraitObject(_) => Err(format!(
+                "`{}` is a trait object, which a fuzz input cannot hold",
+                self.param
+            )),
+            _ => Err(format!(
+                "`{}` has a type a fuzz input cannot hold",
+                self.param
+            )),
+        }
+    }
+
+    fn path(&mut self, path: &syn::Path) -> Result<String, String> {
+        let mut written = match path.leading_colon {
+            Some(_) => "::".to_string(),
+            None => String::new(),
+        };
+        for (index, segment) in path.segments.iter().enumerate() {
+            if index > 0 {
+                written.push_str("::");
+            }
+            written.push_str(&segment.ident.to_string());
+            let PathArguments::AngleBracketed(args) = &segment.arguments else {
+                if matches!(segment.arguments, PathArguments::Parenthesized(_)) {
+                    return Err(format!(
+                        "`{}` has a type a fuzz input cannot hold",
+                        self.param
+                    ));
+                }
+                continue;
+            };
+            let mut owned = Vec::new();
+            for arg in &args.args {
+                match arg {
+                    GenericArgument::Type(ty) => owned.push(self.owned(ty)?),
+                    GenericArgument::Lifetime(_) => {
+                        return Err(format!(
+                            "`{}` borrows within its type, which a fuzz input cannot own",
+                            self.param
+                        ));
+                    }
+                    _ => {
+                        return Err(format!(
+                            "`{}` has a type a fuzz input cannot hold",
+                            self.param
+                        ));
+                    }
+                }
+            }
+            written.push_str(&format!("<{}>", owned.join(", ")));
+        }
+        if let Some(last) = path.segments.last() {
+            let name = last.ident.to_string();
+            if path.segments.len() == 1 && self.generics.contains(&name) {
+                return Err(format!(
+                    "`{}` is of generic type `{name}`, which the fuzzer cannot build",
+                    self.param
+                ));
+            }
+            if !ARBITRARY_TYPES.contains(&name.as_str()) {
+                self.warnings.push(format!(
+                    "`{}` is a `{name}`, which must implement `arbitrary::Arbitrary` for the target to build",
+                    self.param
+                ));
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+        use std::path::Path;\n\
+        \n\
+        pub fn tokenize(input: &[u8]) -> Vec<u8> { todo!() }\n\
+        pub fn apply(path: &Path, limit: Option<u16>, stack: &mut Vec<(char, bool)>) {}\n\
+        pub fn store(row: Config, label: &str) -> Result<(), String> { Ok(()) }\n\
+        pub fn keys(map: std::collections::HashMap<String, [u8; 4]>) {}\n\
+        pub fn borrow(names: Vec<&str>) {}\n\
+        pub fn generic<T>(value: T) {}\n\
+        pub async fn fetch(url: String) {}\n\
+        fn private(value: u8) {}\n\
+        \n\
+        pub struct Config;\n\
+        \n\
+        impl Config {\n\
+            pub fn parse(text: &str) -> Option<Self> { None }\n\
+            pub fn get(&self, key: &str) -> Option<&str> { None }\n\
+        }\n";
+
+    #[test]
+    fn test_parse_owns_each_parameter() {
+        let target = Target::parse(SOURCE, "apply").unwrap();
+        let fields: Vec<(&str, &str, Pass)> = target
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.ty.as_str(), field.pass))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("path", "std::path::PathBuf", Pass::Ref),
+                ("limit", "Option<u16>", Pass::Value),
+                ("stack", "Vec<(char, bool)>", Pass::Mut),
+            ]
+        );
+        assert!(!target.returns && !target.takes_bytes());
+        assert!(target.warnings.is_empty());
+
+        let target = Target::parse(SOURCE, "keys").unwrap();
+        assert_eq!(
+            target.fields[0].ty,
+            "std::collections::HashMap<String, [u8; 4]>"
+        );
+
+        let target = Target::parse(SOURCE, "Config::parse").unwrap();
+        assert_eq!(target.name, "config_parse");
+        assert_eq!(target.fields[0].ty, "String");
+        assert!(target.returns);
+    }
+
+    #[test]
+    fn test_parse_passes_bytes_and_warns_about_unknown_types() {
+        let target = Target::parse(SOURCE, "tokenize").unwrap();
+        assert!(target.takes_bytes());
+        assert_eq!(target.fields[0].name, "input_");
+
+        let target = Target::parse(SOURCE, "store").unwrap();
+        assert_eq!(target.warnings.len(), 1);
+        assert!(target.warnings[0].contains("`row` is a `Config`"));
+    }
+
+    #[test]
+    fn test_parse_rejects_what_cannot_be_fuzzed() {
+        for (function, reason) in [
+            ("borrow", "borrows within its type"),
+            ("generic", "of generic type `T`"),
+            ("fetch", "is async"),
+            ("private", "is not `pub`"),
+            ("Config::get", "takes `self`"),
+            ("missing", "no function `missing`"),
+        ] {
+            let err = Target::parse(SOURCE, function).unwrap_err();
+            assert!(err.contains(reason), "{function}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_module_path_follows_the_file_layout() {
+        assert_eq!(module_path(Path::new("lib.rs")).as_deref(), Some(""));
+        assert_eq!(
+            module_path(Path::new("parser.rs")).as_deref(),
+            Some("parser")
+        );
+        assert_eq!(
+            module_path(Path::new("parser/lexer/mod.rs")).as_deref(),
+            Some("parser::lexer")
+        );
+        assert_eq!(module_path(Path::new("main.rs")), None);
+        assert_eq!(module_path(Path::new("bin/tool.rs")), None);
+    }
+
+    #[test]
+    fn test_add_bin_appends_once() {
+        let path = Path::new("fuzz/Cargo.toml");
+        let manifest = "[package]\nname = \"a-fuzz\"\n\n[[bin]]\nname = \"parse\"\n";
+        let bin = "[[bin]]\nname = \"tokenize\"\n";
+        let added = add_bin(path, manifest, "tokenize", bin).unwrap();
+        assert!(added.ends_with("name = \"parse\"\n\n[[bin]]\nname = \"tokenize\"\n"));
+        assert_eq!(add_bin(path, &added, "tokenize", bin).unwrap(), added);
+    }
+}
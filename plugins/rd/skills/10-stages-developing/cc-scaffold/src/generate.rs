@@ -0,0 +1,557 @@
+//! Turning a located template into a file on disk.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::cache;
+use crate::digest::{self, Sha256};
+use crate::error::{Error, Result};
+use crate::inject;
+use crate::locator::{self, LocatedTemplate};
+use crate::manifest::Manifest;
+use crate::parallel;
+use crate::regions;
+use crate::template_engine::backend::{Native, TemplateBackend};
+use crate::template_engine::{Context, Template};
+use crate::variables;
+
+/// A template rendered in memory, ready to be written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedFile {
+    /// Template the contents were produced from.
+    pub template: PathBuf,
+    /// Where the contents should be written.
+    pub destination: PathBuf,
+    /// Rendered text.
+    pub contents: String,
+    /// Every file the contents were produced from: the template, the
+    /// partials and base templates it includes, and its manifest.
+    pub inputs: Vec<PathBuf>,
+}
+
+impl RenderedFile {
+    /// Content hash of [`inputs`](Self::inputs), which changes whenever the
+    /// template or anything it includes does.
+    pub fn fingerprint(&self) -> Result<String> {
+        fingerprint(&self.inputs)
+    }
+
+    /// Merges the contents into the existing destination when both have
+    /// managed regions, so that only those regions are replaced. Returns the
+    /// rendered regions the destination lacks, which are not added, or
+    /// `None` if the contents replace the whole file.
+    pub fn merge_regions(&mut self) -> Result<Option<Vec<String>>> {
+        let dest = &self.destination;
+        let existing = match fs::read_to_string(dest) {
+            Ok(existing) => existing,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::io(dest, err)),
+        };
+        let merged =
+            regions::merge(&existing, &self.contents).map_err(|message| Error::Regions {
+                path: dest.clone(),
+                message,
+            })?;
+        Ok(merged.map(|merged| {
+            self.contents = merged.contents;
+            merged.missing
+        }))
+    }
+}
+
+/// Hash of the generated part of `contents`, recorded to tell whether a
+/// file was edited since it was generated: its managed regions if it has
+/// any, otherwise the whole file.
+pub fn output_hash(contents: &str) -> String {
+    digest::sha256(regions::managed(contents).as_bytes())
+}
+
+/// Content hash of the files in `inputs`, in order.
+pub fn fingerprint(inputs: &[PathBuf]) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for input in inputs {
+        let bytes = fs::read(input).map_err(|err| Error::io(input, err))?;
+        hasher.update(&(bytes.len() as u64).to_be_bytes());
+        hasher.update(&bytes);
+    }
+    Ok(hasher.finish_tagged())
+}
+
+/// Renders a located template with raw variable `values`.
+///
+/// With a manifest, values are validated against its declared variables and
+/// defaults apply, and the variant of the template for the `edition`
+/// variable is rendered if there is one; without a manifest, every value is
+/// an ad-hoc substitution.
+pub fn render(
+    template: &LocatedTemplate,
+    destination: &Path,
+    values: &BTreeMap<String, String>,
+) -> Result<RenderedFile> {
+    let path = match &template.manifest {
+        Some(manifest) if manifest.file == template.source => {
+            manifest.file_for(manifest.edition(values, &template.env)?.as_deref())
+        }
+        _ => &template.source,
+    };
+    let source = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+    let ctx = context(template, values, &source, path)?;
+    let mut file = render_source(
+        backend(template),
+        path,
+        &source,
+        &template.include_dirs,
+        destination,
+        &ctx,
+    )?;
+    if let Some(manifest) = &template.manifest {
+        file.inputs.push(manifest.path.clone());
+    }
+    Ok(file)
+}
+
+/// Renders every file of a bundle template that `values` generate (see
+/// [`Manifest::generated_files`]) into `out`, each at its rendered
+/// destination pattern. The files share their inputs, so a change
+/// to any file of the bundle changes the fingerprint of all of them. A
+/// template that is not a bundle renders to `out` itself.
+pub fn render_bundle(
+    template: &LocatedTemplate,
+    out: &Path,
+    values: &BTreeMap<String, String>,
+) -> Result<Vec<RenderedFile>> {
+    let Some(manifest) = template.manifest.as_ref().filter(|m| !m.files.is_empty()) else {
+        return Ok(vec![render(template, out, values)?]);
+    };
+    let path = &template.source;
+    let source = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+    let ctx = context(template, values, &source, path)?;
+    let edition = manifest.edition(values, &template.env)?;
+    let invalid = |message: String| Error::Manifest {
+        path: manifest.path.clone(),
+        message,
+    };
+    let bundled_files = manifest.generated_files(values);
+    // Files render independently; the first error in declaration order wins.
+    let mut files = parallel::map(bundled_files.clone(), |bundled| {
+        let destination = Template::parse(&bundled.path)?.render(&ctx)?;
+        let relative = Path::new(destination.trim());
+        if relative.as_os_str().is_empty()
+            || !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(invalid(format!(
+                "file destination `{}` renders to `{destination}`, which is not inside the output directory",
+                bundled.path
+            )));
+        }
+        let path = bundled.template_for(edition.as_deref());
+        let source = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+        render_source(
+            backend(template),
+            path,
+            &source,
+            &template.include_dirs,
+            &out.join(relative),
+            &ctx,
+        )
+    })?;
+    let injects = |i: usize| bundled_files[i].inject.is_some();
+    for (i, file) in files.iter().enumerate() {
+        let clash = files[..i]
+            .iter()
+            .enumerate()
+            .any(|(j, f)| f.destination == file.destination && !(injects(i) && injects(j)));
+        if clash {
+            return Err(invalid(format!(
+                "two files render to {}",
+                file.destination.display()
+            )));
+        }
+    }
+    // Files left out by `when` count too, so that the fingerprint is the
+    // same whichever are generated.
+    let mut inputs: Vec<PathBuf> = Vec::new();
+    let mut rendered = files.iter();
+    for bundled in &manifest.files {
+        let file_inputs = if bundled_files.iter().any(|b| std::ptr::eq(*b, bundled)) {
+            rendered
+                .next()
+                .map(|file| file.inputs.clone())
+                .unwrap_or_default()
+        } else {
+            let path = bundled.template_for(edition.as_deref());
+            let source = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+            let compiled =
+                cache::compile(backend(template), path, &source, &template.include_dirs)?;
+            std::iter::once(path.to_path_buf())
+                .chain(compiled.includes.into_iter().map(|include| include.path))
+                .collect()
+        };
+        for input in file_inputs {
+            if !inputs.contains(&input) {
+                inputs.push(input);
+            }
+        }
+    }
+    inputs.push(manifest.path.clone());
+    for file in &mut files {
+        file.inputs.clone_from(&inputs);
+    }
+    Ok(files)
+}
+
+/// Injects the files [`render_bundle`] rendered with `values` for
+/// `manifest`'s `[[files]]` entries with an `inject` point into their
+/// existing destinations, the snippets for one destination in declaration
+/// order, so that each destination appears once. Returns the files with
+/// whether each was injected into.
+pub fn inject_bundled(
+    manifest: &Manifest,
+    values: &BTreeMap<String, String>,
+    files: Vec<RenderedFile>,
+) -> Result<Vec<(RenderedFile, bool)>> {
+    let bundled = manifest.generated_files(values);
+    let mut out: Vec<(RenderedFile, bool)> = Vec::with_capacity(files.len());
+    for (i, mut file) in files.into_iter().enumerate() {
+        // A single file renders without `[[files]]`, and is never injected.
+        let Some(point) = bundled.get(i).and_then(|f| f.inject.as_ref()) else {
+            out.push((file, false));
+            continue;
+        };
+        let invalid = |message| Error::Inject {
+            path: file.destination.clone(),
+            message,
+        };
+        let earlier = out
+            .iter_mut()
+            .find(|(f, injected)| *injected && f.destination == file.destination);
+        let existing = match &earlier {
+            Some((earlier, _)) => earlier.contents.clone(),
+            None => match fs::read_to_string(&file.destination) {
+                Ok(existing) => existing,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    let message = format!(
+                        "it does not exist; `{}` adds to a file generated some other way",
+                        manifest.id()
+                    );
+                    return Err(invalid(message));
+                }
+                Err(err) => return Err(Error::io(&file.destination, err)),
+            },
+        };
+        file.contents = inject::inject(&existing, &file.contents, point).map_err(invalid)?;
+        match earlier {
+            Some((earlier, _)) => earlier.contents = file.contents,
+            None => out.push((file, true)),
+        }
+    }
+    Ok(out)
+}
+
+/// File name for `template`'s output when only a directory is given: the
+/// value of its first identifier variable with the template file's
+/// extension, e.g. `parse.rs` for `rust/function`, or else the template
+/// file's own name without its `-template` suffix.
+pub fn default_file_name(template: &LocatedTemplate, values: &BTreeMap<String, String>) -> PathBuf {
+    let source = &template.source;
+    let stem = template
+        .manifest
+        .iter()
+        .flat_map(|m| &m.variables)
+        .filter(|v| v.kind == variables::VariableKind::Identifier)
+        .find_map(|v| values.get(&v.name));
+    let Some(stem) = stem else {
+        let name = source.file_name().unwrap_or_default().to_string_lossy();
+        return PathBuf::from(name.replacen("-template", "", 1));
+    };
+    match source.extension() {
+        Some(extension) => Path::new(stem).with_extension(extension),
+        None => PathBuf::from(stem),
+    }
+}
+
+/// Validates `values` for `template` and builds its render context.
+/// Binds `values` for `template`. `source`, read from `file`, is only used
+/// to locate errors.
+fn context(
+    template: &LocatedTemplate,
+    values: &BTreeMap<String, String>,
+    source: &str,
+    file: &Path,
+) -> Result<Context> {
+    match &template.manifest {
+        Some(manifest) => variables::bind(
+            &manifest.variables,
+            values,
+            &template.env,
+            source,
+            Some(&file.to_path_buf()),
+        ),
+        None => {
+            let mut ctx = Context::new();
+            variables::set_builtins(&mut ctx, &template.env);
+            for (key, value) in values {
+                ctx.substitute(key, value.as_str());
+            }
+            Ok(ctx)
+        }
+    }
+}
+
+fn backend(template: &LocatedTemplate) -> &'static dyn TemplateBackend {
+    template
+        .manifest
+        .as_ref()
+        .map_or(&Native as &dyn TemplateBackend, |m| m.backend())
+}
+
+/// Reads and renders native `template` against `ctx`. Partials the
+/// template includes are loaded from disk, next to the template (see
+/// [`locator::include_dirs`]), unless `ctx` already has them.
+pub fn render_file(template: &Path, destination: &Path, ctx: &Context) -> Result<RenderedFile> {
+    let source = fs::read_to_string(template).map_err(|err| Error::io(template, err))?;
+    let dirs = locator::include_dirs(template);
+    render_source(&Native, template, &source, &dirs, destination, ctx)
+}
+
+fn render_source(
+    backend: &dyn TemplateBackend,
+    template: &Path,
+    source: &str,
+    include_dirs: &[PathBuf],
+    destination: &Path,
+    ctx: &Context,
+) -> Result<RenderedFile> {
+    let compiled = cache::compile(backend, template, source, include_dirs)?;
+    let mut ctx = ctx.clone();
+    let mut inputs = vec![template.to_path_buf()];
+    for include in compiled.includes {
+        if !ctx.has_partial(&include.name) {
+            inputs.push(include.path);
+            ctx.add_partial(include.name, include.template);
+        }
+    }
+    let contents = compiled.template.render(&ctx)?;
+    Ok(RenderedFile {
+        template: template.to_path_buf(),
+        destination: destination.to_path_buf(),
+        contents,
+        inputs,
+    })
+}
+
+/// Writes a rendered file, creating parent directories as needed. Refuses to
+/// replace an existing file unless `overwrite` is set.
+pub fn write(file: &RenderedFile, overwrite: bool) -> Result<()> {
+    let dest = &file.destination;
+    if dest.exists() && !overwrite {
+        return Err(Error::DestinationExists(dest.clone()));
+    }
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|err| Error::io(parent, err))?;
+    }
+    fs::write(dest, &file.contents).map_err(|err| Error::io(dest, err))
+}
+
+/// What writing a rendered file would do to its destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Preview {
+    /// The destination does not exist; the diff adds the whole file.
+    Create(String),
+    /// The destination exists with different contents.
+    Overwrite(String),
+    /// The destination already has exactly the rendered contents.
+    Unchanged,
+}
+
+/// Compares a rendered file with its destination without writing anything,
+/// returning a unified diff of the change.
+pub fn preview(file: &RenderedFile) -> Result<Preview> {
+    let dest = &file.destination;
+    let label = dest.display().to_string();
+    match fs::read_to_string(dest) {
+        Ok(current) if current == file.contents => Ok(Preview::Unchanged),
+        Ok(current) => Ok(Preview::Overwrite(unified_diff(
+            &current,
+            &file.contents,
+            &label,
+            &label,
+        ))),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Preview::Create(
+            unified_diff("", &file.contents, "/dev/null", &label),
+        )),
+        Err(err) => Err(Error::io(dest, err)),
+    }
+}
+
+fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(old_label, new_label)
+        .to_string()
+}
+
+/// Parses a `key=value` assignment as passed to `--set`.
+pub fn parse_assignment(arg: &str) -> Result<(String, String)> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(Error::InvalidAssignment(arg.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assignment() {
+        assert_eq!(
+            parse_assignment("function_name=parse").unwrap(),
+            ("function_name".to_string(), "parse".to_string())
+        );
+        assert_eq!(parse_assignment("a=b=c").unwrap().1, "b=c");
+        assert!(parse_assignment("novalue").is_err());
+        assert!(parse_assignment("=x").is_err());
+    }
+
+    #[test]
+    fn test_render_file_attaches_path_to_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = dir.path().join("t.rs");
+        fs::write(&template, "fn {{ missing }}() {}\n").unwrap();
+        let err = render_file(&template, Path::new("out.rs"), &Context::new()).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("t.rs:1:4: undefined variable `missing`"));
+    }
+
+    #[test]
+    fn test_render_file_loads_partials() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("partials")).unwrap();
+        fs::create_dir_all(dir.path().join("rust")).unwrap();
+        fs::write(
+            dir.path().join("partials/banner.txt"),
+            "// {{ name }}\n{{> partials/broken}}",
+        )
+        .unwrap();
+        fs::write(dir.path().join("partials/broken.txt"), "{{ missing }}").unwrap();
+        let template = dir.path().join("rust/t.rs");
+        fs::write(&template, "{{> partials/banner}}fn x() {}\n").unwrap();
+
+        let mut ctx = Context::new();
+        ctx.set("name", "x");
+        let err = render_file(&template, Path::new("out.rs"), &ctx).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("broken.txt:1:1: undefined variable `missing`"));
+
+        ctx.set("missing", "ok");
+        let file = render_file(&template, Path::new("out.rs"), &ctx).unwrap();
+        assert_eq!(file.contents, "// x\nokfn x() {}\n");
+        assert_eq!(file.inputs.len(), 3);
+
+        let before = file.fingerprint().unwrap();
+        fs::write(dir.path().join("partials/broken.txt"), "{{ missing }}!").unwrap();
+        assert_ne!(file.fingerprint().unwrap(), before);
+    }
+
+    #[test]
+    fn test_preview_diffs_against_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RenderedFile {
+            template: PathBuf::from("t.rs"),
+            destination: dir.path().join("out.rs"),
+            contents: "fn a() {}\nfn b() {}\n".to_string(),
+            inputs: Vec::new(),
+        };
+        let Preview::Create(diff) = preview(&file).unwrap() else {
+            panic!("expected a new file");
+        };
+        assert!(diff.starts_with("--- /dev/null\n+++ "));
+        assert!(diff.ends_with("@@ -0,0 +1,2 @@\n+fn a() {}\n+fn b() {}\n"));
+
+        write(&file, false).unwrap();
+        assert_eq!(preview(&file).unwrap(), Preview::Unchanged);
+
+        file.contents = "fn a() {}\nfn c() {}\n".to_string();
+        let Preview::Overwrite(diff) = preview(&file).unwrap() else {
+            panic!("expected a change");
+        };
+        assert!(diff.contains("@@ -1,2 +1,2 @@\n fn a() {}\n-fn b() {}\n+fn c() {}\n"));
+        assert_eq!(
+            fs::read_to_string(&file.destination).unwrap(),
+            "fn a() {}\nfn b() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_inject_bundled_combines_snippets_per_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = Manifest::parse(
+            "name = \"sub\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+             description = \"Sub\"\n\
+             [[files]]\ntemplate = \"variant.rs\"\npath = \"main.rs\"\ninject = \"enum:Command\"\n\
+             [[files]]\ntemplate = \"arm.rs\"\npath = \"main.rs\"\ninject = \"anchor:dispatch\"\n\
+             [[files]]\ntemplate = \"new.rs\"\npath = \"new.rs\"\n",
+            &dir.path().join("sub.template.toml"),
+        )
+        .unwrap();
+        let rendered = |name: &str, contents: &str| RenderedFile {
+            template: PathBuf::from(name),
+            destination: dir.path().join(name),
+            contents: contents.to_string(),
+            inputs: Vec::new(),
+        };
+        let files = || {
+            vec![
+                rendered("main.rs", "B,\n"),
+                rendered("main.rs", "Command::B => b(),\n"),
+                rendered("new.rs", "fn b() {}\n"),
+            ]
+        };
+        let err = inject_bundled(&manifest, &BTreeMap::new(), files()).unwrap_err();
+        assert!(err.to_string().contains("it does not exist"), "{err}");
+
+        let main = "enum Command {\n    A,\n}\n\nfn run(c: Command) {\n    match c {\n        \
+                    Command::A => a(),\n        // cc-agents:insert dispatch\n    }\n}\n";
+        fs::write(dir.path().join("main.rs"), main).unwrap();
+        let files = inject_bundled(&manifest, &BTreeMap::new(), files()).unwrap();
+        assert_eq!(files.len(), 2);
+        let (main, injected) = &files[0];
+        assert!(injected);
+        assert_eq!(
+            main.contents,
+            "enum Command {\n    A,\n    B,\n}\n\nfn run(c: Command) {\n    match c {\n        \
+             Command::A => a(),\n        Command::B => b(),\n        \
+             // cc-agents:insert dispatch\n    }\n}\n"
+        );
+        assert_eq!(files[1].0.contents, "fn b() {}\n");
+        assert!(!files[1].1);
+    }
+
+    #[test]
+    fn test_write_refuses_existing_without_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = RenderedFile {
+            template: PathBuf::from("t.rs"),
+            destination: dir.path().join("nested/out.rs"),
+            contents: "fn x() {}\n".to_string(),
+            inputs: Vec::new(),
+        };
+        write(&file, false).unwrap();
+        assert_eq!(
+            fs::read_to_string(&file.destination).unwrap(),
+            "fn x() {}\n"
+        );
+        assert!(matches!(
+            write(&file, false),
+            Err(Error::DestinationExists(_))
+        ));
+        write(&file, true).unwrap();
+    }
+}
@@ -0,0 +1,545 @@
+//! Post-generation hooks.
+//!
+//! [`rustfmt`] formats rendered contents in memory before they are written,
+//! so a template that renders to invalid syntax fails without touching the
+//! destination. [`cargo_check`] builds the crate a written file belongs to.
+//! Failures are reported as [`Error::Hook`] with the compiler's messages
+//! parsed into [`Diagnostic`]s.
+//!
+//! A manifest may also declare its own hooks, run after the file is written:
+//!
+//! ```toml
+//! [[hooks]]
+//! run = ["cargo", "fmt", "--", "{{ destination }}"]
+//! ```
+//!
+//! Those come from whoever wrote the template, so they only run when asked
+//! to (`--allow-hooks`) and then inside a [`Sandbox`].
+
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+use crate::template_engine::{self, Context};
+
+/// Edition assumed when the destination's crate does not declare one.
+const DEFAULT_EDITION: &str = "2021";
+
+/// One message from a failed hook.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// File the message is about: the destination for `rustfmt`, or a path
+    /// relative to the crate for `cargo check`.
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Whether `destination` is a Rust source file the hooks apply to.
+pub fn is_rust(destination: &Path) -> bool {
+    destination.extension().is_some_and(|ext| ext == "rs")
+}
+
+/// The `Cargo.toml` of the crate `path` belongs to: the nearest one in
+/// `path` or an ancestor directory.
+pub fn crate_manifest(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| manifest.is_file())
+}
+
+/// Formats `contents`, to be written to `destination`, with `rustfmt`. The
+/// edition and any `rustfmt.toml` are taken from the destination's crate.
+pub fn rustfmt(destination: &Path, contents: &str) -> Result<String> {
+    let manifest = crate_manifest(destination);
+    let edition = manifest
+        .as_deref()
+        .and_then(edition)
+        .unwrap_or_else(|| DEFAULT_EDITION.to_string());
+    let dir = manifest
+        .as_deref()
+        .and_then(Path::parent)
+        .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+    let mut child = Command::new("rustfmt")
+        .args(["--emit", "stdout", "--edition", &edition])
+        .current_dir(&dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| failed("rustfmt", destination, format!("cannot run rustfmt: {err}")))?;
+    let mut input = child.stdin.take().expect("stdin is piped");
+    // rustfmt may exit before reading everything; its status says why.
+    let _ = input.write_all(contents.as_bytes());
+    drop(input);
+    let output = child
+        .wait_with_output()
+        .map_err(|err| Error::io(destination, err))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Hook {
+            hook: "rustfmt".to_string(),
+            path: destination.to_path_buf(),
+            diagnostics: rustfmt_diagnostics(&stderr, destination),
+            output: stderr.trim().to_string(),
+        });
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|_| failed("rustfmt", destination, "output is not UTF-8".to_string()))
+}
+
+/// Runs `cargo check` on the crate `destination`, a written file or a
+/// generated project directory, belongs to. Returns `false` without running
+/// anything if it is not inside a crate.
+pub fn cargo_check(destination: &Path) -> Result<bool> {
+    let Some(manifest) = crate_manifest(destination) else {
+        return Ok(false);
+    };
+    let output = Command::new("cargo")
+        .args([
+            "check",
+            "--quiet",
+            "--message-format",
+            "short",
+            "--manifest-path",
+        ])
+        .arg(&manifest)
+        .current_dir(manifest.parent().unwrap_or(Path::new(".")))
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| {
+            failed(
+                "cargo check",
+                destination,
+                format!("cannot run cargo: {err}"),
+            )
+        })?;
+    if output.status.success() {
+        return Ok(true);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(Error::Hook {
+        hook: "cargo check".to_string(),
+        path: destination.to_path_buf(),
+        diagnostics: cargo_diagnostics(&stderr),
+        output: stderr.trim().to_string(),
+    })
+}
+
+/// Programs a template hook may run.
+pub const ALLOWED_COMMANDS: &[&str] = &["cargo", "rustfmt"];
+
+/// `cargo` subcommands a template hook may run: formatting only. Building,
+/// checking or testing runs the build scripts, proc-macros and tests the
+/// template may have written, with the user's access to the filesystem.
+pub const ALLOWED_CARGO_SUBCOMMANDS: &[&str] = &["fmt"];
+
+/// Argument prefixes that change what cargo or rustfmt run or read:
+/// configuration overrides such as `--config=build.rustc-wrapper=...`,
+/// unstable `-Z` flags and `+toolchain` overrides.
+const OVERRIDING_ARGS: &[&str] = &["--config", "-Z", "+"];
+
+/// Environment variables passed through to a hook; everything else is
+/// cleared.
+const KEPT_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "CARGO_HOME",
+    "RUSTUP_HOME",
+    "RUSTUP_TOOLCHAIN",
+];
+
+/// A command declared by a template manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateHook {
+    /// Program and arguments. Arguments may use `{{ destination }}`, the
+    /// written file relative to the project root.
+    pub run: Vec<String>,
+}
+
+impl TemplateHook {
+    /// The command line, for messages.
+    pub fn display(&self) -> String {
+        self.run.join(" ")
+    }
+}
+
+/// A restricted environment for [`TemplateHook`]s.
+///
+/// Only [`ALLOWED_COMMANDS`] run, and of cargo only the formatter, so a
+/// hook never executes code the template wrote. No argument may be an
+/// absolute path, climb out with `..` or override configuration or the
+/// toolchain. The working directory is the project root, the environment is
+/// cleared except for toolchain locations, and cargo is put in offline
+/// mode. The command runs in a new network namespace via `unshare`; where
+/// that cannot be set up, hooks are refused rather than run unisolated.
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    root: PathBuf,
+}
+
+impl Sandbox {
+    /// A sandbox confined to `root`.
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+
+    /// Whether hooks are cut off from the network entirely, rather than only
+    /// told to stay offline.
+    pub fn isolates_network() -> bool {
+        static ISOLATED: OnceLock<bool> = OnceLock::new();
+        *ISOLATED.get_or_init(|| {
+            Command::new("unshare")
+                .args(UNSHARE)
+                .arg("true")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+        })
+    }
+
+    /// Runs `hook` after `destination` has been written.
+    pub fn run(&self, hook: &TemplateHook, destination: &Path) -> Result<()> {
+        let name = hook.display();
+        let refuse = |message: String| failed(&name, &self.root, message);
+        let relative = destination
+            .strip_prefix(&self.root)
+            .map_err(|_| refuse(format!("{} is outside the project", destination.display())))?;
+        let mut ctx = Context::new();
+        ctx.set("destination", relative.to_string_lossy().as_ref());
+        let argv = hook
+            .run
+            .iter()
+            .map(|arg| template_engine::render(arg, &ctx))
+            .collect::<Result<Vec<_>>>()?;
+        let (program, args) = argv
+            .split_first()
+            .ok_or_else(|| refuse("empty command".to_string()))?;
+        if let Some(arg) = args.iter().find(|arg| overrides(arg)) {
+            return Err(refuse(format!(
+                "argument `{arg}` overrides configuration or the toolchain"
+            )));
+        }
+        if !ALLOWED_COMMANDS.contains(&program.as_str()) {
+            return Err(refuse(format!(
+                "`{program}` is not allowed; template hooks may run only {}",
+                ALLOWED_COMMANDS.join(", ")
+            )));
+        }
+        if program == "cargo" {
+            let subcommand = args.iter().find(|a| !a.starts_with('-'));
+            if !subcommand.is_some_and(|s| ALLOWED_CARGO_SUBCOMMANDS.contains(&s.as_str())) {
+                return Err(refuse(format!(
+                    "only `cargo {}` is allowed",
+                    ALLOWED_CARGO_SUBCOMMANDS.join("|")
+                )));
+            }
+        }
+        if let Some(arg) = args.iter().find(|arg| escapes(arg)) {
+            return Err(refuse(format!("argument `{arg}` leaves the project")));
+        }
+
+        if !Self::isolates_network() {
+            return Err(refuse(
+                "cannot isolate it from the network (`unshare` is unavailable); \
+                 refusing to run it"
+                    .to_string(),
+            ));
+        }
+        let mut command = Command::new("unshare");
+        command
+            .args(UNSHARE)
+            .arg(program)
+            .args(args)
+            .current_dir(&self.root)
+            .env_clear();
+        for key in KEPT_ENV {
+            if let Some(value) = std::env::var_os(key) {
+                command.env(key, value);
+            }
+        }
+        for key in [
+            "http_proxy",
+            "https_proxy",
+            "HTTP_PROXY",
+            "HTTPS_PROXY",
+            "ALL_PROXY",
+        ] {
+            command.env(key, "http://127.0.0.1:9");
+        }
+        let output = command
+            .env("CARGO_NET_OFFLINE", "true")
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|err| refuse(format!("cannot run {program}: {err}")))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(Error::Hook {
+            hook: name,
+            path: destination.to_path_buf(),
+            diagnostics: cargo_diagnostics(&stderr),
+            output: stderr.trim().to_string(),
+        })
+    }
+}
+
+/// `unshare` arguments for a new, unprivileged network namespace.
+const UNSHARE: &[&str] = &["--user", "--map-root-user", "--net", "--"];
+
+/// Whether `arg` is one of the [`OVERRIDING_ARGS`].
+fn overrides(arg: &str) -> bool {
+    OVERRIDING_ARGS.iter().any(|prefix| arg.starts_with(prefix))
+}
+
+/// Whether `arg`, or the value of an `--option=value` argument, is an
+/// absolute path or leaves the working directory with `..`.
+fn escapes(arg: &str) -> bool {
+    let value = arg.split_once('=').map_or(arg, |(_, value)| value);
+    [arg, value].iter().any(|s| {
+        let path = Path::new(s);
+        path.is_absolute()
+            || s.starts_with('~')
+            || path.components().any(|c| c == Component::ParentDir)
+    })
+}
+
+/// The edition of the crate `path` belongs to, if it declares one, either
+/// itself or through `edition.workspace = true`.
+pub fn crate_edition(path: &Path) -> Option<String> {
+    edition(&crate_manifest(path)?)
+}
+
+/// `package.edition` from a `Cargo.toml`, or `workspace.package.edition`
+/// from the enclosing workspace's if it is inherited.
+fn edition(manifest: &Path) -> Option<String> {
+    let table = |path: &Path| -> Option<toml::Table> {
+        toml::from_str(&fs::read_to_string(path).ok()?).ok()
+    };
+    let edition = table(manifest)?.get("package")?.get("edition")?.clone();
+    if let Some(edition) = edition.as_str() {
+        return Some(edition.to_string());
+    }
+    if edition.get("workspace")?.as_bool() != Some(true) {
+        return None;
+    }
+    manifest
+        .parent()?
+        .ancestors()
+        .skip(1)
+        .filter_map(|dir| table(&dir.join("Cargo.toml")))
+        .find_map(|root| {
+            let edition = root.get("workspace")?.get("package")?.get("edition")?;
+            edition.as_str().map(String::from)
+        })
+}
+
+/// Parses rustfmt's `error: <message>` / ` --> <stdin>:<line>:<column>`
+/// pairs.
+fn rustfmt_diagnostics(stderr: &str, destination: &Path) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut message = None;
+    for line in stderr.lines() {
+        if let Some(text) = line.strip_prefix("error: ") {
+            message = Some(text.to_string());
+        } else if let Some(location) = line.trim_start().strip_prefix("--> ") {
+            let (Some(text), Some((_, line, column))) = (message.take(), location_parts(location))
+            else {
+                continue;
+            };
+            diagnostics.push(Diagnostic {
+                file: destination.to_path_buf(),
+                line,
+                column,
+                message: text,
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Parses cargo's `--message-format short` errors,
+/// `<file>:<line>:<column>: error[...]: <message>`.
+pub(crate) fn cargo_diagnostics(stderr: &str) -> Vec<Diagnostic> {
+    stderr
+        .lines()
+        .filter_map(|line| {
+            let (location, message) = line.split_once(": error")?;
+            let (file, line, column) = location_parts(location)?;
+            let message = message.split_once(": ").map_or(message, |(_, m)| m);
+            Some(Diagnostic {
+                file: PathBuf::from(file),
+                line,
+                column,
+                message: message.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Splits `<file>:<line>:<column>`.
+fn location_parts(location: &str) -> Option<(&str, usize, usize)> {
+    let mut parts = location.trim().rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    Some((parts.next()?, line, column))
+}
+
+fn failed(hook: &str, path: &Path, output: String) -> Error {
+    Error::Hook {
+        hook: hook.to_string(),
+        path: path.to_path_buf(),
+        diagnostics: Vec::new(),
+        output,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_diagnostics() {
+        let rustfmt = "error: expected one of `(` or `<`, found `{`\n --> <stdin>:2:8\n  |\n";
+        assert_eq!(
+            rustfmt_diagnostics(rustfmt, Path::new("src/a.rs")),
+            [Diagnostic {
+                file: PathBuf::from("src/a.rs"),
+                line: 2,
+                column: 8,
+                message: "expected one of `(` or `<`, found `{`".to_string(),
+            }]
+        );
+        let cargo = "src/a.rs:3:5: error[E0425]: cannot find value `x` in this scope\n\
+                     src/a.rs:1:1: warning: unused import\n\
+                     error: could not compile `demo` (lib) due to 1 previous error\n";
+        assert_eq!(
+            cargo_diagnostics(cargo),
+            [Diagnostic {
+                file: PathBuf::from("src/a.rs"),
+                line: 3,
+                column: 5,
+                message: "cannot find value `x` in this scope".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_sandbox_refuses_commands_outside_the_allowlist() {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = Sandbox::new(dir.path());
+        let dest = dir.path().join("src/lib.rs");
+        let run = |argv: &[&str]| {
+            let hook = TemplateHook {
+                run: argv.iter().map(|s| s.to_string()).collect(),
+            };
+            sandbox.run(&hook, &dest).unwrap_err().to_string()
+        };
+        assert!(run(&["sh", "-c", "curl example.com"]).contains("`sh` is not allowed"));
+        assert!(run(&["cargo", "install", "evil"]).contains("only `cargo fmt` is allowed"));
+        assert!(run(&["cargo", "build"]).contains("only `cargo fmt` is allowed"));
+        assert!(run(&["cargo", "test", "--release"]).contains("only `cargo fmt`"));
+        for arg in [
+            "--config=build.rustc-wrapper=./x.sh",
+            "--config",
+            "-Zunstable-options",
+            "+nightly",
+        ] {
+            assert!(
+                run(&["cargo", arg, "fmt"]).contains("overrides configuration"),
+                "{arg}"
+            );
+        }
+        assert!(run(&["rustfmt", "--config", "edition=2024"]).contains("overrides configuration"));
+        assert!(run(&["rustfmt", "../outside.rs"]).contains("leaves the project"));
+        assert!(run(&["rustfmt", "--config-path=/etc"]).contains("overrides configuration"));
+        let outside = sandbox.run(
+            &TemplateHook {
+                run: vec!["rustfmt".to_string()],
+            },
+            Path::new("/tmp/x.rs"),
+        );
+        assert!(outside
+            .unwrap_err()
+            .to_string()
+            .contains("outside the project"));
+    }
+
+    #[test]
+    fn test_sandbox_runs_hooks_in_the_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("src/lib.rs");
+        fs::create_dir_all(dest.parent().unwrap()).unwrap();
+        fs::write(&dest, "pub fn  run( ) {  }").unwrap();
+        let hook = TemplateHook {
+            run: ["rustfmt", "--edition", "2021", "{{ destination }}"]
+                .map(String::from)
+                .to_vec(),
+        };
+        Sandbox::new(dir.path()).run(&hook, &dest).unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "pub fn run() {}\n");
+    }
+
+    #[test]
+    fn test_rustfmt_formats_or_reports_syntax_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        let dest = dir.path().join("src/lib.rs");
+        assert_eq!(
+            rustfmt(&dest, "pub fn  run( ) {  }").unwrap(),
+            "pub fn run() {}\n"
+        );
+        let err = rustfmt(&dest, "pub fn run( {\n").unwrap_err();
+        let Error::Hook {
+            hook, diagnostics, ..
+        } = &err
+        else {
+            panic!("{err}");
+        };
+        assert_eq!(hook, "rustfmt");
+        assert_eq!(diagnostics[0].file, dest);
+        assert!(err.to_string().contains("rustfmt failed"), "{err}");
+    }
+
+    #[test]
+    fn test_crate_edition_follows_workspace_inheritance() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"*\"]\n\n[workspace.package]\nedition = \"2024\"\n",
+        )
+        .unwrap();
+        for (name, edition) in [("own", "\"2018\""), ("inherited", "{ workspace = true }")] {
+            fs::create_dir_all(root.join(name)).unwrap();
+            fs::write(
+                root.join(name).join("Cargo.toml"),
+                format!("[package]\nname = \"{name}\"\nedition = {edition}\n"),
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            crate_edition(&root.join("own/src/lib.rs")).as_deref(),
+            Some("2018")
+        );
+        assert_eq!(
+            crate_edition(&root.join("inherited/src/lib.rs")).as_deref(),
+            Some("2024")
+        );
+        assert_eq!(crate_edition(&root.join("src/lib.rs")), None);
+    }
+}
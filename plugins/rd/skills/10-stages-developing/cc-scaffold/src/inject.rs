@@ -0,0 +1,623 @@
+//! Injecting a rendered snippet into an existing source file.
+//!
+//! Where the snippet goes is a [`Point`]:
+//!
+//! * `auto` (the default) — before the file's `#[cfg(test)] mod tests`, with
+//!   any `mod tests` in the snippet merged into the existing one; at the end
+//!   if the file has no test module
+//! * `end` — after everything else
+//! * `anchor:<name>` — just above an anchor comment,
+//!   `// cc-agents:insert <name>`, which stays for the next injection
+//! * `enum:<Name>`, `struct:<Name>`, `impl:<Type>`, `trait:<Name>` or
+//!   `mod:<name>` — at the end of that item's body, e.g. a new variant of an
+//!   error enum. A separating comma is added after the last variant or field
+//!   when it lacks one
+//!
+//! Items are found by scanning Rust syntax with comments and string
+//! literals skipped, so braces and names inside them do not confuse it. The
+//! snippet is re-indented to fit where it lands, `use` lines the file
+//! already has are dropped, and a snippet the file already contains is not
+//! injected again.
+
+use std::fmt;
+
+/// Prefix of an anchor comment naming an insertion point.
+pub const ANCHOR: &str = "cc-agents:insert";
+
+/// Where a snippet is injected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Point {
+    Auto,
+    End,
+    Anchor(String),
+    Item { kind: ItemKind, name: String },
+}
+
+/// An item whose body a snippet can be added to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Enum,
+    Struct,
+    Impl,
+    Trait,
+    Mod,
+}
+
+impl ItemKind {
+    fn keyword(self) -> &'static str {
+        match self {
+            ItemKind::Enum => "enum",
+            ItemKind::Struct => "struct",
+            ItemKind::Impl => "impl",
+            ItemKind::Trait => "trait",
+            ItemKind::Mod => "mod",
+        }
+    }
+
+    /// Whether entries of the body are separated by commas.
+    fn comma_separated(self) -> bool {
+        matches!(self, ItemKind::Enum | ItemKind::Struct)
+    }
+}
+
+impl Point {
+    /// Parses `auto`, `end`, `anchor:<name>` or `<item>:<name>`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (kind, name) = match spec.split_once(':') {
+            None if spec == "auto" => return Ok(Point::Auto),
+            None if spec == "end" => return Ok(Point::End),
+            None => return Err(format!("unknown insertion point `{spec}`")),
+            Some((kind, name)) => (kind, name.trim()),
+        };
+        if name.is_empty() {
+            return Err(format!("insertion point `{spec}` has no name"));
+        }
+        let kind = match kind {
+            "anchor" => return Ok(Point::Anchor(name.to_string())),
+            "enum" => ItemKind::Enum,
+            "struct" => ItemKind::Struct,
+            "impl" => ItemKind::Impl,
+            "trait" => ItemKind::Trait,
+            "mod" => ItemKind::Mod,
+            other => return Err(format!("unknown insertion point kind `{other}`")),
+        };
+        Ok(Point::Item {
+            kind,
+            name: name.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Point::Auto => f.write_str("auto"),
+            Point::End => f.write_str("end"),
+            Point::Anchor(name) => write!(f, "anchor:{name}"),
+            Point::Item { kind, name } => write!(f, "{}:{name}", kind.keyword()),
+        }
+    }
+}
+
+/// Returns `existing` with `snippet` injected at `point`, or unchanged if it
+/// already contains the snippet.
+pub fn inject(existing: &str, snippet: &str, point: &Point) -> Result<String, String> {
+    let masked = mask(existing);
+    let snippet = drop_present_uses(snippet, existing);
+    if snippet.trim().is_empty() || contains(existing, &snippet) {
+        return Ok(existing.to_string());
+    }
+    match point {
+        Point::End => Ok(append(existing, &snippet)),
+        Point::Anchor(name) => {
+            let (line_start, indent) = find_anchor(existing, &masked, name)
+                .ok_or_else(|| format!("no `// {ANCHOR} {name}` comment"))?;
+            let mut out = existing[..line_start].to_string();
+            out.push_str(&indent_lines(&dedent(&snippet), indent));
+            out.push('\n');
+            out.push_str(&existing[line_start..]);
+            Ok(out)
+        }
+        Point::Item { kind, name } => {
+            let item = find_item(&masked, *kind, name)
+                .ok_or_else(|| format!("no `{} {name}` found", kind.keyword()))?;
+            Ok(insert_into(existing, &masked, item, *kind, &snippet))
+        }
+        Point::Auto => {
+            let Some(tests) = find_item(&masked, ItemKind::Mod, "tests") else {
+                return Ok(append(existing, &snippet));
+            };
+            let (main, test_body) = split_tests(&snippet);
+            let mut out = existing.to_string();
+            if let Some(body) = test_body.filter(|body| !contains(existing, body)) {
+                out = insert_into(&out, &masked, tests, ItemKind::Mod, &body);
+            }
+            if !main.trim().is_empty() && !contains(existing, &main) {
+                let start = attributes_start(&out, tests.header);
+                let mut before = out[..start].trim_end().to_string();
+                if !before.is_empty() {
+                    before.push_str("\n\n");
+                }
+                before.push_str(dedent(&main).trim_end());
+                before.push_str("\n\n");
+                out = before + &out[start..];
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// A `{ ... }` item found in the masked source.
+#[derive(Debug, Clone, Copy)]
+struct Item {
+    /// Start of the line the item's keyword is on.
+    header: usize,
+    open: usize,
+    close: usize,
+}
+
+/// Copies `source` with comments and string and character literals
+/// replaced by spaces, keeping byte offsets and newlines.
+fn mask(source: &str) -> String {
+    let bytes = source.as_bytes();
+    let mut out = bytes.to_vec();
+    let blank = |out: &mut Vec<u8>, from: usize, to: usize| {
+        for b in &mut out[from..to.min(bytes.len())] {
+            if *b != b'\n' {
+                *b = b' ';
+            }
+        }
+    };
+    let ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        if rest.starts_with(b"//") {
+            let end = rest
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(bytes.len(), |n| i + n);
+            blank(&mut out, i, end);
+            i = end;
+        } else if rest.starts_with(b"/*") {
+            let mut depth = 0;
+            let mut j = i;
+            while j < bytes.len() {
+                if bytes[j..].starts_with(b"/*") {
+                    depth += 1;
+                    j += 2;
+                } else if bytes[j..].starts_with(b"*/") {
+                    depth -= 1;
+                    j += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    j += 1;
+                }
+            }
+            blank(&mut out, i, j);
+            i = j;
+        } else if (rest[0] == b'r' || rest.starts_with(b"br"))
+            && (i == 0 || !ident(bytes[i - 1]))
+            && raw_string_hashes(&rest[if rest[0] == b'b' { 2 } else { 1 }..]).is_some()
+        {
+            let prefix = if rest[0] == b'b' { 2 } else { 1 };
+            let hashes = raw_string_hashes(&rest[prefix..]).unwrap_or(0);
+            let start = i + prefix + hashes + 1;
+            let closing = [&b"\""[..], &vec![b'#'; hashes]].concat();
+            let end = bytes[start..]
+                .windows(closing.len())
+                .position(|w| w == closing.as_slice())
+                .map_or(bytes.len(), |n| start + n + closing.len());
+            blank(&mut out, i, end);
+            i = end;
+        } else if rest[0] == b'"' {
+            let mut j = i + 1;
+            while j < bytes.len() && bytes[j] != b'"' {
+                j += if bytes[j] == b'\\' { 2 } else { 1 };
+            }
+            blank(&mut out, i, j + 1);
+            i = j + 1;
+        } else if rest[0] == b'\'' {
+            // A character literal, unless this is a lifetime or label.
+            let end = if rest.get(1) == Some(&b'\\') {
+                rest.iter()
+                    .skip(2)
+                    .position(|&b| b == b'\'')
+                    .map(|n| i + n + 3)
+            } else {
+                source[i + 1..]
+                    .chars()
+                    .next()
+                    .map(|c| i + 1 + c.len_utf8())
+                    .filter(|&j| bytes.get(j) == Some(&b'\''))
+                    .map(|j| j + 1)
+            };
+            match end {
+                Some(end) => {
+                    blank(&mut out, i, end);
+                    i = end;
+                }
+                None => i += 1,
+            }
+        } else {
+            i += 1;
+        }
+    }
+    String::from_utf8(out).expect("only whole characters are replaced")
+}
+
+/// The number of `#`s of a raw string starting after its `r`.
+fn raw_string_hashes(rest: &[u8]) -> Option<usize> {
+    let hashes = rest.iter().take_while(|&&b| b == b'#').count();
+    (rest.get(hashes) == Some(&b'"')).then_some(hashes)
+}
+
+/// Finds `kind name { ... }` in `masked`.
+fn find_item(masked: &str, kind: ItemKind, name: &str) -> Option<Item> {
+    let keyword = kind.keyword();
+    let bytes = masked.as_bytes();
+    let ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    for (at, _) in masked.match_indices(keyword) {
+        let after = at + keyword.len();
+        if (at > 0 && ident(bytes[at - 1])) || bytes.get(after).is_some_and(|&b| ident(b)) {
+            continue;
+        }
+        let Some(len) = masked[after..].find(['{', ';']) else {
+            continue;
+        };
+        let open = after + len;
+        if bytes[open] != b'{' || item_name(kind, &masked[after..open]) != Some(name) {
+            continue;
+        }
+        let close = matching_brace(masked, open)?;
+        let header = masked[..at].rfind('\n').map_or(0, |n| n + 1);
+        return Some(Item {
+            header,
+            open,
+            close,
+        });
+    }
+    None
+}
+
+/// The name an item header (the text between the keyword and `{`) declares:
+/// the first word for most items, the implementing type for `impl`.
+fn item_name(kind: ItemKind, header: &str) -> Option<&str> {
+    let header = header.split(" where ").next().unwrap_or(header);
+    let mut header = header.trim_start();
+    if kind == ItemKind::Impl && header.starts_with('<') {
+        header = &header[generics_len(header)?..];
+    }
+    let ty = match header.split_once(" for ") {
+        Some((_, ty)) if kind == ItemKind::Impl => ty,
+        _ => header,
+    };
+    let ty = ty.trim_start().trim_start_matches('&');
+    let end = ty
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or(ty.len());
+    let path = &ty[..end];
+    path.rsplit("::").next().filter(|name| !name.is_empty())
+}
+
+/// Length of the `<...>` generics at the start of `s`.
+fn generics_len(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn matching_brace(masked: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, b) in masked.bytes().enumerate().skip(open) {
+        match b {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Inserts `snippet` at the end of `item`'s body.
+fn insert_into(source: &str, masked: &str, item: Item, kind: ItemKind, snippet: &str) -> String {
+    let header_indent = leading_whitespace(&source[item.header..]);
+    let body = &masked[item.open + 1..item.close];
+    // The first segment shares the header's line.
+    let indent = body
+        .split('\n')
+        .skip(1)
+        .find(|line| !line.trim().is_empty())
+        .map(leading_whitespace)
+        .map_or_else(|| format!("{header_indent}    "), str::to_string);
+    let text = indent_lines(&dedent(snippet), &indent);
+
+    let mut out = String::with_capacity(source.len() + text.len() + 2);
+    let last_code = masked[..item.close].trim_end().len();
+    let mut head = &source[..item.close];
+    let line_start = source[..item.close].rfind('\n').map_or(0, |n| n + 1);
+    let close_on_own_line = source[line_start..item.close].trim().is_empty();
+    if close_on_own_line {
+        head = &source[..line_start];
+    }
+    if kind.comma_separated() && !matches!(masked.as_bytes()[last_code - 1], b',' | b'{') {
+        out.push_str(&source[..last_code]);
+        out.push(',');
+        out.push_str(&head[last_code..]);
+    } else {
+        out.push_str(head);
+    }
+    if close_on_own_line {
+        let previous = masked[item.open + 1..line_start].trim_end();
+        if kind == ItemKind::Mod && !previous.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&text);
+        out.push('\n');
+        out.push_str(&source[line_start..]);
+    } else {
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+        out.push_str(&text);
+        out.push('\n');
+        out.push_str(header_indent);
+        out.push_str(&source[item.close..]);
+    }
+    out
+}
+
+/// Finds the anchor comment `name`, returning the start of its line and its
+/// indentation.
+fn find_anchor<'a>(source: &'a str, masked: &str, name: &str) -> Option<(usize, &'a str)> {
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let found = line.find(ANCHOR).filter(|&at| {
+            let in_comment = masked.as_bytes()[offset + at] == b' ';
+            let rest = line[at + ANCHOR.len()..].trim();
+            in_comment && rest.trim_end_matches("*/").trim() == name
+        });
+        if found.is_some() {
+            return Some((offset, leading_whitespace(line)));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Splits a `#[cfg(test)] mod tests { ... }` module out of `snippet`,
+/// returning the rest and the module's body.
+fn split_tests(snippet: &str) -> (String, Option<String>) {
+    let masked = mask(snippet);
+    let Some(tests) = find_item(&masked, ItemKind::Mod, "tests") else {
+        return (snippet.to_string(), None);
+    };
+    let start = attributes_start(snippet, tests.header);
+    let body = snippet[tests.open + 1..tests.close].trim_matches('\n');
+    let rest = format!(
+        "{}{}",
+        snippet[..start].trim_end(),
+        &snippet[tests.close + 1..]
+    );
+    (rest, Some(body.to_string()))
+}
+
+/// Start of the attribute and doc comment lines directly above `line`.
+fn attributes_start(source: &str, mut line: usize) -> usize {
+    while line > 0 {
+        let previous = source[..line - 1].rfind('\n').map_or(0, |n| n + 1);
+        let text = source[previous..line].trim();
+        if !(text.starts_with("#[") || text.starts_with("///")) {
+            break;
+        }
+        line = previous;
+    }
+    line
+}
+
+/// Removes `use` lines of `snippet` that `existing` already has.
+fn drop_present_uses(snippet: &str, existing: &str) -> String {
+    let uses: Vec<&str> = existing
+        .lines()
+        .map(str::trim)
+        .filter(|l| l.starts_with("use "))
+        .collect();
+    snippet
+        .split_inclusive('\n')
+        .filter(|line| {
+            let line = line.trim();
+            !(line.starts_with("use ") && uses.contains(&line))
+        })
+        .collect()
+}
+
+/// Whether `source` already contains `snippet`, ignoring indentation.
+fn contains(source: &str, snippet: &str) -> bool {
+    let normalize = |s: &str| -> Vec<String> {
+        s.lines()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect()
+    };
+    let needle = normalize(snippet);
+    let haystack = normalize(source);
+    !needle.is_empty()
+        && haystack
+            .windows(needle.len())
+            .any(|window| window == needle.as_slice())
+}
+
+fn append(existing: &str, snippet: &str) -> String {
+    let mut out = existing.trim_end().to_string();
+    if !out.is_empty() {
+        out.push_str("\n\n");
+    }
+    out.push_str(dedent(snippet).trim_end());
+    out.push('\n');
+    out
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Removes the indentation common to every non-blank line.
+fn dedent(text: &str) -> String {
+    let text = text.trim_matches('\n');
+    let common = text
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| leading_whitespace(l).len())
+        .min()
+        .unwrap_or(0);
+    text.lines()
+        .map(|l| l.get(common..).unwrap_or("").trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn indent_lines(text: &str, indent: &str) -> String {
+    text.lines()
+        .map(|l| {
+            if l.is_empty() {
+                String::new()
+            } else {
+                format!("{indent}{l}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARSER: &str = "\
+use std::fmt;
+
+/// Errors.
+pub enum Error {
+    Empty, // \"}\" in a comment
+    Invalid(String)
+}
+
+pub fn parse(input: &str) -> &str {
+    // cc-agents:insert helpers
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(\"{\"), \"{\");
+    }
+}
+";
+
+    #[test]
+    fn test_parse_points() {
+        for spec in ["auto", "end", "anchor:helpers", "enum:Error", "impl:Parser"] {
+            assert_eq!(Point::parse(spec).unwrap().to_string(), spec);
+        }
+        assert!(Point::parse("fn:parse").is_err());
+        assert!(Point::parse("enum:").is_err());
+    }
+
+    #[test]
+    fn test_mask_skips_comments_and_literals() {
+        let source =
+            "let a = \"{\"; // }\nlet b = '{'; let c: &'static str = r#\"}\"#; /* { /* } */ } */";
+        let masked = mask(source);
+        assert_eq!(masked.len(), source.len());
+        assert!(!masked.contains(['{', '}']));
+        assert!(masked.contains("&'static"));
+    }
+
+    #[test]
+    fn test_auto_merges_tests_into_existing_module() {
+        let snippet = "use std::fmt;\n\npub fn tokenize(input: &str) -> Vec<&str> {\n    input.split(' ').collect()\n}\n\n\
+                       #[cfg(test)]\nmod tests {\n    use super::*;\n\n    #[test]\n    fn test_tokenize() {\n        assert_eq!(tokenize(\"a b\").len(), 2);\n    }\n}\n";
+        let out = inject(PARSER, snippet, &Point::Auto).unwrap();
+        assert_eq!(out.matches("use std::fmt;").count(), 1);
+        assert_eq!(out.matches("use super::*;").count(), 1);
+        assert!(out.contains(
+            "    input\n}\n\npub fn tokenize(input: &str) -> Vec<&str> {\n    input.split(' ').collect()\n}\n\n#[cfg(test)]\nmod tests {"
+        ), "{out}");
+        assert!(out.ends_with(
+            "        assert_eq!(parse(\"{\"), \"{\");\n    }\n\n    #[test]\n    fn test_tokenize() {\n        assert_eq!(tokenize(\"a b\").len(), 2);\n    }\n}\n"
+        ), "{out}");
+        assert_eq!(inject(&out, snippet, &Point::Auto).unwrap(), out);
+    }
+
+    #[test]
+    fn test_item_and_anchor_points() {
+        let out = inject(
+            PARSER,
+            "TooLong { max: usize },",
+            &Point::parse("enum:Error").unwrap(),
+        )
+        .unwrap();
+        assert!(
+            out.contains("    Invalid(String),\n    TooLong { max: usize },\n}\n"),
+            "{out}"
+        );
+
+        let out = inject(
+            PARSER,
+            "let input = input.trim();",
+            &Point::parse("anchor:helpers").unwrap(),
+        )
+        .unwrap();
+        assert!(
+            out.contains("    let input = input.trim();\n    // cc-agents:insert helpers\n"),
+            "{out}"
+        );
+
+        let out = inject(
+            "struct Config { a: u8 }\n",
+            "b: u16,",
+            &Point::parse("struct:Config").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(out, "struct Config { a: u8,\n    b: u16,\n}\n");
+
+        let source = "impl<T: Clone> fmt::Display for Wrapper<T> {\n}\n";
+        let out = inject(source, "fn x() {}", &Point::parse("impl:Wrapper").unwrap()).unwrap();
+        assert_eq!(
+            out,
+            "impl<T: Clone> fmt::Display for Wrapper<T> {\n    fn x() {}\n}\n"
+        );
+
+        for spec in ["enum:Missing", "anchor:missing"] {
+            assert!(
+                inject(PARSER, "x", &Point::parse(spec).unwrap()).is_err(),
+                "{spec}"
+            );
+        }
+        assert_eq!(
+            inject("", "fn a() {}\n", &Point::End).unwrap(),
+            "fn a() {}\n"
+        );
+    }
+}
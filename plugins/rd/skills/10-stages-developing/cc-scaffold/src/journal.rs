@@ -0,0 +1,376 @@
+//! Undo journal of scaffolding operations.
+//!
+//! Before a command changes a file, the file's path and prior contents are
+//! recorded in an [`Operation`] under `journal/` in
+//! [`locator::agents_home`], one directory per operation. [`rollback`]
+//! undoes an operation: files it created are removed, along with the
+//! directories created for them if they are left empty, and files it
+//! modified get their previous contents back. Files changed again since the
+//! operation are left alone unless forced. Only the newest [`KEEP`]
+//! operations are kept.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::digest;
+use crate::error::{Error, Result};
+use crate::locator;
+use crate::lockfile;
+
+/// Number of operations kept; older ones are forgotten.
+pub const KEEP: usize = 50;
+
+/// Name of an operation's record inside its directory.
+const RECORD: &str = "operation.json";
+
+/// The journal, `journal/` under [`locator::agents_home`].
+pub fn root() -> Option<PathBuf> {
+    Some(locator::agents_home()?.join("journal"))
+}
+
+/// A recorded command and the files it changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: String,
+    /// Arguments the command was run with, e.g.
+    /// `new rust/function --out src/parse.rs`.
+    pub command: String,
+    /// Directory the command ran in.
+    pub cwd: PathBuf,
+    /// Milliseconds since the Unix epoch when the command started.
+    pub started: u64,
+    /// Changed files, in the order they were first changed.
+    pub changes: Vec<Change>,
+}
+
+/// A file an [`Operation`] created or modified.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change {
+    /// Absolute path of the file.
+    pub path: PathBuf,
+    /// Name of the copy of its previous contents in the operation's
+    /// directory, or `None` if the operation created it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup: Option<String>,
+    /// Directories created along with the file, outermost first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub created_dirs: Vec<PathBuf>,
+    /// Content hash of what the operation left in the file, recorded when
+    /// it finished; `None` if the file was gone by then or the command
+    /// stopped before finishing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+}
+
+/// An operation being recorded.
+#[derive(Debug)]
+pub struct Journal {
+    root: Option<PathBuf>,
+    dir: Option<PathBuf>,
+    operation: Operation,
+}
+
+impl Journal {
+    /// Starts recording an operation for `command` in the journal at
+    /// `root`; with `None`, nothing is recorded. Nothing is written until a
+    /// file is [`record`](Self::record)ed.
+    pub fn begin(root: Option<&Path>, command: &str) -> Self {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        Self {
+            root: root.map(Path::to_path_buf),
+            dir: None,
+            operation: Operation {
+                id: String::new(),
+                command: command.to_string(),
+                cwd: lockfile::absolute(Path::new(".")),
+                started,
+                changes: Vec::new(),
+            },
+        }
+    }
+
+    /// Records the current state of `path`, which is about to be written.
+    /// Only the first call for a path counts, so the state before the
+    /// operation is what is restored.
+    pub fn record(&mut self, path: &Path) -> Result<()> {
+        let Some(root) = &self.root else {
+            return Ok(());
+        };
+        let path = lockfile::absolute(path);
+        if self.operation.changes.iter().any(|c| c.path == path) {
+            return Ok(());
+        }
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => {
+                let (id, dir) = new_operation_dir(root, self.operation.started)?;
+                self.operation.id = id;
+                self.dir = Some(dir.clone());
+                dir
+            }
+        };
+        let backup = if path.is_file() {
+            let name = self.operation.changes.len().to_string();
+            fs::copy(&path, dir.join(&name)).map_err(|err| Error::io(&path, err))?;
+            Some(name)
+        } else {
+            None
+        };
+        let mut created_dirs: Vec<PathBuf> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| !dir.exists())
+            .map(Path::to_path_buf)
+            .collect();
+        created_dirs.reverse();
+        self.operation.changes.push(Change {
+            path,
+            backup,
+            created_dirs,
+            result: None,
+        });
+        save(&dir, &self.operation)
+    }
+
+    /// Completes the operation, noting what it left in each file so a
+    /// rollback can tell whether they have been changed since. Files the
+    /// operation did not actually change are dropped, and an operation that
+    /// changed nothing is not kept. Returns the operation's identifier if
+    /// it was kept.
+    pub fn finish(mut self) -> Result<Option<String>> {
+        let (Some(root), Some(dir)) = (&self.root, &self.dir) else {
+            return Ok(None);
+        };
+        self.operation.changes.retain_mut(|change| {
+            change.result = hash_of(&change.path);
+            let previous = change.backup.as_ref().and_then(|b| hash_of(&dir.join(b)));
+            change.result != previous
+        });
+        if self.operation.changes.is_empty() {
+            fs::remove_dir_all(dir).map_err(|err| Error::io(dir, err))?;
+            return Ok(None);
+        }
+        save(dir, &self.operation)?;
+        prune(root)?;
+        Ok(Some(self.operation.id))
+    }
+}
+
+/// Recorded operations in the journal at `root`, newest first.
+pub fn operations(root: &Path) -> Result<Vec<Operation>> {
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(Error::io(root, err)),
+    };
+    let mut operations = Vec::new();
+    for entry in entries {
+        let record = entry
+            .map_err(|err| Error::io(root, err))?
+            .path()
+            .join(RECORD);
+        // A directory whose record cannot be read is an operation that
+        // never recorded a change; it has nothing to roll back.
+        let Ok(text) = fs::read_to_string(&record) else {
+            continue;
+        };
+        let operation: Operation = serde_json::from_str(&text).map_err(|err| Error::Rollback {
+            target: record.display().to_string(),
+            message: err.to_string(),
+        })?;
+        operations.push(operation);
+    }
+    operations.sort_by(|a, b| (b.started, &b.id).cmp(&(a.started, &a.id)));
+    Ok(operations)
+}
+
+/// What [`rollback`] did, or would do with `dry_run`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rollback {
+    pub operation: Operation,
+    /// Files given back their previous contents.
+    pub restored: Vec<PathBuf>,
+    /// Files the operation created, now removed.
+    pub removed: Vec<PathBuf>,
+}
+
+/// Undoes operation `id`, or the newest one, in the journal at `root`, and
+/// forgets it. Refuses, changing nothing, if a file was changed after the
+/// operation, unless `force` is set. With `dry_run`, only reports what
+/// would be done.
+pub fn rollback(root: &Path, id: Option<&str>, force: bool, dry_run: bool) -> Result<Rollback> {
+    let target = || match id {
+        Some(id) => format!("operation `{id}`"),
+        None => "the last operation".to_string(),
+    };
+    let operations = operations(root)?;
+    let operation = match id {
+        Some(id) => operations.into_iter().find(|op| op.id == id),
+        None => operations.into_iter().next(),
+    }
+    .ok_or_else(|| Error::Rollback {
+        target: target(),
+        message: "no such operation is recorded".to_string(),
+    })?;
+    let dir = root.join(&operation.id);
+    if !force {
+        let changed = operation
+            .changes
+            .iter()
+            .find(|change| change.result.is_some() && hash_of(&change.path) != change.result);
+        if let Some(change) = changed {
+            return Err(Error::Rollback {
+                target: format!("operation `{}`", operation.id),
+                message: format!(
+                    "{} was changed after it was generated; pass --force to discard those changes",
+                    change.path.display()
+                ),
+            });
+        }
+    }
+    let mut restored = Vec::new();
+    let mut removed = Vec::new();
+    for change in operation.changes.iter().rev() {
+        let path = &change.path;
+        match &change.backup {
+            Some(backup) => {
+                if !dry_run {
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent).map_err(|err| Error::io(parent, err))?;
+                    }
+                    fs::copy(dir.join(backup), path).map_err(|err| Error::io(path, err))?;
+                }
+                restored.push(path.clone());
+            }
+            None => {
+                if !path.exists() {
+                    continue;
+                }
+                if !dry_run {
+                    fs::remove_file(path).map_err(|err| Error::io(path, err))?;
+                    // Directories that still hold other files stay.
+                    for created in change.created_dirs.iter().rev() {
+                        let _ = fs::remove_dir(created);
+                    }
+                }
+                removed.push(path.clone());
+            }
+        }
+    }
+    if !dry_run {
+        fs::remove_dir_all(&dir).map_err(|err| Error::io(&dir, err))?;
+    }
+    Ok(Rollback {
+        operation,
+        restored,
+        removed,
+    })
+}
+
+/// Creates the directory of a new operation started at `started`.
+fn new_operation_dir(root: &Path, started: u64) -> Result<(String, PathBuf)> {
+    fs::create_dir_all(root).map_err(|err| Error::io(root, err))?;
+    let base = format!("{}-{}", started / 1000, std::process::id());
+    let mut id = base.clone();
+    for n in 1.. {
+        let dir = root.join(&id);
+        match fs::create_dir(&dir) {
+            Ok(()) => return Ok((id, dir)),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                id = format!("{base}-{n}");
+            }
+            Err(err) => return Err(Error::io(&dir, err)),
+        }
+    }
+    unreachable!("operation identifiers are unbounded")
+}
+
+fn save(dir: &Path, operation: &Operation) -> Result<()> {
+    let path = dir.join(RECORD);
+    let json = serde_json::to_string_pretty(operation).expect("operations serialize");
+    fs::write(&path, json).map_err(|err| Error::io(&path, err))
+}
+
+/// Forgets all but the newest [`KEEP`] operations.
+fn prune(root: &Path) -> Result<()> {
+    for old in operations(root)?.iter().skip(KEEP) {
+        let dir = root.join(&old.id);
+        fs::remove_dir_all(&dir).map_err(|err| Error::io(&dir, err))?;
+    }
+    Ok(())
+}
+
+fn hash_of(path: &Path) -> Option<String> {
+    fs::read(path).ok().map(|bytes| digest::sha256(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollback_restores_previous_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("journal");
+        let project = dir.path().join("project");
+        fs::create_dir_all(&project).unwrap();
+        let existing = project.join("lib.rs");
+        fs::write(&existing, "old\n").unwrap();
+        let created = project.join("src/queue/mod.rs");
+        let untouched = project.join("README.md");
+        fs::write(&untouched, "readme\n").unwrap();
+
+        let mut journal = Journal::begin(Some(&root), "new rust/feature");
+        for path in [&existing, &created, &untouched, &existing] {
+            journal.record(path).unwrap();
+        }
+        fs::write(&existing, "new\n").unwrap();
+        fs::create_dir_all(created.parent().unwrap()).unwrap();
+        fs::write(&created, "mod queue;\n").unwrap();
+        let id = journal.finish().unwrap().unwrap();
+
+        let operation = &operations(&root).unwrap()[0];
+        assert_eq!(operation.id, id);
+        assert_eq!(operation.changes.len(), 2, "README.md was not changed");
+
+        let preview = rollback(&root, None, false, true).unwrap();
+        assert_eq!(preview.removed, std::slice::from_ref(&created));
+        assert!(created.exists());
+
+        let done = rollback(&root, Some(&id), false, false).unwrap();
+        assert_eq!(done.restored, std::slice::from_ref(&existing));
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old\n");
+        assert!(!project.join("src").exists());
+        assert!(operations(&root).unwrap().is_empty());
+        assert!(rollback(&root, None, false, false).is_err());
+    }
+
+    #[test]
+    fn test_rollback_refuses_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("journal");
+        let file = dir.path().join("parse.rs");
+        let mut journal = Journal::begin(Some(&root), "new rust/function");
+        journal.record(&file).unwrap();
+        fs::write(&file, "generated\n").unwrap();
+        journal.finish().unwrap();
+        fs::write(&file, "edited\n").unwrap();
+
+        let err = rollback(&root, None, false, false).unwrap_err();
+        assert!(err.to_string().contains("pass --force"), "{err}");
+        assert!(file.exists());
+        rollback(&root, None, true, false).unwrap();
+        assert!(!file.exists());
+
+        let mut journal = Journal::begin(Some(&root), "new --dry-run");
+        journal.record(&file).unwrap();
+        assert_eq!(journal.finish().unwrap(), None);
+        assert!(operations(&root).unwrap().is_empty());
+        assert_eq!(Journal::begin(None, "new").finish().unwrap(), None);
+    }
+}
@@ -0,0 +1,41 @@
+//! Library behind the `cc-scaffold` CLI.
+//!
+//! Locates templates shipped with the `10-stages-developing` skill, renders
+//! them, and writes the result into a project.
+
+pub mod cache;
+pub mod cargo_generate;
+pub mod config;
+pub mod digest;
+pub mod doc_examples;
+pub mod error;
+pub mod fuzz;
+pub mod generate;
+pub mod hooks;
+pub mod inject;
+pub mod journal;
+pub mod lint;
+pub mod locator;
+pub mod lockfile;
+pub mod manifest;
+pub mod mutants;
+pub mod openapi;
+pub mod parallel;
+pub mod prompt;
+pub mod regions;
+pub mod registry;
+pub mod remote;
+pub mod search;
+pub mod selftest;
+pub mod stage;
+pub mod template_engine;
+pub mod variables;
+pub mod vectors;
+pub mod verify;
+pub mod watch;
+pub mod workspace;
+
+pub use error::{Error, Result};
+pub use locator::{LocatedTemplate, Locator};
+pub use manifest::Manifest;
+pub use variables::{ValidationError, VariableKind, VariableSpec};
@@ -0,0 +1,614 @@
+//! Checks on templates themselves, for `cc-scaffold validate`.
+//!
+//! Every file a manifest renders is parsed with its syntax, so unbalanced or
+//! unclosed blocks are reported as parse errors, and then checked together
+//! with the partials and base templates it includes for:
+//!
+//! * expressions, conditions and loops naming no declared variable
+//! * prose placeholders no variable is bound to
+//! * declared variables the templates never use
+//! * in Rust templates, public items without a `///` doc comment, and
+//!   templates without tests: no `#[cfg(test)]` module and, for a bundle,
+//!   no file under `tests/`, unless all it renders of Rust is benchmarks
+//!
+//! Edition variants are checked like the files they stand in for.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::cache;
+use crate::error::Error;
+use crate::manifest::Manifest;
+use crate::template_engine::{self, Condition, Node, Span, Template};
+use crate::variables::{self, VariableKind};
+
+/// Something wrong with a template.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Problem {
+    pub file: PathBuf,
+    /// Where in the file, if the problem is at a particular place.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+impl Problem {
+    fn new(file: &Path, span: Option<Span>, message: impl Into<String>) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// A problem for `err`, at the place it names if it is a template
+    /// error.
+    pub fn from_error(file: &Path, err: &Error) -> Self {
+        match err {
+            Error::Template {
+                file: named,
+                span,
+                message,
+            } => Self::new(named.as_deref().unwrap_or(file), Some(*span), message),
+            _ => Self::new(file, None, err.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(
+                f,
+                "{}:{}:{}: {}",
+                self.file.display(),
+                span.line,
+                span.column,
+                self.message
+            ),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+/// Checks the template files of `manifest`, looking includes up in
+/// `include_dirs`. Problems are sorted by file and position.
+pub fn check(manifest: &Manifest, include_dirs: &[PathBuf]) -> Vec<Problem> {
+    let files: Vec<(&Path, Option<&str>)> = if manifest.files.is_empty() {
+        std::iter::once(&manifest.file)
+            .chain(manifest.editions.values())
+            .map(|file| (file.as_path(), None))
+            .collect()
+    } else {
+        manifest
+            .files
+            .iter()
+            .flat_map(|file| {
+                std::iter::once(&file.template)
+                    .chain(file.editions.values())
+                    .map(|template| (template.as_path(), Some(file.path.as_str())))
+            })
+            .collect()
+    };
+    let mut walker = Walker {
+        manifest,
+        includes: BTreeMap::new(),
+        used: BTreeSet::new(),
+        prose: BTreeSet::new(),
+        active: Vec::new(),
+        problems: Vec::new(),
+    };
+    // Sources of every file parsed, for identifier placeholders and the
+    // Rust checks.
+    let mut sources: BTreeMap<PathBuf, String> = BTreeMap::new();
+    let mut templates = Vec::new();
+    let mut has_tests = false;
+    let mut benches_only = !manifest.files.is_empty();
+    for (path, destination) in files {
+        let relative = destination.map(|d| d.trim_start_matches("./"));
+        has_tests |= relative.is_some_and(|d| d.starts_with("tests/"));
+        benches_only &= relative.is_some_and(|d| d.starts_with("benches/") || !d.ends_with(".rs"));
+        if let Some(destination) = destination {
+            match Template::parse(destination) {
+                Ok(pattern) => walker.use_all(&pattern),
+                Err(err) => walker.problems.push(Problem::new(
+                    &manifest.path,
+                    None,
+                    format!("file destination `{destination}`: {err}"),
+                )),
+            }
+        }
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                walker
+                    .problems
+                    .push(Problem::from_error(path, &Error::io(path, err)));
+                continue;
+            }
+        };
+        match cache::compile(manifest.backend(), path, &source, include_dirs) {
+            Ok(compiled) => {
+                for include in compiled.includes {
+                    if let Ok(text) = fs::read_to_string(&include.path) {
+                        sources.insert(include.path.clone(), text);
+                    }
+                    walker.includes.insert(include.name, include.template);
+                }
+                templates.push(compiled.template);
+            }
+            Err(err) => walker.problems.push(Problem::from_error(path, &err)),
+        }
+        sources.insert(path.to_path_buf(), source);
+    }
+    for template in &templates {
+        let file = template.file().unwrap_or(&manifest.file).to_path_buf();
+        walker.nodes(template.nodes(), &file, &mut Vec::new());
+    }
+    if manifest.has_edition_variants() {
+        walker.used.insert("edition".to_string());
+    }
+    for spec in &manifest.variables {
+        if let Some(default) = spec
+            .default
+            .as_deref()
+            .filter(|_| spec.has_derived_default())
+        {
+            if let Ok(default) = Template::parse(default) {
+                walker.use_all(&default);
+            }
+        }
+        for name in spec
+            .default
+            .iter()
+            .flat_map(|d| variables::env_references(d))
+        {
+            if !manifest.env.contains(&name) {
+                walker.problems.push(Problem::new(
+                    &manifest.path,
+                    None,
+                    format!(
+                        "default for `{}` reads `${{{name}}}`, which is not listed under `env`",
+                        spec.name
+                    ),
+                ));
+            }
+        }
+    }
+
+    let Walker {
+        used,
+        prose,
+        mut problems,
+        ..
+    } = walker;
+    for spec in &manifest.variables {
+        let placed = spec.placeholder.as_deref().is_some_and(|placeholder| {
+            match placeholder
+                .strip_prefix('[')
+                .and_then(|p| p.strip_suffix(']'))
+            {
+                Some(text) => prose.contains(text),
+                None => sources
+                    .values()
+                    .any(|source| template_engine::find_placeholder(source, placeholder).is_some()),
+            }
+        });
+        if !placed && !used.contains(&spec.name) {
+            problems.push(Problem::new(
+                &manifest.path,
+                None,
+                format!("variable `{}` is declared but never used", spec.name),
+            ));
+        }
+    }
+    if manifest.language == "rust" {
+        let rust: Vec<_> = sources
+            .iter()
+            .filter(|(path, _)| path.extension().is_some_and(|ext| ext == "rs"))
+            .collect();
+        for (path, source) in &rust {
+            for (span, item) in undocumented(source) {
+                problems.push(Problem::new(
+                    path,
+                    Some(span),
+                    format!("public {item} has no doc comment"),
+                ));
+            }
+        }
+        has_tests |= rust
+            .iter()
+            .any(|(_, source)| source.contains("#[cfg(test)]"));
+        if !rust.is_empty() && !has_tests && !benches_only {
+            problems.push(Problem::new(
+                &manifest.path,
+                None,
+                "no test module: add a `#[cfg(test)]` module or a file under `tests/`",
+            ));
+        }
+    }
+    problems.sort();
+    problems.dedup();
+    problems
+}
+
+/// Walks node trees in the scope they render in, noting the variables and
+/// prose placeholders they use.
+struct Walker<'a> {
+    manifest: &'a Manifest,
+    /// Partials and base templates by the name they are included by.
+    includes: BTreeMap<String, Template>,
+    used: BTreeSet<String>,
+    prose: BTreeSet<String>,
+    /// Includes being walked, to stop at recursive ones.
+    active: Vec<String>,
+    problems: Vec<Problem>,
+}
+
+/// Names an `{{#each}}` body can use besides the variables: the fields of
+/// its items, or anything if they are not known.
+type LoopScope = Option<Vec<String>>;
+
+impl Walker<'_> {
+    fn nodes(&mut self, nodes: &[Node], file: &Path, scopes: &mut Vec<LoopScope>) {
+        for node in nodes {
+            match node {
+                Node::Text(_) => {}
+                Node::Prose { text, span } => {
+                    self.prose.insert(text.clone());
+                    let placeholder = format!("[{text}]");
+                    let bound = self
+                        .manifest
+                        .variables
+                        .iter()
+                        .any(|v| v.placeholder.as_deref() == Some(placeholder.as_str()));
+                    if !bound {
+                        self.problems.push(Problem::new(
+                            file,
+                            Some(*span),
+                            format!("prose placeholder `{placeholder}` is not bound to a variable"),
+                        ));
+                    }
+                }
+                Node::Expr { path, span, .. } => self.path(path, *span, file, scopes),
+                Node::If {
+                    condition,
+                    then,
+                    otherwise,
+                    span,
+                    ..
+                } => {
+                    let path = match condition {
+                        Condition::Truthy(path) | Condition::Compare { path, .. } => path,
+                    };
+                    self.path(path, *span, file, scopes);
+                    self.nodes(then, file, scopes);
+                    self.nodes(otherwise, file, scopes);
+                }
+                Node::Each {
+                    path,
+                    body,
+                    otherwise,
+                    span,
+                } => {
+                    self.path(path, *span, file, scopes);
+                    let fields = match path.as_slice() {
+                        [name] if scopes.is_empty() => {
+                            match self.manifest.variable(name).map(|v| &v.kind) {
+                                Some(VariableKind::List { fields }) => Some(fields.clone()),
+                                _ => None,
+                            }
+                        }
+                        _ => None,
+                    };
+                    scopes.push(fields);
+                    self.nodes(body, file, scopes);
+                    scopes.pop();
+                    self.nodes(otherwise, file, scopes);
+                }
+                Node::Block { body, .. } => self.nodes(body, file, scopes),
+                Node::Extends { base, blocks, span } => {
+                    self.include("base template", base, *span, file, scopes);
+                    for body in blocks.values() {
+                        self.nodes(body, file, scopes);
+                    }
+                }
+                Node::Partial { name, span, .. } => {
+                    self.include("partial", name, *span, file, scopes)
+                }
+            }
+        }
+    }
+
+    fn include(
+        &mut self,
+        kind: &str,
+        name: &str,
+        span: Span,
+        file: &Path,
+        scopes: &mut Vec<LoopScope>,
+    ) {
+        if self.active.iter().any(|active| active == name) {
+            return;
+        }
+        let Some(template) = self.includes.get(name).cloned() else {
+            self.problems.push(Problem::new(
+                file,
+                Some(span),
+                format!("unknown {kind} `{name}`"),
+            ));
+            return;
+        };
+        self.active.push(name.to_string());
+        let included = template.file().unwrap_or(file).to_path_buf();
+        self.nodes(template.nodes(), &included, scopes);
+        self.active.pop();
+    }
+
+    /// Checks that a variable path names a declared variable, the
+    /// environment, or something the enclosing loops provide.
+    fn path(&mut self, path: &[String], span: Span, file: &Path, scopes: &[LoopScope]) {
+        let Some(first) = path.first() else {
+            return;
+        };
+        if self.manifest.variable(first).is_some() {
+            self.used.insert(first.clone());
+            return;
+        }
+        if first == "env" && scopes.is_empty() {
+            self.env(path, file, Some(span));
+            return;
+        }
+        let in_loop = scopes.iter().any(|scope| match scope {
+            None => true,
+            Some(fields) => fields.contains(first),
+        });
+        let position = ["this", "@index", "@first", "@last"].contains(&first.as_str());
+        if variables::BUILTINS.contains(&first.as_str())
+            || in_loop
+            || (position && !scopes.is_empty())
+        {
+            return;
+        }
+        self.problems.push(Problem::new(
+            file,
+            Some(span),
+            format!("`{}` is not a declared variable", path.join(".")),
+        ));
+    }
+
+    /// Checks that `path`, starting with `env`, reads an environment
+    /// variable the manifest lists, which is all remote and registry copies
+    /// of the template can read.
+    fn env(&mut self, path: &[String], file: &Path, span: Option<Span>) {
+        if path
+            .get(1)
+            .is_some_and(|name| self.manifest.env.contains(name))
+        {
+            return;
+        }
+        self.problems.push(Problem::new(
+            file,
+            span,
+            format!(
+                "`{}` reads an environment variable not listed under `env`",
+                path.join(".")
+            ),
+        ));
+    }
+
+    /// Notes every variable `template`, part of the manifest, refers to,
+    /// checking only its environment reads.
+    fn use_all(&mut self, template: &Template) {
+        fn walk<'a>(nodes: &'a [Node], paths: &mut Vec<&'a [String]>) {
+            for node in nodes {
+                match node {
+                    Node::Expr { path, .. } | Node::Each { path, .. } => paths.push(path),
+                    Node::If { condition, .. } => {
+                        let (Condition::Truthy(path) | Condition::Compare { path, .. }) = condition;
+                        paths.push(path);
+                    }
+                    _ => {}
+                }
+                match node {
+                    Node::If {
+                        then, otherwise, ..
+                    } => {
+                        walk(then, paths);
+                        walk(otherwise, paths);
+                    }
+                    Node::Each {
+                        body, otherwise, ..
+                    } => {
+                        walk(body, paths);
+                        walk(otherwise, paths);
+                    }
+                    Node::Block { body, .. } => walk(body, paths),
+                    _ => {}
+                }
+            }
+        }
+        let mut paths = Vec::new();
+        walk(template.nodes(), &mut paths);
+        for path in paths {
+            self.used.extend(path.first().cloned());
+            if path.first().is_some_and(|first| first == "env") {
+                let manifest = self.manifest.path.clone();
+                self.env(path, &manifest, None);
+            }
+        }
+    }
+}
+
+/// Public Rust items in `source` not preceded by a `///` doc comment, with
+/// where they are and what kind of item they are. Attributes and
+/// standalone block tags between the comment and the item are skipped.
+fn undocumented(source: &str) -> Vec<(Span, &'static str)> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut found = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let Some(item) = public_item(line.trim_start()) else {
+            continue;
+        };
+        let documented = lines[..index]
+            .iter()
+            .rev()
+            .map(|line| line.trim())
+            .find(|line| !line.starts_with("#[") && !is_block_tag(line))
+            .is_some_and(|line| line.starts_with("///") || line.starts_with("#[doc"));
+        if !documented {
+            let column = line.len() - line.trim_start().len() + 1;
+            let span = Span {
+                line: index + 1,
+                column,
+            };
+            found.push((span, item));
+        }
+    }
+    found
+}
+
+/// The kind of public item `line` declares, if it declares one.
+fn public_item(line: &str) -> Option<&'static str> {
+    let mut rest = line.strip_prefix("pub ")?;
+    for qualifier in ["const ", "async ", "unsafe "] {
+        if let Some(after) = rest.strip_prefix(qualifier) {
+            if after.starts_with("fn ") || after.starts_with("unsafe ") {
+                rest = after;
+            }
+        }
+    }
+    const ITEMS: &[(&str, &str)] = &[
+        ("fn ", "function"),
+        ("struct ", "struct"),
+        ("enum ", "enum"),
+        ("trait ", "trait"),
+        ("type ", "type alias"),
+        ("const ", "constant"),
+        ("static ", "static"),
+    ];
+    ITEMS
+        .iter()
+        .find(|(keyword, _)| rest.starts_with(keyword))
+        .map(|(_, item)| *item)
+}
+
+/// Whether `line` is nothing but a `{{#...}}`, `{{/...}}` or `{{else}}` tag.
+fn is_block_tag(line: &str) -> bool {
+    let Some(inner) = line.strip_prefix("{{").and_then(|l| l.strip_suffix("}}")) else {
+        return false;
+    };
+    let inner = inner.trim();
+    !inner.contains("}}") && (inner.starts_with(['#', '/']) || inner == "else")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(dir: &Path, variables: &str, template: &str) -> Manifest {
+        let path = dir.join("rust/widget.template.toml");
+        fs::create_dir_all(dir.join("rust")).unwrap();
+        fs::write(dir.join("rust/widget-template.rs"), template).unwrap();
+        let text = format!(
+            "name = \"widget\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\ndescription = \"d\"\n\
+             file = \"widget-template.rs\"\n{variables}"
+        );
+        fs::write(&path, &text).unwrap();
+        Manifest::load(&path).unwrap()
+    }
+
+    fn messages(problems: &[Problem]) -> Vec<String> {
+        problems
+            .iter()
+            .map(|p| match p.span {
+                Some(span) => format!("{}: {}", span.line, p.message),
+                None => p.message.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_check_reports_template_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = manifest(
+            dir.path(),
+            "[[variables]]\nname = \"name\"\ntype = \"identifier\"\n\n\
+             [[variables]]\nname = \"fields\"\ntype = \"list\"\nfields = [\"field\", \"ty\"]\n\n\
+             [[variables]]\nname = \"unused\"\n",
+            "/// A widget.\npub struct {{ name }} {\n{{#each fields}}\n    pub {{ field }}: {{ ty }},\n{{/each}}\n}\n\n\
+             {{#if tracing}}\n#[derive(Debug)]\n{{/if}}\npub fn [Widget name](x: {{ missing.kind }}) {}\n\n\
+             {{> partials/nowhere}}\n",
+        );
+        let problems = check(
+            &manifest,
+            &[dir.path().join("rust"), dir.path().to_path_buf()],
+        );
+        assert_eq!(
+            messages(&problems),
+            [
+                "8: `tracing` is not a declared variable",
+                "11: public function has no doc comment",
+                "11: prose placeholder `[Widget name]` is not bound to a variable",
+                "11: `missing.kind` is not a declared variable",
+                "13: unknown partial `partials/nowhere`",
+                "no test module: add a `#[cfg(test)]` module or a file under `tests/`",
+                "variable `unused` is declared but never used",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_reports_unlisted_environment_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = manifest(
+            dir.path(),
+            "env = [\"USER\"]\n\n\
+             [[variables]]\nname = \"author\"\ndefault = \"${USER} ${HOME:-}\"\n\n\
+             [[variables]]\nname = \"shell\"\ndefault = \"{{ env.SHELL }}\"\n",
+            "// {{ author }} {{ shell }} {{ env.USER }}\n// {{ env.GITHUB_TOKEN }}\n\n\
+             #[cfg(test)]\nmod tests {}\n",
+        );
+        assert_eq!(
+            messages(&check(&manifest, &[])),
+            [
+                "2: `env.GITHUB_TOKEN` reads an environment variable not listed under `env`",
+                "`env.SHELL` reads an environment variable not listed under `env`",
+                "default for `author` reads `${HOME}`, which is not listed under `env`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_reports_parse_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = manifest(dir.path(), "", "{{#if x}}\nfn f() {}\n");
+        let problems = check(&manifest, &[]);
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert_eq!(problems[0].span, Some(Span { line: 1, column: 1 }));
+        assert!(problems[0].message.contains("never closed"));
+    }
+
+    #[test]
+    fn test_shipped_templates_are_clean() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../templates");
+        let locator = crate::Locator::new(vec![root.clone()]);
+        let mut problems = Vec::new();
+        for path in crate::manifest::find(&root).unwrap() {
+            let manifest = Manifest::load(&path).unwrap();
+            problems.extend(check(&manifest, &locator.include_dirs_for(&manifest)));
+        }
+        assert!(
+            problems.is_empty(),
+            "{}",
+            problems
+                .iter()
+                .map(|p| format!("{p}\n"))
+                .collect::<String>()
+        );
+    }
+}
@@ -0,0 +1,515 @@
+//! Resolves template identifiers such as `rust/function` to files on disk.
+//!
+//! Templates live under a `templates/` root grouped by language
+//! (`templates/rust/function-template.rs`). An identifier is the language
+//! directory followed by the template name; the `-template` suffix and the
+//! file extension are optional, so `rust/function`,
+//! `rust/function-template` and `rust/function-template.rs` all resolve to
+//! the same file. When a `<name>.template.toml` manifest exists it takes
+//! precedence and names the source file itself.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cargo_generate;
+use crate::error::{Error, Result};
+use crate::manifest::{self, Manifest};
+use crate::variables::EnvAccess;
+
+/// Environment variable that overrides template root discovery.
+pub const TEMPLATES_ENV: &str = "CC_SCAFFOLD_TEMPLATES";
+
+/// Environment variable naming the per-user cc-agents directory, by default
+/// `~/.cc-agents`.
+pub const HOME_ENV: &str = "CC_AGENTS_HOME";
+
+/// Location of the skill's templates relative to a repository checkout.
+const SKILL_TEMPLATES: &str = "plugins/rd/skills/10-stages-developing/templates";
+
+/// A template source file and its manifest, if it has one. For a
+/// cargo-generate project template, `source` is the template directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocatedTemplate {
+    pub source: PathBuf,
+    pub manifest: Option<Manifest>,
+    /// Directories searched, in order, for the partials and base templates
+    /// the source includes. See [`locate_include`].
+    pub include_dirs: Vec<PathBuf>,
+    /// Environment variables rendering may read: all of them, unless the
+    /// template comes from somewhere untrusted.
+    pub env: EnvAccess,
+}
+
+/// Finds template files across one or more template roots.
+#[derive(Debug, Clone)]
+pub struct Locator {
+    roots: Vec<PathBuf>,
+}
+
+impl Locator {
+    /// Creates a locator over explicit roots, searched in order.
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        Self { roots }
+    }
+
+    /// Builds a locator using the standard discovery order:
+    ///
+    /// 1. `explicit` (the `--templates-dir` flag)
+    /// 2. the `CC_SCAFFOLD_TEMPLATES` environment variable
+    /// 3. the user registry, `~/.cc-agents/templates` (see
+    ///    [`user_templates_dir`]), overlaid on
+    /// 4. the skill's `templates/` directory found by walking up from the
+    ///    current directory, and
+    /// 5. the `templates/` directory this binary was built next to
+    ///
+    /// The first two replace discovery entirely; the last three are all
+    /// searched, so a user template overrides a built-in one with the same
+    /// identifier.
+    pub fn discover(explicit: Option<PathBuf>) -> Self {
+        if let Some(dir) = explicit {
+            return Self::new(vec![dir]);
+        }
+        if let Some(dir) = env::var_os(TEMPLATES_ENV).filter(|v| !v.is_empty()) {
+            return Self::new(vec![PathBuf::from(dir)]);
+        }
+        let mut roots = Vec::new();
+        roots.extend(user_templates_dir().filter(|dir| dir.is_dir()));
+        if let Ok(cwd) = env::current_dir() {
+            roots.extend(find_skill_templates(&cwd));
+        }
+        let builtin = Path::new(env!("CARGO_MANIFEST_DIR")).join("../templates");
+        if builtin.is_dir() && !roots.iter().any(|r| same_dir(r, &builtin)) {
+            roots.push(builtin);
+        }
+        Self::new(roots)
+    }
+
+    /// Template roots in search order.
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Resolves `id` to a single template file.
+    pub fn locate(&self, id: &str) -> Result<PathBuf> {
+        self.resolve(id).map(|t| t.source)
+    }
+
+    /// Resolves `id` to a template source and its manifest.
+    pub fn resolve(&self, id: &str) -> Result<LocatedTemplate> {
+        let (dir, name) = match id.rsplit_once('/') {
+            Some((dir, name)) => (dir, name),
+            None => ("", id),
+        };
+        for (index, root) in self.roots.iter().enumerate() {
+            let sub = dir;
+            let dir = root.join(sub);
+            let manifest_path = dir.join(format!("{name}{}", manifest::MANIFEST_SUFFIX));
+            if manifest_path.is_file() {
+                let manifest = Manifest::load(&manifest_path)?;
+                return Ok(LocatedTemplate {
+                    include_dirs: self.include_dirs(index, sub, &manifest.file),
+                    source: manifest.file.clone(),
+                    manifest: Some(manifest),
+                    env: EnvAccess::All,
+                });
+            }
+            let project = dir.join(name);
+            if cargo_generate::is_template(&project) {
+                return Ok(LocatedTemplate {
+                    source: project,
+                    manifest: None,
+                    include_dirs: Vec::new(),
+                    env: EnvAccess::All,
+                });
+            }
+            let mut candidates = candidates_in(&dir, name)?;
+            match candidates.len() {
+                0 => continue,
+                1 => {
+                    let source = candidates.remove(0);
+                    let manifest = manifest_for(&dir, &source)?;
+                    return Ok(LocatedTemplate {
+                        include_dirs: self.include_dirs(index, sub, &source),
+                        source,
+                        manifest,
+                        env: EnvAccess::All,
+                    });
+                }
+                _ => {
+                    return Err(Error::AmbiguousTemplate {
+                        id: id.to_string(),
+                        candidates: join_paths(&candidates),
+                    })
+                }
+            }
+        }
+        Err(Error::TemplateNotFound {
+            id: id.to_string(),
+            searched: join_paths(&self.roots),
+        })
+    }
+
+    /// `path` relative to the root it is under, with `/` separators, e.g.
+    /// `rust/function-template.rs`: where a template file was read from
+    /// without where the roots are on this machine.
+    pub fn relative(&self, path: &Path) -> Option<String> {
+        relative_to(self.root_of(path)?, path)
+    }
+
+    /// The root `path` is under, if any.
+    pub fn root_of(&self, path: &Path) -> Option<&Path> {
+        self.roots
+            .iter()
+            .map(PathBuf::as_path)
+            .find(|root| path.starts_with(root))
+    }
+
+    /// Include directories for the template of `manifest`, as
+    /// [`resolve`](Self::resolve) finds them if the manifest is under one of
+    /// the roots.
+    pub fn include_dirs_for(&self, manifest: &Manifest) -> Vec<PathBuf> {
+        let found = self.roots.iter().enumerate().find_map(|(index, root)| {
+            let sub = manifest.path.parent()?.strip_prefix(root).ok()?;
+            Some((index, sub.to_string_lossy().replace('\\', "/")))
+        });
+        match found {
+            Some((index, sub)) => self.include_dirs(index, &sub, &manifest.file),
+            None => include_dirs(&manifest.file),
+        }
+    }
+
+    /// Include directories for `source`, found under `sub` of root `index`:
+    /// its own directories first, then the same places in every other root,
+    /// so a user template can include the built-in partials.
+    fn include_dirs(&self, index: usize, sub: &str, source: &Path) -> Vec<PathBuf> {
+        let mut dirs = include_dirs(source);
+        for (i, root) in self.roots.iter().enumerate() {
+            if i == index {
+                continue;
+            }
+            for dir in [root.join(sub), root.clone()] {
+                if !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+        dirs
+    }
+
+    /// Every manifest across all roots. When two roots define the same
+    /// identifier, the earlier root wins.
+    pub fn manifests(&self) -> Result<Vec<Manifest>> {
+        let mut all: Vec<Manifest> = Vec::new();
+        for root in &self.roots {
+            for manifest in Manifest::discover(root)? {
+                if !all.iter().any(|m| m.id() == manifest.id()) {
+                    all.push(manifest);
+                }
+            }
+        }
+        all.sort_by_key(Manifest::id);
+        Ok(all)
+    }
+}
+
+/// The per-user cc-agents directory: `$CC_AGENTS_HOME`, or `~/.cc-agents`
+/// when the variable is unset.
+pub fn agents_home() -> Option<PathBuf> {
+    match env::var_os(HOME_ENV).filter(|v| !v.is_empty()) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => Some(home_dir()?.join(".cc-agents")),
+    }
+}
+
+/// The user template registry, `templates/` under [`agents_home`].
+pub fn user_templates_dir() -> Option<PathBuf> {
+    Some(agents_home()?.join("templates"))
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The directories a template's includes are looked up in when it is not
+/// resolved through a [`Locator`]: the template's own directory, then the
+/// directory above it (the templates root), so a language can override a
+/// shared partial.
+pub fn include_dirs(template: &Path) -> Vec<PathBuf> {
+    template
+        .parent()
+        .into_iter()
+        .flat_map(|dir| [Some(dir), dir.parent()])
+        .flatten()
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+/// Finds the file a `{{> name}}` partial or `{{#extends name}}` base refers
+/// to, searching `dirs` in order.
+///
+/// `name` is a path such as `partials/test-header` or `function`. As with
+/// template identifiers, the file extension is optional.
+pub fn locate_include(dirs: &[PathBuf], name: &str) -> Result<Option<PathBuf>> {
+    let (sub, stem) = match name.rsplit_once('/') {
+        Some((sub, stem)) => (sub, stem),
+        None => ("", name),
+    };
+    for base in dirs {
+        let mut candidates = candidates_in(&base.join(sub), stem)?;
+        match candidates.len() {
+            0 => continue,
+            1 => return Ok(candidates.pop()),
+            _ => {
+                return Err(Error::AmbiguousTemplate {
+                    id: name.to_string(),
+                    candidates: join_paths(&candidates),
+                })
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Finds the manifest in `dir` whose `file` is `source`.
+pub(crate) fn manifest_for(dir: &Path, source: &Path) -> Result<Option<Manifest>> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(None);
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if manifest::is_manifest(&path) {
+            let manifest = Manifest::load(&path)?;
+            if same_file(&manifest.file, source) {
+                return Ok(Some(manifest));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the files in `dir` that `name` refers to. An exact file name
+/// match wins outright; otherwise `<name>.*` and `<name>-template.*` match,
+/// apart from manifests and signatures.
+fn candidates_in(dir: &Path, name: &str) -> Result<Vec<PathBuf>> {
+    let exact = dir.join(name);
+    if exact.is_file() {
+        return Ok(vec![exact]);
+    }
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(Error::io(dir, err)),
+    };
+    let stems = [format!("{name}."), format!("{name}-template.")];
+    let mut found = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|err| Error::io(dir, err))?;
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+        if entry.path().is_file()
+            && !file_name.ends_with(manifest::MANIFEST_SUFFIX)
+            && !file_name.ends_with(".sig")
+            && stems.iter().any(|s| file_name.starts_with(s.as_str()))
+        {
+            found.push(entry.path());
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+/// Walks up from `start` looking for the skill's templates directory.
+fn find_skill_templates(start: &Path) -> Option<PathBuf> {
+    start.ancestors().find_map(|dir| {
+        if dir.file_name().is_some_and(|n| n == "10-stages-developing") {
+            let templates = dir.join("templates");
+            if templates.is_dir() {
+                return Some(templates);
+            }
+        }
+        let templates = dir.join(SKILL_TEMPLATES);
+        templates.is_dir().then_some(templates)
+    })
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    same_dir(a, b)
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// `path` relative to `root`, with `/` separators, if it is under it.
+pub fn relative_to(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let rust = dir.path().join("rust");
+        fs::create_dir_all(&rust).unwrap();
+        fs::write(rust.join("function-template.rs"), "fn function_name() {}\n").unwrap();
+        fs::write(rust.join("Makefile"), "all:\n").unwrap();
+        let go = dir.path().join("go");
+        fs::create_dir_all(&go).unwrap();
+        fs::write(go.join("function-template.go"), "").unwrap();
+        fs::write(go.join("function-template_test.go"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_locate_short_name() {
+        let dir = fixture();
+        let locator = Locator::new(vec![dir.path().to_path_buf()]);
+        let path = locator.locate("rust/function").unwrap();
+        assert!(path.ends_with("rust/function-template.rs"));
+    }
+
+    #[test]
+    fn test_locate_exact_file_name() {
+        let dir = fixture();
+        let locator = Locator::new(vec![dir.path().to_path_buf()]);
+        assert!(locator
+            .locate("rust/Makefile")
+            .unwrap()
+            .ends_with("rust/Makefile"));
+        assert!(locator
+            .locate("rust/function-template.rs")
+            .unwrap()
+            .ends_with("rust/function-template.rs"));
+    }
+
+    #[test]
+    fn test_relative_strips_the_root() {
+        let dir = fixture();
+        let locator = Locator::new(vec![dir.path().join("go"), dir.path().to_path_buf()]);
+        let path = locator.locate("rust/function").unwrap();
+        assert_eq!(
+            locator.relative(&path).as_deref(),
+            Some("rust/function-template.rs")
+        );
+        assert_eq!(locator.relative(Path::new("/elsewhere/x.rs")), None);
+    }
+
+    #[test]
+    fn test_locate_ignores_sibling_test_file() {
+        let dir = fixture();
+        let locator = Locator::new(vec![dir.path().to_path_buf()]);
+        let path = locator.locate("go/function").unwrap();
+        assert!(path.ends_with("go/function-template.go"));
+    }
+
+    #[test]
+    fn test_locate_not_found() {
+        let dir = fixture();
+        let locator = Locator::new(vec![dir.path().to_path_buf()]);
+        let err = locator.locate("rust/missing").unwrap_err();
+        assert!(matches!(err, Error::TemplateNotFound { .. }));
+        let err = locator.locate("cobol/function").unwrap_err();
+        assert!(matches!(err, Error::TemplateNotFound { .. }));
+    }
+
+    #[test]
+    fn test_locate_include_prefers_language_directory() {
+        let dir = fixture();
+        let dirs = include_dirs(&dir.path().join("rust/function-template.rs"));
+        assert_eq!(locate_include(&dirs, "partials/header").unwrap(), None);
+
+        fs::create_dir_all(dir.path().join("partials")).unwrap();
+        fs::write(dir.path().join("partials/header.txt"), "").unwrap();
+        let shared = locate_include(&dirs, "partials/header").unwrap().unwrap();
+        assert!(shared.ends_with("partials/header.txt"));
+
+        fs::create_dir_all(dir.path().join("rust/partials")).unwrap();
+        fs::write(dir.path().join("rust/partials/header.rs"), "").unwrap();
+        let own = locate_include(&dirs, "partials/header").unwrap().unwrap();
+        assert!(own.ends_with("rust/partials/header.rs"));
+    }
+
+    #[test]
+    fn test_includes_fall_back_to_other_roots() {
+        let user = tempfile::tempdir().unwrap();
+        fs::create_dir_all(user.path().join("rust")).unwrap();
+        fs::write(user.path().join("rust/private.rs"), "").unwrap();
+        let builtin = fixture();
+        fs::create_dir_all(builtin.path().join("partials")).unwrap();
+        fs::write(builtin.path().join("partials/header.txt"), "").unwrap();
+        let locator = Locator::new(vec![
+            user.path().to_path_buf(),
+            builtin.path().to_path_buf(),
+        ]);
+
+        let located = locator.resolve("rust/private").unwrap();
+        let base = locate_include(&located.include_dirs, "function")
+            .unwrap()
+            .unwrap();
+        assert!(base.starts_with(builtin.path()));
+        assert!(base.ends_with("rust/function-template.rs"));
+        assert!(locate_include(&located.include_dirs, "partials/header")
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_locate_ambiguous() {
+        let dir = fixture();
+        fs::write(dir.path().join("rust/function-template.md"), "").unwrap();
+        let locator = Locator::new(vec![dir.path().to_path_buf()]);
+        let err = locator.locate("rust/function").unwrap_err();
+        assert!(matches!(err, Error::AmbiguousTemplate { .. }));
+    }
+
+    #[test]
+    fn test_manifest_takes_precedence() {
+        let dir = fixture();
+        fs::write(
+            dir.path().join("rust/function.template.toml"),
+            "name = \"function\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+             description = \"d\"\nfile = \"function-template.rs\"\n",
+        )
+        .unwrap();
+        let locator = Locator::new(vec![dir.path().to_path_buf()]);
+
+        let located = locator.resolve("rust/function").unwrap();
+        assert!(located.source.ends_with("rust/function-template.rs"));
+        assert_eq!(located.manifest.unwrap().id(), "rust/function");
+
+        let located = locator.resolve("rust/function-template.rs").unwrap();
+        assert!(located.manifest.is_some());
+        assert!(locator.resolve("rust/Makefile").unwrap().manifest.is_none());
+        assert_eq!(locator.manifests().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_earlier_root_wins() {
+        let first = fixture();
+        let second = fixture();
+        let locator = Locator::new(vec![
+            first.path().to_path_buf(),
+            second.path().to_path_buf(),
+        ]);
+        let path = locator.locate("rust/function").unwrap();
+        assert!(path.starts_with(first.path()));
+    }
+}
@@ -0,0 +1,257 @@
+//! `cc-agents.lock`: which template version produced each generated file.
+//!
+//! Every file written by `cc-scaffold new` is recorded with the template it
+//! came from, where that template was found (its file relative to the
+//! templates root, or a remote's repository URL), a content hash of the
+//! template (including its partials and manifest), a hash of the generated
+//! output and the variable values used. Later runs refuse to render a
+//! template whose hash no longer matches its entry, and remote templates are
+//! fetched at the locked commit, so scaffolding stays reproducible until
+//! `cc-scaffold update` deliberately moves the lock forward.
+//!
+//! ```toml
+//! version = 1
+//!
+//! [[file]]
+//! path = "src/parser.rs"
+//! template = "rust/function"
+//! source = "rust/function-template.rs"
+//! hash = "sha256:…"
+//! output = "sha256:…"
+//!
+//! [file.values]
+//! function_name = "parse"
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// File name of the lockfile.
+pub const LOCKFILE: &str = "cc-agents.lock";
+
+const VERSION: u32 = 1;
+
+const HEADER: &str = "# Generated by cc-scaffold. Update it with `cc-scaffold update`.\n\n";
+
+/// The parsed contents of a lockfile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    #[serde(default, rename = "file")]
+    pub files: Vec<LockedFile>,
+}
+
+/// One generated file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedFile {
+    /// Destination, relative to the lockfile's directory.
+    pub path: PathBuf,
+    /// Template identifier as given to `cc-scaffold new`.
+    pub template: String,
+    /// Template file this file was rendered from, relative to its
+    /// templates root, or the repository URL of a remote template.
+    pub source: String,
+    /// Commit of a remote template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    /// Content hash of the template and everything it includes.
+    pub hash: String,
+    /// Content hash of the generated file as written.
+    pub output: String,
+    /// Output directory of the bundle the file was generated with, relative
+    /// to the lockfile's directory, so `update` can render the bundle again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle: Option<PathBuf>,
+    /// Whether the output was formatted with `rustfmt`, which `update`
+    /// repeats.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub rustfmt: bool,
+    /// Variable values the file was rendered with.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, String>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            files: Vec::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    /// Reads the lockfile at `path`, or an empty one if it does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(Error::io(path, err)),
+        };
+        let invalid = |message: String| Error::Lockfile {
+            path: path.to_path_buf(),
+            message,
+        };
+        let lock: Self = toml::from_str(&text).map_err(|err| invalid(err.to_string()))?;
+        if lock.version != VERSION {
+            return Err(invalid(format!(
+                "unsupported version {} (expected {VERSION})",
+                lock.version
+            )));
+        }
+        Ok(lock)
+    }
+
+    /// Writes the lockfile to `path`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = toml::to_string(self).map_err(|err| Error::Lockfile {
+            path: path.to_path_buf(),
+            message: err.to_string(),
+        })?;
+        fs::write(path, format!("{HEADER}{text}")).map_err(|err| Error::io(path, err))
+    }
+
+    /// The entry for a file generated from `template`, if any. Every file
+    /// from one template is locked to the same version.
+    pub fn template(&self, template: &str) -> Option<&LockedFile> {
+        self.files.iter().find(|f| f.template == template)
+    }
+
+    /// Adds `entry`, replacing any entry for the same path.
+    pub fn record(&mut self, entry: LockedFile) {
+        match self.files.iter_mut().find(|f| f.path == entry.path) {
+            Some(existing) => *existing = entry,
+            None => {
+                self.files.push(entry);
+                self.files.sort_by(|a, b| a.path.cmp(&b.path));
+            }
+        }
+    }
+}
+
+/// Finds the lockfile governing files under `start`: the nearest existing
+/// `cc-agents.lock` in `start` or its ancestors, else one at the root of the
+/// enclosing git repository, else one in `start` itself.
+pub fn locate(start: &Path) -> PathBuf {
+    let start = absolute(start);
+    if let Some(existing) = start
+        .ancestors()
+        .map(|dir| dir.join(LOCKFILE))
+        .find(|lock| lock.is_file())
+    {
+        return existing;
+    }
+    let root = start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(&start);
+    root.join(LOCKFILE)
+}
+
+/// Expresses `path` relative to the directory holding `lockfile`, as stored
+/// in [`LockedFile::path`]. Paths outside that directory stay absolute.
+pub fn relative_path(lockfile: &Path, path: &Path) -> PathBuf {
+    let dir = absolute(lockfile.parent().unwrap_or(Path::new(".")));
+    let path = absolute(path);
+    path.strip_prefix(&dir)
+        .map(Path::to_path_buf)
+        .unwrap_or(path)
+}
+
+/// Makes `path` absolute against the current directory and removes `.` and
+/// `..` components without touching the filesystem, so it works for files
+/// that do not exist yet.
+pub fn absolute(path: &Path) -> PathBuf {
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+    let mut out = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, template: &str) -> LockedFile {
+        LockedFile {
+            path: PathBuf::from(path),
+            template: template.to_string(),
+            source: "rust/function-template.rs".to_string(),
+            revision: None,
+            hash: "sha256:aa".to_string(),
+            output: "sha256:bb".to_string(),
+            bundle: None,
+            rustfmt: false,
+            values: BTreeMap::from([("function_name".to_string(), "parse".to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKFILE);
+        let mut lock = Lockfile::load(&path).unwrap();
+        assert!(lock.files.is_empty());
+
+        lock.record(entry("src/b.rs", "rust/function"));
+        lock.record(entry("src/a.rs", "rust/test"));
+        let mut replaced = entry("src/b.rs", "rust/function");
+        replaced.hash = "sha256:cc".to_string();
+        lock.record(replaced);
+        lock.save(&path).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with(HEADER));
+        assert!(text.contains("[file.values]\nfunction_name = \"parse\"\n"));
+        let loaded = Lockfile::load(&path).unwrap();
+        assert_eq!(loaded, lock);
+        assert_eq!(loaded.files[0].path, Path::new("src/a.rs"));
+        assert_eq!(loaded.template("rust/function").unwrap().hash, "sha256:cc");
+    }
+
+    #[test]
+    fn test_rejects_unknown_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKFILE);
+        fs::write(&path, "version = 7\n").unwrap();
+        assert!(matches!(Lockfile::load(&path), Err(Error::Lockfile { .. })));
+    }
+
+    #[test]
+    fn test_locate_prefers_existing_then_repository_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(locate(&nested), nested.join(LOCKFILE));
+
+        fs::create_dir(dir.path().join("a/.git")).unwrap();
+        assert_eq!(locate(&nested), dir.path().join("a").join(LOCKFILE));
+
+        fs::write(nested.join(LOCKFILE), "").unwrap();
+        assert_eq!(locate(&nested.join("c")), nested.join(LOCKFILE));
+
+        let lock = dir.path().join("a").join(LOCKFILE);
+        assert_eq!(
+            relative_path(&lock, &nested.join("../x.rs")),
+            Path::new("x.rs")
+        );
+    }
+}
@@ -0,0 +1,24 @@
+//! `cc-scaffold` — instantiate 10-stages-developing templates.
+
+mod cli;
+
+use std::process::ExitCode;
+
+use clap::{CommandFactory, Parser};
+use clap_complete::CompleteEnv;
+
+fn main() -> ExitCode {
+    // Answers completion requests from the script `completions` prints.
+    CompleteEnv::with_factory(cli::Cli::command)
+        .var(cli::COMPLETE_ENV)
+        .complete();
+    let cli = cli::Cli::parse();
+    let format = cli.format;
+    match cli::run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            cli::print_error(format, &err);
+            ExitCode::FAILURE
+        }
+    }
+}
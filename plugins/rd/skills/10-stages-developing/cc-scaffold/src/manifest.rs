@@ -0,0 +1,1226 @@
+//! Template manifests.
+//!
+//! Every template ships a `<name>.template.toml` next to its source file
+//! describing what it is and which variables it takes:
+//!
+//! ```toml
+//! name = "function"
+//! language = "rust"
+//! stage = "5-implementation"
+//! description = "Documented function with a unit test module"
+//! tags = ["function", "testing"]
+//! file = "function-template.rs"
+//!
+//! [[variables]]
+//! name = "function_name"
+//! type = "identifier"
+//! placeholder = "function_name"
+//! description = "Name of the function"
+//!
+//! [[variables]]
+//! name = "description"
+//! placeholder = "[Brief description of what the function does]"
+//! default = "TODO: describe what this function does"
+//! ```
+//!
+//! Variable `type` is one of `string` (the default), `identifier`,
+//! `integer`, `bool`, `enum` (with `options = [...]`) or `list` (with
+//! optional `fields = [...]`, see [`VariableKind::List`]). A variable
+//! without a `default` is required.
+//!
+//! A template that `{{#extends}}` another can set `extends = "<name>"` to
+//! inherit the variables of the manifest with that name in the same
+//! directory. Its own `[[variables]]` are added after the inherited ones,
+//! replacing any with the same name.
+//!
+//! `env = ["GIT_AUTHOR_NAME"]` lists the environment variables the
+//! template and its defaults read, adding to its base's. From a remote or
+//! the user registry it can read no others (see
+//! [`EnvAccess`](crate::variables::EnvAccess)).
+//!
+//! `syntax` names the [`backend`] the template source is written for:
+//! `native` (the default), `handlebars`, `tera` or `liquid`. A manifest
+//! with `extends` and no `syntax` of its own uses its base's.
+//!
+//! A bundle template expands to several files, each with a destination
+//! pattern relative to the output directory that is rendered with the same
+//! variables; `file` can then be left out:
+//!
+//! ```toml
+//! [[files]]
+//! template = "feature/mod-template.rs"
+//! path = "src/{{ module }}/mod.rs"
+//!
+//! [[files]]
+//! template = "feature/test-template.rs"
+//! path = "tests/{{ module }}.rs"
+//! ```
+//!
+//! A `[[files]]` entry with an `inject` point (see [`crate::inject`]) is a
+//! snippet added to an existing file instead of a file of its own, so a
+//! bundle can extend code it did not generate. Several entries can inject
+//! into the same destination; they are applied in order:
+//!
+//! ```toml
+//! [[files]]
+//! template = "subcommand/variant-template.rs"
+//! path = "src/main.rs"
+//! inject = "enum:Command"
+//! ```
+//!
+//! A `[[files]]` entry with `when` naming a bool variable is generated
+//! only when the variable is true, for files that go with an option:
+//!
+//! ```toml
+//! [[files]]
+//! template = "serde/snapshot-template.snap"
+//! path = "src/snapshots/{{ module }}.snap"
+//! when = "snapshots"
+//! ```
+//!
+//! A Rust template can ship variants of a file for particular editions,
+//! chosen by the value of its `edition` variable, which `cc-scaffold new`
+//! takes from `--edition` or the destination crate's `Cargo.toml`. The
+//! file itself is used for every edition without a variant. `editions`
+//! goes at the top level for `file`, or on a `[[files]]` entry:
+//!
+//! ```toml
+//! file = "function-template.rs"
+//! editions = { "2018" = "function-2018-template.rs" }
+//!
+//! [[variables]]
+//! name = "edition"
+//! type = "enum"
+//! options = ["2018", "2021", "2024"]
+//! default = "2021"
+//! ```
+//!
+//! `[[hooks]]` declare commands to run after the file is written; see
+//! [`crate::hooks`]. A manifest without hooks of its own inherits its
+//! base's.
+//!
+//! `[selftest]` lists the crates a Rust template's output needs, so
+//! `cc-scaffold selftest` can compile it in a scratch crate (see
+//! [`crate::selftest`]); variables can give a `sample` to render it with.
+//! A manifest without a `[selftest]` of its own inherits its base's:
+//!
+//! ```toml
+//! [selftest]
+//! dev-dependencies = { tokio = { version = "1", features = ["macros", "rt"] } }
+//! ```
+//!
+//! A bundle that injects into existing files names the template that
+//! creates them as its `[selftest]` `base`, another manifest in the same
+//! directory; the self-test renders that first.
+//!
+//! The template identifier is `<language>/<name>`, e.g. `rust/function`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::hooks::TemplateHook;
+use crate::inject::Point;
+use crate::stage;
+use crate::template_engine::backend::{self, TemplateBackend};
+use crate::template_engine::{is_identifier, Template, Value};
+use crate::variables::{self, EnvAccess, VariableKind, VariableSpec};
+
+/// File name suffix that marks a template manifest.
+pub const MANIFEST_SUFFIX: &str = ".template.toml";
+
+/// Rust editions a template can have variants for.
+pub const EDITIONS: &[&str] = &["2015", "2018", "2021", "2024"];
+
+/// A parsed and validated template manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// Template name, unique within its language.
+    pub name: String,
+    /// Language directory the template belongs to.
+    pub language: String,
+    /// Workflow stage the template supports, one of [`stage::STAGES`],
+    /// e.g. `5-implementation`.
+    pub stage: String,
+    /// One-line summary.
+    pub description: String,
+    /// Free-form labels used for filtering.
+    pub tags: Vec<String>,
+    /// Template source, resolved relative to the manifest. For a bundle
+    /// without `file`, the first file's template.
+    pub file: PathBuf,
+    /// Variants of `file` by edition, resolved relative to the manifest.
+    pub editions: BTreeMap<String, PathBuf>,
+    /// Files a bundle expands to; empty for a single-file template.
+    pub files: Vec<BundleFile>,
+    /// Manifest this one inherits variables from.
+    pub extends: Option<String>,
+    /// Name of the template's syntax backend.
+    pub syntax: String,
+    /// Declared variables, in prompt order, including inherited ones.
+    pub variables: Vec<VariableSpec>,
+    /// Environment variables the template reads, including inherited ones;
+    /// the only ones it can read from a remote or the user registry.
+    pub env: Vec<String>,
+    /// Commands to run after generating the file.
+    pub hooks: Vec<TemplateHook>,
+    /// What the self-test needs to build the template's output.
+    pub selftest: SelfTest,
+    /// Path of the manifest itself.
+    pub path: PathBuf,
+}
+
+/// One file of a bundle template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleFile {
+    /// Template source, resolved relative to the manifest.
+    pub template: PathBuf,
+    /// Destination relative to the output directory, as a native template
+    /// over the manifest's variables, e.g. `src/{{ module }}/mod.rs`.
+    pub path: String,
+    /// Variants of `template` by edition, resolved relative to the
+    /// manifest.
+    pub editions: BTreeMap<String, PathBuf>,
+    /// Where the rendered snippet goes in the existing destination, for a
+    /// file injected into rather than written.
+    pub inject: Option<Point>,
+    /// Bool variable the file is generated for; without one it always is.
+    pub when: Option<String>,
+}
+
+impl BundleFile {
+    /// The template source to render for `edition`.
+    pub fn template_for(&self, edition: Option<&str>) -> &Path {
+        variant(&self.template, &self.editions, edition)
+    }
+}
+
+/// The `[selftest]` section of a manifest.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SelfTest {
+    /// Added to `[dependencies]` of the scratch crate, as in `Cargo.toml`.
+    #[serde(default)]
+    pub dependencies: toml::Table,
+    /// Added to `[dev-dependencies]` of the scratch crate.
+    #[serde(default)]
+    pub dev_dependencies: toml::Table,
+    /// Added to `[build-dependencies]` of the scratch crate, for a
+    /// rendered `build.rs`.
+    #[serde(default)]
+    pub build_dependencies: toml::Table,
+    /// Added to `[features]` of the scratch crate, which is built with all
+    /// of them enabled.
+    #[serde(default)]
+    pub features: toml::Table,
+    /// Manifest in the same directory whose template is rendered into the
+    /// scratch crate first, for a bundle that injects into its files.
+    pub base: Option<String>,
+}
+
+// Dependency tables hold strings, arrays, booleans and tables, never floats.
+impl Eq for SelfTest {}
+
+impl SelfTest {
+    /// Whether the template's output needs crates besides the standard
+    /// library.
+    pub fn has_dependencies(&self) -> bool {
+        !self.dependencies.is_empty()
+            || !self.dev_dependencies.is_empty()
+            || !self.build_dependencies.is_empty()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawManifest {
+    name: String,
+    language: String,
+    stage: String,
+    description: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    file: Option<PathBuf>,
+    #[serde(default)]
+    editions: BTreeMap<String, PathBuf>,
+    #[serde(default)]
+    files: Vec<RawFile>,
+    extends: Option<String>,
+    syntax: Option<String>,
+    #[serde(default)]
+    variables: Vec<RawVariable>,
+    #[serde(default)]
+    env: Vec<String>,
+    #[serde(default)]
+    hooks: Vec<RawHook>,
+    selftest: Option<SelfTest>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawFile {
+    template: PathBuf,
+    path: String,
+    #[serde(default)]
+    editions: BTreeMap<String, PathBuf>,
+    inject: Option<String>,
+    when: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawHook {
+    run: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawVariable {
+    name: String,
+    #[serde(rename = "type", default = "default_type")]
+    kind: String,
+    placeholder: Option<String>,
+    description: Option<String>,
+    default: Option<toml::Value>,
+    sample: Option<toml::Value>,
+    #[serde(default)]
+    options: Vec<String>,
+    #[serde(default)]
+    fields: Vec<String>,
+}
+
+fn default_type() -> String {
+    "string".to_string()
+}
+
+impl Manifest {
+    /// Template identifier, `<language>/<name>`.
+    pub fn id(&self) -> String {
+        format!("{}/{}", self.language, self.name)
+    }
+
+    /// Reads and validates the manifest at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::load_extending(path, &mut Vec::new())
+    }
+
+    /// Parses manifest `text` as if it were read from `path`.
+    pub fn parse(text: &str, path: &Path) -> Result<Self> {
+        Self::parse_extending(text, path, &mut Vec::new())
+    }
+
+    /// Loads a manifest while `chain` (the manifests extending it) is being
+    /// parsed, so `extends` cycles are reported instead of recursing.
+    fn load_extending(path: &Path, chain: &mut Vec<String>) -> Result<Self> {
+        let text = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+        Self::parse_extending(&text, path, chain)
+    }
+
+    fn parse_extending(text: &str, path: &Path, chain: &mut Vec<String>) -> Result<Self> {
+        let invalid = |message: String| Error::Manifest {
+            path: path.to_path_buf(),
+            message,
+        };
+        let raw: RawManifest = toml::from_str(text).map_err(|err| invalid(err.to_string()))?;
+
+        let stem = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix(MANIFEST_SUFFIX));
+        if stem.is_some_and(|stem| stem != raw.name) {
+            return Err(invalid(format!(
+                "name `{}` does not match the manifest file name",
+                raw.name
+            )));
+        }
+        for (field, value) in [
+            ("name", &raw.name),
+            ("language", &raw.language),
+            ("stage", &raw.stage),
+        ] {
+            if value.trim().is_empty() || value.contains(char::is_whitespace) {
+                return Err(invalid(format!("`{field}` must be a non-empty word")));
+            }
+        }
+        if !stage::STAGES.contains(&raw.stage.as_str()) {
+            return Err(invalid(format!(
+                "unknown stage `{}`, expected one of: {}",
+                raw.stage,
+                stage::STAGES.join(", ")
+            )));
+        }
+
+        let mut seen = BTreeSet::new();
+        let mut variables = Vec::with_capacity(raw.variables.len());
+        for var in raw.variables {
+            if !seen.insert(var.name.clone()) {
+                return Err(invalid(format!(
+                    "variable `{}` is declared twice",
+                    var.name
+                )));
+            }
+            variables.push(var.into_spec().map_err(&invalid)?);
+        }
+
+        if let Some(syntax) = raw
+            .syntax
+            .as_deref()
+            .filter(|s| backend::by_name(s).is_none())
+        {
+            let known: Vec<_> = backend::BACKENDS.iter().map(|b| b.name()).collect();
+            return Err(invalid(format!(
+                "unknown syntax `{syntax}`, expected one of: {}",
+                known.join(", ")
+            )));
+        }
+
+        if raw.hooks.iter().any(|hook| hook.run.is_empty()) {
+            return Err(invalid("a hook's `run` must name a command".to_string()));
+        }
+        let mut hooks: Vec<TemplateHook> = raw
+            .hooks
+            .into_iter()
+            .map(|hook| TemplateHook { run: hook.run })
+            .collect();
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut destinations = BTreeMap::new();
+        let mut points = Vec::with_capacity(raw.files.len());
+        for file in &raw.files {
+            let relative = Path::new(&file.path)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+            if !relative || file.path.trim().is_empty() {
+                return Err(invalid(format!(
+                    "file destination `{}` must be relative to the output directory",
+                    file.path
+                )));
+            }
+            Template::parse(&file.path)
+                .map_err(|err| invalid(format!("file destination `{}`: {err}", file.path)))?;
+            let point = match &file.inject {
+                Some(spec) => Some(Point::parse(spec).map_err(|message| {
+                    invalid(format!("file destination `{}`: {message}", file.path))
+                })?),
+                None => None,
+            };
+            // Snippets can share a destination; files written whole cannot.
+            let injects = point.is_some();
+            if let Some(&all_inject) = destinations.get(&file.path) {
+                if !(all_inject && injects) {
+                    return Err(invalid(format!(
+                        "file destination `{}` is declared twice",
+                        file.path
+                    )));
+                }
+            }
+            destinations.insert(&file.path, injects);
+            points.push(point);
+        }
+        let files: Vec<BundleFile> = raw
+            .files
+            .into_iter()
+            .zip(points)
+            .map(|(file, inject)| BundleFile {
+                template: dir.join(file.template),
+                path: file.path,
+                editions: resolve_editions(dir, file.editions),
+                inject,
+                when: file.when,
+            })
+            .collect();
+        let file = match (raw.file, files.first()) {
+            (Some(file), _) => dir.join(file),
+            (None, Some(first)) => first.template.clone(),
+            (None, None) => {
+                return Err(invalid(
+                    "either `file` or `[[files]]` must name the template source".to_string(),
+                ))
+            }
+        };
+        let editions = resolve_editions(dir, raw.editions);
+        if !editions.is_empty() && !files.is_empty() {
+            return Err(invalid(
+                "top-level `editions` apply to `file`; give a bundle's variants on its `[[files]]`"
+                    .to_string(),
+            ));
+        }
+        if let Some(name) = raw.env.iter().find(|name| !is_identifier(name)) {
+            return Err(invalid(format!(
+                "`env` must list environment variable names, got `{name}`"
+            )));
+        }
+        let mut env = raw.env;
+        let mut syntax = raw.syntax;
+        let mut selftest = raw.selftest;
+        if let Some(base) = &raw.extends {
+            if base.contains(['/', '\\']) {
+                return Err(invalid(format!(
+                    "`extends` must name another manifest in the same directory, got `{base}`"
+                )));
+            }
+            let base_path = dir.join(format!("{base}{MANIFEST_SUFFIX}"));
+            if !base_path.is_file() {
+                return Err(invalid(format!("base manifest `{base}` not found")));
+            }
+            chain.push(raw.name.clone());
+            if chain.contains(base) {
+                return Err(invalid(format!(
+                    "`extends` cycle: {} -> {base}",
+                    chain.join(" -> ")
+                )));
+            }
+            let base = Self::load_extending(&base_path, chain)?;
+            chain.pop();
+            syntax.get_or_insert(base.syntax);
+            if hooks.is_empty() {
+                hooks = base.hooks;
+            }
+            selftest.get_or_insert(base.selftest);
+            for name in base.env {
+                if !env.contains(&name) {
+                    env.push(name);
+                }
+            }
+            let mut inherited = base.variables;
+            inherited.retain(|v| {
+                !variables
+                    .iter()
+                    .any(|own: &VariableSpec| own.name == v.name)
+            });
+            inherited.append(&mut variables);
+            variables = inherited;
+        }
+        if let Some(base) = selftest.as_ref().and_then(|s| s.base.as_ref()) {
+            let base_path = dir.join(format!("{base}{MANIFEST_SUFFIX}"));
+            if base.contains(['/', '\\']) || !base_path.is_file() {
+                return Err(invalid(format!(
+                    "selftest `base` must name another manifest in the same directory, got `{base}`"
+                )));
+            }
+        }
+        for file in &files {
+            let Some(name) = &file.when else {
+                continue;
+            };
+            if !variables
+                .iter()
+                .any(|v| v.name == *name && v.kind == VariableKind::Bool)
+            {
+                return Err(invalid(format!(
+                    "file destination `{}`: `when` must name a bool variable, got `{name}`",
+                    file.path
+                )));
+            }
+        }
+        let variants = editions
+            .keys()
+            .chain(files.iter().flat_map(|f| f.editions.keys()));
+        for edition in variants {
+            if !EDITIONS.contains(&edition.as_str()) {
+                return Err(invalid(format!(
+                    "unknown edition `{edition}`, expected one of: {}",
+                    EDITIONS.join(", ")
+                )));
+            }
+            if !variables.iter().any(|v| v.name == "edition") {
+                return Err(invalid(
+                    "edition variants need an `edition` variable to choose by".to_string(),
+                ));
+            }
+        }
+        Ok(Self {
+            name: raw.name,
+            language: raw.language,
+            stage: raw.stage,
+            description: raw.description,
+            tags: raw.tags,
+            file,
+            editions,
+            files,
+            extends: raw.extends,
+            syntax: syntax.unwrap_or_else(|| backend::Native.name().to_string()),
+            variables,
+            env,
+            hooks,
+            selftest: selftest.unwrap_or_default(),
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Loads every manifest under `root`, sorted by identifier.
+    pub fn discover(root: &Path) -> Result<Vec<Self>> {
+        let mut manifests = find(root)?
+            .iter()
+            .map(|path| Self::load(path))
+            .collect::<Result<Vec<_>>>()?;
+        manifests.sort_by_key(Manifest::id);
+        Ok(manifests)
+    }
+
+    /// The backend that parses the template source.
+    pub fn backend(&self) -> &'static dyn TemplateBackend {
+        backend::by_name(&self.syntax).unwrap_or(&backend::Native)
+    }
+
+    /// The template source to render for `edition`, for a single-file
+    /// template.
+    pub fn file_for(&self, edition: Option<&str>) -> &Path {
+        variant(&self.file, &self.editions, edition)
+    }
+
+    /// The `[[files]]` generated with `values`: those without a `when`, and
+    /// those whose `when` variable is true, or defaults to true.
+    pub fn generated_files(&self, values: &BTreeMap<String, String>) -> Vec<&BundleFile> {
+        self.files
+            .iter()
+            .filter(|file| {
+                let Some(name) = &file.when else {
+                    return true;
+                };
+                let spec = self.variables.iter().find(|v| v.name == *name);
+                spec.and_then(|spec| {
+                    let raw = values.get(name).or(spec.default.as_ref())?;
+                    spec.parse_value(raw).ok()
+                }) == Some(Value::Bool(true))
+            })
+            .collect()
+    }
+
+    /// Whether any file of the template has edition variants.
+    pub fn has_edition_variants(&self) -> bool {
+        !self.editions.is_empty() || self.files.iter().any(|f| !f.editions.is_empty())
+    }
+
+    /// Value of the `edition` variable for raw `values`: the one supplied,
+    /// or its default, read with `env`. `None` if the template declares no
+    /// such variable.
+    pub fn edition(
+        &self,
+        values: &BTreeMap<String, String>,
+        env: &EnvAccess,
+    ) -> Result<Option<String>> {
+        let Some(spec) = self.variable("edition") else {
+            return Ok(None);
+        };
+        match values.get(&spec.name) {
+            Some(value) => Ok(Some(value.clone())),
+            None => spec.default_for(values, env),
+        }
+    }
+
+    /// Looks up a declared variable.
+    pub fn variable(&self, name: &str) -> Option<&VariableSpec> {
+        self.variables.iter().find(|v| v.name == name)
+    }
+}
+
+/// `template`'s variant for `edition`, or `template` itself.
+fn variant<'a>(
+    template: &'a Path,
+    editions: &'a BTreeMap<String, PathBuf>,
+    edition: Option<&str>,
+) -> &'a Path {
+    edition
+        .and_then(|edition| editions.get(edition))
+        .map_or(template, PathBuf::as_path)
+}
+
+/// Edition variants with their paths resolved against the manifest's
+/// directory `dir`.
+fn resolve_editions(dir: &Path, editions: BTreeMap<String, PathBuf>) -> BTreeMap<String, PathBuf> {
+    editions
+        .into_iter()
+        .map(|(edition, path)| (edition, dir.join(path)))
+        .collect()
+}
+
+/// Criteria for selecting manifests, as used by `cc-scaffold list`.
+///
+/// Every set criterion must match. Comparisons ignore ASCII case, and a
+/// stage also matches by its number or its name alone, so `5`,
+/// `implementation` and `5-implementation` select the same templates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    pub language: Option<String>,
+    pub stage: Option<String>,
+    /// Tags the manifest must all carry.
+    pub tags: Vec<String>,
+}
+
+impl Filter {
+    pub fn matches(&self, manifest: &Manifest) -> bool {
+        let language = self
+            .language
+            .as_ref()
+            .is_none_or(|lang| lang.eq_ignore_ascii_case(&manifest.language));
+        let stage = self.stage.as_ref().is_none_or(|stage| {
+            let (number, name) = manifest
+                .stage
+                .split_once('-')
+                .unwrap_or((&manifest.stage, ""));
+            [manifest.stage.as_str(), number, name]
+                .iter()
+                .any(|s| !s.is_empty() && s.eq_ignore_ascii_case(stage))
+        });
+        let tags = self
+            .tags
+            .iter()
+            .all(|tag| manifest.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
+        language && stage && tags
+    }
+}
+
+/// Paths of every manifest under `root`, sorted.
+pub fn find(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut manifests = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(Error::io(&dir, err)),
+        };
+        for entry in entries {
+            let path = entry.map_err(|err| Error::io(&dir, err))?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if is_manifest(&path) {
+                manifests.push(path);
+            }
+        }
+    }
+    manifests.sort();
+    Ok(manifests)
+}
+
+/// Whether `path` names a template manifest.
+pub fn is_manifest(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.ends_with(MANIFEST_SUFFIX))
+}
+
+impl RawVariable {
+    fn into_spec(self) -> std::result::Result<VariableSpec, String> {
+        if !self.fields.is_empty() && self.kind != "list" {
+            return Err(format!(
+                "`fields` is only valid for list variables, not `{}`",
+                self.name
+            ));
+        }
+        if let Some(field) = self.fields.iter().find(|f| !is_identifier(f)) {
+            return Err(format!(
+                "field `{field}` of `{}` is not an identifier",
+                self.name
+            ));
+        }
+        let kind = match self.kind.as_str() {
+            "string" => VariableKind::String,
+            "identifier" => VariableKind::Identifier,
+            "integer" => VariableKind::Integer,
+            "bool" => VariableKind::Bool,
+            "enum" if self.options.is_empty() => {
+                return Err(format!("enum variable `{}` has no options", self.name))
+            }
+            "enum" => VariableKind::Enum(self.options),
+            "list" => VariableKind::List {
+                fields: self.fields,
+            },
+            other => {
+                return Err(format!(
+                    "variable `{}` has unknown type `{other}`",
+                    self.name
+                ))
+            }
+        };
+        let scalar = |field: &str, value: Option<toml::Value>| match value {
+            None => Ok(None),
+            Some(toml::Value::String(s)) => Ok(Some(s)),
+            Some(toml::Value::Integer(n)) => Ok(Some(n.to_string())),
+            Some(toml::Value::Boolean(b)) => Ok(Some(b.to_string())),
+            Some(other) => Err(format!(
+                "{field} for `{}` must be a string, integer or bool, got {}",
+                self.name,
+                other.type_str()
+            )),
+        };
+        let default = scalar("default", self.default)?;
+        let sample = scalar("sample", self.sample)?;
+        let spec = VariableSpec {
+            name: self.name,
+            kind,
+            placeholder: self.placeholder,
+            description: self.description,
+            default,
+            sample,
+        };
+        let invalid = |err: String| format!("default for `{}` is invalid: {err}", spec.name);
+        if let Some(default) = &spec.default {
+            variables::check_env_references(default).map_err(invalid)?;
+        }
+        if spec.has_derived_default() {
+            let default = spec.default.as_deref().unwrap_or_default();
+            Template::parse(default).map_err(|err| invalid(err.to_string()))?;
+        } else if let Some(default) = spec.default.as_ref().filter(|d| !d.contains("${")) {
+            spec.parse_value(default)
+                .map_err(|reason| format!("default for `{}` is invalid: {reason}", spec.name))?;
+        }
+        if let Some(sample) = &spec.sample {
+            spec.parse_value(sample)
+                .map_err(|reason| format!("sample for `{}` is invalid: {reason}", spec.name))?;
+        }
+        Ok(spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FUNCTION: &str = r#"
+name = "function"
+language = "rust"
+stage = "5-implementation"
+description = "Documented function with tests"
+tags = ["function"]
+file = "function-template.rs"
+
+[[variables]]
+name = "function_name"
+type = "identifier"
+description = "Name of the function"
+
+[[variables]]
+name = "async"
+type = "bool"
+default = false
+
+[[variables]]
+name = "runtime"
+type = "enum"
+options = ["tokio", "async-std"]
+default = "tokio"
+"#;
+
+    fn parse(text: &str) -> Result<Manifest> {
+        Manifest::parse(text, Path::new("templates/rust/function.template.toml"))
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = parse(FUNCTION).unwrap();
+        assert_eq!(manifest.id(), "rust/function");
+        assert_eq!(
+            manifest.file,
+            Path::new("templates/rust/function-template.rs")
+        );
+        assert_eq!(manifest.variables.len(), 3);
+        assert!(manifest.variable("function_name").unwrap().is_required());
+        assert_eq!(
+            manifest.variable("async").unwrap().default.as_deref(),
+            Some("false")
+        );
+        assert_eq!(
+            manifest.variable("runtime").unwrap().kind,
+            VariableKind::Enum(vec!["tokio".into(), "async-std".into()])
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_default() {
+        let text = FUNCTION.replace("default = \"tokio\"", "default = \"smol\"");
+        let err = parse(&text).unwrap_err().to_string();
+        assert!(err.contains("default for `runtime` is invalid"), "{err}");
+    }
+
+    #[test]
+    fn test_derived_defaults_are_checked_for_syntax() {
+        let text = FUNCTION.replace(
+            "default = \"tokio\"",
+            "default = \"{{ function_name | upper }}\"",
+        );
+        assert!(parse(&text).is_ok());
+
+        let text = FUNCTION.replace(
+            "default = \"tokio\"",
+            "default = \"{{ function_name | reverse }}\"",
+        );
+        assert!(parse(&text)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown filter `reverse`"));
+    }
+
+    #[test]
+    fn test_rejects_duplicate_variables_and_unknown_types() {
+        let text = FUNCTION.replace("name = \"async\"", "name = \"function_name\"");
+        assert!(parse(&text)
+            .unwrap_err()
+            .to_string()
+            .contains("declared twice"));
+
+        let text = FUNCTION.replace("type = \"bool\"", "type = \"float\"");
+        assert!(parse(&text)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown type `float`"));
+    }
+
+    #[test]
+    fn test_list_variables() {
+        let text = format!(
+            "{FUNCTION}\n[[variables]]\nname = \"params\"\ntype = \"list\"\n\
+             fields = [\"name\", \"type\"]\ndefault = \"input:&str\"\n"
+        );
+        let manifest = parse(&text).unwrap();
+        assert_eq!(
+            manifest.variable("params").unwrap().kind,
+            VariableKind::List {
+                fields: vec!["name".into(), "type".into()]
+            }
+        );
+
+        let text = FUNCTION.replace("type = \"bool\"", "type = \"bool\"\nfields = [\"a\"]");
+        assert!(parse(&text)
+            .unwrap_err()
+            .to_string()
+            .contains("only valid for list variables"));
+    }
+
+    #[test]
+    fn test_rejects_name_mismatch_and_unknown_fields() {
+        let text = FUNCTION.replace("name = \"function\"", "name = \"other\"");
+        assert!(parse(&text)
+            .unwrap_err()
+            .to_string()
+            .contains("does not match"));
+
+        let text = format!("authors = [\"x\"]\n{FUNCTION}");
+        assert!(parse(&text)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown field"));
+    }
+
+    #[test]
+    fn test_rejects_unknown_stage() {
+        let text = FUNCTION.replace("\"5-implementation\"", "\"5\"");
+        assert!(parse(&text)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown stage `5`, expected one of: 0-setup, 1-specification,"));
+
+        let text = FUNCTION.replace("\"5-implementation\"", "\"9-report\"");
+        assert_eq!(parse(&text).unwrap().stage, "9-report");
+    }
+
+    #[test]
+    fn test_env_lists_variables_and_adds_to_the_base() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("function.template.toml"),
+            FUNCTION.replace("tags = [", "env = [\"USER\"]\ntags = ["),
+        )
+        .unwrap();
+        let child = dir.path().join("async-function.template.toml");
+        let text = "name = \"async-function\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+                    description = \"Async\"\nfile = \"async.rs\"\nextends = \"function\"\n";
+        fs::write(
+            &child,
+            format!("{text}env = [\"GIT_AUTHOR_NAME\", \"USER\"]\n"),
+        )
+        .unwrap();
+        assert_eq!(
+            Manifest::load(&child).unwrap().env,
+            ["GIT_AUTHOR_NAME", "USER"]
+        );
+
+        fs::write(&child, format!("{text}env = [\"not a name\"]\n")).unwrap();
+        assert!(Manifest::load(&child)
+            .unwrap_err()
+            .to_string()
+            .contains("`env` must list environment variable names, got `not a name`"));
+    }
+
+    #[test]
+    fn test_extends_inherits_variables() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("function.template.toml"), FUNCTION).unwrap();
+        let child = dir.path().join("async-function.template.toml");
+        fs::write(
+            &child,
+            "name = \"async-function\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+             description = \"Async\"\nfile = \"async.rs\"\nextends = \"function\"\n\
+             [[variables]]\nname = \"async\"\ntype = \"bool\"\ndefault = true\n",
+        )
+        .unwrap();
+        let manifest = Manifest::load(&child).unwrap();
+        let names: Vec<_> = manifest.variables.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["function_name", "runtime", "async"]);
+        assert_eq!(
+            manifest.variable("async").unwrap().default.as_deref(),
+            Some("true")
+        );
+
+        fs::write(
+            &child,
+            fs::read_to_string(&child)
+                .unwrap()
+                .replace("extends = \"function\"", "extends = \"missing\""),
+        )
+        .unwrap();
+        assert!(Manifest::load(&child)
+            .unwrap_err()
+            .to_string()
+            .contains("base manifest `missing` not found"));
+
+        fs::write(
+            dir.path().join("function.template.toml"),
+            format!("extends = \"async-function\"\n{FUNCTION}"),
+        )
+        .unwrap();
+        fs::write(
+            &child,
+            fs::read_to_string(&child)
+                .unwrap()
+                .replace("extends = \"missing\"", "extends = \"function\""),
+        )
+        .unwrap();
+        assert!(Manifest::load(&child)
+            .unwrap_err()
+            .to_string()
+            .contains("cycle: async-function -> function -> async-function"));
+    }
+
+    #[test]
+    fn test_hooks() {
+        let text = format!("{FUNCTION}\n[[hooks]]\nrun = [\"cargo\", \"fmt\"]\n");
+        let manifest = parse(&text).unwrap();
+        assert_eq!(manifest.hooks[0].display(), "cargo fmt");
+        let err = parse(&format!("{FUNCTION}\n[[hooks]]\nrun = []\n")).unwrap_err();
+        assert!(err.to_string().contains("must name a command"), "{err}");
+    }
+
+    #[test]
+    fn test_bundle_files() {
+        let bundle = "[[files]]\ntemplate = \"slice/mod.rs\"\npath = \"src/{{ name }}/mod.rs\"\n\
+                      [[files]]\ntemplate = \"slice/test.rs\"\npath = \"tests/{{ name }}.rs\"\n";
+        let text = FUNCTION.replace("file = \"function-template.rs\"\n", "");
+        let manifest = parse(&format!("{text}\n{bundle}")).unwrap();
+        assert_eq!(manifest.files.len(), 2);
+        assert_eq!(manifest.file, Path::new("templates/rust/slice/mod.rs"));
+        assert_eq!(manifest.files[1].path, "tests/{{ name }}.rs");
+
+        let err = parse(&text).unwrap_err();
+        assert!(
+            err.to_string().contains("either `file` or `[[files]]`"),
+            "{err}"
+        );
+        for (path, message) in [
+            ("../up.rs", "must be relative to the output directory"),
+            ("/etc/x.rs", "must be relative to the output directory"),
+            ("src/{{ name.rs", "file destination `src/{{ name.rs`:"),
+        ] {
+            let bundle = format!("[[files]]\ntemplate = \"a.rs\"\npath = \"{path}\"\n");
+            let err = parse(&format!("{text}\n{bundle}")).unwrap_err();
+            assert!(err.to_string().contains(message), "{err}");
+        }
+        let twice = "[[files]]\ntemplate = \"a.rs\"\npath = \"a.rs\"\n\
+                     [[files]]\ntemplate = \"b.rs\"\npath = \"a.rs\"\n";
+        let err = parse(&format!("{text}\n{twice}")).unwrap_err();
+        assert!(err.to_string().contains("declared twice"), "{err}");
+    }
+
+    #[test]
+    fn test_bundle_files_injected_into_existing_ones() {
+        let text = FUNCTION.replace("file = \"function-template.rs\"\n", "");
+        let snippets = "[[files]]\ntemplate = \"variant.rs\"\npath = \"src/main.rs\"\n\
+                        inject = \"enum:Command\"\n\
+                        [[files]]\ntemplate = \"arm.rs\"\npath = \"src/main.rs\"\n\
+                        inject = \"anchor:commands\"\n";
+        let manifest = parse(&format!("{text}\n{snippets}")).unwrap();
+        let points: Vec<_> = manifest.files.iter().map(|f| f.inject.clone()).collect();
+        assert_eq!(
+            points,
+            [
+                Some(Point::parse("enum:Command").unwrap()),
+                Some(Point::Anchor("commands".to_string())),
+            ]
+        );
+
+        let written = "[[files]]\ntemplate = \"main.rs\"\npath = \"src/main.rs\"\n";
+        let err = parse(&format!("{text}\n{snippets}{written}")).unwrap_err();
+        assert!(err.to_string().contains("declared twice"), "{err}");
+        let bad = "[[files]]\ntemplate = \"a.rs\"\npath = \"a.rs\"\ninject = \"match:run\"\n";
+        let err = parse(&format!("{text}\n{bad}")).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("unknown insertion point kind `match`"),
+            "{err}"
+        );
+        let based = format!("{text}\n{snippets}\n[selftest]\nbase = \"missing\"\n");
+        let err = parse(&based).unwrap_err();
+        assert!(err.to_string().contains("selftest `base`"), "{err}");
+    }
+
+    #[test]
+    fn test_bundle_files_generated_when_a_bool_is_true() {
+        let text = FUNCTION.replace("file = \"function-template.rs\"\n", "");
+        let bundle = "[[files]]\ntemplate = \"mod.rs\"\npath = \"src/mod.rs\"\n\
+                      [[files]]\ntemplate = \"async.rs\"\npath = \"src/async.rs\"\n\
+                      when = \"async\"\n";
+        let manifest = parse(&format!("{text}\n{bundle}")).unwrap();
+        let generated = |values: &[(&str, &str)]| -> Vec<&str> {
+            let values = values
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            manifest
+                .generated_files(&values)
+                .iter()
+                .map(|f| f.path.as_str())
+                .collect()
+        };
+        assert_eq!(generated(&[]), ["src/mod.rs"]);
+        assert_eq!(generated(&[("async", "no")]), ["src/mod.rs"]);
+        assert_eq!(
+            generated(&[("async", "yes")]),
+            ["src/mod.rs", "src/async.rs"]
+        );
+
+        for name in ["runtime", "missing"] {
+            let bundle = bundle.replace("\"async\"\n", &format!("\"{name}\"\n"));
+            let err = parse(&format!("{text}\n{bundle}")).unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains(&format!("`when` must name a bool variable, got `{name}`")),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_edition_variants() {
+        let edition = "\n[[variables]]\nname = \"edition\"\ntype = \"enum\"\n\
+                       options = [\"2018\", \"2021\", \"2024\"]\ndefault = \"2021\"\n";
+        let text = format!("editions = {{ \"2018\" = \"function-2018.rs\" }}\n{FUNCTION}{edition}");
+        let manifest = parse(&text).unwrap();
+        assert!(manifest.has_edition_variants());
+        assert_eq!(
+            manifest.file_for(Some("2018")),
+            Path::new("templates/rust/function-2018.rs")
+        );
+        assert_eq!(manifest.file_for(Some("2024")), manifest.file);
+        assert_eq!(manifest.file_for(None), manifest.file);
+        let mut values = BTreeMap::new();
+        assert_eq!(
+            manifest
+                .edition(&values, &EnvAccess::All)
+                .unwrap()
+                .as_deref(),
+            Some("2021")
+        );
+        values.insert("edition".to_string(), "2018".to_string());
+        assert_eq!(
+            manifest
+                .edition(&values, &EnvAccess::All)
+                .unwrap()
+                .as_deref(),
+            Some("2018")
+        );
+        assert_eq!(
+            parse(FUNCTION)
+                .unwrap()
+                .edition(&values, &EnvAccess::All)
+                .unwrap(),
+            None
+        );
+
+        let bundle = "[[files]]\ntemplate = \"mod.rs\"\npath = \"src/mod.rs\"\n\
+                      editions = { \"2024\" = \"mod-2024.rs\" }\n";
+        let bare = FUNCTION.replace("file = \"function-template.rs\"\n", "");
+        let manifest = parse(&format!("{bare}{edition}\n{bundle}")).unwrap();
+        assert_eq!(
+            manifest.files[0].template_for(Some("2024")),
+            Path::new("templates/rust/mod-2024.rs")
+        );
+        assert_eq!(
+            manifest.files[0].template_for(Some("2021")),
+            manifest.files[0].template
+        );
+
+        for (text, message) in [
+            (
+                text.replace("\"2018\" =", "\"2019\" ="),
+                "unknown edition `2019`",
+            ),
+            (
+                format!("editions = {{ \"2018\" = \"f.rs\" }}\n{FUNCTION}"),
+                "need an `edition` variable",
+            ),
+            (
+                format!("editions = {{ \"2018\" = \"f.rs\" }}\n{bare}{edition}\n{bundle}"),
+                "give a bundle's variants on its `[[files]]`",
+            ),
+        ] {
+            let err = parse(&text).unwrap_err();
+            assert!(err.to_string().contains(message), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_syntax_is_validated_and_inherited() {
+        assert_eq!(parse(FUNCTION).unwrap().backend().name(), "native");
+        let err = parse(&format!("syntax = \"jinja\"\n{FUNCTION}")).unwrap_err();
+        assert!(err.to_string().contains("unknown syntax `jinja`"), "{err}");
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("function.template.toml"),
+            format!("syntax = \"tera\"\n{FUNCTION}"),
+        )
+        .unwrap();
+        let child = dir.path().join("async-function.template.toml");
+        let text = "name = \"async-function\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+                    description = \"Async\"\nfile = \"async.rs\"\nextends = \"function\"\n";
+        fs::write(&child, text).unwrap();
+        assert_eq!(Manifest::load(&child).unwrap().syntax, "tera");
+        fs::write(&child, format!("syntax = \"handlebars\"\n{text}")).unwrap();
+        assert_eq!(Manifest::load(&child).unwrap().syntax, "handlebars");
+    }
+
+    #[test]
+    fn test_filter() {
+        let manifest = parse(FUNCTION).unwrap();
+        let filter = |language: Option<&str>, stage: Option<&str>, tags: &[&str]| Filter {
+            language: language.map(String::from),
+            stage: stage.map(String::from),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        };
+        assert!(filter(None, None, &[]).matches(&manifest));
+        assert!(filter(Some("Rust"), Some("5"), &["function"]).matches(&manifest));
+        assert!(filter(None, Some("implementation"), &[]).matches(&manifest));
+        assert!(filter(None, Some("5-implementation"), &[]).matches(&manifest));
+        assert!(!filter(Some("go"), None, &[]).matches(&manifest));
+        assert!(!filter(None, Some("6-expand-tests"), &[]).matches(&manifest));
+        assert!(!filter(None, None, &["function", "async"]).matches(&manifest));
+    }
+
+    #[test]
+    fn test_shipped_manifests_are_valid() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../templates");
+        let manifests = Manifest::discover(&root).unwrap();
+        assert!(manifests.iter().any(|m| m.id() == "rust/function"));
+        for manifest in &manifests {
+            assert!(
+                manifest.file.is_file(),
+                "{} points at a missing file",
+                manifest.id()
+            );
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! `cargo-mutants` configuration and results, for `cc-scaffold mutants`.
+//!
+//! [`exclusions`] curates what a crate's mutants should leave out, each
+//! with the reason it says nothing about the tests: files generated by a
+//! tool, whose generator is what the tests should cover, an `xtask`
+//! automation crate, and `Debug` implementations. [`render_config`] writes
+//! them into the `.cargo/mutants.toml` `cargo mutants` reads, with the
+//! `rust/mutants/config` template.
+//!
+//! [`Outcomes::load`] reads the lists of mutants `cargo mutants` writes to
+//! `mutants.out/` and [`Outcomes::stage_report`] turns them into the
+//! `6-expand-tests` stage's [`StageReport`]: every mutant the tests missed
+//! fails the gate, and one that timed out is a warning, since a test that
+//! hangs did notice something.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::generate::{self, RenderedFile};
+use crate::hooks::{self, Diagnostic};
+use crate::locator::Locator;
+use crate::stage::{self, StageReport};
+use crate::template_engine::{Context, Value};
+
+/// Where `cargo mutants` looks for its configuration, from the crate root.
+pub const CONFIG: &str = ".cargo/mutants.toml";
+
+/// Where `cargo mutants` writes its results, from the crate root.
+pub const OUTPUT: &str = "mutants.out";
+
+const TEMPLATE: &str = "rust/mutants/config";
+
+/// Marks a file as generated, within its first lines.
+const GENERATED_MARKERS: &[&str] = &["@generated", "Generated by `cc-scaffold"];
+
+/// Directories that hold no code of the crate's to mutate.
+const SKIPPED_DIRS: &[&str] = &[
+    "target", "tests", "benches", "examples", "fuzz", "xtask", OUTPUT,
+];
+
+/// Function names `cargo mutants` matches against `exclude_re` and leaves
+/// alone, with why.
+const EXCLUDED_RE: &[(&str, &str)] = &[(
+    "impl Debug",
+    "Debug output is for people reading it, and tests rarely pin it down",
+)];
+
+/// A pattern `cargo mutants` is told to skip, with why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exclusion {
+    pub pattern: String,
+    pub reason: String,
+}
+
+/// The `exclude_globs` and `exclude_re` of the crate in `crate_dir`.
+pub fn exclusions(crate_dir: &Path) -> Result<(Vec<Exclusion>, Vec<Exclusion>)> {
+    let mut globs = Vec::new();
+    if crate_dir.join("xtask/Cargo.toml").is_file() {
+        globs.push(Exclusion {
+            pattern: "xtask/**".to_string(),
+            reason: "workspace automation run by `cargo xtask`, not part of what ships".to_string(),
+        });
+    }
+    for file in generated_files(crate_dir)? {
+        let relative = file.strip_prefix(crate_dir).unwrap_or(&file);
+        globs.push(Exclusion {
+            pattern: relative.to_string_lossy().replace('\\', "/"),
+            reason: "generated; regenerate it rather than testing it by hand".to_string(),
+        });
+    }
+    let res = EXCLUDED_RE
+        .iter()
+        .map(|(pattern, reason)| Exclusion {
+            pattern: pattern.to_string(),
+            reason: reason.to_string(),
+        })
+        .collect();
+    Ok((globs, res))
+}
+
+/// Renders the `.cargo/mutants.toml` of the crate `package` in
+/// `crate_dir`.
+pub fn render_config(locator: &Locator, crate_dir: &Path, package: &str) -> Result<RenderedFile> {
+    let (globs, res) = exclusions(crate_dir)?;
+    let list = |exclusions: Vec<Exclusion>| {
+        Value::List(
+            exclusions
+                .into_iter()
+                .map(|exclusion| {
+                    Value::Map(BTreeMap::from([
+                        ("pattern".to_string(), Value::from(exclusion.pattern)),
+                        ("reason".to_string(), Value::from(exclusion.reason)),
+                    ]))
+                })
+                .collect(),
+        )
+    };
+    let mut ctx = Context::new();
+    ctx.set("package", package);
+    ctx.set("globs", list(globs));
+    ctx.set("res", list(res));
+    let template = locator.resolve(TEMPLATE)?;
+    generate::render_file(&template.source, &crate_dir.join(CONFIG), &ctx)
+}
+
+/// The Rust files of the crate in `crate_dir` marked as generated, in
+/// path order.
+fn generated_files(crate_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut pending = vec![crate_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir).map_err(|err| Error::io(&dir, err))?;
+        for entry in entries {
+            let path = entry.map_err(|err| Error::io(&dir, err))?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if path.is_dir() {
+                if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                    pending.push(path);
+                }
+            } else if hooks::is_rust(&path) {
+                let source = fs::read_to_string(&path).map_err(|err| Error::io(&path, err))?;
+                let generated = source
+                    .lines()
+                    .take(10)
+                    .any(|line| GENERATED_MARKERS.iter().any(|marker| line.contains(marker)));
+                if generated {
+                    found.push(path);
+                }
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+/// One mutant `cargo mutants` tried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mutant {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    /// What was changed, e.g. `replace is_even -> bool with true`.
+    pub description: String,
+}
+
+impl Mutant {
+    /// Parses a line of a `mutants.out` list:
+    /// `src/lib.rs:3:5: replace is_even -> bool with true`.
+    fn parse(line: &str) -> Option<Self> {
+        let (file, rest) = line.split_once(':')?;
+        let (line, rest) = rest.split_once(':')?;
+        let (column, description) = rest.split_once(':')?;
+        Some(Self {
+            file: PathBuf::from(file),
+            line: line.parse().ok()?,
+            column: column.parse().ok()?,
+            description: description.trim().to_string(),
+        })
+    }
+
+    fn diagnostic(&self, outcome: &str) -> Diagnostic {
+        Diagnostic {
+            file: self.file.clone(),
+            line: self.line,
+            column: self.column,
+            message: format!("{}: {outcome}", self.description),
+        }
+    }
+}
+
+/// The mutants of one `cargo mutants` run, by outcome.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outcomes {
+    /// Made a test fail.
+    pub caught: Vec<Mutant>,
+    /// Survived: every test still passed.
+    pub missed: Vec<Mutant>,
+    /// Made the tests run past the timeout.
+    pub timeout: Vec<Mutant>,
+    /// Did not build.
+    pub unviable: Vec<Mutant>,
+}
+
+impl Outcomes {
+    /// Reads the `caught.txt`, `missed.txt`, `timeout.txt` and
+    /// `unviable.txt` of the `mutants.out` directory `dir`.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let invalid = |message: String| Error::Mutants {
+            path: dir.to_path_buf(),
+            message,
+        };
+        if !dir.join("caught.txt").is_file() && !dir.join("missed.txt").is_file() {
+            return Err(invalid(
+                "it holds no results; run `cargo mutants` first".to_string(),
+            ));
+        }
+        let list = |name: &str| -> Result<Vec<Mutant>> {
+            let path = dir.join(name);
+            let text = match fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(err) => return Err(Error::io(&path, err)),
+            };
+            text.lines()
+                .filter(|line| !line.trim().is_empty())
+                .enumerate()
+                .map(|(index, line)| {
+                    Mutant::parse(line).ok_or_else(|| {
+                        invalid(format!(
+                            "line {} of {name} is not a mutant: `{line}`",
+                            index + 1
+                        ))
+                    })
+                })
+                .collect()
+        };
+        Ok(Self {
+            caught: list("caught.txt")?,
+            missed: list("missed.txt")?,
+            timeout: list("timeout.txt")?,
+            unviable: list("unviable.txt")?,
+        })
+    }
+
+    /// The `6-expand-tests` gate: passed unless a mutant was missed.
+    pub fn stage_report(&self) -> StageReport {
+        let counts = BTreeMap::from([
+            ("caught".to_string(), self.caught.len()),
+            ("missed".to_string(), self.missed.len()),
+            ("timeout".to_string(), self.timeout.len()),
+            ("unviable".to_string(), self.unviable.len()),
+        ]);
+        let problems = self
+            .missed
+            .iter()
+            .map(|mutant| mutant.diagnostic("no test failed"))
+            .collect();
+        let mut report = StageReport::new(stage::EXPAND_TESTS, "mutants", counts, problems);
+        report.warnings = self
+            .timeout
+            .iter()
+            .map(|mutant| {
+                format!(
+                    "{}:{}:{}: {}: the tests timed out",
+                    mutant.file.display(),
+                    mutant.line,
+                    mutant.column,
+                    mutant.description
+                )
+            })
+            .collect();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclusions_find_generated_files_and_xtask() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/petstore")).unwrap();
+        fs::create_dir_all(dir.path().join("xtask")).unwrap();
+        fs::write(dir.path().join("xtask/Cargo.toml"), "").unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "pub mod petstore;\n").unwrap();
+        fs::write(
+            dir.path().join("src/petstore/mod.rs"),
+            "//! Petstore.\n//!\n//! Generated by `cc-scaffold from-openapi` from the service's OpenAPI\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("src/schema.rs"),
+            "// @generated by diesel\n",
+        )
+        .unwrap();
+
+        let (globs, res) = exclusions(dir.path()).unwrap();
+        let patterns: Vec<&str> = globs.iter().map(|g| g.pattern.as_str()).collect();
+        assert_eq!(
+            patterns,
+            ["xtask/**", "src/petstore/mod.rs", "src/schema.rs"]
+        );
+        assert_eq!(res[0].pattern, "impl Debug");
+    }
+
+    #[test]
+    fn test_outcomes_fail_the_gate_on_missed_mutants() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("caught.txt"),
+            "src/lib.rs:3:5: replace is_even -> bool with false\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("missed.txt"),
+            "src/lib.rs:3:5: replace is_even -> bool with true\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("timeout.txt"),
+            "src/lib.rs:9:11: replace += with -= in count\n",
+        )
+        .unwrap();
+
+        let report = Outcomes::load(dir.path()).unwrap().stage_report();
+        assert!(!report.passed);
+        assert_eq!(report.counts["caught"], 1);
+        assert_eq!(report.counts["unviable"], 0);
+        assert_eq!(report.problems.len(), 1);
+        assert_eq!(report.problems[0].line, 3);
+        assert_eq!(
+            report.problems[0].message,
+            "replace is_even -> bool with true: no test failed"
+        );
+        assert_eq!(
+            report.warnings,
+            ["src/lib.rs:9:11: replace += with -= in count: the tests timed out"]
+        );
+
+        fs::write(dir.path().join("missed.txt"), "").unwrap();
+        assert!(Outcomes::load(dir.path()).unwrap().stage_report().passed);
+    }
+
+    #[test]
+    fn test_outcomes_reject_missing_and_malformed_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = Outcomes::load(dir.path()).unwrap_err();
+        assert!(
+            err.to_string().contains("run `cargo mutants` first"),
+            "{err}"
+        );
+
+        fs::write(dir.path().join("missed.txt"), "not a mutant\n").unwrap();
+        let err = Outcomes::load(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("line 1 of missed.txt"), "{err}");
+    }
+}
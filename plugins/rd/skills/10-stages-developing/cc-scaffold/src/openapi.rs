@@ -0,0 +1,1531 @@
+//! Rust clients generated from [OpenAPI] 3 documents, for
+//! `cc-scaffold from-openapi`.
+//!
+//! [`Api::load`] reads a document, YAML or JSON, into the models and
+//! endpoint groups of a client. An object schema under `components/schemas`
+//! becomes a struct, a string enum an enum, and any other schema a type
+//! alias; an enum declared inline on a property becomes an enum named after
+//! the struct and the property. Each operation becomes a method, named after
+//! its `operationId`, on the group of its first tag or, untagged, of the
+//! first segment of its path. Path and query parameters become arguments, a
+//! JSON request body a `&Model` argument, and the JSON body of the first
+//! 2xx response the return type.
+//!
+//! [`Api::render`] renders them with the `rust/openapi-client` templates:
+//! their `client.rs` extends the `rust/rest-client` one, overriding the
+//! groups it lists, and `error.rs` is the rest-client one as it is.
+//!
+//! Schemas the generator cannot express as a type, such as `oneOf` or an
+//! inline object with properties, become `serde_json::Value`; header and
+//! cookie parameters, array or object query parameters and request bodies
+//! that are not JSON are skipped. Each is reported in [`Api::warnings`].
+//!
+//! [OpenAPI]: https://spec.openapis.org/oas/v3.1.0
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::generate::{self, RenderedFile};
+use crate::locator::Locator;
+use crate::template_engine::filters;
+use crate::template_engine::{Context, Value};
+
+/// Templates [`Api::render`] renders, with the file each is written to
+/// under the output directory. Groups are rendered from
+/// [`GROUP_TEMPLATE`], once each.
+const TEMPLATES: &[(&str, &str)] = &[
+    ("rust/openapi-client/mod", "mod.rs"),
+    ("rust/openapi-client/client", "client.rs"),
+    ("rust/rest-client/error", "error.rs"),
+    ("rust/openapi-client/models", "models.rs"),
+];
+
+const GROUP_TEMPLATE: &str = "rust/openapi-client/group";
+
+/// Module names the generated client uses for itself.
+const RESERVED_MODULES: &[&str] = &["client", "error", "models"];
+
+/// Methods of the generated client a group accessor cannot be named.
+const RESERVED_METHODS: &[&str] = &["new", "request"];
+
+/// Names the generated code uses unqualified next to the models.
+const RESERVED_TYPES: &[&str] = &[
+    "ApiError", "Box", "HashMap", "Method", "Option", "Result", "String", "Vec",
+];
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// The type schemas the generator cannot express map to.
+const ANY: &str = "serde_json::Value";
+
+/// An API read from an OpenAPI document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Api {
+    /// `info.title`.
+    pub title: String,
+    /// `info.version`, the version of the API rather than of OpenAPI.
+    pub version: String,
+    /// `info.description`.
+    pub description: Option<String>,
+    /// Types for the schemas, in document order.
+    pub models: Vec<Model>,
+    /// Endpoint groups, sorted by module name.
+    pub groups: Vec<Group>,
+    /// What the document uses that the generated client leaves out or
+    /// approximates.
+    pub warnings: Vec<String>,
+}
+
+/// A type generated for a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Model {
+    /// The type's name, in PascalCase.
+    pub name: String,
+    pub description: Option<String>,
+    pub kind: ModelKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelKind {
+    Struct(Vec<Field>),
+    /// A string enum, with its variants.
+    Enum(Vec<Variant>),
+    /// Any other schema, as `type Name = ...;`.
+    Alias(String),
+}
+
+/// A property of an object schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// Field name, in snake case and escaped if it is a keyword.
+    pub name: String,
+    /// Property name in JSON.
+    pub wire: String,
+    /// Rust type, `Option<...>` unless the property is required and not
+    /// nullable.
+    pub ty: String,
+    /// Whether the property may be left out.
+    pub optional: bool,
+    pub description: Option<String>,
+}
+
+/// A value of a string enum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    /// Variant name, in PascalCase.
+    pub name: String,
+    /// The value in JSON.
+    pub wire: String,
+}
+
+/// The operations of one tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    /// The tag, or the path segment the group was named after.
+    pub tag: String,
+    pub description: Option<String>,
+    /// Module, file and accessor method name, in snake case.
+    pub module: String,
+    /// Name of the struct holding the group's methods: the tag in
+    /// PascalCase with an `Api` suffix, so it cannot clash with a model.
+    pub type_name: String,
+    pub operations: Vec<Operation>,
+}
+
+/// One method of a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+    /// Method name, in snake case.
+    pub name: String,
+    /// HTTP method, upper case, as in `reqwest::Method::GET`.
+    pub method: String,
+    /// Path as the document gives it, e.g. `/pets/{petId}`.
+    pub path: String,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    /// Parameters in the path, in the order they appear in it.
+    pub path_params: Vec<Param>,
+    pub query: Vec<Param>,
+    /// Request body argument, of type `&Model`.
+    pub body: Option<Param>,
+    /// Type the JSON response decodes to; `None` if the response is not
+    /// JSON or has no body.
+    pub response: Option<String>,
+}
+
+/// An argument of an [`Operation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    /// Argument name, in snake case and escaped if it is a keyword.
+    pub name: String,
+    /// The parameter's name in the document.
+    pub wire: String,
+    /// Argument type: `&str` for strings, `Option<...>` if not required.
+    pub ty: String,
+    pub required: bool,
+}
+
+impl Api {
+    /// Reads the OpenAPI document at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+        Self::parse(&text).map_err(|message| Error::OpenApi {
+            path: path.to_path_buf(),
+            message,
+        })
+    }
+
+    /// Parses an OpenAPI 3 document. YAML is a superset of JSON, so both are
+    /// read by the YAML parser.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let raw: RawDocument = serde_yaml_ng::from_str(text).map_err(|err| err.to_string())?;
+        if raw.swagger.is_some() {
+            return Err(
+                "Swagger 2.0 documents are not supported; convert it to OpenAPI 3 first"
+                    .to_string(),
+            );
+        }
+        match &raw.openapi {
+            Some(version) if version.starts_with("3.") => {}
+            Some(version) => return Err(format!("unsupported OpenAPI version `{version}`")),
+            None => return Err("missing `openapi` version field".to_string()),
+        }
+        let mut builder = Builder {
+            raw: &raw,
+            models: Vec::new(),
+            warnings: Vec::new(),
+        };
+        builder.models()?;
+        let groups = builder.groups()?;
+        Ok(Api {
+            title: raw.info.title.clone(),
+            version: raw.info.version.clone(),
+            description: raw.info.description.clone(),
+            models: builder.models,
+            groups,
+            warnings: builder.warnings,
+        })
+    }
+
+    /// Renders the client into the module directory `out`, with the client
+    /// struct named `client_name`: `mod.rs`, `client.rs`, `error.rs`,
+    /// `models.rs` and one file per group.
+    pub fn render(
+        &self,
+        locator: &Locator,
+        out: &Path,
+        client_name: &str,
+    ) -> Result<Vec<RenderedFile>> {
+        let mut ctx = Context::new();
+        ctx.set("api", self.to_value(client_name));
+        ctx.set("client_name", client_name);
+        ctx.bind("ApiClient", "client_name");
+        let mut files = Vec::new();
+        for (id, file) in TEMPLATES {
+            let template = locator.resolve(id)?;
+            files.push(generate::render_file(
+                &template.source,
+                &out.join(file),
+                &ctx,
+            )?);
+        }
+        let template = locator.resolve(GROUP_TEMPLATE)?;
+        for (group, value) in self.groups.iter().zip(self.group_values()) {
+            ctx.set("group", value);
+            let destination = out.join(format!("{}.rs", group.module));
+            files.push(generate::render_file(&template.source, &destination, &ctx)?);
+        }
+        Ok(files)
+    }
+
+    /// The API as the `api` variable of the templates.
+    fn to_value(&self, client_name: &str) -> Value {
+        let mut doc = vec![format!("//! {}, version {}.", self.title, self.version)];
+        if let Some(description) = &self.description {
+            doc.push("//!".to_string());
+            doc.extend(doc_lines("//!", description));
+        }
+        let mut modules: Vec<(String, bool)> = RESERVED_MODULES
+            .iter()
+            .map(|&module| (module.to_string(), module == "models"))
+            .chain(self.groups.iter().map(|g| (g.module.clone(), false)))
+            .collect();
+        modules.sort();
+        let exports = self
+            .groups
+            .iter()
+            .map(|g| format!("{}::{}", g.module, g.type_name));
+        let mut client_imports: Vec<String> = std::iter::once("error::ApiError".to_string())
+            .chain(exports.clone())
+            .collect();
+        client_imports.sort();
+        let mut exports: Vec<String> = [
+            format!("client::{client_name}"),
+            "client::{Auth, TIMEOUT}".to_string(),
+            "error::{ApiError, Problem}".to_string(),
+        ]
+        .into_iter()
+        .chain(exports)
+        .collect();
+        exports.sort();
+
+        let mut structs = Vec::new();
+        let mut enums = Vec::new();
+        let mut aliases = Vec::new();
+        for model in &self.models {
+            let description = model
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("The `{}` schema.", model.name));
+            let mut fields = vec![
+                ("name", Value::from(model.name.as_str())),
+                ("doc", Value::from(doc_lines("///", &description))),
+            ];
+            match &model.kind {
+                ModelKind::Struct(properties) => {
+                    let properties: Vec<Value> = properties.iter().map(field_value).collect();
+                    fields.push(("fields", Value::from(properties)));
+                    structs.push(map(fields));
+                }
+                ModelKind::Enum(variants) => {
+                    let variants: Vec<Value> = variants
+                        .iter()
+                        .map(|variant| {
+                            let rename = if variant.name == variant.wire {
+                                String::new()
+                            } else {
+                                format!("{:?}", variant.wire)
+                            };
+                            map([
+                                ("name", Value::from(variant.name.as_str())),
+                                ("rename", Value::from(rename)),
+                            ])
+                        })
+                        .collect();
+                    fields.push(("variants", Value::from(variants)));
+                    enums.push(map(fields));
+                }
+                ModelKind::Alias(ty) => {
+                    fields.push(("type", Value::from(ty.as_str())));
+                    aliases.push(map(fields));
+                }
+            }
+        }
+        map([
+            ("title", Value::from(self.title.as_str())),
+            ("doc", Value::from(doc)),
+            (
+                "modules",
+                Value::from(
+                    modules
+                        .into_iter()
+                        .map(|(name, public)| {
+                            map([("name", Value::from(name)), ("public", Value::from(public))])
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+            ),
+            ("exports", Value::from(exports)),
+            ("client_imports", Value::from(client_imports)),
+            ("structs", Value::from(structs)),
+            ("enums", Value::from(enums)),
+            ("aliases", Value::from(aliases)),
+            ("groups", Value::from(self.group_values())),
+        ])
+    }
+
+    fn group_values(&self) -> Vec<Value> {
+        let models: BTreeSet<&str> = self.models.iter().map(|m| m.name.as_str()).collect();
+        self.groups
+            .iter()
+            .map(|group| {
+                let mut used = BTreeSet::new();
+                for operation in &group.operations {
+                    let types = operation
+                        .path_params
+                        .iter()
+                        .chain(&operation.query)
+                        .chain(&operation.body)
+                        .map(|param| param.ty.as_str())
+                        .chain(operation.response.as_deref());
+                    for ty in types {
+                        used.extend(
+                            ty.split(|c: char| !c.is_alphanumeric() && c != '_')
+                                .filter(|word| models.contains(word)),
+                        );
+                    }
+                }
+                let imports = match used.len() {
+                    0 => String::new(),
+                    1 => used.into_iter().collect(),
+                    _ => format!("{{{}}}", used.into_iter().collect::<Vec<_>>().join(", ")),
+                };
+                let mut doc = Vec::new();
+                if let Some(description) = &group.description {
+                    doc.extend(doc_lines("///", description));
+                    doc.push("///".to_string());
+                }
+                let operations: Vec<Value> = group.operations.iter().map(operation_value).collect();
+                map([
+                    ("tag", Value::from(group.tag.as_str())),
+                    ("doc", Value::from(doc)),
+                    ("module", Value::from(group.module.as_str())),
+                    ("type_name", Value::from(group.type_name.as_str())),
+                    ("imports", Value::from(imports)),
+                    ("operations", Value::from(operations)),
+                ])
+            })
+            .collect()
+    }
+}
+
+fn field_value(field: &Field) -> Value {
+    let mut serde = Vec::new();
+    if field.name.trim_start_matches("r#") != field.wire {
+        serde.push(format!("rename = {:?}", field.wire));
+    }
+    if field.optional {
+        serde.push("default, skip_serializing_if = \"Option::is_none\"".to_string());
+    }
+    let doc = field
+        .description
+        .as_deref()
+        .map(|description| doc_lines("///", description))
+        .unwrap_or_default();
+    map([
+        ("name", Value::from(field.name.as_str())),
+        ("type", Value::from(field.ty.as_str())),
+        ("serde", Value::from(serde.join(", "))),
+        ("doc", Value::from(doc)),
+    ])
+}
+
+fn operation_value(operation: &Operation) -> Value {
+    let mut doc = Vec::new();
+    for text in [&operation.summary, &operation.description]
+        .into_iter()
+        .flatten()
+    {
+        doc.extend(doc_lines("///", text));
+        doc.push("///".to_string());
+    }
+    doc.push(format!("/// `{} {}`.", operation.method, operation.path));
+    let mut args = String::new();
+    for param in operation
+        .path_params
+        .iter()
+        .chain(&operation.body)
+        .chain(&operation.query)
+    {
+        args.push_str(&format!(", {}: {}", param.name, param.ty));
+    }
+    let mut path = operation.path.clone();
+    for param in &operation.path_params {
+        path = path.replace(
+            &format!("{{{}}}", param.wire),
+            &format!("{{{}}}", param.name),
+        );
+    }
+    // Each parameter is percent-encoded as one segment: a `/` or `?` in a
+    // string must not reach another endpoint.
+    let path = if operation.path_params.is_empty() {
+        format!("{path:?}")
+    } else {
+        let segments: String = operation
+            .path_params
+            .iter()
+            .map(|param| format!(", {0} = client::segment({0})", param.name))
+            .collect();
+        format!("&format!({path:?}{segments})")
+    };
+    let query = |required: bool| -> Vec<Value> {
+        operation
+            .query
+            .iter()
+            .filter(|param| param.required == required)
+            .map(|param| {
+                map([
+                    ("name", Value::from(param.name.as_str())),
+                    ("wire", Value::from(format!("{:?}", param.wire))),
+                ])
+            })
+            .collect()
+    };
+    let body = operation.body.as_ref().map(|b| b.name.as_str());
+    map([
+        ("name", Value::from(operation.name.as_str())),
+        ("doc", Value::from(doc)),
+        ("args", Value::from(args)),
+        ("method", Value::from(operation.method.as_str())),
+        ("path", Value::from(path)),
+        ("required_query", Value::from(query(true))),
+        ("optional_query", Value::from(query(false))),
+        ("body", Value::from(body.unwrap_or_default())),
+        (
+            "returns",
+            Value::from(operation.response.as_deref().unwrap_or("()")),
+        ),
+        (
+            "decoder",
+            Value::from(if operation.response.is_some() {
+                "json"
+            } else {
+                "empty"
+            }),
+        ),
+    ])
+}
+
+fn map<'a>(fields: impl IntoIterator<Item = (&'a str, Value)>) -> Value {
+    Value::Map(
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+/// `text` as doc comment lines starting with `prefix`, blank lines as the
+/// bare prefix so none ends in a space.
+fn doc_lines(prefix: &str, text: &str) -> Vec<String> {
+    text.trim()
+        .lines()
+        .map(|line| match line.trim_end() {
+            "" => prefix.to_string(),
+            line => format!("{prefix} {line}"),
+        })
+        .collect()
+}
+
+/// `name` in snake case as a Rust identifier: keywords are escaped as raw
+/// identifiers, and the names that cannot be get a trailing `_`.
+pub fn field_name(name: &str) -> String {
+    let snake = filters::apply("snake_case", name);
+    let snake = match snake.chars().next() {
+        None => "value".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{snake}"),
+        Some(_) => snake,
+    };
+    match snake.as_str() {
+        "self" | "super" | "crate" => format!("{snake}_"),
+        _ if KEYWORDS.contains(&snake.as_str()) => format!("r#{snake}"),
+        _ => snake,
+    }
+}
+
+/// `name` in PascalCase as a Rust type name.
+pub fn type_name(name: &str) -> String {
+    let pascal = filters::apply("pascal_case", name);
+    match pascal.chars().next() {
+        None => "Value".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{pascal}"),
+        Some(_) if pascal == "Self" => "Self_".to_string(),
+        Some(_) => pascal,
+    }
+}
+
+const SCHEMA_REF: &str = "#/components/schemas/";
+
+/// Turns the raw document into models and groups, collecting warnings.
+struct Builder<'a> {
+    raw: &'a RawDocument,
+    models: Vec<Model>,
+    warnings: Vec<String>,
+}
+
+impl<'a> Builder<'a> {
+    fn models(&mut self) -> Result<(), String> {
+        let raw = self.raw;
+        let mut names: BTreeMap<String, &str> = BTreeMap::new();
+        for (key, _) in &raw.components.schemas.0 {
+            let name = type_name(key);
+            if RESERVED_TYPES.contains(&name.as_str()) {
+                return Err(format!(
+                    "schema `{key}` would be named `{name}`, which the generated code uses itself"
+                ));
+            }
+            if let Some(other) = names.insert(name.clone(), key) {
+                return Err(format!(
+                    "schemas `{other}` and `{key}` are both named `{name}`"
+                ));
+            }
+        }
+        for (key, schema) in &raw.components.schemas.0 {
+            let name = type_name(key);
+            let location = format!("{SCHEMA_REF}{key}");
+            let kind = match string_enum(schema) {
+                Some(values) => ModelKind::Enum(variants(&location, &values)?),
+                None if schema.is_object() && !schema.properties.0.is_empty() => {
+                    ModelKind::Struct(self.fields(&name, &location, schema, &mut names)?)
+                }
+                None => ModelKind::Alias(self.rust_type(&location, schema)),
+            };
+            self.models.push(Model {
+                name,
+                description: schema.description.clone(),
+                kind,
+            });
+        }
+        Ok(())
+    }
+
+    fn fields(
+        &mut self,
+        model: &str,
+        location: &str,
+        schema: &RawSchema,
+        names: &mut BTreeMap<String, &'a str>,
+    ) -> Result<Vec<Field>, String> {
+        let mut fields: Vec<Field> = Vec::new();
+        for (wire, property) in &schema.properties.0 {
+            let location = format!("{location}/properties/{wire}");
+            let name = field_name(wire);
+            if let Some(other) = fields.iter().find(|f| f.name == name) {
+                return Err(format!(
+                    "properties `{}` and `{wire}` of {location} are both named `{name}`",
+                    other.wire
+                ));
+            }
+            let ty = match string_enum(property) {
+                Some(values) => {
+                    let enum_name = format!("{model}{}", type_name(wire));
+                    if names.contains_key(&enum_name) {
+                        return Err(format!(
+                            "the enum of {location} would be named `{enum_name}`, \
+                             which a schema already is"
+                        ));
+                    }
+                    names.insert(enum_name.clone(), "");
+                    let description = property
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| format!("The `{wire}` of a [`{model}`]."));
+                    self.models.push(Model {
+                        name: enum_name.clone(),
+                        description: Some(description),
+                        kind: ModelKind::Enum(variants(&location, &values)?),
+                    });
+                    enum_name
+                }
+                None => self.rust_type(&location, property),
+            };
+            let required = schema.required.contains(wire);
+            let optional = !required || property.is_nullable();
+            fields.push(Field {
+                name,
+                wire: wire.clone(),
+                ty: if optional {
+                    format!("Option<{ty}>")
+                } else {
+                    ty
+                },
+                optional: !required,
+                description: property.description.clone(),
+            });
+        }
+        Ok(fields)
+    }
+
+    /// The Rust type for `schema`, found at `location`.
+    fn rust_type(&mut self, location: &str, schema: &RawSchema) -> String {
+        if let Some(reference) = &schema.reference {
+            return self.reference(location, reference);
+        }
+        if let [only] = schema.all_of.as_slice() {
+            // `allOf: [$ref]` is how OpenAPI 3.0 adds a description to a ref.
+            if let Some(reference) = &only.reference {
+                return self.reference(location, reference);
+            }
+        }
+        if !schema.all_of.is_empty() || !schema.one_of.is_empty() || !schema.any_of.is_empty() {
+            self.warn(format!(
+                "{location} combines schemas with allOf, oneOf or anyOf; generated as {ANY}"
+            ));
+            return ANY.to_string();
+        }
+        match schema.kind() {
+            Some("string") => "String".to_string(),
+            Some("integer") => match schema.format.as_deref() {
+                Some("int32") => "i32",
+                _ => "i64",
+            }
+            .to_string(),
+            Some("number") => match schema.format.as_deref() {
+                Some("float") => "f32",
+                _ => "f64",
+            }
+            .to_string(),
+            Some("boolean") => "bool".to_string(),
+            Some("array") => match &schema.items {
+                Some(items) => format!(
+                    "Vec<{}>",
+                    self.rust_type(&format!("{location}/items"), items)
+                ),
+                None => format!("Vec<{ANY}>"),
+            },
+            Some("object") | None if !schema.properties.0.is_empty() => {
+                self.warn(format!(
+                    "{location} is an inline object; generated as {ANY}, \
+                     move it to components/schemas for a struct"
+                ));
+                ANY.to_string()
+            }
+            Some("object") => match &schema.additional_properties {
+                Some(AdditionalProperties::Schema(values)) => format!(
+                    "std::collections::HashMap<String, {}>",
+                    self.rust_type(&format!("{location}/additionalProperties"), values)
+                ),
+                _ => ANY.to_string(),
+            },
+            _ => ANY.to_string(),
+        }
+    }
+
+    fn reference(&mut self, location: &str, reference: &str) -> String {
+        match reference.strip_prefix(SCHEMA_REF) {
+            Some(key) if self.raw.components.schemas.get(key).is_some() => type_name(key),
+            _ => {
+                self.warn(format!(
+                    "{location} refers to `{reference}`, which is not one of the \
+                     document's schemas; generated as {ANY}"
+                ));
+                ANY.to_string()
+            }
+        }
+    }
+
+    fn warn(&mut self, message: String) {
+        self.warnings.push(message);
+    }
+
+    fn groups(&mut self) -> Result<Vec<Group>, String> {
+        let raw = self.raw;
+        let mut groups: Vec<Group> = Vec::new();
+        for (path, item) in &raw.paths.0 {
+            for (method, operation) in item.operations() {
+                let tag = operation
+                    .tags
+                    .first()
+                    .cloned()
+                    .or_else(|| {
+                        path.split('/')
+                            .find(|segment| !segment.is_empty() && !segment.starts_with('{'))
+                            .map(String::from)
+                    })
+                    .unwrap_or_else(|| "default".to_string());
+                let operation = self.operation(path, method, &item.parameters, operation)?;
+                let index = match groups.iter().position(|g| g.tag == tag) {
+                    Some(index) => index,
+                    None => {
+                        groups.push(self.group(&tag, &groups)?);
+                        groups.len() - 1
+                    }
+                };
+                let group = &mut groups[index];
+                if let Some(other) = group.operations.iter().find(|o| o.name == operation.name) {
+                    return Err(format!(
+                        "`{} {}` and `{} {}` of group `{tag}` are both named `{}`",
+                        other.method, other.path, operation.method, operation.path, operation.name
+                    ));
+                }
+                group.operations.push(operation);
+            }
+        }
+        groups.sort_by(|a, b| a.module.cmp(&b.module));
+        Ok(groups)
+    }
+
+    fn group(&self, tag: &str, groups: &[Group]) -> Result<Group, String> {
+        let module = field_name(tag);
+        if RESERVED_MODULES.contains(&module.as_str())
+            || RESERVED_METHODS.contains(&module.as_str())
+        {
+            return Err(format!(
+                "tag `{tag}` would be module `{module}`, which the generated client uses itself"
+            ));
+        }
+        if let Some(other) = groups.iter().find(|g| g.module == module) {
+            return Err(format!(
+                "tags `{}` and `{tag}` are both module `{module}`",
+                other.tag
+            ));
+        }
+        let description = self
+            .raw
+            .tags
+            .iter()
+            .find(|t| t.name == tag)
+            .and_then(|t| t.description.clone());
+        Ok(Group {
+            tag: tag.to_string(),
+            description,
+            type_name: format!("{}Api", type_name(tag)),
+            module,
+            operations: Vec::new(),
+        })
+    }
+
+    fn operation(
+        &mut self,
+        path: &str,
+        method: &str,
+        shared: &'a [MaybeRef<RawParameter>],
+        raw: &'a RawOperation,
+    ) -> Result<Operation, String> {
+        let method = method.to_uppercase();
+        let location = format!("`{method} {path}`");
+        let mut name = match &raw.operation_id {
+            Some(id) => field_name(id),
+            None => field_name(&format!("{method} {path}")),
+        };
+        if name == "new" {
+            // Taken by the group's constructor.
+            name.push('_');
+        }
+        let components = &self.raw.components;
+        // An operation's parameters override the path's of the same name
+        // and location.
+        let mut parameters: Vec<&'a RawParameter> = Vec::new();
+        for parameter in raw.parameters.iter().chain(shared) {
+            let parameter = resolve(parameter, "parameters", &components.parameters)?;
+            let seen = parameters
+                .iter()
+                .any(|p| p.name == parameter.name && p.location == parameter.location);
+            if !seen {
+                parameters.push(parameter);
+            }
+        }
+
+        let mut path_params = Vec::new();
+        for segment in path.split('{').skip(1) {
+            let Some((wire, _)) = segment.split_once('}') else {
+                continue;
+            };
+            // A path segment is text whatever its schema says, so only
+            // numbers and booleans keep their type.
+            let ty = parameters
+                .iter()
+                .find(|p| p.location == "path" && p.name == wire)
+                .and_then(|p| p.schema.as_ref())
+                .and_then(|schema| self.argument_type(&location, wire, schema))
+                .filter(|ty| !self.is_enum(ty))
+                .unwrap_or_else(|| "&str".to_string());
+            path_params.push(Param {
+                name: field_name(wire),
+                wire: wire.to_string(),
+                ty,
+                required: true,
+            });
+        }
+        let mut query = Vec::new();
+        for parameter in &parameters {
+            match parameter.location.as_str() {
+                "path" => continue,
+                "query" => {}
+                other => {
+                    self.warn(format!(
+                        "{location} takes the {other} parameter `{}`, which is skipped",
+                        parameter.name
+                    ));
+                    continue;
+                }
+            }
+            let ty = match &parameter.schema {
+                Some(schema) => self.argument_type(&location, &parameter.name, schema),
+                None => Some("&str".to_string()),
+            };
+            let Some(ty) = ty else {
+                self.warn(format!(
+                    "{location} takes the query parameter `{}` as an array or object, \
+                     which is skipped",
+                    parameter.name
+                ));
+                continue;
+            };
+            query.push(Param {
+                name: field_name(&parameter.name),
+                wire: parameter.name.clone(),
+                ty: if parameter.required {
+                    ty
+                } else {
+                    format!("Option<{ty}>")
+                },
+                required: parameter.required,
+            });
+        }
+
+        let mut body = None;
+        if let Some(request_body) = &raw.request_body {
+            let request_body = resolve(request_body, "requestBodies", &components.request_bodies)?;
+            match json_schema(&request_body.content) {
+                Some(schema) => {
+                    let ty = self.rust_type(&format!("{location} request body"), schema);
+                    let name = match &schema.reference {
+                        Some(_) if ty != ANY => field_name(&ty),
+                        _ => "body".to_string(),
+                    };
+                    body = Some(Param {
+                        name,
+                        wire: String::new(),
+                        ty: format!("&{ty}"),
+                        required: true,
+                    });
+                }
+                None => self.warn(format!(
+                    "{location} has a request body that is not JSON, which is skipped"
+                )),
+            }
+        }
+        let mut args: Vec<&mut Param> = path_params.iter_mut().chain(&mut body).collect();
+        args.extend(query.iter_mut());
+        let mut taken = BTreeSet::from(["request".to_string()]);
+        for arg in args {
+            while !taken.insert(arg.name.clone()) {
+                arg.name.push('_');
+            }
+        }
+
+        let mut response = None;
+        for (status, raw_response) in &raw.responses.0 {
+            if !status.starts_with('2') {
+                continue;
+            }
+            let raw_response = resolve(raw_response, "responses", &components.responses)?;
+            if let Some(schema) = json_schema(&raw_response.content) {
+                response = Some(self.rust_type(&format!("{location} response {status}"), schema));
+            }
+            break;
+        }
+
+        Ok(Operation {
+            name,
+            method,
+            path: path.to_string(),
+            summary: raw.summary.clone(),
+            description: raw.description.clone(),
+            path_params,
+            query,
+            body,
+            response,
+        })
+    }
+
+    /// The argument type for parameter `name`, or `None` if it is not a
+    /// string, number, boolean or string enum.
+    fn argument_type(&mut self, location: &str, name: &str, schema: &RawSchema) -> Option<String> {
+        let ty = self.rust_type(&format!("{location} parameter `{name}`"), schema);
+        match ty.as_str() {
+            "String" => Some("&str".to_string()),
+            "i32" | "i64" | "f32" | "f64" | "bool" => Some(ty),
+            _ if self.is_enum(&ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    fn is_enum(&self, ty: &str) -> bool {
+        self.models
+            .iter()
+            .any(|m| m.name == ty && matches!(m.kind, ModelKind::Enum(_)))
+    }
+}
+
+/// The component a `$ref` to `#/components/<section>/...` points to in
+/// `components`, or `item` itself if it is inline.
+fn resolve<'a, T>(
+    item: &'a MaybeRef<T>,
+    section: &str,
+    components: &'a BTreeMap<String, MaybeRef<T>>,
+) -> Result<&'a T, String> {
+    let reference = match item {
+        MaybeRef::Inline(item) => return Ok(item),
+        MaybeRef::Ref { reference } => reference,
+    };
+    let key = reference
+        .strip_prefix(&format!("#/components/{section}/"))
+        .ok_or_else(|| format!("unsupported reference `{reference}`"))?;
+    match components.get(key) {
+        Some(MaybeRef::Inline(item)) => Ok(item),
+        Some(MaybeRef::Ref { .. }) => Err(format!("`{reference}` refers to another reference")),
+        None => Err(format!("`{reference}` does not exist")),
+    }
+}
+
+/// The string values of `schema`, if it is a string enum.
+fn string_enum(schema: &RawSchema) -> Option<Vec<String>> {
+    let values = schema.values.as_ref()?;
+    if matches!(schema.kind(), Some(kind) if kind != "string") {
+        return None;
+    }
+    values
+        .iter()
+        .filter(|value| !value.is_null())
+        .map(|value| value.as_str().map(String::from))
+        .collect()
+}
+
+fn variants(location: &str, values: &[String]) -> Result<Vec<Variant>, String> {
+    let mut variants: Vec<Variant> = Vec::new();
+    for value in values {
+        let name = type_name(value);
+        if let Some(other) = variants.iter().find(|v| v.name == name) {
+            return Err(format!(
+                "values `{}` and `{value}` of {location} are both named `{name}`",
+                other.wire
+            ));
+        }
+        variants.push(Variant {
+            name,
+            wire: value.clone(),
+        });
+    }
+    Ok(variants)
+}
+
+/// The schema of the JSON media type in `content`.
+fn json_schema(content: &Ordered<RawMedia>) -> Option<&RawSchema> {
+    content
+        .0
+        .iter()
+        .find(|(media, _)| {
+            let media = media.split(';').next().unwrap_or_default().trim();
+            media == "application/json" || media.ends_with("+json")
+        })
+        .and_then(|(_, media)| media.schema.as_ref())
+}
+
+#[derive(Debug, Deserialize)]
+struct RawDocument {
+    openapi: Option<String>,
+    swagger: Option<String>,
+    info: RawInfo,
+    #[serde(default)]
+    tags: Vec<RawTag>,
+    #[serde(default)]
+    paths: Ordered<RawPathItem>,
+    #[serde(default)]
+    components: RawComponents,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawInfo {
+    title: String,
+    version: String,
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawTag {
+    name: String,
+    description: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawComponents {
+    #[serde(default)]
+    schemas: Ordered<RawSchema>,
+    #[serde(default)]
+    parameters: BTreeMap<String, MaybeRef<RawParameter>>,
+    #[serde(default)]
+    request_bodies: BTreeMap<String, MaybeRef<RawRequestBody>>,
+    #[serde(default)]
+    responses: BTreeMap<String, MaybeRef<RawResponse>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawPathItem {
+    #[serde(default)]
+    parameters: Vec<MaybeRef<RawParameter>>,
+    get: Option<RawOperation>,
+    put: Option<RawOperation>,
+    post: Option<RawOperation>,
+    delete: Option<RawOperation>,
+    options: Option<RawOperation>,
+    head: Option<RawOperation>,
+    patch: Option<RawOperation>,
+    trace: Option<RawOperation>,
+}
+
+impl RawPathItem {
+    fn operations(&self) -> impl Iterator<Item = (&'static str, &RawOperation)> {
+        [
+            ("get", &self.get),
+            ("put", &self.put),
+            ("post", &self.post),
+            ("delete", &self.delete),
+            ("options", &self.options),
+            ("head", &self.head),
+            ("patch", &self.patch),
+            ("trace", &self.trace),
+        ]
+        .into_iter()
+        .filter_map(|(method, operation)| Some((method, operation.as_ref()?)))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawOperation {
+    operation_id: Option<String>,
+    summary: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    parameters: Vec<MaybeRef<RawParameter>>,
+    request_body: Option<MaybeRef<RawRequestBody>>,
+    #[serde(default)]
+    responses: Ordered<MaybeRef<RawResponse>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawParameter {
+    name: String,
+    #[serde(rename = "in")]
+    location: String,
+    #[serde(default)]
+    required: bool,
+    schema: Option<RawSchema>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRequestBody {
+    #[serde(default)]
+    content: Ordered<RawMedia>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawResponse {
+    #[serde(default)]
+    content: Ordered<RawMedia>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMedia {
+    schema: Option<RawSchema>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSchema {
+    #[serde(rename = "$ref")]
+    reference: Option<String>,
+    #[serde(rename = "type")]
+    kind: Option<OneOrMany>,
+    format: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    nullable: bool,
+    #[serde(rename = "enum")]
+    values: Option<Vec<serde_json::Value>>,
+    items: Option<Box<RawSchema>>,
+    #[serde(default)]
+    properties: Ordered<RawSchema>,
+    #[serde(default)]
+    required: Vec<String>,
+    additional_properties: Option<AdditionalProperties>,
+    #[serde(default)]
+    all_of: Vec<RawSchema>,
+    #[serde(default)]
+    one_of: Vec<RawSchema>,
+    #[serde(default)]
+    any_of: Vec<RawSchema>,
+}
+
+impl RawSchema {
+    /// The schema's type, without the `"null"` OpenAPI 3.1 lists next to
+    /// it for a nullable one.
+    fn kind(&self) -> Option<&str> {
+        match self.kind.as_ref()? {
+            OneOrMany::One(kind) => Some(kind),
+            OneOrMany::Many(kinds) => kinds.iter().map(String::as_str).find(|&k| k != "null"),
+        }
+    }
+
+    fn is_nullable(&self) -> bool {
+        self.nullable
+            || matches!(&self.kind, Some(OneOrMany::Many(kinds)) if kinds.iter().any(|k| k == "null"))
+    }
+
+    fn is_object(&self) -> bool {
+        matches!(self.kind(), Some("object") | None)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AdditionalProperties {
+    Schema(Box<RawSchema>),
+    /// `true` or `false`.
+    Allowed(de::IgnoredAny),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MaybeRef<T> {
+    Ref {
+        #[serde(rename = "$ref")]
+        reference: String,
+    },
+    Inline(T),
+}
+
+/// A map that keeps the document's key order, so structs list their fields
+/// and the client its operations as the document does.
+#[derive(Debug)]
+struct Ordered<V>(Vec<(String, V)>);
+
+impl<V> Default for Ordered<V> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<V> Ordered<V> {
+    fn get(&self, key: &str) -> Option<&V> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+}
+
+impl<'de, V: Deserialize<'de>> Deserialize<'de> for Ordered<V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct OrderedVisitor<V>(PhantomData<V>);
+
+        impl<'de, V: Deserialize<'de>> Visitor<'de> for OrderedVisitor<V> {
+            type Value = Ordered<V>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = access.next_entry::<String, V>()? {
+                    entries.push(entry);
+                }
+                Ok(Ordered(entries))
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(Ordered(Vec::new()))
+            }
+        }
+
+        deserializer.deserialize_map(OrderedVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PETSTORE: &str = "\
+openapi: 3.0.3
+info:
+  title: Petstore
+  version: 1.0.0
+tags:
+  - name: pets
+    description: Everything about the pets.
+paths:
+  /pets:
+    get:
+      tags: [pets]
+      operationId: listPets
+      summary: List all pets.
+      parameters:
+        - {name: limit, in: query, schema: {type: integer, format: int32}}
+        - {name: status, in: query, required: true, schema: {$ref: '#/components/schemas/Status'}}
+        - {name: X-Request-Id, in: header, schema: {type: string}}
+      responses:
+        '200':
+          description: The pets.
+          content:
+            application/json:
+              schema: {type: array, items: {$ref: '#/components/schemas/Pet'}}
+    post:
+      tags: [pets]
+      operationId: createPet
+      requestBody:
+        content:
+          application/json:
+            schema: {$ref: '#/components/schemas/NewPet'}
+      responses:
+        '201':
+          description: Created.
+          content:
+            application/json:
+              schema: {$ref: '#/components/schemas/Pet'}
+  /pets/{petId}:
+    parameters:
+      - {name: petId, in: path, required: true, schema: {type: integer}}
+    delete:
+      tags: [pets]
+      operationId: deletePet
+      responses:
+        '204': {description: Deleted.}
+  /store/inventory:
+    get:
+      responses:
+        '200':
+          description: Counts by status.
+          content:
+            application/json:
+              schema: {type: object, additionalProperties: {type: integer, format: int32}}
+components:
+  schemas:
+    Pet:
+      type: object
+      required: [id, name]
+      properties:
+        id: {type: integer, format: int64}
+        name: {type: string, description: The pet's name.}
+        type: {type: string}
+        status: {type: string, enum: [available, sold]}
+        ownerId: {type: string, nullable: true}
+    NewPet:
+      type: object
+      required: [name]
+      properties:
+        name: {type: string}
+    Status:
+      type: string
+      enum: [active, archived]
+    Tags:
+      type: array
+      items: {type: string}
+";
+
+    fn parse(text: &str) -> Api {
+        Api::parse(text).unwrap()
+    }
+
+    fn model<'a>(api: &'a Api, name: &str) -> &'a ModelKind {
+        &api.models.iter().find(|m| m.name == name).unwrap().kind
+    }
+
+    #[test]
+    fn test_parse_models() {
+        let api = parse(PETSTORE);
+        assert_eq!(
+            (api.title.as_str(), api.version.as_str()),
+            ("Petstore", "1.0.0")
+        );
+        let names: Vec<&str> = api.models.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["PetStatus", "Pet", "NewPet", "Status", "Tags"]);
+
+        let ModelKind::Struct(fields) = model(&api, "Pet") else {
+            panic!("Pet is a struct");
+        };
+        let fields: Vec<(&str, &str, &str, bool)> = fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.wire.as_str(), f.ty.as_str(), f.optional))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("id", "id", "i64", false),
+                ("name", "name", "String", false),
+                ("r#type", "type", "Option<String>", true),
+                ("status", "status", "Option<PetStatus>", true),
+                ("owner_id", "ownerId", "Option<String>", true),
+            ]
+        );
+        let ModelKind::Enum(variants) = model(&api, "Status") else {
+            panic!("Status is an enum");
+        };
+        assert_eq!(variants[1].name, "Archived");
+        assert_eq!(variants[1].wire, "archived");
+        assert_eq!(
+            model(&api, "Tags"),
+            &ModelKind::Alias("Vec<String>".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_groups() {
+        let api = parse(PETSTORE);
+        let modules: Vec<(&str, &str)> = api
+            .groups
+            .iter()
+            .map(|g| (g.module.as_str(), g.type_name.as_str()))
+            .collect();
+        assert_eq!(modules, [("pets", "PetsApi"), ("store", "StoreApi")]);
+        let pets = &api.groups[0];
+        assert_eq!(
+            pets.description.as_deref(),
+            Some("Everything about the pets.")
+        );
+
+        let list = &pets.operations[0];
+        assert_eq!(
+            (list.name.as_str(), list.method.as_str()),
+            ("list_pets", "GET")
+        );
+        let query: Vec<(&str, &str)> = list
+            .query
+            .iter()
+            .map(|p| (p.name.as_str(), p.ty.as_str()))
+            .collect();
+        assert_eq!(query, [("limit", "Option<i32>"), ("status", "Status")]);
+        assert_eq!(list.response.as_deref(), Some("Vec<Pet>"));
+
+        let create = &pets.operations[1];
+        let body = create.body.as_ref().unwrap();
+        assert_eq!(
+            (body.name.as_str(), body.ty.as_str()),
+            ("new_pet", "&NewPet")
+        );
+        assert_eq!(create.response.as_deref(), Some("Pet"));
+
+        let delete = &pets.operations[2];
+        assert_eq!(delete.path_params[0].name, "pet_id");
+        assert_eq!(delete.path_params[0].ty, "i64");
+        assert_eq!(delete.response, None);
+
+        let inventory = &api.groups[1].operations[0];
+        assert_eq!(inventory.name, "get_store_inventory");
+        assert_eq!(
+            inventory.response.as_deref(),
+            Some("std::collections::HashMap<String, i32>")
+        );
+        assert_eq!(
+            api.warnings,
+            ["`GET /pets` takes the header parameter `X-Request-Id`, which is skipped"]
+        );
+    }
+
+    #[test]
+    fn test_parse_json_with_component_refs() {
+        let api = parse(
+            r##"{
+              "openapi": "3.1.0",
+              "info": {"title": "Notes", "version": "2"},
+              "paths": {"/notes/{id}": {"put": {
+                "operationId": "updateNote",
+                "parameters": [{"$ref": "#/components/parameters/Id"}],
+                "requestBody": {"$ref": "#/components/requestBodies/Note"},
+                "responses": {"200": {"$ref": "#/components/responses/Note"}}
+              }}},
+              "components": {
+                "schemas": {"Note": {"type": "object", "properties": {
+                  "body": {"type": ["string", "null"]},
+                  "extra": {"oneOf": [{"type": "string"}, {"type": "integer"}]}
+                }}},
+                "parameters": {"Id": {"name": "id", "in": "path", "required": true,
+                                      "schema": {"type": "string"}}},
+                "requestBodies": {"Note": {"content": {"application/json":
+                  {"schema": {"$ref": "#/components/schemas/Note"}}}}},
+                "responses": {"Note": {"description": "The note.", "content":
+                  {"application/merge-patch+json": {"schema": {"$ref": "#/components/schemas/Note"}}}}}
+              }
+            }"##,
+        );
+        let operation = &api.groups[0].operations[0];
+        assert_eq!(operation.path_params[0].ty, "&str");
+        assert_eq!(operation.body.as_ref().unwrap().ty, "&Note");
+        assert_eq!(operation.response.as_deref(), Some("Note"));
+        let ModelKind::Struct(fields) = model(&api, "Note") else {
+            panic!("Note is a struct");
+        };
+        assert_eq!(fields[0].ty, "Option<String>");
+        assert_eq!(fields[1].ty, "Option<serde_json::Value>");
+        assert_eq!(api.warnings.len(), 1);
+        assert!(api.warnings[0].starts_with("#/components/schemas/Note/properties/extra combines"));
+    }
+
+    #[test]
+    fn test_parse_rejects_what_it_cannot_generate() {
+        let err = |text: &str| Api::parse(text).unwrap_err();
+        assert!(err("swagger: '2.0'\ninfo: {title: x, version: '1'}\n").starts_with("Swagger 2.0"));
+        assert_eq!(
+            err("openapi: 2.5.0\ninfo: {title: x, version: '1'}\n"),
+            "unsupported OpenAPI version `2.5.0`"
+        );
+        assert!(err("openapi: 3.0.0\ninfo: [\n").contains("line"));
+
+        let document = |paths: &str, schemas: &str| {
+            format!(
+                "openapi: 3.0.0\ninfo: {{title: x, version: '1'}}\n\
+                 paths: {paths}\ncomponents: {{schemas: {schemas}}}\n"
+            )
+        };
+        assert_eq!(
+            err(&document("{}", "{user_id: {}, UserId: {}}")),
+            "schemas `user_id` and `UserId` are both named `UserId`"
+        );
+        assert!(err(&document("{}", "{Result: {}}")).contains("which the generated code uses"));
+        assert_eq!(
+            err(&document("{/models: {get: {responses: {}}}}", "{}")),
+            "tag `models` would be module `models`, which the generated client uses itself"
+        );
+        assert!(err(&document(
+            "{/a: {get: {operationId: a, tags: [t]}}, /b: {get: {operationId: a, tags: [t]}}}",
+            "{}"
+        ))
+        .ends_with("of group `t` are both named `a`"));
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(field_name("petId"), "pet_id");
+        assert_eq!(field_name("type"), "r#type");
+        assert_eq!(field_name("self"), "self_");
+        assert_eq!(field_name("2fa"), "_2fa");
+        assert_eq!(type_name("pet-owner"), "PetOwner");
+        assert_eq!(type_name("3d_model"), "_3dModel");
+    }
+
+    #[test]
+    fn test_to_value_sorts_modules_and_exports() {
+        let mut api = parse(PETSTORE);
+        api.groups[0].module = "accounts".to_string();
+        api.groups[0].type_name = "AccountsApi".to_string();
+        let value = api.to_value("PetstoreClient");
+        let strings = |field: &str| match value.field(field) {
+            Some(Value::List(items)) => items.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            _ => panic!("`{field}` is a list"),
+        };
+        assert_eq!(
+            strings("client_imports"),
+            [
+                "accounts::AccountsApi",
+                "error::ApiError",
+                "store::StoreApi"
+            ]
+        );
+        assert_eq!(
+            strings("exports"),
+            [
+                "accounts::AccountsApi",
+                "client::PetstoreClient",
+                "client::{Auth, TIMEOUT}",
+                "error::{ApiError, Problem}",
+                "store::StoreApi"
+            ]
+        );
+        let modules: Vec<String> = strings("modules");
+        assert_eq!(modules[0], "{name: accounts, public: false}");
+        assert_eq!(modules[3], "{name: models, public: true}");
+    }
+}
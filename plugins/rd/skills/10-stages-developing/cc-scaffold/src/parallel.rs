@@ -0,0 +1,94 @@
+//! Running independent per-file work, such as rendering and writing the
+//! files of a bundle, on several threads.
+//!
+//! The work runs on a `rayon` pool of [`jobs`] threads, built on first use.
+//! Its threads spend most of their time waiting on the filesystem, so
+//! [`JOBS_ENV`] can cap them where that is slow to share, e.g. on a
+//! network filesystem.
+
+use std::num::NonZeroUsize;
+use std::sync::OnceLock;
+use std::thread;
+
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::error::Result;
+
+/// Environment variable capping the number of worker threads; `1` makes
+/// everything serial.
+pub const JOBS_ENV: &str = "CC_SCAFFOLD_JOBS";
+
+/// Applies `task` to every item on up to [`jobs`] threads and returns the
+/// results in item order. Every item is processed even if some fail, and
+/// the error returned is that of the first failing item, so what is
+/// reported does not depend on which thread finished first.
+pub fn map<T, R>(items: Vec<T>, task: impl Fn(T) -> Result<R> + Sync) -> Result<Vec<R>>
+where
+    T: Send,
+    R: Send,
+{
+    let results: Vec<Result<R>> = match pool() {
+        Some(pool) if items.len() > 1 => {
+            pool.install(|| items.into_par_iter().map(&task).collect())
+        }
+        _ => items.into_iter().map(&task).collect(),
+    };
+    results.into_iter().collect()
+}
+
+/// Number of worker threads: [`JOBS_ENV`] if set to a positive number,
+/// else the available parallelism.
+pub fn jobs() -> usize {
+    std::env::var(JOBS_ENV)
+        .ok()
+        .and_then(|jobs| jobs.trim().parse::<NonZeroUsize>().ok())
+        .or_else(|| thread::available_parallelism().ok())
+        .map_or(1, NonZeroUsize::get)
+}
+
+/// The pool [`map`] runs on, or `None` to run serially: with a single job,
+/// or if the pool cannot start its threads.
+fn pool() -> Option<&'static ThreadPool> {
+    static POOL: OnceLock<Option<ThreadPool>> = OnceLock::new();
+    POOL.get_or_init(|| {
+        let threads = jobs();
+        if threads <= 1 {
+            return None;
+        }
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("cc-scaffold-{index}"))
+            .build()
+            .ok()
+    })
+    .as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn test_map_keeps_order_and_reports_first_error() {
+        let doubled = map((0..64).collect(), |n: u64| Ok(n * 2)).unwrap();
+        assert_eq!(doubled, (0..64).map(|n| n * 2).collect::<Vec<_>>());
+
+        // Later items fail faster, but the first failing item is reported.
+        let err = map((0..16).collect(), |n: u64| {
+            thread::sleep(std::time::Duration::from_millis(16 - n));
+            if n % 5 == 3 {
+                Err(Error::InvalidAssignment(n.to_string()))
+            } else {
+                Ok(n)
+            }
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            Error::InvalidAssignment("3".into()).to_string()
+        );
+        assert!(map(Vec::<u8>::new(), Ok).unwrap().is_empty());
+    }
+}
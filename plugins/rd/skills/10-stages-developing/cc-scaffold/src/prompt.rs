@@ -0,0 +1,160 @@
+//! Interactive prompting for variables not supplied on the command line.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+
+use crate::error::{Error, Result};
+use crate::variables::{EnvAccess, VariableSpec};
+
+/// Asks for every variable in `specs` that has no entry in `values`,
+/// storing the answers back into `values`.
+///
+/// Each prompt shows the variable's description, type and default, with a
+/// derived default rendered from the answers before it and the environment
+/// `env` allows. An empty
+/// answer accepts the default; invalid answers are explained and asked
+/// again. End of input while a required variable is still unanswered turns
+/// into [`Error::MissingVariable`].
+pub fn fill_missing<R: BufRead, W: Write>(
+    specs: &[VariableSpec],
+    values: &mut BTreeMap<String, String>,
+    env: &EnvAccess,
+    input: &mut R,
+    output: &mut W,
+) -> Result<()> {
+    for spec in specs {
+        if values.contains_key(&spec.name) {
+            continue;
+        }
+        let default = spec.default_for(values, env)?;
+        let answer = ask(spec, default, input, output)?;
+        values.insert(spec.name.clone(), answer);
+    }
+    Ok(())
+}
+
+fn ask<R: BufRead, W: Write>(
+    spec: &VariableSpec,
+    default: Option<String>,
+    input: &mut R,
+    output: &mut W,
+) -> Result<String> {
+    let io_err = |err| Error::io("<terminal>", err);
+    if let Some(description) = &spec.description {
+        writeln!(output, "{description}").map_err(io_err)?;
+    }
+    loop {
+        match &default {
+            Some(default) => write!(output, "{} ({}) [{default}]: ", spec.name, spec.kind),
+            None => write!(output, "{} ({}): ", spec.name, spec.kind),
+        }
+        .map_err(io_err)?;
+        output.flush().map_err(io_err)?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line).map_err(io_err)? == 0 {
+            writeln!(output).map_err(io_err)?;
+            return default.ok_or_else(|| Error::MissingVariable(spec.name.clone()));
+        }
+        let answer = line.trim_end_matches(['\r', '\n']);
+        if answer.is_empty() {
+            match &default {
+                Some(default) => return Ok(default.clone()),
+                None => {
+                    writeln!(output, "  a value is required").map_err(io_err)?;
+                    continue;
+                }
+            }
+        }
+        match spec.parse_value(answer) {
+            Ok(_) => return Ok(answer.to_string()),
+            Err(reason) => writeln!(output, "  {reason}").map_err(io_err)?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variables::VariableKind;
+    use std::io::Cursor;
+
+    fn specs() -> Vec<VariableSpec> {
+        vec![
+            VariableSpec::new("function_name", VariableKind::Identifier)
+                .description("Name of the function"),
+            VariableSpec::new("retries", VariableKind::Integer).default_value("3"),
+        ]
+    }
+
+    fn run(specs: &[VariableSpec], answers: &str) -> (Result<BTreeMap<String, String>>, String) {
+        let mut values = BTreeMap::new();
+        let mut output = Vec::new();
+        let result = fill_missing(
+            specs,
+            &mut values,
+            &EnvAccess::All,
+            &mut Cursor::new(answers),
+            &mut output,
+        );
+        (result.map(|()| values), String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_prompts_with_description_type_and_default() {
+        let (values, output) = run(&specs(), "parse\n\n");
+        let values = values.unwrap();
+        assert_eq!(values["function_name"], "parse");
+        assert_eq!(values["retries"], "3");
+        assert!(output.contains("Name of the function\nfunction_name (identifier): "));
+        assert!(output.contains("retries (integer) [3]: "));
+    }
+
+    #[test]
+    fn test_reprompts_on_invalid_and_empty_answers() {
+        let (values, output) = run(&specs(), "\nmy func\nparse\nlots\n5\n");
+        let values = values.unwrap();
+        assert_eq!(values["function_name"], "parse");
+        assert_eq!(values["retries"], "5");
+        assert!(output.contains("a value is required"));
+        assert!(output.contains("identifier cannot contain whitespace"));
+        assert!(output.contains("expected an integer"));
+    }
+
+    #[test]
+    fn test_derived_default_uses_earlier_answers() {
+        let specs = vec![
+            specs().remove(0),
+            VariableSpec::new("result_type", VariableKind::Identifier)
+                .default_value("{{ function_name | pascal_case }}Result"),
+        ];
+        let (values, output) = run(&specs, "parse_header\n\n");
+        assert_eq!(values.unwrap()["result_type"], "ParseHeaderResult");
+        assert!(output.contains("result_type (identifier) [ParseHeaderResult]: "));
+    }
+
+    #[test]
+    fn test_skips_supplied_values() {
+        let mut values = BTreeMap::from([("function_name".to_string(), "given".to_string())]);
+        let mut output = Vec::new();
+        fill_missing(
+            &specs(),
+            &mut values,
+            &EnvAccess::All,
+            &mut Cursor::new("\n"),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(values["function_name"], "given");
+        assert!(!String::from_utf8(output).unwrap().contains("function_name"));
+    }
+
+    #[test]
+    fn test_end_of_input_uses_default_or_fails() {
+        let (result, _) = run(&specs()[1..], "");
+        assert_eq!(result.unwrap()["retries"], "3");
+
+        let (result, _) = run(&specs(), "");
+        assert!(matches!(result, Err(Error::MissingVariable(name)) if name == "function_name"));
+    }
+}
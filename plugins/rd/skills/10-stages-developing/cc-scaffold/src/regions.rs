@@ -0,0 +1,212 @@
+//! Managed regions: generated sections of a file that regeneration may
+//! replace, leaving the rest of the file to its owner.
+//!
+//! A template marks each region with a pair of comment lines, in whatever
+//! comment syntax the file uses:
+//!
+//! ```text
+//! // cc-agents:begin imports
+//! use crate::error::Result;
+//! // cc-agents:end imports
+//! ```
+//!
+//! When a template with regions is rendered over a file that has them too,
+//! [`merge`] swaps in the new contents of each region and keeps everything
+//! outside them as it is in the file. Regions are matched by name; unnamed
+//! ones by their position among the unnamed regions.
+
+/// Marker opening a managed region.
+pub const BEGIN: &str = "cc-agents:begin";
+/// Marker closing a managed region.
+pub const END: &str = "cc-agents:end";
+
+/// A managed region in some text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// Name given after the markers, or `#<n>` for the n-th unnamed region.
+    pub name: String,
+    /// Byte range of the lines between the markers.
+    pub body: std::ops::Range<usize>,
+}
+
+/// Result of [`merge`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merged {
+    pub contents: String,
+    /// Regions of the rendered template the file does not have, which were
+    /// not added.
+    pub missing: Vec<String>,
+}
+
+/// Finds the managed regions of `text`. Errors name the 1-based line of an
+/// unbalanced or mismatched marker.
+pub fn regions(text: &str) -> Result<Vec<Region>, String> {
+    let mut regions = Vec::new();
+    let mut open: Option<(String, usize, usize)> = None;
+    let mut unnamed = 0;
+    let mut offset = 0;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let number = index + 1;
+        if let Some(name) = marker(line, BEGIN) {
+            if let Some((name, _, at)) = &open {
+                return Err(format!(
+                    "line {number}: region starts inside region `{name}` from line {at}"
+                ));
+            }
+            let name = if name.is_empty() {
+                unnamed += 1;
+                format!("#{unnamed}")
+            } else {
+                name.to_string()
+            };
+            if regions.iter().any(|r: &Region| r.name == name) {
+                return Err(format!("line {number}: region `{name}` is defined twice"));
+            }
+            open = Some((name, offset + line.len(), number));
+        } else if let Some(name) = marker(line, END) {
+            let Some((open_name, start, _)) = open.take() else {
+                return Err(format!("line {number}: `{END}` without `{BEGIN}`"));
+            };
+            if !name.is_empty() && name != open_name {
+                return Err(format!(
+                    "line {number}: `{END} {name}` closes region `{open_name}`"
+                ));
+            }
+            regions.push(Region {
+                name: open_name,
+                body: start..offset,
+            });
+        }
+        offset += line.len();
+    }
+    if let Some((name, _, at)) = open {
+        return Err(format!("line {at}: region `{name}` is never closed"));
+    }
+    Ok(regions)
+}
+
+/// Whether `text` has managed regions.
+pub fn has_regions(text: &str) -> bool {
+    regions(text).is_ok_and(|r| !r.is_empty())
+}
+
+/// The managed parts of `text`, for telling whether they were edited: the
+/// regions' names and contents, or all of `text` if it has none.
+pub fn managed(text: &str) -> String {
+    match regions(text) {
+        Ok(regions) if !regions.is_empty() => regions
+            .iter()
+            .map(|r| format!("{}\n{}", r.name, &text[r.body.clone()]))
+            .collect(),
+        _ => text.to_string(),
+    }
+}
+
+/// Replaces the regions of `existing` with those of `rendered`. Returns
+/// `None` unless both have regions, in which case the caller decides what
+/// replacing the whole file means.
+pub fn merge(existing: &str, rendered: &str) -> Result<Option<Merged>, String> {
+    let new = regions(rendered).map_err(|err| format!("rendered template: {err}"))?;
+    if new.is_empty() {
+        return Ok(None);
+    }
+    let old = regions(existing)?;
+    if old.is_empty() {
+        return Ok(None);
+    }
+    let mut contents = String::with_capacity(existing.len());
+    let mut copied = 0;
+    for region in &old {
+        contents.push_str(&existing[copied..region.body.start]);
+        let body = new
+            .iter()
+            .find(|r| r.name == region.name)
+            .map_or(&existing[region.body.clone()], |r| {
+                &rendered[r.body.clone()]
+            });
+        contents.push_str(body);
+        copied = region.body.end;
+    }
+    contents.push_str(&existing[copied..]);
+    let missing = new
+        .iter()
+        .filter(|r| !old.iter().any(|o| o.name == r.name))
+        .map(|r| r.name.clone())
+        .collect();
+    Ok(Some(Merged { contents, missing }))
+}
+
+/// The name after `keyword` if `line` is a marker line, without comment
+/// closers such as `-->` or `*/`.
+fn marker<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    let at = line.find(keyword)?;
+    let rest = &line[at + keyword.len()..];
+    if rest.starts_with(|c: char| !c.is_whitespace()) {
+        return None;
+    }
+    let rest = rest.trim();
+    let rest = rest
+        .strip_suffix("-->")
+        .or_else(|| rest.strip_suffix("*/"))
+        .unwrap_or(rest);
+    Some(rest.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regions() {
+        let text = "a\n// cc-agents:begin imports\nuse x;\n// cc-agents:end imports\n\
+                    <!-- cc-agents:begin -->\nb\n<!-- cc-agents:end -->\n";
+        let found = regions(text).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].name, "imports");
+        assert_eq!(&text[found[0].body.clone()], "use x;\n");
+        assert_eq!(found[1].name, "#1");
+        assert_eq!(&text[found[1].body.clone()], "b\n");
+
+        for (text, message) in [
+            (
+                "// cc-agents:begin a\n",
+                "line 1: region `a` is never closed",
+            ),
+            ("// cc-agents:end\n", "line 1: `cc-agents:end` without"),
+            (
+                "# cc-agents:begin a\n# cc-agents:begin b\n",
+                "line 2: region starts inside region `a`",
+            ),
+            (
+                "# cc-agents:begin a\n# cc-agents:end b\n",
+                "line 2: `cc-agents:end b` closes region `a`",
+            ),
+            (
+                "# cc-agents:begin a\n# cc-agents:end\n# cc-agents:begin a\n# cc-agents:end\n",
+                "line 3: region `a` is defined twice",
+            ),
+        ] {
+            let err = regions(text).unwrap_err();
+            assert!(err.contains(message), "{text:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_merge_keeps_hand_written_code() {
+        let existing = "// mine\nfn helper() {}\n// cc-agents:begin api\nfn old() {}\n\
+                        // cc-agents:end api\nfn also_mine() {}\n";
+        let rendered = "// cc-agents:begin api\nfn new() {}\n// cc-agents:end api\n\
+                        // cc-agents:begin extra\n// cc-agents:end extra\n";
+        let merged = merge(existing, rendered).unwrap().unwrap();
+        assert_eq!(
+            merged.contents,
+            "// mine\nfn helper() {}\n// cc-agents:begin api\nfn new() {}\n\
+             // cc-agents:end api\nfn also_mine() {}\n"
+        );
+        assert_eq!(merged.missing, ["extra"]);
+        assert_eq!(merge("plain\n", rendered).unwrap(), None);
+        assert_eq!(merge(existing, "plain\n").unwrap(), None);
+        assert_eq!(managed(existing), "api\nfn old() {}\n");
+        assert_eq!(managed("plain\n"), "plain\n");
+    }
+}
@@ -0,0 +1,221 @@
+//! Installing templates into the user registry.
+//!
+//! The registry (see [`locator::user_templates_dir`]) is an ordinary
+//! templates root laid out as `<language>/<files>`. It is searched before
+//! the built-in templates, so a registered template with the same
+//! identifier as a built-in one replaces it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::locator;
+use crate::manifest::{self, Manifest};
+use crate::verify;
+
+/// Copies the templates at `source` into `registry`, returning the files
+/// written.
+///
+/// `source` may be:
+///
+/// * a manifest, which is installed with its template file (and their
+///   signatures, if any) under the manifest's language,
+/// * a template file, via the manifest next to it that names it, or
+/// * a templates root, whose whole tree is merged into the registry so shared
+///   partials and base templates come along.
+///
+/// Every manifest is validated before anything is written. Existing files
+/// are only replaced when `overwrite` is set.
+pub fn add(source: &Path, registry: &Path, overwrite: bool) -> Result<Vec<PathBuf>> {
+    let copies = if source.is_dir() {
+        tree(source, registry)?
+    } else if manifest::is_manifest(source) {
+        single(Manifest::load(source)?, registry)?
+    } else if source.is_file() {
+        let dir = source.parent().unwrap_or(Path::new("."));
+        match locator::manifest_for(dir, source)? {
+            Some(manifest) => single(manifest, registry)?,
+            None => {
+                return Err(registry_error(
+                    source,
+                    "no manifest names this file; add its manifest instead",
+                ))
+            }
+        }
+    } else {
+        return Err(registry_error(source, "no such file or directory"));
+    };
+    if !overwrite {
+        if let Some((_, to)) = copies.iter().find(|(_, to)| to.exists()) {
+            return Err(Error::DestinationExists(to.clone()));
+        }
+    }
+    let mut written = Vec::with_capacity(copies.len());
+    for (from, to) in copies {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).map_err(|err| Error::io(parent, err))?;
+        }
+        fs::copy(&from, &to).map_err(|err| Error::io(&from, err))?;
+        written.push(to);
+    }
+    Ok(written)
+}
+
+/// Plans copying a manifest and its template files into `<registry>/<language>/`.
+fn single(manifest: Manifest, registry: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    if let Some(base) = &manifest.extends {
+        return Err(registry_error(
+            &manifest.path,
+            &format!("it extends `{base}`; add the directory containing both instead"),
+        ));
+    }
+    let dir = manifest.path.parent().unwrap_or(Path::new("."));
+    let target = registry.join(&manifest.language);
+    let name = manifest
+        .path
+        .file_name()
+        .expect("manifest paths name a file");
+    let mut copies = vec![(manifest.path.clone(), target.join(name))];
+    let sources = std::iter::once(&manifest.file).chain(manifest.files.iter().map(|f| &f.template));
+    for source in sources {
+        if !source.is_file() {
+            return Err(registry_error(
+                &manifest.path,
+                &format!("template file {} does not exist", source.display()),
+            ));
+        }
+        let file = source.strip_prefix(dir).map_err(|_| {
+            registry_error(
+                &manifest.path,
+                "the template file is outside the manifest's directory",
+            )
+        })?;
+        let copy = (source.clone(), target.join(file));
+        if !copies.contains(&copy) {
+            copies.push(copy);
+        }
+    }
+    // Detached signatures travel with the files they sign.
+    for (from, to) in copies.clone() {
+        let signature = verify::signature_path(&from);
+        if signature.is_file() {
+            copies.push((signature, verify::signature_path(&to)));
+        }
+    }
+    Ok(copies)
+}
+
+/// Plans copying every file under `root` to the same place under `registry`.
+fn tree(root: &Path, registry: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    Manifest::discover(root)?;
+    let mut copies = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir).map_err(|err| Error::io(&dir, err))?;
+        for entry in entries {
+            let path = entry.map_err(|err| Error::io(&dir, err))?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                let relative = path.strip_prefix(root).expect("walked from root");
+                copies.push((path.clone(), registry.join(relative)));
+            }
+        }
+    }
+    if copies.is_empty() {
+        return Err(registry_error(root, "the directory is empty"));
+    }
+    copies.sort();
+    Ok(copies)
+}
+
+fn registry_error(path: &Path, message: &str) -> Error {
+    Error::Registry {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Locator;
+
+    fn source() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("rust/partials")).unwrap();
+        fs::write(
+            dir.path().join("rust/private.template.toml"),
+            "name = \"private\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+             description = \"d\"\nfile = \"private-template.rs\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("rust/private-template.rs"),
+            "{{> partials/banner}}",
+        )
+        .unwrap();
+        fs::write(dir.path().join("rust/partials/banner.rs"), "// banner\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_add_manifest_copies_template() {
+        let src = source();
+        let registry = tempfile::tempdir().unwrap();
+        let written = add(
+            &src.path().join("rust/private.template.toml"),
+            registry.path(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(written.len(), 2);
+        let located = Locator::new(vec![registry.path().to_path_buf()])
+            .resolve("rust/private")
+            .unwrap();
+        assert!(located.manifest.is_some());
+
+        let err = add(
+            &src.path().join("rust/private-template.rs"),
+            registry.path(),
+            false,
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::DestinationExists(_)));
+        add(
+            &src.path().join("rust/private-template.rs"),
+            registry.path(),
+            true,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_add_directory_keeps_layout() {
+        let src = source();
+        let registry = tempfile::tempdir().unwrap();
+        let written = add(src.path(), registry.path(), false).unwrap();
+        assert_eq!(written.len(), 3);
+        assert!(registry.path().join("rust/partials/banner.rs").is_file());
+    }
+
+    #[test]
+    fn test_add_rejects_unregistrable_files() {
+        let src = source();
+        let registry = tempfile::tempdir().unwrap();
+        let err = add(
+            &src.path().join("rust/partials/banner.rs"),
+            registry.path(),
+            false,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("no manifest names this file"));
+
+        fs::write(src.path().join("rust/broken.template.toml"), "name = 1\n").unwrap();
+        assert!(matches!(
+            add(src.path(), registry.path(), false),
+            Err(Error::Manifest { .. })
+        ));
+        assert!(fs::read_dir(registry.path()).unwrap().next().is_none());
+    }
+}
@@ -0,0 +1,435 @@
+//! Templates fetched from git repositories.
+//!
+//! A remote identifier names a repository, a template path inside it and
+//! optionally a ref:
+//!
+//! ```text
+//! github:org/repo//templates/rust/actor
+//! gitlab:org/repo//rust/actor#v1.2.0
+//! git+https://example.com/repo.git//templates/rust/actor#main
+//! github:org/cargo-generate-template#v2
+//! ```
+//!
+//! The `//<path>` part may be left out when the repository is a
+//! cargo-generate template (see [`cargo_generate`]).
+//!
+//! Repositories are shallow-fetched with the `git` command into a cache under
+//! [`locator::agents_home`] and reused until they are older than
+//! [`CACHE_TTL`]. A ref that is a full commit hash never goes stale.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+use crate::cargo_generate;
+use crate::error::{Error, Result};
+use crate::locator::{self, LocatedTemplate, Locator};
+use crate::variables::EnvAccess;
+
+/// How long a fetched branch or tag is reused before fetching it again.
+pub const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// A parsed remote template identifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteTemplate {
+    /// The identifier as written.
+    pub spec: String,
+    /// URL passed to `git fetch`.
+    pub url: String,
+    /// Template identifier relative to the repository root. Empty when the
+    /// repository itself is a cargo-generate template.
+    pub path: String,
+    /// Branch, tag or commit; the remote's default branch when unset.
+    pub reference: Option<String>,
+}
+
+impl RemoteTemplate {
+    /// Parses `spec`, returning `None` when it is not a remote identifier.
+    pub fn parse(spec: &str) -> Result<Option<Self>> {
+        let (url, rest) = if let Some(rest) = spec.strip_prefix("github:") {
+            host_shorthand("https://github.com", spec, rest)?
+        } else if let Some(rest) = spec.strip_prefix("gitlab:") {
+            host_shorthand("https://gitlab.com", spec, rest)?
+        } else if let Some(rest) = spec.strip_prefix("git+") {
+            let scheme_end = rest.find("://").map_or(0, |i| i + 3);
+            match rest[scheme_end..].find("//").map(|i| i + scheme_end) {
+                Some(split) => (rest[..split].to_string(), &rest[split + 2..]),
+                None => match rest.rsplit_once('#') {
+                    Some((url, _)) => (url.to_string(), &rest[url.len()..]),
+                    None => (rest.to_string(), ""),
+                },
+            }
+        } else {
+            return Ok(None);
+        };
+        let (path, reference) = match rest.rsplit_once('#') {
+            Some((path, reference)) if !reference.is_empty() => (path, Some(reference.to_string())),
+            Some(_) => return Err(remote_error(spec, "empty ref after `#`")),
+            None => (rest, None),
+        };
+        let path = path.trim_matches('/');
+        if path.split('/').any(|part| part == "..") {
+            return Err(remote_error(spec, "the template path may not contain `..`"));
+        }
+        let remote = Self {
+            spec: spec.to_string(),
+            url,
+            path: path.to_string(),
+            reference,
+        };
+        remote.check()?;
+        Ok(Some(remote))
+    }
+
+    /// Rejects a URL or ref git could take for an option, and a ref that is
+    /// not a plain branch, tag or commit name: ASCII letters, digits, `.`,
+    /// `_`, `-` and `/`, not starting with `-` or `/` and without `..`.
+    /// Lockfile refs reach [`fetch`] through [`at`](Self::at), so it checks
+    /// again before running git.
+    fn check(&self) -> Result<()> {
+        if self.url.is_empty() || self.url.starts_with('-') {
+            return Err(remote_error(&self.spec, "the URL may not start with `-`"));
+        }
+        let Some(reference) = &self.reference else {
+            return Ok(());
+        };
+        let valid = !reference.starts_with(['-', '/'])
+            && !reference.ends_with(['/', '.'])
+            && !reference.contains("..")
+            && !reference.contains("//")
+            && reference
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b".-_/".contains(&b));
+        if !valid {
+            return Err(remote_error(
+                &self.spec,
+                &format!("`{reference}` is not a branch, tag or commit name"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Directory this repository and ref are cached in, under `cache`.
+    pub fn cache_dir(&self, cache: &Path) -> PathBuf {
+        let key = format!("{}#{}", self.url, self.reference.as_deref().unwrap_or(""));
+        let name = self
+            .url
+            .trim_end_matches('/')
+            .trim_end_matches(".git")
+            .rsplit(['/', ':'])
+            .next()
+            .filter(|n| !n.is_empty())
+            .unwrap_or("repo");
+        cache.join(format!("{name}-{:016x}", fnv1a(key.as_bytes())))
+    }
+
+    /// The same repository and path at `reference`.
+    pub fn at(&self, reference: &str) -> Self {
+        Self {
+            reference: Some(reference.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// Whether the ref names an exact commit, whose contents cannot change.
+    fn is_pinned(&self) -> bool {
+        self.reference
+            .as_deref()
+            .is_some_and(|r| r.len() == 40 && r.bytes().all(|b| b.is_ascii_hexdigit()))
+    }
+}
+
+/// The remote template cache, `cache/git` under [`locator::agents_home`].
+pub fn cache_root() -> Option<PathBuf> {
+    Some(locator::agents_home()?.join("cache/git"))
+}
+
+/// Makes sure `remote` is checked out in `cache`, fetching it when it is
+/// missing, stale, or `refresh` is set, and returns the checkout.
+pub fn fetch(remote: &RemoteTemplate, cache: &Path, refresh: bool) -> Result<PathBuf> {
+    remote.check()?;
+    let dir = remote.cache_dir(cache);
+    let fetched = dir.join(".git/FETCH_HEAD");
+    let fresh = match fs::metadata(&fetched).and_then(|m| m.modified()) {
+        Ok(_) if remote.is_pinned() => true,
+        Ok(modified) => SystemTime::now()
+            .duration_since(modified)
+            .is_ok_and(|age| age < CACHE_TTL),
+        Err(_) => false,
+    };
+    if fresh && !refresh {
+        return Ok(dir);
+    }
+    let initialised = dir.join(".git").is_dir();
+    if !initialised {
+        fs::create_dir_all(&dir).map_err(|err| Error::io(&dir, err))?;
+        git(remote, &dir, &["init", "--quiet"])?;
+    }
+    let reference = remote.reference.as_deref().unwrap_or("HEAD");
+    let result = git(
+        remote,
+        &dir,
+        &[
+            "fetch",
+            "--quiet",
+            "--depth",
+            "1",
+            "--end-of-options",
+            &remote.url,
+            reference,
+        ],
+    )
+    .and_then(|_| {
+        // `checkout` does not take `--end-of-options`; the trailing `--`
+        // makes `FETCH_HEAD` the commit and ends its arguments.
+        git(
+            remote,
+            &dir,
+            &[
+                "checkout",
+                "--quiet",
+                "--force",
+                "--detach",
+                "FETCH_HEAD",
+                "--",
+            ],
+        )
+    });
+    if result.is_err() && !initialised {
+        // Do not leave an empty checkout behind to be mistaken for a cache.
+        let _ = fs::remove_dir_all(&dir);
+    }
+    result.map(|_| dir)
+}
+
+/// The commit checked out in a cache directory returned by [`fetch`].
+pub fn revision(remote: &RemoteTemplate, checkout: &Path) -> Result<String> {
+    git(remote, checkout, &["rev-parse", "HEAD"])
+}
+
+/// Fetches `remote` if needed and resolves its template path inside the
+/// checkout, returning it with the commit it was read from.
+pub fn resolve(remote: &RemoteTemplate, refresh: bool) -> Result<(LocatedTemplate, String)> {
+    let Some(cache) = cache_root() else {
+        return Err(remote_error(
+            &remote.spec,
+            &format!("no home directory for the cache; set {}", locator::HOME_ENV),
+        ));
+    };
+    let checkout = fetch(remote, &cache, refresh)?;
+    let revision = revision(remote, &checkout)?;
+    if remote.path.is_empty() {
+        if !cargo_generate::is_template(&checkout) {
+            return Err(missing_path(&remote.spec));
+        }
+        let template = LocatedTemplate {
+            source: checkout,
+            manifest: None,
+            include_dirs: Vec::new(),
+            env: EnvAccess::All,
+        };
+        return Ok((template, revision));
+    }
+    let template = Locator::new(vec![checkout]).resolve(&remote.path)?;
+    Ok((template, revision))
+}
+
+fn git(remote: &RemoteTemplate, dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        // No `ext::` or other transports that run commands.
+        .env("GIT_ALLOW_PROTOCOL", "file:git:http:https:ssh")
+        .output()
+        .map_err(|err| remote_error(&remote.spec, &format!("cannot run git: {err}")))?;
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(remote_error(
+        &remote.spec,
+        &format!("git {} failed: {}", args[0], stderr.trim()),
+    ))
+}
+
+fn host_shorthand<'a>(host: &str, spec: &str, rest: &'a str) -> Result<(String, &'a str)> {
+    let (repo, path) = match rest.split_once("//") {
+        Some(split) => split,
+        None => match rest.find('#') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        },
+    };
+    if repo.split('/').filter(|part| !part.is_empty()).count() != 2 {
+        return Err(remote_error(spec, "expected `<owner>/<repo>`"));
+    }
+    Ok((format!("{host}/{}.git", repo.trim_matches('/')), path))
+}
+
+fn missing_path(spec: &str) -> Error {
+    remote_error(
+        spec,
+        "expected `//<path>` to a template inside the repository, \
+         which is not itself a cargo-generate template",
+    )
+}
+
+fn remote_error(spec: &str, message: &str) -> Error {
+    Error::Remote {
+        spec: spec.to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// 64-bit FNV-1a, used to give each cached repository a stable directory.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(spec: &str) -> RemoteTemplate {
+        RemoteTemplate::parse(spec).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_parse_shorthands() {
+        let remote = parse("github:org/repo//templates/rust/actor");
+        assert_eq!(remote.url, "https://github.com/org/repo.git");
+        assert_eq!(remote.path, "templates/rust/actor");
+        assert_eq!(remote.reference, None);
+
+        let remote = parse("gitlab:org/repo//rust/actor#v1.2.0");
+        assert_eq!(remote.url, "https://gitlab.com/org/repo.git");
+        assert_eq!(remote.reference.as_deref(), Some("v1.2.0"));
+
+        let remote = parse("github:org/repo#v2");
+        assert_eq!(remote.url, "https://github.com/org/repo.git");
+        assert_eq!(remote.path, "");
+        assert_eq!(remote.reference.as_deref(), Some("v2"));
+
+        assert_eq!(RemoteTemplate::parse("rust/function").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_git_urls() {
+        let remote = parse("git+https://example.com/a/repo.git//rust/actor#main");
+        assert_eq!(remote.url, "https://example.com/a/repo.git");
+        assert_eq!(remote.path, "rust/actor");
+        let remote = parse("git+https://example.com/a/repo.git#main");
+        assert_eq!(remote.url, "https://example.com/a/repo.git");
+        assert_eq!(remote.path, "");
+        let remote = parse("git+file:///tmp/repo//rust/actor");
+        assert_eq!(remote.url, "file:///tmp/repo");
+        assert!(remote
+            .cache_dir(Path::new("cache"))
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("repo-"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for spec in [
+            "github:org",
+            "github:org//rust/x",
+            "github:org/repo//rust/x#",
+            "github:org/repo//../x",
+            "github:org/repo//rust/x#--upload-pack=touch /tmp/pwned",
+            "github:org/repo//rust/x#-v",
+            "github:org/repo//rust/x#main..HEAD",
+            "github:org/repo//rust/x#v1;rm",
+            "git+--upload-pack=sh//rust/x",
+        ] {
+            assert!(
+                matches!(RemoteTemplate::parse(spec), Err(Error::Remote { .. })),
+                "{spec}"
+            );
+        }
+        // A ref read back from a lockfile is checked before git sees it.
+        let locked = parse("github:org/repo//rust/x").at("--upload-pack=touch pwned");
+        let cache = tempfile::tempdir().unwrap();
+        let err = fetch(&locked, cache.path(), false).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("is not a branch, tag or commit name"),
+            "{err}"
+        );
+        assert!(fs::read_dir(cache.path()).unwrap().next().is_none());
+    }
+
+    fn commit(repo: &Path, contents: &str) {
+        fs::write(repo.join("rust/actor.rs"), contents).unwrap();
+        for args in [
+            &["add", "."][..],
+            &[
+                "-c",
+                "user.name=t",
+                "-c",
+                "user.email=t@t",
+                "commit",
+                "-qm",
+                "x",
+            ],
+        ] {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(repo)
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+    }
+
+    #[test]
+    fn test_fetch_caches_until_refreshed() {
+        let repo = tempfile::tempdir().unwrap();
+        fs::create_dir_all(repo.path().join("rust")).unwrap();
+        let status = Command::new("git")
+            .args(["init", "-q"])
+            .arg(repo.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+        commit(repo.path(), "v1\n");
+
+        let cache = tempfile::tempdir().unwrap();
+        let remote = parse(&format!("git+file://{}//rust/actor", repo.path().display()));
+        let checkout = fetch(&remote, cache.path(), false).unwrap();
+        assert_eq!(
+            fs::read_to_string(checkout.join("rust/actor.rs")).unwrap(),
+            "v1\n"
+        );
+
+        commit(repo.path(), "v2\n");
+        fetch(&remote, cache.path(), false).unwrap();
+        assert_eq!(
+            fs::read_to_string(checkout.join("rust/actor.rs")).unwrap(),
+            "v1\n"
+        );
+        fetch(&remote, cache.path(), true).unwrap();
+        assert_eq!(
+            fs::read_to_string(checkout.join("rust/actor.rs")).unwrap(),
+            "v2\n"
+        );
+
+        let missing = parse(&format!(
+            "git+file://{}//rust/actor#no-such-branch",
+            repo.path().display()
+        ));
+        assert!(matches!(
+            fetch(&missing, cache.path(), false),
+            Err(Error::Remote { .. })
+        ));
+        assert!(!missing.cache_dir(cache.path()).exists());
+    }
+}
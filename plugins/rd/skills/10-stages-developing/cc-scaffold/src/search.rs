@@ -0,0 +1,173 @@
+//! Fuzzy search over template manifests.
+//!
+//! A query is split into terms, and each term is scored against the words
+//! of a manifest's name, tags, language, stage and description. Exact
+//! words score highest, then prefixes, near-misses (one or two typos) and
+//! finally in-order subsequences (`fnc` for `function`). Matches in the
+//! name count more than matches in tags, which count more than the rest.
+
+use crate::manifest::Manifest;
+use crate::template_engine::filters;
+
+/// A manifest that matched a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit<'a> {
+    pub manifest: &'a Manifest,
+    /// Number of query terms that matched somewhere.
+    pub matched: usize,
+    /// Relevance; higher is better.
+    pub score: u32,
+}
+
+/// Returns the manifests matching `query`, best first. Templates matching
+/// more of the query's terms always rank above those matching fewer.
+pub fn search<'a>(manifests: &'a [Manifest], query: &str) -> Vec<Hit<'a>> {
+    let terms: Vec<String> = words(query);
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let mut hits: Vec<Hit<'a>> = manifests
+        .iter()
+        .filter_map(|manifest| {
+            let fields = [
+                (3, words(&manifest.name)),
+                (2, manifest.tags.iter().flat_map(|t| words(t)).collect()),
+                (1, words(&manifest.language)),
+                (1, words(&manifest.stage)),
+                (1, words(&manifest.description)),
+            ];
+            let scores: Vec<u32> = terms
+                .iter()
+                .map(|term| {
+                    fields
+                        .iter()
+                        .flat_map(|(weight, words)| {
+                            words.iter().map(move |w| weight * score(term, w))
+                        })
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect();
+            let matched = scores.iter().filter(|&&s| s > 0).count();
+            (matched > 0).then(|| Hit {
+                manifest,
+                matched,
+                score: scores.iter().sum(),
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| {
+        (b.matched, b.score)
+            .cmp(&(a.matched, a.score))
+            .then_with(|| a.manifest.id().cmp(&b.manifest.id()))
+    });
+    hits
+}
+
+fn words(text: &str) -> Vec<String> {
+    filters::words(text)
+        .into_iter()
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Scores one query term against one word, 0 meaning no match.
+fn score(term: &str, word: &str) -> u32 {
+    if term == word {
+        return 10;
+    }
+    if word.starts_with(term) {
+        return 6;
+    }
+    let len = term.chars().count();
+    let distance = edit_distance(term, word);
+    if (len >= 4 && distance <= 1) || (len >= 7 && distance <= 2) {
+        return 4;
+    }
+    if len >= 3 && is_subsequence(term, word) {
+        return 2;
+    }
+    0
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+/// Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn manifest(name: &str, language: &str, tags: &[&str], description: &str) -> Manifest {
+        let text = format!(
+            "name = \"{name}\"\nlanguage = \"{language}\"\nstage = \"5-implementation\"\n\
+             description = \"{description}\"\ntags = {tags:?}\nfile = \"{name}.txt\"\n"
+        );
+        Manifest::parse(
+            &text,
+            Path::new(&format!("{language}/{name}.template.toml")),
+        )
+        .unwrap()
+    }
+
+    fn ids(hits: &[Hit<'_>]) -> Vec<String> {
+        hits.iter().map(|h| h.manifest.id()).collect()
+    }
+
+    #[test]
+    fn test_ranks_name_matches_first() {
+        let manifests = vec![
+            manifest("http-client", "rust", &["network"], "Blocking HTTP client"),
+            manifest(
+                "function",
+                "rust",
+                &["testing"],
+                "Function with an http example",
+            ),
+            manifest("server", "go", &["network"], "HTTP server"),
+        ];
+        let hits = search(&manifests, "http client");
+        assert_eq!(
+            ids(&hits),
+            ["rust/http-client", "go/server", "rust/function"]
+        );
+        assert_eq!(hits[0].matched, 2);
+    }
+
+    #[test]
+    fn test_tolerates_typos_and_abbreviations() {
+        let manifests = vec![
+            manifest("function", "rust", &["testing"], "Documented function"),
+            manifest("makefile", "rust", &["build"], "Makefile"),
+        ];
+        assert_eq!(ids(&search(&manifests, "fucntion")), ["rust/function"]);
+        assert_eq!(ids(&search(&manifests, "fnc")), ["rust/function"]);
+        assert_eq!(ids(&search(&manifests, "test")), ["rust/function"]);
+        assert!(search(&manifests, "database").is_empty());
+        assert!(search(&manifests, "  ").is_empty());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+}
@@ -0,0 +1,651 @@
+//! Compiling templates' output, for `cc-scaffold selftest`.
+//!
+//! A Rust template is rendered with sample values into a scratch crate,
+//! which is then built with `cargo check --all-targets` and tested with
+//! `cargo test`, all features enabled and warnings denied. A template that
+//! declares features is tested again with them disabled, so its fallback
+//! for a build without them is checked too. Each variable
+//! takes its manifest `sample`, else its default, else a value of its type
+//! named after it.
+//!
+//! A template that renders a `Cargo.toml` is the crate. Otherwise the
+//! scratch crate is made up around the rendered files: a single-file
+//! template becomes `src/lib.rs`, and a bundle's top-level modules under
+//! `src/` are declared in a generated `src/lib.rs`. The crate is named
+//! after the `crate_name` variable, if there is one, so bundles that
+//! `use` their own crate build. Either way the dependencies and features
+//! listed in the manifest's `[selftest]` are added, and the crate is kept
+//! out of any enclosing workspace. A template that renders a workspace
+//! root keeps its own `[workspace]`, and the dependencies go to its
+//! `[workspace.dependencies]` for the members to inherit.
+//!
+//! A bundle that injects into existing files is tested on the output of
+//! its `[selftest]` `base` template, rendered with the base's sample
+//! values, with the bundle's snippets injected into it. A bundle that adds
+//! files alongside a base's, such as a migration after its schema, is
+//! tested the same way; the crate takes the base's `crate_name` unless the
+//! bundle has its own.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+use crate::generate;
+use crate::hooks::{self, Diagnostic};
+use crate::locator::{self, LocatedTemplate};
+use crate::lockfile;
+use crate::manifest::{Manifest, MANIFEST_SUFFIX};
+use crate::variables::{EnvAccess, VariableKind, VariableSpec};
+
+/// Package name of a scratch crate whose template has no `crate_name`.
+const DEFAULT_CRATE_NAME: &str = "selftest";
+
+/// What happened to one template.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Outcome {
+    pub template: String,
+    /// The scratch crate.
+    pub dir: PathBuf,
+    #[serde(flatten)]
+    pub verdict: Verdict,
+}
+
+/// Whether a template's output built and passed its tests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Verdict {
+    Passed,
+    /// The template renders no Rust code to build.
+    Skipped {
+        reason: String,
+    },
+    Failed {
+        step: Step,
+        output: String,
+        diagnostics: Vec<Diagnostic>,
+    },
+}
+
+/// The part of a self-test that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Step {
+    #[serde(rename = "render")]
+    Render,
+    #[serde(rename = "cargo check")]
+    Check,
+    #[serde(rename = "cargo test")]
+    Test,
+    #[serde(rename = "cargo test --no-default-features")]
+    TestWithoutFeatures,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Step::Render => "render",
+            Step::Check => "cargo check",
+            Step::Test => "cargo test",
+            Step::TestWithoutFeatures => "cargo test --no-default-features",
+        })
+    }
+}
+
+/// Renders `template` into a scratch crate at `dir`, replacing anything
+/// there, and builds and tests it with its artifacts in `target_dir`.
+pub fn run(template: &LocatedTemplate, dir: &Path, target_dir: &Path) -> Result<Outcome> {
+    let id = template
+        .manifest
+        .as_ref()
+        .map_or_else(|| template.source.display().to_string(), Manifest::id);
+    let features = template
+        .manifest
+        .as_ref()
+        .is_some_and(|manifest| !manifest.selftest.features.is_empty());
+    let verdict = match scaffold(template, dir)? {
+        Some(verdict) => verdict,
+        None => build(dir, target_dir, features)?,
+    };
+    Ok(Outcome {
+        template: id,
+        dir: dir.to_path_buf(),
+        verdict,
+    })
+}
+
+/// The values `manifest`'s template is rendered with.
+pub fn sample_values(manifest: &Manifest) -> BTreeMap<String, String> {
+    manifest
+        .variables
+        .iter()
+        .filter_map(|spec| {
+            let value = match (&spec.sample, &spec.default) {
+                (Some(sample), _) => sample.clone(),
+                (None, Some(_)) => return None,
+                (None, None) => fallback(spec),
+            };
+            Some((spec.name.clone(), value))
+        })
+        .collect()
+}
+
+/// A value of the type of a variable with no sample or default.
+fn fallback(spec: &VariableSpec) -> String {
+    match &spec.kind {
+        VariableKind::String | VariableKind::Identifier => spec.name.clone(),
+        VariableKind::Integer => "0".to_string(),
+        VariableKind::Bool => "false".to_string(),
+        VariableKind::Enum(options) => options.first().cloned().unwrap_or_default(),
+        VariableKind::List { .. } => String::new(),
+    }
+}
+
+/// Writes the scratch crate for `template` to `dir`. Returns the verdict
+/// if there is nothing to build.
+fn scaffold(template: &LocatedTemplate, dir: &Path) -> Result<Option<Verdict>> {
+    let Some(manifest) = &template.manifest else {
+        return Ok(Some(Verdict::Skipped {
+            reason: "no manifest".to_string(),
+        }));
+    };
+    if manifest.files.is_empty() && !hooks::is_rust(&manifest.file) {
+        return Ok(Some(Verdict::Skipped {
+            reason: "renders no Rust source".to_string(),
+        }));
+    }
+    let values = sample_values(manifest);
+    let render_failed = |err: Error| {
+        Some(Verdict::Failed {
+            step: Step::Render,
+            output: err.to_string(),
+            diagnostics: Vec::new(),
+        })
+    };
+    let rendered = generate::render_bundle(template, &crate_output(manifest, dir), &values)
+        .and_then(|files| Ok((base_files(manifest, dir)?, files)));
+    let ((base, base_values), files) = match rendered {
+        Ok(rendered) => rendered,
+        Err(err) => return Ok(render_failed(err)),
+    };
+    if !files.iter().any(|file| hooks::is_rust(&file.destination)) {
+        return Ok(Some(Verdict::Skipped {
+            reason: "renders no Rust source".to_string(),
+        }));
+    }
+    match fs::remove_dir_all(dir) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(Error::io(dir, err)),
+    }
+    // The base's files are written first, for the bundle to inject into.
+    for file in &base {
+        generate::write(file, true)?;
+    }
+    let files = match generate::inject_bundled(manifest, &values, files) {
+        Ok(files) => files,
+        Err(err) => return Ok(render_failed(err)),
+    };
+    let mut crate_files: Vec<_> = base
+        .into_iter()
+        .filter(|b| !files.iter().any(|(f, _)| f.destination == b.destination))
+        .collect();
+    crate_files.extend(files.into_iter().map(|(file, _)| file));
+    // The base's files may `use` the crate by the base's `crate_name`.
+    let mut crate_values = base_values;
+    crate_values.extend(values);
+    write_crate(manifest, dir, &crate_files, &crate_values)?;
+    Ok(None)
+}
+
+/// The files of `manifest`'s `[selftest]` base, if it has one, rendered
+/// with the base's own sample values into the scratch crate at `dir`, and
+/// those values.
+fn base_files(
+    manifest: &Manifest,
+    dir: &Path,
+) -> Result<(Vec<generate::RenderedFile>, BTreeMap<String, String>)> {
+    let Some(name) = &manifest.selftest.base else {
+        return Ok((Vec::new(), BTreeMap::new()));
+    };
+    let path = manifest
+        .path
+        .with_file_name(format!("{name}{MANIFEST_SUFFIX}"));
+    let base = Manifest::load(&path)?;
+    let values = sample_values(&base);
+    let output = crate_output(&base, dir);
+    let template = LocatedTemplate {
+        source: base.file.clone(),
+        include_dirs: locator::include_dirs(&base.file),
+        manifest: Some(base),
+        env: EnvAccess::All,
+    };
+    let files = generate::render_bundle(&template, &output, &values)?;
+    Ok((files, values))
+}
+
+/// Where `manifest`'s template renders to in a scratch crate at `dir`:
+/// `src/lib.rs` for a single file, the crate itself for a bundle.
+pub fn crate_output(manifest: &Manifest, dir: &Path) -> PathBuf {
+    if manifest.files.is_empty() {
+        dir.join("src/lib.rs")
+    } else {
+        dir.to_path_buf()
+    }
+}
+
+/// Writes `files`, rendered from `manifest`'s template with `values` into
+/// [`crate_output`], and makes up the rest of the scratch crate at `dir`
+/// around them. Returns every file written.
+pub fn write_crate(
+    manifest: &Manifest,
+    dir: &Path,
+    files: &[generate::RenderedFile],
+    values: &BTreeMap<String, String>,
+) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::with_capacity(files.len() + 2);
+    for file in files {
+        generate::write(file, true)?;
+        written.push(file.destination.clone());
+    }
+
+    let cargo_toml = dir.join("Cargo.toml");
+    let rendered = files.iter().any(|file| file.destination == cargo_toml);
+    let mut package: toml::Table = if rendered {
+        let text = fs::read_to_string(&cargo_toml).map_err(|err| Error::io(&cargo_toml, err))?;
+        toml::from_str(&text).map_err(|err| Error::Manifest {
+            path: cargo_toml.clone(),
+            message: format!("rendered Cargo.toml is invalid: {err}"),
+        })?
+    } else {
+        let name = values
+            .get("crate_name")
+            .map_or(DEFAULT_CRATE_NAME, String::as_str);
+        let edition = manifest.edition(values, &EnvAccess::All)?;
+        written.push(cargo_toml.clone());
+        generated_manifest(name, edition.as_deref(), files, dir)
+    };
+    package
+        .entry("workspace")
+        .or_insert_with(|| toml::Table::new().into());
+    // A workspace root has no dependencies of its own; its members inherit
+    // them from `[workspace.dependencies]`.
+    let virtual_root = !package.contains_key("package");
+    for (section, extra) in [
+        ("dependencies", &manifest.selftest.dependencies),
+        ("dev-dependencies", &manifest.selftest.dev_dependencies),
+        ("build-dependencies", &manifest.selftest.build_dependencies),
+    ] {
+        if extra.is_empty() {
+            continue;
+        }
+        let parent = match package.get_mut("workspace").and_then(|w| w.as_table_mut()) {
+            Some(workspace) if virtual_root => workspace,
+            _ => &mut package,
+        };
+        let section = if virtual_root {
+            "dependencies"
+        } else {
+            section
+        };
+        let entry = parent
+            .entry(section)
+            .or_insert_with(|| toml::Table::new().into());
+        if let Some(table) = entry.as_table_mut() {
+            table.extend(extra.clone());
+        }
+    }
+    // Features belong to a package; the members of a workspace root
+    // declare their own.
+    if !virtual_root && !manifest.selftest.features.is_empty() {
+        let entry = package
+            .entry("features")
+            .or_insert_with(|| toml::Table::new().into());
+        if let Some(table) = entry.as_table_mut() {
+            table.extend(manifest.selftest.features.clone());
+        }
+    }
+    let text = toml::to_string(&package).expect("TOML tables serialize");
+    fs::write(&cargo_toml, text).map_err(|err| Error::io(&cargo_toml, err))?;
+
+    // A rendered `Cargo.toml` names its own targets.
+    let src = dir.join("src");
+    if !rendered && !src.join("lib.rs").exists() && !src.join("main.rs").exists() {
+        let lib = src.join("lib.rs");
+        let declarations = module_declarations(&src)?;
+        // A bundle of tests or benchmarks alone renders nothing under `src`.
+        fs::create_dir_all(&src).map_err(|err| Error::io(&src, err))?;
+        fs::write(&lib, declarations).map_err(|err| Error::io(&lib, err))?;
+        written.push(lib);
+    }
+    Ok(written)
+}
+
+/// `Cargo.toml` for a scratch crate named `name` that the template does
+/// not provide, on the template's `edition` if it has one, with a
+/// harness-less `[[bench]]` for every rendered benchmark, as the bundles'
+/// benchmarks expect.
+fn generated_manifest(
+    name: &str,
+    edition: Option<&str>,
+    files: &[generate::RenderedFile],
+    dir: &Path,
+) -> toml::Table {
+    let mut package = toml::Table::new();
+    package.insert("name".to_string(), name.into());
+    package.insert("version".to_string(), "0.0.0".into());
+    package.insert("edition".to_string(), edition.unwrap_or("2021").into());
+    package.insert("publish".to_string(), false.into());
+    let mut manifest = toml::Table::new();
+    manifest.insert("package".to_string(), package.into());
+    let benches: Vec<toml::Value> = files
+        .iter()
+        .filter_map(|file| {
+            let relative = file.destination.strip_prefix(dir).ok()?;
+            if !relative.starts_with("benches") || !hooks::is_rust(relative) {
+                return None;
+            }
+            let mut bench = toml::Table::new();
+            bench.insert(
+                "name".to_string(),
+                relative.file_stem()?.to_string_lossy().into_owned().into(),
+            );
+            bench.insert("harness".to_string(), false.into());
+            Some(bench.into())
+        })
+        .collect();
+    if !benches.is_empty() {
+        manifest.insert("bench".to_string(), benches.into());
+    }
+    manifest
+}
+
+/// A `lib.rs` declaring the modules directly under `src`.
+fn module_declarations(src: &Path) -> Result<String> {
+    let mut modules = Vec::new();
+    let entries = match fs::read_dir(src) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(err) => return Err(Error::io(src, err)),
+    };
+    for entry in entries {
+        let path = entry.map_err(|err| Error::io(src, err))?.path();
+        let module = if path.is_dir() && path.join("mod.rs").is_file() {
+            path.file_name()
+        } else if hooks::is_rust(&path) {
+            path.file_stem()
+        } else {
+            None
+        };
+        modules.extend(module.map(|m| m.to_string_lossy().into_owned()));
+    }
+    modules.sort();
+    Ok(modules.iter().map(|m| format!("pub mod {m};\n")).collect())
+}
+
+/// Builds and tests the scratch crate in `dir`, and tests it again with
+/// its features disabled if it declares `features`.
+fn build(dir: &Path, target_dir: &Path, features: bool) -> Result<Verdict> {
+    let steps: &[Step] = if features {
+        &[Step::Check, Step::Test, Step::TestWithoutFeatures]
+    } else {
+        &[Step::Check, Step::Test]
+    };
+    cargo(dir, target_dir, steps)
+}
+
+/// Builds the scratch crate in `dir` with `cargo check`, without testing
+/// it.
+pub fn check(dir: &Path, target_dir: &Path) -> Result<Verdict> {
+    cargo(dir, target_dir, &[Step::Check])
+}
+
+/// Runs the cargo `steps` on the scratch crate in `dir`, stopping at the
+/// first that fails.
+fn cargo(dir: &Path, target_dir: &Path, steps: &[Step]) -> Result<Verdict> {
+    // cargo runs in `dir`, so relative paths would resolve against it.
+    let dir = &lockfile::absolute(dir);
+    let target_dir = lockfile::absolute(target_dir);
+    let mut rustflags = env::var("RUSTFLAGS").unwrap_or_default();
+    rustflags.push_str(" -D warnings");
+    for &step in steps {
+        let args: &[&str] = match step {
+            Step::Render => continue,
+            Step::Check => &[
+                "check",
+                "--all-targets",
+                "--all-features",
+                "--message-format",
+                "short",
+            ],
+            Step::Test => &["test", "--all-features"],
+            Step::TestWithoutFeatures => &["test", "--no-default-features"],
+        };
+        let output = Command::new("cargo")
+            .args(args)
+            .arg("--quiet")
+            .arg("--manifest-path")
+            .arg(dir.join("Cargo.toml"))
+            .current_dir(dir)
+            .env("CARGO_TARGET_DIR", &target_dir)
+            .env("RUSTFLAGS", rustflags.trim())
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|err| Error::Hook {
+                hook: step.to_string(),
+                path: dir.to_path_buf(),
+                diagnostics: Vec::new(),
+                output: format!("cannot run cargo: {err}"),
+            })?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            return Ok(Verdict::Failed {
+                step,
+                diagnostics: hooks::cargo_diagnostics(&stderr),
+                output: format!("{stdout}{stderr}").trim().to_string(),
+            });
+        }
+    }
+    Ok(Verdict::Passed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locator::include_dirs;
+
+    fn located(dir: &Path, manifest: &str, files: &[(&str, &str)]) -> LocatedTemplate {
+        for (name, contents) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        let path = dir.join("widget.template.toml");
+        fs::write(&path, manifest).unwrap();
+        let manifest = Manifest::load(&path).unwrap();
+        LocatedTemplate {
+            source: manifest.file.clone(),
+            include_dirs: include_dirs(&manifest.file),
+            manifest: Some(manifest),
+            env: EnvAccess::All,
+        }
+    }
+
+    #[test]
+    fn test_sample_values_prefer_samples_then_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = located(
+            dir.path(),
+            "name = \"widget\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+             description = \"Widget\"\nfile = \"widget-template.rs\"\n\n\
+             [[variables]]\nname = \"name\"\ntype = \"identifier\"\nsample = \"gizmo\"\n\n\
+             [[variables]]\nname = \"kind\"\ntype = \"enum\"\noptions = [\"a\", \"b\"]\n\n\
+             [[variables]]\nname = \"count\"\ntype = \"integer\"\ndefault = 3\n\n\
+             [[variables]]\nname = \"label\"\n",
+            &[("widget-template.rs", "")],
+        );
+        let values = sample_values(template.manifest.as_ref().unwrap());
+        let expected: BTreeMap<String, String> =
+            [("name", "gizmo"), ("kind", "a"), ("label", "label")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn test_scaffold_builds_crate_around_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = located(
+            &dir.path().join("templates"),
+            "name = \"widget\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+             description = \"Widget\"\n\n\
+             [[variables]]\nname = \"module\"\ntype = \"identifier\"\nsample = \"gizmo\"\n\n\
+             [[variables]]\nname = \"crate_name\"\ntype = \"identifier\"\n\n\
+             [selftest]\ndev-dependencies = { criterion = \"0.5\" }\n\
+             build-dependencies = { cc = \"1\" }\n\
+             features = { fast = [] }\n\n\
+             [[files]]\ntemplate = \"mod-template.rs\"\npath = \"src/{{ module }}/mod.rs\"\n\n\
+             [[files]]\ntemplate = \"bench-template.rs\"\npath = \"benches/{{ module }}.rs\"\n",
+            &[
+                ("mod-template.rs", "pub fn {{ module }}() {}\n"),
+                (
+                    "bench-template.rs",
+                    "use {{ crate_name }}::{{ module }};\nfn main() {}\n",
+                ),
+            ],
+        );
+        let out = dir.path().join("scratch");
+        fs::create_dir_all(&out).unwrap();
+        fs::write(out.join("stale.rs"), "").unwrap();
+
+        assert_eq!(scaffold(&template, &out).unwrap(), None);
+        assert!(!out.join("stale.rs").exists());
+        assert_eq!(
+            fs::read_to_string(out.join("src/lib.rs")).unwrap(),
+            "pub mod gizmo;\n"
+        );
+        let cargo: toml::Table =
+            toml::from_str(&fs::read_to_string(out.join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!(cargo["package"]["name"].as_str(), Some("crate_name"));
+        assert_eq!(cargo["bench"][0]["name"].as_str(), Some("gizmo"));
+        assert_eq!(cargo["bench"][0]["harness"].as_bool(), Some(false));
+        assert_eq!(cargo["dev-dependencies"]["criterion"].as_str(), Some("0.5"));
+        assert_eq!(cargo["build-dependencies"]["cc"].as_str(), Some("1"));
+        assert!(cargo["features"]["fast"].as_array().unwrap().is_empty());
+        assert!(cargo["workspace"].as_table().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_scaffold_keeps_rendered_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = located(
+            &dir.path().join("templates"),
+            "name = \"widget\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+             description = \"Workspace\"\n\n\
+             [selftest]\ndependencies = { quote = \"1\" }\n\n\
+             [[files]]\ntemplate = \"Cargo.toml\"\npath = \"Cargo.toml\"\n\n\
+             [[files]]\ntemplate = \"member.toml\"\npath = \"member/Cargo.toml\"\n\n\
+             [[files]]\ntemplate = \"lib.rs\"\npath = \"member/src/lib.rs\"\n",
+            &[
+                ("Cargo.toml", "[workspace]\nmembers = [\"member\"]\n"),
+                (
+                    "member.toml",
+                    "[package]\nname = \"member\"\nversion = \"0.0.0\"\n",
+                ),
+                ("lib.rs", "\n"),
+            ],
+        );
+        let out = dir.path().join("scratch");
+
+        assert_eq!(scaffold(&template, &out).unwrap(), None);
+        let cargo: toml::Table =
+            toml::from_str(&fs::read_to_string(out.join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!(cargo["workspace"]["members"][0].as_str(), Some("member"));
+        assert_eq!(
+            cargo["workspace"]["dependencies"]["quote"].as_str(),
+            Some("1")
+        );
+        assert!(!cargo.contains_key("dependencies"));
+        assert!(!out.join("src").exists());
+    }
+
+    #[test]
+    fn test_scaffold_adds_bundle_to_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates = dir.path().join("templates");
+        fs::create_dir_all(&templates).unwrap();
+        fs::write(
+            templates.join("schema.template.toml"),
+            "name = \"schema\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+             description = \"Schema\"\n\n\
+             [[variables]]\nname = \"crate_name\"\ntype = \"identifier\"\nsample = \"app\"\n\n\
+             [[files]]\ntemplate = \"schema.rs\"\npath = \"src/schema.rs\"\n\n\
+             [[files]]\ntemplate = \"schema.sql\"\npath = \"migrations/0001_schema.sql\"\n",
+        )
+        .unwrap();
+        let template = located(
+            &templates,
+            "name = \"widget\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+             description = \"Migration\"\n\n\
+             [selftest]\nbase = \"schema\"\n\n\
+             [[files]]\ntemplate = \"up.sql\"\npath = \"migrations/0002_more.up.sql\"\n\n\
+             [[files]]\ntemplate = \"test.rs\"\npath = \"tests/more.rs\"\n",
+            &[
+                ("schema.rs", "pub struct Schema;\n"),
+                ("schema.sql", "CREATE TABLE t (id INT);\n"),
+                ("up.sql", "ALTER TABLE t ADD COLUMN n INT;\n"),
+                ("test.rs", "use app::schema::Schema;\n"),
+            ],
+        );
+        let out = dir.path().join("scratch");
+
+        assert_eq!(scaffold(&template, &out).unwrap(), None);
+        assert!(out.join("migrations/0001_schema.sql").is_file());
+        assert!(out.join("migrations/0002_more.up.sql").is_file());
+        assert_eq!(
+            fs::read_to_string(out.join("src/lib.rs")).unwrap(),
+            "pub mod schema;\n"
+        );
+        let cargo: toml::Table =
+            toml::from_str(&fs::read_to_string(out.join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!(cargo["package"]["name"].as_str(), Some("app"));
+    }
+
+    #[test]
+    fn test_scaffold_skips_non_rust_and_reports_render_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = |file: &str| {
+            format!(
+                "name = \"widget\"\nlanguage = \"rust\"\nstage = \"0-setup\"\n\
+                 description = \"Widget\"\nfile = \"{file}\"\n"
+            )
+        };
+        let make = located(
+            &dir.path().join("make"),
+            &manifest("Makefile"),
+            &[("Makefile", "test:\n\tcargo test\n")],
+        );
+        assert!(matches!(
+            scaffold(&make, &dir.path().join("out")).unwrap(),
+            Some(Verdict::Skipped { .. })
+        ));
+
+        let broken = located(
+            &dir.path().join("broken"),
+            &manifest("widget-template.rs"),
+            &[("widget-template.rs", "{{> partials/missing}}\n")],
+        );
+        let Some(Verdict::Failed { step, .. }) =
+            scaffold(&broken, &dir.path().join("out")).unwrap()
+        else {
+            panic!("expected a render failure");
+        };
+        assert_eq!(step, Step::Render);
+    }
+}
@@ -0,0 +1,120 @@
+//! Results of a workflow stage's gate.
+//!
+//! A gate is a check a stage has to pass before the next one starts, such
+//! as the doc examples `cc-scaffold doc-examples` requires in
+//! [`IMPLEMENTATION`] or the surviving mutants `cc-scaffold mutants report`
+//! looks for in [`EXPAND_TESTS`]. Its [`StageReport`] counts what the check
+//! saw and lists each problem at its file and position; `--format json`
+//! prints it as it is, and a gate that fails is an
+//! [`Error::Gate`](crate::Error::Gate) carrying it.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::hooks::Diagnostic;
+
+/// Stage 5 of the workflow, implementing the function, as template
+/// manifests name it.
+pub const IMPLEMENTATION: &str = "5-implementation";
+
+/// Stage 6 of the workflow, expanding the test suite, as template manifests
+/// name it.
+pub const EXPAND_TESTS: &str = "6-expand-tests";
+
+/// The workflow's ten stages, 0 to 9, as template manifests name them.
+pub const STAGES: &[&str] = &[
+    "0-setup",
+    "1-specification",
+    "2-smoke-test",
+    "3-syntax-check",
+    "4-run-smoke-test",
+    IMPLEMENTATION,
+    EXPAND_TESTS,
+    "7-final-syntax-check",
+    "8-verify-tests",
+    "9-report",
+];
+
+/// The outcome of one gate of one stage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StageReport {
+    /// The stage, named as in template manifests, e.g. [`EXPAND_TESTS`].
+    pub stage: String,
+    /// The check the stage ran.
+    pub gate: String,
+    /// Whether the stage may be left: there are no problems.
+    pub passed: bool,
+    /// How many of what the check saw ended each way.
+    pub counts: BTreeMap<String, usize>,
+    /// What fails the gate.
+    pub problems: Vec<Diagnostic>,
+    /// What does not fail the gate but deserves a look.
+    pub warnings: Vec<String>,
+}
+
+impl StageReport {
+    /// A report for `gate` of `stage` with these `counts` and `problems`,
+    /// which pass it if there are none.
+    pub fn new(
+        stage: &str,
+        gate: &str,
+        counts: BTreeMap<String, usize>,
+        problems: Vec<Diagnostic>,
+    ) -> Self {
+        Self {
+            stage: stage.to_string(),
+            gate: gate.to_string(),
+            passed: problems.is_empty(),
+            counts,
+            problems,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// `6-expand-tests gate `mutants` failed: 40 caught, 2 missed`.
+impl fmt::Display for StageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.passed { "passed" } else { "failed" };
+        write!(f, "{} gate `{}` {verdict}", self.stage, self.gate)?;
+        let counts: Vec<String> = self
+            .counts
+            .iter()
+            .map(|(outcome, count)| format!("{count} {outcome}"))
+            .collect();
+        if !counts.is_empty() {
+            write!(f, ": {}", counts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_passes_without_problems() {
+        let counts = BTreeMap::from([("caught".to_string(), 3), ("missed".to_string(), 0)]);
+        let report = StageReport::new(EXPAND_TESTS, "mutants", counts.clone(), Vec::new());
+        assert!(report.passed);
+        assert_eq!(
+            report.to_string(),
+            "6-expand-tests gate `mutants` passed: 3 caught, 0 missed"
+        );
+
+        let problem = Diagnostic {
+            file: "src/lib.rs".into(),
+            line: 3,
+            column: 5,
+            message: "replace even -> bool with true".to_string(),
+        };
+        let report = StageReport::new(EXPAND_TESTS, "mutants", counts, vec![problem]);
+        assert!(!report.passed);
+        assert!(report
+            .to_string()
+            .starts_with("6-expand-tests gate `mutants` failed"));
+    }
+}
@@ -0,0 +1,246 @@
+//! Handlebars, which the native syntax is modelled on.
+//!
+//! Handlebars-only constructs are translated into native syntax:
+//!
+//! * comments, `{{! ... }}` and `{{!-- ... --}}`
+//! * triple-stash `{{{ name }}}`, rendered like `{{ name }}` since output is
+//!   never HTML-escaped
+//! * `~` whitespace control, `{{~ name ~}}`
+//! * `{{else if flag}}` chains
+//! * `(eq name "literal")` and `(ne name "literal")` conditions
+//! * filters called as helpers, `{{snake_case name}}` or
+//!   `{{upper (snake_case name)}}`
+//!
+//! `{{#if}}`, `{{#unless}}`, `{{#each}}` with `this` and `@index`,
+//! `{{> partial}}` and `\{{` escapes already mean the same thing. Other
+//! helpers, block parameters and `../` paths are reported as errors.
+
+use crate::template_engine::filters;
+
+/// Rewrites Handlebars `source` into native template syntax. Errors carry
+/// the byte offset of the offending tag.
+pub(super) fn translate(source: &str) -> Result<String, (usize, String)> {
+    let mut out = String::with_capacity(source.len());
+    // Open blocks, with the closing tags owed for each: one per `else if`
+    // plus the block itself.
+    let mut stack: Vec<(&'static str, Vec<&'static str>, usize)> = Vec::new();
+    let mut rest = source;
+    let mut trim_next = false;
+    loop {
+        let offset = source.len() - rest.len();
+        let next = rest.find("{{");
+        let mut text = &rest[..next.unwrap_or(rest.len())];
+        if trim_next {
+            text = text.trim_start();
+        }
+        let Some(start) = next else {
+            out.push_str(text);
+            break;
+        };
+        if text.ends_with('\\') {
+            out.push_str(text);
+            out.push_str("{{");
+            rest = &rest[start + 2..];
+            trim_next = false;
+            continue;
+        }
+        out.push_str(text);
+        let tag_offset = offset + start;
+        let tag = &rest[start..];
+        let (open, close) = if tag.starts_with("{{{") {
+            ("{{{", "}}}")
+        } else if tag.starts_with("{{!--") || tag.starts_with("{{~!--") {
+            ("{{", "--}}")
+        } else {
+            ("{{", "}}")
+        };
+        let Some(len) = tag[2..].find(close) else {
+            return Err((tag_offset, format!("unclosed `{open}`")));
+        };
+        let mut inner = &tag[2..2 + len];
+        rest = &tag[2 + len + close.len()..];
+        let stash = open == "{{{";
+        if stash {
+            inner = &inner[1..];
+        }
+        if let Some(stripped) = inner.strip_prefix('~') {
+            inner = stripped;
+            out.truncate(out.trim_end().len());
+        }
+        trim_next = false;
+        if let Some(stripped) = inner.strip_suffix('~') {
+            inner = stripped;
+            trim_next = true;
+        }
+        let at = |message: String| (tag_offset, message);
+        if inner.starts_with('!') && !stash {
+            continue;
+        }
+        let inner = inner.trim();
+        let unexpected = || at(format!("unexpected `{{{{{inner}}}}}`"));
+        if stash {
+            out.push_str(&format!("{{{{ {} }}}}", expression(inner).map_err(at)?));
+        } else if let Some(block) = inner.strip_prefix('#') {
+            let (keyword, args) = block.split_once(char::is_whitespace).unwrap_or((block, ""));
+            let args = args.trim();
+            let keyword = match keyword {
+                "if" => "if",
+                "unless" => "unless",
+                "each" => "each",
+                _ => return Err(at(format!("unsupported Handlebars helper `#{keyword}`"))),
+            };
+            let args = if keyword == "each" {
+                path(args).map_err(at)?
+            } else {
+                condition(args).map_err(at)?
+            };
+            out.push_str(&format!("{{{{#{keyword} {args}}}}}"));
+            stack.push((keyword, vec![keyword], tag_offset));
+        } else if let Some(keyword) = inner.strip_prefix('/') {
+            match stack.pop() {
+                Some((open, closers, _)) if open == keyword.trim() => {
+                    for closer in closers.iter().rev() {
+                        out.push_str(&format!("{{{{/{closer}}}}}"));
+                    }
+                }
+                _ => return Err(unexpected()),
+            }
+        } else if inner == "else" {
+            if stack.is_empty() {
+                return Err(unexpected());
+            }
+            out.push_str("{{else}}");
+        } else if let Some(args) = inner.strip_prefix("else if ") {
+            match stack.last_mut() {
+                Some(("if" | "unless", closers, _)) => closers.push("if"),
+                _ => return Err(unexpected()),
+            }
+            let args = condition(args.trim()).map_err(at)?;
+            out.push_str(&format!("{{{{else}}}}{{{{#if {args}}}}}"));
+        } else if let Some(name) = inner.strip_prefix('>') {
+            let name = name.trim();
+            let name = quoted(name).unwrap_or(name);
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(at(format!("unsupported partial `{{{{{inner}}}}}`")));
+            }
+            out.push_str(&format!("{{{{> {name}}}}}"));
+        } else {
+            out.push_str(&format!("{{{{ {} }}}}", expression(inner).map_err(at)?));
+        }
+    }
+    if let Some((open, _, offset)) = stack.pop() {
+        return Err((offset, format!("unclosed `{{{{#{open}}}}}`")));
+    }
+    Ok(out)
+}
+
+/// Translates `name`, `helper name` or `helper (helper name)`, where every
+/// helper is a filter, into `name | helper | ...`.
+fn expression(inner: &str) -> Result<String, String> {
+    let inner = inner.trim();
+    let inner = inner
+        .strip_prefix('(')
+        .and_then(|i| i.strip_suffix(')'))
+        .unwrap_or(inner)
+        .trim();
+    let Some((helper, arg)) = inner.split_once(char::is_whitespace) else {
+        return path(inner);
+    };
+    if !filters::is_filter(helper) {
+        return Err(format!("unsupported Handlebars helper `{helper}`"));
+    }
+    Ok(format!("{} | {helper}", expression(arg)?))
+}
+
+/// Translates `name`, `(eq name "literal")` or `(ne name "literal")`.
+fn condition(args: &str) -> Result<String, String> {
+    let Some(call) = args.strip_prefix('(').and_then(|a| a.strip_suffix(')')) else {
+        return path(args);
+    };
+    let mut parts = call.split_whitespace();
+    let op = match parts.next() {
+        Some("eq") => "==",
+        Some("ne") => "!=",
+        _ => return Err(format!("unsupported Handlebars condition `{args}`")),
+    };
+    let (Some(lhs), Some(rhs), None) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(format!("unsupported Handlebars condition `{args}`"));
+    };
+    let literal = quoted(rhs)
+        .or_else(|| matches!(rhs, "true" | "false").then_some(rhs))
+        .ok_or_else(|| format!("unsupported Handlebars comparison `{args}`"))?;
+    Ok(format!("{} {op} \"{literal}\"", path(lhs)?))
+}
+
+fn path(name: &str) -> Result<String, String> {
+    let position = matches!(name, "@index" | "@first" | "@last");
+    let valid = !name.is_empty()
+        && name.split('.').all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+    if position || valid {
+        Ok(name.to_string())
+    } else {
+        Err(format!("unsupported Handlebars expression `{name}`"))
+    }
+}
+
+/// The contents of a `"..."` or `'...'` literal.
+fn quoted(s: &str) -> Option<&str> {
+    s.strip_prefix('"')
+        .and_then(|r| r.strip_suffix('"'))
+        .or_else(|| s.strip_prefix('\'').and_then(|r| r.strip_suffix('\'')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_handlebars() {
+        let hbs = |s| translate(s).unwrap();
+        assert_eq!(
+            hbs("{{! note }}{{!-- {{x}} --}}fn {{snake_case name}}() -> {{{ty}}}"),
+            "fn {{ name | snake_case }}() -> {{ ty }}"
+        );
+        assert_eq!(
+            hbs("{{#if (eq runtime \"tokio\")}}a{{else if async}}b{{else}}c{{/if}}"),
+            "{{#if runtime == \"tokio\"}}a{{else}}{{#if async}}b{{else}}c{{/if}}{{/if}}"
+        );
+        assert_eq!(
+            hbs("a  {{~#each xs~}}  {{upper (snake_case this.name)}}{{#unless @last}}, {{/unless}}{{/each}}"),
+            "a{{#each xs}}{{ this.name | snake_case | upper }}{{#unless @last}}, {{/unless}}{{/each}}"
+        );
+        assert_eq!(
+            hbs("{{> \"partials/header\"}}\\{{ literal }}"),
+            "{{> partials/header}}\\{{ literal }}"
+        );
+    }
+
+    #[test]
+    fn test_translate_rejects_unsupported_syntax() {
+        for (source, message) in [
+            (
+                "{{#with a}}{{/with}}",
+                "unsupported Handlebars helper `#with`",
+            ),
+            ("{{lookup a b}}", "unsupported Handlebars helper `lookup`"),
+            ("{{../name}}", "unsupported Handlebars expression"),
+            (
+                "{{#each xs as |x|}}{{/each}}",
+                "unsupported Handlebars expression",
+            ),
+            (
+                "{{#if (gt a 1)}}{{/if}}",
+                "unsupported Handlebars condition",
+            ),
+            ("{{#if a}}", "unclosed `{{#if}}`"),
+            ("{{#if a}}{{/each}}", "unexpected `{{/each}}`"),
+            ("{{else}}", "unexpected `{{else}}`"),
+        ] {
+            let (_, err) = translate(source).unwrap_err();
+            assert!(err.contains(message), "{source}: {err}");
+        }
+    }
+}
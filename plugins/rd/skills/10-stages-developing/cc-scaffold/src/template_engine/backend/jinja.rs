@@ -0,0 +1,488 @@
+//! Tera and Liquid, which share the `{{ }}`/`{% %}` tag style.
+//!
+//! Both are translated into native syntax. The subset supported:
+//!
+//! * `{{ path | filter }}` with filters that have a native equivalent
+//! * `{% if %}` / `{% elif %}` (Tera) or `{% elsif %}` (Liquid) /
+//!   `{% else %}` / `{% endif %}`, with a variable, `not variable` (Tera) or
+//!   an `==`/`!=` comparison against a literal, and Liquid's `{% unless %}`
+//! * `{% for item in items %}` ... `{% endfor %}`, where `item`, `item.field`
+//!   and `loop.index0`/`loop.first`/`loop.last` (Tera) or
+//!   `forloop.index0`/`forloop.first`/`forloop.last` (Liquid) are available
+//! * Tera's `{% include "name" %}`, `{% extends "name" %}` and
+//!   `{% block name %}` ... `{% endblock %}`
+//! * `{% raw %}`, comments (Tera `{# #}`, Liquid `{% comment %}`) and `-`
+//!   whitespace control
+//!
+//! Anything else is reported as an error rather than rendered wrongly.
+
+/// What distinguishes one dialect from the other.
+pub(super) struct Dialect {
+    /// Name used in error messages.
+    pub name: &'static str,
+    /// Keyword of an else-if branch.
+    pub elif: &'static str,
+    /// Whether `not x` negates a condition.
+    pub not: bool,
+    /// Whether `{% unless %}` is available.
+    pub unless: bool,
+    /// Whether `{# #}` is a comment.
+    pub hash_comments: bool,
+    /// Whether `{% comment %}` ... `{% endcomment %}` is a comment.
+    pub comment_tag: bool,
+    /// Whether `include`, `extends` and `block` are available.
+    pub inheritance: bool,
+    /// Variable describing the position in a `for` loop.
+    pub loop_object: &'static str,
+    /// Filters whose native name differs.
+    pub filters: &'static [(&'static str, &'static str)],
+}
+
+pub(super) const TERA: Dialect = Dialect {
+    name: "Tera",
+    elif: "elif",
+    not: true,
+    unless: false,
+    hash_comments: true,
+    comment_tag: false,
+    inheritance: true,
+    loop_object: "loop",
+    filters: &[("title", "title_case")],
+};
+
+pub(super) const LIQUID: Dialect = Dialect {
+    name: "Liquid",
+    elif: "elsif",
+    not: false,
+    unless: true,
+    hash_comments: false,
+    comment_tag: true,
+    inheritance: false,
+    loop_object: "forloop",
+    filters: &[
+        ("upper_camel_case", "pascal_case"),
+        ("lower_camel_case", "camel_case"),
+        ("upcase", "upper"),
+        ("downcase", "lower"),
+    ],
+};
+
+/// Rewrites `source` in `dialect` into native template syntax. Errors carry
+/// the byte offset of the offending tag.
+pub(super) fn translate(source: &str, dialect: &Dialect) -> Result<String, (usize, String)> {
+    Translator {
+        dialect,
+        out: String::with_capacity(source.len()),
+        stack: Vec::new(),
+        extends: false,
+    }
+    .run(source)
+}
+
+enum Open {
+    /// Closing tags owed at `{% endif %}`, one per `elif` plus the `if`.
+    If {
+        closers: Vec<&'static str>,
+    },
+    Unless,
+    For {
+        variable: String,
+    },
+    Block,
+}
+
+struct Translator<'d> {
+    dialect: &'d Dialect,
+    out: String,
+    stack: Vec<(Open, usize)>,
+    extends: bool,
+}
+
+impl Translator<'_> {
+    fn run(mut self, source: &str) -> Result<String, (usize, String)> {
+        let mut rest = source;
+        let mut trim_next = false;
+        loop {
+            let offset = source.len() - rest.len();
+            let openers: &[&str] = if self.dialect.hash_comments {
+                &["{{", "{%", "{#"]
+            } else {
+                &["{{", "{%"]
+            };
+            let next = openers.iter().filter_map(|o| rest.find(o)).min();
+            let text = &rest[..next.unwrap_or(rest.len())];
+            self.out
+                .push_str(if trim_next { text.trim_start() } else { text });
+            let Some(start) = next else { break };
+            let tag_offset = offset + start;
+            let opener = &rest[start..start + 2];
+            let close = match opener {
+                "{{" => "}}",
+                "{%" => "%}",
+                _ => "#}",
+            };
+            let Some(len) = rest[start + 2..].find(close) else {
+                return Err((tag_offset, format!("unclosed `{opener}`")));
+            };
+            let mut inner = &rest[start + 2..start + 2 + len];
+            rest = &rest[start + 4 + len..];
+            if let Some(stripped) = inner.strip_prefix('-') {
+                inner = stripped;
+                self.out.truncate(self.out.trim_end().len());
+            }
+            trim_next = false;
+            if let Some(stripped) = inner.strip_suffix('-') {
+                inner = stripped;
+                trim_next = true;
+            }
+            let inner = inner.trim();
+            let at = |message: String| (tag_offset, message);
+            match opener {
+                "{{" => {
+                    let expr = self.expression(inner).map_err(at)?;
+                    self.out.push_str(&format!("{{{{ {expr} }}}}"));
+                }
+                "{#" => {}
+                _ => {
+                    rest = self.statement(inner, rest, tag_offset)?;
+                }
+            }
+        }
+        if let Some((open, offset)) = self.stack.pop() {
+            let keyword = match open {
+                Open::If { .. } => "if",
+                Open::Unless => "unless",
+                Open::For { .. } => "for",
+                Open::Block => "block",
+            };
+            return Err((offset, format!("unclosed `{{% {keyword} %}}`")));
+        }
+        if self.extends {
+            self.out.push_str("{{/extends}}");
+        }
+        Ok(self.out)
+    }
+
+    /// Translates one `{% %}` tag, returning the source left after it.
+    fn statement<'s>(
+        &mut self,
+        inner: &str,
+        rest: &'s str,
+        offset: usize,
+    ) -> Result<&'s str, (usize, String)> {
+        let (keyword, args) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+        let args = args.trim();
+        let at = |message: String| (offset, message);
+        let unexpected = || at(format!("unexpected `{{% {keyword} %}}`"));
+        let dialect = self.dialect;
+        match keyword {
+            "if" => {
+                let (open, condition) = self.condition(args).map_err(at)?;
+                self.out.push_str(&format!("{{{{#{open} {condition}}}}}"));
+                self.stack.push((
+                    Open::If {
+                        closers: vec![open],
+                    },
+                    offset,
+                ));
+            }
+            k if k == dialect.elif => {
+                let (open, condition) = self.condition(args).map_err(at)?;
+                match self.stack.last_mut() {
+                    Some((Open::If { closers }, _)) => closers.push(open),
+                    _ => return Err(unexpected()),
+                }
+                self.out
+                    .push_str(&format!("{{{{else}}}}{{{{#{open} {condition}}}}}"));
+            }
+            "else" => match self.stack.last() {
+                Some((Open::If { .. } | Open::Unless | Open::For { .. }, _)) => {
+                    self.out.push_str("{{else}}")
+                }
+                _ => return Err(unexpected()),
+            },
+            "endif" => match self.stack.pop() {
+                Some((Open::If { closers }, _)) => {
+                    for closer in closers.iter().rev() {
+                        self.out.push_str(&format!("{{{{/{closer}}}}}"));
+                    }
+                }
+                _ => return Err(unexpected()),
+            },
+            "unless" if dialect.unless => {
+                let (open, condition) = self.condition(args).map_err(at)?;
+                if open != "if" {
+                    return Err(at(format!("unsupported Liquid condition `{args}`")));
+                }
+                self.out.push_str(&format!("{{{{#unless {condition}}}}}"));
+                self.stack.push((Open::Unless, offset));
+            }
+            "endunless" if dialect.unless => match self.stack.pop() {
+                Some((Open::Unless, _)) => self.out.push_str("{{/unless}}"),
+                _ => return Err(unexpected()),
+            },
+            "for" => {
+                let (variable, list) = args
+                    .split_once(" in ")
+                    .map(|(v, l)| (v.trim(), l.trim()))
+                    .filter(|(v, _)| is_name(v))
+                    .ok_or_else(|| at(format!("unsupported loop `{{% for {args} %}}`")))?;
+                let list = self.path(list).map_err(at)?;
+                self.out.push_str(&format!("{{{{#each {list}}}}}"));
+                self.stack.push((
+                    Open::For {
+                        variable: variable.to_string(),
+                    },
+                    offset,
+                ));
+            }
+            "endfor" => match self.stack.pop() {
+                Some((Open::For { .. }, _)) => self.out.push_str("{{/each}}"),
+                _ => return Err(unexpected()),
+            },
+            "include" if dialect.inheritance => {
+                let name =
+                    quoted(args).ok_or_else(|| at(format!("unsupported include `{args}`")))?;
+                self.out.push_str(&format!("{{{{> {name}}}}}"));
+            }
+            "extends" if dialect.inheritance => {
+                let name =
+                    quoted(args).ok_or_else(|| at(format!("unsupported extends `{args}`")))?;
+                if self.extends || !self.out.trim().is_empty() {
+                    return Err(at("`{% extends %}` must come first".to_string()));
+                }
+                self.out.clear();
+                self.out.push_str(&format!("{{{{#extends {name}}}}}"));
+                self.extends = true;
+            }
+            "block" if dialect.inheritance && is_name(args) => {
+                self.out.push_str(&format!("{{{{#block {args}}}}}"));
+                self.stack.push((Open::Block, offset));
+            }
+            "endblock" if dialect.inheritance => match self.stack.pop() {
+                Some((Open::Block, _)) => self.out.push_str("{{/block}}"),
+                _ => return Err(unexpected()),
+            },
+            "raw" | "comment" if keyword == "raw" || dialect.comment_tag => {
+                let end = format!("end{keyword}");
+                let Some((body, after)) = until_tag(rest, &end) else {
+                    return Err(at(format!("`{{% {keyword} %}}` without `{{% {end} %}}`")));
+                };
+                if keyword == "raw" {
+                    self.out.push_str(&body.replace("{{", "\\{{"));
+                }
+                return Ok(after);
+            }
+            _ => {
+                return Err(at(format!(
+                    "unsupported {} tag `{{% {keyword} %}}`",
+                    dialect.name
+                )))
+            }
+        }
+        Ok(rest)
+    }
+
+    /// Translates `path | filter | ...`.
+    fn expression(&self, inner: &str) -> Result<String, String> {
+        let mut parts = inner.split('|').map(str::trim);
+        let mut out = self.path(parts.next().unwrap_or_default())?;
+        for name in parts {
+            let native = self
+                .dialect
+                .filters
+                .iter()
+                .find(|(from, _)| *from == name)
+                .map_or(name, |(_, to)| to);
+            if !crate::template_engine::filters::is_filter(native) {
+                return Err(format!("unsupported {} filter `{name}`", self.dialect.name));
+            }
+            out.push_str(" | ");
+            out.push_str(native);
+        }
+        Ok(out)
+    }
+
+    /// Translates a condition, returning the native block keyword to open
+    /// (`if`, or `unless` for `not x`) and its argument.
+    fn condition(&self, args: &str) -> Result<(&'static str, String), String> {
+        if let Some(negated) = args.strip_prefix("not ").filter(|_| self.dialect.not) {
+            return Ok(("unless", self.path(negated.trim())?));
+        }
+        for op in ["==", "!="] {
+            if let Some((lhs, rhs)) = args.split_once(op) {
+                let rhs = rhs.trim();
+                let literal = quoted(rhs)
+                    .or_else(|| matches!(rhs, "true" | "false").then_some(rhs))
+                    .ok_or_else(|| {
+                        format!("unsupported {} comparison `{args}`", self.dialect.name)
+                    })?;
+                return Ok((
+                    "if",
+                    format!("{} {op} \"{literal}\"", self.path(lhs.trim())?),
+                ));
+            }
+        }
+        self.path(args)
+            .map(|path| ("if", path))
+            .map_err(|_| format!("unsupported {} condition `{args}`", self.dialect.name))
+    }
+
+    /// Translates a variable path, mapping the innermost loop variable and
+    /// loop position variables to their native forms.
+    fn path(&self, name: &str) -> Result<String, String> {
+        let valid = !name.is_empty()
+            && name.split('.').all(|part| {
+                part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                    && part
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+            });
+        if !valid {
+            return Err(format!(
+                "unsupported {} expression `{name}`",
+                self.dialect.name
+            ));
+        }
+        let name = name.replace('-', "_");
+        let (first, rest) = name.split_once('.').unwrap_or((&name, ""));
+        let mut loops = self.stack.iter().rev().filter_map(|(open, _)| match open {
+            Open::For { variable } => Some(variable.as_str()),
+            _ => None,
+        });
+        let innermost = loops.next();
+        if first == self.dialect.loop_object && innermost.is_some() {
+            return match rest {
+                "index0" => Ok("@index".to_string()),
+                "first" => Ok("@first".to_string()),
+                "last" => Ok("@last".to_string()),
+                _ => Err(format!("unsupported loop variable `{name}`")),
+            };
+        }
+        if innermost == Some(first) {
+            return Ok(if rest.is_empty() {
+                "this".to_string()
+            } else {
+                format!("this.{rest}")
+            });
+        }
+        if loops.any(|outer| outer == first) {
+            return Err(format!(
+                "`{first}` belongs to an outer loop, which is not supported"
+            ));
+        }
+        Ok(name)
+    }
+}
+
+fn is_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The contents of a `"..."` or `'...'` literal.
+fn quoted(s: &str) -> Option<&str> {
+    s.strip_prefix('"')
+        .and_then(|r| r.strip_suffix('"'))
+        .or_else(|| s.strip_prefix('\'').and_then(|r| r.strip_suffix('\'')))
+}
+
+/// Splits `text` at the first `{% keyword %}` tag, returning what precedes
+/// it and what follows it.
+fn until_tag<'a>(text: &'a str, keyword: &str) -> Option<(&'a str, &'a str)> {
+    let mut searched = 0;
+    while let Some(start) = text[searched..].find("{%").map(|i| i + searched) {
+        let len = text[start..].find("%}")?;
+        let inner =
+            text[start + 2..start + len].trim_matches(|c: char| c == '-' || c.is_whitespace());
+        if inner == keyword {
+            return Some((&text[..start], &text[start + len + 2..]));
+        }
+        searched = start + 2;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_liquid() {
+        let liquid = |s| translate(s, &LIQUID).unwrap();
+        assert_eq!(
+            liquid("name = \"{{project-name}}\"\n{{ crate_name | upper_camel_case | upcase }}"),
+            "name = \"{{ project_name }}\"\n{{ crate_name | pascal_case | upper }}"
+        );
+        assert_eq!(
+            liquid("{% if a %}A{% elsif b == 'x' %}B{% else %}C{% endif %}"),
+            "{{#if a}}A{{else}}{{#if b == \"x\"}}B{{else}}C{{/if}}{{/if}}"
+        );
+        assert_eq!(
+            liquid("a  {%- unless gh -%}  b{% endunless %}"),
+            "a{{#unless gh}}b{{/unless}}"
+        );
+        assert_eq!(
+            liquid("{% raw %}{{ x }}{% endraw %}{% comment %}no{% endcomment %}"),
+            "\\{{ x }}"
+        );
+        assert_eq!(
+            liquid("{% for f in fields %}{{ forloop.index0 }}{{ f.name }}{% endfor %}"),
+            "{{#each fields}}{{ @index }}{{ this.name }}{{/each}}"
+        );
+    }
+
+    #[test]
+    fn test_translate_tera() {
+        let tera = |s| translate(s, &TERA).unwrap();
+        assert_eq!(
+            tera("{% extends \"base.rs\" %}\n{% block body %}{{ name | title }}{% endblock body %}\n"),
+            "{{#extends base.rs}}\n{{#block body}}{{ name | title_case }}{{/block}}\n{{/extends}}"
+        );
+        assert_eq!(
+            tera("{# note #}{% if not a %}x{% elif b %}y{% endif %}{% include 'partials/h' %}"),
+            "{{#unless a}}x{{else}}{{#if b}}y{{/if}}{{/unless}}{{> partials/h}}"
+        );
+        assert_eq!(
+            tera("{%- for x in xs -%} {{ x }}{% if loop.last %};{% endif %}{% endfor %}"),
+            "{{#each xs}}{{ this }}{{#if @last}};{{/if}}{{/each}}"
+        );
+    }
+
+    #[test]
+    fn test_translate_rejects_unsupported_syntax() {
+        for (dialect, source, message) in [
+            (&LIQUID, "{% assign x = 1 %}", "unsupported Liquid tag"),
+            (
+                &LIQUID,
+                "{{ name | truncate: 3 }}",
+                "unsupported Liquid filter",
+            ),
+            (
+                &LIQUID,
+                "{% if a and b %}{% endif %}",
+                "unsupported Liquid condition",
+            ),
+            (&LIQUID, "{% if a %}", "unclosed `{% if %}`"),
+            (&LIQUID, "{% endif %}", "unexpected `{% endif %}`"),
+            (&LIQUID, "{{ \"lit\" }}", "unsupported Liquid expression"),
+            (&LIQUID, "{% include 'x' %}", "unsupported Liquid tag"),
+            (&TERA, "{% set x = 1 %}", "unsupported Tera tag"),
+            (&TERA, "{{ a ~ b }}", "unsupported Tera expression"),
+            (&TERA, "x{% extends 'b' %}", "must come first"),
+            (
+                &TERA,
+                "{% for a in as %}{% for b in bs %}{{ a }}{% endfor %}{% endfor %}",
+                "outer loop",
+            ),
+            (
+                &TERA,
+                "{% unless a %}{% endunless %}",
+                "unsupported Tera tag",
+            ),
+        ] {
+            let (_, err) = translate(source, dialect).unwrap_err();
+            assert!(err.contains(message), "{source}: {err}");
+        }
+    }
+}
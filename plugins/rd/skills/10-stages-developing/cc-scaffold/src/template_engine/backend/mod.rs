@@ -0,0 +1,166 @@
+//! Template syntax backends.
+//!
+//! A manifest chooses how its template is written with `syntax`:
+//!
+//! * `native` (the default) — the syntax described in the `parser` module
+//! * `handlebars` — see the `handlebars` module
+//! * `tera` and `liquid` — see the `jinja` module
+//!
+//! Every backend parses into the same [`Template`] tree, so identifier and
+//! prose placeholders, filters, partials and error reporting behave the same
+//! whichever syntax a template uses. The foreign syntaxes are translated
+//! into native syntax first and support the subset of their language that
+//! has a native equivalent; anything else is a parse error rather than
+//! being rendered differently from the real engine.
+
+mod handlebars;
+mod jinja;
+
+use std::fs;
+use std::path::Path;
+
+use super::parser::LineIndex;
+use super::Template;
+use crate::error::{Error, Result};
+
+/// A template syntax.
+pub trait TemplateBackend: Sync {
+    /// Name of the syntax, as given to `syntax` in a manifest.
+    fn name(&self) -> &'static str;
+
+    /// Parses `source` written in this syntax.
+    fn parse(&self, source: &str) -> Result<Template>;
+
+    /// Reads and parses the template at `path`. Parse and render errors
+    /// name the file.
+    fn load(&self, path: &Path) -> Result<Template> {
+        let source = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+        self.parse(&source)
+            .map(|template| template.with_file(path))
+            .map_err(|err| err.in_file(path))
+    }
+}
+
+/// The `{{ name }}` syntax templates use by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Native;
+
+impl TemplateBackend for Native {
+    fn name(&self) -> &'static str {
+        "native"
+    }
+
+    fn parse(&self, source: &str) -> Result<Template> {
+        Template::parse(source)
+    }
+}
+
+/// [Handlebars](https://handlebarsjs.com).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Handlebars;
+
+impl TemplateBackend for Handlebars {
+    fn name(&self) -> &'static str {
+        "handlebars"
+    }
+
+    fn parse(&self, source: &str) -> Result<Template> {
+        translated(source, handlebars::translate(source))
+    }
+}
+
+/// [Tera](https://keats.github.io/tera/).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tera;
+
+impl TemplateBackend for Tera {
+    fn name(&self) -> &'static str {
+        "tera"
+    }
+
+    fn parse(&self, source: &str) -> Result<Template> {
+        translated(source, jinja::translate(source, &jinja::TERA))
+    }
+}
+
+/// [Liquid](https://shopify.github.io/liquid/), as used by cargo-generate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Liquid;
+
+impl TemplateBackend for Liquid {
+    fn name(&self) -> &'static str {
+        "liquid"
+    }
+
+    fn parse(&self, source: &str) -> Result<Template> {
+        translated(source, jinja::translate(source, &jinja::LIQUID))
+    }
+}
+
+/// Every available backend.
+pub const BACKENDS: &[&dyn TemplateBackend] = &[&Native, &Handlebars, &Tera, &Liquid];
+
+/// Looks up a backend by [`TemplateBackend::name`].
+pub fn by_name(name: &str) -> Option<&'static dyn TemplateBackend> {
+    BACKENDS
+        .iter()
+        .copied()
+        .find(|backend| backend.name() == name)
+}
+
+/// Parses the native translation of `source`, reporting translation errors
+/// at their position in `source`.
+fn translated(
+    source: &str,
+    native: std::result::Result<String, (usize, String)>,
+) -> Result<Template> {
+    match native {
+        Ok(native) => Template::parse(&native),
+        Err((offset, message)) => Err(Error::template(
+            LineIndex::new(source).span(offset),
+            message,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template_engine::Context;
+
+    #[test]
+    fn test_backends_render_alike() {
+        let mut ctx = Context::new();
+        ctx.set("name", "parse header");
+        ctx.set("public", true);
+        let expected = "pub fn parse_header() {}\n";
+        for (backend, source) in [
+            (
+                "native",
+                "{{#if public}}pub {{/if}}fn {{ name | snake_case }}() {}\n",
+            ),
+            (
+                "handlebars",
+                "{{#if public}}pub {{/if}}fn {{snake_case name}}() {}\n",
+            ),
+            (
+                "tera",
+                "{% if public %}pub {% endif %}fn {{ name | snake_case }}() {}\n",
+            ),
+            (
+                "liquid",
+                "{% if public %}pub {% endif %}fn {{ name | snake_case }}() {}\n",
+            ),
+        ] {
+            let template = by_name(backend).unwrap().parse(source).unwrap();
+            assert_eq!(template.render(&ctx).unwrap(), expected, "{backend}");
+        }
+        assert!(by_name("jinja2").is_none());
+    }
+
+    #[test]
+    fn test_translation_errors_point_into_the_source() {
+        let err = Tera.parse("a\n  {% set x = 1 %}\n").unwrap_err();
+        assert_eq!(err.to_string(), "2:3: unsupported Tera tag `{% set %}`");
+    }
+}
@@ -0,0 +1,127 @@
+//! Filters applied to expression values: `{{ name | pascal_case }}`.
+//!
+//! The case filters split their input into words at `_`, `-`, spaces and
+//! other punctuation, at lower-to-upper transitions (`functionName`), and
+//! before the last capital of an acronym (`HTTPServer` is `HTTP` `Server`),
+//! then join the words in the requested style.
+
+/// Names accepted after `|`, with what they produce for
+/// `parse_http_header`.
+pub const FILTERS: &[(&str, &str)] = &[
+    ("snake_case", "parse_http_header"),
+    ("kebab_case", "parse-http-header"),
+    ("camel_case", "parseHttpHeader"),
+    ("pascal_case", "ParseHttpHeader"),
+    ("shouty", "PARSE_HTTP_HEADER"),
+    ("shouty_snake_case", "PARSE_HTTP_HEADER"),
+    ("title_case", "Parse Http Header"),
+    ("lower", "parse_http_header"),
+    ("upper", "PARSE_HTTP_HEADER"),
+];
+
+/// Whether `name` is a known filter.
+pub fn is_filter(name: &str) -> bool {
+    FILTERS.iter().any(|(n, _)| *n == name)
+}
+
+/// Applies the filter `name` to `value`. Unknown names are rejected when
+/// the template is parsed, so they leave the value unchanged here.
+pub fn apply(name: &str, value: &str) -> String {
+    let words = || words(value).into_iter();
+    match name {
+        "snake_case" => join(words().map(lower), "_"),
+        "kebab_case" => join(words().map(lower), "-"),
+        "camel_case" => words()
+            .enumerate()
+            .map(|(i, w)| if i == 0 { lower(w) } else { capitalize(w) })
+            .collect(),
+        "pascal_case" => words().map(capitalize).collect(),
+        "shouty" | "shouty_snake_case" => join(words().map(upper), "_"),
+        "title_case" => join(words().map(capitalize), " "),
+        "lower" => value.to_lowercase(),
+        "upper" => value.to_uppercase(),
+        _ => value.to_string(),
+    }
+}
+
+/// Splits `value` into words, see the module documentation.
+pub fn words(value: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let chars: Vec<(usize, char)> = value.char_indices().collect();
+    let mut start = None;
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if let Some(s) = start.take() {
+                words.push(&value[s..offset]);
+            }
+            continue;
+        }
+        if let Some(s) = start {
+            let prev = chars[i - 1].1;
+            let next = chars.get(i + 1).map(|&(_, c)| c);
+            let boundary = c.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_numeric()
+                    || (prev.is_uppercase() && next.is_some_and(char::is_lowercase)));
+            if boundary {
+                words.push(&value[s..offset]);
+                start = Some(offset);
+            }
+        } else {
+            start = Some(offset);
+        }
+    }
+    if let Some(s) = start {
+        words.push(&value[s..]);
+    }
+    words
+}
+
+fn join(words: impl Iterator<Item = String>, separator: &str) -> String {
+    words.collect::<Vec<_>>().join(separator)
+}
+
+fn lower(word: &str) -> String {
+    word.to_lowercase()
+}
+
+fn upper(word: &str) -> String {
+    word.to_uppercase()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_splitting() {
+        assert_eq!(words("parse_http_header"), ["parse", "http", "header"]);
+        assert_eq!(words("ParseHTTPHeader"), ["Parse", "HTTP", "Header"]);
+        assert_eq!(words("parseHttp2Header"), ["parse", "Http2", "Header"]);
+        assert_eq!(words("  my-func name "), ["my", "func", "name"]);
+        assert!(words("__").is_empty());
+    }
+
+    #[test]
+    fn test_documented_examples() {
+        for (name, expected) in FILTERS {
+            let input = if name.starts_with("lower") || name.starts_with("upper") {
+                "parse_http_header"
+            } else {
+                "ParseHTTPHeader"
+            };
+            assert_eq!(apply(name, input), *expected, "filter `{name}`");
+        }
+    }
+}
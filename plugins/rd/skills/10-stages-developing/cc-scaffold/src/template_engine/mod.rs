@@ -0,0 +1,591 @@
+//! Placeholder substitution engine.
+//!
+//! Templates are plain source files that stay valid code in their target
+//! language. Placeholders come in three forms:
+//!
+//! * **identifiers** such as `function_name` or `FunctionResult`, renamed
+//!   wherever they appear as a whole identifier or as an `_`/camel-case
+//!   delimited part of one, so `test_function_name_smoke` follows a rename
+//!   of `function_name`
+//! * **prose** such as `[Brief description of what the function does]`
+//! * **expressions** such as `{{ name }}`, optionally transformed with
+//!   [`filters`]: `{{ name | pascal_case }}`
+//!
+//! Sections can be switched on and off with
+//! `{{#if flag}} ... {{else}} ... {{/if}}` and
+//! `{{#unless flag}} ... {{/unless}}`, and repeated for every item of a list
+//! with `{{#each items}} ... {{/each}}`. Shared fragments registered with
+//! [`Context::add_partial`] are included with `{{> name}}`, and a template
+//! can `{{#extends}}` a registered base, overriding its `{{#block}}`
+//! sections. See the `parser` module for the full syntax, and [`backend`]
+//! for templates written in Handlebars, Tera or Liquid instead.
+//!
+//! ```
+//! use cc_scaffold::template_engine::{render, Context};
+//!
+//! let mut ctx = Context::new();
+//! ctx.substitute("function_name", "parse");
+//! ctx.substitute("Brief description", "Parses input.");
+//! let out = render("/// [Brief description]\nfn function_name() {}\n", &ctx).unwrap();
+//! assert_eq!(out, "/// Parses input.\nfn parse() {}\n");
+//! ```
+
+pub mod backend;
+pub mod filters;
+mod parser;
+mod render;
+mod value;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+pub use backend::TemplateBackend;
+pub use parser::{Condition, Node, Span};
+pub use value::Value;
+
+use crate::error::{Error, Result};
+pub(crate) use parser::is_identifier;
+use parser::LineIndex;
+use render::at_boundary;
+
+/// Variable values plus the placeholders in the source they replace.
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    values: BTreeMap<String, Value>,
+    identifiers: BTreeMap<String, String>,
+    prose: BTreeMap<String, String>,
+    partials: BTreeMap<String, Template>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a variable, available to `{{ name }}` expressions.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<Value>) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    /// Returns a variable's value.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    /// Makes `placeholder` in the source render as `variable`.
+    ///
+    /// A placeholder wrapped in brackets (`[Description]`) is a prose
+    /// placeholder; anything else is treated as an identifier.
+    pub fn bind(&mut self, placeholder: &str, variable: impl Into<String>) {
+        let variable = variable.into();
+        match placeholder
+            .strip_prefix('[')
+            .and_then(|p| p.strip_suffix(']'))
+        {
+            Some(prose) => self.prose.insert(prose.to_string(), variable),
+            None => self.identifiers.insert(placeholder.to_string(), variable),
+        };
+    }
+
+    /// Sets a variable named after `placeholder` and binds it, the shorthand
+    /// used by `--set key=value`. Keys that are not identifiers are taken to
+    /// be prose placeholders with or without their brackets.
+    pub fn substitute(&mut self, placeholder: &str, value: impl Into<Value>) {
+        let name = placeholder
+            .strip_prefix('[')
+            .and_then(|p| p.strip_suffix(']'))
+            .unwrap_or(placeholder);
+        self.set(name, value);
+        if is_identifier(name) {
+            self.bind(name, name);
+        } else {
+            self.bind(&format!("[{name}]"), name);
+        }
+    }
+
+    /// Registers `template` as the partial included by `{{> name}}`, or the
+    /// base template of `{{#extends name}}`.
+    pub fn add_partial(&mut self, name: impl Into<String>, template: Template) {
+        self.partials.insert(name.into(), template);
+    }
+
+    /// Whether a partial called `name` has been registered.
+    pub fn has_partial(&self, name: &str) -> bool {
+        self.partials.contains_key(name)
+    }
+
+    pub(crate) fn lookup(&self, path: &[String]) -> Option<&Value> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.values.get(first)?, |value, field| value.field(field))
+    }
+}
+
+/// Parses and renders `source` in one step.
+pub fn render(source: &str, ctx: &Context) -> Result<String> {
+    Template::parse(source)?.render(ctx)
+}
+
+/// A parsed template that can be rendered repeatedly. Serializing it keeps
+/// only the node tree, not the file it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Template {
+    nodes: Vec<Node>,
+    #[serde(skip)]
+    file: Option<PathBuf>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self> {
+        Ok(Self {
+            nodes: parser::parse(source)?,
+            file: None,
+        })
+    }
+
+    /// Reads and parses the template at `path`. Parse and render errors
+    /// name the file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+        Self::parse(&source)
+            .map(|template| template.with_file(path))
+            .map_err(|err| err.in_file(path))
+    }
+
+    /// Records the file the template came from, so render errors name it.
+    pub fn with_file(mut self, path: &Path) -> Self {
+        self.file = Some(path.to_path_buf());
+        self
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// File the template was loaded from.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Names of the partials and base template the template refers to
+    /// directly, in order of first use.
+    pub fn includes(&self) -> Vec<&str> {
+        fn walk<'a>(nodes: &'a [Node], names: &mut Vec<&'a str>) {
+            for node in nodes {
+                match node {
+                    Node::Partial { name, .. } => {
+                        if !names.contains(&name.as_str()) {
+                            names.push(name);
+                        }
+                    }
+                    Node::If {
+                        then, otherwise, ..
+                    } => {
+                        walk(then, names);
+                        walk(otherwise, names);
+                    }
+                    Node::Each {
+                        body, otherwise, ..
+                    } => {
+                        walk(body, names);
+                        walk(otherwise, names);
+                    }
+                    Node::Block { body, .. } => walk(body, names),
+                    Node::Extends { base, blocks, .. } => {
+                        if !names.contains(&base.as_str()) {
+                            names.push(base);
+                        }
+                        for body in blocks.values() {
+                            walk(body, names);
+                        }
+                    }
+                    Node::Text(_) | Node::Prose { .. } | Node::Expr { .. } => {}
+                }
+            }
+        }
+        let mut names = Vec::new();
+        walk(&self.nodes, &mut names);
+        names
+    }
+
+    pub fn render(&self, ctx: &Context) -> Result<String> {
+        render::render(self, ctx)
+    }
+}
+
+/// Finds the first use of `placeholder` in `source`: an identifier at a
+/// rename boundary, a bracketed prose phrase, or a `{{ placeholder }}`
+/// expression.
+pub fn find_placeholder(source: &str, placeholder: &str) -> Option<Span> {
+    if placeholder.is_empty() {
+        return None;
+    }
+    let offset = if placeholder.starts_with('[') {
+        source.find(placeholder)
+    } else {
+        source
+            .match_indices(placeholder)
+            .map(|(i, _)| i)
+            .find(|&i| at_boundary(source, i, placeholder))
+    }
+    .or_else(|| {
+        source.match_indices("{{").map(|(i, _)| i).find(|&i| {
+            source[i + 2..]
+                .trim_start()
+                .strip_prefix(placeholder)
+                .is_some_and(|rest| rest.trim_start().starts_with("}}"))
+        })
+    })?;
+    Some(LineIndex::new(source).span(offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FUNCTION: &str = include_str!("../../../templates/rust/function-template.rs");
+    const TEST_HEADER: &str = include_str!("../../../templates/rust/partials/test-header.rs");
+
+    #[test]
+    fn test_identifier_renames_follow_word_parts() {
+        let mut ctx = Context::new();
+        ctx.substitute("function_name", "parse");
+        let out = render(
+            "fn function_name() {}\nfn test_function_name_smoke() {}\nfn function_names() {}",
+            &ctx,
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "fn parse() {}\nfn test_parse_smoke() {}\nfn function_names() {}"
+        );
+    }
+
+    #[test]
+    fn test_camel_case_boundaries() {
+        let mut ctx = Context::new();
+        ctx.substitute("FunctionResult", "ParseOutput");
+        let out = render(
+            "FunctionResult MyFunctionResultBuilder FunctionResults",
+            &ctx,
+        )
+        .unwrap();
+        assert_eq!(out, "ParseOutput MyParseOutputBuilder FunctionResults");
+    }
+
+    #[test]
+    fn test_longest_placeholder_wins() {
+        let mut ctx = Context::new();
+        ctx.substitute("param1", "a");
+        ctx.substitute("param10", "b");
+        assert_eq!(render("param1 param10", &ctx).unwrap(), "a b");
+    }
+
+    #[test]
+    fn test_replacement_is_not_rescanned() {
+        let mut ctx = Context::new();
+        ctx.substitute("param1", "param2");
+        ctx.substitute("param2", "param1");
+        assert_eq!(render("param1 param2", &ctx).unwrap(), "param2 param1");
+    }
+
+    #[test]
+    fn test_prose_placeholders() {
+        let mut ctx = Context::new();
+        ctx.substitute("[Description of param1]", "The input text");
+        ctx.substitute("param1", "input");
+        let out = render(
+            "/// * `param1` - [Description of param1]\n/// [Unbound param1 note]",
+            &ctx,
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "/// * `input` - The input text\n/// [Unbound input note]"
+        );
+    }
+
+    #[test]
+    fn test_explicit_binding_to_named_variable() {
+        let mut ctx = Context::new();
+        ctx.set("name", "parse");
+        ctx.bind("function_name", "name");
+        assert_eq!(
+            render("fn function_name() -> {{ name }}", &ctx).unwrap(),
+            "fn parse() -> parse"
+        );
+    }
+
+    #[test]
+    fn test_undefined_expression_is_an_error() {
+        let err = render("a\n{{ missing }}", &Context::new()).unwrap_err();
+        assert_eq!(err.to_string(), "2:1: undefined variable `missing`");
+    }
+
+    #[test]
+    fn test_expression_with_field_path() {
+        let mut ctx = Context::new();
+        let mut map = BTreeMap::new();
+        map.insert("name".to_string(), Value::from("x"));
+        ctx.set("param", Value::Map(map));
+        assert_eq!(render("{{ param.name }}", &ctx).unwrap(), "x");
+    }
+
+    #[test]
+    fn test_filters_derive_names_from_one_variable() {
+        let mut ctx = Context::new();
+        ctx.set("name", "parse_header");
+        let out = render(
+            "pub struct {{ name | pascal_case }}Result;\n\
+             pub fn {{ name }}() {}\n\
+             const {{ name | shouty }}_LIMIT: usize = 8;\n\
+             fn test_{{ name | snake_case }}_smoke() {}\n",
+            &ctx,
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "pub struct ParseHeaderResult;\n\
+             pub fn parse_header() {}\n\
+             const PARSE_HEADER_LIMIT: usize = 8;\n\
+             fn test_parse_header_smoke() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_conditional_sections() {
+        let source = "pub {{#if async}}async {{/if}}fn run() {}\n\
+                      {{#if tracing}}\n\
+                      #[tracing::instrument]\n\
+                      {{else}}\n\
+                      // no tracing\n\
+                      {{/if}}\n\
+                      {{#unless async}}\n\
+                      #[test]\n\
+                      {{/unless}}\n";
+        let mut ctx = Context::new();
+        ctx.set("async", true);
+        assert_eq!(
+            render(source, &ctx).unwrap(),
+            "pub async fn run() {}\n// no tracing\n"
+        );
+
+        let mut ctx = Context::new();
+        ctx.set("tracing", "yes");
+        assert_eq!(
+            render(source, &ctx).unwrap(),
+            "pub fn run() {}\n#[tracing::instrument]\n#[test]\n"
+        );
+    }
+
+    #[test]
+    fn test_conditional_comparison() {
+        let source = "{{#if runtime == \"tokio\"}}tokio{{else}}other{{/if}}";
+        let mut ctx = Context::new();
+        ctx.set("runtime", "tokio");
+        assert_eq!(render(source, &ctx).unwrap(), "tokio");
+        ctx.set("runtime", "smol");
+        assert_eq!(render(source, &ctx).unwrap(), "other");
+    }
+
+    #[test]
+    fn test_each_over_typed_params() {
+        let source = "pub fn run(\
+                      {{#each params}}{{ name }}: {{ type }}{{#unless @last}}, {{/unless}}{{/each}}\
+                      ) {}\n\
+                      {{#each params}}\n\
+                      // {{ @index }}: {{ this.name }} of {{ kind }}\n\
+                      {{/each}}\n";
+        let param = |name: &str, ty: &str| {
+            Value::Map(BTreeMap::from([
+                ("name".to_string(), Value::from(name)),
+                ("type".to_string(), Value::from(ty)),
+            ]))
+        };
+        let mut ctx = Context::new();
+        ctx.set("kind", "run");
+        ctx.set(
+            "params",
+            Value::List(vec![param("input", "&str"), param("limit", "usize")]),
+        );
+        assert_eq!(
+            render(source, &ctx).unwrap(),
+            "pub fn run(input: &str, limit: usize) {}\n\
+             // 0: input of run\n\
+             // 1: limit of run\n"
+        );
+    }
+
+    #[test]
+    fn test_each_empty_list_and_errors() {
+        let source = "{{#each cases}}{{ this }};{{else}}none{{/each}}";
+        let mut ctx = Context::new();
+        assert_eq!(render(source, &ctx).unwrap(), "none");
+        ctx.set("cases", vec!["a", "b"]);
+        assert_eq!(render(source, &ctx).unwrap(), "a;b;");
+        ctx.set("cases", "a");
+        assert_eq!(
+            render(source, &ctx).unwrap_err().to_string(),
+            "1:1: `cases` is not a list"
+        );
+        assert!(render("{{ @index }}", &ctx).is_err());
+    }
+
+    #[test]
+    fn test_partials_are_indented_and_share_variables() {
+        let mut ctx = Context::new();
+        ctx.substitute("function_name", "parse");
+        ctx.add_partial(
+            "header",
+            Template::parse("use super::*;\n\n// tests for function_name\n").unwrap(),
+        );
+        let template = Template::parse("mod tests {\n    {{> header}}\n}\n").unwrap();
+        assert_eq!(template.includes(), ["header"]);
+        assert_eq!(
+            template.render(&ctx).unwrap(),
+            "mod tests {\n    use super::*;\n\n    // tests for parse\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_partial_errors() {
+        let mut ctx = Context::new();
+        assert_eq!(
+            render("x\n{{> missing}}", &ctx).unwrap_err().to_string(),
+            "2:1: unknown partial `missing`"
+        );
+        ctx.add_partial("a", Template::parse("{{> b}}").unwrap());
+        ctx.add_partial("b", Template::parse("{{> a}}").unwrap());
+        assert!(render("{{> a}}", &ctx)
+            .unwrap_err()
+            .to_string()
+            .contains("partial `a` includes itself"));
+    }
+
+    #[test]
+    fn test_extends_overrides_blocks() {
+        let base = "{{#block signature}}pub fn function_name(){{/block}} {\n\
+                    {{#block body}}    todo!(){{/block}}\n\
+                    }\n\
+                    {{#block attr}}#[test]{{/block}}\nfn a() {}\n\
+                    {{#block attr}}#[test]{{/block}}\nfn b() {}\n";
+        let mut ctx = Context::new();
+        ctx.substitute("function_name", "run");
+        ctx.add_partial("base", Template::parse(base).unwrap());
+        ctx.add_partial(
+            "middle",
+            Template::parse("{{#extends base}}{{#block attr}}#[tokio::test]{{/block}}{{/extends}}")
+                .unwrap(),
+        );
+        let child = Template::parse(
+            "{{#extends middle}}\n\
+             {{#block signature}}pub async fn function_name(){{/block}}\n\
+             {{#block attr}}#[tokio::test(flavor = \"multi_thread\")]{{/block}}\n\
+             {{/extends}}\n",
+        )
+        .unwrap();
+        assert_eq!(child.includes(), ["middle"]);
+        assert_eq!(
+            child.render(&ctx).unwrap(),
+            "pub async fn run() {\n    todo!()\n}\n\
+             #[tokio::test(flavor = \"multi_thread\")]\nfn a() {}\n\
+             #[tokio::test(flavor = \"multi_thread\")]\nfn b() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_extends_errors_name_the_right_file() {
+        let mut ctx = Context::new();
+        ctx.add_partial(
+            "base",
+            Template::parse("{{#block a}}{{/block}}\n{{ missing }}")
+                .unwrap()
+                .with_file(Path::new("base.rs")),
+        );
+        let child =
+            Template::parse("{{#extends base}}\n{{#block a}}{{ other }}{{/block}}\n{{/extends}}")
+                .unwrap()
+                .with_file(Path::new("child.rs"));
+        assert_eq!(
+            child.render(&ctx).unwrap_err().to_string(),
+            "child.rs:2:13: undefined variable `other`"
+        );
+        ctx.set("other", "x");
+        assert_eq!(
+            child.render(&ctx).unwrap_err().to_string(),
+            "base.rs:2:1: undefined variable `missing`"
+        );
+        let orphan = Template::parse("{{#extends nowhere}}{{/extends}}").unwrap();
+        assert_eq!(
+            orphan.render(&ctx).unwrap_err().to_string(),
+            "1:1: unknown base template `nowhere`"
+        );
+    }
+
+    #[test]
+    fn test_find_placeholder() {
+        let source = "// [Module docs]\nfn test_function_name() {}\n  {{ flag }}\n";
+        assert_eq!(
+            find_placeholder(source, "function_name"),
+            Some(Span { line: 2, column: 9 })
+        );
+        assert_eq!(
+            find_placeholder(source, "[Module docs]"),
+            Some(Span { line: 1, column: 4 })
+        );
+        assert_eq!(
+            find_placeholder(source, "flag"),
+            Some(Span { line: 3, column: 6 })
+        );
+        assert_eq!(find_placeholder(source, "func"), None);
+    }
+
+    #[test]
+    fn test_function_template_renders_without_placeholders_left() {
+        let mut ctx = Context::new();
+        ctx.substitute("function_name", "normalize_key");
+        ctx.substitute("FunctionResult", "NormalizedKey");
+        ctx.substitute("param1", "raw");
+        ctx.substitute("param2", "limit");
+        ctx.substitute(
+            "Brief description of what this module does",
+            "Key normalization.",
+        );
+        ctx.substitute(
+            "Brief description of what the function does",
+            "Normalizes a raw key.",
+        );
+        ctx.substitute("Description of param1", "Raw key text");
+        ctx.substitute("Description of param2", "Maximum length");
+        ctx.set("test_vectors", Value::List(vec![]));
+        ctx.add_partial(
+            "partials/test-header",
+            Template::parse(TEST_HEADER).unwrap(),
+        );
+        let out = render(FUNCTION, &ctx).unwrap();
+
+        assert!(out.starts_with("// Key normalization.\n"));
+        assert!(out.contains("/// Normalizes a raw key.\n"));
+        assert!(out.contains("/// * `raw` - Raw key text\n"));
+        assert!(out.contains(
+            "pub fn normalize_key(raw: &str, limit: i32) -> Result<NormalizedKey, String>"
+        ));
+        assert!(out.contains("fn test_normalize_key_parametrized()"));
+        assert!(out.contains("\n#[cfg(test)]\nmod tests {\n    use super::*;\n\n    #[test]\n"));
+        assert!(out.contains("\"raw cannot be empty\""));
+        assert!(!out.contains("tracing"));
+        assert!(out.contains("(\"world\", 5, \"processed_world\", 10, false),\n"));
+        for leftover in [
+            "function_name",
+            "FunctionResult",
+            "param1",
+            "param2",
+            "[Brief",
+        ] {
+            assert!(!out.contains(leftover), "`{leftover}` left in output");
+        }
+    }
+}
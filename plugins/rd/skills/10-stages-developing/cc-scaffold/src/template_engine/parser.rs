@@ -0,0 +1,822 @@
+//! Parses template source into a tree of nodes.
+//!
+//! Recognised syntax:
+//!
+//! * prose placeholders — bracketed descriptions such as
+//!   `[Brief description of what the function does]`
+//! * expressions — `{{ name }}` or `{{ item.field }}`, optionally piped
+//!   through filters: `{{ name | pascal_case }}`
+//! * conditionals — `{{#if flag}} ... {{else}} ... {{/if}}` and
+//!   `{{#unless flag}} ... {{/unless}}`; the condition may also compare a
+//!   variable with a literal: `{{#if runtime == "tokio"}}`
+//! * loops — `{{#each items}} ... {{else}} ... {{/each}}`; inside the body
+//!   `{{ this }}` is the current item, `{{ field }}` or `{{ this.field }}`
+//!   one of its fields, and `@index`, `@first` and `@last` describe its
+//!   position. The `{{else}}` branch renders when the list is empty
+//! * partials — `{{> partials/test-header}}` inserts a shared fragment,
+//!   rendered with the same variables. A standalone partial tag's
+//!   indentation is applied to every line of the fragment
+//! * inheritance — a base template marks overridable sections with
+//!   `{{#block name}} ... {{/block}}`; a template consisting of
+//!   `{{#extends base}} ... {{/extends}}` renders the base with the
+//!   `{{#block}}` sections it contains replacing the base's own. A block
+//!   name may be used more than once, and every use is overridden
+//!
+//! Identifier placeholders (`function_name`, `param1`) are ordinary text and
+//! are renamed at render time. `\{{` is an escaped literal `{{`, and a `{{`
+//! that does not start a valid tag (e.g. `format!("{{}}")`) is left as text.
+//!
+//! A block tag alone on its line (ignoring whitespace) is "standalone": the
+//! whole line, including its newline, is dropped from the output so
+//! conditionals do not leave blank lines behind.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::filters;
+use crate::error::{Error, Result};
+
+/// 1-based line and column of a node in its template source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A parsed template fragment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Node {
+    /// Literal text, subject to identifier renaming.
+    Text(String),
+    /// A bracketed prose placeholder; `text` excludes the brackets.
+    Prose { text: String, span: Span },
+    /// A `{{ path | filter }}` expression; `filters` apply left to right.
+    Expr {
+        path: Vec<String>,
+        filters: Vec<String>,
+        span: Span,
+    },
+    /// An `{{#if}}` (or, with `negated`, `{{#unless}}`) block.
+    If {
+        condition: Condition,
+        negated: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+        span: Span,
+    },
+    /// An `{{#each}}` block, rendering `body` once per item of the list at
+    /// `path` or `otherwise` if it is empty.
+    Each {
+        path: Vec<String>,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+        span: Span,
+    },
+    /// A `{{#block name}}` section a derived template may override.
+    Block {
+        name: String,
+        body: Vec<Node>,
+        span: Span,
+    },
+    /// A `{{#extends base}}` template: `base` rendered with `blocks`
+    /// replacing its sections of the same name. Only valid as the sole
+    /// top-level node.
+    Extends {
+        base: String,
+        blocks: BTreeMap<String, Vec<Node>>,
+        span: Span,
+    },
+    /// A `{{> name}}` partial. `indent` is the whitespace before a
+    /// standalone tag, applied to each line of the partial.
+    Partial {
+        name: String,
+        indent: String,
+        span: Span,
+    },
+}
+
+/// The test of an `{{#if}}` block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Condition {
+    /// True when the variable is set and truthy.
+    Truthy(Vec<String>),
+    /// Compares the variable's rendered value with a literal.
+    Compare {
+        path: Vec<String>,
+        equal: bool,
+        literal: String,
+    },
+}
+
+impl Condition {
+    /// Variable path the condition reads.
+    pub fn path(&self) -> &[String] {
+        match self {
+            Condition::Truthy(path) | Condition::Compare { path, .. } => path,
+        }
+    }
+}
+
+/// Parses `source` into a node tree.
+pub fn parse(source: &str) -> Result<Vec<Node>> {
+    let tokens = lex(source)?;
+    let mut parser = Parser {
+        tokens: tokens.into_iter(),
+    };
+    let (mut nodes, end) = parser.block(None)?;
+    debug_assert!(end.is_none());
+    if let Some(index) = nodes.iter().position(|n| matches!(n, Node::Extends { .. })) {
+        let extends = nodes.remove(index);
+        if let Some(span) = nodes.iter().find_map(content_span) {
+            return Err(Error::template(
+                span,
+                "a template using `{{#extends}}` cannot have content outside it",
+            ));
+        }
+        nodes = vec![extends];
+    }
+    Ok(nodes)
+}
+
+/// Where a node outside of `{{#extends}}` produces output, if it does.
+/// Whitespace-only text counts as none; other text has no span of its own,
+/// so it is reported at the start of the template.
+fn content_span(node: &Node) -> Option<Span> {
+    match node {
+        Node::Text(text) if text.trim().is_empty() => None,
+        Node::Text(_) => Some(Span { line: 1, column: 1 }),
+        Node::Prose { span, .. }
+        | Node::Expr { span, .. }
+        | Node::If { span, .. }
+        | Node::Each { span, .. }
+        | Node::Block { span, .. }
+        | Node::Extends { span, .. }
+        | Node::Partial { span, .. } => Some(*span),
+    }
+}
+
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Prose(String, Span),
+    Tag(Tag, Span),
+    Partial(String, String, Span),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Tag {
+    Expr(Vec<String>, Vec<String>),
+    Open {
+        keyword: &'static str,
+        condition: Condition,
+    },
+    Each(Vec<String>),
+    Block(String),
+    Extends(String),
+    Else,
+    Close(String),
+    Partial(String),
+}
+
+impl Tag {
+    /// Whether the tag may stand alone on its line and take the line with it.
+    fn is_standalone(&self) -> bool {
+        !matches!(self, Tag::Expr(..))
+    }
+}
+
+fn lex(source: &str) -> Result<Vec<Token>> {
+    let lines = LineIndex::new(source);
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    // Whether anything other than whitespace or standalone tags has been
+    // emitted on the current line.
+    let mut line_has_content = false;
+    let mut i = 0;
+
+    while i < source.len() {
+        let rest = &source[i..];
+        if rest.starts_with("\\{{") {
+            text.push_str("{{");
+            line_has_content = true;
+            i += 3;
+            continue;
+        }
+        if rest.starts_with("{{") && is_tag_start(&rest[2..]) {
+            let span = lines.span(i);
+            let Some(end) = rest.find("}}") else {
+                return Err(Error::template(span, "unclosed `{{` tag"));
+            };
+            let tag = parse_tag(rest[2..end].trim(), span)?;
+            i += end + 2;
+            let mut indent = String::new();
+            if tag.is_standalone() && !line_has_content {
+                let tail = &source[i..];
+                let line_end = tail.find('\n').map_or(tail.len(), |n| n + 1);
+                if tail[..line_end].trim().is_empty() {
+                    let keep = text.trim_end_matches([' ', '\t']).len();
+                    indent = text.split_off(keep);
+                    i += line_end;
+                }
+            } else {
+                line_has_content = true;
+            }
+            flush(&mut tokens, &mut text);
+            tokens.push(match tag {
+                Tag::Partial(name) => Token::Partial(name, indent, span),
+                tag => Token::Tag(tag, span),
+            });
+            continue;
+        }
+        if let Some(len) = prose_len(rest) {
+            flush(&mut tokens, &mut text);
+            tokens.push(Token::Prose(rest[1..len - 1].to_string(), lines.span(i)));
+            line_has_content = true;
+            i += len;
+            continue;
+        }
+        let ch = rest.chars().next().expect("non-empty remainder");
+        text.push(ch);
+        if ch == '\n' {
+            line_has_content = false;
+        } else if !ch.is_whitespace() {
+            line_has_content = true;
+        }
+        i += ch.len_utf8();
+    }
+    flush(&mut tokens, &mut text);
+    Ok(tokens)
+}
+
+fn flush(tokens: &mut Vec<Token>, text: &mut String) {
+    if !text.is_empty() {
+        tokens.push(Token::Text(std::mem::take(text)));
+    }
+}
+
+struct Parser {
+    tokens: std::vec::IntoIter<Token>,
+}
+
+/// How a block of nodes ended.
+enum End {
+    Else(Span),
+    Close,
+}
+
+impl Parser {
+    /// Parses nodes until the closing tag of `open` (a keyword and the span
+    /// of its opening tag), or end of input at the top level.
+    fn block(&mut self, open: Option<(&str, Span)>) -> Result<(Vec<Node>, Option<End>)> {
+        let mut nodes: Vec<Node> = Vec::new();
+        while let Some(token) = self.tokens.next() {
+            match token {
+                Token::Text(text) => match nodes.last_mut() {
+                    Some(Node::Text(prev)) => prev.push_str(&text),
+                    _ => nodes.push(Node::Text(text)),
+                },
+                Token::Prose(text, span) => nodes.push(Node::Prose { text, span }),
+                Token::Partial(name, indent, span) => {
+                    nodes.push(Node::Partial { name, indent, span })
+                }
+                Token::Tag(Tag::Expr(path, filters), span) => nodes.push(Node::Expr {
+                    path,
+                    filters,
+                    span,
+                }),
+                Token::Tag(Tag::Open { keyword, condition }, span) => {
+                    nodes.push(self.conditional(keyword, condition, span)?);
+                }
+                Token::Tag(Tag::Each(path), span) => {
+                    let (body, otherwise) = self.branches("each", span)?;
+                    nodes.push(Node::Each {
+                        path,
+                        body,
+                        otherwise,
+                        span,
+                    });
+                }
+                Token::Tag(Tag::Block(name), span) => {
+                    let (body, end) = self.block(Some(("block", span)))?;
+                    if let Some(End::Else(span)) = end {
+                        return Err(Error::template(
+                            span,
+                            "`{{else}}` is not allowed in `{{#block}}`",
+                        ));
+                    }
+                    nodes.push(Node::Block { name, body, span });
+                }
+                Token::Tag(Tag::Extends(base), span) => {
+                    if open.is_some() {
+                        return Err(Error::template(
+                            span,
+                            "`{{#extends}}` must be at the top level",
+                        ));
+                    }
+                    if nodes.iter().any(|n| matches!(n, Node::Extends { .. })) {
+                        return Err(Error::template(span, "duplicate `{{#extends}}`"));
+                    }
+                    nodes.push(self.extends(base, span)?);
+                }
+                Token::Tag(Tag::Else, span) => {
+                    if open.is_none() {
+                        return Err(Error::template(span, "`{{else}}` outside of a block"));
+                    }
+                    return Ok((nodes, Some(End::Else(span))));
+                }
+                Token::Tag(Tag::Partial(_), _) => unreachable!("lexed as Token::Partial"),
+                Token::Tag(Tag::Close(keyword), span) => match open {
+                    Some((expected, _)) if expected == keyword => {
+                        return Ok((nodes, Some(End::Close)));
+                    }
+                    Some((expected, _)) => {
+                        return Err(Error::template(
+                            span,
+                            format!("expected `{{{{/{expected}}}}}`, found `{{{{/{keyword}}}}}`"),
+                        ))
+                    }
+                    None => {
+                        return Err(Error::template(
+                            span,
+                            format!("`{{{{/{keyword}}}}}` without a matching opening tag"),
+                        ))
+                    }
+                },
+            }
+        }
+        match open {
+            Some((keyword, span)) => Err(Error::template(
+                span,
+                format!("`{{{{#{keyword}}}}}` is never closed"),
+            )),
+            None => Ok((nodes, None)),
+        }
+    }
+
+    fn conditional(&mut self, keyword: &str, condition: Condition, span: Span) -> Result<Node> {
+        let (then, otherwise) = self.branches(keyword, span)?;
+        Ok(Node::If {
+            condition,
+            negated: keyword == "unless",
+            then,
+            otherwise,
+            span,
+        })
+    }
+
+    /// Parses the overrides of an `{{#extends}}` block. Only `{{#block}}`
+    /// sections and whitespace may appear inside it.
+    fn extends(&mut self, base: String, span: Span) -> Result<Node> {
+        let (body, end) = self.block(Some(("extends", span)))?;
+        if let Some(End::Else(span)) = end {
+            return Err(Error::template(
+                span,
+                "`{{else}}` is not allowed in `{{#extends}}`",
+            ));
+        }
+        let mut blocks = BTreeMap::new();
+        for node in body {
+            match node {
+                Node::Block { name, body, span } => {
+                    if blocks.insert(name.clone(), body).is_some() {
+                        return Err(Error::template(
+                            span,
+                            format!("block `{name}` is overridden twice"),
+                        ));
+                    }
+                }
+                other => {
+                    if let Some(span) = content_span(&other) {
+                        return Err(Error::template(
+                            span,
+                            "only `{{#block}}` overrides may appear in `{{#extends}}`",
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(Node::Extends { base, blocks, span })
+    }
+
+    /// Parses the body of a block opened by `keyword` at `span` and its
+    /// optional `{{else}}` branch.
+    fn branches(&mut self, keyword: &str, span: Span) -> Result<(Vec<Node>, Vec<Node>)> {
+        let (body, end) = self.block(Some((keyword, span)))?;
+        let otherwise = match end {
+            Some(End::Else(_)) => match self.block(Some((keyword, span)))? {
+                (nodes, Some(End::Close)) => nodes,
+                (_, Some(End::Else(span))) => {
+                    return Err(Error::template(span, "duplicate `{{else}}` in block"))
+                }
+                (_, None) => unreachable!("nested blocks end with a tag or an error"),
+            },
+            _ => Vec::new(),
+        };
+        Ok((body, otherwise))
+    }
+}
+
+/// Whether the text after `{{` begins a tag rather than literal braces.
+fn is_tag_start(after: &str) -> bool {
+    let after = after.trim_start_matches([' ', '\t']);
+    after.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '@')
+        || ((after.starts_with('#') || after.starts_with('/'))
+            && after[1..].starts_with(|c: char| c.is_ascii_alphabetic()))
+        || (after.starts_with('>')
+            && after[1..]
+                .trim_start()
+                .starts_with(|c: char| c.is_ascii_alphanumeric()))
+}
+
+fn parse_tag(inner: &str, span: Span) -> Result<Tag> {
+    let invalid = || Error::template(span, format!("invalid tag `{{{{{inner}}}}}`"));
+    if inner == "else" {
+        return Ok(Tag::Else);
+    }
+    if let Some(name) = inner.strip_prefix('>') {
+        return parse_template_name(name)
+            .map(Tag::Partial)
+            .ok_or_else(invalid);
+    }
+    if let Some(keyword) = inner.strip_prefix('/') {
+        return Ok(Tag::Close(keyword.trim().to_string()));
+    }
+    if let Some(rest) = inner.strip_prefix('#') {
+        let (keyword, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let keyword = match keyword {
+            "each" => {
+                return parse_path(args)
+                    .filter(|path| !is_position(path))
+                    .map(Tag::Each)
+                    .ok_or_else(invalid)
+            }
+            "block" if is_identifier(args.trim()) => {
+                return Ok(Tag::Block(args.trim().to_string()))
+            }
+            "extends" => {
+                return parse_template_name(args)
+                    .map(Tag::Extends)
+                    .ok_or_else(invalid)
+            }
+            "block" => return Err(invalid()),
+            "if" => "if",
+            "unless" => "unless",
+            other => {
+                return Err(Error::template(
+                    span,
+                    format!("unknown block `{{{{#{other}}}}}`"),
+                ))
+            }
+        };
+        let condition = parse_condition(args.trim()).ok_or_else(invalid)?;
+        return Ok(Tag::Open { keyword, condition });
+    }
+    let mut parts = inner.split('|');
+    let path = parts.next().and_then(parse_path).ok_or_else(invalid)?;
+    let filters = parts
+        .map(|name| {
+            let name = name.trim();
+            if filters::is_filter(name) {
+                Ok(name.to_string())
+            } else {
+                Err(Error::template(span, format!("unknown filter `{name}`")))
+            }
+        })
+        .collect::<Result<_>>()?;
+    Ok(Tag::Expr(path, filters))
+}
+
+fn parse_condition(args: &str) -> Option<Condition> {
+    for (op, equal) in [("==", true), ("!=", false)] {
+        if let Some((lhs, rhs)) = args.split_once(op) {
+            let literal = rhs.trim().strip_prefix('"')?.strip_suffix('"')?;
+            return Some(Condition::Compare {
+                path: parse_path(lhs)?,
+                equal,
+                literal: literal.to_string(),
+            });
+        }
+    }
+    parse_path(args).map(Condition::Truthy)
+}
+
+/// Parses the name of a partial or base template: a relative path such as
+/// `partials/test-header` that cannot climb out with `..`.
+fn parse_template_name(s: &str) -> Option<String> {
+    let name = s.trim();
+    let valid = !name.is_empty()
+        && name.split('/').all(|part| {
+            !part.is_empty()
+                && part != ".."
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        });
+    valid.then(|| name.to_string())
+}
+
+/// Parses a dotted variable path. `@index`, `@first` and `@last` are only
+/// valid on their own.
+fn parse_path(s: &str) -> Option<Vec<String>> {
+    let path: Vec<String> = s.trim().split('.').map(|p| p.trim().to_string()).collect();
+    (is_position(&path) || path.iter().all(|p| is_identifier(p))).then_some(path)
+}
+
+fn is_position(path: &[String]) -> bool {
+    matches!(path, [name] if matches!(name.as_str(), "@index" | "@first" | "@last"))
+}
+
+/// Returns the byte length of a prose placeholder at the start of `text`.
+///
+/// A prose placeholder is `[` followed by a capitalised phrase on a single
+/// line and a closing `]`. The first space must follow a letter, which keeps
+/// code such as `[Value; 3]` or `[Ok, Err]` from being mistaken for prose.
+fn prose_len(text: &str) -> Option<usize> {
+    let body = text.strip_prefix('[')?;
+    if !body.starts_with(|c: char| c.is_uppercase()) {
+        return None;
+    }
+    let end = body.find([']', '[', '\n'])?;
+    if !body[end..].starts_with(']') {
+        return None;
+    }
+    let inner = &body[..end];
+    let space = inner.find(' ')?;
+    if !inner[..space].ends_with(|c: char| c.is_alphabetic()) {
+        return None;
+    }
+    Some(end + 2)
+}
+
+/// Whether `s` is a non-empty ASCII identifier.
+pub(crate) fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Maps byte offsets to line/column positions.
+pub(crate) struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub(crate) fn new(source: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { starts }
+    }
+
+    pub(crate) fn span(&self, offset: usize) -> Span {
+        let line = self.starts.partition_point(|&start| start <= offset);
+        Span {
+            line,
+            column: offset - self.starts[line - 1] + 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Node {
+        Node::Text(s.into())
+    }
+
+    #[test]
+    fn test_parse_prose_and_expressions() {
+        let nodes = parse("// [Brief description here]\nfn {{ name }}() {}\n").unwrap();
+        assert_eq!(
+            nodes,
+            vec![
+                text("// "),
+                Node::Prose {
+                    text: "Brief description here".into(),
+                    span: Span { line: 1, column: 4 },
+                },
+                text("\nfn "),
+                Node::Expr {
+                    path: vec!["name".into()],
+                    filters: vec![],
+                    span: Span { line: 2, column: 4 },
+                },
+                text("() {}\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rust_brackets_are_not_prose() {
+        let source = "#[derive(Debug)]\nlet a: [Value; 3];\nlet b = [Ok, Err];\nlet c = vec![x];\n";
+        assert_eq!(parse(source).unwrap(), vec![Node::Text(source.into())]);
+    }
+
+    #[test]
+    fn test_literal_braces_are_text() {
+        let source = "format!(\"{{}}\"); \\{{ name }} {{/}}";
+        assert_eq!(
+            parse(source).unwrap(),
+            vec![text("format!(\"{{}}\"); {{ name }} {{/}}")]
+        );
+    }
+
+    #[test]
+    fn test_dotted_path() {
+        let nodes = parse("{{item.name}}").unwrap();
+        assert!(matches!(&nodes[0], Node::Expr { path, .. } if path == &["item", "name"]));
+    }
+
+    #[test]
+    fn test_filters() {
+        let nodes = parse("{{ name | snake_case|upper }}").unwrap();
+        assert!(matches!(
+            &nodes[0],
+            Node::Expr { filters, .. } if filters == &["snake_case", "upper"]
+        ));
+        assert_eq!(
+            parse("\n {{ name | reverse }}").unwrap_err().to_string(),
+            "2:2: unknown filter `reverse`"
+        );
+        assert!(parse("{{ name | }}").is_err());
+    }
+
+    #[test]
+    fn test_unclosed_tag_reports_position() {
+        let err = parse("line one\n  {{ name").unwrap_err();
+        assert_eq!(err.to_string(), "2:3: unclosed `{{` tag");
+    }
+
+    #[test]
+    fn test_invalid_expression() {
+        assert!(parse("{{ a b }}").is_err());
+    }
+
+    #[test]
+    fn test_standalone_block_lines_are_removed() {
+        let nodes = parse("a\n  {{#if flag}}\n  b\n  {{else}}\n  c\n{{/if}}\nd\n").unwrap();
+        assert_eq!(
+            nodes,
+            vec![
+                text("a\n"),
+                Node::If {
+                    condition: Condition::Truthy(vec!["flag".into()]),
+                    negated: false,
+                    then: vec![text("  b\n")],
+                    otherwise: vec![text("  c\n")],
+                    span: Span { line: 2, column: 3 },
+                },
+                text("d\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_block_keeps_surrounding_text() {
+        let nodes = parse("pub {{#if async}}async {{/if}}fn x()\n").unwrap();
+        assert_eq!(nodes[0], text("pub "));
+        assert!(matches!(&nodes[1], Node::If { then, .. } if then == &[text("async ")]));
+        assert_eq!(nodes[2], text("fn x()\n"));
+    }
+
+    #[test]
+    fn test_unless_and_comparison() {
+        let nodes = parse("{{#unless runtime == \"tokio\"}}x{{/unless}}").unwrap();
+        assert_eq!(
+            nodes,
+            vec![Node::If {
+                condition: Condition::Compare {
+                    path: vec!["runtime".into()],
+                    equal: true,
+                    literal: "tokio".into(),
+                },
+                negated: true,
+                then: vec![text("x")],
+                otherwise: vec![],
+                span: Span { line: 1, column: 1 },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_each_with_else() {
+        let nodes = parse("{{#each params}}\n{{ name }}{{#unless @last}}, {{/unless}}\n{{else}}\nnone\n{{/each}}\n").unwrap();
+        assert_eq!(
+            nodes,
+            vec![Node::Each {
+                path: vec!["params".into()],
+                body: vec![
+                    Node::Expr {
+                        path: vec!["name".into()],
+                        filters: vec![],
+                        span: Span { line: 2, column: 1 },
+                    },
+                    Node::If {
+                        condition: Condition::Truthy(vec!["@last".into()]),
+                        negated: true,
+                        then: vec![text(", ")],
+                        otherwise: vec![],
+                        span: Span {
+                            line: 2,
+                            column: 11
+                        },
+                    },
+                    text("\n"),
+                ],
+                otherwise: vec![text("none\n")],
+                span: Span { line: 1, column: 1 },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_partials() {
+        let nodes = parse("mod tests {\n    {{> partials/test-header}}\n}\n{{>x}}").unwrap();
+        assert_eq!(
+            nodes,
+            vec![
+                text("mod tests {\n"),
+                Node::Partial {
+                    name: "partials/test-header".into(),
+                    indent: "    ".into(),
+                    span: Span { line: 2, column: 5 },
+                },
+                text("}\n"),
+                Node::Partial {
+                    name: "x".into(),
+                    indent: String::new(),
+                    span: Span { line: 4, column: 1 },
+                },
+            ]
+        );
+        assert!(parse("{{> partials/../secrets}}").is_err());
+        assert_eq!(parse("a {{>}}").unwrap(), vec![text("a {{>}}")]);
+    }
+
+    #[test]
+    fn test_extends_collects_block_overrides() {
+        let nodes = parse(
+            "{{#extends function}}\n{{#block signature}}pub async fn f(){{/block}}\n{{/extends}}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            nodes,
+            vec![Node::Extends {
+                base: "function".into(),
+                blocks: BTreeMap::from([("signature".to_string(), vec![text("pub async fn f()")])]),
+                span: Span { line: 1, column: 1 },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_extends_errors() {
+        let err = |s| parse(s).unwrap_err().to_string();
+        assert_eq!(
+            err("fn x() {}\n{{#extends base}}{{/extends}}"),
+            "1:1: a template using `{{#extends}}` cannot have content outside it"
+        );
+        assert_eq!(
+            err("{{#extends base}}\n{{ name }}\n{{/extends}}"),
+            "2:1: only `{{#block}}` overrides may appear in `{{#extends}}`"
+        );
+        assert_eq!(
+            err("{{#extends base}}{{#block a}}{{/block}}{{#block a}}{{/block}}{{/extends}}"),
+            "1:40: block `a` is overridden twice"
+        );
+        assert_eq!(
+            err("{{#if x}}{{#extends base}}{{/extends}}{{/if}}"),
+            "1:10: `{{#extends}}` must be at the top level"
+        );
+        assert!(err("{{#block a b}}{{/block}}").contains("invalid tag"));
+    }
+
+    #[test]
+    fn test_block_errors() {
+        let err = |s| parse(s).unwrap_err().to_string();
+        assert_eq!(err("x\n{{#if a}}\ny"), "2:1: `{{#if}}` is never closed");
+        assert_eq!(
+            err("{{/if}}"),
+            "1:1: `{{/if}}` without a matching opening tag"
+        );
+        assert_eq!(
+            err("{{#if a}}{{/unless}}"),
+            "1:10: expected `{{/if}}`, found `{{/unless}}`"
+        );
+        assert_eq!(err("{{else}}"), "1:1: `{{else}}` outside of a block");
+        assert_eq!(
+            err("{{#if a}}{{else}}{{else}}{{/if}}"),
+            "1:18: duplicate `{{else}}` in block"
+        );
+        assert_eq!(err("{{#bogus a}}"), "1:1: unknown block `{{#bogus}}`");
+        assert_eq!(
+            err("{{#each a}}{{/if}}"),
+            "1:12: expected `{{/each}}`, found `{{/if}}`"
+        );
+        assert!(err("{{#each @index}}{{/each}}").contains("invalid tag"));
+        assert!(err("{{ a.@index }}").contains("invalid tag"));
+        assert!(err("{{#if}}{{/if}}").contains("invalid tag"));
+    }
+}
@@ -0,0 +1,348 @@
+//! Renders a parsed node tree against a [`Context`].
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::filters;
+use super::parser::{Condition, Node, Span};
+use super::{Context, Template, Value};
+use crate::error::{Error, Result};
+
+/// How deeply partials and base templates may nest before rendering gives
+/// up.
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+/// Renders `template` to a string.
+pub(super) fn render(template: &Template, ctx: &Context) -> Result<String> {
+    let mut renderer = Renderer {
+        ctx,
+        renames: Renames::new(ctx)?,
+        active: Vec::new(),
+        overrides: Vec::new(),
+        file: template.file(),
+    };
+    let mut out = String::new();
+    renderer
+        .nodes(template.nodes(), None, &mut out)
+        .map_err(|err| in_file(err, template.file()))?;
+    Ok(out)
+}
+
+/// The item an `{{#each}}` body is being rendered for. Scopes nest, and
+/// names not found on the innermost item are looked up in the outer ones
+/// and finally in the [`Context`].
+struct Scope<'a> {
+    item: &'a Value,
+    index: usize,
+    len: usize,
+    parent: Option<&'a Scope<'a>>,
+}
+
+/// The `{{#block}}` overrides of one `{{#extends}}` template.
+type Blocks = BTreeMap<String, Vec<Node>>;
+
+struct Renderer<'a> {
+    ctx: &'a Context,
+    renames: Renames,
+    /// Partials and base templates currently being rendered, outermost
+    /// first.
+    active: Vec<&'a str>,
+    /// `{{#block}}` overrides of the `{{#extends}}` templates being
+    /// rendered, most derived first, with the file each came from.
+    overrides: Vec<(&'a Blocks, Option<&'a Path>)>,
+    /// File of the template whose nodes are being rendered.
+    file: Option<&'a Path>,
+}
+
+impl<'a> Renderer<'a> {
+    fn nodes(
+        &mut self,
+        nodes: &'a [Node],
+        scope: Option<&Scope<'_>>,
+        out: &mut String,
+    ) -> Result<()> {
+        let ctx = self.ctx;
+        for node in nodes {
+            match node {
+                Node::Text(text) => self.renames.apply(text, out),
+                Node::Prose { text, span } => match ctx.prose.get(text) {
+                    Some(var) => out.push_str(&resolve(ctx, var, *span)?.to_string()),
+                    None => self.renames.apply(&format!("[{text}]"), out),
+                },
+                Node::Expr {
+                    path,
+                    filters,
+                    span,
+                } => match lookup(ctx, scope, path) {
+                    Some(value) => {
+                        out.push_str(&filters.iter().fold(value.to_string(), |text, filter| {
+                            filters::apply(filter, &text)
+                        }))
+                    }
+                    None => {
+                        return Err(Error::template(
+                            *span,
+                            format!("undefined variable `{}`", path.join(".")),
+                        ))
+                    }
+                },
+                Node::If {
+                    condition,
+                    negated,
+                    then,
+                    otherwise,
+                    ..
+                } => {
+                    let branch = if evaluate(condition, ctx, scope) != *negated {
+                        then
+                    } else {
+                        otherwise
+                    };
+                    self.nodes(branch, scope, out)?;
+                }
+                Node::Each {
+                    path,
+                    body,
+                    otherwise,
+                    span,
+                } => {
+                    let list = lookup(ctx, scope, path);
+                    let items = match list.as_deref() {
+                        None => &[][..],
+                        Some(Value::List(items)) => items,
+                        Some(_) => {
+                            return Err(Error::template(
+                                *span,
+                                format!("`{}` is not a list", path.join(".")),
+                            ))
+                        }
+                    };
+                    if items.is_empty() {
+                        self.nodes(otherwise, scope, out)?;
+                    }
+                    for (index, item) in items.iter().enumerate() {
+                        let inner = Scope {
+                            item,
+                            index,
+                            len: items.len(),
+                            parent: scope,
+                        };
+                        self.nodes(body, Some(&inner), out)?;
+                    }
+                }
+                Node::Partial { name, indent, span } => {
+                    self.partial(name, indent, *span, scope, out)?;
+                }
+                Node::Block { name, body, .. } => self.block(name, body, scope, out)?,
+                Node::Extends { base, blocks, span } => {
+                    self.overrides.push((blocks, self.file));
+                    let outer = self.file;
+                    self.file = ctx.partials.get(base.as_str()).and_then(Template::file);
+                    let result = self.include("base template", base, *span, scope, out);
+                    self.file = outer;
+                    self.overrides.pop();
+                    result?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn partial(
+        &mut self,
+        name: &'a str,
+        indent: &str,
+        span: Span,
+        scope: Option<&Scope<'_>>,
+        out: &mut String,
+    ) -> Result<()> {
+        let mut text = String::new();
+        self.include("partial", name, span, scope, &mut text)?;
+        if indent.is_empty() {
+            out.push_str(&text);
+        } else {
+            for line in text.split_inclusive('\n') {
+                if line != "\n" {
+                    out.push_str(indent);
+                }
+                out.push_str(line);
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders the registered template `name`, refusing to recurse into a
+    /// template that is already being rendered.
+    fn include(
+        &mut self,
+        kind: &str,
+        name: &'a str,
+        span: Span,
+        scope: Option<&Scope<'_>>,
+        out: &mut String,
+    ) -> Result<()> {
+        let Some(template) = self.ctx.partials.get(name) else {
+            return Err(Error::template(span, format!("unknown {kind} `{name}`")));
+        };
+        if self.active.contains(&name) || self.active.len() >= MAX_INCLUDE_DEPTH {
+            return Err(Error::template(
+                span,
+                format!("{kind} `{name}` includes itself"),
+            ));
+        }
+        self.active.push(name);
+        let result = self.nodes(template.nodes(), scope, out);
+        self.active.pop();
+        result.map_err(|err| in_file(err, template.file()))
+    }
+
+    /// Renders `body` for `{{#block name}}`, or the most derived override.
+    fn block(
+        &mut self,
+        name: &str,
+        body: &'a [Node],
+        scope: Option<&Scope<'_>>,
+        out: &mut String,
+    ) -> Result<()> {
+        let found = self
+            .overrides
+            .iter()
+            .enumerate()
+            .find_map(|(depth, (blocks, file))| Some((depth, blocks.get(name)?, *file)));
+        match found {
+            Some((depth, body, file)) => {
+                // Hide this override and the more derived ones while it
+                // renders so a block it contains falls through to the base.
+                let hidden: Vec<_> = self.overrides.drain(..=depth).collect();
+                let result = self.nodes(body, scope, out);
+                self.overrides.splice(0..0, hidden);
+                result.map_err(|err| in_file(err, file))
+            }
+            None => self.nodes(body, scope, out),
+        }
+    }
+}
+
+fn in_file(err: Error, file: Option<&Path>) -> Error {
+    match file {
+        Some(file) => err.in_file(file),
+        None => err,
+    }
+}
+
+/// Resolves a variable path against the loop scopes, innermost first, and
+/// then the context.
+fn lookup<'a>(
+    ctx: &'a Context,
+    scope: Option<&Scope<'a>>,
+    path: &[String],
+) -> Option<Cow<'a, Value>> {
+    let (first, rest) = path.split_first()?;
+    if let Some(scope) = scope {
+        let position = match first.as_str() {
+            "@index" => Some(Value::Integer(scope.index as i64)),
+            "@first" => Some(Value::Bool(scope.index == 0)),
+            "@last" => Some(Value::Bool(scope.index + 1 == scope.len)),
+            _ => None,
+        };
+        if position.is_some() {
+            return position.map(Cow::Owned);
+        }
+        let (start, rest) = match first.as_str() {
+            "this" => (Some(scope.item), rest),
+            _ => (scope.item.field(first), rest),
+        };
+        if let Some(start) = start {
+            return rest
+                .iter()
+                .try_fold(start, |value, field| value.field(field))
+                .map(Cow::Borrowed);
+        }
+        return lookup(ctx, scope.parent, path);
+    }
+    ctx.lookup(path).map(Cow::Borrowed)
+}
+
+/// Evaluates a condition. Unset variables are falsy so optional flags do
+/// not have to be declared everywhere they are tested.
+fn evaluate(condition: &Condition, ctx: &Context, scope: Option<&Scope<'_>>) -> bool {
+    match condition {
+        Condition::Truthy(path) => lookup(ctx, scope, path).is_some_and(|v| v.is_truthy()),
+        Condition::Compare {
+            path,
+            equal,
+            literal,
+        } => {
+            let actual = lookup(ctx, scope, path).map(|v| v.to_string());
+            (actual.as_deref() == Some(literal.as_str())) == *equal
+        }
+    }
+}
+
+pub(super) fn resolve<'a>(ctx: &'a Context, var: &str, span: Span) -> Result<&'a Value> {
+    ctx.get(var)
+        .ok_or_else(|| Error::template(span, format!("undefined variable `{var}`")))
+}
+
+/// Identifier placeholders and their replacement text, longest first so
+/// that `param10` is tried before `param1`.
+pub(super) struct Renames(Vec<(String, String)>);
+
+impl Renames {
+    pub(super) fn new(ctx: &Context) -> Result<Self> {
+        let mut pairs = ctx
+            .identifiers
+            .iter()
+            .map(|(placeholder, var)| {
+                resolve(ctx, var, Span::default()).map(|v| (placeholder.clone(), v.to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        pairs.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+        Ok(Self(pairs))
+    }
+
+    pub(super) fn apply(&self, text: &str, out: &mut String) {
+        if self.0.is_empty() {
+            out.push_str(text);
+            return;
+        }
+        let mut i = 0;
+        while i < text.len() {
+            let hit = self.0.iter().find(|(from, _)| {
+                text[i..].starts_with(from.as_str()) && at_boundary(text, i, from)
+            });
+            match hit {
+                Some((from, to)) => {
+                    out.push_str(to);
+                    i += from.len();
+                }
+                None => {
+                    let ch = text[i..].chars().next().expect("non-empty remainder");
+                    out.push(ch);
+                    i += ch.len_utf8();
+                }
+            }
+        }
+    }
+}
+
+/// Whether `ident` at byte `start` of `text` stands on its own: the
+/// neighbouring characters are non-alphanumeric (so `_` separates words),
+/// or form a camel-case boundary with the identifier's own first/last char.
+pub(super) fn at_boundary(text: &str, start: usize, ident: &str) -> bool {
+    let before = text[..start].chars().next_back();
+    let after = text[start + ident.len()..].chars().next();
+    let first = ident.chars().next().unwrap_or_default();
+    let last = ident.chars().next_back().unwrap_or_default();
+
+    let left = match before {
+        Some(c) if c.is_alphanumeric() => first.is_uppercase() && !c.is_uppercase(),
+        _ => true,
+    };
+    let right = match after {
+        Some(c) if c.is_alphanumeric() => c.is_uppercase() && !last.is_uppercase(),
+        _ => true,
+    };
+    left && right
+}
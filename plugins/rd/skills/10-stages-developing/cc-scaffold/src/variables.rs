@@ -0,0 +1,833 @@
+//! Typed template variables.
+//!
+//! A [`VariableSpec`] declares what a template variable may hold. Raw string
+//! input (from `--set`, prompts, or config) is checked against the spec
+//! before rendering, so `my func` is rejected as a function name instead of
+//! producing code that does not compile.
+//!
+//! Defaults can read the environment: `${VAR}` expands to the variable's
+//! value, `${VAR:-fallback}` to `fallback` when it is unset or empty, and
+//! `$${` is a literal `${`. Templates and derived defaults can also use
+//! `{{ env.VAR }}`, and the current UTC time as `{{ now.timestamp }}` or
+//! `{{ now.date }}`, see [`now`]. Templates from a remote or the user
+//! registry only read the variables their manifest lists, see
+//! [`EnvAccess`].
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, Result};
+use crate::template_engine::{self, is_identifier, Context, Span, Value};
+
+/// Rust keywords that cannot be used as plain identifiers.
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "gen", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// The type a template variable is declared with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VariableKind {
+    /// Any text.
+    String,
+    /// An ASCII Rust identifier that is not a keyword.
+    Identifier,
+    /// A signed 64-bit integer.
+    Integer,
+    /// `true`/`false` (also `yes`/`no`, `1`/`0`).
+    Bool,
+    /// One of a fixed set of options.
+    Enum(Vec<String>),
+    /// Comma-separated items for `{{#each}}`. With `fields`, each item is
+    /// split on `:` into a map with those keys, e.g. `input:&str` for
+    /// `fields = ["name", "type"]`.
+    List { fields: Vec<String> },
+}
+
+impl fmt::Display for VariableKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VariableKind::String => f.write_str("string"),
+            VariableKind::Identifier => f.write_str("identifier"),
+            VariableKind::Integer => f.write_str("integer"),
+            VariableKind::Bool => f.write_str("bool"),
+            VariableKind::Enum(options) => write!(f, "one of {}", options.join("|")),
+            VariableKind::List { fields } if fields.is_empty() => f.write_str("list"),
+            VariableKind::List { fields } => write!(f, "list of {}", fields.join(":")),
+        }
+    }
+}
+
+/// Declaration of a single template variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableSpec {
+    /// Name used in `{{ name }}` expressions and `--set name=...`.
+    pub name: String,
+    /// Accepted values.
+    pub kind: VariableKind,
+    /// Text in the template this variable replaces, e.g. `function_name` or
+    /// `[Brief description of what the function does]`.
+    pub placeholder: Option<String>,
+    /// Human-readable explanation shown when asking for a value.
+    pub description: Option<String>,
+    /// Raw value used when none is supplied. Variables without a default
+    /// are required. A default containing `{{ }}` expressions is rendered
+    /// against the variables before it, e.g.
+    /// `{{ function_name | pascal_case }}Result`.
+    pub default: Option<String>,
+    /// Raw value `cc-scaffold selftest` renders the template with, in place
+    /// of the default.
+    pub sample: Option<String>,
+}
+
+impl VariableSpec {
+    pub fn new(name: impl Into<String>, kind: VariableKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            placeholder: None,
+            description: None,
+            default: None,
+            sample: None,
+        }
+    }
+
+    pub fn placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = Some(placeholder.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn default_value(mut self, default: impl Into<String>) -> Self {
+        self.default = Some(default.into());
+        self
+    }
+
+    pub fn sample_value(mut self, sample: impl Into<String>) -> Self {
+        self.sample = Some(sample.into());
+        self
+    }
+
+    /// Whether a value must be supplied.
+    pub fn is_required(&self) -> bool {
+        self.default.is_none()
+    }
+
+    /// Whether the default is derived from other variables.
+    pub fn has_derived_default(&self) -> bool {
+        self.default.as_deref().is_some_and(|d| d.contains("{{"))
+    }
+
+    /// Whether the default reads [`now`], so it changes from run to run.
+    pub fn has_clock_default(&self) -> bool {
+        self.has_derived_default()
+            && self.default.as_deref().is_some_and(|default| {
+                default.match_indices("now").any(|(at, _)| {
+                    let word = |c: char| c.is_alphanumeric() || c == '_';
+                    !default[..at].ends_with(word) && !default[at + 3..].starts_with(word)
+                })
+            })
+    }
+
+    /// The default value given the raw `values` known so far, rendering a
+    /// derived default with what `env` lets it read.
+    pub fn default_for(
+        &self,
+        values: &BTreeMap<String, String>,
+        env: &EnvAccess,
+    ) -> Result<Option<String>> {
+        let Some(default) = &self.default else {
+            return Ok(None);
+        };
+        let failed = |message: String| Error::DerivedDefault {
+            variable: self.name.clone(),
+            message,
+        };
+        let default = env.expand(default).map_err(failed)?;
+        if !self.has_derived_default() {
+            return Ok(Some(default));
+        }
+        let mut ctx = Context::new();
+        set_builtins(&mut ctx, env);
+        for (name, value) in values {
+            ctx.set(name.as_str(), value.as_str());
+        }
+        template_engine::render(&default, &ctx)
+            .map(Some)
+            .map_err(|err| failed(err.to_string()))
+    }
+
+    /// Converts raw input into a typed value, or explains why it is invalid.
+    pub fn parse_value(&self, raw: &str) -> std::result::Result<Value, String> {
+        match &self.kind {
+            VariableKind::String => Ok(Value::from(raw)),
+            VariableKind::Identifier => check_identifier(raw).map(|()| Value::from(raw)),
+            VariableKind::Integer => raw
+                .trim()
+                .parse::<i64>()
+                .map(Value::Integer)
+                .map_err(|_| "expected an integer".to_string()),
+            VariableKind::Bool => match raw.trim().to_ascii_lowercase().as_str() {
+                "true" | "yes" | "1" => Ok(Value::Bool(true)),
+                "false" | "no" | "0" => Ok(Value::Bool(false)),
+                _ => Err("expected true or false".to_string()),
+            },
+            VariableKind::Enum(options) => {
+                if options.iter().any(|o| o == raw) {
+                    Ok(Value::from(raw))
+                } else {
+                    Err(format!("expected one of: {}", options.join(", ")))
+                }
+            }
+            VariableKind::List { fields } => parse_list(raw, fields),
+        }
+    }
+}
+
+/// Splits `raw` into list items. Commas and colons inside quotes or
+/// brackets do not split, so items may be tuples such as `("a", 1)` and
+/// field values may be types such as `HashMap<String, u8>` or `std::io::Error`.
+fn parse_list(raw: &str, fields: &[String]) -> std::result::Result<Value, String> {
+    let mut items = Vec::new();
+    for item in split_top_level(raw, |s, i| s[i..].starts_with(',')) {
+        let item = item.trim();
+        if item.is_empty() {
+            continue;
+        }
+        if fields.is_empty() {
+            items.push(Value::from(item));
+            continue;
+        }
+        let parts = split_top_level(item, |s, i| {
+            s[i..].starts_with(':') && !s[..i].ends_with(':') && !s[i + 1..].starts_with(':')
+        });
+        if parts.len() != fields.len() {
+            return Err(format!(
+                "expected items of the form `{}`, got `{item}`",
+                fields.join(":")
+            ));
+        }
+        let map = fields
+            .iter()
+            .zip(parts)
+            .map(|(field, part)| (field.clone(), Value::from(part.trim())))
+            .collect();
+        items.push(Value::Map(map));
+    }
+    Ok(Value::List(items))
+}
+
+fn split_top_level(s: &str, is_separator: impl Fn(&str, usize) -> bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut start = 0;
+    let mut prev = '\0';
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' if prev != '\\' => quoted = !quoted,
+            _ if quoted => {}
+            '(' | '[' | '{' | '<' => depth += 1,
+            '>' if prev == '-' => {}
+            ')' | ']' | '}' | '>' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && is_separator(s, i) => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn check_identifier(raw: &str) -> std::result::Result<(), String> {
+    if raw.is_empty() {
+        return Err("identifier cannot be empty".to_string());
+    }
+    if let Some(c) = raw
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '_')
+    {
+        return Err(match c {
+            ' ' | '\t' => "identifier cannot contain whitespace".to_string(),
+            _ => format!("identifier cannot contain `{c}`"),
+        });
+    }
+    if !is_identifier(raw) || raw == "_" {
+        return Err("identifier must start with a letter or underscore".to_string());
+    }
+    if RUST_KEYWORDS.contains(&raw) {
+        return Err(format!("`{raw}` is a reserved Rust keyword"));
+    }
+    Ok(())
+}
+
+/// A supplied value that does not match its [`VariableSpec`].
+///
+/// `span` points at the first place the variable is used in the template,
+/// so the message shows what the value would have been substituted into.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{}invalid {kind} `{value}` for variable `{variable}`: {reason}", location(file.as_ref(), span.as_ref()))]
+pub struct ValidationError {
+    pub variable: String,
+    pub value: String,
+    pub kind: VariableKind,
+    pub reason: String,
+    pub file: Option<PathBuf>,
+    pub span: Option<Span>,
+}
+
+fn location(file: Option<&PathBuf>, span: Option<&Span>) -> String {
+    match (file, span) {
+        (Some(file), Some(span)) => format!("{}:{}:{}: ", file.display(), span.line, span.column),
+        (None, Some(span)) => format!("{}:{}: ", span.line, span.column),
+        (Some(file), None) => format!("{}: ", file.display()),
+        (None, None) => String::new(),
+    }
+}
+
+/// Names of the values every template and derived default can read.
+pub const BUILTINS: &[&str] = &["env", "now"];
+
+/// Sets the [`BUILTINS`] in `ctx`: the environment `env` lets templates
+/// read and [`now`].
+pub fn set_builtins(ctx: &mut Context, env: &EnvAccess) {
+    ctx.set("env", env.value());
+    ctx.set("now", now());
+}
+
+/// The environment variables a template can read, as `{{ env.VAR }}` or
+/// as `${VAR}` in a default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum EnvAccess {
+    /// All of them, for templates from the user's own roots.
+    #[default]
+    All,
+    /// Only these, the ones its manifest lists under `env`, for templates
+    /// from a remote or the user registry: they are someone else's code,
+    /// and must not copy `GITHUB_TOKEN` or the like into generated files.
+    Only(Vec<String>),
+}
+
+impl EnvAccess {
+    /// Whether `name` can be read.
+    pub fn allows(&self, name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(names) => names.iter().any(|allowed| allowed == name),
+        }
+    }
+
+    /// The readable environment as a map for `{{ env.VAR }}`, leaving out
+    /// variables whose name or value is not valid UTF-8.
+    pub fn value(&self) -> Value {
+        let vars = std::env::vars_os()
+            .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+            .filter(|(name, _)| self.allows(name))
+            .map(|(name, value)| (name, Value::String(value)))
+            .collect();
+        Value::Map(vars)
+    }
+
+    /// [`expand_env`], refusing the variables that cannot be read.
+    pub fn expand(&self, text: &str) -> std::result::Result<String, String> {
+        expand_env_with(text, |name| {
+            if !self.allows(name) {
+                return Err(format!(
+                    "`${{{name}}}` is not among the environment variables the manifest lists under `env`"
+                ));
+            }
+            Ok(std::env::var(name).ok().filter(|value| !value.is_empty()))
+        })
+    }
+}
+
+/// The current UTC time as a map for `{{ now.timestamp }}`, e.g.
+/// `20260101093000` as migration versions use, and `{{ now.date }}`, e.g.
+/// `2026-01-01`.
+pub fn now() -> Value {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    utc(secs)
+}
+
+/// [`now`] at `secs` seconds after the Unix epoch.
+fn utc(secs: u64) -> Value {
+    let (days, time) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since 1970-01-01, counting in 400-year eras
+    // that start on 0000-03-01 so leap days end each year.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    let (hour, minute, second) = (time / 3_600, time / 60 % 60, time % 60);
+    Value::Map(BTreeMap::from([
+        (
+            "timestamp".to_string(),
+            Value::String(format!(
+                "{year:04}{month:02}{day:02}{hour:02}{minute:02}{second:02}"
+            )),
+        ),
+        (
+            "date".to_string(),
+            Value::String(format!("{year:04}-{month:02}-{day:02}")),
+        ),
+    ]))
+}
+
+/// Expands `${VAR}` and `${VAR:-fallback}` references to environment
+/// variables in `text`. An unset variable without a fallback is an error.
+pub fn expand_env(text: &str) -> std::result::Result<String, String> {
+    EnvAccess::All.expand(text)
+}
+
+/// Checks the `${...}` references in `text` without reading the
+/// environment.
+pub(crate) fn check_env_references(text: &str) -> std::result::Result<(), String> {
+    expand_env_with(text, |_| Ok(Some(String::new()))).map(drop)
+}
+
+/// The variables the `${...}` references in `text` name, in order.
+pub(crate) fn env_references(text: &str) -> Vec<String> {
+    let names = std::cell::RefCell::new(Vec::new());
+    let _ = expand_env_with(text, |name| {
+        names.borrow_mut().push(name.to_string());
+        Ok(Some(String::new()))
+    });
+    names.into_inner()
+}
+
+fn expand_env_with(
+    text: &str,
+    lookup: impl Fn(&str) -> std::result::Result<Option<String>, String>,
+) -> std::result::Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find("${") {
+        if rest[..at].ends_with('$') {
+            out.push_str(&rest[..at - 1]);
+            out.push_str("${");
+            rest = &rest[at + 2..];
+            continue;
+        }
+        out.push_str(&rest[..at]);
+        let Some(end) = rest[at..].find('}') else {
+            return Err(format!("unclosed `${{` in `{text}`"));
+        };
+        let reference = &rest[at + 2..at + end];
+        let (name, fallback) = match reference.split_once(":-") {
+            Some((name, fallback)) => (name, Some(fallback)),
+            None => (reference, None),
+        };
+        if !is_identifier(name) {
+            return Err(format!(
+                "`${{{reference}}}` does not name an environment variable"
+            ));
+        }
+        match (lookup(name)?, fallback) {
+            (Some(value), _) => out.push_str(&value),
+            (None, Some(fallback)) => out.push_str(fallback),
+            (None, None) => {
+                return Err(format!(
+                    "environment variable `{name}` is not set (use `${{{name}:-fallback}}` for a fallback)"
+                ))
+            }
+        }
+        rest = &rest[at + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Adds to `values` the defaults of `specs` that read [`now`] and were not
+/// supplied, so that recording `values` pins them: rendering again later,
+/// as `cc-scaffold update` does, then gives the same output. Defaults that
+/// cannot be derived yet are left for [`bind`] to report.
+pub fn pin_clock_defaults(
+    specs: &[VariableSpec],
+    values: &mut BTreeMap<String, String>,
+    env: &EnvAccess,
+) {
+    let mut resolved = values.clone();
+    for spec in specs {
+        if resolved.contains_key(&spec.name) {
+            continue;
+        }
+        let Ok(Some(default)) = spec.default_for(&resolved, env) else {
+            continue;
+        };
+        if spec.has_clock_default() {
+            values.insert(spec.name.clone(), default.clone());
+        }
+        resolved.insert(spec.name.clone(), default);
+    }
+}
+
+/// Validates `values` against `specs` and builds a render [`Context`].
+///
+/// Defaults fill in values that were not supplied, in declaration order so
+/// derived defaults can use earlier variables, and every spec with a
+/// placeholder is bound to it. Values with no matching spec are passed
+/// through as ad-hoc substitutions, as `--set` does without a spec.
+/// Templates and defaults read the environment `env` allows. `source` and
+/// `file` are only used to locate errors.
+pub fn bind(
+    specs: &[VariableSpec],
+    values: &BTreeMap<String, String>,
+    env: &EnvAccess,
+    source: &str,
+    file: Option<&PathBuf>,
+) -> Result<Context> {
+    let mut ctx = Context::new();
+    set_builtins(&mut ctx, env);
+    for (key, value) in values {
+        if !specs.iter().any(|s| &s.name == key) {
+            ctx.substitute(key, value.as_str());
+        }
+    }
+    let mut resolved = values.clone();
+    for spec in specs {
+        if !resolved.contains_key(&spec.name) {
+            if let Some(default) = spec.default_for(&resolved, env)? {
+                resolved.insert(spec.name.clone(), default);
+            }
+        }
+        let Some(raw) = resolved.get(&spec.name) else {
+            return Err(Error::MissingVariable(spec.name.clone()));
+        };
+        let value = spec.parse_value(raw).map_err(|reason| {
+            let used_as = spec.placeholder.as_deref().unwrap_or(&spec.name);
+            ValidationError {
+                variable: spec.name.clone(),
+                value: raw.clone(),
+                kind: spec.kind.clone(),
+                reason,
+                file: file.cloned(),
+                span: template_engine::find_placeholder(source, used_as)
+                    .or_else(|| template_engine::find_placeholder(source, &spec.name)),
+            }
+        })?;
+        ctx.set(spec.name.as_str(), value);
+        if let Some(placeholder) = &spec.placeholder {
+            ctx.bind(placeholder, spec.name.as_str());
+        }
+    }
+    Ok(ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_identifier_validation() {
+        let spec = VariableSpec::new("name", VariableKind::Identifier);
+        assert_eq!(
+            spec.parse_value("parse_header"),
+            Ok(Value::from("parse_header"))
+        );
+        assert_eq!(spec.parse_value("_private"), Ok(Value::from("_private")));
+        assert!(spec
+            .parse_value("my func")
+            .unwrap_err()
+            .contains("whitespace"));
+        assert!(spec.parse_value("my-func").unwrap_err().contains("`-`"));
+        assert!(spec.parse_value("1st").unwrap_err().contains("start with"));
+        assert!(spec.parse_value("_").is_err());
+        assert!(spec.parse_value("").is_err());
+        assert!(spec.parse_value("fn").unwrap_err().contains("keyword"));
+    }
+
+    #[test]
+    fn test_scalar_kinds() {
+        let int = VariableSpec::new("n", VariableKind::Integer);
+        assert_eq!(int.parse_value("-42"), Ok(Value::Integer(-42)));
+        assert!(int.parse_value("4.2").is_err());
+
+        let flag = VariableSpec::new("async", VariableKind::Bool);
+        assert_eq!(flag.parse_value("yes"), Ok(Value::Bool(true)));
+        assert_eq!(flag.parse_value("False"), Ok(Value::Bool(false)));
+        assert!(flag.parse_value("maybe").is_err());
+
+        let choice = VariableSpec::new(
+            "runtime",
+            VariableKind::Enum(vec!["tokio".into(), "async-std".into()]),
+        );
+        assert_eq!(choice.parse_value("tokio"), Ok(Value::from("tokio")));
+        assert!(choice
+            .parse_value("smol")
+            .unwrap_err()
+            .contains("tokio, async-std"));
+    }
+
+    #[test]
+    fn test_list_kind() {
+        let plain = VariableSpec::new("cases", VariableKind::List { fields: vec![] });
+        assert_eq!(
+            plain.parse_value(r#"("a, b", 1), (x, [2, 3]),"#),
+            Ok(Value::from(vec![r#"("a, b", 1)"#, "(x, [2, 3])"]))
+        );
+        assert_eq!(plain.parse_value(""), Ok(Value::List(vec![])));
+
+        let params = VariableSpec::new(
+            "params",
+            VariableKind::List {
+                fields: vec!["name".into(), "type".into()],
+            },
+        );
+        let Ok(Value::List(items)) =
+            params.parse_value("path: std::path::PathBuf, map: HashMap<String, u8>")
+        else {
+            panic!("expected a list");
+        };
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0].field("type"),
+            Some(&Value::from("std::path::PathBuf"))
+        );
+        assert_eq!(items[1].field("name"), Some(&Value::from("map")));
+        assert_eq!(
+            items[1].field("type"),
+            Some(&Value::from("HashMap<String, u8>"))
+        );
+        assert!(params
+            .parse_value("path")
+            .unwrap_err()
+            .contains("`name:type`"));
+    }
+
+    #[test]
+    fn test_bind_applies_defaults_and_placeholders() {
+        let specs = vec![
+            VariableSpec::new("name", VariableKind::Identifier).placeholder("function_name"),
+            VariableSpec::new("count", VariableKind::Integer).default_value("3"),
+        ];
+        let ctx = bind(
+            &specs,
+            &values(&[("name", "parse")]),
+            &EnvAccess::All,
+            "",
+            None,
+        )
+        .unwrap();
+        assert_eq!(ctx.get("count"), Some(&Value::Integer(3)));
+        let out = template_engine::render("fn function_name() -> {{ count }}", &ctx).unwrap();
+        assert_eq!(out, "fn parse() -> 3");
+    }
+
+    #[test]
+    fn test_bind_renders_derived_defaults() {
+        let specs = vec![
+            VariableSpec::new("name", VariableKind::Identifier).placeholder("function_name"),
+            VariableSpec::new("result", VariableKind::Identifier)
+                .placeholder("FunctionResult")
+                .default_value("{{ name | pascal_case }}Result"),
+        ];
+        let ctx = bind(
+            &specs,
+            &values(&[("name", "parse_header")]),
+            &EnvAccess::All,
+            "",
+            None,
+        )
+        .unwrap();
+        assert_eq!(ctx.get("result"), Some(&Value::from("ParseHeaderResult")));
+
+        let ctx = bind(
+            &specs,
+            &values(&[("name", "parse_header"), ("result", "Header")]),
+            &EnvAccess::All,
+            "",
+            None,
+        )
+        .unwrap();
+        assert_eq!(ctx.get("result"), Some(&Value::from("Header")));
+
+        let out_of_order: Vec<_> = specs.iter().rev().cloned().collect();
+        let err = bind(&out_of_order, &values(&[]), &EnvAccess::All, "", None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "default for `result` cannot be derived: 1:1: undefined variable `name`"
+        );
+    }
+
+    #[test]
+    fn test_expand_env() {
+        let env = |name: &str| Ok((name == "USER").then(|| "jane".to_string()));
+        assert_eq!(
+            expand_env_with("by ${USER}, ${EDITOR:-vi}, $${USER}", env).unwrap(),
+            "by jane, vi, ${USER}"
+        );
+        let err = expand_env_with("${EDITOR}", env).unwrap_err();
+        assert!(
+            err.starts_with("environment variable `EDITOR` is not set"),
+            "{err}"
+        );
+        assert!(expand_env_with("${USER", env).is_err());
+        assert!(expand_env_with("${not a name}", env).is_err());
+    }
+
+    #[test]
+    fn test_defaults_read_the_environment() {
+        let path = std::env::var("PATH").unwrap();
+        let specs = vec![
+            VariableSpec::new("a", VariableKind::String).default_value("${PATH}"),
+            VariableSpec::new("b", VariableKind::String).default_value("{{ env.PATH }}"),
+            VariableSpec::new("c", VariableKind::String)
+                .default_value("${CC_SCAFFOLD_UNSET:-none}"),
+        ];
+        let ctx = bind(&specs, &values(&[]), &EnvAccess::All, "", None).unwrap();
+        assert_eq!(ctx.get("a"), Some(&Value::from(path.as_str())));
+        assert_eq!(ctx.get("b"), Some(&Value::from(path.as_str())));
+        assert_eq!(ctx.get("c"), Some(&Value::from("none")));
+    }
+
+    #[test]
+    fn test_restricted_env_reads_only_listed_variables() {
+        let path = std::env::var("PATH").unwrap();
+        let env = EnvAccess::Only(vec!["PATH".to_string()]);
+        let specs = vec![
+            VariableSpec::new("a", VariableKind::String).default_value("${PATH}"),
+            VariableSpec::new("b", VariableKind::String).default_value("{{ env.PATH }}"),
+        ];
+        let ctx = bind(&specs, &values(&[]), &env, "", None).unwrap();
+        assert_eq!(ctx.get("a"), Some(&Value::from(path.as_str())));
+        assert_eq!(ctx.get("b"), Some(&Value::from(path.as_str())));
+        assert_eq!(
+            ctx.get("env"),
+            Some(&Value::Map(BTreeMap::from([(
+                "PATH".to_string(),
+                Value::from(path.as_str())
+            )])))
+        );
+
+        for default in ["${HOME}", "${HOME:-none}", "{{ env.HOME }}"] {
+            let specs = vec![VariableSpec::new("a", VariableKind::String).default_value(default)];
+            let err = bind(&specs, &values(&[]), &env, "", None).unwrap_err();
+            assert!(
+                err.to_string()
+                    .starts_with("default for `a` cannot be derived"),
+                "{default}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_now_is_the_utc_time() {
+        let field = |value: &Value, name: &str| value.field(name).cloned().unwrap();
+        for (secs, timestamp, date) in [
+            (0, "19700101000000", "1970-01-01"),
+            (951_825_599, "20000229115959", "2000-02-29"),
+            (1_700_000_000, "20231114221320", "2023-11-14"),
+            (4_107_542_400, "21000301000000", "2100-03-01"),
+        ] {
+            let now = utc(secs);
+            assert_eq!(field(&now, "timestamp"), Value::from(timestamp));
+            assert_eq!(field(&now, "date"), Value::from(date));
+        }
+
+        let specs = vec![VariableSpec::new("version", VariableKind::Integer)
+            .default_value("{{ now.timestamp }}")];
+        let ctx = bind(&specs, &values(&[]), &EnvAccess::All, "", None).unwrap();
+        let Some(Value::Integer(version)) = ctx.get("version") else {
+            panic!("{:?}", ctx.get("version"));
+        };
+        assert!(*version > 20_260_000_000_000, "{version}");
+    }
+
+    #[test]
+    fn test_pin_clock_defaults_adds_only_defaults_reading_now() {
+        let specs = vec![
+            VariableSpec::new("name", VariableKind::Identifier).default_value("notes"),
+            VariableSpec::new("version", VariableKind::Integer)
+                .default_value("{{ now.timestamp }}"),
+            VariableSpec::new("known", VariableKind::String).default_value("{{ name }}_known"),
+            VariableSpec::new("file", VariableKind::String)
+                .default_value("{{ version }}_{{ name }}"),
+        ];
+        assert!(specs[1].has_clock_default());
+        assert!(!specs[2].has_clock_default());
+        let mut pinned = values(&[]);
+        pin_clock_defaults(&specs, &mut pinned, &EnvAccess::All);
+        assert_eq!(pinned.keys().collect::<Vec<_>>(), ["version"]);
+        assert_eq!(pinned["version"].len(), 14);
+
+        let mut given = values(&[("version", "1")]);
+        pin_clock_defaults(&specs, &mut given, &EnvAccess::All);
+        assert_eq!(given, values(&[("version", "1")]));
+    }
+
+    #[test]
+    fn test_bind_reports_missing_required_variable() {
+        let specs = vec![VariableSpec::new("name", VariableKind::Identifier)];
+        let err = bind(&specs, &BTreeMap::new(), &EnvAccess::All, "", None).unwrap_err();
+        assert!(matches!(err, Error::MissingVariable(name) if name == "name"));
+    }
+
+    #[test]
+    fn test_validation_error_points_at_placeholder() {
+        let specs =
+            vec![VariableSpec::new("name", VariableKind::Identifier).placeholder("function_name")];
+        let source = "// docs\npub fn function_name() {}\n";
+        let file = PathBuf::from("function-template.rs");
+        let err = bind(
+            &specs,
+            &values(&[("name", "my func")]),
+            &EnvAccess::All,
+            source,
+            Some(&file),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "function-template.rs:2:8: invalid identifier `my func` for variable `name`: \
+             identifier cannot contain whitespace"
+        );
+        let Error::Validation(err) = err else {
+            panic!("expected a validation error");
+        };
+        assert_eq!(err.span, Some(Span { line: 2, column: 8 }));
+    }
+
+    #[test]
+    fn test_bind_passes_through_unknown_values() {
+        let ctx = bind(
+            &[],
+            &values(&[("param1", "input")]),
+            &EnvAccess::All,
+            "",
+            None,
+        )
+        .unwrap();
+        assert_eq!(template_engine::render("param1", &ctx).unwrap(), "input");
+    }
+}
@@ -0,0 +1,621 @@
+//! Parametrized boundary-value tests generated from a function's
+//! signature, for `cc-scaffold test-vectors`.
+//!
+//! [`Table::parse`] finds a function in a Rust source file, a free one by
+//! its name or an associated one as `Type::name`, and gives each of its
+//! parameters a typical value and the boundary values of its type: the
+//! empty and a blank string, zero, `-1`, `MIN` and `MAX` for integers, NaN
+//! and the infinities for floats, `None`, an empty `Vec`. The first row of
+//! the table holds the typical values, and each row after it changes one
+//! parameter to one of its boundaries.
+//!
+//! [`Table::render`] renders the table as a test in the shape of the
+//! parametrized one of `rust/function`, with the `rust/test-vectors/table`
+//! template, to be injected into the file's test module. Which rows should
+//! succeed cannot be told from a signature: for a function returning a
+//! `Result` or an `Option` each row has an expectation, left `None` until
+//! it is decided, and every row checks that the call does not panic.
+//!
+//! A parameter of a type without known boundaries is passed its `Default`
+//! and reported in [`Table::warnings`]. Generic and `impl Trait`
+//! parameters, methods taking `self` and unsafe functions are rejected.
+
+use std::path::Path;
+
+use syn::{
+    FnArg, GenericArgument, GenericParam, Generics, ImplItem, Item, Pat, PathArguments, ReturnType,
+    Safety, Signature, Type, TypePath, Visibility,
+};
+
+use crate::error::Result;
+use crate::generate::{self, RenderedFile};
+use crate::locator::Locator;
+use crate::template_engine::filters;
+use crate::template_engine::{Context, Value};
+
+const TEMPLATE: &str = "rust/test-vectors/table";
+
+/// Names the generated test binds besides the parameters.
+const RESERVED_NAMES: &[&str] = &["expect_ok", "expect_some", "result", "row", "test_cases"];
+
+/// A parametrized test of one function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    /// The function as the test calls it: `name`, or `Type::name`.
+    pub function: String,
+    /// Name of the generated test.
+    pub test_name: String,
+    pub is_async: bool,
+    /// One per parameter, in order.
+    pub columns: Vec<Column>,
+    pub outcome: Outcome,
+    /// Whether the file already defines a function named like the test.
+    pub test_exists: bool,
+    /// Parameters passed their `Default` for want of boundary values.
+    pub warnings: Vec<String>,
+}
+
+/// The values of one parameter in a [`Table`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    /// What the test binds the parameter's value of a row to.
+    pub name: String,
+    /// Expressions a row holds, the typical one first.
+    pub values: Vec<String>,
+    pub pass: Pass,
+}
+
+/// How the value a row holds is passed to the function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    /// As it is.
+    Value,
+    /// As `&value`, for a reference parameter.
+    Ref,
+    /// As `&mut value`, from a `mut` binding.
+    Mut,
+    /// As `Path::new(value)`, for a `&Path` parameter.
+    Path,
+}
+
+/// What the function returns, which decides what each row checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// A `Result`: rows can expect it to be `Ok` or not.
+    Result,
+    /// An `Option`: rows can expect it to be `Some` or not.
+    Option,
+    /// Another value, dropped.
+    Value,
+    /// Nothing.
+    Unit,
+}
+
+impl Outcome {
+    /// The expectation column of a row and the method it is checked
+    /// against, if rows have one.
+    fn expectation(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Outcome::Result => Some(("expect_ok", "is_ok")),
+            Outcome::Option => Some(("expect_some", "is_some")),
+            Outcome::Value | Outcome::Unit => None,
+        }
+    }
+}
+
+impl Table {
+    /// The table for `function` of the Rust file `source`: `name` for a
+    /// function at its top level, `Type::name` for one in an inherent
+    /// `impl` there.
+    pub fn parse(source: &str, function: &str) -> Result<Self, String> {
+        let file = syn::parse_file(source).map_err(|err| format!("it does not parse: {err}"))?;
+        let (owner, name) = match function.rsplit_once("::") {
+            Some((owner, name)) => (Some(owner), name),
+            None => (None, function),
+        };
+        let (sig, impl_generics, _) = find(&file.items, owner, name)
+            .ok_or_else(|| format!("no function `{function}` found"))?;
+        if sig.receiver().is_some() {
+            return Err(format!(
+                "`{function}` takes `self`; only functions without one can be called from a table"
+            ));
+        }
+        if matches!(sig.safety, Safety::Unsafe(_)) {
+            return Err(format!(
+                "`{function}` is unsafe; boundary values may break what it requires of its arguments"
+            ));
+        }
+        if sig.inputs.is_empty() {
+            return Err(format!("`{function}` takes no parameters"));
+        }
+        let mut builder = Builder {
+            generics: type_params(impl_generics)
+                .chain(type_params(Some(&sig.generics)))
+                .collect(),
+            param: String::new(),
+            warnings: Vec::new(),
+        };
+        let mut columns = Vec::with_capacity(sig.inputs.len());
+        for (index, input) in sig.inputs.iter().enumerate() {
+            let FnArg::Typed(input) = input else {
+                continue;
+            };
+            let name = binding(&input.pat, index);
+            builder.param = name.clone();
+            let (values, pass) = builder.column(&input.ty)?;
+            columns.push(Column { name, values, pass });
+        }
+        let test_name = format!(
+            "test_{}_boundaries",
+            filters::apply("snake_case", &function.replace("::", "_"))
+        );
+        Ok(Self {
+            function: function.to_string(),
+            test_exists: defines(&file.items, &test_name),
+            test_name,
+            is_async: sig.asyncness.is_some(),
+            columns,
+            outcome: outcome(&sig.output),
+            warnings: builder.warnings,
+        })
+    }
+
+    /// The rows of the test, as the tuple expressions it lists.
+    pub fn rows(&self) -> Vec<String> {
+        let values: Vec<Vec<String>> = self.columns.iter().map(|c| c.values.clone()).collect();
+        let expectation = self.outcome.expectation().map(|_| "None".to_string());
+        vary(&values)
+            .into_iter()
+            .map(|row| tuple(row.into_iter().chain(expectation.clone()).collect()))
+            .collect()
+    }
+
+    /// Renders the test, in a `mod tests` to inject into `destination`.
+    pub fn render(&self, locator: &Locator, destination: &Path) -> Result<RenderedFile> {
+        let template = locator.resolve(TEMPLATE)?;
+        let (expect, check) = self.outcome.expectation().unwrap_or_default();
+        let bindings = self.columns.iter().map(|column| match column.pass {
+            Pass::Mut => format!("mut {}", column.name),
+            _ => column.name.clone(),
+        });
+        let pattern = tuple(
+            bindings
+                .chain(self.outcome.expectation().map(|_| expect.to_string()))
+                .collect(),
+        );
+        let args: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| match column.pass {
+                Pass::Value => column.name.clone(),
+                Pass::Ref => format!("&{}", column.name),
+                Pass::Mut => format!("&mut {}", column.name),
+                Pass::Path => format!("std::path::Path::new({})", column.name),
+            })
+            .collect();
+        let mut call = format!("{}({})", self.function, args.join(", "));
+        if self.is_async {
+            call.push_str(".await");
+        }
+        let mut ctx = Context::new();
+        ctx.set("function", self.function.as_str());
+        ctx.set("test_name", self.test_name.as_str());
+        ctx.set("async", self.is_async);
+        ctx.set(
+            "rows",
+            Value::List(self.rows().into_iter().map(Value::from).collect()),
+        );
+        ctx.set("pattern", pattern);
+        ctx.set("call", call);
+        ctx.set("expect", expect);
+        ctx.set("check", check);
+        ctx.set("discard", self.outcome == Outcome::Value);
+        generate::render_file(&template.source, destination, &ctx)
+    }
+}
+
+/// The signature of function `name` of `items`, or of the inherent `impl`
+/// of `owner` there, with the generics of that `impl` and the function's
+/// visibility.
+pub(crate) fn find<'a>(
+    items: &'a [Item],
+    owner: Option<&str>,
+    name: &str,
+) -> Option<(&'a Signature, Option<&'a Generics>, &'a Visibility)> {
+    items.iter().find_map(|item| match (item, owner) {
+        (Item::Fn(item), None) if item.sig.ident == name => Some((&item.sig, None, &item.vis)),
+        (Item::Impl(item), Some(owner))
+            if item.trait_.is_none() && type_ident(&item.self_ty).is_some_and(|i| i == owner) =>
+        {
+            item.items.iter().find_map(|member| match member {
+                ImplItem::Fn(member) if member.sig.ident == name => {
+                    Some((&member.sig, Some(&item.generics), &member.vis))
+                }
+                _ => None,
+            })
+        }
+        _ => None,
+    })
+}
+
+/// Whether `items`, or a module inline among them, define a function
+/// `name`.
+fn defines(items: &[Item], name: &str) -> bool {
+    items.iter().any(|item| match item {
+        Item::Fn(item) => item.sig.ident == name,
+        Item::Mod(item) => item
+            .content
+            .as_ref()
+            .is_some_and(|(_, items)| defines(items, name)),
+        _ => false,
+    })
+}
+
+pub(crate) fn type_params(generics: Option<&Generics>) -> impl Iterator<Item = String> + '_ {
+    generics
+        .into_iter()
+        .flat_map(|generics| &generics.params)
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(param.ident.to_string()),
+            _ => None,
+        })
+}
+
+/// The name the test binds parameter `index` to: the parameter's own, if
+/// it has one the test does not use for itself.
+fn binding(pat: &Pat, index: usize) -> String {
+    let name = match pat {
+        Pat::Ident(pat) => pat.ident.to_string(),
+        _ => format!("arg{}", index + 1),
+    };
+    if RESERVED_NAMES.contains(&name.as_str()) {
+        format!("{name}_")
+    } else {
+        name
+    }
+}
+
+fn outcome(output: &ReturnType) -> Outcome {
+    let ReturnType::Type(_, ty) = output else {
+        return Outcome::Unit;
+    };
+    match &**ty {
+        Type::Tuple(tuple) if tuple.elems.is_empty() => Outcome::Unit,
+        ty => match type_ident(ty).as_deref() {
+            Some("Result") => Outcome::Result,
+            Some("Option") => Outcome::Option,
+            _ => Outcome::Value,
+        },
+    }
+}
+
+/// The last segment of the path `ty` names, if it is one.
+pub(crate) fn type_ident(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        Type::Paren(ty) => type_ident(&ty.elem),
+        Type::Group(ty) => type_ident(&ty.elem),
+        _ => None,
+    }
+}
+
+/// The typical row, then one row per boundary value of each column with
+/// the others at their typical values.
+fn vary(columns: &[Vec<String>]) -> Vec<Vec<String>> {
+    let typical: Vec<String> = columns.iter().map(|values| values[0].clone()).collect();
+    let mut rows = vec![typical.clone()];
+    for (index, values) in columns.iter().enumerate() {
+        for value in &values[1..] {
+            let mut row = typical.clone();
+            row[index] = value.clone();
+            rows.push(row);
+        }
+    }
+    rows
+}
+
+/// `items` as a tuple, or the item itself if it is the only one.
+fn tuple(mut items: Vec<String>) -> String {
+    match items.len() {
+        1 => items.remove(0),
+        _ => format!("({})", items.join(", ")),
+    }
+}
+
+/// Works out the values of the parameters of one signature.
+struct Builder {
+    /// Type parameters in scope, which have no values.
+    generics: Vec<String>,
+    /// The parameter whose values are being worked out.
+    param: String,
+    warnings: Vec<String>,
+}
+
+impl Builder {
+    /// The values of a parameter of type `ty` and how they are passed.
+    fn column(&mut self, ty: &Type) -> Result<(Vec<String>, Pass), String> {
+        let Type::Reference(reference) = ty else {
+            return Ok((self.values(ty)?, Pass::Value));
+        };
+        let pass = match reference.mutability {
+            Some(_) => Pass::Mut,
+            None => Pass::Ref,
+        };
+        match &*reference.elem {
+            Type::Path(path) if pass == Pass::Ref && is(path, "str") => {
+                Ok((strings(), Pass::Value))
+            }
+            Type::Path(path) if pass == Pass::Ref && is(path, "Path") => {
+                Ok((strings(), Pass::Path))
+            }
+            Type::Slice(slice) => Ok((vecs(self.values(&slice.elem)?), pass)),
+            elem => Ok((self.values(elem)?, pass)),
+        }
+    }
+
+    /// Expressions of type `ty`, the typical one first.
+    fn values(&mut self, ty: &Type) -> Result<Vec<String>, String> {
+        match ty {
+            Type::Paren(ty) => self.values(&ty.elem),
+            Type::Group(ty) => self.values(&ty.elem),
+            Type::Tuple(tuple) if tuple.elems.is_empty() => Ok(vec!["()".to_string()]),
+            Type::Tuple(tuple) => {
+                let elems = tuple
+                    .elems
+                    .iter()
+                    .map(|elem| self.values(elem))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(vary(&elems)
+                    .into_iter()
+                    .map(|row| match row.len() {
+                        1 => format!("({},)", row[0]),
+                        _ => format!("({})", row.join(", ")),
+                    })
+                    .collect())
+            }
+            Type::Path(path) if path.qself.is_none() => self.path_values(path),
+            Type::Array(_) => Ok(self.default_value("an array")),
+            Type::ImplTrait(_) => Err(format!(
+                "`{}` is an `impl Trait`, which a table cannot hold",
+                self.param
+            )),
+            Type::TraitObject(_) => Err(format!(
+                "`{}` is a trait object, which a table cannot hold",
+                self.param
+            )),
+            _ => Err(format!("`{}` has a type a table cannot hold", self.param)),
+        }
+    }
+
+    fn path_values(&mut self, path: &TypePath) -> Result<Vec<String>, String> {
+        let Some(segment) = path.path.segments.last() else {
+            return Ok(self.default_value("its type"));
+        };
+        let name = segment.ident.to_string();
+        if path.path.segments.len() == 1 && self.generics.contains(&name) {
+            return Err(format!(
+                "`{}` is of generic type `{name}`, which has no values to test with",
+                self.param
+            ));
+        }
+        let argument = match &segment.arguments {
+            PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            }),
+            _ => None,
+        };
+        let values = match (name.as_str(), argument) {
+            ("String", _) => strings()
+                .into_iter()
+                .map(|s| match s.as_str() {
+                    "\"\"" => "String::new()".to_string(),
+                    _ => format!("{s}.to_string()"),
+                })
+                .collect(),
+            ("i8" | "i16" | "i32" | "i64" | "i128" | "isize", _) => {
+                let bounds = ["1", "0", "-1", "MIN", "MAX"];
+                constants(&name, &bounds)
+            }
+            ("u8" | "u16" | "u32" | "u64" | "u128" | "usize", _) => {
+                constants(&name, &["1", "0", "MAX"])
+            }
+            ("f32" | "f64", _) => {
+                let bounds = [
+                    "1.0",
+                    "0.0",
+                    "-1.0",
+                    "MIN",
+                    "MAX",
+                    "NAN",
+                    "INFINITY",
+                    "NEG_INFINITY",
+                ];
+                constants(&name, &bounds)
+            }
+            ("bool", _) => vec!["true".to_string(), "false".to_string()],
+            ("char", _) => constants("char", &["'a'", "'\\0'", "MAX"]),
+            ("PathBuf", _) => vec![
+                "std::path::PathBuf::from(\"file.txt\")".to_string(),
+                "std::path::PathBuf::new()".to_string(),
+            ],
+            ("Option", Some(inner)) => {
+                let inner = self.values(inner)?;
+                let mut values = vec![format!("Some({})", inner[0]), "None".to_string()];
+                values.extend(inner[1..].iter().map(|value| format!("Some({value})")));
+                values
+            }
+            ("Vec", Some(inner)) => vecs(self.values(inner)?),
+            ("Box", Some(inner)) => self
+                .values(inner)?
+                .into_iter()
+                .map(|value| format!("Box::new({value})"))
+                .collect(),
+            _ => self.default_value(&format!("`{name}`")),
+        };
+        Ok(values)
+    }
+
+    /// `Default::default()`, warning that `what` has no boundary values.
+    fn default_value(&mut self, what: &str) -> Vec<String> {
+        self.warnings.push(format!(
+            "no boundary values for {what} of `{}`; it is passed `Default::default()`",
+            self.param
+        ));
+        vec!["Default::default()".to_string()]
+    }
+}
+
+/// Whether `path` names `name`.
+pub(crate) fn is(path: &TypePath, name: &str) -> bool {
+    path.qself.is_none() && path.path.segments.last().is_some_and(|s| s.ident == name)
+}
+
+/// String literals: typical, empty, blank and beyond ASCII.
+fn strings() -> Vec<String> {
+    ["\"hello\"", "\"\"", "\" \"", "\"\u{1f980}\""]
+        .map(String::from)
+        .to_vec()
+}
+
+/// `values`, with those naming a constant qualified by `ty`.
+fn constants(ty: &str, values: &[&str]) -> Vec<String> {
+    values
+        .iter()
+        .map(
+            |value| match value.starts_with(|c: char| c.is_ascii_uppercase()) {
+                true => format!("{ty}::{value}"),
+                false => value.to_string(),
+            },
+        )
+        .collect()
+}
+
+/// `Vec`s of the typical element: with one, and empty.
+fn vecs(elements: Vec<String>) -> Vec<String> {
+    vec![format!("vec![{}]", elements[0]), "Vec::new()".to_string()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+        use std::path::{Path, PathBuf};\n\
+        \n\
+        pub fn parse(input: &str, limit: u8) -> Result<u8, String> { todo!() }\n\
+        pub fn find(items: &[i32], key: Option<i64>) -> Option<usize> { todo!() }\n\
+        pub fn touch(path: &Path, flags: &mut Vec<bool>) {}\n\
+        pub async fn fetch(url: String, (a, b): (f32, char)) -> u64 { 0 }\n\
+        pub fn generic<T>(value: T) {}\n\
+        pub unsafe fn raw(ptr: usize) {}\n\
+        pub fn later(row: Config) {}\n\
+        \n\
+        pub struct Config;\n\
+        \n\
+        impl Config {\n\
+            pub fn new(name: &str) -> Self { Config }\n\
+            pub fn get(&self, key: &str) -> Option<&str> { None }\n\
+        }\n\
+        \n\
+        #[cfg(test)]\n\
+        mod tests {\n\
+            fn test_config_new_boundaries() {}\n\
+        }\n";
+
+    fn table(function: &str) -> Table {
+        Table::parse(SOURCE, function).unwrap()
+    }
+
+    #[test]
+    fn test_parse_boundaries_per_type() {
+        let parse = table("parse");
+        assert_eq!(parse.test_name, "test_parse_boundaries");
+        assert_eq!(parse.outcome, Outcome::Result);
+        assert!(!parse.is_async && !parse.test_exists);
+        assert_eq!(
+            parse.rows(),
+            [
+                "(\"hello\", 1, None)",
+                "(\"\", 1, None)",
+                "(\" \", 1, None)",
+                "(\"\u{1f980}\", 1, None)",
+                "(\"hello\", 0, None)",
+                "(\"hello\", u8::MAX, None)",
+            ]
+        );
+
+        let find = table("find");
+        assert_eq!(find.outcome, Outcome::Option);
+        assert_eq!(find.columns[0].values, ["vec![1]", "Vec::new()"]);
+        assert_eq!(find.columns[0].pass, Pass::Ref);
+        assert_eq!(
+            find.columns[1].values,
+            [
+                "Some(1)",
+                "None",
+                "Some(0)",
+                "Some(-1)",
+                "Some(i64::MIN)",
+                "Some(i64::MAX)"
+            ]
+        );
+
+        let touch = table("touch");
+        assert_eq!(touch.outcome, Outcome::Unit);
+        assert_eq!(touch.columns[0].pass, Pass::Path);
+        assert_eq!(touch.columns[1].pass, Pass::Mut);
+        assert_eq!(touch.columns[1].values, ["vec![true]", "Vec::new()"]);
+    }
+
+    #[test]
+    fn test_parse_async_tuple_and_unknown_types() {
+        let fetch = table("fetch");
+        assert!(fetch.is_async);
+        assert_eq!(fetch.outcome, Outcome::Value);
+        assert_eq!(
+            fetch.columns[0].values[..2],
+            ["\"hello\".to_string()", "String::new()"]
+        );
+        assert_eq!(fetch.columns[1].name, "arg2");
+        assert_eq!(fetch.columns[1].values[0], "(1.0, 'a')");
+        assert!(fetch.columns[1]
+            .values
+            .contains(&"(f32::NAN, 'a')".to_string()));
+        assert!(fetch.columns[1]
+            .values
+            .contains(&"(1.0, char::MAX)".to_string()));
+        assert!(fetch.warnings.is_empty());
+
+        let later = table("later");
+        assert_eq!(later.columns[0].name, "row_");
+        assert_eq!(later.columns[0].values, ["Default::default()"]);
+        assert_eq!(
+            later.warnings,
+            ["no boundary values for `Config` of `row_`; it is passed `Default::default()`"]
+        );
+        assert_eq!(later.rows(), ["Default::default()"]);
+    }
+
+    #[test]
+    fn test_parse_associated_function() {
+        let new = table("Config::new");
+        assert_eq!(new.test_name, "test_config_new_boundaries");
+        assert_eq!(new.outcome, Outcome::Value);
+        assert!(new.test_exists);
+        assert_eq!(new.rows()[0], "\"hello\"");
+    }
+
+    #[test]
+    fn test_parse_rejects_what_a_table_cannot_call() {
+        let error = |function| Table::parse(SOURCE, function).unwrap_err();
+        assert_eq!(error("missing"), "no function `missing` found");
+        assert_eq!(error("Other::new"), "no function `Other::new` found");
+        assert!(error("Config::get").contains("takes `self`"));
+        assert!(error("raw").contains("is unsafe"));
+        assert!(error("generic").contains("generic type `T`"));
+        assert!(Table::parse("fn broken(", "broken")
+            .unwrap_err()
+            .starts_with("it does not parse"));
+    }
+}
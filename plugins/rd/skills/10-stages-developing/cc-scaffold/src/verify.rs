@@ -0,0 +1,178 @@
+//! Detached signatures for templates from outside this repository.
+//!
+//! A remote or user-registry template file may come with an SSH signature
+//! next to it (`function-template.rs.sig`), made with
+//!
+//! ```text
+//! ssh-keygen -Y sign -f ~/.ssh/id_ed25519 -n cc-scaffold function-template.rs
+//! ```
+//!
+//! Signatures are checked with `ssh-keygen -Y verify` against the trusted
+//! keys in [`allowed_signers`], in the format described in ssh-keygen(1).
+//! Once that file exists, every such template file must be signed.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::error::{Error, Result};
+use crate::locator;
+
+/// Signature namespace passed to `ssh-keygen -Y`, so signatures made for
+/// other purposes (such as git commits) are not accepted.
+pub const NAMESPACE: &str = "cc-scaffold";
+
+/// Trusted signing keys: `allowed_signers` under [`locator::agents_home`].
+pub fn allowed_signers() -> Option<PathBuf> {
+    Some(locator::agents_home()?.join("allowed_signers"))
+}
+
+/// Detached signature file for `path`.
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+/// Checks the signature of every file in `files`. Unsigned files are
+/// rejected only when `signers` is set; signed files always need it.
+pub fn check(files: &[PathBuf], signers: Option<&Path>) -> Result<()> {
+    for file in files {
+        let signature = signature_path(file);
+        if !signature.is_file() {
+            if let Some(signers) = signers {
+                return Err(unverified(
+                    file,
+                    &format!("it is not signed, and {} requires it", signers.display()),
+                ));
+            }
+            continue;
+        }
+        let Some(signers) = signers else {
+            return Err(unverified(
+                file,
+                "it is signed, but no trusted signers are configured",
+            ));
+        };
+        verify(file, &signature, signers)?;
+    }
+    Ok(())
+}
+
+fn verify(file: &Path, signature: &Path, signers: &Path) -> Result<()> {
+    let found = ssh_keygen(
+        file,
+        &["-Y", "find-principals", "-f"],
+        signers,
+        signature,
+        None,
+    )
+    .map_err(|_| unverified(file, "the signature is not from a trusted signer"))?;
+    let data = fs::read(file).map_err(|err| Error::io(file, err))?;
+    for principal in found.lines().filter(|l| !l.is_empty()) {
+        let args = ["-Y", "verify", "-n", NAMESPACE, "-I", principal, "-f"];
+        if ssh_keygen(file, &args, signers, signature, Some(&data)).is_ok() {
+            return Ok(());
+        }
+    }
+    Err(unverified(
+        file,
+        "the signature does not match its contents",
+    ))
+}
+
+/// Runs `ssh-keygen <args> <signers> -s <signature>`, feeding `stdin`.
+fn ssh_keygen(
+    file: &Path,
+    args: &[&str],
+    signers: &Path,
+    signature: &Path,
+    stdin: Option<&[u8]>,
+) -> Result<String> {
+    let mut child = Command::new("ssh-keygen")
+        .args(args)
+        .arg(signers)
+        .arg("-s")
+        .arg(signature)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| unverified(file, &format!("cannot run ssh-keygen: {err}")))?;
+    let mut input = child.stdin.take().expect("stdin is piped");
+    // ssh-keygen may exit before reading everything; its status says why.
+    let _ = input.write_all(stdin.unwrap_or_default());
+    drop(input);
+    let output = child
+        .wait_with_output()
+        .map_err(|err| Error::io(signature, err))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(unverified(
+            file,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        ))
+    }
+}
+
+fn unverified(path: &Path, message: &str) -> Error {
+    Error::Unverified {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(dir: &Path, args: &[&str]) {
+        let status = Command::new("ssh-keygen")
+            .current_dir(dir)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success(), "ssh-keygen {args:?}");
+    }
+
+    #[test]
+    fn test_check_signatures() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+        run(path, &["-q", "-t", "ed25519", "-N", "", "-f", "key"]);
+        run(path, &["-q", "-t", "ed25519", "-N", "", "-f", "other"]);
+        let key = fs::read_to_string(path.join("key.pub")).unwrap();
+        let signers = path.join("allowed_signers");
+        fs::write(&signers, format!("dev@example.com {key}")).unwrap();
+
+        let template = path.join("t.rs");
+        fs::write(&template, "fn x() {}\n").unwrap();
+        let files = vec![template.clone()];
+        check(&files, None).unwrap();
+        assert!(check(&files, Some(&signers)).is_err());
+
+        run(path, &["-Y", "sign", "-f", "key", "-n", NAMESPACE, "t.rs"]);
+        check(&files, Some(&signers)).unwrap();
+        let err = check(&files, None).unwrap_err();
+        assert!(err.to_string().contains("no trusted signers"));
+
+        fs::write(&template, "fn y() {}\n").unwrap();
+        let err = check(&files, Some(&signers)).unwrap_err();
+        assert!(err.to_string().contains("does not match"), "{err}");
+
+        fs::remove_file(signature_path(&template)).unwrap();
+        run(
+            path,
+            &["-Y", "sign", "-f", "other", "-n", NAMESPACE, "t.rs"],
+        );
+        let err = check(&files, Some(&signers)).unwrap_err();
+        assert!(
+            err.to_string().contains("not from a trusted signer"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Re-rendering a template as it is edited, for `cc-scaffold watch`.
+//!
+//! Each round renders the template into a preview directory with the
+//! values [`selftest`] would use, overridden by those in a values file,
+//! and optionally checks the output as a scratch crate. The files the
+//! round read — the template, its partials and base templates, its
+//! manifest and the values file — are then polled with [`Stamps`] until
+//! one of them changes, which starts the next round.
+//!
+//! A values file is a TOML table of variable values, as in the
+//! `[variables]` of `cc-agents.toml`:
+//!
+//! ```toml
+//! function_name = "parse_header"
+//! async = true
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use crate::config;
+use crate::error::{Error, Result};
+use crate::generate;
+use crate::hooks;
+use crate::locator::LocatedTemplate;
+use crate::selftest::{self, Step, Verdict};
+
+/// What one round rendered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Round {
+    pub template: String,
+    /// Files written into the preview directory.
+    pub files: Vec<PathBuf>,
+    /// Files the round read, to watch for the next one.
+    pub inputs: Vec<PathBuf>,
+    /// `passed` once rendered and, if asked for, checked.
+    #[serde(flatten)]
+    pub verdict: Verdict,
+}
+
+/// Reads the variable values in the TOML file at `path`.
+pub fn load_values(path: &Path) -> Result<BTreeMap<String, String>> {
+    let invalid = |message: String| Error::Config {
+        path: path.to_path_buf(),
+        message,
+    };
+    let text = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+    let raw = toml::from_str(&text).map_err(|err| invalid(err.to_string()))?;
+    config::variable_values(raw).map_err(invalid)
+}
+
+/// Renders `template` with `values` into `dir`, after removing the files
+/// `previous` wrote there. Given `check`, a target directory, the output
+/// is built with `cargo check`; a Rust template is then laid out in `dir`
+/// as a scratch crate, as for `selftest`.
+///
+/// Failing to render or check is the round's verdict rather than an error,
+/// so that watching goes on.
+pub fn render(
+    template: &LocatedTemplate,
+    values: &BTreeMap<String, String>,
+    dir: &Path,
+    check: Option<&Path>,
+    previous: &[PathBuf],
+) -> Result<Round> {
+    let manifest = template.manifest.as_ref();
+    let id = manifest.map_or_else(|| template.source.display().to_string(), |m| m.id());
+    let mut inputs = vec![template.source.clone()];
+    inputs.extend(manifest.map(|m| m.path.clone()));
+    let crate_manifest =
+        manifest.filter(|m| check.is_some() && (!m.files.is_empty() || hooks::is_rust(&m.file)));
+    let out = match (crate_manifest, manifest) {
+        (Some(manifest), _) => selftest::crate_output(manifest, dir),
+        (None, Some(manifest)) if !manifest.files.is_empty() => dir.to_path_buf(),
+        _ => dir.join(generate::default_file_name(template, values)),
+    };
+    let rendered = generate::render_bundle(template, &out, values);
+    for file in previous {
+        match fs::remove_file(file) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(Error::io(file, err)),
+        }
+    }
+    let files = match rendered {
+        Ok(files) => files,
+        Err(err) => {
+            return Ok(Round {
+                template: id,
+                files: Vec::new(),
+                inputs,
+                verdict: Verdict::Failed {
+                    step: Step::Render,
+                    output: err.to_string(),
+                    diagnostics: Vec::new(),
+                },
+            })
+        }
+    };
+    for input in files.iter().flat_map(|file| &file.inputs) {
+        if !inputs.contains(input) {
+            inputs.push(input.clone());
+        }
+    }
+    let is_crate = files.iter().any(|file| hooks::is_rust(&file.destination));
+    let (written, verdict) = match (crate_manifest.filter(|_| is_crate), check) {
+        (Some(manifest), Some(target_dir)) => {
+            let written = selftest::write_crate(manifest, dir, &files, values)?;
+            (written, selftest::check(dir, target_dir)?)
+        }
+        _ => {
+            for file in &files {
+                generate::write(file, true)?;
+            }
+            let verdict = match check {
+                Some(_) => Verdict::Skipped {
+                    reason: "renders no Rust source".to_string(),
+                },
+                None => Verdict::Passed,
+            };
+            (
+                files.iter().map(|f| f.destination.clone()).collect(),
+                verdict,
+            )
+        }
+    };
+    Ok(Round {
+        template: id,
+        files: written,
+        inputs,
+        verdict,
+    })
+}
+
+/// Modification times and sizes of a set of files, to tell when one of
+/// them changes, appears or disappears.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stamps(Vec<(PathBuf, Option<(SystemTime, u64)>)>);
+
+impl Stamps {
+    /// Stamps the files in `paths` as they are now.
+    pub fn take(paths: &[PathBuf]) -> Self {
+        Self(
+            paths
+                .iter()
+                .map(|path| {
+                    let stamp = fs::metadata(path)
+                        .ok()
+                        .and_then(|meta| Some((meta.modified().ok()?, meta.len())));
+                    (path.clone(), stamp)
+                })
+                .collect(),
+        )
+    }
+
+    /// Whether any of the files changed since they were stamped.
+    pub fn changed(&self) -> bool {
+        let paths: Vec<PathBuf> = self.0.iter().map(|(path, _)| path.clone()).collect();
+        *self != Self::take(&paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locator::include_dirs;
+    use crate::manifest::Manifest;
+    use crate::variables::EnvAccess;
+
+    fn located(dir: &Path, template: &str) -> LocatedTemplate {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("greet-template.txt"), template).unwrap();
+        let path = dir.join("greet.template.toml");
+        fs::write(
+            &path,
+            "name = \"greet\"\nlanguage = \"text\"\nstage = \"5-implementation\"\n\
+             description = \"Greeting\"\nfile = \"greet-template.txt\"\n\n\
+             [[variables]]\nname = \"name\"\ntype = \"identifier\"\nsample = \"world\"\n",
+        )
+        .unwrap();
+        let manifest = Manifest::load(&path).unwrap();
+        LocatedTemplate {
+            source: manifest.file.clone(),
+            include_dirs: include_dirs(&manifest.file),
+            manifest: Some(manifest),
+            env: EnvAccess::All,
+        }
+    }
+
+    #[test]
+    fn test_load_values_converts_scalars() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sample.toml");
+        fs::write(&path, "name = \"gizmo\"\ncount = 3\nasync = true\n").unwrap();
+        let values = load_values(&path).unwrap();
+        assert_eq!(values["name"], "gizmo");
+        assert_eq!(values["count"], "3");
+        assert_eq!(values["async"], "true");
+
+        fs::write(&path, "name = [1]\n").unwrap();
+        assert!(matches!(load_values(&path), Err(Error::Config { .. })));
+    }
+
+    #[test]
+    fn test_render_replaces_previous_round() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = located(&dir.path().join("templates"), "Hello, {{ name }}!\n");
+        let preview = dir.path().join("preview");
+        let values = selftest::sample_values(template.manifest.as_ref().unwrap());
+
+        let first = render(&template, &values, &preview, None, &[]).unwrap();
+        assert_eq!(first.verdict, Verdict::Passed);
+        assert_eq!(first.files, vec![preview.join("world.txt")]);
+        assert_eq!(
+            fs::read_to_string(preview.join("world.txt")).unwrap(),
+            "Hello, world!\n"
+        );
+        assert!(first.inputs.contains(&template.source));
+
+        let mut values = values;
+        values.insert("name".to_string(), "gizmo".to_string());
+        let second = render(&template, &values, &preview, None, &first.files).unwrap();
+        assert_eq!(second.files, vec![preview.join("gizmo.txt")]);
+        assert!(!preview.join("world.txt").exists());
+
+        fs::write(&template.source, "Hello, {{> missing}}\n").unwrap();
+        let failed = render(&template, &values, &preview, None, &second.files).unwrap();
+        assert!(matches!(
+            failed.verdict,
+            Verdict::Failed {
+                step: Step::Render,
+                ..
+            }
+        ));
+        assert!(failed.files.is_empty());
+    }
+
+    #[test]
+    fn test_stamps_notice_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("template.txt");
+        fs::write(&path, "one").unwrap();
+        let stamps = Stamps::take(std::slice::from_ref(&path));
+        assert!(!stamps.changed());
+        fs::write(&path, "three").unwrap();
+        assert!(stamps.changed());
+
+        let missing = Stamps::take(&[dir.path().join("missing.txt")]);
+        assert!(!missing.changed());
+        fs::write(dir.path().join("missing.txt"), "").unwrap();
+        assert!(missing.changed());
+    }
+}
@@ -0,0 +1,210 @@
+//! Cargo workspaces that generated crates join.
+//!
+//! [`find`] locates the workspace enclosing a new crate and [`add_member`]
+//! lists the crate in its `members`. The manifest is edited as text, so
+//! its comments and formatting survive.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// Whether `name` is a valid Cargo package name: ASCII letters, digits,
+/// `-` and `_`, starting with a letter.
+pub fn is_package_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The `Cargo.toml` of the nearest workspace enclosing `dir`, which need
+/// not exist yet.
+pub fn find(dir: &Path) -> Option<PathBuf> {
+    crate::lockfile::absolute(dir)
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| {
+            fs::read_to_string(manifest)
+                .ok()
+                .and_then(|text| text.parse::<toml::Table>().ok())
+                .is_some_and(|table| table.contains_key("workspace"))
+        })
+}
+
+/// The member path `crate_dir` needs in the `members` of the workspace
+/// `manifest`, relative to the workspace root, or `None` if a member entry
+/// (or glob such as `crates/*`) already covers it.
+pub fn member_to_add(manifest: &Path, crate_dir: &Path) -> Result<Option<String>> {
+    let member = crate::lockfile::relative_path(manifest, crate_dir);
+    if member.is_absolute() {
+        return Err(Error::Workspace {
+            path: manifest.to_path_buf(),
+            message: format!("{} is outside the workspace", crate_dir.display()),
+        });
+    }
+    let member = member.to_string_lossy().replace('\\', "/");
+    let table = load(manifest)?.1;
+    let members = table
+        .get("workspace")
+        .and_then(|w| w.get("members"))
+        .and_then(|m| m.as_array())
+        .map(|m| m.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    Ok((!members.iter().any(|pattern| covers(pattern, &member))).then_some(member))
+}
+
+/// Appends `member` to the `members` of the workspace `manifest`.
+pub fn add_member(manifest: &Path, member: &str) -> Result<()> {
+    let text = load(manifest)?.0;
+    let updated = insert_member(&text, member).ok_or_else(|| Error::Workspace {
+        path: manifest.to_path_buf(),
+        message: "`workspace.members` is not a plain array; add the crate by hand".to_string(),
+    })?;
+    fs::write(manifest, updated).map_err(|err| Error::io(manifest, err))
+}
+
+fn load(manifest: &Path) -> Result<(String, toml::Table)> {
+    let text = fs::read_to_string(manifest).map_err(|err| Error::io(manifest, err))?;
+    let table = text.parse().map_err(|err| Error::Workspace {
+        path: manifest.to_path_buf(),
+        message: format!("{err}"),
+    })?;
+    Ok((text, table))
+}
+
+/// Whether workspace member `pattern`, possibly ending in a `*` segment,
+/// names `member`.
+fn covers(pattern: &str, member: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+    match pattern.strip_suffix('*') {
+        Some(prefix) => member
+            .strip_prefix(prefix)
+            .is_some_and(|rest| !rest.is_empty() && !rest.contains('/')),
+        None => pattern == member,
+    }
+}
+
+/// `text` with `member` appended to the `members` array of its
+/// `[workspace]` table, which is created if missing.
+fn insert_member(text: &str, member: &str) -> Option<String> {
+    let entry = format!("\"{}\"", member.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut offset = 0;
+    let mut in_workspace = false;
+    let mut header_end = None;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_workspace = trimmed == "[workspace]";
+            if in_workspace {
+                header_end = Some(offset + line.len());
+            }
+        } else if in_workspace && key_of(trimmed) == Some("members") {
+            let open = offset + line.find('[')?;
+            let close = open + text[open..].find(']')?;
+            let inner = &text[open + 1..close];
+            let body = inner.trim_end();
+            let trailing = &inner[body.len()..];
+            let body_end = open + 1 + body.len();
+            let insertion = if body.trim().is_empty() {
+                entry
+            } else if inner.contains('\n') {
+                // One member per line: match the indentation of the last.
+                let indent: String = body
+                    .rsplit('\n')
+                    .next()
+                    .unwrap_or("")
+                    .chars()
+                    .take_while(|c| c.is_whitespace())
+                    .collect();
+                if body.ends_with(',') {
+                    format!("\n{indent}{entry},")
+                } else {
+                    format!(",\n{indent}{entry}")
+                }
+            } else {
+                let comma = if body.ends_with(',') { "" } else { "," };
+                format!("{comma} {entry}")
+            };
+            let mut out = String::with_capacity(text.len() + insertion.len());
+            out.push_str(&text[..body_end]);
+            out.push_str(&insertion);
+            out.push_str(trailing.trim_start_matches([' ', '\t']));
+            out.push_str(&text[close..]);
+            return Some(out);
+        }
+        offset += line.len();
+    }
+    let mut out = text.to_string();
+    match header_end {
+        Some(end) => out.insert_str(end, &format!("members = [{entry}]\n")),
+        None => {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str(&format!("\n[workspace]\nmembers = [{entry}]\n"));
+        }
+    }
+    Some(out)
+}
+
+/// The key of a `key = value` line.
+fn key_of(line: &str) -> Option<&str> {
+    line.split_once('=').map(|(key, _)| key.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_member_keeps_layout() {
+        assert_eq!(
+            insert_member("[workspace]\nmembers = [\"a\"]\n", "b").unwrap(),
+            "[workspace]\nmembers = [\"a\", \"b\"]\n"
+        );
+        assert_eq!(
+            insert_member("[workspace]\nmembers = [\n    \"a\",\n]\n", "crates/b").unwrap(),
+            "[workspace]\nmembers = [\n    \"a\",\n    \"crates/b\",\n]\n"
+        );
+        assert_eq!(
+            insert_member("# root\n[workspace]\nresolver = \"2\"\n", "b").unwrap(),
+            "# root\n[workspace]\nmembers = [\"b\"]\nresolver = \"2\"\n"
+        );
+        assert_eq!(
+            insert_member("[workspace]\nmembers = []\n", "b").unwrap(),
+            "[workspace]\nmembers = [\"b\"]\n"
+        );
+    }
+
+    #[test]
+    fn test_covers() {
+        assert!(covers("crates/*", "crates/b"));
+        assert!(!covers("crates/*", "crates/b/c"));
+        assert!(covers("b", "b"));
+        assert!(!covers("b", "bc"));
+    }
+
+    #[test]
+    fn test_add_member() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        fs::write(&manifest, "[workspace]\nmembers = [\"crates/*\"]\n").unwrap();
+        let crate_dir = dir.path().join("tools/gen");
+        assert_eq!(find(&crate_dir), Some(manifest.clone()));
+        let member = member_to_add(&manifest, &crate_dir).unwrap().unwrap();
+        assert_eq!(member, "tools/gen");
+        add_member(&manifest, &member).unwrap();
+        assert_eq!(member_to_add(&manifest, &crate_dir).unwrap(), None);
+        let covered = dir.path().join("crates/x");
+        assert_eq!(member_to_add(&manifest, &covered).unwrap(), None);
+        let text = fs::read_to_string(&manifest).unwrap();
+        assert_eq!(
+            text,
+            "[workspace]\nmembers = [\"crates/*\", \"tools/gen\"]\n"
+        );
+        assert!(is_package_name("my-crate_2"));
+        assert!(!is_package_name("2fast") && !is_package_name("a b"));
+    }
+}
@@ -0,0 +1,3666 @@
+//! End-to-end tests driving the `cc-scaffold` binary against the skill's
+//! real `templates/` tree.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn templates_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../templates")
+}
+
+/// The `cc-scaffold` binary, keeping its journal, caches and user
+/// templates in `home` instead of the real `~/.cc-agents`.
+fn binary(home: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_cc-scaffold"));
+    command.env("CC_AGENTS_HOME", home);
+    command
+}
+
+fn cc_scaffold(args: &[&str]) -> Output {
+    let home = tempfile::tempdir().unwrap();
+    binary(home.path())
+        .arg("--templates-dir")
+        .arg(templates_dir())
+        .args(args)
+        .output()
+        .expect("failed to run cc-scaffold")
+}
+
+#[test]
+fn test_new_writes_rendered_template() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("src/parser.rs");
+    let output = cc_scaffold(&[
+        "new",
+        "rust/function",
+        "--out",
+        out.to_str().unwrap(),
+        "--set",
+        "function_name=parse_header",
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let contents = fs::read_to_string(&out).unwrap();
+    assert!(contents.contains("pub fn parse_header(param1: &str"));
+    assert!(contents.contains("-> Result<ParseHeaderResult, String>"));
+    assert!(contents.contains("fn test_parse_header_smoke()"));
+    assert!(!contents.contains("function_name"));
+    assert!(!contents.contains("{{"));
+}
+
+#[test]
+fn test_new_conditional_flag() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("parser.rs");
+    let output = cc_scaffold(&[
+        "new",
+        "rust/function",
+        "--out",
+        out.to_str().unwrap(),
+        "--set",
+        "function_name=parse",
+        "--set",
+        "tracing=true",
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let contents = fs::read_to_string(&out).unwrap();
+    assert!(contents.contains("/// ```\n#[tracing::instrument(level = \"debug\")]\npub fn parse("));
+}
+
+#[test]
+fn test_new_substitutes_prose_placeholders() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("parser.rs");
+    let output = cc_scaffold(&[
+        "new",
+        "rust/function",
+        "--out",
+        out.to_str().unwrap(),
+        "--set",
+        "function_name=parse_header",
+        "--set",
+        "param1=input",
+        "--set",
+        "param1_description=Raw header line",
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let contents = fs::read_to_string(&out).unwrap();
+    assert!(contents.contains("/// * `input` - Raw header line\n"));
+    assert!(contents.contains("\"input cannot be empty\""));
+}
+
+#[test]
+fn test_new_refuses_to_overwrite_without_force() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("lib.rs");
+    fs::write(&out, "// hand written\n").unwrap();
+
+    let output = cc_scaffold(&[
+        "new",
+        "rust/function",
+        "--out",
+        out.to_str().unwrap(),
+        "--set",
+        "function_name=parse",
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("already exists"), "{stderr}");
+    assert!(stderr.contains("\nhelp: pass --force"), "{stderr}");
+    assert_eq!(fs::read_to_string(&out).unwrap(), "// hand written\n");
+
+    let output = cc_scaffold(&[
+        "new",
+        "rust/function",
+        "--out",
+        out.to_str().unwrap(),
+        "--set",
+        "function_name=parse",
+        "--force",
+    ]);
+    assert!(output.status.success());
+    assert!(fs::read_to_string(&out).unwrap().contains("pub fn parse"));
+}
+
+#[test]
+fn test_new_unknown_template_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("x.rs");
+    let output = cc_scaffold(&["new", "rust/does-not-exist", "--out", out.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not found"));
+    assert!(!out.exists());
+}
+
+#[test]
+fn test_new_rejects_invalid_identifier() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("x.rs");
+    let output = cc_scaffold(&[
+        "new",
+        "rust/function",
+        "--out",
+        out.to_str().unwrap(),
+        "--set",
+        "function_name=my func",
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("function-template.rs:3:15: invalid identifier `my func`"),
+        "{stderr}"
+    );
+    assert!(!out.exists());
+}
+
+#[test]
+fn test_new_requires_manifest_variables() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("x.rs");
+    let output = cc_scaffold(&[
+        "new",
+        "rust/function",
+        "--out",
+        out.to_str().unwrap(),
+        "--no-interactive",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("`function_name`"));
+}
+
+#[test]
+fn test_list_shows_manifests() {
+    let output = cc_scaffold(&["list"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("ID"));
+    assert!(stdout.contains("rust/function"));
+    assert!(stdout.contains("5-implementation"));
+    assert!(stdout.contains("python/test"));
+}
+
+#[test]
+fn test_new_expands_list_variables() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("parser.rs");
+    let output = cc_scaffold(&[
+        "new",
+        "rust/function",
+        "--out",
+        out.to_str().unwrap(),
+        "--set",
+        "function_name=parse",
+        "--set",
+        r#"test_vectors=("a", 1, "processed_a", 2, false), ("", 1, "", 0, true)"#,
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let contents = fs::read_to_string(&out).unwrap();
+    assert!(contents.contains(
+        "vec![\n            (\"a\", 1, \"processed_a\", 2, false),\n            (\"\", 1, \"\", 0, true),\n        ];"
+    ));
+}
+
+#[test]
+fn test_new_renders_async_variant() {
+    let dir = tempfile::tempdir().unwrap();
+    let render = |template: &str, extra: &[&str]| {
+        let out = dir
+            .path()
+            .join(format!("{}.rs", template.replace('/', "-")));
+        let mut args = vec![
+            "new",
+            template,
+            "--out",
+            out.to_str().unwrap(),
+            "--set",
+            "function_name=fetch",
+            "--no-interactive",
+            "--force",
+        ];
+        args.extend(extra);
+        let output = cc_scaffold(&args);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        fs::read_to_string(&out).unwrap()
+    };
+    let contents = render("rust/async-function", &[]);
+    assert!(contents.contains("/// TODO: describe what this function does\n"));
+    assert!(contents.contains("/// let result = fetch(\"example\", 42).await?;\n"));
+    assert!(contents.contains("/// # Cancellation safety\n"));
+    assert!(contents.contains("pub async fn fetch(param1: &str, param2: i32) -> Result<"));
+    assert!(contents.contains("tokio::time::timeout(limit, fetch(param1, param2))"));
+    assert!(contents.contains("    #[tokio::test]\n    async fn test_fetch_smoke() {\n"));
+    assert!(contents.contains("let result = fetch(\"test\", 10).await;\n"));
+    assert!(contents.contains("fetch_with_timeout(\"test\", 10, limit).await"));
+    assert!(!contents.contains("#[test]"));
+    assert!(!contents.contains("{{"));
+
+    // The variant is the async flag of rust/function, on by default.
+    assert_eq!(render("rust/function", &["--set", "async=true"]), contents);
+    let sync = render("rust/function", &[]);
+    assert!(!sync.contains("async"));
+    assert!(!sync.contains("Cancellation"));
+}
+
+/// What a rendered file must contain, or not.
+enum Expect {
+    Has(&'static str),
+    Lacks(&'static str),
+    StartsWith(&'static str),
+    Is(&'static str),
+}
+
+use Expect::*;
+
+/// `cc-scaffold new template --out out --set values...` and what each file
+/// it writes must look like, named relative to `out`; `""` is `out` itself.
+struct Render {
+    template: &'static str,
+    out: &'static str,
+    values: &'static [&'static str],
+    files: &'static [(&'static str, &'static [Expect])],
+}
+
+const RENDERS: &[Render] = &[
+    Render {
+        template: "rust/error-type",
+        out: "config.rs",
+        values: &[
+            "module=config",
+            r#"variants=Missing: "missing key, or empty""#,
+            "sources=Parse: std::num::ParseIntError",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub type Result<T> = std::result::Result<T, ConfigError>;"),
+                    Has("    #[error(\"missing key, or empty\")]\n    Missing,\n"),
+                    Has("    Parse(#[from] std::num::ParseIntError),\n}"),
+                    Has("assert_eq!(ConfigError::Missing.to_string(), \"missing key, or empty\");"),
+                    Has("converts::<std::num::ParseIntError>();"),
+                    Lacks("Io(#[from]"),
+                    Lacks("{{"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/builder",
+        out: "server.rs",
+        values: &[
+            "module=server",
+            r#"required=host: String: "localhost".to_string()"#,
+            "optional=port: u16: 8080",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct ServerBuilder {\n    host: Option<String>,\n    port: Option<u16>,\n}"),
+                    Has("host: self.host.ok_or(ServerError::MissingField(\"host\"))?,"),
+                    Has("port: self.port.unwrap_or(8080),"),
+                    Has("assert_eq!(*value.port(), 8080);"),
+                    Has("let last = \"host\";"),
+                    Lacks("capacity"),
+                    Lacks("{{"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/trait",
+        out: "storage.rs",
+        values: &[
+            "module=storage",
+            "impl_name=MemoryStorage",
+            "method_name=store",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub trait Storage {"),
+                    Has("fn store(&self, input: &str) -> Result<String, String>;"),
+                    Has("fn store_all(&self, inputs: &[&str])"),
+                    Has("impl Storage for MemoryStorage {"),
+                    Has("fn boxed() -> Box<dyn Storage> {"),
+                    Lacks("{{"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/mock",
+        out: "billing.rs",
+        values: &[
+            "module=billing",
+            "trait_name=Ledger",
+            "method_name=balance",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("#[cfg_attr(test, mockall::automock)]\npub trait Ledger {"),
+                    Has("impl<T: Ledger> BillingService<T> {"),
+                    Has("fn expect_balance(\n"),
+                    Has("    ) -> MockLedger {\n"),
+                    Has("mock.expect_balance()"),
+                    Has("BillingService::new(MockLedger::new())"),
+                    Lacks("{{"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/cli",
+        out: "app",
+        values: &[
+            "description=Count and greet",
+        ],
+        files: &[
+            (
+                "src/main.rs",
+                &[
+                    Has("/// Count and greet\n#[derive(Debug, Parser)]"),
+                    Has("CliError::Usage(_) => ExitCode::from(64),"),
+                ],
+            ),
+            (
+                "tests/cli.rs",
+                &[
+                    Has("Command::cargo_bin(env!(\"CARGO_PKG_NAME\"))"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/lib",
+        out: "lib.rs",
+        values: &[
+            "crate_name=shapes",
+            "modules=circle, square",
+            "prelude=circle::Circle, square::Square",
+            "forbid_unsafe=false",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("//! use shapes::prelude::*;\n"),
+                    Has("#![deny(missing_docs)]\n"),
+                    Has("pub mod circle;\npub mod square;\n"),
+                    Has("pub mod prelude {\n    pub use crate::circle::Circle;\n    pub use crate::square::Square;\n}"),
+                    Lacks("unsafe_code"),
+                    Lacks("{{"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/integration-test",
+        out: "project",
+        values: &[
+            "feature=storage",
+            "crate_name=vault",
+        ],
+        files: &[
+            (
+                "tests/storage_test.rs",
+                &[
+                    Has("through the public API of `vault`"),
+                    Has("fn test_storage_reads_input() {\n    // Arrange\n"),
+                    Has("impl Drop for Fixture {"),
+                    Lacks("{{"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/property",
+        out: "project",
+        values: &[
+            "module=ids",
+            "function_name=join_ids",
+            "inverse_name=split_ids",
+            "max_len=8",
+        ],
+        files: &[
+            (
+                "src/ids.rs",
+                &[
+                    Has("pub fn split_ids(text: &str)"),
+                    Has("fn test_join_ids_roundtrip(values in values()) {"),
+                    Has("any::<u32>(), 0..=8)"),
+                ],
+            ),
+            (
+                "proptest-regressions/ids.txt",
+                &[
+                    StartsWith("# Seeds for failure cases"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/golden",
+        out: "project",
+        values: &[
+            "feature=pretty_print",
+            "crate_name=docfmt",
+        ],
+        files: &[
+            (
+                "tests/golden/mod.rs",
+                &[
+                    Has("pub const UPDATE_ENV: &str = \"UPDATE_GOLDEN\";"),
+                    Has("pub fn diff(expected: &str, actual: &str) -> String {"),
+                ],
+            ),
+            (
+                "tests/pretty_print_golden.rs",
+                &[
+                    Has("mod golden;"),
+                    Has("golden::fixtures_dir(\"pretty_print\")"),
+                    Has("e.g. `docfmt::pretty_print::format(input)`"),
+                ],
+            ),
+            (
+                "tests/fixtures/pretty_print/basic.input",
+                &[
+                ],
+            ),
+            (
+                "tests/fixtures/pretty_print/basic.expected",
+                &[
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/benchmark",
+        out: "project",
+        values: &[
+            "bench=hashing",
+            "function_name=fnv1a",
+            "sizes=8, 1024",
+        ],
+        files: &[
+            (
+                "benches/hashing.rs",
+                &[
+                    Has("//! [[bench]]\n//! name = \"hashing\"\n//! harness = false\n"),
+                    Has("const SIZES: &[usize] = &[8, 1024];"),
+                    Has("BenchmarkId::new(\"fnv1a\", size)"),
+                    Has("BenchmarkId::new(\"baseline\", size)"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/ffi",
+        out: "ffi.rs",
+        values: &[
+            "function_name=count_words",
+            "prefix=textkit",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("#[no_mangle]\npub unsafe extern \"C\" fn textkit_count_words("),
+                    Has("-> TextkitStatus {"),
+                    Has("#[link_name = \"textkit_count_words\"]"),
+                    Has("//! include_guard = \"TEXTKIT_H\"\n"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/wasm",
+        out: "wasm.rs",
+        values: &[
+            "function_name=parse_color",
+            "max_len=64",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("#[wasm_bindgen(js_name = parseColor)]\npub fn js_parse_color(input: &str) -> Result<u32, JsValue> {"),
+                    Has("impl From<ParseColorError> for JsValue {"),
+                    Has("pub const MAX_INPUT: usize = 64;"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/worker-pool",
+        out: "pool.rs",
+        values: &[
+            "pool_name=ImagePool",
+            "queue_depth=8",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct ImagePool {"),
+                    Has("pub const QUEUE_CAPACITY: usize = 8;"),
+                    Has("impl Drop for ImagePool {"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/pipeline",
+        out: "pipeline.rs",
+        values: &[
+            "pipeline=index_documents",
+            "capacity=4",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub fn index_documents<I, T, O>("),
+                    Has(") -> Result<(), IndexDocumentsError>\n"),
+                    Has("pub const CHANNEL_CAPACITY: usize = 4;"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/retry",
+        out: "retry.rs",
+        values: &[
+            "function_name=with_retries",
+            "max_attempts=3",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub fn with_retries<T, E, F>("),
+                    Has("            max_attempts: 3,\n"),
+                    Has("Duration::from_millis(100),\n"),
+                    Lacks("retry_with_backoff"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/lru-cache",
+        out: "cache.rs",
+        values: &[
+            "cache_name=SessionCache",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct SessionCache<K, V> {"),
+                    Has("impl<K: Eq + Hash + Clone, V> SessionCache<K, V> {"),
+                    Has("pub struct CacheStats {"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/rate-limiter",
+        out: "limiter.rs",
+        values: &[
+            "limiter_name=UploadLimiter",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct UploadLimiter<C = SystemClock> {"),
+                    Has("impl<C: Clock> UploadLimiter<C> {"),
+                    Has("pub async fn acquire_async(&self) {"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/plugin-registry",
+        out: "plugins.rs",
+        values: &[
+            "plugin=Formatter",
+            "method_name=format",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub trait Formatter: Send + Sync {"),
+                    Has("pub struct FormatterRegistry {"),
+                    Has("    plugins: Vec<Box<dyn Formatter>>,\n"),
+                    Has("fn format(&self, input: String) -> String;"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/event-bus",
+        out: "events.rs",
+        values: &[
+            "bus_name=OrderEvents",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct OrderEvents {"),
+                    Has("let mut debug = f.debug_struct(\"OrderEvents\");"),
+                    Has("pub fn subscribe<E, F>(&self, handler: F) -> Subscription"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/tower-layer",
+        out: "middleware.rs",
+        values: &[
+            "name=TraceId",
+            "header=x-trace-id",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("impl<S> Layer<S> for TraceIdLayer {"),
+                    Has("impl<S, B, R> Service<Request<B>> for TraceId<S>\n"),
+                    Has("pub struct TraceIdValue(pub HeaderValue);"),
+                    Has("HeaderName::from_static(\"x-trace-id\");"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/pagination",
+        out: "pages.rs",
+        values: &[
+            "max_page_size=25",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub const MAX_PAGE_SIZE: usize = 25;"),
+                    Has("pub struct Page<T> {"),
+                    Has("pub fn decode(encoded: &str) -> Result<Self, CursorError> {"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/iterator-adapter",
+        out: "sample.rs",
+        values: &[
+            "adapter=Sample",
+            "double_ended=false",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub trait SampleExt: Iterator + Sized {"),
+                    Has("fn sample(self, step: usize) -> Sample<Self> {"),
+                    Has("impl<I: Iterator> Iterator for Sample<I> {"),
+                    Lacks("DoubleEndedIterator"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/newtype",
+        out: "contact.rs",
+        values: &[
+            "type_name=ContactEmail",
+            "max_len=120",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct ContactEmail(String);"),
+                    Has("pub const MAX_LEN: usize = 120;"),
+                    Has("impl TryFrom<String> for ContactEmail {"),
+                    Has("impl FromStr for ContactEmail {"),
+                    Has("#[cfg_attr(feature = \"serde\", serde(try_from = \"String\""),
+                    Has("type Error = ContactEmailError;"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/shutdown",
+        out: "shutdown.rs",
+        values: &[
+            "coordinator=Supervisor",
+            "drain_ms=250",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct Supervisor {"),
+                    Has("Duration::from_millis(250);"),
+                    Has("let future = task(self.token.child_token());"),
+                    Has("async fn test_abandons_tasks_past_drain_timeout()"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/signal",
+        out: "signals.rs",
+        values: &[
+            "signals=StopSignals",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct StopSignals {"),
+                    Has("#[cfg(unix)]\nmod os {"),
+                    Has("#[cfg(windows)]\nmod os {"),
+                    Has("let (deliver, mut signals) = StopSignals::channel();"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/scheduler",
+        out: "jobs.rs",
+        values: &[
+            "scheduler=JobRunner",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct JobRunner<C = SystemClock> {"),
+                    Has("impl<C: Clock> JobRunner<C> {"),
+                    Has("pub struct CronExpr {"),
+                    Has("fn test_missed_tick_policies()"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/file-watcher",
+        out: "watch.rs",
+        values: &[
+            "watcher=SourceWatcher",
+            "debounce_ms=50",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct SourceWatcher {"),
+                    Has("Duration::from_millis(50);"),
+                    Has("pub fn changes_in(event: &Event)"),
+                    Has("fn test_short_lived_file_is_not_reported()"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/unsafe",
+        out: "pair.rs",
+        values: &[
+            "function_name=pair_mut",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub unsafe fn pair_mut_unchecked<T>("),
+                    Has("/// # Safety"),
+                    Has("Some(unsafe { pair_mut_unchecked(slice, a, b) })"),
+                    Has("#![deny(unsafe_op_in_unsafe_fn)]"),
+                    Has("if cfg!(miri)"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/container",
+        out: "history.rs",
+        values: &[
+            "container=History",
+            "capacity=64",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct History<T> {"),
+                    Has("pub const DEFAULT_CAPACITY: usize = 64;"),
+                    Has("impl<T> FromIterator<T> for History<T> {"),
+                    Has("fn test_every_value_dropped_exactly_once()"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/arena",
+        out: "nodes.rs",
+        values: &[
+            "arena=NodeArena",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct NodeArena<T> {"),
+                    Has("pub struct NodeArenaIndex {"),
+                    Has("impl<T> ops::Index<NodeArenaIndex> for NodeArena<T> {"),
+                    Has("fn test_stale_index_cannot_reach_new_value()"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/display-fromstr",
+        out: "selector.rs",
+        values: &[
+            "type_name=Selector",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("impl fmt::Display for Selector {"),
+                    Has("impl FromStr for Selector {"),
+                    Has("type Err = ParseSelectorError;"),
+                    Has("fn test_parse_inverts_display(value in values())"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/shared-state",
+        out: "inventory.rs",
+        values: &[
+            "shared=Inventory",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct Inventory {"),
+                    Has("state: Arc<RwLock<State>>,"),
+                    Has(".unwrap_or_else(PoisonError::into_inner)"),
+                    Has("fn test_readers_never_see_half_a_transfer()"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/feature-gate",
+        out: "tracing.rs",
+        values: &[
+            "feature=span_timing",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("#[cfg(feature = \"span-timing\")]\nmod imp {"),
+                    Has("#[cfg(not(feature = \"span-timing\"))]\nmod imp {"),
+                    Has("//! span-timing = []"),
+                    Has("pub struct Recorder;"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/no-std-function",
+        out: "lib.rs",
+        values: &[
+            "function_name=decode_frame",
+            "key1_capacity=64",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("#![no_std]\n\n#[cfg(test)]\nextern crate std;"),
+                    Has("pub const KEY1_CAPACITY: usize = 64;"),
+                    Has("Result<DecodeFrameResult, DecodeFrameError>"),
+                    Has("impl core::error::Error for DecodeFrameError {}"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/actix",
+        out: "orders.rs",
+        values: &[
+            "module=orders",
+            "type_name=Order",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has(".service(web::scope(\"/orders\").configure(orders::configure))"),
+                    Has("input: web::Json<CreateOrder>,"),
+                    Has("const BASE: &str = \"/orders\";"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/redis-cache",
+        out: "sessions.rs",
+        values: &[
+            "cache_name=SessionCache",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct SessionCache<S> {"),
+                    Has("impl<S: Store> SessionCache<S> {"),
+                    Has("fn cache() -> SessionCache<MemoryStore> {"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/kafka-consumer",
+        out: "billing.rs",
+        values: &[
+            "consumer=BillingConsumer",
+            "event=OrderPlaced",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct BillingConsumer<S, D> {"),
+                    Has("        H: FnMut(OrderPlaced) -> Fut,\n"),
+                    Has("fn decode(record: &Record) -> Result<OrderPlaced, String> {"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/graphql-client",
+        out: "items.rs",
+        values: &[
+            "operation=GetItem",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("impl Operation for GetItem {"),
+                    Has("const NAME: &'static str = \"GetItem\";"),
+                    Has("\nquery GetItem($id: ID!) {\n"),
+                    Has("pub struct GetItemVariables {"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/tui-screen",
+        out: "tasks.rs",
+        values: &[
+            "screen=TaskList",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct TaskList {"),
+                    Has("screen: &mut TaskList,"),
+                    Has("use ratatui::backend::TestBackend;"),
+                    Lacks("ScreenName"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/macro",
+        out: "project",
+        values: &[
+            "module=maps",
+            "macro_name=ordered_map",
+            "crate_name=collections",
+        ],
+        files: &[
+            (
+                "src/maps.rs",
+                &[
+                    Has("#[macro_export]\nmacro_rules! ordered_map {"),
+                    Has("/// use collections::ordered_map;\n"),
+                ],
+            ),
+            (
+                "tests/ordered_map_ui.rs",
+                &[
+                    Has("cases.compile_fail(\"tests/ui/ordered_map/missing_value.rs\");"),
+                ],
+            ),
+            (
+                "tests/ui/ordered_map/missing_value.stderr",
+                &[
+                    StartsWith("error: ordered_map! takes `key => value` pairs\n"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/derive",
+        out: "project",
+        values: &[
+            "name=column-names",
+            "derive_name=Columns",
+        ],
+        files: &[
+            (
+                "Cargo.toml",
+                &[
+                    Has("members = [\"column-names\", \"column-names-tests\"]"),
+                ],
+            ),
+            (
+                "column-names/src/lib.rs",
+                &[
+                    Has("#[proc_macro_derive(Columns, attributes(columns))]"),
+                    Has("const ATTRIBUTE: &str = \"columns\";"),
+                ],
+            ),
+            (
+                "column-names-tests/tests/derive.rs",
+                &[
+                    Has("use column_names::Columns;\n"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/typestate",
+        out: "project",
+        values: &[
+            "module=upload",
+            "crate_name=transfer",
+        ],
+        files: &[
+            (
+                "src/upload.rs",
+                &[
+                    Has("//! use transfer::upload::Upload;\n"),
+                    Has("//! ```compile_fail,E0599\n"),
+                    Has("impl Upload<Running> {"),
+                    Has("pub fn stop(self) -> Upload<Stopped> {"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/serde",
+        out: "project",
+        values: &[
+            "module=shipment",
+            "deny_unknown_fields=false",
+        ],
+        files: &[
+            (
+                "src/shipment.rs",
+                &[
+                    Has("#[serde(rename_all = \"camelCase\")]\npub struct Shipment {"),
+                    Has("\"/tests/fixtures/shipment.json\""),
+                    Has("assert_eq!(parsed.unwrap().id, 7);"),
+                    Lacks("unknown field `colour`"),
+                ],
+            ),
+            (
+                "tests/fixtures/shipment.json",
+                &[
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/config",
+        out: "config.rs",
+        values: &[
+            "env_prefix=RELAY",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub const VAR_PREFIX: &str = \"RELAY_\";"),
+                    Has("//! 3. environment variables `RELAY_HOST`, `RELAY_PORT`,\n"),
+                    Has("fn test_env_overrides_file() {"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/telemetry",
+        out: "telemetry.rs",
+        values: &[
+            "default_filter=warn,relay=debug",
+            "default_format=json",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub const DEFAULT_FILTER: &str = \"warn,relay=debug\";"),
+                    Has("    Pretty,\n    /// One JSON object per line, for machines.\n    #[default]\n    Json,\n"),
+                    Has("pub fn instrumented<T>(name: &str, f: impl FnOnce() -> T) -> T {"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/http-client",
+        out: "billing.rs",
+        values: &[
+            "module=billing",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("pub struct BillingClient {"),
+                    Has("fn client(server: &MockServer) -> BillingClient {"),
+                    Has("status.is_server_error()"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/axum",
+        out: "orders.rs",
+        values: &[
+            "module=orders",
+            "type_name=Order",
+        ],
+        files: &[
+            (
+                "",
+                &[
+                    Has("//!     .nest(\"/orders\", orders::router())\n"),
+                    Has("Json(input): Json<CreateOrder>,"),
+                    Has("const BASE: &str = \"/orders\";"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/sqlx",
+        out: "project",
+        values: &[
+            "module=accounts",
+            "type_name=Account",
+            "crate_name=ledger",
+        ],
+        files: &[
+            (
+                "src/accounts.rs",
+                &[
+                    Has("pub struct AccountRepository {"),
+                    Has("\"SELECT id, title, body FROM accounts WHERE id = $1\""),
+                ],
+            ),
+            (
+                "migrations/0001_create_accounts.sql",
+                &[
+                    Has("CREATE TABLE accounts ("),
+                ],
+            ),
+            (
+                "tests/accounts.rs",
+                &[
+                    Has("use ledger::accounts::AccountRepository;\n"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/diesel",
+        out: "project",
+        values: &[
+            "module=accounts",
+            "type_name=Account",
+            "crate_name=ledger",
+        ],
+        files: &[
+            (
+                "src/schema.rs",
+                &[
+                    Has("    accounts (id) {\n"),
+                ],
+            ),
+            (
+                "src/accounts.rs",
+                &[
+                    Has("pub fn get(conn: &mut SqliteConnection, id: i64) -> Result<Account> {"),
+                ],
+            ),
+            (
+                "migrations/0001_create_accounts/up.sql",
+                &[
+                    Has("CREATE TABLE accounts ("),
+                ],
+            ),
+            (
+                "migrations/0001_create_accounts/down.sql",
+                &[
+                    Is("DROP TABLE accounts;\n"),
+                ],
+            ),
+            (
+                "tests/accounts.rs",
+                &[
+                    Has("use ledger::accounts::{self as repository, NewAccount, RepositoryError};\n"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/grpc",
+        out: "project",
+        values: &[
+            "module=inventory",
+            "crate_name=warehouse",
+            "rpc=CountItems",
+        ],
+        files: &[
+            (
+                "proto/inventory.proto",
+                &[
+                    Has("package inventory;"),
+                    Has("rpc CountItems (CountItemsRequest) returns (CountItemsReply);"),
+                ],
+            ),
+            (
+                "build.rs",
+                &[
+                    Has("tonic_build::compile_protos(\"proto/inventory.proto\")?;"),
+                ],
+            ),
+            (
+                "src/inventory.rs",
+                &[
+                    Has("impl Inventory for InventoryService {"),
+                    Has("async fn count_items("),
+                ],
+            ),
+            (
+                "tests/inventory.rs",
+                &[
+                    Has("use warehouse::inventory::proto::inventory_client::InventoryClient;"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/protobuf",
+        out: "project",
+        values: &[
+            "module=parcel",
+            "crate_name=courier",
+            "package=courier_v1",
+        ],
+        files: &[
+            (
+                "proto/parcel.proto",
+                &[
+                    Has("package courier_v1;"),
+                    Has("message Parcel {"),
+                ],
+            ),
+            (
+                "build.rs",
+                &[
+                    Has("prost_build::compile_protos(&[\"proto/parcel.proto\"], &[\"proto\"])?;"),
+                ],
+            ),
+            (
+                "src/parcel.rs",
+                &[
+                    Has("include!(concat!(env!(\"OUT_DIR\"), \"/courier_v1.rs\"));"),
+                    Has("impl TryFrom<proto::Parcel> for Parcel {"),
+                ],
+            ),
+            (
+                "tests/parcel.rs",
+                &[
+                    Has("use courier::parcel::{DecodeError, Status, Parcel};"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/line-parser",
+        out: "project",
+        values: &[
+            "module=stock",
+        ],
+        files: &[
+            (
+                "src/stock.rs",
+                &[
+                    Has("pub struct StockRecord {"),
+                    Has("impl<R: BufRead> Iterator for StockRecordParser<R> {"),
+                    Has("\"/tests/fixtures/stock.csv\""),
+                ],
+            ),
+            (
+                "tests/fixtures/stock.csv",
+                &[
+                    StartsWith("name,quantity,price\n"),
+                ],
+            ),
+        ],
+    },
+    Render {
+        template: "rust/rest-client",
+        out: "project",
+        values: &[
+            "module=billing",
+            "crate_name=shop",
+        ],
+        files: &[
+            (
+                "src/billing/mod.rs",
+                &[
+                    Has("pub use client::BillingClient;"),
+                ],
+            ),
+            (
+                "src/billing/client.rs",
+                &[
+                    Has("Auth::Bearer(token) => request.bearer_auth(token),"),
+                ],
+            ),
+            (
+                "src/billing/error.rs",
+                &[
+                    Has("Transport(reqwest::Error),"),
+                ],
+            ),
+            (
+                "src/billing/users.rs",
+                &[
+                    Has("pub async fn get(&self, id: u64) -> Result<User, ApiError> {"),
+                ],
+            ),
+            (
+                "tests/billing.rs",
+                &[
+                    Has("use shop::billing::BillingClient;"),
+                    Has("fn test_unreachable_service_is_a_transport_error()"),
+                ],
+            ),
+        ],
+    },
+];
+
+/// Renders every shipped Rust template: those in [`RENDERS`] with the values
+/// there, checking what they write, and the rest, other than bundles that
+/// inject into existing files, with their self-test sample values.
+#[test]
+fn test_new_renders_shipped_templates() {
+    let manifests = cc_scaffold::Locator::new(vec![templates_dir()])
+        .manifests()
+        .unwrap();
+    for render in RENDERS {
+        assert!(
+            manifests.iter().any(|m| m.id() == render.template),
+            "no template {}",
+            render.template
+        );
+    }
+    for manifest in manifests.iter().filter(|m| m.language == "rust") {
+        let id = manifest.id();
+        let renders: Vec<&Render> = RENDERS.iter().filter(|r| r.template == id).collect();
+        // Injected files need a file to inject into; see
+        // test_new_injects_subcommand_bundle_into_cli.
+        let injects = manifest.files.iter().any(|file| file.inject.is_some());
+        if renders.is_empty() && !injects {
+            let dir = tempfile::tempdir().unwrap();
+            let out = cc_scaffold::selftest::crate_output(manifest, &dir.path().join("crate"));
+            let values: Vec<String> = cc_scaffold::selftest::sample_values(manifest)
+                .into_iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect();
+            let mut args = vec!["new", &id, "--out", out.to_str().unwrap()];
+            for value in &values {
+                args.extend(["--set", value]);
+            }
+            args.push("--no-interactive");
+            let output = cc_scaffold(&args);
+            assert!(
+                output.status.success(),
+                "{id}: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        for render in renders {
+            let dir = tempfile::tempdir().unwrap();
+            let out = dir.path().join(render.out);
+            let mut args = vec!["new", render.template, "--out", out.to_str().unwrap()];
+            for value in render.values {
+                args.extend(["--set", value]);
+            }
+            args.push("--no-interactive");
+            let output = cc_scaffold(&args);
+            assert!(
+                output.status.success(),
+                "{id}: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            for (file, expects) in render.files {
+                let path = if file.is_empty() {
+                    out.clone()
+                } else {
+                    out.join(file)
+                };
+                let contents = fs::read_to_string(&path)
+                    .unwrap_or_else(|err| panic!("{id}: {}: {err}", path.display()));
+                for expect in expects.iter() {
+                    let ok = match *expect {
+                        Has(text) => contents.contains(text),
+                        Lacks(text) => !contents.contains(text),
+                        StartsWith(text) => contents.starts_with(text),
+                        Is(text) => contents == text,
+                    };
+                    assert!(ok, "{id}: {}:\n{contents}", path.display());
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_new_renders_sqlx_migration_pair() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    let output = cc_scaffold(&[
+        "new",
+        "rust/sqlx-migration",
+        "--out",
+        project.to_str().unwrap(),
+        "--set",
+        "migration=add_notes_archived_at",
+        "--set",
+        "version=20260314092653",
+        "--set",
+        "table=notes",
+        "--set",
+        "column=archived_at",
+        "--no-interactive",
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let migrations = project.join("migrations");
+    let up =
+        fs::read_to_string(migrations.join("20260314092653_add_notes_archived_at.up.sql")).unwrap();
+    assert!(up.contains("ALTER TABLE notes ADD COLUMN archived_at TEXT;\n"));
+    let down = fs::read_to_string(migrations.join("20260314092653_add_notes_archived_at.down.sql"))
+        .unwrap();
+    assert!(down.contains("ALTER TABLE notes DROP COLUMN archived_at;\n"));
+    let tests = fs::read_to_string(project.join("tests/migrate_add_notes_archived_at.rs")).unwrap();
+    assert!(tests.contains("const VERSION: i64 = 20260314092653;\n"));
+
+    let output = cc_scaffold(&[
+        "new",
+        "rust/sqlx-migration",
+        "--out",
+        project.to_str().unwrap(),
+        "--set",
+        "migration=add_tags",
+        "--set",
+        "version=next",
+        "--no-interactive",
+    ]);
+    assert!(!output.status.success());
+
+    // Without a version, the migration is stamped with the current UTC time.
+    let output = cc_scaffold(&[
+        "new",
+        "rust/sqlx-migration",
+        "--out",
+        project.to_str().unwrap(),
+        "--set",
+        "migration=add_tags",
+        "--set",
+        "table=notes",
+        "--set",
+        "column=tags",
+        "--no-interactive",
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stamped: Vec<String> = fs::read_dir(&migrations)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with("_add_tags.up.sql"))
+        .collect();
+    assert_eq!(stamped.len(), 1, "{stamped:?}");
+    let version = stamped[0].split('_').next().unwrap();
+    assert!(
+        version.len() == 14 && version.chars().all(|c| c.is_ascii_digit()),
+        "{version}"
+    );
+}
+
+#[test]
+fn test_update_keeps_generated_migration_version() {
+    let project = tempfile::tempdir().unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .current_dir(project.path())
+            .arg("--templates-dir")
+            .arg(templates_dir())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let migrations = || -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(project.path().join("p/migrations"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    };
+
+    let output = run(&[
+        "new",
+        "rust/sqlx-migration",
+        "--out",
+        "p",
+        "--set",
+        "migration=add_tags",
+        "--set",
+        "table=notes",
+        "--set",
+        "column=tags",
+        "--no-interactive",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let generated = migrations();
+    assert_eq!(generated.len(), 2, "{generated:?}");
+    let version = generated[0].split('_').next().unwrap().to_string();
+    let lock = fs::read_to_string(project.path().join("cc-agents.lock")).unwrap();
+    assert!(
+        lock.contains(&format!("version = \"{version}\"\n")),
+        "{lock}"
+    );
+
+    // A second later the default would be another version.
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let output = run(&["update"]);
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stderr.contains("no longer generated"), "{stderr}");
+    assert_eq!(migrations(), generated);
+}
+
+#[test]
+fn test_new_renders_serde_snapshots_only_when_asked() {
+    let dir = tempfile::tempdir().unwrap();
+    for (project, snapshots) in [("literal", "false"), ("snapshot", "true")] {
+        let output = cc_scaffold(&[
+            "new",
+            "rust/serde",
+            "--out",
+            dir.path().join(project).to_str().unwrap(),
+            "--set",
+            "module=invoice",
+            "--set",
+            &format!("snapshots={snapshots}"),
+            "--no-interactive",
+        ]);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let literal = dir.path().join("literal");
+    assert!(!literal.join("src/snapshots").exists());
+    let model = fs::read_to_string(literal.join("src/invoice.rs")).unwrap();
+    assert!(model.contains("fn test_serialize_matches_fixture()"));
+    assert!(!model.contains("insta"));
+
+    let snapshot = dir.path().join("snapshot");
+    let serialized =
+        fs::read_to_string(snapshot.join("src/snapshots/invoice_serialized.snap")).unwrap();
+    assert!(serialized.starts_with("---\nsource: src/invoice.rs\n"));
+    assert!(serialized.contains("\"id\": \"[id]\""));
+    assert!(snapshot
+        .join("src/snapshots/invoice_defaults.snap")
+        .exists());
+    let model = fs::read_to_string(snapshot.join("src/invoice.rs")).unwrap();
+    assert!(model.contains("fn snapshot_settings(redacted: &[&str]) -> insta::Settings {"));
+    assert!(model.contains("insta::assert_json_snapshot!(\"invoice_serialized\", sample());"));
+    assert!(!model.contains("fn test_serialize_matches_fixture()"));
+}
+
+#[test]
+fn test_new_injects_subcommand_bundle_into_cli() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = dir.path().join("app");
+    let app_arg = app.to_str().unwrap();
+    let subcommand = [
+        "new",
+        "rust/subcommand",
+        "--out",
+        app_arg,
+        "--set",
+        "subcommand=WordCount",
+        "--no-interactive",
+    ];
+    let output = cc_scaffold(&subcommand);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("it does not exist"), "{stderr}");
+
+    let cli = ["new", "rust/cli", "--out", app_arg, "--no-interactive"];
+    assert!(cc_scaffold(&cli).status.success());
+    let output = cc_scaffold(&subcommand);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("injected into"), "{stdout}");
+    let main = fs::read_to_string(app.join("src/main.rs")).unwrap();
+    assert!(main.contains("    WordCount(WordCountArgs),\n}"));
+    assert!(main.contains(
+        "Command::WordCount(args) => args.run(cli.verbose),\n        // cc-agents:insert dispatch"
+    ));
+    assert!(main.contains("struct WordCountArgs {"));
+    let tests = fs::read_to_string(app.join("tests/cli.rs")).unwrap();
+    assert!(tests.contains("fn test_greet_default()"));
+    assert!(tests.contains("let command = \"word-count\";"));
+
+    // Injecting again changes nothing.
+    let output = cc_scaffold(&subcommand);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("is up to date"), "{stderr}");
+    assert_eq!(fs::read_to_string(app.join("src/main.rs")).unwrap(), main);
+}
+
+#[test]
+fn test_new_dry_run_prints_diff_without_writing() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("parser.rs");
+    let args = [
+        "new",
+        "rust/function",
+        "--out",
+        out.to_str().unwrap(),
+        "--set",
+        "function_name=parse",
+        "--dry-run",
+    ];
+    let output = cc_scaffold(&args);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("--- /dev/null\n"));
+    assert!(stdout.contains("+pub fn parse(param1: &str, param2: i32)"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("would create"));
+    assert!(!out.exists());
+
+    let mut changed = args.to_vec();
+    changed.pop();
+    assert!(cc_scaffold(&changed).status.success());
+    changed[5] = "function_name=parse_line";
+    changed.push("--dry-run");
+    let output = cc_scaffold(&changed);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout
+        .contains("-pub fn parse(param1: &str, param2: i32) -> Result<ParseResult, String> {\n"));
+    assert!(stdout.contains(
+        "+pub fn parse_line(param1: &str, param2: i32) -> Result<ParseLineResult, String> {\n"
+    ));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("writing it requires --force"));
+    assert!(fs::read_to_string(&out).unwrap().contains("pub fn parse("));
+}
+
+#[test]
+fn test_json_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("parser.rs");
+    let output = cc_scaffold(&[
+        "--format",
+        "json",
+        "new",
+        "rust/function",
+        "--out",
+        out.to_str().unwrap(),
+        "--set",
+        "function_name=parse",
+    ]);
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["action"], "created");
+    assert_eq!(report["template"], "rust/function");
+    assert_eq!(report["destination"], out.to_str().unwrap());
+    assert_eq!(
+        report["contents"].as_str().unwrap(),
+        fs::read_to_string(&out).unwrap()
+    );
+    assert_eq!(report["warnings"], serde_json::json!([]));
+
+    let output = cc_scaffold(&["new", "rust/function", "--format", "json", "--out", "x.rs"]);
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report["error"],
+        "missing value for required variable `function_name`"
+    );
+    assert_eq!(report["code"], "missing_variable");
+    assert_eq!(report["suggestion"], "pass --set function_name=VALUE");
+
+    let json_new = |set: &str| {
+        let output = cc_scaffold(&[
+            "--format",
+            "json",
+            "new",
+            "rust/function",
+            "--out",
+            out.to_str().unwrap(),
+            "--set",
+            set,
+        ]);
+        assert!(!output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+    let report = json_new("function_name=parse");
+    assert_eq!(report["code"], "destination_exists");
+    assert_eq!(report["file"], out.to_str().unwrap());
+    assert!(report["suggestion"].as_str().unwrap().contains("--force"));
+    let report = json_new("function_name=my func");
+    assert_eq!(report["code"], "invalid_value");
+    assert!(report["file"]
+        .as_str()
+        .unwrap()
+        .ends_with("function-template.rs"));
+    assert_eq!(report["span"], serde_json::json!({"line": 3, "column": 15}));
+    assert!(report.get("suggestion").is_none());
+
+    let output = cc_scaffold(&["list", "--format", "json"]);
+    let templates: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let function = templates
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["id"] == "rust/function")
+        .unwrap();
+    assert_eq!(function["stage"], "5-implementation");
+    assert_eq!(function["variables"][0]["name"], "function_name");
+    assert_eq!(function["variables"][0]["required"], true);
+}
+
+#[test]
+fn test_list_filters() {
+    let stdout = |args: &[&str]| String::from_utf8(cc_scaffold(args).stdout).unwrap();
+
+    let rust = stdout(&["list", "--lang", "rust"]);
+    assert!(rust.contains("rust/function "));
+    assert!(!rust.contains("go/"));
+
+    let tests = stdout(&["list", "--stage", "6", "--tag", "testing"]);
+    assert!(tests.contains("python/test"));
+    assert!(!tests.contains("rust/function"));
+
+    let async_only = stdout(&["list", "--tag", "async", "--format", "json"]);
+    let templates: serde_json::Value = serde_json::from_str(&async_only).unwrap();
+    let ids: Vec<_> = templates
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["rust/async-function"]);
+
+    let output = cc_scaffold(&["list", "--lang", "cobol"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_search_ranks_templates() {
+    let output = cc_scaffold(&["search", "rust", "fucntion"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let first = stdout.lines().nth(1).unwrap();
+    assert!(first.starts_with("rust/"), "{stdout}");
+    assert!(stdout.contains("rust/async-function"));
+
+    let output = cc_scaffold(&[
+        "search", "python", "tests", "--format", "json", "--limit", "1",
+    ]);
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(results.as_array().unwrap().len(), 1);
+    assert_eq!(results[0]["template"]["id"], "python/test");
+}
+
+#[test]
+fn test_templates_add_overlays_builtin_templates() {
+    let home = tempfile::tempdir().unwrap();
+    let source = tempfile::tempdir().unwrap();
+    fs::create_dir_all(source.path().join("rust")).unwrap();
+    fs::write(
+        source.path().join("rust/private.template.toml"),
+        "name = \"private\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+         description = \"Team-only helper\"\nfile = \"private-template.rs\"\n\n\
+         [[variables]]\nname = \"name\"\ntype = \"identifier\"\n",
+    )
+    .unwrap();
+    fs::write(
+        source.path().join("rust/private-template.rs"),
+        "pub fn {{ name }}() {}\n\n{{> partials/test-header}}\n}\n",
+    )
+    .unwrap();
+    let user_scaffold = |args: &[&str]| {
+        binary(home.path())
+            .env_remove("CC_SCAFFOLD_TEMPLATES")
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .args(args)
+            .output()
+            .expect("failed to run cc-scaffold")
+    };
+
+    let output = user_scaffold(&["templates", "add", source.path().to_str().unwrap()]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(home
+        .path()
+        .join("templates/rust/private-template.rs")
+        .is_file());
+    let output = user_scaffold(&["templates", "add", source.path().to_str().unwrap()]);
+    assert!(!output.status.success());
+
+    let output = user_scaffold(&["list", "--lang", "rust"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("rust/private"));
+    assert!(stdout.contains("rust/function"));
+
+    let out = home.path().join("out.rs");
+    let output = user_scaffold(&[
+        "new",
+        "rust/private",
+        "--out",
+        out.to_str().unwrap(),
+        "--set",
+        "name=helper",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let contents = fs::read_to_string(&out).unwrap();
+    assert!(contents.starts_with("pub fn helper() {}\n\n#[cfg(test)]\nmod tests {\n"));
+}
+
+#[test]
+fn test_new_renders_remote_template() {
+    let repo = tempfile::tempdir().unwrap();
+    fs::create_dir_all(repo.path().join("templates/rust")).unwrap();
+    fs::write(
+        repo.path().join("templates/rust/actor.rs"),
+        "pub struct ActorName;\n",
+    )
+    .unwrap();
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(repo.path())
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&["commit", "-qm", "actor"]);
+    git(&["tag", "v1"]);
+
+    let home = tempfile::tempdir().unwrap();
+    let out = home.path().join("actor.rs");
+    let spec = format!(
+        "git+file://{}//templates/rust/actor#v1",
+        repo.path().display()
+    );
+    let output = binary(home.path())
+        .args(["new", &spec, "--out", out.to_str().unwrap()])
+        .args(["--set", "ActorName=Mailbox"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(fs::read_to_string(&out).unwrap(), "pub struct Mailbox;\n");
+    assert!(home.path().join("cache/git").is_dir());
+    let lock = fs::read_to_string(home.path().join("cc-agents.lock")).unwrap();
+    assert!(lock.contains("revision = "));
+
+    // Moving the tag does not affect files generated under the lock.
+    fs::write(
+        repo.path().join("templates/rust/actor.rs"),
+        "pub enum ActorName {}\n",
+    )
+    .unwrap();
+    git(&["commit", "-qam", "enum"]);
+    git(&["tag", "-f", "v1"]);
+    let second = home.path().join("second.rs");
+    let output = binary(home.path())
+        .args(["new", &spec, "--refresh", "--out", second.to_str().unwrap()])
+        .args(["--set", "ActorName=Inbox"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "pub struct Inbox;\n");
+}
+
+#[test]
+fn test_lockfile_pins_template_until_update() {
+    let templates = tempfile::tempdir().unwrap();
+    fs::create_dir_all(templates.path().join("rust")).unwrap();
+    let template = templates.path().join("rust/widget.rs");
+    fs::write(&template, "pub struct Widget;\n").unwrap();
+    let project = tempfile::tempdir().unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .current_dir(project.path())
+            .arg("--templates-dir")
+            .arg(templates.path())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&[
+        "new",
+        "rust/widget",
+        "--out",
+        "a.rs",
+        "--set",
+        "Widget=Gear",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let lock = fs::read_to_string(project.path().join("cc-agents.lock")).unwrap();
+    assert!(
+        lock.contains("path = \"a.rs\"\ntemplate = \"rust/widget\"\nsource = \"rust/widget.rs\"\n")
+    );
+    assert!(lock.contains("[file.values]\nWidget = \"Gear\"\n"));
+
+    fs::write(&template, "/// Generated.\npub struct Widget;\n").unwrap();
+    let output = run(&["new", "rust/widget", "--out", "b.rs"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("has changed since it was locked"),
+        "{stderr}"
+    );
+
+    let output = run(&["update", "--dry-run"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("+/// Generated.\n"));
+    let output = run(&["update"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        fs::read_to_string(project.path().join("a.rs")).unwrap(),
+        "/// Generated.\npub struct Gear;\n"
+    );
+    assert!(run(&["new", "rust/widget", "--out", "b.rs"])
+        .status
+        .success());
+
+    fs::write(project.path().join("a.rs"), "// mine\n").unwrap();
+    fs::write(&template, "pub struct Widget(u8);\n").unwrap();
+    let output = run(&["update", "rust/widget"]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("a.rs was edited since it was generated"),
+        "{stderr}"
+    );
+    assert_eq!(
+        fs::read_to_string(project.path().join("a.rs")).unwrap(),
+        "// mine\n"
+    );
+    assert_eq!(
+        fs::read_to_string(project.path().join("b.rs")).unwrap(),
+        "pub struct Widget(u8);\n"
+    );
+}
+
+#[test]
+fn test_registry_templates_require_trusted_signatures() {
+    let home = tempfile::tempdir().unwrap();
+    let rust = home.path().join("templates/rust");
+    fs::create_dir_all(&rust).unwrap();
+    fs::write(rust.join("signed.rs"), "pub struct Signed;\n").unwrap();
+    let keygen = |args: &[&str]| {
+        let status = Command::new("ssh-keygen")
+            .current_dir(home.path())
+            .args(args)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    keygen(&["-q", "-t", "ed25519", "-N", "", "-f", "key"]);
+    let key = fs::read_to_string(home.path().join("key.pub")).unwrap();
+    fs::write(
+        home.path().join("allowed_signers"),
+        format!("team@example.com {key}"),
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .env_remove("CC_SCAFFOLD_TEMPLATES")
+            .current_dir(home.path())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["new", "rust/signed", "--out", "a.rs", "--no-lock"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("signed.rs: it is not signed"), "{stderr}");
+
+    let output = run(&[
+        "new",
+        "rust/signed",
+        "--out",
+        "a.rs",
+        "--no-lock",
+        "--allow-unverified",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("warning: cannot verify"));
+
+    let template = rust.join("signed.rs");
+    keygen(&[
+        "-Y",
+        "sign",
+        "-f",
+        "key",
+        "-n",
+        "cc-scaffold",
+        template.to_str().unwrap(),
+    ]);
+    let output = run(&["new", "rust/signed", "--out", "b.rs", "--no-lock"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(!String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("cannot verify"));
+}
+
+#[test]
+fn test_registry_templates_read_only_listed_environment() {
+    let home = tempfile::tempdir().unwrap();
+    let rust = home.path().join("templates/rust");
+    fs::create_dir_all(&rust).unwrap();
+    fs::write(
+        rust.join("token.template.toml"),
+        "name = \"token\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+         description = \"Token\"\nfile = \"token-template.rs\"\nenv = [\"CC_TEST_OWNER\"]\n",
+    )
+    .unwrap();
+    let template = rust.join("token-template.rs");
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .env_remove("CC_SCAFFOLD_TEMPLATES")
+            .env("CC_TEST_OWNER", "jane")
+            .env("CC_TEST_SECRET", "hunter2")
+            .current_dir(home.path())
+            .args([
+                "new",
+                "rust/token",
+                "--no-lock",
+                "--allow-unverified",
+                "--force",
+            ])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    fs::write(&template, "// {{ env.CC_TEST_OWNER }}\n").unwrap();
+    let output = run(&["--out", "a.rs"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        fs::read_to_string(home.path().join("a.rs")).unwrap(),
+        "// jane\n"
+    );
+
+    fs::write(&template, "// {{ env.CC_TEST_SECRET }}\n").unwrap();
+    let output = run(&["--out", "b.rs"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("undefined variable `env.CC_TEST_SECRET`"),
+        "{stderr}"
+    );
+    assert!(!home.path().join("b.rs").exists());
+
+    // The same template in a templates directory of the user's own reads it.
+    let local = tempfile::tempdir().unwrap();
+    fs::create_dir_all(local.path().join("rust")).unwrap();
+    for name in ["token.template.toml", "token-template.rs"] {
+        fs::copy(rust.join(name), local.path().join("rust").join(name)).unwrap();
+    }
+    let output = binary(home.path())
+        .env("CC_TEST_SECRET", "hunter2")
+        .current_dir(home.path())
+        .arg("--templates-dir")
+        .arg(local.path())
+        .args(["new", "rust/token", "--no-lock", "--out", "c.rs"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        fs::read_to_string(home.path().join("c.rs")).unwrap(),
+        "// hunter2\n"
+    );
+}
+
+#[test]
+fn test_new_generates_cargo_generate_project() {
+    let templates = tempfile::tempdir().unwrap();
+    let root = templates.path().join("rust/cli-app");
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(
+        root.join("cargo-generate.toml"),
+        "[placeholders.description]\ntype = \"string\"\nprompt = \"Description?\"\n\
+         default = \"A tool\"\n\n[hooks]\npre = [\"pre.rhai\"]\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"{{project-name}}\"\ndescription = \"{{ description }}\"\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("src/main.rs"),
+        "fn main() {\n    println!(\"{{crate_name}}\");\n}\n",
+    )
+    .unwrap();
+    let out = templates.path().join("generated");
+    let home = tempfile::tempdir().unwrap();
+    let output = binary(home.path())
+        .arg("--templates-dir")
+        .arg(templates.path())
+        .args(["new", "rust/cli-app", "--out", out.to_str().unwrap()])
+        .args(["--set", "project-name=hello-cli"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("hooks are not supported"), "{stderr}");
+    assert_eq!(
+        fs::read_to_string(out.join("Cargo.toml")).unwrap(),
+        "[package]\nname = \"hello-cli\"\ndescription = \"A tool\"\n"
+    );
+    assert!(fs::read_to_string(out.join("src/main.rs"))
+        .unwrap()
+        .contains("println!(\"hello_cli\");"));
+    assert!(!out.join("cargo-generate.toml").exists());
+}
+
+#[test]
+fn test_new_renders_tera_template_with_tera_partials() {
+    let templates = tempfile::tempdir().unwrap();
+    let rust = templates.path().join("rust");
+    fs::create_dir_all(rust.join("partials")).unwrap();
+    fs::write(
+        rust.join("handler.template.toml"),
+        "name = \"handler\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+         description = \"Tera handler\"\nfile = \"handler-template.rs\"\nsyntax = \"tera\"\n\n\
+         [[variables]]\nname = \"name\"\ntype = \"identifier\"\n\n\
+         [[variables]]\nname = \"routes\"\ntype = \"list\"\ndefault = \"\"\n",
+    )
+    .unwrap();
+    fs::write(
+        rust.join("handler-template.rs"),
+        "{# generated -#}\n{% include \"partials/header\" %}\n\
+         pub fn {{ name }}() {\n{% for r in routes %}    route(\"{{ r }}\");\n{% endfor %}}\n",
+    )
+    .unwrap();
+    fs::write(rust.join("partials/header"), "// {{ name | title }}\n").unwrap();
+    let out = templates.path().join("out.rs");
+    let home = tempfile::tempdir().unwrap();
+    let output = binary(home.path())
+        .arg("--templates-dir")
+        .arg(templates.path())
+        .args(["new", "rust/handler", "--out", out.to_str().unwrap()])
+        .args(["--set", "name=list_users", "--set", "routes=/users,/admins"])
+        .args(["--no-lock"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        fs::read_to_string(&out).unwrap(),
+        "// List Users\npub fn list_users() {\n    route(\"/users\");\n    route(\"/admins\");\n}\n"
+    );
+}
+
+#[test]
+fn test_new_rustfmt_and_cargo_check_hooks() {
+    let dir = tempfile::tempdir().unwrap();
+    let templates = dir.path().join("templates/rust");
+    fs::create_dir_all(&templates).unwrap();
+    fs::write(
+        templates.join("ok-template.rs"),
+        "pub fn  {{ name }}( )->u32 { 1 }\n",
+    )
+    .unwrap();
+    fs::write(
+        templates.join("broken-template.rs"),
+        "pub fn {{ name }}( {\n",
+    )
+    .unwrap();
+    fs::write(
+        templates.join("untyped-template.rs"),
+        "pub fn {{ name }}() -> u32 { \"x\" }\n",
+    )
+    .unwrap();
+    let project = dir.path().join("demo");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::write(
+        project.join("Cargo.toml"),
+        "[package]\nname = \"demo\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+    )
+    .unwrap();
+    fs::write(project.join("src/lib.rs"), "pub mod gen;\n").unwrap();
+    let out = project.join("src/gen.rs");
+    let home = tempfile::tempdir().unwrap();
+    let run = |template: &str, extra: &[&str]| {
+        binary(home.path())
+            .arg("--templates-dir")
+            .arg(dir.path().join("templates"))
+            .args(["new", template, "--out", out.to_str().unwrap()])
+            .args(["--set", "name=answer", "--rustfmt", "--no-lock", "--force"])
+            .args(extra)
+            .output()
+            .unwrap()
+    };
+
+    let output = run("rust/ok", &["--check"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        fs::read_to_string(&out).unwrap(),
+        "pub fn answer() -> u32 {\n    1\n}\n"
+    );
+
+    let output = run("rust/broken", &["--format", "json"]);
+    assert!(!output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["hook"], "rustfmt");
+    assert_eq!(json["diagnostics"][0]["line"], 1);
+    assert!(fs::read_to_string(&out).unwrap().contains("-> u32"));
+
+    let output = run("rust/untyped", &["--check", "--format", "json"]);
+    assert!(!output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["hook"], "cargo check");
+    assert_eq!(json["diagnostics"][0]["file"], "src/gen.rs");
+}
+
+#[test]
+fn test_template_hooks_require_opt_in() {
+    let dir = tempfile::tempdir().unwrap();
+    let templates = dir.path().join("templates/rust");
+    fs::create_dir_all(&templates).unwrap();
+    let manifest = "name = \"hooked\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+                    description = \"Formats itself\"\nfile = \"hooked-template.rs\"\n\n\
+                    [[hooks]]\nrun = [\"rustfmt\", \"--edition\", \"2021\", \"{{ destination }}\"]\n";
+    fs::write(templates.join("hooked.template.toml"), manifest).unwrap();
+    fs::write(
+        templates.join("hooked-template.rs"),
+        "pub fn  run( ) {  }\n",
+    )
+    .unwrap();
+    let project = dir.path().join("project");
+    fs::create_dir_all(project.join(".git")).unwrap();
+    let out = project.join("src/run.rs");
+    let home = tempfile::tempdir().unwrap();
+    let run = |extra: &[&str]| {
+        binary(home.path())
+            .arg("--templates-dir")
+            .arg(dir.path().join("templates"))
+            .args([
+                "new",
+                "rust/hooked",
+                "--out",
+                out.to_str().unwrap(),
+                "--force",
+            ])
+            .args(extra)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&[]);
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("pass --allow-hooks to run them"),
+        "{stderr}"
+    );
+    assert_eq!(fs::read_to_string(&out).unwrap(), "pub fn  run( ) {  }\n");
+
+    let output = run(&["--allow-hooks"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(fs::read_to_string(&out).unwrap(), "pub fn run() {}\n");
+
+    fs::write(
+        templates.join("hooked.template.toml"),
+        manifest.replace("\"rustfmt\", \"--edition\", \"2021\",", "\"sh\", \"-c\","),
+    )
+    .unwrap();
+    let output = run(&["--allow-hooks", "--no-lock"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("`sh` is not allowed"), "{stderr}");
+}
+
+#[test]
+fn test_new_injects_snippets_into_existing_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let templates = dir.path().join("templates/rust");
+    fs::create_dir_all(&templates).unwrap();
+    fs::write(
+        templates.join("helper-template.rs"),
+        "pub fn {{ name }}() {}\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n\n    \
+         #[test]\n    fn test_{{ name }}() {\n        {{ name }}();\n    }\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        templates.join("variant-template.rs"),
+        "{{ name }}(String)\n",
+    )
+    .unwrap();
+    let out = dir.path().join("lib.rs");
+    fs::write(
+        &out,
+        "pub enum Error {\n    Io,\n}\n\npub fn a() {}\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n}\n",
+    )
+    .unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let run = |template: &str, args: &[&str]| {
+        binary(home.path())
+            .arg("--templates-dir")
+            .arg(dir.path().join("templates"))
+            .args(["new", template, "--out", out.to_str().unwrap()])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run("rust/helper", &["--set", "name=b", "--inject"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("injected into"));
+    let output = run(
+        "rust/variant",
+        &["--set", "name=Parse", "--inject", "enum:Error"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        fs::read_to_string(&out).unwrap(),
+        "pub enum Error {\n    Io,\n    Parse(String)\n}\n\npub fn a() {}\n\npub fn b() {}\n\n\
+         #[cfg(test)]\nmod tests {\n    use super::*;\n\n    #[test]\n    fn test_b() {\n        b();\n    }\n}\n"
+    );
+    assert!(!dir.path().join("cc-agents.lock").exists());
+
+    let output = run("rust/helper", &["--set", "name=b", "--inject"]);
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("is up to date"));
+    let output = run(
+        "rust/helper",
+        &["--set", "name=c", "--inject", "impl:Missing"],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("no `impl Missing` found"));
+}
+
+#[test]
+fn test_regeneration_keeps_code_outside_managed_regions() {
+    let templates = tempfile::tempdir().unwrap();
+    let template = templates.path().join("rust/routes-template.rs");
+    fs::create_dir_all(template.parent().unwrap()).unwrap();
+    fs::write(
+        &template,
+        "// cc-agents:begin routes\npub const ROUTES: &[&str] = &[\"{{ route }}\"];\n\
+         // cc-agents:end routes\n",
+    )
+    .unwrap();
+    let project = tempfile::tempdir().unwrap();
+    let out = project.path().join("routes.rs");
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .current_dir(project.path())
+            .arg("--templates-dir")
+            .arg(templates.path())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&[
+        "new",
+        "rust/routes",
+        "--out",
+        "routes.rs",
+        "--set",
+        "route=a",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let hand_written = "\npub fn handler() {}\n";
+    let mut contents = fs::read_to_string(&out).unwrap();
+    contents.push_str(hand_written);
+    fs::write(&out, &contents).unwrap();
+
+    let output = run(&[
+        "new",
+        "rust/routes",
+        "--out",
+        "routes.rs",
+        "--set",
+        "route=b",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("regenerated the managed regions of"));
+    assert!(fs::read_to_string(&out).unwrap().ends_with(&format!(
+        "&[\"b\"];\n// cc-agents:end routes\n{hand_written}"
+    )));
+
+    // Hand-written code is not an edit that blocks updates.
+    fs::write(
+        &template,
+        "// cc-agents:begin routes\npub const ROUTES: &[&str] = &[\"{{ route }}\", \"/\"];\n\
+         // cc-agents:end routes\n// cc-agents:begin extra\n// cc-agents:end extra\n",
+    )
+    .unwrap();
+    let output = run(&["update"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("has no managed region `extra`"));
+    assert_eq!(
+        fs::read_to_string(&out).unwrap(),
+        format!(
+            "// cc-agents:begin routes\npub const ROUTES: &[&str] = &[\"b\", \"/\"];\n\
+             // cc-agents:end routes\n{hand_written}"
+        )
+    );
+
+    fs::write(&out, "// cc-agents:begin routes\n").unwrap();
+    let output = run(&[
+        "new",
+        "rust/routes",
+        "--out",
+        "routes.rs",
+        "--set",
+        "route=c",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("region `routes` is never closed"));
+}
+
+#[test]
+fn test_new_generates_bundle_of_files() {
+    let templates = tempfile::tempdir().unwrap();
+    let rust = templates.path().join("rust");
+    fs::create_dir_all(rust.join("slice")).unwrap();
+    fs::write(
+        rust.join("slice.template.toml"),
+        "name = \"slice\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+         description = \"Feature slice\"\n\n[[variables]]\nname = \"name\"\ntype = \"identifier\"\n\n\
+         [[files]]\ntemplate = \"slice/mod-template.rs\"\npath = \"src/{{ name }}/mod.rs\"\n\n\
+         [[files]]\ntemplate = \"slice/test-template.rs\"\npath = \"tests/{{ name }}.rs\"\n",
+    )
+    .unwrap();
+    fs::write(
+        rust.join("slice/mod-template.rs"),
+        "pub fn {{ name }}() {}\n",
+    )
+    .unwrap();
+    fs::write(
+        rust.join("slice/test-template.rs"),
+        "#[test]\nfn test_{{ name }}() {}\n",
+    )
+    .unwrap();
+    let project = tempfile::tempdir().unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .current_dir(project.path())
+            .arg("--templates-dir")
+            .arg(templates.path())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["new", "rust/slice", "--out", ".", "--set", "name=parse"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("created ./src/parse/mod.rs"), "{stdout}");
+    assert_eq!(
+        fs::read_to_string(project.path().join("tests/parse.rs")).unwrap(),
+        "#[test]\nfn test_parse() {}\n"
+    );
+    let lock = fs::read_to_string(project.path().join("cc-agents.lock")).unwrap();
+    assert_eq!(
+        lock.matches("template = \"rust/slice\"").count(),
+        2,
+        "{lock}"
+    );
+    assert!(
+        lock.contains("source = \"rust/slice/mod-template.rs\"\n"),
+        "{lock}"
+    );
+    assert!(
+        lock.contains("source = \"rust/slice/test-template.rs\"\n"),
+        "{lock}"
+    );
+
+    // A clash with one file writes none of them.
+    fs::write(project.path().join("tests/parse.rs"), "// mine\n").unwrap();
+    fs::remove_file(project.path().join("src/parse/mod.rs")).unwrap();
+    let output = run(&["new", "rust/slice", "--out", ".", "--set", "name=parse"]);
+    assert!(!output.status.success());
+    assert!(!project.path().join("src/parse/mod.rs").exists());
+
+    let output = run(&[
+        "new",
+        "rust/slice",
+        "--out",
+        ".",
+        "--set",
+        "name=parse",
+        "--force",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    fs::write(
+        rust.join("slice/mod-template.rs"),
+        "pub fn {{ name }}() -> u8 {\n    0\n}\n",
+    )
+    .unwrap();
+    let output = run(&["update"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        fs::read_to_string(project.path().join("src/parse/mod.rs")).unwrap(),
+        "pub fn parse() -> u8 {\n    0\n}\n"
+    );
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("updated tests/parse.rs"));
+    let lock = fs::read_to_string(project.path().join("cc-agents.lock")).unwrap();
+    assert!(
+        lock.contains("source = \"rust/slice/test-template.rs\"\n"),
+        "{lock}"
+    );
+}
+
+#[test]
+fn test_crate_generates_workspace_member() {
+    let workspace = tempfile::tempdir().unwrap();
+    let manifest = workspace.path().join("Cargo.toml");
+    fs::write(&manifest, "[workspace]\nmembers = [\"core\"]\n").unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .current_dir(workspace.path())
+            .arg("--templates-dir")
+            .arg(templates_dir())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["crate", "my-tool", "--kind", "bin", "--dry-run"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("would add my-tool to the workspace members"));
+    assert!(!workspace.path().join("my-tool").exists());
+
+    let output = run(&["crate", "my-tool", "--kind", "bin"]);
+    assert!(output.status.success(), "{output:?}");
+    let dir = workspace.path().join("my-tool");
+    for file in [
+        "Cargo.toml",
+        "src/lib.rs",
+        "src/main.rs",
+        "tests/cli.rs",
+        "benches/my_tool.rs",
+        "examples/basic.rs",
+        "Makefile",
+    ] {
+        assert!(dir.join(file).is_file(), "{file} was not generated");
+    }
+    assert!(fs::read_to_string(dir.join("src/main.rs"))
+        .unwrap()
+        .contains("my_tool::greet(&name)"));
+    assert_eq!(
+        fs::read_to_string(&manifest).unwrap(),
+        "[workspace]\nmembers = [\"core\", \"my-tool\"]\n"
+    );
+
+    let output = run(&[
+        "crate",
+        "lib-only",
+        "--path",
+        "libs/lib-only",
+        "--no-workspace",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(workspace
+        .path()
+        .join("libs/lib-only/tests/lib_only.rs")
+        .is_file());
+    assert!(!fs::read_to_string(&manifest).unwrap().contains("lib-only"));
+
+    let output = run(&["crate", "2fast"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("invalid package name `2fast`"));
+}
+
+#[test]
+fn test_crate_generates_xtask_member() {
+    let workspace = tempfile::tempdir().unwrap();
+    let manifest = workspace.path().join("Cargo.toml");
+    fs::write(&manifest, "[workspace]\nmembers = [\"core\"]\n").unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let output = binary(home.path())
+        .current_dir(workspace.path())
+        .arg("--templates-dir")
+        .arg(templates_dir())
+        .args(["crate", "xtask", "--kind", "xtask"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let dir = workspace.path().join("xtask");
+    let main = fs::read_to_string(dir.join("src/main.rs")).unwrap();
+    assert!(main.contains("\"codegen\" => {"));
+    let codegen = fs::read_to_string(dir.join("src/codegen.rs")).unwrap();
+    assert!(codegen.contains("const OUTPUT: &str = \"src/generated.rs\";"));
+    assert!(fs::read_to_string(dir.join("Cargo.toml"))
+        .unwrap()
+        .contains("name = \"xtask\"\n"));
+    assert_eq!(
+        fs::read_to_string(&manifest).unwrap(),
+        "[workspace]\nmembers = [\"core\", \"xtask\"]\n"
+    );
+}
+
+/// An OpenAPI document using what `from-openapi` supports, and a header
+/// parameter it skips.
+const PETSTORE_SPEC: &str = "\
+openapi: 3.0.3
+info: {title: Petstore, version: 1.0.0}
+paths:
+  /pets:
+    get:
+      tags: [pets]
+      operationId: listPets
+      parameters:
+        - {name: limit, in: query, schema: {type: integer, format: int32}}
+        - {name: X-Request-Id, in: header, schema: {type: string}}
+      responses:
+        '200':
+          description: The pets.
+          content:
+            application/json:
+              schema: {type: array, items: {$ref: '#/components/schemas/Pet'}}
+  /pets/{petId}:
+    get:
+      tags: [pets]
+      operationId: showPetById
+      parameters:
+        - {name: petId, in: path, required: true, schema: {type: integer}}
+      responses:
+        '200':
+          description: The pet.
+          content:
+            application/json:
+              schema: {$ref: '#/components/schemas/Pet'}
+  /pets/named/{petName}:
+    get:
+      tags: [pets]
+      operationId: findPetByName
+      parameters:
+        - {name: petName, in: path, required: true, schema: {type: string}}
+      responses:
+        '200':
+          description: The pet.
+          content:
+            application/json:
+              schema: {$ref: '#/components/schemas/Pet'}
+components:
+  schemas:
+    Pet:
+      type: object
+      required: [id, name]
+      properties:
+        id: {type: integer}
+        name: {type: string}
+        ownerId: {type: string}
+        status: {type: string, enum: [available, sold]}
+";
+
+#[test]
+fn test_from_openapi_generates_client() {
+    let project = tempfile::tempdir().unwrap();
+    fs::write(project.path().join("petstore.yaml"), PETSTORE_SPEC).unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .current_dir(project.path())
+            .arg("--templates-dir")
+            .arg(templates_dir())
+            .args(["from-openapi", "petstore.yaml"])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let output = run(&[]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "warning: `GET /pets` takes the header parameter `X-Request-Id`, which is skipped"
+    ));
+
+    let module = project.path().join("src/petstore");
+    let mut files: Vec<String> = fs::read_dir(&module)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        ["client.rs", "error.rs", "mod.rs", "models.rs", "pets.rs"]
+    );
+    let read = |file: &str| fs::read_to_string(module.join(file)).unwrap();
+    let client = read("client.rs");
+    assert!(client.contains("pub struct PetstoreClient {"));
+    assert!(client.contains("use super::pets::PetsApi;"));
+    assert!(client.contains("    pub fn pets(&self) -> PetsApi<'_> {\n        PetsApi::new(self)"));
+    assert!(!client.contains("Users"));
+    let pets = read("pets.rs");
+    assert!(pets.contains("use super::models::Pet;"));
+    assert!(
+        pets.contains("pub async fn show_pet_by_id(&self, pet_id: i64) -> Result<Pet, ApiError> {")
+    );
+    assert!(pets.contains("request = request.query(&[(\"limit\", limit)]);"));
+    assert!(pets.contains(
+        "Method::GET, &format!(\"/pets/named/{pet_name}\", pet_name = client::segment(pet_name)))"
+    ));
+    let models = read("models.rs");
+    assert!(models.contains(
+        "    #[serde(rename = \"ownerId\", default, skip_serializing_if = \"Option::is_none\")]\n    \
+         pub owner_id: Option<String>,"
+    ));
+    assert!(models.contains("pub enum PetStatus {"));
+    assert!(read("mod.rs").contains("pub use client::PetstoreClient;\n"));
+    assert!(!read("error.rs").contains("{{"));
+
+    let output = run(&[]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pets.rs is up to date"));
+    fs::write(module.join("pets.rs"), "// edited\n").unwrap();
+    let output = run(&[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pets.rs already exists"));
+    let output = run(&["--dry-run", "--client-name", "Pets"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("+pub struct Pets {"));
+    assert_eq!(
+        fs::read_to_string(module.join("pets.rs")).unwrap(),
+        "// edited\n"
+    );
+    assert!(run(&["--force"]).status.success());
+    assert_eq!(read("pets.rs"), pets);
+}
+
+#[test]
+fn test_from_openapi_reports_invalid_document() {
+    let dir = tempfile::tempdir().unwrap();
+    let spec = dir.path().join("api.json");
+    fs::write(
+        &spec,
+        r#"{"swagger": "2.0", "info": {"title": "x", "version": "1"}}"#,
+    )
+    .unwrap();
+    let output = cc_scaffold(&["--format", "json", "from-openapi", spec.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["code"], "invalid_openapi");
+    assert!(report["error"]
+        .as_str()
+        .unwrap()
+        .ends_with("Swagger 2.0 documents are not supported; convert it to OpenAPI 3 first"));
+    assert!(!dir.path().join("src").exists());
+
+    let output = cc_scaffold(&[
+        "from-openapi",
+        spec.to_str().unwrap(),
+        "--client-name",
+        "a-b",
+    ]);
+    assert!(!output.status.success());
+}
+
+#[test]
+#[cfg_attr(
+    not(feature = "registry-tests"),
+    ignore = "fetches and builds crates from the registry; enable the registry-tests feature"
+)]
+fn test_from_openapi_client_builds() {
+    let project = tempfile::tempdir().unwrap();
+    fs::write(
+        project.path().join("Cargo.toml"),
+        "[package]\nname = \"pets\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+         [dependencies]\n\
+         reqwest = { version = \"0.12\", default-features = false, features = [\"json\"] }\n\
+         serde = { version = \"1\", features = [\"derive\"] }\nserde_json = \"1\"\n",
+    )
+    .unwrap();
+    fs::create_dir(project.path().join("src")).unwrap();
+    fs::write(project.path().join("src/lib.rs"), "pub mod petstore;\n").unwrap();
+    let spec = project.path().join("petstore.yaml");
+    fs::write(&spec, PETSTORE_SPEC).unwrap();
+    let out = project.path().join("src/petstore");
+    let output = cc_scaffold(&[
+        "from-openapi",
+        spec.to_str().unwrap(),
+        "--out",
+        out.to_str().unwrap(),
+        "--rustfmt",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let mut module = fs::read_to_string(out.join("mod.rs")).unwrap();
+    module.push_str(
+        "\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn test_segment_escapes_separators() {\n        \
+         assert_eq!(super::client::segment(\"a b/../c?d#e\"), \"a%20b%2F..%2Fc%3Fd%23e\");\n    \
+         }\n}\n",
+    );
+    fs::write(out.join("mod.rs"), module).unwrap();
+    let test = Command::new("cargo")
+        .args(["test", "--quiet"])
+        .current_dir(project.path())
+        .output()
+        .unwrap();
+    assert!(
+        test.status.success(),
+        "{}",
+        String::from_utf8_lossy(&test.stderr)
+    );
+}
+
+const VECTORS_SOURCE: &str = "\
+pub fn clamp(text: &str, width: usize) -> Result<String, String> {
+    todo!()
+}
+
+pub async fn fetch(id: Option<u32>) {}
+";
+
+#[test]
+fn test_test_vectors_injects_parametrized_test() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("lib.rs");
+    fs::write(&file, VECTORS_SOURCE).unwrap();
+    let path = file.to_str().unwrap();
+
+    let output = cc_scaffold(&["test-vectors", path, "clamp", "--dry-run"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("+    fn test_clamp_boundaries() {"));
+    assert_eq!(fs::read_to_string(&file).unwrap(), VECTORS_SOURCE);
+
+    let output = cc_scaffold(&["test-vectors", path, "clamp"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("injected into {path}\n")
+    );
+    let contents = fs::read_to_string(&file).unwrap();
+    assert!(contents.starts_with(VECTORS_SOURCE));
+    assert!(contents.contains("#[cfg(test)]\nmod tests {\n    use super::*;\n"));
+    assert!(contents.contains("            (\"hello\", 1, None),\n"));
+    assert!(contents.contains("            (\"\", 1, None),\n"));
+    assert!(contents.contains("            (\"hello\", usize::MAX, None),\n"));
+    assert!(contents.contains("let result = clamp(text, width);"));
+
+    let output = cc_scaffold(&["test-vectors", path, "fetch"]);
+    assert!(output.status.success());
+    let contents = fs::read_to_string(&file).unwrap();
+    assert_eq!(contents.matches("mod tests {").count(), 1);
+    assert!(contents.contains("    #[tokio::test]\n    async fn test_fetch_boundaries() {"));
+    assert!(contents.contains("            fetch(id).await;\n"));
+
+    // Expectations filled in since are kept.
+    let edited = contents.replace("(\"\", 1, None)", "(\"\", 1, Some(false))");
+    fs::write(&file, &edited).unwrap();
+    let output = cc_scaffold(&["test-vectors", path, "clamp"]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("already has `test_clamp_boundaries`"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("is up to date"), "{stderr}");
+    assert_eq!(fs::read_to_string(&file).unwrap(), edited);
+}
+
+#[test]
+fn test_test_vectors_reports_uncallable_function() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("lib.rs");
+    fs::write(&file, "pub fn wrap<T>(value: T) -> T {\n    value\n}\n").unwrap();
+    let path = file.to_str().unwrap();
+    for (function, message) in [
+        ("wrap", "`value` is of generic type `T`"),
+        ("unwrap", "no function `unwrap` found"),
+    ] {
+        let output = cc_scaffold(&["--format", "json", "test-vectors", path, function]);
+        assert!(!output.status.success());
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(report["code"], "inject");
+        assert!(
+            report["error"].as_str().unwrap().contains(message),
+            "{report}"
+        );
+    }
+}
+
+#[test]
+fn test_fuzz_creates_fuzz_crate_then_adds_targets() {
+    let project = tempfile::tempdir().unwrap();
+    fs::write(
+        project.path().join("Cargo.toml"),
+        "[package]\nname = \"tiny-parse\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::create_dir(project.path().join("src")).unwrap();
+    fs::write(project.path().join("src/lib.rs"), "pub mod parser;\n").unwrap();
+    fs::write(
+        project.path().join("src/parser.rs"),
+        "pub fn tokenize(input: &[u8]) -> Vec<u8> {\n    input.to_vec()\n}\n\n\
+         pub fn apply(name: &str, stack: &mut Vec<u8>) {}\n\n\
+         fn private(value: u8) {}\n",
+    )
+    .unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .current_dir(project.path())
+            .arg("--templates-dir")
+            .arg(templates_dir())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["fuzz", "tokenize"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let fuzz = project.path().join("fuzz");
+    let target = fs::read_to_string(fuzz.join("fuzz_targets/tokenize.rs")).unwrap();
+    assert!(target.contains("fuzz_target!(|data: &[u8]| {"));
+    assert!(target.contains("let _ = tiny_parse::parser::tokenize(data);"));
+    assert!(fuzz.join("corpus/tokenize/empty").is_file());
+    assert!(fuzz.join(".gitignore").is_file());
+
+    let output = run(&["fuzz", "apply"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("injected into fuzz/Cargo.toml"));
+    let target = fs::read_to_string(fuzz.join("fuzz_targets/apply.rs")).unwrap();
+    assert!(target.contains("    name: String,\n    stack: Vec<u8>,\n"));
+    assert!(target.contains("let Input { name, mut stack } = input;"));
+    assert!(target.contains("    tiny_parse::parser::apply(&name, &mut stack);"));
+    let manifest = fs::read_to_string(fuzz.join("Cargo.toml")).unwrap();
+    assert!(manifest.contains("[dependencies.tiny-parse]\npath = \"..\""));
+    assert_eq!(manifest.matches("[[bin]]").count(), 2);
+
+    let output = run(&["--format", "json", "fuzz", "private"]);
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["code"], "fuzz");
+    assert!(
+        report["error"].as_str().unwrap().contains("is not `pub`"),
+        "{report}"
+    );
+}
+
+#[test]
+fn test_mutants_init_excludes_generated_files() {
+    let project = tempfile::tempdir().unwrap();
+    fs::write(
+        project.path().join("Cargo.toml"),
+        "[package]\nname = \"tiny-parse\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::create_dir(project.path().join("src")).unwrap();
+    fs::write(project.path().join("src/lib.rs"), "pub mod schema;\n").unwrap();
+    fs::write(project.path().join("src/schema.rs"), "// @generated\n").unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .current_dir(project.path())
+            .arg("--templates-dir")
+            .arg(templates_dir())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["mutants", "init"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let config = fs::read_to_string(project.path().join(".cargo/mutants.toml")).unwrap();
+    assert!(config.contains("tiny-parse"), "{config}");
+    assert!(config.contains("\"src/schema.rs\","), "{config}");
+    assert!(config.contains("\"impl Debug\","), "{config}");
+    let parsed: toml::Table = config.parse().unwrap();
+    assert_eq!(parsed["exclude_globs"].as_array().unwrap().len(), 1);
+
+    let output = run(&["mutants", "init"]);
+    assert!(output.status.success(), "an unchanged config is left alone");
+    fs::write(project.path().join(".cargo/mutants.toml"), "# edited\n").unwrap();
+    let output = run(&["mutants", "init"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--force"));
+}
+
+#[test]
+fn test_mutants_report_fails_gate_on_missed_mutants() {
+    let project = tempfile::tempdir().unwrap();
+    let results = project.path().join("mutants.out");
+    fs::create_dir(&results).unwrap();
+    fs::write(
+        results.join("caught.txt"),
+        "src/lib.rs:3:5: replace is_even -> bool with false\n",
+    )
+    .unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .current_dir(project.path())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["--format", "json", "mutants", "report"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["stage"], "6-expand-tests");
+    assert_eq!(report["passed"], true);
+    assert_eq!(report["counts"]["caught"], 1);
+
+    fs::write(
+        results.join("missed.txt"),
+        "src/lib.rs:3:5: replace is_even -> bool with true\n",
+    )
+    .unwrap();
+    let output = run(&["mutants", "report"]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "src/lib.rs:3:5: replace is_even -> bool with true: no test failed\n"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("6-expand-tests gate `mutants` failed: 1 caught, 1 missed"),
+        "{stderr}"
+    );
+
+    let output = run(&["--format", "json", "mutants", "report"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["code"], "gate_failed");
+    assert_eq!(report["report"]["problems"][0]["line"], 3);
+}
+
+#[test]
+fn test_doc_examples_gate_reports_items_without_examples() {
+    let project = tempfile::tempdir().unwrap();
+    fs::create_dir(project.path().join("src")).unwrap();
+    fs::write(
+        project.path().join("src/lib.rs"),
+        "/// Doubles it.\n///\n/// ```\n/// assert_eq!(tiny::double(2), 4);\n/// ```\n\
+         pub fn double(x: u32) -> u32 {\n    x * 2\n}\n\n\
+         /// Fetches it.\n///\n/// ```no_run\n/// tiny::fetch();\n/// ```\npub fn fetch() {}\n\n\
+         /// Halves it.\npub fn half(x: u32) -> u32 {\n    x / 2\n}\n",
+    )
+    .unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .current_dir(project.path())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["doc-examples"]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "src/lib.rs:12:5: `no_run` example does not say why it is not run: \
+         start it with a `//` comment giving the reason\n\
+         src/lib.rs:18:1: the doc comment of public function `half` has no example\n"
+    );
+
+    let output = run(&["--format", "json", "doc-examples", "src"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["code"], "gate_failed");
+    assert_eq!(report["report"]["gate"], "doc-examples");
+    assert_eq!(report["report"]["counts"]["documented"], 2);
+    assert_eq!(report["report"]["problems"][1]["line"], 18);
+
+    fs::write(
+        project.path().join("src/lib.rs"),
+        "/// Fetches it.\n///\n/// ```no_run\n/// // Needs the network.\n/// tiny::fetch();\n/// ```\n\
+         pub fn fetch() {}\n",
+    )
+    .unwrap();
+    let output = run(&["doc-examples"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("5-implementation gate `doc-examples` passed: 1 documented"));
+}
+
+#[test]
+fn test_doc_examples_shipped_templates_document_and_justify() {
+    let output = cc_scaffold(&["--format", "json", "doc-examples", "--templates"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let counts = &report["report"]["counts"];
+    assert_eq!(counts["undocumented"], 0, "{report:#}");
+    assert_eq!(counts["unjustified"], 0, "{report:#}");
+    assert_eq!(counts["unparsed"], 0, "{report:#}");
+}
+
+#[test]
+fn test_project_config_supplies_defaults() {
+    let project = tempfile::tempdir().unwrap();
+    fs::write(
+        project.path().join("cc-agents.toml"),
+        "[variables]\nauthor = \"Jane Doe\"\ntracing = true\n\n\
+         [output]\nrust = \"src\"\n\"rust/crate-lib\" = \"crates\"\n",
+    )
+    .unwrap();
+    fs::create_dir(project.path().join("src")).unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .current_dir(project.path().join("src"))
+            .arg("--templates-dir")
+            .arg(templates_dir())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["new", "rust/function", "--set", "function_name=parse"]);
+    assert!(output.status.success(), "{output:?}");
+    let contents = fs::read_to_string(project.path().join("src/parse.rs")).unwrap();
+    assert!(contents.contains("#[tracing::instrument"));
+
+    // --set wins over the config.
+    let output = run(&[
+        "new",
+        "rust/function",
+        "--set",
+        "function_name=lex",
+        "--set",
+        "tracing=false",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let contents = fs::read_to_string(project.path().join("src/lex.rs")).unwrap();
+    assert!(!contents.contains("#[tracing::instrument"));
+
+    let output = run(&["crate", "util"]);
+    assert!(output.status.success(), "{output:?}");
+    let cargo = fs::read_to_string(project.path().join("crates/util/Cargo.toml")).unwrap();
+    assert!(cargo.contains("authors = [\"Jane Doe\"]\n"), "{cargo}");
+
+    let output = run(&[
+        "new",
+        "rust/function",
+        "--set",
+        "function_name=parse",
+        "--no-config",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("no destination for `rust/function`"));
+}
+
+#[test]
+fn test_environment_interpolation() {
+    let templates = tempfile::tempdir().unwrap();
+    let rust = templates.path().join("rust");
+    fs::create_dir_all(&rust).unwrap();
+    fs::write(
+        rust.join("header.template.toml"),
+        "name = \"header\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+         description = \"Doc header\"\nfile = \"header-template.rs\"\n\n\
+         [[variables]]\nname = \"author\"\ndefault = \"${CC_TEST_AUTHOR:-nobody}\"\n",
+    )
+    .unwrap();
+    fs::write(
+        rust.join("header-template.rs"),
+        "//! Author: {{ author }}\n//! Host: {{ env.CC_TEST_HOST }}\n",
+    )
+    .unwrap();
+    let out = templates.path().join("header.rs");
+    let home = tempfile::tempdir().unwrap();
+    let run = |author: Option<&str>| {
+        let mut command = binary(home.path());
+        command
+            .arg("--templates-dir")
+            .arg(templates.path())
+            .args(["new", "rust/header", "--no-lock", "--force", "--out"])
+            .arg(&out)
+            .env("CC_TEST_HOST", "ci")
+            .env_remove("CC_TEST_AUTHOR");
+        if let Some(author) = author {
+            command.env("CC_TEST_AUTHOR", author);
+        }
+        command.output().unwrap()
+    };
+
+    let output = run(Some("Jane"));
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        fs::read_to_string(&out).unwrap(),
+        "//! Author: Jane\n//! Host: ci\n"
+    );
+    let output = run(None);
+    assert!(output.status.success(), "{output:?}");
+    assert!(fs::read_to_string(&out)
+        .unwrap()
+        .starts_with("//! Author: nobody\n"));
+}
+
+#[test]
+fn test_rollback_undoes_last_operation() {
+    let home = tempfile::tempdir().unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    let manifest = workspace.path().join("Cargo.toml");
+    fs::write(&manifest, "[workspace]\nmembers = []\n").unwrap();
+    let run = |args: &[&str]| {
+        binary(home.path())
+            .current_dir(workspace.path())
+            .arg("--templates-dir")
+            .arg(templates_dir())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["crate", "my-lib"]);
+    assert!(output.status.success(), "{output:?}");
+    let parser = workspace.path().join("my-lib/src/parser.rs");
+    let output = run(&[
+        "new",
+        "rust/function",
+        "--out",
+        parser.to_str().unwrap(),
+        "--set",
+        "function_name=parse",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+
+    let output = run(&["rollback", "--list"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "{stdout}");
+    assert!(lines[0].contains("  2 files  "), "{stdout}");
+    assert!(lines[0].contains("new rust/function"), "{stdout}");
+    assert!(lines[1].contains("crate my-lib"), "{stdout}");
+    let crate_op = lines[1].split_whitespace().next().unwrap().to_string();
+
+    // The last operation: the function and its lockfile entry.
+    let output = run(&["rollback"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(!parser.exists());
+    assert!(!fs::read_to_string(workspace.path().join("cc-agents.lock"))
+        .unwrap()
+        .contains("parser.rs"));
+
+    // A named operation refuses to discard edits without --force.
+    fs::write(workspace.path().join("my-lib/src/lib.rs"), "// edited\n").unwrap();
+    let output = run(&["rollback", &crate_op]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("was changed after it was generated"));
+    let output = run(&["rollback", &crate_op, "--force"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(!workspace.path().join("my-lib").exists());
+    assert!(!workspace.path().join("cc-agents.lock").exists());
+    assert_eq!(
+        fs::read_to_string(&manifest).unwrap(),
+        "[workspace]\nmembers = []\n"
+    );
+    assert!(!run(&["rollback"]).status.success());
+}
+
+#[test]
+fn test_validate_reports_template_problems() {
+    let output = cc_scaffold(&["validate"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("no problems"));
+
+    let templates = tempfile::tempdir().unwrap();
+    fs::create_dir_all(templates.path().join("rust")).unwrap();
+    fs::write(
+        templates.path().join("rust/broken.template.toml"),
+        "name = \"broken\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+         description = \"Broken\"\nfile = \"broken-template.rs\"\n\n\
+         [[variables]]\nname = \"name\"\ntype = \"identifier\"\n\n\
+         [[variables]]\nname = \"unused\"\n",
+    )
+    .unwrap();
+    fs::write(
+        templates.path().join("rust/broken-template.rs"),
+        "pub fn {{ name }}() {}\n",
+    )
+    .unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let validate = |format: &str| {
+        binary(home.path())
+            .arg("--templates-dir")
+            .arg(templates.path())
+            .args(["--format", format, "validate"])
+            .output()
+            .unwrap()
+    };
+
+    let output = validate("text");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("broken-template.rs:1:1: public function has no doc comment"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("variable `unused` is declared but never used"));
+    assert!(stdout.contains("no test module"));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("3 problems found in templates"));
+
+    let output = validate("json");
+    assert!(!output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["problems"].as_array().unwrap().len(), 3, "{json}");
+}
+
+/// Runs `cc-scaffold selftest` on the shipped Rust templates that need
+/// crates from the registry (`with_dependencies`) or only the standard
+/// library. The scratch crates are kept under Cargo's target directory, so
+/// later runs reuse what they built.
+fn selftest_shipped(with_dependencies: bool) {
+    let ids: Vec<String> = cc_scaffold::Locator::new(vec![templates_dir()])
+        .manifests()
+        .unwrap()
+        .into_iter()
+        .filter(|m| m.language == "rust" && m.selftest.has_dependencies() == with_dependencies)
+        .map(|m| m.id())
+        .collect();
+    assert!(!ids.is_empty());
+    let work = Path::new(env!("CARGO_TARGET_TMPDIR")).join(if with_dependencies {
+        "selftest-dependencies"
+    } else {
+        "selftest"
+    });
+    let mut args = vec!["selftest", "--work-dir", work.to_str().unwrap()];
+    args.extend(ids.iter().map(String::as_str));
+    let output = cc_scaffold(&args);
+    assert!(
+        output.status.success(),
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_selftest_builds_shipped_templates() {
+    selftest_shipped(false);
+}
+
+#[test]
+#[cfg_attr(
+    not(feature = "registry-tests"),
+    ignore = "fetches and builds crates from the registry; enable the registry-tests feature"
+)]
+fn test_selftest_builds_shipped_templates_with_dependencies() {
+    selftest_shipped(true);
+}
+
+#[test]
+fn test_new_picks_edition_variant() {
+    let templates = tempfile::tempdir().unwrap();
+    let rust = templates.path().join("rust");
+    fs::create_dir_all(&rust).unwrap();
+    fs::write(
+        rust.join("greet.template.toml"),
+        "name = \"greet\"\nlanguage = \"rust\"\nstage = \"5-implementation\"\n\
+         description = \"Greeting\"\nfile = \"greet-template.rs\"\n\
+         editions = { \"2018\" = \"greet-2018-template.rs\" }\n\n\
+         [[variables]]\nname = \"edition\"\ntype = \"enum\"\n\
+         options = [\"2018\", \"2021\", \"2024\"]\ndefault = \"2021\"\n",
+    )
+    .unwrap();
+    fs::write(
+        rust.join("greet-template.rs"),
+        "{{#if edition == \"2024\"}}// 2024\n{{else}}// 2021\n{{/if}}",
+    )
+    .unwrap();
+    fs::write(rust.join("greet-2018-template.rs"), "// 2018\n").unwrap();
+    let project = tempfile::tempdir().unwrap();
+    fs::write(
+        project.path().join("Cargo.toml"),
+        "[package]\nname = \"demo\"\nversion = \"0.1.0\"\nedition = \"2018\"\n",
+    )
+    .unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let render = |name: &str, extra: &[&str]| {
+        let out = project.path().join("src").join(name);
+        let output = binary(home.path())
+            .arg("--templates-dir")
+            .arg(templates.path())
+            .args(["new", "rust/greet", "--no-lock", "--out"])
+            .arg(&out)
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        fs::read_to_string(out).unwrap()
+    };
+
+    assert_eq!(render("detected.rs", &[]), "// 2018\n");
+    assert_eq!(render("flag.rs", &["--edition", "2024"]), "// 2024\n");
+    assert_eq!(render("set.rs", &["--set", "edition=2021"]), "// 2021\n");
+}
+
+#[test]
+fn test_completions_include_template_names() {
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish"] {
+        let output = cc_scaffold(&["completions", shell]);
+        assert!(output.status.success(), "{output:?}");
+        let script = String::from_utf8(output.stdout).unwrap();
+        assert!(script.contains("COMPLETE"), "{shell}: {script}");
+    }
+
+    let home = tempfile::tempdir().unwrap();
+    let output = binary(home.path())
+        .env("COMPLETE", "fish")
+        .env("CC_SCAFFOLD_TEMPLATES", templates_dir())
+        .args(["--", "cc-scaffold", "new", "rust/fu"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("rust/function\tDocumented function"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("rust/feature"), "{stdout}");
+}
+
+#[test]
+fn test_watch_rerenders_on_change() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    let dir = tempfile::tempdir().unwrap();
+    let vars = dir.path().join("sample.toml");
+    fs::write(&vars, "function_name = \"parse_header\"\n").unwrap();
+    let out = dir.path().join("preview");
+    let home = tempfile::tempdir().unwrap();
+    let child = binary(home.path())
+        .arg("--templates-dir")
+        .arg(templates_dir())
+        .args(["--format", "json", "watch", "rust/function", "--vars"])
+        .arg(&vars)
+        .arg("--out")
+        .arg(&out)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to run cc-scaffold");
+    /// Stops `watch` even if the test fails.
+    struct Watch(std::process::Child);
+    impl Drop for Watch {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+    let mut child = Watch(child);
+    // Rounds are read on another thread, so that a missed change fails the
+    // test instead of hanging it.
+    let stdout = child.0.stdout.take().unwrap();
+    let (send, rounds) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let round: serde_json::Value = serde_json::from_str(&line.unwrap()).unwrap();
+            if send.send(round).is_err() {
+                break;
+            }
+        }
+    });
+    let next_round = || {
+        rounds
+            .recv_timeout(std::time::Duration::from_secs(30))
+            .expect("no round reported")
+    };
+
+    let first = next_round();
+    assert_eq!(first["status"], "passed");
+    assert_eq!(first["template"], "rust/function");
+    let header = out.join("parse_header.rs");
+    assert!(fs::read_to_string(&header)
+        .unwrap()
+        .contains("pub fn parse_header("));
+
+    fs::write(&vars, "function_name = \"tokenize\"\n").unwrap();
+    let second = next_round();
+    drop(child);
+    assert_eq!(second["status"], "passed");
+    assert!(fs::read_to_string(out.join("tokenize.rs"))
+        .unwrap()
+        .contains("pub fn tokenize("));
+    assert!(!header.exists());
+}
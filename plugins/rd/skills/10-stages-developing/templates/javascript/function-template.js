@@ -8,7 +8,7 @@
  *
  * @param {string} param1 - [Description of param1]
  * @param {number} param2 - [Description of param2]
- * @returns {{key1: string, key2: number}} Object containing processed results
+ * @returns \{{key1: string, key2: number}} Object containing processed results
  * @throws {Error} If param1 is empty or param2 is negative
  *
  * @example
@@ -0,0 +1,234 @@
+//! [Brief description of what this module does]
+//!
+//! [`configure`] serves `Resource`s under the scope it is mounted at:
+//!
+//! * `GET` lists them,
+//! * `POST` creates one from a JSON body,
+//! * `GET /{id}` fetches one.
+//!
+//! Register it on the application, sharing one [`AppState`] between the
+//! workers:
+//!
+//! ```ignore
+//! // Binds a port and serves until the process is stopped.
+//! let state = web::Data::new(AppState::default());
+//! HttpServer::new(move || {
+//!     App::new()
+//!         .app_data(state.clone())
+//!         .service(web::scope("/{{ module }}").configure({{ module }}::configure))
+//! })
+//! ```
+//!
+//! Every failure, including a body or path the extractors reject, is
+//! answered as a status and `{"error": "..."}`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use actix_web::error::{JsonPayloadError, PathError};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+/// State the handlers share, as `web::Data<AppState>`; in an application,
+/// replace the in-memory store with its database pool or service handles.
+#[derive(Debug, Default)]
+pub struct AppState {
+    store: Mutex<BTreeMap<u64, Resource>>,
+}
+
+/// A resource as the API returns it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Resource {
+    pub id: u64,
+    pub name: String,
+}
+
+/// Body of a request to create a resource.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CreateResource {
+    pub name: String,
+}
+
+/// Why a request failed; answered as a status and `{"error": "..."}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiError {
+    /// No resource has the id.
+    NotFound(u64),
+    /// The request is well-formed but its content is not acceptable.
+    Invalid(String),
+    /// The request could not be read: bad JSON or a bad path segment.
+    Malformed(String),
+}
+
+/// What a handler returns.
+pub type ApiResult<T> = Result<T, ApiError>;
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(id) => write!(f, "no resource {id}"),
+            Self::Invalid(message) | Self::Malformed(message) => f.write_str(message),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Malformed(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let error = self.to_string();
+        HttpResponse::build(self.status_code()).json(ErrorBody { error })
+    }
+}
+
+/// Routes of this module, to mount with `web::scope(..).configure`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(web::JsonConfig::default().error_handler(json_error))
+        .app_data(web::PathConfig::default().error_handler(path_error))
+        .service(web::resource("").get(list).post(create))
+        .service(web::resource("/{id}").get(fetch));
+}
+
+/// A JSON body that is not JSON is malformed; one that is JSON but not a
+/// [`CreateResource`] is invalid, as with axum's `Json`.
+fn json_error(err: JsonPayloadError, _: &HttpRequest) -> actix_web::Error {
+    match err {
+        JsonPayloadError::Deserialize(err) if err.is_data() => ApiError::Invalid(err.to_string()),
+        err => ApiError::Malformed(err.to_string()),
+    }
+    .into()
+}
+
+fn path_error(err: PathError, _: &HttpRequest) -> actix_web::Error {
+    ApiError::Malformed(err.to_string()).into()
+}
+
+async fn list(state: web::Data<AppState>) -> web::Json<Vec<Resource>> {
+    let store = state.store.lock().unwrap();
+    web::Json(store.values().cloned().collect())
+}
+
+async fn create(
+    state: web::Data<AppState>,
+    input: web::Json<CreateResource>,
+) -> ApiResult<HttpResponse> {
+    let name = input.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::Invalid("name must not be empty".to_string()));
+    }
+    let mut store = state.store.lock().unwrap();
+    let id = store.keys().next_back().map_or(1, |last| last + 1);
+    let created = Resource {
+        id,
+        name: name.to_string(),
+    };
+    store.insert(id, created.clone());
+    Ok(HttpResponse::Created().json(created))
+}
+
+async fn fetch(state: web::Data<AppState>, id: web::Path<u64>) -> ApiResult<web::Json<Resource>> {
+    let id = id.into_inner();
+    let found = state.store.lock().unwrap().get(&id).cloned();
+    found.map(web::Json).ok_or(ApiError::NotFound(id))
+}
+
+{{> partials/test-header}}
+    use actix_web::test::{self, TestRequest};
+    use actix_web::App;
+    use serde_json::{json, Value};
+
+    const BASE: &str = "/{{ module }}";
+
+    fn post(uri: &str, body: Value) -> TestRequest {
+        TestRequest::post().uri(uri).set_json(body)
+    }
+
+    /// Sends `request` to the routes mounted as the application would, and
+    /// returns the status and the JSON body, `null` if there is none.
+    async fn send(state: &web::Data<AppState>, request: TestRequest) -> (StatusCode, Value) {
+        let scope = web::scope(BASE).configure(configure);
+        let app = App::new().app_data(state.clone()).service(scope);
+        let app = test::init_service(app).await;
+        let response = test::call_service(&app, request.to_request()).await;
+        let status = response.status();
+        let bytes = test::read_body(response).await;
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        (status, body)
+    }
+
+    fn get(uri: &str) -> TestRequest {
+        TestRequest::get().uri(uri)
+    }
+
+    #[actix_web::test]
+    async fn test_create_then_fetch() {
+        let state = web::Data::new(AppState::default());
+        let (status, created) = send(&state, post(BASE, json!({ "name": "first" }))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created, json!({ "id": 1, "name": "first" }));
+
+        let (status, fetched) = send(&state, get(&format!("{BASE}/1"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fetched, created);
+    }
+
+    #[actix_web::test]
+    async fn test_list() {
+        let state = web::Data::new(AppState::default());
+        for name in ["first", "second"] {
+            send(&state, post(BASE, json!({ "name": name }))).await;
+        }
+        let (status, listed) = send(&state, get(BASE)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed.as_array().unwrap().len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_fetch_missing_is_not_found() {
+        let state = web::Data::new(AppState::default());
+        let (status, body) = send(&state, get(&format!("{BASE}/9"))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body, json!({ "error": "no resource 9" }));
+    }
+
+    #[actix_web::test]
+    async fn test_create_rejects_empty_name() {
+        let state = web::Data::new(AppState::default());
+        let (status, body) = send(&state, post(BASE, json!({ "name": " " }))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"], "name must not be empty");
+    }
+
+    #[actix_web::test]
+    async fn test_extractor_rejections_are_json_errors() {
+        let state = web::Data::new(AppState::default());
+        let (status, body) = send(&state, get(&format!("{BASE}/not-a-number"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].is_string());
+
+        let (status, body) = send(&state, post(BASE, json!({ "title": "x" }))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(body["error"].as_str().unwrap().contains("name"));
+
+        let malformed = TestRequest::post()
+            .uri(BASE)
+            .insert_header(("content-type", "application/json"))
+            .set_payload("{");
+        let (status, body) = send(&state, malformed).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].is_string());
+    }
+}
@@ -0,0 +1,340 @@
+//! [Brief description of what this module does]
+//!
+//! [`ArenaName`] stores values in a `Vec` of slots and hands out
+//! [`IndexName`]es instead of references, so values can point at each other
+//! — graph nodes, entities, handles held across frames — without lifetimes
+//! or `Rc`. Freed slots are reused, so the arena grows only to the largest
+//! number of values alive at once.
+//!
+//! Reuse is what makes plain `usize` indices dangerous: an index kept after
+//! its value was removed would silently reach whatever moved into the slot
+//! next (the ABA problem). An index therefore carries the slot's generation,
+//! bumped on every removal, and a lookup with an older generation misses.
+//! A slot whose generation would wrap around is retired instead of reused,
+//! so no stale index can ever match again.
+//!
+//! Indices are not tied to the arena that issued them; one from another
+//! arena finds whatever is at its slot, or nothing.
+
+use std::mem;
+use std::ops;
+
+/// A handle to a value in an [`ArenaName`], valid until that value is
+/// removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IndexName {
+    slot: u32,
+    generation: u32,
+}
+
+impl IndexName {
+    /// Position of the value's slot in the arena.
+    pub fn slot(self) -> usize {
+        self.slot as usize
+    }
+
+    /// How many values the slot held and lost before this one.
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Entry<T> {
+    Occupied(T),
+    /// Free, and linked to the next free slot.
+    Vacant {
+        next_free: Option<u32>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    generation: u32,
+    entry: Entry<T>,
+}
+
+impl<T> Slot<T> {
+    /// The value, if the slot holds one for `generation`.
+    fn get(&self, generation: u32) -> Option<&T> {
+        match &self.entry {
+            Entry::Occupied(value) if self.generation == generation => Some(value),
+            _ => None,
+        }
+    }
+
+    fn get_mut(&mut self, generation: u32) -> Option<&mut T> {
+        match &mut self.entry {
+            Entry::Occupied(value) if self.generation == generation => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// A slab of values addressed by generational [`IndexName`]es.
+#[derive(Debug, Clone)]
+pub struct ArenaName<T> {
+    slots: Vec<Slot<T>>,
+    /// The most recently freed slot, heading the free list.
+    free: Option<u32>,
+    len: usize,
+}
+
+impl<T> Default for ArenaName<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ArenaName<T> {
+    /// An empty arena.
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// An empty arena with room for `capacity` values before it reallocates.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: Vec::with_capacity(capacity),
+            free: None,
+            len: 0,
+        }
+    }
+
+    /// Values held.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the arena holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Stores `value`, in a freed slot if there is one, and returns its
+    /// index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena already has `u32::MAX` slots.
+    pub fn insert(&mut self, value: T) -> IndexName {
+        self.len += 1;
+        if let Some(slot) = self.free {
+            let reused = &mut self.slots[slot as usize];
+            let Entry::Vacant { next_free } = reused.entry else {
+                unreachable!("free list reached an occupied slot");
+            };
+            self.free = next_free;
+            reused.entry = Entry::Occupied(value);
+            let generation = reused.generation;
+            return IndexName { slot, generation };
+        }
+        let slot = u32::try_from(self.slots.len()).expect("arena is out of slots");
+        let entry = Entry::Occupied(value);
+        self.slots.push(Slot {
+            generation: 0,
+            entry,
+        });
+        IndexName {
+            slot,
+            generation: 0,
+        }
+    }
+
+    /// The value at `index`, unless it has been removed.
+    pub fn get(&self, index: IndexName) -> Option<&T> {
+        self.slots.get(index.slot())?.get(index.generation)
+    }
+
+    /// The value at `index` mutably, unless it has been removed.
+    pub fn get_mut(&mut self, index: IndexName) -> Option<&mut T> {
+        self.slots.get_mut(index.slot())?.get_mut(index.generation)
+    }
+
+    /// Whether `index` still reaches a value.
+    pub fn contains(&self, index: IndexName) -> bool {
+        self.get(index).is_some()
+    }
+
+    /// Removes and returns the value at `index`, freeing its slot. Returns
+    /// `None`, removing nothing, if it was already removed.
+    pub fn remove(&mut self, index: IndexName) -> Option<T> {
+        self.get(index)?;
+        let slot = &mut self.slots[index.slot()];
+        let vacant = Entry::Vacant {
+            next_free: self.free,
+        };
+        let Entry::Occupied(value) = mem::replace(&mut slot.entry, vacant) else {
+            unreachable!("`get` found the value");
+        };
+        // The new generation is what makes `index`, and every copy of it,
+        // miss from now on. A slot that has run out of generations is left
+        // off the free list for good.
+        if let Some(generation) = slot.generation.checked_add(1) {
+            slot.generation = generation;
+            self.free = Some(index.slot);
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Removes every value for which `keep` returns `false`, visiting them
+    /// in slot order.
+    pub fn retain(&mut self, mut keep: impl FnMut(IndexName, &mut T) -> bool) {
+        let removed: Vec<IndexName> = self
+            .iter_mut()
+            .filter_map(|(index, value)| (!keep(index, value)).then_some(index))
+            .collect();
+        for index in removed {
+            self.remove(index);
+        }
+    }
+
+    /// The values with their indices, in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (IndexName, &T)> {
+        self.slots.iter().zip(0..).filter_map(|(slot, n)| {
+            let index = IndexName {
+                slot: n,
+                generation: slot.generation,
+            };
+            Some((index, slot.get(index.generation)?))
+        })
+    }
+
+    /// The values mutably with their indices, in slot order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (IndexName, &mut T)> {
+        self.slots.iter_mut().zip(0..).filter_map(|(slot, n)| {
+            let index = IndexName {
+                slot: n,
+                generation: slot.generation,
+            };
+            Some((index, slot.get_mut(index.generation)?))
+        })
+    }
+}
+
+/// Panics if the value at the index has been removed.
+impl<T> ops::Index<IndexName> for ArenaName<T> {
+    type Output = T;
+
+    fn index(&self, index: IndexName) -> &T {
+        self.get(index).expect("stale arena index")
+    }
+}
+
+/// Panics if the value at the index has been removed.
+impl<T> ops::IndexMut<IndexName> for ArenaName<T> {
+    fn index_mut(&mut self, index: IndexName) -> &mut T {
+        self.get_mut(index).expect("stale arena index")
+    }
+}
+
+{{> partials/test-header}}
+    #[test]
+    fn test_insert_get_remove() {
+        let mut arena = ArenaName::new();
+        let a = arena.insert(String::from("a"));
+        let b = arena.insert(String::from("b"));
+        assert_eq!(arena.len(), 2);
+        assert_eq!(arena.get(a).map(String::as_str), Some("a"));
+        arena[b].push('!');
+        assert_eq!(arena.remove(b).as_deref(), Some("b!"));
+        assert!(!arena.contains(b));
+        assert_eq!(arena.len(), 1);
+    }
+
+    #[test]
+    fn test_freed_slots_are_reused() {
+        let mut arena = ArenaName::with_capacity(2);
+        let first: Vec<_> = (0..3).map(|n| arena.insert(n)).collect();
+        arena.remove(first[0]);
+        arena.remove(first[2]);
+        // Most recently freed first.
+        let again = arena.insert(30);
+        let later = arena.insert(10);
+        assert_eq!((again.slot(), later.slot()), (2, 0));
+        assert_eq!((again.generation(), later.generation()), (1, 1));
+        assert_eq!(arena.slots.len(), 3);
+        assert_eq!(arena.insert(40).slot(), 3);
+    }
+
+    #[test]
+    fn test_stale_index_cannot_reach_new_value() {
+        let mut arena = ArenaName::new();
+        let stale = arena.insert(String::from("old"));
+        let copy = stale;
+        arena.remove(stale);
+        let fresh = arena.insert(String::from("new"));
+        assert_eq!(fresh.slot(), stale.slot());
+        assert_ne!(fresh, stale);
+
+        // Neither the index nor its copy sees, changes or frees the
+        // value now in their slot.
+        assert_eq!(arena.get(copy), None);
+        assert_eq!(arena.get_mut(stale), None);
+        assert_eq!(arena.remove(copy), None);
+        assert_eq!(arena.len(), 1);
+        assert_eq!(arena[fresh], "new");
+    }
+
+    #[test]
+    fn test_worn_out_slot_is_retired() {
+        let mut arena = ArenaName::new();
+        let index = arena.insert("a");
+        arena.slots[index.slot()].generation = u32::MAX;
+        let last = IndexName {
+            generation: u32::MAX,
+            ..index
+        };
+        assert_eq!(arena.remove(last), Some("a"));
+        let next = arena.insert("b");
+        assert_ne!(next.slot(), last.slot());
+        assert_eq!(arena.get(last), None);
+        assert_eq!(arena.get(index), None);
+    }
+
+    #[test]
+    fn test_iteration_agrees_with_held_indices() {
+        let mut arena = ArenaName::new();
+        let held: Vec<_> = (0..6).map(|n| arena.insert(vec![n])).collect();
+        arena.remove(held[1]);
+        arena.remove(held[4]);
+        let replaced = arena.insert(vec![10]);
+
+        for (_, value) in arena.iter_mut() {
+            value.push(0);
+        }
+        let seen: Vec<_> = arena.iter().map(|(index, _)| index).collect();
+        let expected = [held[0], held[2], held[3], replaced, held[5]];
+        assert_eq!(seen, expected);
+        for &index in &held {
+            let value = arena.get(index);
+            assert_eq!(value.is_some(), expected.contains(&index));
+            assert!(value.is_none_or(|value| value.ends_with(&[0])));
+        }
+        assert_eq!(arena[replaced], [10, 0]);
+    }
+
+    #[test]
+    fn test_retain_leaves_kept_indices_valid() {
+        let mut arena = ArenaName::new();
+        let held: Vec<_> = (0..8).map(|n| arena.insert(n.to_string())).collect();
+        arena.retain(|_, value| value.parse::<u32>().unwrap() % 2 == 0);
+        assert_eq!(arena.len(), 4);
+        for (n, &index) in held.iter().enumerate() {
+            let expected = (n % 2 == 0).then(|| n.to_string());
+            assert_eq!(arena.get(index), expected.as_ref());
+        }
+        let reused = arena.insert(String::from("new"));
+        assert_eq!(reused.generation(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "stale arena index")]
+    fn test_index_operator_panics_on_stale_index() {
+        let mut arena = ArenaName::new();
+        let index = arena.insert(1);
+        arena.remove(index);
+        let _ = arena[index];
+    }
+}
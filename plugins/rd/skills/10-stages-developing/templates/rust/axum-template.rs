@@ -0,0 +1,186 @@
+//! [Brief description of what this module does]
+//!
+//! [`router`] serves `Resource`s under the path it is nested at:
+//!
+//! * `GET /` lists them,
+//! * `POST /` creates one from a JSON body,
+//! * `GET /{id}` fetches one.
+//!
+//! Register it with the application's router:
+//!
+//! ```ignore
+//! // Belongs in the binary, where the shared state is built.
+//! let app = Router::new()
+//!     .nest("/{{ module }}", {{ module }}::router())
+//!     .with_state(state);
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+
+/// State the handlers share; in an application, replace the in-memory
+/// store with its database pool or service handles.
+#[derive(Debug, Clone, Default)]
+pub struct AppState {
+    store: Arc<Mutex<BTreeMap<u64, Resource>>>,
+}
+
+/// A resource as the API returns it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Resource {
+    pub id: u64,
+    pub name: String,
+}
+
+/// Body of a request to create a resource.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CreateResource {
+    pub name: String,
+}
+
+/// Why a request failed; answered as a status and `{"error": "..."}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiError {
+    /// No resource has the id.
+    NotFound(u64),
+    /// The request is well-formed but its content is not acceptable.
+    Invalid(String),
+}
+
+/// What a handler returns.
+pub type ApiResult<T> = Result<T, ApiError>;
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error) = match self {
+            Self::NotFound(id) => (StatusCode::NOT_FOUND, format!("no resource {id}")),
+            Self::Invalid(message) => (StatusCode::UNPROCESSABLE_ENTITY, message),
+        };
+        (status, Json(ErrorBody { error })).into_response()
+    }
+}
+
+/// Routes of this module, to nest in the application's router.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list).post(create))
+        .route("/{id}", get(fetch))
+}
+
+async fn list(State(state): State<AppState>) -> Json<Vec<Resource>> {
+    let store = state.store.lock().unwrap();
+    Json(store.values().cloned().collect())
+}
+
+async fn create(
+    State(state): State<AppState>,
+    Json(input): Json<CreateResource>,
+) -> ApiResult<(StatusCode, Json<Resource>)> {
+    let name = input.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::Invalid("name must not be empty".to_string()));
+    }
+    let mut store = state.store.lock().unwrap();
+    let id = store.keys().next_back().map_or(1, |last| last + 1);
+    let created = Resource {
+        id,
+        name: name.to_string(),
+    };
+    store.insert(id, created.clone());
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+async fn fetch(State(state): State<AppState>, Path(id): Path<u64>) -> ApiResult<Json<Resource>> {
+    let found = state.store.lock().unwrap().get(&id).cloned();
+    found.map(Json).ok_or(ApiError::NotFound(id))
+}
+
+{{> partials/test-header}}
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    const BASE: &str = "/{{ module }}";
+
+    fn get(uri: &str) -> Request<Body> {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    fn post(uri: &str, body: Value) -> Request<Body> {
+        Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    /// Sends `request` through the router nested as the application would,
+    /// and returns the status and the JSON body, `null` if there is none.
+    async fn send(state: &AppState, request: Request<Body>) -> (StatusCode, Value) {
+        let app = Router::new().nest(BASE, router()).with_state(state.clone());
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn test_create_then_fetch() {
+        let state = AppState::default();
+        let (status, created) = send(&state, post(BASE, json!({ "name": "first" }))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created, json!({ "id": 1, "name": "first" }));
+
+        let (status, fetched) = send(&state, get(&format!("{BASE}/1"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fetched, created);
+    }
+
+    #[tokio::test]
+    async fn test_list() {
+        let state = AppState::default();
+        for name in ["first", "second"] {
+            send(&state, post(BASE, json!({ "name": name }))).await;
+        }
+        let (status, listed) = send(&state, get(BASE)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed.as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_missing_is_not_found() {
+        let (status, body) = send(&AppState::default(), get(&format!("{BASE}/9"))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body, json!({ "error": "no resource 9" }));
+    }
+
+    #[tokio::test]
+    async fn test_create_rejects_empty_name() {
+        let request = post(BASE, json!({ "name": " " }));
+        let (status, body) = send(&AppState::default(), request).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"], "name must not be empty");
+    }
+
+    #[tokio::test]
+    async fn test_extractor_rejections() {
+        let state = AppState::default();
+        let (status, _) = send(&state, get(&format!("{BASE}/not-a-number"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send(&state, post(BASE, json!({ "title": "x" }))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
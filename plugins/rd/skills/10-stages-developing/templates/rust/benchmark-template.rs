@@ -0,0 +1,64 @@
+//! Benchmarks for `function_name`: [Brief description of what is measured]
+//!
+//! Each input size is measured for `function_name` and for a baseline, in
+//! one group, so the report compares them side by side. Save a run with
+//! `cargo bench -- --save-baseline before` and compare a later one with
+//! `cargo bench -- --baseline before`.
+//!
+//! Needs in `Cargo.toml`:
+//!
+//! ```toml
+//! [[bench]]
+//! name = "bench_name"
+//! harness = false
+//!
+//! [dev-dependencies]
+//! criterion = "0.5"
+//! ```
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Input sizes to measure at.
+const SIZES: &[usize] = &[{{#each sizes}}{{ this }}{{#unless @last}}, {{/unless}}{{/each}}];
+
+/// The code being measured. Replace the body with a call into the crate,
+/// e.g. `my_crate::function_name(input)`.
+fn function_name(input: &[u64]) -> u64 {
+    input.iter().fold(0, |sum, &value| sum.wrapping_add(value))
+}
+
+/// What `function_name` is compared against: the straightforward version
+/// it should beat, or the previous implementation.
+fn baseline(input: &[u64]) -> u64 {
+    let mut sum = 0u64;
+    for &value in input {
+        sum = sum.wrapping_add(value);
+    }
+    sum
+}
+
+/// Input of `size` elements, built outside the measured loop.
+fn input(size: usize) -> Vec<u64> {
+    (0..size as u64).collect()
+}
+
+fn bench_bench_name(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_name");
+    for &size in SIZES {
+        let input = input(size);
+        assert_eq!(function_name(&input), baseline(&input), "size {size}");
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::new("function_name", size), &input, |b, input| {
+            b.iter(|| function_name(black_box(input)))
+        });
+        group.bench_with_input(BenchmarkId::new("baseline", size), &input, |b, input| {
+            b.iter(|| baseline(black_box(input)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_bench_name);
+criterion_main!(benches);
@@ -0,0 +1,153 @@
+// [Brief description of what this module does]
+
+use std::fmt;
+
+/// [Brief description of what the type represents]
+///
+/// # Examples
+///
+/// ```ignore
+/// // Doctests link the crate as a dependency, where `crate::` paths do not resolve.
+/// use crate::type_module::TypeName;
+///
+/// let value = TypeName::builder()
+{{#each required}}
+///     .{{ name }}({{ example }})
+{{/each}}
+///     .build()?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeName {
+{{#each required}}
+    {{ name }}: {{ type }},
+{{/each}}
+{{#each optional}}
+    {{ name }}: {{ type }},
+{{/each}}
+}
+
+impl TypeName {
+    /// Starts building a [`TypeName`].
+    pub fn builder() -> TypeNameBuilder {
+        TypeNameBuilder::default()
+    }
+{{#each required}}
+
+    /// The `{{ name }}` it was built with.
+    pub fn {{ name }}(&self) -> &{{ type }} {
+        &self.{{ name }}
+    }
+{{/each}}
+{{#each optional}}
+
+    /// The `{{ name }}` it was built with, `{{ default }}` unless set.
+    pub fn {{ name }}(&self) -> &{{ type }} {
+        &self.{{ name }}
+    }
+{{/each}}
+}
+
+/// Builder for [`TypeName`], from [`TypeName::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct TypeNameBuilder {
+{{#each required}}
+    {{ name }}: Option<{{ type }}>,
+{{/each}}
+{{#each optional}}
+    {{ name }}: Option<{{ type }}>,
+{{/each}}
+}
+
+impl TypeNameBuilder {
+{{#each required}}
+    /// Sets `{{ name }}`, which is required.
+    pub fn {{ name }}(mut self, {{ name }}: {{ type }}) -> Self {
+        self.{{ name }} = Some({{ name }});
+        self
+    }
+
+{{/each}}
+{{#each optional}}
+    /// Sets `{{ name }}`, `{{ default }}` by default.
+    pub fn {{ name }}(mut self, {{ name }}: {{ type }}) -> Self {
+        self.{{ name }} = Some({{ name }});
+        self
+    }
+
+{{/each}}
+    /// Builds the [`TypeName`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TypeNameError::MissingField`] naming the first required
+    /// field that was not set.
+    pub fn build(self) -> Result<TypeName, TypeNameError> {
+        Ok(TypeName {
+{{#each required}}
+            {{ name }}: self.{{ name }}.ok_or(TypeNameError::MissingField("{{ name }}"))?,
+{{/each}}
+{{#each optional}}
+            {{ name }}: self.{{ name }}.unwrap_or({{ default }}),
+{{/each}}
+        })
+    }
+}
+
+/// Why [`TypeNameBuilder::build`] failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeNameError {
+    /// A required field was not set.
+    MissingField(&'static str),
+}
+
+impl fmt::Display for TypeNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingField(field) => write!(f, "missing required field `{field}`"),
+        }
+    }
+}
+
+impl std::error::Error for TypeNameError {}
+
+{{> partials/test-header}}
+
+    #[test]
+    fn test_type_module_build_applies_defaults() {
+        let value = TypeName::builder()
+{{#each required}}
+            .{{ name }}({{ example }})
+{{/each}}
+            .build()
+            .unwrap();
+
+{{#each required}}
+        assert_eq!(*value.{{ name }}(), {{ example }});
+{{/each}}
+{{#each optional}}
+        assert_eq!(*value.{{ name }}(), {{ default }});
+{{/each}}
+    }
+
+    #[test]
+    fn test_type_module_build_without_required_fields() {
+        let err = TypeName::builder().build().unwrap_err();
+        let first = {{#each required}}{{#if @first}}"{{ name }}"{{/if}}{{/each}};
+        assert_eq!(err, TypeNameError::MissingField(first));
+        assert!(err.to_string().starts_with("missing required field"));
+    }
+
+    #[test]
+    fn test_type_module_build_missing_last_required_field() {
+        let err = TypeName::builder()
+{{#each required}}
+{{#unless @last}}
+            .{{ name }}({{ example }})
+{{/unless}}
+{{/each}}
+            .build()
+            .unwrap_err();
+        let last = {{#each required}}{{#if @last}}"{{ name }}"{{/if}}{{/each}};
+        assert_eq!(err, TypeNameError::MissingField(last));
+    }
+}
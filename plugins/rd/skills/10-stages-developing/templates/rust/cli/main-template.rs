@@ -0,0 +1,111 @@
+//! Command-line entry point: parses the arguments, dispatches to the
+//! subcommand and turns its error into an exit status.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+/// [Brief description of what the command does]
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Explain what is being done on stderr.
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print a greeting.
+    Greet {
+        /// Who to greet.
+        #[arg(default_value = "world")]
+        name: String,
+    },
+    /// Print the number of lines in a file.
+    Count {
+        /// File to read.
+        path: PathBuf,
+    },
+}
+
+/// Why a subcommand failed.
+#[derive(Debug)]
+enum CliError {
+    /// The arguments parsed but cannot be used.
+    Usage(String),
+    /// A file could not be read.
+    Io { path: PathBuf, source: io::Error },
+}
+
+impl CliError {
+    /// Exit status for the error, following sysexits(3). clap exits with
+    /// 2 by itself when the arguments do not parse.
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            CliError::Usage(_) => ExitCode::from(64),
+            CliError::Io { .. } => ExitCode::from(74),
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => f.write_str(message),
+            CliError::Io { path, source } => write!(f, "cannot read {}: {source}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for CliError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CliError::Usage(_) => None,
+            CliError::Io { source, .. } => Some(source),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            err.exit_code()
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), CliError> {
+    match cli.command {
+        Command::Greet { name } => greet(&name),
+        Command::Count { path } => count(path, cli.verbose),
+        // cc-agents:insert dispatch
+    }
+}
+
+fn greet(name: &str) -> Result<(), CliError> {
+    if name.trim().is_empty() {
+        return Err(CliError::Usage("name cannot be empty".to_string()));
+    }
+    println!("Hello, {name}!");
+    Ok(())
+}
+
+fn count(path: PathBuf, verbose: bool) -> Result<(), CliError> {
+    if verbose {
+        eprintln!("reading {}", path.display());
+    }
+    let text = fs::read_to_string(&path).map_err(|source| CliError::Io { path, source })?;
+    println!("{}", text.lines().count());
+    Ok(())
+}
@@ -0,0 +1,56 @@
+//! End-to-end tests driving the binary.
+
+use assert_cmd::Command;
+
+fn cli() -> Command {
+    Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap()
+}
+
+#[test]
+fn test_greet_default() {
+    cli()
+        .arg("greet")
+        .assert()
+        .success()
+        .stdout("Hello, world!\n");
+}
+
+#[test]
+fn test_greet_name() {
+    cli()
+        .args(["greet", "tests"])
+        .assert()
+        .success()
+        .stdout("Hello, tests!\n");
+}
+
+#[test]
+fn test_greet_empty_name_is_usage_error() {
+    cli()
+        .args(["greet", ""])
+        .assert()
+        .code(64)
+        .stderr("error: name cannot be empty\n");
+}
+
+#[test]
+fn test_count_lines() {
+    let path = std::env::temp_dir().join(format!("count-{}.txt", std::process::id()));
+    std::fs::write(&path, "one\ntwo\nthree\n").unwrap();
+    let assert = cli().arg("count").arg(&path).assert();
+    std::fs::remove_file(&path).unwrap();
+    assert.success().stdout("3\n");
+}
+
+#[test]
+fn test_count_missing_file_is_io_error() {
+    cli()
+        .args(["count", "--verbose", "does-not-exist.txt"])
+        .assert()
+        .code(74);
+}
+
+#[test]
+fn test_unknown_subcommand_is_rejected_by_clap() {
+    cli().arg("frobnicate").assert().code(2);
+}
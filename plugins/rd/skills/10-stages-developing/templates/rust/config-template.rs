@@ -0,0 +1,281 @@
+//! [Brief description of what this module does]
+//!
+//! [`Config::load`] layers three sources, each overriding the one before:
+//!
+//! 1. the defaults of [`Config::default`],
+//! 2. a TOML file, if given, which may set any subset of the fields,
+//! 3. environment variables `ENV_PREFIX_HOST`, `ENV_PREFIX_PORT`,
+//!    `ENV_PREFIX_WORKERS` and `ENV_PREFIX_LOG_LEVEL`.
+//!
+//! The result is then validated, so a loaded `Config` is always usable.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// Prefix of the environment variables that override the file.
+pub const VAR_PREFIX: &str = "ENV_PREFIX_";
+
+/// Log levels `log_level` accepts.
+pub const LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+/// Validated configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Address to listen on; not empty.
+    pub host: String,
+    /// Port to listen on; not 0.
+    pub port: u16,
+    /// Worker threads, from 1 to 256.
+    pub workers: usize,
+    /// One of [`LOG_LEVELS`].
+    pub log_level: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            workers: 4,
+            log_level: "info".to_string(),
+        }
+    }
+}
+
+/// Why configuration could not be loaded.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file could not be read.
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The file is not valid TOML, or has fields of the wrong type or that
+    /// `Config` does not have.
+    Parse { path: PathBuf, message: String },
+    /// An environment variable does not hold a value of the field's type.
+    Env {
+        key: String,
+        value: String,
+        message: String,
+    },
+    /// A field holds a value out of range.
+    Invalid {
+        field: &'static str,
+        message: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read { path, source } => write!(f, "cannot read {}: {source}", path.display()),
+            Self::Parse { path, message } => write!(f, "{}: {message}", path.display()),
+            Self::Env {
+                key,
+                value,
+                message,
+            } => write!(f, "{key}={value:?}: {message}"),
+            Self::Invalid { field, message } => write!(f, "invalid `{field}`: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Read { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration from the defaults, the file at `path` if
+    /// given, and the process environment.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`ConfigError`] met in any layer, or in
+    /// validating the result.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        Self::load_from(path, env::vars())
+    }
+
+    /// Like [`Config::load`], with the environment given as `vars`.
+    ///
+    /// # Errors
+    ///
+    /// As for [`Config::load`].
+    pub fn load_from(
+        path: Option<&Path>,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, ConfigError> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        for (key, value) in vars {
+            if let Some(field) = key.strip_prefix(VAR_PREFIX) {
+                config.set(field, &key, value)?;
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// The defaults, overridden by the fields the file at `path` sets.
+    fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&text).map_err(|err| ConfigError::Parse {
+            path: path.to_path_buf(),
+            message: err.message().to_string(),
+        })
+    }
+
+    /// Overrides the field `field` names from the variable `key`. Variables
+    /// with the prefix that name no field are ignored.
+    fn set(&mut self, field: &str, key: &str, value: String) -> Result<(), ConfigError> {
+        let invalid = |message: String| ConfigError::Env {
+            key: key.to_string(),
+            value: value.clone(),
+            message,
+        };
+        match field {
+            "HOST" => self.host = value,
+            "PORT" => self.port = value.parse().map_err(|err| invalid(format!("{err}")))?,
+            "WORKERS" => self.workers = value.parse().map_err(|err| invalid(format!("{err}")))?,
+            "LOG_LEVEL" => self.log_level = value.to_lowercase(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Checks that every field is in range.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Invalid`] for the first field that is not.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |field, message: &str| {
+            Err(ConfigError::Invalid {
+                field,
+                message: message.to_string(),
+            })
+        };
+        if self.host.trim().is_empty() {
+            return invalid("host", "must not be empty");
+        }
+        if self.port == 0 {
+            return invalid("port", "must not be 0");
+        }
+        if !(1..=256).contains(&self.workers) {
+            return invalid("workers", "must be from 1 to 256");
+        }
+        if !LOG_LEVELS.contains(&self.log_level.as_str()) {
+            return invalid("log_level", "must be error, warn, info, debug or trace");
+        }
+        Ok(())
+    }
+}
+
+{{> partials/test-header}}
+    use std::process;
+
+    /// A config file with `contents`, removed when dropped.
+    struct File(PathBuf);
+
+    impl File {
+        fn new(name: &str, contents: &str) -> Self {
+            let path = env::temp_dir().join(format!("config-test-{}-{name}.toml", process::id()));
+            fs::write(&path, contents).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for File {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (format!("{VAR_PREFIX}{key}"), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_defaults_are_valid() {
+        let config = Config::load_from(None, Vec::new()).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_file_overrides_defaults() {
+        let file = File::new("file", "port = 9000\nlog_level = \"debug\"\n");
+        let config = Config::load_from(Some(&file.0), Vec::new()).unwrap();
+        assert_eq!(config.port, 9000);
+        assert_eq!(config.log_level, "debug");
+        assert_eq!(config.host, Config::default().host);
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let file = File::new("env", "port = 9000\nworkers = 8\n");
+        let env = vars(&[("PORT", "9100"), ("LOG_LEVEL", "WARN"), ("UNUSED", "x")]);
+        let config = Config::load_from(Some(&file.0), env).unwrap();
+        assert_eq!(config.port, 9100);
+        assert_eq!(config.workers, 8);
+        assert_eq!(config.log_level, "warn");
+    }
+
+    #[test]
+    fn test_invalid_env_value() {
+        let err = Config::load_from(None, vars(&[("PORT", "eighty")])).unwrap_err();
+        assert!(matches!(&err, ConfigError::Env { key, .. } if key.ends_with("PORT")));
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        let file = File::new("invalid", "port = \"high\"\n");
+        let err = Config::load_from(Some(&file.0), Vec::new()).unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { .. }));
+
+        let file = File::new("unknown", "colour = \"red\"\n");
+        let err = Config::load_from(Some(&file.0), Vec::new()).unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { .. }));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let path = Path::new("/nonexistent/config.toml");
+        let err = Config::load_from(Some(path), Vec::new()).unwrap_err();
+        assert!(matches!(err, ConfigError::Read { .. }));
+    }
+
+    #[test]
+    fn test_out_of_range_values() {
+        for (key, value, field) in [
+            ("HOST", " ", "host"),
+            ("PORT", "0", "port"),
+            ("WORKERS", "0", "workers"),
+            ("LOG_LEVEL", "loud", "log_level"),
+        ] {
+            let err = Config::load_from(None, vars(&[(key, value)])).unwrap_err();
+            assert!(
+                matches!(err, ConfigError::Invalid { field: f, .. } if f == field),
+                "{key}={value}: {err}"
+            );
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! [Brief description of what this module does]
+//!
+//! [`ContainerName`] is a ring buffer: a queue of at most `capacity`
+//! values, oldest first. [`push`](ContainerName::push) makes room by
+//! evicting the oldest value and hands it back, for histories and sliding
+//! windows where only the latest values matter;
+//! [`try_push`](ContainerName::try_push) refuses instead, for bounded
+//! queues where nothing may be lost.
+//!
+//! The buffer is generic over any `T`, with no bounds on the type itself;
+//! each impl asks only for what it uses, so `Clone`, `PartialEq` and
+//! `Debug` are available exactly when `T` has them. It iterates by value
+//! and by reference from oldest to newest, collects from any iterator and
+//! extends like a queue.
+
+use std::collections::vec_deque::{self, VecDeque};
+use std::fmt;
+
+/// Capacity of a buffer collected from an iterator of fewer values.
+pub const DEFAULT_CAPACITY: usize = DEFAULT_LEN;
+
+/// The value [`ContainerName::try_push`] could not add because the buffer
+/// was full.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapacityError<T>(pub T);
+
+impl<T> CapacityError<T> {
+    /// The value that was not added.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("buffer is full")
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for CapacityError<T> {}
+
+/// A queue of at most `capacity` values that evicts the oldest when full.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerName<T> {
+    values: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> ContainerName<T> {
+    /// Creates an empty buffer holding at most `capacity` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "buffer capacity must be positive");
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// The most values the buffer holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Values held.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether the buffer holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Whether the next [`push`](Self::push) will evict a value.
+    pub fn is_full(&self) -> bool {
+        self.values.len() == self.capacity
+    }
+
+    /// Adds `value` as the newest, evicting and returning the oldest if
+    /// the buffer is full.
+    pub fn push(&mut self, value: T) -> Option<T> {
+        let evicted = if self.is_full() {
+            self.values.pop_front()
+        } else {
+            None
+        };
+        self.values.push_back(value);
+        evicted
+    }
+
+    /// Adds `value` as the newest unless the buffer is full.
+    ///
+    /// # Errors
+    ///
+    /// Returns `value` in a [`CapacityError`] if the buffer is full.
+    pub fn try_push(&mut self, value: T) -> Result<(), CapacityError<T>> {
+        if self.is_full() {
+            return Err(CapacityError(value));
+        }
+        self.values.push_back(value);
+        Ok(())
+    }
+
+    /// Removes and returns the oldest value.
+    pub fn pop(&mut self) -> Option<T> {
+        self.values.pop_front()
+    }
+
+    /// The oldest value.
+    pub fn oldest(&self) -> Option<&T> {
+        self.values.front()
+    }
+
+    /// The newest value.
+    pub fn newest(&self) -> Option<&T> {
+        self.values.back()
+    }
+
+    /// The values from oldest to newest.
+    pub fn iter(&self) -> vec_deque::Iter<'_, T> {
+        self.values.iter()
+    }
+
+    /// Removes every value, keeping the capacity.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+impl<T> IntoIterator for ContainerName<T> {
+    type Item = T;
+    type IntoIter = vec_deque::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ContainerName<T> {
+    type Item = &'a T;
+    type IntoIter = vec_deque::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut ContainerName<T> {
+    type Item = &'a mut T;
+    type IntoIter = vec_deque::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.iter_mut()
+    }
+}
+
+/// Collects every value, oldest first. The capacity is the number of
+/// values, or [`DEFAULT_CAPACITY`] if that is larger, so nothing is
+/// evicted.
+impl<T> FromIterator<T> for ContainerName<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let values: VecDeque<T> = iter.into_iter().collect();
+        let capacity = values.len().max(DEFAULT_CAPACITY);
+        Self { values, capacity }
+    }
+}
+
+/// Pushes every value in turn, evicting the oldest as
+/// [`push`](ContainerName::push) does.
+impl<T> Extend<T> for ContainerName<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+{{> partials/test-header}}
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Counts its drops, to check values are dropped once and only once.
+    #[derive(Debug)]
+    struct Tracked(Rc<Cell<usize>>);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    /// The queue behaviour every element type must share, given four
+    /// distinct values.
+    fn check_queue<T: Clone + PartialEq + fmt::Debug>(values: [T; 4]) {
+        let [a, b, c, d] = values;
+        let mut buffer = ContainerName::new(3);
+        assert_eq!(buffer.push(a.clone()), None);
+        assert_eq!(buffer.push(b.clone()), None);
+        assert_eq!(buffer.push(c.clone()), None);
+        assert!(buffer.is_full());
+        assert_eq!(buffer.push(d.clone()), Some(a));
+        assert_eq!(buffer.oldest(), Some(&b));
+        assert_eq!(buffer.newest(), Some(&d));
+
+        let copy = buffer.clone();
+        assert_eq!(buffer.pop(), Some(b.clone()));
+        assert_eq!(buffer.len(), 2);
+        let remaining: Vec<T> = copy.into_iter().collect();
+        assert_eq!(remaining, [b, c, d]);
+    }
+
+    #[test]
+    fn test_queue_of_copy_values() {
+        check_queue([1_u8, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_queue_of_owned_values() {
+        check_queue(["a", "b", "c", "d"].map(String::from));
+        check_queue([vec![1], vec![], vec![2, 3], vec![4]]);
+    }
+
+    #[test]
+    fn test_queue_of_borrowed_values() {
+        let words: [&str; 4] = ["one", "two", "three", "four"];
+        let slices: [&[u8]; 4] = [b"1", b"22", b"333", b"4444"];
+        check_queue(words);
+        check_queue(slices);
+    }
+
+    #[test]
+    fn test_try_push_returns_value_when_full() {
+        let mut buffer = ContainerName::new(1);
+        buffer.try_push(String::from("kept")).unwrap();
+        let err = buffer.try_push(String::from("refused")).unwrap_err();
+        assert_eq!(err.to_string(), "buffer is full");
+        assert_eq!(err.into_inner(), "refused");
+        assert_eq!(buffer.iter().collect::<Vec<_>>(), ["kept"]);
+    }
+
+    #[test]
+    fn test_every_value_dropped_exactly_once() {
+        let drops = Rc::new(Cell::new(0));
+        let tracked = || Tracked(Rc::clone(&drops));
+        let mut buffer = ContainerName::new(2);
+        for _ in 0..5 {
+            drop(buffer.push(tracked()));
+        }
+        // Three evicted, two still held.
+        assert_eq!(drops.get(), 3);
+        assert!(buffer.try_push(tracked()).is_err());
+        assert_eq!(drops.get(), 4);
+        drop(buffer);
+        assert_eq!(drops.get(), 6);
+    }
+
+    #[test]
+    fn test_iterates_oldest_first() {
+        let mut buffer = ContainerName::new(3);
+        buffer.extend(1..=5);
+        for value in &mut buffer {
+            *value *= 10;
+        }
+        let borrowed: Vec<&i32> = (&buffer).into_iter().collect();
+        assert_eq!(borrowed, [&30, &40, &50]);
+        assert_eq!(buffer.into_iter().sum::<i32>(), 120);
+    }
+
+    #[test]
+    fn test_collect_keeps_every_value() {
+        let few: ContainerName<char> = "abc".chars().collect();
+        assert_eq!(few.capacity(), DEFAULT_CAPACITY);
+        assert_eq!(few.len(), 3);
+
+        let count = DEFAULT_CAPACITY * 2;
+        let many: ContainerName<String> = (0..count).map(|n| n.to_string()).collect();
+        assert_eq!(many.capacity(), count);
+        assert!(many.is_full());
+        assert_eq!(many.oldest().map(String::as_str), Some("0"));
+    }
+
+    #[test]
+    fn test_clear_keeps_capacity() {
+        let mut buffer: ContainerName<Vec<u8>> = ContainerName::new(2);
+        buffer.extend([vec![1], vec![2]]);
+        buffer.clear();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "buffer capacity must be positive")]
+    fn test_new_rejects_zero_capacity() {
+        let _ = ContainerName::<String>::new(0);
+    }
+}
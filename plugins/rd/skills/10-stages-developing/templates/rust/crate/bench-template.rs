@@ -0,0 +1,16 @@
+//! Benchmarks for `{{ crate_name }}`, run with `cargo bench`. Uses only the
+//! standard library; switch to a harness such as criterion when needed.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 100_000;
+
+fn main() {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box({{ crate_name }}::greet(black_box("bench")).ok());
+    }
+    let elapsed = start.elapsed();
+    println!("greet: {:?} per call", elapsed / ITERATIONS);
+}
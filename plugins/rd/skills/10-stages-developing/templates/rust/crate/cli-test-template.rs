@@ -0,0 +1,22 @@
+//! End-to-end tests driving the `{{ name }}` binary.
+
+use std::process::Command;
+
+#[test]
+fn test_greets_argument() {
+    let output = Command::new(env!("CARGO_BIN_EXE_{{ name }}"))
+        .arg("tests")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Hello, tests!\n");
+}
+
+#[test]
+fn test_rejects_empty_argument() {
+    let output = Command::new(env!("CARGO_BIN_EXE_{{ name }}"))
+        .arg("")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
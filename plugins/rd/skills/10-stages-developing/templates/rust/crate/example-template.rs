@@ -0,0 +1,6 @@
+//! Minimal use of `{{ crate_name }}`; run with `cargo run --example basic`.
+
+fn main() -> Result<(), String> {
+    println!("{}", {{ crate_name }}::greet("example")?);
+    Ok(())
+}
@@ -0,0 +1,25 @@
+//! {{ description }}
+
+/// Greets `name`, rejecting an empty one.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!({{ crate_name }}::greet("world").unwrap(), "Hello, world!");
+/// ```
+pub fn greet(name: &str) -> Result<String, String> {
+    if name.is_empty() {
+        return Err("name cannot be empty".to_string());
+    }
+    Ok(format!("Hello, {name}!"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_greet_rejects_empty_name() {
+        assert!(greet("").is_err());
+    }
+}
@@ -0,0 +1,18 @@
+//! Command-line entry point of `{{ name }}`; the logic lives in the
+//! library so tests, benchmarks and examples can use it.
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let name = std::env::args().nth(1).unwrap_or_else(|| "world".to_string());
+    match {{ crate_name }}::greet(&name) {
+        Ok(greeting) => {
+            println!("{greeting}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,6 @@
+//! Integration tests for `{{ crate_name }}`'s public API.
+
+#[test]
+fn test_greet() {
+    assert_eq!({{ crate_name }}::greet("tests").unwrap(), "Hello, tests!");
+}
@@ -0,0 +1,135 @@
+//! `#[derive(DeriveName)]`: [Brief description of what the derive generates]
+//!
+//! The derive parses its input with `syn` and generates code with `quote`.
+//! Mistakes in the input are reported as compile errors pointing at the
+//! offending tokens, not as panics.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// The field attribute the derive reads, as declared below.
+const ATTRIBUTE: &str = "derive_attr";
+
+/// Adds an associated `FIELD_NAMES` constant to a struct with named
+/// fields, listing them in declaration order. A field marked
+/// `#[derive_attr(skip)]` is left out, and one marked
+/// `#[derive_attr(rename = "...")]` is listed under that name.
+#[proc_macro_derive(DeriveName, attributes(derive_attr))]
+pub fn derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// The expansion, on `proc_macro2` tokens so that unit tests can run it.
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "DeriveName can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "DeriveName needs a struct with named fields",
+        ));
+    };
+    let mut names = Vec::new();
+    for field in &fields.named {
+        let mut name = field.ident.as_ref().map(ToString::to_string);
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident(ATTRIBUTE)) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    name = None;
+                    Ok(())
+                } else if meta.path.is_ident("rename") {
+                    let renamed: LitStr = meta.value()?.parse()?;
+                    name = Some(renamed.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `skip` or `rename = \"...\"`"))
+                }
+            })?;
+        }
+        names.extend(name);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Names of the fields, in declaration order.
+            pub const FIELD_NAMES: &'static [&'static str] = &[#(#names),*];
+        }
+    })
+}
+
+{{> partials/test-header}}
+    use syn::parse_quote;
+
+    fn expanded(input: DeriveInput) -> String {
+        expand(&input).unwrap().to_string()
+    }
+
+    fn error(input: DeriveInput) -> String {
+        expand(&input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_expand_lists_fields() {
+        let input = parse_quote! {
+            struct Point {
+                x: i32,
+                #[derive_attr(rename = "vertical")]
+                y: i32,
+                #[derive_attr(skip)]
+                cache: u8,
+            }
+        };
+        let expected = quote! {
+            impl Point {
+                /// Names of the fields, in declaration order.
+                pub const FIELD_NAMES: &'static [&'static str] = &["x", "vertical"];
+            }
+        };
+        assert_eq!(expanded(input), expected.to_string());
+    }
+
+    #[test]
+    fn test_expand_keeps_generics() {
+        let input = parse_quote! {
+            struct Wrapper<T: Clone> where T: Default {
+                inner: T,
+            }
+        };
+        assert!(expanded(input)
+            .starts_with(&quote! { impl<T: Clone> Wrapper<T> where T: Default }.to_string()));
+    }
+
+    #[test]
+    fn test_expand_rejects_enums_and_tuple_structs() {
+        assert_eq!(
+            error(parse_quote! { enum Shape { Circle } }),
+            "DeriveName can only be derived for structs"
+        );
+        assert_eq!(
+            error(parse_quote! { struct Pair(u8, u8); }),
+            "DeriveName needs a struct with named fields"
+        );
+    }
+
+    #[test]
+    fn test_expand_rejects_unknown_attribute() {
+        let input = parse_quote! {
+            struct Point {
+                #[derive_attr(hide)]
+                x: i32,
+            }
+        };
+        assert_eq!(error(input), "expected `skip` or `rename = \"...\"`");
+    }
+}
@@ -0,0 +1,38 @@
+//! `#[derive(DeriveName)]` used from another crate, as its users will.
+
+use {{ name | snake_case }}::DeriveName;
+
+#[derive(DeriveName)]
+#[allow(dead_code)]
+struct Point {
+    x: i32,
+    #[derive_attr(rename = "vertical")]
+    y: i32,
+    #[derive_attr(skip)]
+    cache: u8,
+}
+
+#[derive(DeriveName)]
+#[allow(dead_code)]
+struct Wrapper<T> {
+    inner: T,
+    count: usize,
+}
+
+#[derive(DeriveName)]
+struct Empty {}
+
+#[test]
+fn test_derive_lists_fields() {
+    assert_eq!(Point::FIELD_NAMES, ["x", "vertical"]);
+}
+
+#[test]
+fn test_derive_on_generic_struct() {
+    assert_eq!(Wrapper::<String>::FIELD_NAMES, ["inner", "count"]);
+}
+
+#[test]
+fn test_derive_on_struct_without_fields() {
+    assert!(Empty::FIELD_NAMES.is_empty());
+}
@@ -0,0 +1,175 @@
+//! [Brief description of what this module does]
+//!
+//! The functions here read and write the `records_table` table, described
+//! in `schema.rs` and created by the migration under `migrations/`. Diesel
+//! is synchronous: each call takes the connection it runs on, so a service
+//! checks one out of its pool (e.g. `diesel::r2d2`), and async code calls
+//! these from `spawn_blocking`.
+//!
+//! The backend is SQLite; for PostgreSQL, take a `PgConnection` instead
+//! and check the models against `diesel::pg::Pg`.
+
+use std::fmt;
+
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+
+use crate::schema::records_table;
+
+/// A row of `records_table`.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable)]
+#[diesel(table_name = records_table)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Record {
+    pub id: i64,
+    pub title: String,
+    pub body: String,
+}
+
+/// Fields of a [`Record`] to insert or update.
+#[derive(Debug, Clone, PartialEq, Eq, Insertable, AsChangeset)]
+#[diesel(table_name = records_table)]
+pub struct NewRecord {
+    pub title: String,
+    pub body: String,
+}
+
+/// Why a repository call failed.
+#[derive(Debug)]
+pub enum RepositoryError {
+    /// No row has the id.
+    NotFound(i64),
+    /// A unique constraint rejected the row, e.g. a duplicate title.
+    Conflict(String),
+    /// Any other database error.
+    Database(DieselError),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(id) => write!(f, "no record with id {id}"),
+            Self::Conflict(message) => write!(f, "conflicts with an existing record: {message}"),
+            Self::Database(err) => write!(f, "database error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Database(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<DieselError> for RepositoryError {
+    fn from(err: DieselError) -> Self {
+        match err {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, info) => {
+                Self::Conflict(info.message().to_string())
+            }
+            err => Self::Database(err),
+        }
+    }
+}
+
+/// Result of a repository call.
+pub type Result<T> = std::result::Result<T, RepositoryError>;
+
+/// Inserts `new` and returns the stored row.
+///
+/// # Errors
+///
+/// [`RepositoryError::Conflict`] if the title is taken.
+pub fn create(conn: &mut SqliteConnection, new: &NewRecord) -> Result<Record> {
+    let row = diesel::insert_into(records_table::table)
+        .values(new)
+        .returning(Record::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+/// The row `id`.
+///
+/// # Errors
+///
+/// [`RepositoryError::NotFound`] if there is none.
+pub fn get(conn: &mut SqliteConnection, id: i64) -> Result<Record> {
+    records_table::table
+        .find(id)
+        .select(Record::as_select())
+        .first(conn)
+        .optional()?
+        .ok_or(RepositoryError::NotFound(id))
+}
+
+/// Every row, by id.
+///
+/// # Errors
+///
+/// [`RepositoryError::Database`] if the query fails.
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Record>> {
+    let rows = records_table::table
+        .order(records_table::id)
+        .select(Record::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+/// Replaces the fields of row `id` with `new` and returns it.
+///
+/// # Errors
+///
+/// [`RepositoryError::NotFound`] if there is no such row, and
+/// [`RepositoryError::Conflict`] if the title is taken.
+pub fn update(conn: &mut SqliteConnection, id: i64, new: &NewRecord) -> Result<Record> {
+    diesel::update(records_table::table.find(id))
+        .set(new)
+        .returning(Record::as_returning())
+        .get_result(conn)
+        .optional()?
+        .ok_or(RepositoryError::NotFound(id))
+}
+
+/// Deletes row `id`.
+///
+/// # Errors
+///
+/// [`RepositoryError::NotFound`] if there is none.
+pub fn delete(conn: &mut SqliteConnection, id: i64) -> Result<()> {
+    let deleted = diesel::delete(records_table::table.find(id)).execute(conn)?;
+    if deleted == 0 {
+        return Err(RepositoryError::NotFound(id));
+    }
+    Ok(())
+}
+
+/// Runs `f` in a transaction: committed if it returns `Ok`, rolled back if
+/// it returns an error, so calls made in `f` apply all together or not at
+/// all. Nested calls use savepoints.
+///
+/// # Errors
+///
+/// Whatever `f` returns, or [`RepositoryError::Database`] if the
+/// transaction itself cannot begin or commit.
+pub fn in_transaction<T, F>(conn: &mut SqliteConnection, f: F) -> Result<T>
+where
+    F: FnOnce(&mut SqliteConnection) -> Result<T>,
+{
+    conn.transaction(f)
+}
+
+{{> partials/test-header}}
+
+    #[test]
+    fn test_error_display() {
+        assert_eq!(
+            RepositoryError::NotFound(7).to_string(),
+            "no record with id 7"
+        );
+        let err = RepositoryError::from(DieselError::NotFound);
+        assert!(matches!(err, RepositoryError::Database(_)));
+    }
+}
@@ -0,0 +1,13 @@
+//! Diesel's description of the database tables.
+//!
+//! `diesel print-schema` regenerates this file from a migrated database;
+//! until the project runs the Diesel CLI, keep it in step with
+//! `migrations/` by hand.
+
+diesel::table! {
+    records_table (id) {
+        id -> BigInt,
+        title -> Text,
+        body -> Text,
+    }
+}
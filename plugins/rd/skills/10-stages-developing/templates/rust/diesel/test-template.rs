@@ -0,0 +1,95 @@
+//! Integration tests for the `{{ module }}` repository against SQLite.
+//!
+//! Each test gets its own in-memory database with the migrations applied,
+//! so they need no server and run with a plain `cargo test`.
+
+use diesel::{Connection, SqliteConnection};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+use crate_name::{{ module }}::{self as repository, NewRecord, RepositoryError};
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
+
+/// A fresh database, migrated to the latest schema.
+fn database() -> SqliteConnection {
+    let mut conn = SqliteConnection::establish(":memory:").unwrap();
+    conn.run_pending_migrations(MIGRATIONS).unwrap();
+    conn
+}
+
+fn new(title: &str) -> NewRecord {
+    NewRecord {
+        title: title.to_string(),
+        body: format!("about {title}"),
+    }
+}
+
+#[test]
+fn test_create_then_get() {
+    let conn = &mut database();
+    let created = repository::create(conn, &new("first")).unwrap();
+    assert_eq!(repository::get(conn, created.id).unwrap(), created);
+}
+
+#[test]
+fn test_list_update_delete() {
+    let conn = &mut database();
+    let first = repository::create(conn, &new("first")).unwrap();
+    repository::create(conn, &new("second")).unwrap();
+    assert_eq!(repository::list(conn).unwrap().len(), 2);
+
+    let updated = repository::update(conn, first.id, &new("renamed")).unwrap();
+    assert_eq!(updated.title, "renamed");
+
+    repository::delete(conn, first.id).unwrap();
+    assert!(matches!(
+        repository::get(conn, first.id),
+        Err(RepositoryError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_missing_rows() {
+    let conn = &mut database();
+    assert!(matches!(
+        repository::update(conn, 99, &new("x")),
+        Err(RepositoryError::NotFound(99))
+    ));
+    assert!(matches!(
+        repository::delete(conn, 99),
+        Err(RepositoryError::NotFound(99))
+    ));
+}
+
+#[test]
+fn test_duplicate_title_is_conflict() {
+    let conn = &mut database();
+    repository::create(conn, &new("first")).unwrap();
+    assert!(matches!(
+        repository::create(conn, &new("first")),
+        Err(RepositoryError::Conflict(_))
+    ));
+}
+
+#[test]
+fn test_transaction_commits_on_ok() {
+    let conn = &mut database();
+    let created = repository::in_transaction(conn, |conn| {
+        repository::create(conn, &new("first"))?;
+        repository::create(conn, &new("second"))
+    })
+    .unwrap();
+    assert_eq!(repository::get(conn, created.id).unwrap(), created);
+    assert_eq!(repository::list(conn).unwrap().len(), 2);
+}
+
+#[test]
+fn test_transaction_rolls_back_on_error() {
+    let conn = &mut database();
+    let result = repository::in_transaction(conn, |conn| {
+        repository::create(conn, &new("first"))?;
+        repository::create(conn, &new("first"))
+    });
+    assert!(matches!(result, Err(RepositoryError::Conflict(_))));
+    assert!(repository::list(conn).unwrap().is_empty());
+}
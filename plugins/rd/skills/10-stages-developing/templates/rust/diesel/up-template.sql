@@ -0,0 +1,8 @@
+-- Table behind crate_name's `Record` repository. Diesel runs migrations in
+-- the order of their directory names; add later changes as new
+-- directories rather than editing this one once it has run anywhere.
+CREATE TABLE records_table (
+    id INTEGER PRIMARY KEY NOT NULL,
+    title TEXT NOT NULL UNIQUE,
+    body TEXT NOT NULL DEFAULT ''
+);
@@ -0,0 +1,96 @@
+// [Brief description of what this module does]
+
+/// Result of an operation that can fail with [`ErrorType`].
+pub type Result<T> = std::result::Result<T, ErrorType>;
+
+/// [Brief description of what can go wrong]
+///
+/// # Examples
+///
+/// ```ignore
+/// // Doctests link the crate as a dependency, where `crate::` paths do not resolve.
+/// use crate::error_module::{ErrorType, Result};
+///
+/// fn read_config(path: &str) -> Result<String> {
+///     // Source errors convert with `?`.
+///     Ok(std::fs::read_to_string(path)?)
+/// }
+/// ```
+#[derive(Debug, thiserror::Error)]
+pub enum ErrorType {
+{{#each variants}}
+    /// Displayed as {{ message }}.
+    #[error({{ message }})]
+    {{ name }},
+
+{{else}}
+    /// The input was empty.
+    #[error("input is empty")]
+    Empty,
+
+    /// The input could not be parsed.
+    #[error("invalid input `{0}`")]
+    Invalid(String),
+
+{{/each}}
+{{#each sources}}
+    /// A [`{{ type }}`] from an underlying operation.
+    #[error(transparent)]
+    {{ name }}(#[from] {{ type }}),
+{{#unless @last}}
+
+{{/unless}}
+{{else}}
+    /// An I/O operation failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+{{/each}}
+}
+
+{{> partials/test-header}}
+
+{{#each variants}}
+    #[test]
+    fn test_{{ name | snake_case }}_display() {
+        assert_eq!(ErrorType::{{ name }}.to_string(), {{ message }});
+    }
+
+{{else}}
+    #[test]
+    fn test_empty_display() {
+        assert_eq!(ErrorType::Empty.to_string(), "input is empty");
+    }
+
+    #[test]
+    fn test_invalid_display() {
+        use std::error::Error as _;
+
+        let err = ErrorType::Invalid("x=".to_string());
+        assert_eq!(err.to_string(), "invalid input `x=`");
+        assert!(err.source().is_none());
+    }
+
+{{/each}}
+    #[test]
+    fn test_from_conversions() {
+        fn converts<E: Into<ErrorType>>() {}
+{{#each sources}}
+        converts::<{{ type }}>();
+{{else}}
+        converts::<std::io::Error>();
+{{/each}}
+    }
+{{#unless sources}}
+
+    #[test]
+    fn test_io_error_is_transparent() {
+        fn read() -> Result<()> {
+            Err(std::io::Error::other("disk full"))?
+        }
+
+        let err = read().unwrap_err();
+        assert!(matches!(err, ErrorType::Io(_)));
+        assert_eq!(err.to_string(), "disk full");
+    }
+{{/unless}}
+}
@@ -0,0 +1,183 @@
+//! [Brief description of what this module does]
+//!
+//! [`BusName`] delivers events by type: a handler subscribed for `E` is
+//! called with every `E` published, and with nothing else. Subscribing
+//! returns a [`Subscription`], which owns the handler; the bus only holds
+//! a weak reference to it, so dropping the subscription unsubscribes, and
+//! the dead reference is cleared at the next publish of that type.
+//!
+//! Handlers are called after the bus lock is released, so they may
+//! publish or subscribe themselves.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+
+type AnyHandler = dyn Any + Send + Sync;
+type Handlers = HashMap<TypeId, Vec<Weak<AnyHandler>>>;
+
+struct Handler<E>(Box<dyn Fn(&E) + Send + Sync>);
+
+/// Keeps a handler subscribed until it is dropped.
+#[must_use = "dropping a subscription unsubscribes its handler"]
+pub struct Subscription {
+    _handler: Arc<AnyHandler>,
+}
+
+impl Subscription {
+    /// Unsubscribes the handler now, the same as dropping it.
+    pub fn unsubscribe(self) {}
+}
+
+impl fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscription").finish_non_exhaustive()
+    }
+}
+
+/// An in-process publish/subscribe bus keyed by event type.
+#[derive(Default)]
+pub struct BusName {
+    handlers: Mutex<Handlers>,
+}
+
+impl BusName {
+    /// Creates a bus without subscribers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls `handler` with every `E` published, until the returned
+    /// [`Subscription`] is dropped.
+    pub fn subscribe<E, F>(&self, handler: F) -> Subscription
+    where
+        E: 'static,
+        F: Fn(&E) + Send + Sync + 'static,
+    {
+        let handler: Arc<AnyHandler> = Arc::new(Handler(Box::new(handler)));
+        self.lock()
+            .entry(TypeId::of::<E>())
+            .or_default()
+            .push(Arc::downgrade(&handler));
+        Subscription { _handler: handler }
+    }
+
+    /// Calls every live handler of `E` with `event`, in subscription
+    /// order, and returns how many were called.
+    pub fn publish<E: 'static>(&self, event: &E) -> usize {
+        let live: Vec<Arc<AnyHandler>> = {
+            let mut handlers = self.lock();
+            let Some(subscribed) = handlers.get_mut(&TypeId::of::<E>()) else {
+                return 0;
+            };
+            subscribed.retain(|handler| handler.strong_count() > 0);
+            subscribed.iter().filter_map(Weak::upgrade).collect()
+        };
+        for handler in &live {
+            if let Some(Handler(handler)) = handler.downcast_ref::<Handler<E>>() {
+                handler(event);
+            }
+        }
+        live.len()
+    }
+
+    /// Handlers of `E` whose subscription is still alive.
+    pub fn subscriber_count<E: 'static>(&self) -> usize {
+        self.lock().get(&TypeId::of::<E>()).map_or(0, |handlers| {
+            handlers.iter().filter(|h| h.strong_count() > 0).count()
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Handlers> {
+        self.handlers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for BusName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("BusName");
+        debug.field("event_types", &self.lock().len());
+        debug.finish()
+    }
+}
+
+{{> partials/test-header}}
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Created(u32);
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Deleted(u32);
+
+    /// Subscribes a handler recording every `Created` id into a shared
+    /// list.
+    fn record(bus: &BusName, seen: &Arc<Mutex<Vec<u32>>>) -> Subscription {
+        let seen = Arc::clone(seen);
+        bus.subscribe(move |event: &Created| seen.lock().unwrap().push(event.0))
+    }
+
+    #[test]
+    fn test_publish_fans_out_to_subscribers() {
+        let bus = BusName::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let _first = record(&bus, &seen);
+        let _second = record(&bus, &seen);
+        assert_eq!(bus.publish(&Created(1)), 2);
+        assert_eq!(bus.publish(&Created(2)), 2);
+        assert_eq!(*seen.lock().unwrap(), [1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn test_delivers_only_subscribed_type() {
+        let bus = BusName::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let _created = record(&bus, &seen);
+        let deleted = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&deleted);
+        let _deleted = bus.subscribe(move |_: &Deleted| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        bus.publish(&Deleted(7));
+        assert!(seen.lock().unwrap().is_empty());
+        assert_eq!(deleted.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_dropped_subscription_stops_delivery() {
+        let bus = BusName::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let kept = record(&bus, &seen);
+        let dropped = record(&bus, &seen);
+        bus.publish(&Created(1));
+        dropped.unsubscribe();
+        assert_eq!(bus.subscriber_count::<Created>(), 1);
+        assert_eq!(bus.publish(&Created(2)), 1);
+        drop(kept);
+        assert_eq!(bus.publish(&Created(3)), 0);
+        assert_eq!(*seen.lock().unwrap(), [1, 1, 2]);
+    }
+
+    #[test]
+    fn test_publish_without_subscribers() {
+        let bus = BusName::new();
+        assert_eq!(bus.publish(&Created(1)), 0);
+        assert_eq!(bus.subscriber_count::<Created>(), 0);
+    }
+
+    #[test]
+    fn test_handler_can_publish() {
+        let bus = Arc::new(BusName::new());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let _recorder = record(&bus, &seen);
+        let inner = Arc::downgrade(&bus);
+        let _forward = bus.subscribe(move |event: &Deleted| {
+            if let Some(bus) = inner.upgrade() {
+                bus.publish(&Created(event.0));
+            }
+        });
+        bus.publish(&Deleted(9));
+        assert_eq!(*seen.lock().unwrap(), [9]);
+    }
+}
@@ -0,0 +1,153 @@
+//! [Brief description of what this module does]
+//!
+//! This module is behind the `{{ feature | kebab_case }}` Cargo feature.
+//! With it, [`RecorderName`] counts events; without it, a stub of the same
+//! name and API records nothing and compiles to nothing. Callers use it
+//! unconditionally and need no `#[cfg]` of their own, and [`ENABLED`] says
+//! which one they got.
+//!
+//! Add the feature to `Cargo.toml`, off by default, and declare the module
+//! in `lib.rs` without a `#[cfg]`, since the stub stands in for it:
+//!
+//! ```toml
+//! [features]
+//! {{ feature | kebab_case }} = []
+//! ```
+//!
+//! A feature that pulls in an optional dependency lists it, as in
+//! `{{ feature | kebab_case }} = ["dep:some-crate"]`.
+//!
+//! Both builds must keep the same public API — the same items, signatures
+//! and derives — or code that compiles with the feature breaks without it.
+//! Test both, as CI should:
+//!
+//! ```sh
+//! cargo test
+//! cargo test --features {{ feature | kebab_case }}
+//! ```
+
+pub use imp::RecorderName;
+
+/// Whether the `{{ feature | kebab_case }}` feature is enabled, and
+/// [`RecorderName`] really records.
+pub const ENABLED: bool = cfg!(feature = "{{ feature | kebab_case }}");
+
+#[cfg(feature = "{{ feature | kebab_case }}")]
+mod imp {
+    use std::collections::BTreeMap;
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+
+    /// Counts of named events, shareable between threads.
+    #[derive(Debug, Default)]
+    pub struct RecorderName {
+        counts: Mutex<BTreeMap<&'static str, u64>>,
+    }
+
+    impl RecorderName {
+        /// A recorder with nothing counted yet.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Counts one occurrence of `event`.
+        pub fn record(&self, event: &'static str) {
+            *self.lock().entry(event).or_default() += 1;
+        }
+
+        /// How many times `event` was recorded.
+        pub fn count(&self, event: &str) -> u64 {
+            self.lock().get(event).copied().unwrap_or(0)
+        }
+
+        /// Every event recorded, with its count, by name.
+        pub fn snapshot(&self) -> Vec<(&'static str, u64)> {
+            self.lock().clone().into_iter().collect()
+        }
+
+        fn lock(&self) -> MutexGuard<'_, BTreeMap<&'static str, u64>> {
+            self.counts.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+    }
+}
+
+#[cfg(not(feature = "{{ feature | kebab_case }}"))]
+mod imp {
+    /// Stand-in for the recorder without the `{{ feature | kebab_case }}`
+    /// feature: it has no state and records nothing.
+    #[derive(Debug, Default)]
+    pub struct RecorderName;
+
+    impl RecorderName {
+        /// A recorder that records nothing.
+        pub fn new() -> Self {
+            Self
+        }
+
+        /// Does nothing.
+        #[inline]
+        pub fn record(&self, _event: &'static str) {}
+
+        /// Always zero.
+        #[inline]
+        pub fn count(&self, _event: &str) -> u64 {
+            0
+        }
+
+        /// Always empty.
+        pub fn snapshot(&self) -> Vec<(&'static str, u64)> {
+            Vec::new()
+        }
+    }
+}
+
+{{> partials/test-header}}
+    // Tests without a `#[cfg]` hold in both builds; the others check what
+    // only one of them does. Run both: `cargo test`, then `cargo test
+    // --features {{ feature | kebab_case }}`.
+
+    #[test]
+    fn test_same_calls_compile_either_way() {
+        let recorder = RecorderName::new();
+        recorder.record("start");
+        recorder.record("start");
+        let expected = if ENABLED { 2 } else { 0 };
+        assert_eq!(recorder.count("start"), expected);
+        assert_eq!(recorder.snapshot().is_empty(), !ENABLED);
+    }
+
+    #[cfg(feature = "{{ feature | kebab_case }}")]
+    #[test]
+    fn test_counts_events_by_name() {
+        let recorder = RecorderName::new();
+        for event in ["b", "a", "b"] {
+            recorder.record(event);
+        }
+        assert_eq!(recorder.snapshot(), [("a", 1), ("b", 2)]);
+        assert_eq!(recorder.count("c"), 0);
+    }
+
+    #[cfg(feature = "{{ feature | kebab_case }}")]
+    #[test]
+    fn test_shared_between_threads() {
+        let recorder = RecorderName::new();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        recorder.record("tick");
+                    }
+                });
+            }
+        });
+        assert_eq!(recorder.count("tick"), 400);
+    }
+
+    #[cfg(not(feature = "{{ feature | kebab_case }}"))]
+    #[test]
+    fn test_stub_has_no_state() {
+        assert_eq!(std::mem::size_of::<RecorderName>(), 0);
+        let recorder = RecorderName::new();
+        recorder.record("start");
+        assert_eq!(recorder.snapshot(), []);
+    }
+}
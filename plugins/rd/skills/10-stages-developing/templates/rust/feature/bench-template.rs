@@ -0,0 +1,20 @@
+//! Benchmarks for `{{ crate_name }}::{{ module }}`. Needs a `[[bench]]`
+//! target with `harness = false` and `criterion` as a dev-dependency.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use {{ crate_name }}::{{ module }}::{{ type_name }};
+
+fn bench_push(c: &mut Criterion) {
+    c.bench_function("{{ module }}_push", |b| {
+        b.iter(|| {
+            let mut {{ module }} = {{ type_name }}::new();
+            for item in ["a", "b", "c"] {
+                {{ module }}.push(item).unwrap();
+            }
+            {{ module }}
+        })
+    });
+}
+
+criterion_group!(benches, bench_push);
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+//! Implementation of [`{{ type_name }}`].
+
+/// {{ description }}
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct {{ type_name }} {
+    items: Vec<String>,
+}
+
+impl {{ type_name }} {
+    /// Creates an empty `{{ type_name }}`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an item, rejecting empty ones.
+    pub fn push(&mut self, item: &str) -> Result<(), String> {
+        if item.is_empty() {
+            return Err("item cannot be empty".to_string());
+        }
+        self.items.push(item.to_string());
+        Ok(())
+    }
+
+    /// Number of items added so far.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether no items were added.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_rejects_empty_items() {
+        let mut {{ module }} = {{ type_name }}::new();
+        assert!({{ module }}.push("").is_err());
+        assert!({{ module }}.is_empty());
+    }
+}
@@ -0,0 +1,5 @@
+//! {{ description }}
+
+mod imp;
+
+pub use imp::{{ type_name }};
@@ -0,0 +1,11 @@
+//! Integration tests for `{{ crate_name }}::{{ module }}`.
+
+use {{ crate_name }}::{{ module }}::{{ type_name }};
+
+#[test]
+fn test_{{ module }}_collects_items() {
+    let mut {{ module }} = {{ type_name }}::new();
+    {{ module }}.push("a").unwrap();
+    {{ module }}.push("b").unwrap();
+    assert_eq!({{ module }}.len(), 2);
+}
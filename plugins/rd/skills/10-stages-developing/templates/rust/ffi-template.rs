@@ -0,0 +1,148 @@
+//! [Brief description of what this module does]
+//!
+//! `function_name` is exposed to C as `ffi_prefix_function_name`. The
+//! exported function returns a [`FfiStatus`] and writes its result through
+//! an out-pointer, so every failure — a null pointer, text that is not
+//! UTF-8, an error or a panic — reaches the caller as a code rather than
+//! undefined behaviour.
+//!
+//! Generate the header with `cbindgen --config cbindgen.toml --output
+//! ffi_prefix.h`, given a `cbindgen.toml` like:
+//!
+//! ```toml
+//! language = "C"
+//! include_guard = "{{ prefix | shouty }}_H"
+//! cpp_compat = true
+//!
+//! [enum]
+//! prefix_with_name = true
+//! rename_variants = "ScreamingSnakeCase"
+//! ```
+//!
+//! and build the crate as a `cdylib` or `staticlib`.
+
+use std::ffi::{c_char, CStr};
+use std::panic;
+
+/// Status codes returned across the C ABI; `0` is success.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiStatus {
+    /// The result was written.
+    Ok = 0,
+    /// A pointer argument was null.
+    NullPointer = 1,
+    /// The input was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// The function returned an error.
+    Failed = 3,
+    /// The function panicked.
+    Panicked = 4,
+}
+
+/// [Brief description of what the function does]
+///
+/// # Errors
+///
+/// Returns an error message if `input` is empty.
+pub fn function_name(input: &str) -> Result<u64, String> {
+    if input.is_empty() {
+        return Err("input cannot be empty".to_string());
+    }
+    Ok(input.split_whitespace().count() as u64)
+}
+
+/// C entry point for [`function_name`]: reads the NUL-terminated string
+/// `input` and writes the result to `out`, which is left untouched unless
+/// [`FfiStatus::Ok`] is returned.
+///
+/// # Safety
+///
+/// `input` must be null or point to a NUL-terminated string that stays
+/// valid for the duration of the call, and `out` must be null or valid for
+/// writing a `u64`.
+#[no_mangle]
+pub unsafe extern "C" fn ffi_prefix_function_name(input: *const c_char, out: *mut u64) -> FfiStatus {
+    if input.is_null() || out.is_null() {
+        return FfiStatus::NullPointer;
+    }
+    // SAFETY: `input` is not null, and the caller guarantees it is
+    // NUL-terminated and valid.
+    let Ok(input) = unsafe { CStr::from_ptr(input) }.to_str() else {
+        return FfiStatus::InvalidUtf8;
+    };
+    // Unwinding out of an `extern "C"` function aborts the process.
+    match panic::catch_unwind(|| function_name(input)) {
+        Ok(Ok(value)) => {
+            // SAFETY: `out` is not null, and the caller guarantees it is
+            // valid for writes.
+            unsafe { out.write(value) };
+            FfiStatus::Ok
+        }
+        Ok(Err(_)) => FfiStatus::Failed,
+        Err(_) => FfiStatus::Panicked,
+    }
+}
+
+{{> partials/test-header}}
+    use std::ffi::CString;
+    use std::ptr;
+
+    // Declared the way C sees it, so the tests go through the exported
+    // symbol rather than the Rust function.
+    extern "C" {
+        #[link_name = "ffi_prefix_function_name"]
+        fn exported(input: *const c_char, out: *mut u64) -> FfiStatus;
+    }
+
+    fn call(input: &[u8]) -> (FfiStatus, u64) {
+        let input = CString::new(input).unwrap();
+        let mut out = u64::MAX;
+        // SAFETY: both pointers come from live locals.
+        let status = unsafe { exported(input.as_ptr(), &mut out) };
+        (status, out)
+    }
+
+    #[test]
+    fn test_function_name_success() {
+        assert_eq!(function_name("one two three"), Ok(3));
+    }
+
+    #[test]
+    fn test_exported_writes_result() {
+        assert_eq!(call(b"one two three"), (FfiStatus::Ok, 3));
+    }
+
+    #[test]
+    fn test_exported_rejects_null_pointers() {
+        let input = CString::new("text").unwrap();
+        let mut out = 0;
+        // SAFETY: null is allowed for either argument.
+        unsafe {
+            assert_eq!(exported(ptr::null(), &mut out), FfiStatus::NullPointer);
+            assert_eq!(
+                exported(input.as_ptr(), ptr::null_mut()),
+                FfiStatus::NullPointer
+            );
+        }
+    }
+
+    #[test]
+    fn test_exported_rejects_invalid_utf8() {
+        assert_eq!(call(b"\xff\xfe"), (FfiStatus::InvalidUtf8, u64::MAX));
+    }
+
+    #[test]
+    fn test_exported_reports_errors() {
+        assert_eq!(call(b""), (FfiStatus::Failed, u64::MAX));
+    }
+
+    #[test]
+    fn test_status_codes_are_stable() {
+        assert_eq!(FfiStatus::Ok as i32, 0);
+        assert_eq!(FfiStatus::NullPointer as i32, 1);
+        assert_eq!(FfiStatus::InvalidUtf8 as i32, 2);
+        assert_eq!(FfiStatus::Failed as i32, 3);
+        assert_eq!(FfiStatus::Panicked as i32, 4);
+    }
+}
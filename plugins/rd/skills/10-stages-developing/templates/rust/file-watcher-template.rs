@@ -0,0 +1,327 @@
+//! [Brief description of what this module does]
+//!
+//! [`WatcherName`] watches a directory tree with the `notify` crate and
+//! reports each change to a file as one [`Change`], once the file has been
+//! quiet for the debounce window. Saving a file makes the operating system
+//! report several raw events — create, a few writes, perhaps a rename — and
+//! acting on each would repeat work on a half-written file.
+//!
+//! Raw events are mapped to [`ChangeKind`]s in [`changes_in`], the place to
+//! drop paths the program does not care about. [`Debouncer`] then merges
+//! the changes to each path: a file created and written is
+//! [`ChangeKind::Added`], one removed and recreated — as editors that save
+//! atomically do — is [`ChangeKind::Modified`], and one created and removed
+//! within the window is not reported at all. The debouncer takes the time
+//! as an argument, so its rules are tested without touching the disk.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+/// How long a file must be quiet before its change is reported.
+pub const DEBOUNCE: Duration = Duration::from_millis(DEBOUNCE_MS);
+
+/// What happened to a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// The file is new.
+    Added,
+    /// The file's content or metadata changed, or it was replaced.
+    Modified,
+    /// The file is gone.
+    Removed,
+}
+
+/// A debounced change to one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// The file that changed.
+    pub path: PathBuf,
+    /// What happened to it.
+    pub kind: ChangeKind,
+}
+
+/// The changes a raw `notify` event describes.
+pub fn changes_in(event: &Event) -> Vec<(PathBuf, ChangeKind)> {
+    let kind = match event.kind {
+        EventKind::Create(_) => ChangeKind::Added,
+        EventKind::Remove(_) => ChangeKind::Removed,
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => ChangeKind::Removed,
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => ChangeKind::Added,
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            // `paths` holds the old name, then the new one.
+            let kinds = [ChangeKind::Removed, ChangeKind::Added];
+            return event.paths.iter().cloned().zip(kinds).collect();
+        }
+        EventKind::Modify(_) => ChangeKind::Modified,
+        EventKind::Access(_) | EventKind::Any | EventKind::Other => return Vec::new(),
+    };
+    let paths = event.paths.iter().cloned();
+    paths.map(|path| (path, kind)).collect()
+}
+
+/// Merges the changes to each path until the path has been quiet for the
+/// window.
+#[derive(Debug)]
+pub struct Debouncer {
+    window: Duration,
+    pending: HashMap<PathBuf, (ChangeKind, Instant)>,
+}
+
+impl Debouncer {
+    /// A debouncer reporting a path once it has been quiet for `window`.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Records that `kind` happened to `path` at `now`.
+    pub fn push(&mut self, path: PathBuf, kind: ChangeKind, now: Instant) {
+        let Some(&(earlier, _)) = self.pending.get(&path) else {
+            self.pending.insert(path, (kind, now));
+            return;
+        };
+        use ChangeKind::{Added, Modified, Removed};
+        let merged = match (earlier, kind) {
+            // Never seen by anyone else, so nothing to report.
+            (Added, Removed) => None,
+            (Added, _) => Some(Added),
+            (Removed, Removed) => Some(Removed),
+            (Removed, _) => Some(Modified),
+            (Modified, Removed) => Some(Removed),
+            (Modified, _) => Some(Modified),
+        };
+        match merged {
+            Some(kind) => self.pending.insert(path, (kind, now)),
+            None => self.pending.remove(&path),
+        };
+    }
+
+    /// When the next pending change will be ready, if there is one.
+    pub fn next_ready(&self) -> Option<Instant> {
+        let last = self.pending.values().map(|&(_, at)| at).min()?;
+        Some(last + self.window)
+    }
+
+    /// Takes the changes to paths quiet since `now - window`, by path.
+    pub fn take_ready(&mut self, now: Instant) -> Vec<Change> {
+        let window = self.window;
+        let mut ready = Vec::new();
+        self.pending.retain(|path, &mut (kind, at)| {
+            let quiet = now.saturating_duration_since(at) >= window;
+            if quiet {
+                let path = path.clone();
+                ready.push(Change { path, kind });
+            }
+            !quiet
+        });
+        ready.sort_by(|a, b| a.path.cmp(&b.path));
+        ready
+    }
+}
+
+/// Debounced changes to the files under a directory.
+#[derive(Debug)]
+pub struct WatcherName {
+    // Dropping the watcher ends the debouncing thread.
+    _watcher: RecommendedWatcher,
+    changes: Receiver<notify::Result<Change>>,
+}
+
+impl WatcherName {
+    /// Watches everything under `root`, reporting a file's change once it
+    /// has been quiet for `debounce`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` cannot be watched.
+    pub fn new(root: &Path, debounce: Duration) -> notify::Result<Self> {
+        let (raw_sender, raw) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // Fails only once the debouncing thread is gone.
+            let _ = raw_sender.send(event);
+        })?;
+        watcher.watch(root, RecursiveMode::Recursive)?;
+        let (sender, changes) = mpsc::channel();
+        thread::spawn(move || debounce_events(&raw, &sender, debounce));
+        Ok(Self {
+            _watcher: watcher,
+            changes,
+        })
+    }
+
+    /// The next change, waiting up to `timeout` for it, or `None` if there
+    /// was none by then.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<notify::Result<Change>> {
+        self.changes.recv_timeout(timeout).ok()
+    }
+
+    /// The changes as they come, blocking for each.
+    pub fn iter(&self) -> impl Iterator<Item = notify::Result<Change>> + '_ {
+        self.changes.iter()
+    }
+}
+
+type RawEvents = Receiver<notify::Result<Event>>;
+type Changes = mpsc::Sender<notify::Result<Change>>;
+
+/// Feeds raw events through a [`Debouncer`] until either channel closes.
+fn debounce_events(raw: &RawEvents, changes: &Changes, window: Duration) {
+    let mut debouncer = Debouncer::new(window);
+    loop {
+        let received = match debouncer.next_ready() {
+            Some(ready) => raw.recv_timeout(ready.saturating_duration_since(Instant::now())),
+            None => raw.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(Ok(event)) => {
+                let now = Instant::now();
+                for (path, kind) in changes_in(&event) {
+                    debouncer.push(path, kind, now);
+                }
+            }
+            Ok(Err(err)) => {
+                if changes.send(Err(err)).is_err() {
+                    return;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        for change in debouncer.take_ready(Instant::now()) {
+            if changes.send(Ok(change)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+{{> partials/test-header}}
+    use std::fs;
+
+    use notify::event::{AccessKind, CreateKind, DataChange, RemoveKind};
+
+    const WINDOW: Duration = Duration::from_millis(100);
+    /// Generous, for slow CI machines; tests finish as soon as events come.
+    const WAIT: Duration = Duration::from_secs(5);
+
+    fn event(kind: EventKind, paths: &[&str]) -> Event {
+        let add = |event: Event, path: &&str| event.add_path(path.into());
+        paths.iter().fold(Event::new(kind), add)
+    }
+
+    fn change(path: &str, kind: ChangeKind) -> Change {
+        let path = path.into();
+        Change { path, kind }
+    }
+
+    #[test]
+    fn test_maps_raw_events() {
+        use ChangeKind::{Added, Modified, Removed};
+        let kinds = |kind, paths: &[&str]| -> Vec<ChangeKind> {
+            let changes = changes_in(&event(kind, paths));
+            changes.into_iter().map(|(_, kind)| kind).collect()
+        };
+        assert_eq!(kinds(EventKind::Create(CreateKind::File), &["a"]), [Added]);
+        let written = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        assert_eq!(kinds(written, &["a"]), [Modified]);
+        let removed = EventKind::Remove(RemoveKind::File);
+        assert_eq!(kinds(removed, &["a"]), [Removed]);
+        let opened = EventKind::Access(AccessKind::Any);
+        assert!(kinds(opened, &["a"]).is_empty());
+
+        let renamed = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+        let changes = changes_in(&event(renamed, &["a", "b"]));
+        let expected = [("a".into(), Removed), ("b".into(), Added)];
+        assert_eq!(changes, expected);
+    }
+
+    #[test]
+    fn test_debouncer_merges_changes_per_path() {
+        use ChangeKind::{Added, Modified, Removed};
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(WINDOW);
+        let ms = |ms| start + Duration::from_millis(ms);
+        debouncer.push("new".into(), Added, ms(0));
+        debouncer.push("new".into(), Modified, ms(10));
+        debouncer.push("saved".into(), Removed, ms(0));
+        debouncer.push("saved".into(), Added, ms(20));
+        debouncer.push("temp".into(), Added, ms(0));
+        debouncer.push("temp".into(), Removed, ms(30));
+        debouncer.push("old".into(), Modified, ms(0));
+        debouncer.push("old".into(), Removed, ms(40));
+
+        // Quiet for the window only since the last change to each path.
+        assert_eq!(debouncer.next_ready(), Some(ms(110)));
+        assert!(debouncer.take_ready(ms(109)).is_empty());
+        let ready = [change("new", Added), change("saved", Modified)];
+        assert_eq!(debouncer.take_ready(ms(130)), ready);
+        assert_eq!(debouncer.take_ready(ms(140)), [change("old", Removed)]);
+        assert_eq!(debouncer.next_ready(), None);
+    }
+
+    #[test]
+    fn test_debouncer_restarts_window_on_each_change() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(WINDOW);
+        let ms = |ms| start + Duration::from_millis(ms);
+        for at in [ms(0), ms(60), ms(120)] {
+            debouncer.push("busy".into(), ChangeKind::Modified, at);
+            assert!(debouncer.take_ready(at).is_empty());
+        }
+        assert!(debouncer.take_ready(ms(200)).is_empty());
+        let ready = debouncer.take_ready(ms(220));
+        assert_eq!(ready, [change("busy", ChangeKind::Modified)]);
+    }
+
+    fn next_change(watcher: &WatcherName) -> Change {
+        watcher.recv_timeout(WAIT).expect("a change").unwrap()
+    }
+
+    #[test]
+    fn test_reports_file_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let watcher = WatcherName::new(&root, WINDOW).unwrap();
+        let file = root.join("notes.txt");
+
+        // Created and written many times, reported once.
+        for line in 0..5 {
+            fs::write(&file, format!("line {line}\n")).unwrap();
+        }
+        let kind_of = |change: Change| {
+            assert_eq!(change.path, file);
+            change.kind
+        };
+        assert_eq!(kind_of(next_change(&watcher)), ChangeKind::Added);
+
+        fs::write(&file, "edited\n").unwrap();
+        assert_eq!(kind_of(next_change(&watcher)), ChangeKind::Modified);
+
+        fs::remove_file(&file).unwrap();
+        assert_eq!(kind_of(next_change(&watcher)), ChangeKind::Removed);
+        assert!(watcher.recv_timeout(WINDOW * 3).is_none());
+    }
+
+    #[test]
+    fn test_short_lived_file_is_not_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let watcher = WatcherName::new(&root, WINDOW).unwrap();
+        let scratch = root.join("scratch.tmp");
+        fs::write(&scratch, "x").unwrap();
+        fs::remove_file(&scratch).unwrap();
+        fs::write(root.join("kept.txt"), "y").unwrap();
+
+        let change = next_change(&watcher);
+        assert_eq!(change.path, root.join("kept.txt"));
+        assert!(watcher.recv_timeout(WINDOW * 3).is_none());
+    }
+}
@@ -1,7 +1,5 @@
 // [Brief description of what this module does]
 
-use std::collections::HashMap;
-
 /// Result of function_name operation
 #[derive(Debug, PartialEq)]
 pub struct FunctionResult {
@@ -23,12 +21,24 @@ pub struct FunctionResult {
 ///
 /// # Examples
 ///
-/// ```
-/// let result = function_name("example", 42)?;
+/// ```ignore
+/// // The function's path depends on the module it is rendered into.
+/// let result = function_name("example", 42){{#block await}}{{#if async}}.await{{/if}}{{/block}}?;
 /// assert_eq!(result.key1, "processed_example");
 /// assert_eq!(result.key2, 84);
 /// ```
-pub fn function_name(param1: &str, param2: i32) -> Result<FunctionResult, String> {
+{{#if async}}
+///
+/// # Cancellation safety
+///
+/// The function holds nothing across an `.await`, so dropping its future
+/// before it completes loses no work. Keep it that way, or say here what
+/// is lost, when adding awaits between partial updates.
+{{/if}}
+{{#if tracing}}
+#[tracing::instrument(level = "debug")]
+{{/if}}
+{{#block signature}}pub {{#if async}}async {{/if}}fn function_name(param1: &str, param2: i32){{/block}} -> Result<FunctionResult, String> {
     // Input validation
     if param1.is_empty() {
         return Err("param1 cannot be empty".to_string());
@@ -46,67 +56,106 @@ pub fn function_name(param1: &str, param2: i32) -> Result<FunctionResult, String
 
     Ok(result)
 }
+{{#if async}}
+
+/// Runs [`function_name`], giving up once `limit` has elapsed.
+///
+/// # Errors
+///
+/// Returns the error `function_name` returns, or a message saying that it
+/// timed out.
+pub async fn function_name_with_timeout(
+    param1: &str,
+    param2: i32,
+    limit: std::time::Duration,
+) -> Result<FunctionResult, String> {
+    tokio::time::timeout(limit, function_name(param1, param2))
+        .await
+        .map_err(|_| format!("function_name timed out after {limit:?}"))?
+}
+{{/if}}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+{{> partials/test-header}}
 
-    #[test]
-    fn test_function_name_smoke() {
-        let result = function_name("test", 10);
+    {{#block test_fn}}{{#if async}}#[tokio::test]
+    async fn{{else}}#[test]
+    fn{{/if}}{{/block}} test_function_name_smoke() {
+        let result = function_name("test", 10){{#block await}}{{#if async}}.await{{/if}}{{/block}};
         assert!(result.is_ok());
         let result = result.unwrap();
         assert!(!result.key1.is_empty());
         assert_ne!(result.key2, 0);
     }
 
-    #[test]
-    fn test_function_name_valid_input() {
-        let result = function_name("hello", 42).unwrap();
+    {{#block test_fn}}{{#if async}}#[tokio::test]
+    async fn{{else}}#[test]
+    fn{{/if}}{{/block}} test_function_name_valid_input() {
+        let result = function_name("hello", 42){{#block await}}{{#if async}}.await{{/if}}{{/block}}.unwrap();
 
         assert_eq!(result.key1, "processed_hello");
         assert_eq!(result.key2, 84);
     }
 
-    #[test]
-    fn test_function_name_empty_string() {
-        let result = function_name("", 10);
+    {{#block test_fn}}{{#if async}}#[tokio::test]
+    async fn{{else}}#[test]
+    fn{{/if}}{{/block}} test_function_name_empty_string() {
+        let result = function_name("", 10){{#block await}}{{#if async}}.await{{/if}}{{/block}};
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("empty"));
     }
 
-    #[test]
-    fn test_function_name_negative_number() {
-        let result = function_name("test", -1);
+    {{#block test_fn}}{{#if async}}#[tokio::test]
+    async fn{{else}}#[test]
+    fn{{/if}}{{/block}} test_function_name_negative_number() {
+        let result = function_name("test", -1){{#block await}}{{#if async}}.await{{/if}}{{/block}};
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("non-negative"));
     }
 
-    #[test]
-    fn test_function_name_zero() {
-        let result = function_name("test", 0).unwrap();
+    {{#block test_fn}}{{#if async}}#[tokio::test]
+    async fn{{else}}#[test]
+    fn{{/if}}{{/block}} test_function_name_zero() {
+        let result = function_name("test", 0){{#block await}}{{#if async}}.await{{/if}}{{/block}}.unwrap();
         assert_eq!(result.key2, 0);
     }
 
-    #[test]
-    fn test_function_name_large_number() {
-        let result = function_name("test", 1000000).unwrap();
+    {{#block test_fn}}{{#if async}}#[tokio::test]
+    async fn{{else}}#[test]
+    fn{{/if}}{{/block}} test_function_name_large_number() {
+        let result = function_name("test", 1000000){{#block await}}{{#if async}}.await{{/if}}{{/block}}.unwrap();
         assert_eq!(result.key2, 2000000);
     }
+{{#if async}}
+
+    #[tokio::test]
+    async fn test_function_name_with_timeout() {
+        let limit = std::time::Duration::from_secs(5);
+        let result = function_name_with_timeout("test", 10, limit).await.unwrap();
+        assert_eq!(result.key2, 20);
+
+        let result = function_name_with_timeout("", 10, limit).await;
+        assert!(result.unwrap_err().contains("empty"));
+    }
+{{/if}}
 
     // Parametrized tests using test vectors
-    #[test]
-    fn test_function_name_parametrized() {
+    {{#block test_fn}}{{#if async}}#[tokio::test]
+    async fn{{else}}#[test]
+    fn{{/if}}{{/block}} test_function_name_parametrized() {
         let test_cases = vec![
+{{#each test_vectors}}
+            {{ this }},
+{{else}}
             ("hello", 10, "processed_hello", 20, false),
             ("world", 5, "processed_world", 10, false),
             ("test", 0, "processed_test", 0, false),
             ("", 10, "", 0, true),
             ("test", -1, "", 0, true),
+{{/each}}
         ];
 
         for (input_str, input_num, expected_key1, expected_key2, expect_error) in test_cases {
-            let result = function_name(input_str, input_num);
+            let result = function_name(input_str, input_num){{#block await}}{{#if async}}.await{{/if}}{{/block}};
 
             if expect_error {
                 assert!(result.is_err(), "Expected error for input: {}, {}", input_str, input_num);
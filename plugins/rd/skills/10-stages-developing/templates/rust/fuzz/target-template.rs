@@ -0,0 +1,33 @@
+//! Fuzz target for `{{ path }}`.
+//!
+//! libFuzzer calls it with arbitrary input, starting from the seeds in
+//! `corpus/{{ target }}/`, until it panics, overflows or runs out of time
+//! or memory. Run it from the crate root with
+//! `cargo +nightly fuzz run {{ target }}`; an input that fails is saved in
+//! `artifacts/{{ target }}/` and replayed by passing it to the same command.
+
+#![no_main]
+
+{{#if bytes}}
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    {{#if returns}}let _ = {{/if}}{{ call }};
+});
+{{else}}
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+/// The arguments of one call, built by `arbitrary` from the fuzzer's bytes.
+#[derive(Debug, Arbitrary)]
+struct Input {
+{{#each fields}}
+    {{ name }}: {{ ty }},
+{{/each}}
+}
+
+fuzz_target!(|input: Input| {
+    let Input { {{ bindings }} } = input;
+    {{#if returns}}let _ = {{/if}}{{ call }};
+});
+{{/if}}
@@ -0,0 +1,126 @@
+//! Golden-file testing: output compared with expected files checked in
+//! under `tests/fixtures/`.
+//!
+//! A test renders each `<case>.input` of a fixtures directory and hands the
+//! output to [`check`], which compares it with the `<case>.expected` beside
+//! it and describes a mismatch with a line [`diff`]. Run the tests with
+//! `UPDATE_GOLDEN=1` to write the output as the expected files instead,
+//! then review the change with `git diff` before committing it.
+//!
+//! Shared by the `tests/*_golden.rs` files, which declare it with
+//! `mod golden;`.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Set to `1` to regenerate the expected files from the current output.
+pub const UPDATE_ENV: &str = "UPDATE_GOLDEN";
+
+/// Unchanged lines shown around each change in a diff.
+const CONTEXT: usize = 2;
+
+/// The fixtures directory `tests/fixtures/<name>` of the crate under test.
+pub fn fixtures_dir(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+/// The `.input` files in `dir`, in name order.
+pub fn inputs(dir: &Path) -> Vec<PathBuf> {
+    let entries =
+        fs::read_dir(dir).unwrap_or_else(|err| panic!("cannot read {}: {err}", dir.display()));
+    let mut inputs: Vec<PathBuf> = entries
+        .map(|entry| entry.expect("cannot read fixtures directory").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "input"))
+        .collect();
+    inputs.sort();
+    inputs
+}
+
+/// Whether the expected files are being regenerated.
+pub fn updating() -> bool {
+    std::env::var_os(UPDATE_ENV).is_some_and(|value| value == "1")
+}
+
+/// Checks that `actual` is the contents of the file `expected`, or, when
+/// [`updating`], writes it there. A mismatch or a missing file is
+/// described for the test to report.
+pub fn check(expected: &Path, actual: &str) -> Result<(), String> {
+    if updating() {
+        return fs::write(expected, actual)
+            .map_err(|err| format!("cannot write {}: {err}", expected.display()));
+    }
+    let Ok(golden) = fs::read_to_string(expected) else {
+        return Err(format!(
+            "{} is missing; run the tests with {UPDATE_ENV}=1 to create it",
+            expected.display()
+        ));
+    };
+    if golden == actual {
+        return Ok(());
+    }
+    Err(format!(
+        "{} does not match the output (-expected +actual):\n{}\
+         run the tests with {UPDATE_ENV}=1 to accept the output",
+        expected.display(),
+        diff(&golden, actual)
+    ))
+}
+
+/// A line diff from `expected` to `actual`: removed lines start with `-`,
+/// added ones with `+` and the unchanged lines around them with a space,
+/// and each run of changes follows an `@@ line N @@` header numbering the
+/// expected lines.
+pub fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+    // common[i][j]: length of the longest common subsequence of
+    // old[i..] and new[j..].
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    // (marker, expected line index, text) for every line of either side.
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', i, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', i, old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', i, new[j]));
+            j += 1;
+        }
+    }
+
+    let mut out = String::new();
+    let mut previous = None;
+    for (index, &(marker, line, text)) in lines.iter().enumerate() {
+        let window = index.saturating_sub(CONTEXT)..(index + CONTEXT + 1).min(lines.len());
+        if lines[window].iter().all(|&(marker, ..)| marker == ' ') {
+            continue;
+        }
+        if previous.is_none_or(|previous| previous + 1 != index) {
+            let _ = writeln!(out, "@@ line {} @@", line + 1);
+        }
+        let _ = writeln!(out, "{marker}{text}");
+        previous = Some(index);
+    }
+    if out.is_empty() && expected != actual {
+        out.push_str("(the lines are equal; the line endings or final newline differ)\n");
+    }
+    out
+}
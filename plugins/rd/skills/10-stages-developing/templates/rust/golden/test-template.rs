@@ -0,0 +1,94 @@
+//! Golden-file tests for `feature_name`: [Brief description of the output under test]
+//!
+//! Each `tests/fixtures/feature_name/<case>.input` is rendered and compared
+//! with the `<case>.expected` beside it, and every case that differs is
+//! reported with a diff. To add a case, add its input and run
+//! `UPDATE_GOLDEN=1 cargo test --test feature_name_golden`, then review the
+//! expected file it writes.
+
+mod golden;
+
+use std::fs;
+
+/// The output under test. Replace the body with the call into `crate_name`
+/// it stands for, e.g. `crate_name::feature_name::format(input)`; this one
+/// trims trailing whitespace and collapses runs of blank lines.
+fn render(input: &str) -> String {
+    let mut output = String::new();
+    let mut blank = false;
+    for line in input.lines().map(str::trim_end) {
+        if line.is_empty() {
+            blank = !output.is_empty();
+            continue;
+        }
+        if blank {
+            output.push('\n');
+            blank = false;
+        }
+        output.push_str(line);
+        output.push('\n');
+    }
+    output
+}
+
+#[test]
+fn test_feature_name_matches_golden_files() {
+    let inputs = golden::inputs(&golden::fixtures_dir("feature_name"));
+    assert!(!inputs.is_empty(), "no .input files to check");
+
+    let failures: Vec<String> = inputs
+        .iter()
+        .filter_map(|input| {
+            let source = fs::read_to_string(input).expect("cannot read input");
+            golden::check(&input.with_extension("expected"), &render(&source)).err()
+        })
+        .collect();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn test_diff_shows_changes_with_context() {
+    let diff = golden::diff("a\nb\nc\nd\ne\nf\n", "a\nb\nC\nd\ne\nf\n");
+
+    assert_eq!(diff, "@@ line 1 @@\n a\n b\n-c\n+C\n d\n e\n");
+}
+
+#[test]
+fn test_diff_separates_distant_changes() {
+    let diff = golden::diff("1\n2\n3\n4\n5\n6\n7\n8\n", "0\n2\n3\n4\n5\n6\n7\n9\n");
+
+    assert_eq!(
+        diff,
+        "@@ line 1 @@\n-1\n+0\n 2\n 3\n@@ line 6 @@\n 6\n 7\n-8\n+9\n"
+    );
+}
+
+#[test]
+fn test_diff_notes_a_missing_final_newline() {
+    let diff = golden::diff("done\n", "done");
+
+    assert!(diff.contains("final newline"), "{diff}");
+}
+
+#[test]
+fn test_check_reports_mismatch_with_update_hint() {
+    if golden::updating() {
+        return;
+    }
+    let expected = std::env::temp_dir().join(format!(
+        "feature_name-golden-{}.expected",
+        std::process::id()
+    ));
+    fs::write(&expected, "old\n").expect("cannot write expected file");
+
+    let result = golden::check(&expected, "new\n");
+    let _ = fs::remove_file(&expected);
+
+    let message = result.unwrap_err();
+    assert!(message.contains("-old\n+new\n"), "{message}");
+    assert!(message.contains("UPDATE_GOLDEN=1"), "{message}");
+    assert!(golden::check(&expected, "new\n")
+        .unwrap_err()
+        .contains("is missing"));
+}
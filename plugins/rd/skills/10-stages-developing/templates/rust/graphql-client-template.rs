@@ -0,0 +1,429 @@
+//! [Brief description of what this module does]
+//!
+//! [`OperationName`] is one GraphQL operation, typed: its query document,
+//! the [`OperationNameVariables`] it takes and the [`OperationNameData`] it
+//! returns, all three kept together by the [`Operation`] trait. Add another
+//! operation the same way, and keep each document's selection in step with
+//! the fields of its data type.
+//!
+//! [`GraphqlClient`] posts operations through a [`Transport`] and tells
+//! apart the ways one can fail: the request never got an answer, the
+//! answer was not a GraphQL response, or it was one that reports `errors`.
+//!
+//! ```ignore
+//! // Sends the operation to a live GraphQL endpoint.
+//! let transport = HttpTransport::new("https://api.example.com/graphql")?;
+//! let client = GraphqlClient::new(transport);
+//! let variables = OperationNameVariables { id: "42".to_string() };
+//! let item = client.execute::<OperationName>(&variables).await?.item;
+//! ```
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::Future;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A GraphQL operation: its document, and the types of its variables and
+/// of the `data` it returns.
+pub trait Operation {
+    /// The operation's name in [`QUERY`](Self::QUERY).
+    const NAME: &'static str;
+    /// The GraphQL document.
+    const QUERY: &'static str;
+    /// The operation's variables, serialized to the request's `variables`.
+    type Variables: Serialize;
+    /// The `data` of a response.
+    type Data: DeserializeOwned;
+}
+
+/// Marker type for the `OperationName` query.
+#[derive(Debug, Clone, Copy)]
+pub struct OperationName;
+
+impl Operation for OperationName {
+    const NAME: &'static str = "OperationName";
+    const QUERY: &'static str = r"
+query OperationName($id: ID!) {
+  item(id: $id) {
+    id
+    name
+    tags
+  }
+}
+";
+    type Variables = OperationNameVariables;
+    type Data = OperationNameData;
+}
+
+/// Variables of `OperationName`, named as in its document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationNameVariables {
+    pub id: String,
+}
+
+/// What `OperationName` returns, shaped like its selection.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationNameData {
+    /// `null` when there is no item with the id.
+    pub item: Option<Item>,
+}
+
+/// An item as `OperationName` selects it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Item {
+    pub id: String,
+    pub name: String,
+    pub tags: Vec<String>,
+}
+
+/// An entry of a response's `errors`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GraphqlError {
+    pub message: String,
+    /// Where in `data` the error happened, if in a field.
+    #[serde(default)]
+    pub path: Vec<PathSegment>,
+    /// Details the server adds, such as a `code`.
+    #[serde(default)]
+    pub extensions: Map<String, Value>,
+}
+
+impl GraphqlError {
+    /// The `extensions.code` the server gave, e.g. `NOT_FOUND`.
+    pub fn code(&self) -> Option<&str> {
+        self.extensions.get("code").and_then(Value::as_str)
+    }
+}
+
+/// A step of a [`GraphqlError::path`]: a field name or a list index.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum PathSegment {
+    Field(String),
+    Index(usize),
+}
+
+/// Why an operation failed.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The request could not be sent or its response read.
+    Transport(TransportError),
+    /// The server answered with an error status and no GraphQL response.
+    Status { status: u16, body: String },
+    /// The response was not a GraphQL response with this operation's data.
+    Decode(serde_json::Error),
+    /// The server reported errors, and any `data` was dropped.
+    Graphql(Vec<GraphqlError>),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(err) => write!(f, "request failed: {err}"),
+            Self::Status { status, body } => write!(f, "server returned {status}: {body}"),
+            Self::Decode(err) => write!(f, "invalid response: {err}"),
+            Self::Graphql(errors) => {
+                let messages: Vec<_> = errors.iter().map(|e| e.message.as_str()).collect();
+                write!(f, "operation failed: {}", messages.join("; "))
+            }
+        }
+    }
+}
+
+impl StdError for ClientError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Transport(err) => Some(err.as_ref()),
+            Self::Decode(err) => Some(err),
+            Self::Status { .. } | Self::Graphql(_) => None,
+        }
+    }
+}
+
+/// Why a [`Transport`] got no response.
+pub type TransportError = Box<dyn StdError + Send + Sync>;
+
+/// Sends a JSON request body to the GraphQL endpoint, returning the
+/// response's status and body whatever the status.
+pub trait Transport {
+    fn post(
+        &self,
+        body: Vec<u8>,
+    ) -> impl Future<Output = Result<(u16, Vec<u8>), TransportError>> + Send;
+}
+
+/// A [`Transport`] over HTTP with reqwest.
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    http: reqwest::Client,
+    endpoint: String,
+}
+
+impl HttpTransport {
+    /// A transport posting to `endpoint`, with a 30 second timeout.
+    ///
+    /// # Errors
+    ///
+    /// [`reqwest::Error`] if the HTTP client cannot be set up.
+    pub fn new(endpoint: impl Into<String>) -> reqwest::Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()?;
+        Ok(Self::with_client(http, endpoint))
+    }
+
+    /// A transport posting to `endpoint` with `http`, e.g. one that sets
+    /// authentication headers.
+    pub fn with_client(http: reqwest::Client, endpoint: impl Into<String>) -> Self {
+        Self {
+            http,
+            endpoint: endpoint.into(),
+        }
+    }
+}
+
+impl Transport for HttpTransport {
+    async fn post(&self, body: Vec<u8>) -> Result<(u16, Vec<u8>), TransportError> {
+        let response = self
+            .http
+            .post(&self.endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await?;
+        let status = response.status().as_u16();
+        Ok((status, response.bytes().await?.to_vec()))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Request<'a, V> {
+    query: &'static str,
+    operation_name: &'static str,
+    variables: &'a V,
+}
+
+#[derive(Deserialize)]
+struct Response<D> {
+    data: Option<D>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+/// Client for one GraphQL endpoint.
+#[derive(Debug, Clone)]
+pub struct GraphqlClient<T> {
+    transport: T,
+}
+
+impl<T: Transport> GraphqlClient<T> {
+    /// A client sending operations through `transport`.
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    /// Runs `O` with `variables` and returns its data.
+    ///
+    /// # Errors
+    ///
+    /// [`ClientError::Graphql`] if the response reports any errors, even
+    /// with partial data; [`execute_partial`](Self::execute_partial) keeps
+    /// that data. The other [`ClientError`]s if there is no GraphQL
+    /// response to read.
+    pub async fn execute<O: Operation>(
+        &self,
+        variables: &O::Variables,
+    ) -> Result<O::Data, ClientError> {
+        match self.execute_partial::<O>(variables).await? {
+            (Some(data), errors) if errors.is_empty() => Ok(data),
+            (None, errors) if errors.is_empty() => Err(ClientError::Decode(no_data())),
+            (_, errors) => Err(ClientError::Graphql(errors)),
+        }
+    }
+
+    /// Runs `O` with `variables` and returns whatever data and errors the
+    /// response holds: with a GraphQL server, a field that fails is `null`
+    /// in `data` and explained in `errors`, and the rest is still there.
+    ///
+    /// # Errors
+    ///
+    /// [`ClientError::Transport`], [`ClientError::Status`] and
+    /// [`ClientError::Decode`] if there is no GraphQL response to read.
+    pub async fn execute_partial<O: Operation>(
+        &self,
+        variables: &O::Variables,
+    ) -> Result<(Option<O::Data>, Vec<GraphqlError>), ClientError> {
+        let request = Request {
+            query: O::QUERY,
+            operation_name: O::NAME,
+            variables,
+        };
+        let body = serde_json::to_vec(&request).map_err(ClientError::Decode)?;
+        let response = self.transport.post(body).await;
+        let (status, body) = response.map_err(ClientError::Transport)?;
+        match serde_json::from_slice::<Response<O::Data>>(&body) {
+            Ok(response) => Ok((response.data, response.errors)),
+            Err(_) if !(200..300).contains(&status) => Err(ClientError::Status {
+                status,
+                body: String::from_utf8_lossy(&body).into_owned(),
+            }),
+            Err(err) => Err(ClientError::Decode(err)),
+        }
+    }
+}
+
+/// The error for a response with neither `data` nor `errors`.
+fn no_data() -> serde_json::Error {
+    serde::de::Error::custom("response has neither data nor errors")
+}
+
+{{> partials/test-header}}
+    use std::sync::Mutex;
+
+    use serde_json::json;
+
+    /// A transport answering every request with `reply`, keeping the
+    /// requests it was sent.
+    struct MockTransport {
+        reply: Result<(u16, String), String>,
+        sent: Mutex<Vec<Value>>,
+    }
+
+    impl Transport for MockTransport {
+        async fn post(&self, body: Vec<u8>) -> Result<(u16, Vec<u8>), TransportError> {
+            let request = serde_json::from_slice(&body).unwrap();
+            self.sent.lock().unwrap().push(request);
+            match &self.reply {
+                Ok((status, body)) => Ok((*status, body.clone().into_bytes())),
+                Err(message) => Err(message.clone().into()),
+            }
+        }
+    }
+
+    type MockClient = GraphqlClient<MockTransport>;
+
+    fn client(status: u16, body: Value) -> MockClient {
+        GraphqlClient::new(MockTransport {
+            reply: Ok((status, body.to_string())),
+            sent: Mutex::default(),
+        })
+    }
+
+    fn variables() -> OperationNameVariables {
+        OperationNameVariables {
+            id: "42".to_string(),
+        }
+    }
+
+    async fn execute(client: &MockClient) -> Result<OperationNameData, ClientError> {
+        client.execute::<OperationName>(&variables()).await
+    }
+
+    fn item() -> Value {
+        json!({ "id": "42", "name": "widget", "tags": ["new"] })
+    }
+
+    #[tokio::test]
+    async fn test_sends_document_name_and_variables() {
+        let client = client(200, json!({ "data": { "item": null } }));
+        execute(&client).await.unwrap();
+
+        let sent = client.transport.sent.lock().unwrap();
+        assert_eq!(sent[0]["operationName"], "OperationName");
+        assert_eq!(sent[0]["query"], OperationName::QUERY);
+        assert_eq!(sent[0]["variables"], json!({ "id": "42" }));
+        assert!(OperationName::QUERY.contains("query OperationName("));
+    }
+
+    #[tokio::test]
+    async fn test_decodes_data() {
+        let found = client(200, json!({ "data": { "item": item() } }));
+        let item = execute(&found).await.unwrap().item.unwrap();
+        assert_eq!(item.name, "widget");
+        assert_eq!(item.tags, ["new"]);
+
+        let missing = client(200, json!({ "data": { "item": null } }));
+        assert_eq!(execute(&missing).await.unwrap().item, None);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_extracted() {
+        let body = json!({
+            "data": null,
+            "errors": [{
+                "message": "not allowed",
+                "path": ["item", 0, "name"],
+                "extensions": { "code": "FORBIDDEN" },
+            }],
+        });
+        let err = execute(&client(200, body)).await.unwrap_err();
+
+        let ClientError::Graphql(errors) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(errors[0].message, "not allowed");
+        assert_eq!(errors[0].code(), Some("FORBIDDEN"));
+        let path = [
+            PathSegment::Field("item".to_string()),
+            PathSegment::Index(0),
+            PathSegment::Field("name".to_string()),
+        ];
+        assert_eq!(errors[0].path, path);
+    }
+
+    #[tokio::test]
+    async fn test_partial_data_is_kept_by_execute_partial() {
+        let body = json!({
+            "data": { "item": item() },
+            "errors": [{ "message": "tags are stale" }],
+        });
+        let client = client(200, body);
+        let result = execute(&client).await;
+        assert!(matches!(result, Err(ClientError::Graphql(_))));
+
+        let (data, errors) = client
+            .execute_partial::<OperationName>(&variables())
+            .await
+            .unwrap();
+        assert_eq!(data.unwrap().item.unwrap().id, "42");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_graphql_errors_with_error_status() {
+        let body = json!({ "errors": [{ "message": "syntax error" }] });
+        let err = execute(&client(400, body)).await.unwrap_err();
+        assert_eq!(err.to_string(), "operation failed: syntax error");
+    }
+
+    #[tokio::test]
+    async fn test_responses_that_are_not_graphql() {
+        let bad_gateway = client(502, json!("bad gateway"));
+        let err = execute(&bad_gateway).await.unwrap_err();
+        assert!(matches!(err, ClientError::Status { status: 502, .. }));
+
+        let wrong_shape = json!({ "data": { "item": { "id": 42 } } });
+        let err = execute(&client(200, wrong_shape)).await.unwrap_err();
+        assert!(matches!(err, ClientError::Decode(_)));
+
+        let err = execute(&client(200, json!({}))).await.unwrap_err();
+        assert!(matches!(err, ClientError::Decode(_)));
+    }
+
+    #[tokio::test]
+    async fn test_transport_failure() {
+        let client = GraphqlClient::new(MockTransport {
+            reply: Err("connection refused".to_string()),
+            sent: Mutex::default(),
+        });
+        let err = execute(&client).await.unwrap_err();
+        assert_eq!(err.to_string(), "request failed: connection refused");
+    }
+}
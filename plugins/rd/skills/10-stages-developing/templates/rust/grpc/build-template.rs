@@ -0,0 +1,16 @@
+//! Generates the gRPC server and client code for `proto/{{ module }}.proto`.
+//!
+//! `protoc` is taken from `PROTOC` if it is set, and otherwise from the
+//! `protoc-bin-vendored` build dependency, which can be dropped where
+//! `protoc` is installed.
+
+use std::env;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    if env::var_os("PROTOC").is_none() {
+        env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+    tonic_build::compile_protos("proto/{{ module }}.proto")?;
+    Ok(())
+}
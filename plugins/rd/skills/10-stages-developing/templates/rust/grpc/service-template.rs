@@ -0,0 +1,54 @@
+//! The `ServiceName` gRPC service of `proto/{{ module }}.proto`.
+//!
+//! [`proto`] holds the code `build.rs` generates: the messages, the
+//! `ServiceName` server trait and a client. [`ServiceNameService`]
+//! implements the trait, and [`server`] wraps it for
+//! `tonic::transport::Server::add_service`.
+
+use tonic::{Request, Response, Status};
+
+/// Code generated from `proto/{{ module }}.proto`.
+#[allow(clippy::all, missing_docs)]
+pub mod proto {
+    tonic::include_proto!("proto_package");
+}
+
+use proto::{{ service | snake_case }}_server::{ServiceName, ServiceNameServer};
+use proto::{MethodNameReply, MethodNameRequest};
+
+/// Implementation of the `ServiceName` service.
+#[derive(Debug, Default)]
+pub struct ServiceNameService;
+
+#[tonic::async_trait]
+impl ServiceName for ServiceNameService {
+    async fn {{ rpc | snake_case }}(
+        &self,
+        request: Request<MethodNameRequest>,
+    ) -> Result<Response<MethodNameReply>, Status> {
+        let name = request.into_inner().name;
+        if name.trim().is_empty() {
+            return Err(Status::invalid_argument("name must not be empty"));
+        }
+        Ok(Response::new(MethodNameReply {
+            message: format!("Hello, {name}!"),
+        }))
+    }
+}
+
+/// The service, ready to add to a `tonic::transport::Server`.
+pub fn server() -> ServiceNameServer<ServiceNameService> {
+    ServiceNameServer::new(ServiceNameService)
+}
+
+{{> partials/test-header}}
+
+    #[tokio::test]
+    async fn test_{{ rpc | snake_case }}_in_process() {
+        let request = Request::new(MethodNameRequest {
+            name: "Ada".to_string(),
+        });
+        let reply = ServiceNameService.{{ rpc | snake_case }}(request).await.unwrap();
+        assert_eq!(reply.into_inner().message, "Hello, Ada!");
+    }
+}
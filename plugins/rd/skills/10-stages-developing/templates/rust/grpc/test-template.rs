@@ -0,0 +1,48 @@
+//! `ServiceName` served over a local TCP port and called through the
+//! generated client, as a remote caller would.
+
+use std::net::SocketAddr;
+
+use crate_name::{{ module }}::proto::{{ service | snake_case }}_client::ServiceNameClient;
+use crate_name::{{ module }}::proto::MethodNameRequest;
+use crate_name::{{ module }}::server;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::{Channel, Server};
+use tonic::Code;
+
+/// Serves the service on a free port in the background and returns a
+/// client connected to it.
+async fn connect() -> ServiceNameClient<Channel> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(server())
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    ServiceNameClient::connect(format!("http://{addr}"))
+        .await
+        .unwrap()
+}
+
+fn request(name: &str) -> MethodNameRequest {
+    MethodNameRequest {
+        name: name.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_{{ rpc | snake_case }}() {
+    let mut client = connect().await;
+    let reply = client.{{ rpc | snake_case }}(request("Ada")).await.unwrap();
+    assert_eq!(reply.into_inner().message, "Hello, Ada!");
+}
+
+#[tokio::test]
+async fn test_{{ rpc | snake_case }}_rejects_empty_name() {
+    let mut client = connect().await;
+    let status = client.{{ rpc | snake_case }}(request(" ")).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(status.message(), "name must not be empty");
+}
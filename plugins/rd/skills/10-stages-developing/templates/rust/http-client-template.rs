@@ -0,0 +1,275 @@
+//! [Brief description of what this module does]
+//!
+//! [`ApiClient`] sends typed requests and decodes typed responses. Requests
+//! that fail with a 5xx status, a timeout or a connection error are retried
+//! with exponential backoff, up to [`ClientConfig::max_retries`] times;
+//! other failures are returned at once.
+
+use std::fmt;
+use std::time::Duration;
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// How an [`ApiClient`] connects and retries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientConfig {
+    /// Base URL, without a trailing slash, e.g. `https://api.example.com`.
+    pub base_url: String,
+    /// Limit on each attempt, from connecting to reading the body.
+    pub timeout: Duration,
+    /// Attempts after the first one, for retryable failures.
+    pub max_retries: u32,
+    /// Wait before the first retry; doubled before each one after it.
+    pub backoff: Duration,
+}
+
+impl ClientConfig {
+    /// Defaults for `base_url`: 10s timeout and 3 retries from 100ms.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            timeout: Duration::from_secs(10),
+            max_retries: 3,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Body of a request to create an item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CreateItem {
+    pub name: String,
+    pub quantity: u32,
+}
+
+/// An item as the service returns it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Item {
+    pub id: u64,
+    pub name: String,
+    pub quantity: u32,
+}
+
+/// Why a request failed.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The request could not be sent or its response read or decoded.
+    Http(reqwest::Error),
+    /// The service answered with an error status.
+    Status { status: StatusCode, body: String },
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http(err) => write!(f, "request failed: {err}"),
+            Self::Status { status, body } => write!(f, "service returned {status}: {body}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(err) => Some(err),
+            Self::Status { .. } => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Http(err)
+    }
+}
+
+/// Client for the service at [`ClientConfig::base_url`].
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    config: ClientConfig,
+}
+
+impl ApiClient {
+    /// Creates a client with `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClientError::Http`] if the HTTP client cannot be set up.
+    pub fn new(config: ClientConfig) -> Result<Self, ClientError> {
+        let http = reqwest::Client::builder().timeout(config.timeout).build()?;
+        Ok(Self { http, config })
+    }
+
+    /// Fetches the item `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`ClientError`] of the last attempt.
+    pub async fn get_item(&self, id: u64) -> Result<Item, ClientError> {
+        let url = format!("{}/items/{id}", self.config.base_url);
+        self.json(|| self.http.get(&url)).await
+    }
+
+    /// Creates an item from `request`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`ClientError`] of the last attempt.
+    pub async fn create_item(&self, request: &CreateItem) -> Result<Item, ClientError> {
+        let url = format!("{}/items", self.config.base_url);
+        self.json(|| self.http.post(&url).json(request)).await
+    }
+
+    /// Sends the request `build` makes, retrying as the module describes,
+    /// and decodes a successful response as JSON.
+    async fn json<T: DeserializeOwned>(
+        &self,
+        build: impl Fn() -> RequestBuilder,
+    ) -> Result<T, ClientError> {
+        let mut backoff = self.config.backoff;
+        let mut retries = 0;
+        loop {
+            let result = match build().send().await {
+                Ok(response) => check(response).await,
+                Err(err) => Err(ClientError::Http(err)),
+            };
+            match result {
+                Err(err) if retries < self.config.max_retries && is_retryable(&err) => {
+                    retries += 1;
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Ok(response) => return Ok(response.json().await?),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// `response` if its status is a success, else the error it stands for.
+async fn check(response: Response) -> Result<Response, ClientError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(ClientError::Status { status, body })
+}
+
+fn is_retryable(err: &ClientError) -> bool {
+    match err {
+        ClientError::Http(err) => err.is_timeout() || err.is_connect(),
+        ClientError::Status { status, .. } => status.is_server_error(),
+    }
+}
+
+{{> partials/test-header}}
+    use serde_json::{json, Value};
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn item() -> Value {
+        json!({ "id": 7, "name": "bolt", "quantity": 3 })
+    }
+
+    fn client(server: &MockServer) -> ApiClient {
+        let mut config = ClientConfig::new(server.uri());
+        config.timeout = Duration::from_millis(200);
+        config.max_retries = 2;
+        config.backoff = Duration::from_millis(1);
+        ApiClient::new(config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_item() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/items/7"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(item()))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let found = client(&server).get_item(7).await.unwrap();
+        assert_eq!(found.name, "bolt");
+    }
+
+    #[tokio::test]
+    async fn test_create_item_sends_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/items"))
+            .and(body_json(json!({ "name": "bolt", "quantity": 3 })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(item()))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let request = CreateItem {
+            name: "bolt".to_string(),
+            quantity: 3,
+        };
+        assert_eq!(client(&server).create_item(&request).await.unwrap().id, 7);
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(item()))
+            .expect(1)
+            .mount(&server)
+            .await;
+        assert_eq!(client(&server).get_item(7).await.unwrap().id, 7);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("down"))
+            .expect(3)
+            .mount(&server)
+            .await;
+        let err = client(&server).get_item(7).await.unwrap_err();
+        assert!(
+            matches!(&err, ClientError::Status { status, body }
+                if *status == StatusCode::INTERNAL_SERVER_ERROR && body == "down"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let err = client(&server).get_item(7).await.unwrap_err();
+        assert!(
+            matches!(err, ClientError::Status { status, .. } if status == StatusCode::NOT_FOUND)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_times_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+            .mount(&server)
+            .await;
+        let err = client(&server).get_item(7).await.unwrap_err();
+        assert!(
+            matches!(&err, ClientError::Http(err) if err.is_timeout()),
+            "{err}"
+        );
+    }
+}
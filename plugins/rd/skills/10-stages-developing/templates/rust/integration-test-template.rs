@@ -0,0 +1,103 @@
+//! Integration tests for `feature_name`: [Brief description of the behaviour under test]
+//!
+//! Each test arranges its inputs in a fresh [`Fixture`] directory, acts
+//! through the public API of `crate_name`, and asserts on the outcome.
+//! The directory is removed when the fixture is dropped, even if the test
+//! fails.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A scratch directory for one test.
+struct Fixture {
+    dir: PathBuf,
+}
+
+impl Fixture {
+    /// Setup: creates an empty directory no other test uses.
+    fn setup() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "feature_name-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).expect("cannot create fixture directory");
+        Self { dir }
+    }
+
+    /// Path of `name` in the fixture directory.
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Writes `contents` to `name` in the fixture and returns its path.
+    fn write(&self, name: &str, contents: &str) -> PathBuf {
+        let path = self.path(name);
+        fs::write(&path, contents).expect("cannot write fixture file");
+        path
+    }
+}
+
+impl Drop for Fixture {
+    /// Teardown: removes the directory and everything in it.
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// The behaviour under test. Replace the body with the call into
+/// `crate_name` it stands for, e.g. `crate_name::feature_name::load(input)`.
+fn act(input: &Path) -> Result<String, String> {
+    fs::read_to_string(input).map_err(|err| err.to_string())
+}
+
+#[test]
+fn test_feature_name_reads_input() {
+    // Arrange
+    let fixture = Fixture::setup();
+    let input = fixture.write("input.txt", "hello\n");
+
+    // Act
+    let result = act(&input);
+
+    // Assert
+    assert_eq!(result.unwrap(), "hello\n");
+}
+
+#[test]
+fn test_feature_name_empty_input() {
+    // Arrange
+    let fixture = Fixture::setup();
+    let input = fixture.write("empty.txt", "");
+
+    // Act
+    let result = act(&input);
+
+    // Assert
+    assert_eq!(result.unwrap(), "");
+}
+
+#[test]
+fn test_feature_name_missing_input() {
+    // Arrange
+    let fixture = Fixture::setup();
+
+    // Act
+    let result = act(&fixture.path("missing.txt"));
+
+    // Assert
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_fixture_teardown_removes_directory() {
+    let fixture = Fixture::setup();
+    let dir = fixture.dir.clone();
+    assert!(dir.is_dir());
+
+    drop(fixture);
+
+    assert!(!dir.exists());
+}
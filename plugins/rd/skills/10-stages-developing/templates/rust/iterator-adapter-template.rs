@@ -0,0 +1,201 @@
+//! [Brief description of what this module does]
+//!
+//! [`AdapterName`] wraps an iterator and yields its first item and then
+//! every `step`-th one after it. [`AdapterNameExt`] adds it to every
+//! iterator as `.method_name(step)`, the way `std` provides its own
+//! combinators, and the tests hold it to the same laws as the equivalent
+//! `std` chain, [`Iterator::step_by`].
+{{#if double_ended}}
+//!
+//! It iterates from the back too when the inner iterator knows its length,
+//! since which item comes last depends on how many there are.
+{{/if}}
+
+use std::iter::FusedIterator;
+
+/// Yields the first item of `iter`, then every `step`-th after it.
+///
+/// Made by [`AdapterNameExt::method_name`].
+#[derive(Debug, Clone)]
+#[must_use = "iterator adapters are lazy and do nothing unless consumed"]
+pub struct AdapterName<I> {
+    iter: I,
+    /// Items to skip between two that are yielded.
+    skip: usize,
+    /// Whether the next item from the front is the first one.
+    first: bool,
+}
+
+impl<I> AdapterName<I> {
+    fn new(iter: I, step: usize) -> Self {
+        assert!(step != 0, "step must be non-zero");
+        Self {
+            iter,
+            skip: step - 1,
+            first: true,
+        }
+    }
+
+    /// How many of `len` remaining inner items would be yielded.
+    fn yielded(&self, len: usize) -> usize {
+        let step = self.skip + 1;
+        match (self.first, len) {
+            (true, 0) => 0,
+            (true, len) => 1 + (len - 1) / step,
+            (false, len) => len / step,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for AdapterName<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.first {
+            self.first = false;
+            return self.iter.next();
+        }
+        self.iter.nth(self.skip)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        (self.yielded(lower), upper.map(|upper| self.yielded(upper)))
+    }
+}
+{{#if double_ended}}
+
+impl<I> DoubleEndedIterator for AdapterName<I>
+where
+    I: DoubleEndedIterator + ExactSizeIterator,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let len = self.iter.len();
+        if len == 0 {
+            return None;
+        }
+        // Drop the items past the last one the front would reach.
+        let step = self.skip + 1;
+        let past_last = if self.first {
+            (len - 1) % step
+        } else {
+            len % step
+        };
+        self.iter.nth_back(past_last)
+    }
+}
+{{/if}}
+
+impl<I: ExactSizeIterator> ExactSizeIterator for AdapterName<I> {}
+
+impl<I: FusedIterator> FusedIterator for AdapterName<I> {}
+
+/// Adds the [`AdapterName`] combinator to every iterator.
+pub trait AdapterNameExt: Iterator + Sized {
+    /// Yields the first item, then every `step`-th after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is 0.
+    fn method_name(self, step: usize) -> AdapterName<Self> {
+        AdapterName::new(self, step)
+    }
+}
+
+impl<I: Iterator> AdapterNameExt for I {}
+
+{{> partials/test-header}}
+    use std::ops::Range;
+
+    fn adapter(len: usize, step: usize) -> AdapterName<Range<usize>> {
+        (0..len).method_name(step)
+    }
+
+    /// Each law is checked for every input length and step in these ranges.
+    fn cases() -> impl Iterator<Item = (usize, usize)> {
+        (0..20).flat_map(|len| (1..6).map(move |step| (len, step)))
+    }
+
+    #[test]
+    fn test_matches_std_step_by() {
+        for (len, step) in cases() {
+            let ours: Vec<usize> = adapter(len, step).collect();
+            let std: Vec<usize> = (0..len).step_by(step).collect();
+            assert_eq!(ours, std, "len {len}, step {step}");
+        }
+    }
+
+    #[test]
+    fn test_size_hint_is_exact() {
+        for (len, step) in cases() {
+            let mut iter = adapter(len, step);
+            let mut remaining = (0..len).step_by(step).count();
+            loop {
+                assert_eq!(iter.size_hint(), (remaining, Some(remaining)));
+                assert_eq!(iter.len(), remaining);
+                if iter.next().is_none() {
+                    break;
+                }
+                remaining -= 1;
+            }
+            assert_eq!(remaining, 0, "len {len}, step {step}");
+        }
+    }
+
+    #[test]
+    fn test_unbounded_size_hint() {
+        let evens = (0..).filter(|n| n % 2 == 0);
+        let iter = evens.method_name(3);
+        assert_eq!(iter.size_hint(), (0, None));
+    }
+
+    #[test]
+    fn test_is_lazy_on_infinite_input() {
+        let iter = (0_u64..).method_name(3);
+        assert_eq!(iter.take(4).collect::<Vec<_>>(), [0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn test_step_of_one_is_identity() {
+        let items = ["a", "b", "c"];
+        let iter = items.into_iter().method_name(1);
+        assert!(iter.eq(items));
+    }
+{{#if double_ended}}
+
+    #[test]
+    fn test_rev_matches_reversed_forward() {
+        for (len, step) in cases() {
+            let ours: Vec<usize> = adapter(len, step).rev().collect();
+            let mut forward: Vec<usize> = adapter(len, step).collect();
+            forward.reverse();
+            assert_eq!(ours, forward, "len {len}, step {step}");
+            let std: Vec<usize> = (0..len).step_by(step).rev().collect();
+            assert_eq!(ours, std, "len {len}, step {step}");
+        }
+    }
+
+    #[test]
+    fn test_mixed_ends_match_std() {
+        for (len, step) in cases() {
+            let mut ours = adapter(len, step);
+            let mut std = (0..len).step_by(step);
+            // Alternate between the ends, starting at the front.
+            for call in 0..len + 1 {
+                let (a, b) = if call.is_multiple_of(2) {
+                    (ours.next(), std.next())
+                } else {
+                    (ours.next_back(), std.next_back())
+                };
+                assert_eq!(a, b, "len {len}, step {step}, call {call}");
+            }
+        }
+    }
+{{/if}}
+
+    #[test]
+    #[should_panic(expected = "step must be non-zero")]
+    fn test_zero_step_panics() {
+        let _ = (0..3).method_name(0);
+    }
+}
@@ -0,0 +1,744 @@
+//! [Brief description of what this module does]
+//!
+//! [`ConsumerName::run`] reads records from a [`Source`], decodes each
+//! payload as JSON into an [`EventName`] and passes it to a handler, until
+//! it is told to shut down. Delivery is at least once:
+//!
+//! * A record's offset is committed only once the record was handled or,
+//!   if its payload does not decode, sent to the [`DeadLetters`]. Records
+//!   not committed are read again after a restart or a rebalance, so the
+//!   handler must tolerate seeing one twice.
+//! * Commits are batched: every [`CommitPolicy::every`] records, every
+//!   [`CommitPolicy::interval`], and, waiting for the broker, on shutdown.
+//! * Before a rebalance takes partitions away, [`KafkaSource`] commits
+//!   what was handled on them, so their next owner resumes after it.
+//! * A handler error stops the loop, after committing the records before
+//!   the failed one; that one is read again when the consumer restarts.
+//!
+//! Shutdown is checked between records, so the record in hand is finished
+//! first. The loop sees only the [`Source`] and [`DeadLetters`] traits:
+//! [`KafkaSource`] and [`KafkaDeadLetters`] implement them with rdkafka, and
+//! the tests with in-memory mocks.
+//!
+//! ```ignore
+//! // Needs a running Kafka broker.
+//! let source = KafkaSource::new("localhost:9092", "billing", &["orders"])?;
+//! let dead_letters = KafkaDeadLetters::new("localhost:9092", "orders.dlq")?;
+//! let mut consumer = ConsumerName::new(source, dead_letters, CommitPolicy::default());
+//! let stats = consumer.run(handle, shutdown.cancelled()).await?;
+//! ```
+
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::Future;
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{
+    BaseConsumer, CommitMode, Consumer, ConsumerContext, Rebalance, StreamConsumer,
+};
+use rdkafka::error::{KafkaError, KafkaResult};
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::{ClientContext, Message, Offset, TopicPartitionList};
+use serde::Deserialize;
+use tokio::time::{self, MissedTickBehavior};
+
+/// An event as it is published, in JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct EventName {
+    pub id: u64,
+    pub action: String,
+}
+
+/// A record as read from Kafka, copied out of the consumer's buffers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub key: Option<Vec<u8>>,
+    pub payload: Option<Vec<u8>>,
+}
+
+/// A partition of a topic, as `(topic, partition)`.
+pub type Partition = (String, i32);
+
+/// Offsets done with but not committed yet: for each partition, the offset
+/// of the next record to read there, which is what Kafka commits.
+#[derive(Debug, Default)]
+pub struct PendingOffsets {
+    next: BTreeMap<Partition, i64>,
+    records: u64,
+}
+
+impl PendingOffsets {
+    /// Marks `record`, and every record before it on its partition, done.
+    pub fn done(&mut self, record: &Record) {
+        let partition = (record.topic.clone(), record.partition);
+        self.next.insert(partition, record.offset + 1);
+        self.records += 1;
+    }
+
+    /// How many records were marked done since the last [`take`](Self::take).
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// Every pending offset, leaving none.
+    pub fn take(&mut self) -> BTreeMap<Partition, i64> {
+        self.records = 0;
+        mem::take(&mut self.next)
+    }
+
+    /// The pending offsets on the `revoked` partitions, leaving the others.
+    pub fn take_revoked(&mut self, revoked: &[Partition]) -> BTreeMap<Partition, i64> {
+        revoked
+            .iter()
+            .filter_map(|partition| self.next.remove_entry(partition))
+            .collect()
+    }
+}
+
+fn lock(pending: &Mutex<PendingOffsets>) -> MutexGuard<'_, PendingOffsets> {
+    pending.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Where a [`ConsumerName`] reads records and commits their offsets.
+pub trait Source {
+    /// The next record; `None` once there will be no more, which never
+    /// happens on a Kafka subscription.
+    fn recv(&mut self) -> impl Future<Output = Result<Option<Record>, KafkaError>> + Send;
+
+    /// Offsets done with and not committed yet, shared with whatever
+    /// commits them when a rebalance revokes their partitions.
+    fn pending(&self) -> &Mutex<PendingOffsets>;
+
+    /// Commits `offsets`; with [`CommitMode::Sync`], waits until the broker
+    /// has them.
+    fn commit(&mut self, offsets: &BTreeMap<Partition, i64>, mode: CommitMode) -> KafkaResult<()>;
+}
+
+/// Where a [`ConsumerName`] sends records whose payload does not decode.
+pub trait DeadLetters {
+    /// Publishes `record` with the `reason` it was rejected.
+    fn send(&self, record: &Record, reason: &str) -> impl Future<Output = KafkaResult<()>> + Send;
+}
+
+/// When a [`ConsumerName`] commits what it has done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitPolicy {
+    /// Commit once this many records were done since the last commit.
+    pub every: u64,
+    /// Commit this often, if anything was done since the last commit.
+    pub interval: Duration,
+}
+
+impl Default for CommitPolicy {
+    /// Every 100 records or 5 seconds.
+    fn default() -> Self {
+        Self {
+            every: 100,
+            interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Why [`ConsumerName::run`] stopped early.
+#[derive(Debug)]
+pub enum ConsumerError {
+    /// Reading, committing or dead-lettering failed.
+    Kafka(KafkaError),
+    /// The handler failed on the record at `offset` on `partition`.
+    Handler {
+        partition: Partition,
+        offset: i64,
+        source: Box<dyn StdError + Send + Sync>,
+    },
+}
+
+impl fmt::Display for ConsumerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Kafka(err) => write!(f, "kafka error: {err}"),
+            Self::Handler {
+                partition: (topic, partition),
+                offset,
+                source,
+            } => write!(
+                f,
+                "handler failed on {topic}/{partition} at {offset}: {source}"
+            ),
+        }
+    }
+}
+
+impl StdError for ConsumerError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Kafka(err) => Some(err),
+            Self::Handler { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
+
+impl From<KafkaError> for ConsumerError {
+    fn from(err: KafkaError) -> Self {
+        Self::Kafka(err)
+    }
+}
+
+/// What a [`ConsumerName`] has done with the records it read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Records passed to the handler, which accepted them.
+    pub handled: u64,
+    /// Records sent to the dead letters.
+    pub dead_lettered: u64,
+}
+
+/// A consumer loop over a [`Source`], with undecodable records sent to
+/// [`DeadLetters`].
+pub struct ConsumerName<S, D> {
+    source: S,
+    dead_letters: D,
+    policy: CommitPolicy,
+    stats: Stats,
+}
+
+impl<S: Source, D: DeadLetters> ConsumerName<S, D> {
+    /// A consumer reading `source`, committing as `policy` says.
+    pub fn new(source: S, dead_letters: D, policy: CommitPolicy) -> Self {
+        Self {
+            source,
+            dead_letters,
+            policy,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Passes each record's event to `handle` until `shutdown` completes or
+    /// the source ends, then commits what was done and returns the totals.
+    ///
+    /// # Errors
+    ///
+    /// [`ConsumerError::Handler`] if `handle` fails, and
+    /// [`ConsumerError::Kafka`] if reading, dead-lettering or committing
+    /// does. What was done before the error is committed all the same.
+    pub async fn run<H, Fut, E>(
+        &mut self,
+        mut handle: H,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<Stats, ConsumerError>
+    where
+        H: FnMut(EventName) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        let consumed = self.consume(&mut handle, shutdown).await;
+        let committed = self.commit(CommitMode::Sync);
+        consumed?;
+        committed?;
+        Ok(self.stats)
+    }
+
+    async fn consume<H, Fut, E>(
+        &mut self,
+        handle: &mut H,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<(), ConsumerError>
+    where
+        H: FnMut(EventName) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        let mut ticks = time::interval(self.policy.interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // An interval's first tick is immediate, and there is nothing to
+        // commit yet.
+        ticks.tick().await;
+        tokio::pin!(shutdown);
+        loop {
+            // In this order: shutdown first, then a due commit, then a record.
+            tokio::select! {
+                biased;
+                () = &mut shutdown => return Ok(()),
+                _ = ticks.tick() => self.commit(CommitMode::Async)?,
+                record = self.source.recv() => match record? {
+                    Some(record) => self.process(record, handle).await?,
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+
+    async fn process<H, Fut, E>(
+        &mut self,
+        record: Record,
+        handle: &mut H,
+    ) -> Result<(), ConsumerError>
+    where
+        H: FnMut(EventName) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        match decode(&record) {
+            Ok(event) => {
+                handle(event).await.map_err(|err| ConsumerError::Handler {
+                    partition: (record.topic.clone(), record.partition),
+                    offset: record.offset,
+                    source: err.into(),
+                })?;
+                self.stats.handled += 1;
+            }
+            Err(reason) => {
+                self.dead_letters.send(&record, &reason).await?;
+                tracing::warn!(
+                    topic = record.topic,
+                    partition = record.partition,
+                    offset = record.offset,
+                    reason,
+                    "sent undecodable record to the dead letters"
+                );
+                self.stats.dead_lettered += 1;
+            }
+        }
+        let due = {
+            let mut pending = lock(self.source.pending());
+            pending.done(&record);
+            pending.records() >= self.policy.every
+        };
+        if due {
+            self.commit(CommitMode::Async)?;
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self, mode: CommitMode) -> Result<(), ConsumerError> {
+        let offsets = lock(self.source.pending()).take();
+        if offsets.is_empty() {
+            return Ok(());
+        }
+        self.source.commit(&offsets, mode)?;
+        Ok(())
+    }
+}
+
+/// The event in `record`'s payload, or why there is none.
+fn decode(record: &Record) -> Result<EventName, String> {
+    let payload = record.payload.as_deref().ok_or("record has no payload")?;
+    serde_json::from_slice(payload).map_err(|err| err.to_string())
+}
+
+/// `offsets` as the list rdkafka commits.
+fn partition_list(offsets: &BTreeMap<Partition, i64>) -> KafkaResult<TopicPartitionList> {
+    let mut list = TopicPartitionList::with_capacity(offsets.len());
+    for ((topic, partition), offset) in offsets {
+        list.add_partition_offset(topic, *partition, Offset::Offset(*offset))?;
+    }
+    Ok(list)
+}
+
+/// Commits what was done on partitions a rebalance is about to revoke.
+struct RebalanceContext {
+    pending: Arc<Mutex<PendingOffsets>>,
+}
+
+impl ClientContext for RebalanceContext {}
+
+impl ConsumerContext for RebalanceContext {
+    fn pre_rebalance(&self, consumer: &BaseConsumer<Self>, rebalance: &Rebalance<'_>) {
+        let Rebalance::Revoke(list) = rebalance else {
+            return;
+        };
+        let revoked: Vec<Partition> = list
+            .elements()
+            .iter()
+            .map(|element| (element.topic().to_string(), element.partition()))
+            .collect();
+        let offsets = lock(&self.pending).take_revoked(&revoked);
+        if offsets.is_empty() {
+            return;
+        }
+        let committed =
+            partition_list(&offsets).and_then(|list| consumer.commit(&list, CommitMode::Sync));
+        if let Err(err) = committed {
+            // There is no caller to return this to; the next owner of the
+            // partitions reads these records again.
+            tracing::warn!(%err, "could not commit offsets of revoked partitions");
+        }
+    }
+}
+
+/// A [`Source`] on a Kafka consumer group subscription.
+pub struct KafkaSource {
+    consumer: StreamConsumer<RebalanceContext>,
+    pending: Arc<Mutex<PendingOffsets>>,
+}
+
+impl KafkaSource {
+    /// A member of `group` subscribed to `topics` on `brokers`. Offsets are
+    /// committed only as [`ConsumerName`] says, never automatically, and a
+    /// group with none committed starts at the earliest record.
+    ///
+    /// # Errors
+    ///
+    /// [`KafkaError`] if the consumer cannot be created or subscribed.
+    pub fn new(brokers: &str, group: &str, topics: &[&str]) -> KafkaResult<Self> {
+        let pending = Arc::default();
+        let context = RebalanceContext {
+            pending: Arc::clone(&pending),
+        };
+        let consumer: StreamConsumer<RebalanceContext> = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("group.id", group)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create_with_context(context)?;
+        consumer.subscribe(topics)?;
+        Ok(Self { consumer, pending })
+    }
+}
+
+impl Source for KafkaSource {
+    async fn recv(&mut self) -> Result<Option<Record>, KafkaError> {
+        let message = self.consumer.recv().await?;
+        Ok(Some(Record {
+            topic: message.topic().to_string(),
+            partition: message.partition(),
+            offset: message.offset(),
+            key: message.key().map(<[u8]>::to_vec),
+            payload: message.payload().map(<[u8]>::to_vec),
+        }))
+    }
+
+    fn pending(&self) -> &Mutex<PendingOffsets> {
+        &self.pending
+    }
+
+    fn commit(&mut self, offsets: &BTreeMap<Partition, i64>, mode: CommitMode) -> KafkaResult<()> {
+        self.consumer.commit(&partition_list(offsets)?, mode)
+    }
+}
+
+/// [`DeadLetters`] published to a Kafka topic, with the original record's
+/// key and payload, and its origin and the reason in `dlq.*` headers.
+pub struct KafkaDeadLetters {
+    producer: FutureProducer,
+    topic: String,
+    timeout: Duration,
+}
+
+impl KafkaDeadLetters {
+    /// Dead letters published to `topic` on `brokers`, each waiting up to
+    /// 10 seconds for the broker to acknowledge it.
+    ///
+    /// # Errors
+    ///
+    /// [`KafkaError`] if the producer cannot be created.
+    pub fn new(brokers: &str, topic: &str) -> KafkaResult<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .create()?;
+        Ok(Self {
+            producer,
+            topic: topic.to_string(),
+            timeout: Duration::from_secs(10),
+        })
+    }
+}
+
+impl DeadLetters for KafkaDeadLetters {
+    async fn send(&self, record: &Record, reason: &str) -> KafkaResult<()> {
+        let partition = record.partition.to_string();
+        let offset = record.offset.to_string();
+        let headers = [
+            ("dlq.reason", reason),
+            ("dlq.topic", record.topic.as_str()),
+            ("dlq.partition", partition.as_str()),
+            ("dlq.offset", offset.as_str()),
+        ]
+        .into_iter()
+        .fold(OwnedHeaders::new(), |headers, (key, value)| {
+            headers.insert(Header {
+                key,
+                value: Some(value),
+            })
+        });
+        let mut message = FutureRecord::<[u8], [u8]>::to(&self.topic).headers(headers);
+        if let Some(key) = &record.key {
+            message = message.key(key);
+        }
+        if let Some(payload) = &record.payload {
+            message = message.payload(payload);
+        }
+        let sent = self.producer.send(message, self.timeout).await;
+        sent.map(|_| ()).map_err(|(err, _)| err)
+    }
+}
+
+{{> partials/test-header}}
+    use std::collections::VecDeque;
+    use std::convert::Infallible;
+    use std::future::pending;
+
+    use rdkafka::types::RDKafkaErrorCode;
+
+    const TOPIC: &str = "events";
+    const SYNC: bool = true;
+    const ASYNC: bool = false;
+
+    /// What the mock source does when asked for a record.
+    enum Step {
+        Record(Record),
+        Fail(KafkaError),
+        /// A rebalance revoking `partition`, committing its offsets the way
+        /// `RebalanceContext` does.
+        Revoke(i32),
+    }
+
+    /// A source replaying `steps`, then ending, or, if `park`, waiting for
+    /// records that never come, as a quiet topic does.
+    #[derive(Default)]
+    struct MockSource {
+        steps: VecDeque<Step>,
+        park: bool,
+        pending: Mutex<PendingOffsets>,
+        /// Each commit, and whether it was synchronous.
+        commits: Vec<(BTreeMap<Partition, i64>, bool)>,
+    }
+
+    impl MockSource {
+        fn new(steps: impl IntoIterator<Item = Step>) -> Self {
+            Self {
+                steps: steps.into_iter().collect(),
+                ..Self::default()
+            }
+        }
+    }
+
+    impl Source for MockSource {
+        async fn recv(&mut self) -> Result<Option<Record>, KafkaError> {
+            loop {
+                match self.steps.pop_front() {
+                    Some(Step::Record(record)) => return Ok(Some(record)),
+                    Some(Step::Fail(err)) => return Err(err),
+                    Some(Step::Revoke(partition)) => {
+                        let revoked = [(TOPIC.to_string(), partition)];
+                        let offsets = lock(&self.pending).take_revoked(&revoked);
+                        self.commits.push((offsets, SYNC));
+                    }
+                    None if self.park => pending().await,
+                    None => return Ok(None),
+                }
+            }
+        }
+
+        fn pending(&self) -> &Mutex<PendingOffsets> {
+            &self.pending
+        }
+
+        fn commit(
+            &mut self,
+            offsets: &BTreeMap<Partition, i64>,
+            mode: CommitMode,
+        ) -> KafkaResult<()> {
+            let sync = matches!(mode, CommitMode::Sync);
+            self.commits.push((offsets.clone(), sync));
+            Ok(())
+        }
+    }
+
+    /// Dead letters kept as `(offset, reason)`, or refused if `fail`.
+    #[derive(Default)]
+    struct MockDeadLetters {
+        sent: Mutex<Vec<(i64, String)>>,
+        fail: bool,
+    }
+
+    impl DeadLetters for MockDeadLetters {
+        async fn send(&self, record: &Record, reason: &str) -> KafkaResult<()> {
+            if self.fail {
+                return Err(KafkaError::MessageProduction(
+                    RDKafkaErrorCode::MessageTimedOut,
+                ));
+            }
+            let mut sent = self.sent.lock().unwrap();
+            sent.push((record.offset, reason.to_string()));
+            Ok(())
+        }
+    }
+
+    fn record(partition: i32, offset: i64, payload: Option<&str>) -> Step {
+        Step::Record(Record {
+            topic: TOPIC.to_string(),
+            partition,
+            offset,
+            key: None,
+            payload: payload.map(|payload| payload.as_bytes().to_vec()),
+        })
+    }
+
+    /// A record on partition 0 of the event with id `offset`.
+    fn event(offset: i64) -> Step {
+        let payload = format!(r#"{{"id": {offset}, "action": "created"}}"#);
+        record(0, offset, Some(&payload))
+    }
+
+    fn offsets(entries: &[(i32, i64)]) -> BTreeMap<Partition, i64> {
+        let entries = entries.iter();
+        entries
+            .map(|&(partition, next)| ((TOPIC.to_string(), partition), next))
+            .collect()
+    }
+
+    type MockConsumer = ConsumerName<MockSource, MockDeadLetters>;
+
+    fn consumer(source: MockSource, every: u64) -> MockConsumer {
+        let policy = CommitPolicy {
+            every,
+            interval: Duration::from_secs(60),
+        };
+        ConsumerName::new(source, MockDeadLetters::default(), policy)
+    }
+
+    async fn accept(_: EventName) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_commits_every_n_records_and_on_shutdown() {
+        let mut consumer = consumer(MockSource::new((0..5).map(event)), 2);
+        let mut ids = Vec::new();
+        let handle = |event: EventName| {
+            ids.push(event.id);
+            accept(event)
+        };
+        let stats = consumer.run(handle, pending()).await.unwrap();
+
+        assert_eq!(ids, [0, 1, 2, 3, 4]);
+        assert_eq!(stats.handled, 5);
+        let expected = [
+            (offsets(&[(0, 2)]), ASYNC),
+            (offsets(&[(0, 4)]), ASYNC),
+            (offsets(&[(0, 5)]), SYNC),
+        ];
+        assert_eq!(consumer.source.commits, expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_undecodable_records_are_dead_lettered() {
+        let steps = [
+            event(0),
+            record(0, 1, Some("not json")),
+            record(0, 2, None),
+            event(3),
+        ];
+        let mut consumer = consumer(MockSource::new(steps), 10);
+        let stats = consumer.run(accept, pending()).await.unwrap();
+
+        assert_eq!(
+            stats,
+            Stats {
+                handled: 2,
+                dead_lettered: 2
+            }
+        );
+        let sent = consumer.dead_letters.sent.lock().unwrap().clone();
+        let offsets_sent: Vec<_> = sent.iter().map(|(offset, _)| *offset).collect();
+        assert_eq!(offsets_sent, [1, 2]);
+        assert_eq!(sent[1].1, "record has no payload");
+        // Dead-lettered records are done with, and committed past.
+        assert_eq!(consumer.source.commits, [(offsets(&[(0, 4)]), SYNC)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_handler_error_commits_only_earlier_records() {
+        let mut consumer = consumer(MockSource::new((0..4).map(event)), 10);
+        let handle = |event: EventName| async move {
+            match event.id {
+                2 => Err("rejected"),
+                _ => Ok(()),
+            }
+        };
+        let err = consumer.run(handle, pending()).await.unwrap_err();
+
+        match err {
+            ConsumerError::Handler { offset, source, .. } => {
+                assert_eq!(offset, 2);
+                assert_eq!(source.to_string(), "rejected");
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+        assert_eq!(consumer.source.commits, [(offsets(&[(0, 2)]), SYNC)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dead_letter_failure_leaves_record_uncommitted() {
+        let steps = [event(0), record(0, 1, Some("not json")), event(2)];
+        let mut consumer = consumer(MockSource::new(steps), 10);
+        consumer.dead_letters.fail = true;
+        let err = consumer.run(accept, pending()).await.unwrap_err();
+
+        assert!(matches!(err, ConsumerError::Kafka(_)));
+        assert_eq!(consumer.source.commits, [(offsets(&[(0, 1)]), SYNC)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_revoked_partitions_are_committed_once() {
+        let steps = [
+            record(0, 7, Some(r#"{"id": 1, "action": "a"}"#)),
+            record(1, 3, Some(r#"{"id": 2, "action": "b"}"#)),
+            Step::Revoke(0),
+            record(1, 4, Some(r#"{"id": 3, "action": "c"}"#)),
+        ];
+        let mut consumer = consumer(MockSource::new(steps), 10);
+        consumer.run(accept, pending()).await.unwrap();
+
+        let expected = [(offsets(&[(0, 8)]), SYNC), (offsets(&[(1, 5)]), SYNC)];
+        assert_eq!(consumer.source.commits, expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_consumer_commits_on_interval_until_shutdown() {
+        let mut source = MockSource::new([event(0)]);
+        source.park = true;
+        let mut consumer = consumer(source, 10);
+        let shutdown = time::sleep(Duration::from_secs(150));
+        let stats = consumer.run(accept, shutdown).await.unwrap();
+
+        assert_eq!(stats.handled, 1);
+        // The first tick commits the record; nothing is left for the others.
+        assert_eq!(consumer.source.commits, [(offsets(&[(0, 1)]), ASYNC)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_receive_error_stops_after_committing() {
+        let failure = KafkaError::MessageConsumption(RDKafkaErrorCode::BrokerTransportFailure);
+        let steps = [event(0), Step::Fail(failure), event(1)];
+        let mut consumer = consumer(MockSource::new(steps), 10);
+        let err = consumer.run(accept, pending()).await.unwrap_err();
+
+        assert!(matches!(err, ConsumerError::Kafka(_)));
+        assert_eq!(consumer.source.commits, [(offsets(&[(0, 1)]), SYNC)]);
+    }
+
+    #[test]
+    fn test_take_revoked_leaves_other_partitions() {
+        let mut pending = PendingOffsets::default();
+        for step in [record(0, 1, None), record(1, 5, None), record(0, 2, None)] {
+            let Step::Record(record) = step else {
+                unreachable!()
+            };
+            pending.done(&record);
+        }
+        assert_eq!(pending.records(), 3);
+        let revoked = [(TOPIC.to_string(), 0), (TOPIC.to_string(), 9)];
+        assert_eq!(pending.take_revoked(&revoked), offsets(&[(0, 3)]));
+        assert_eq!(pending.take(), offsets(&[(1, 6)]));
+        assert_eq!(pending.records(), 0);
+    }
+}
@@ -0,0 +1,49 @@
+//! [Brief description of what this crate does]
+//!
+//! The items most programs need are re-exported from [`prelude`]:
+//!
+//! ```ignore
+//! // Shows the import only; each item is documented where it is defined.
+//! use crate_name::prelude::*;
+//! ```
+
+// Every warning is fixed before a stage is complete, so the lints that
+// keep the public API documented and idiomatic are on from the start.
+#![deny(missing_docs)]
+#![warn(
+    missing_debug_implementations,
+    rust_2018_idioms,
+    unreachable_pub,
+    clippy::all
+)]
+{{#if forbid_unsafe}}
+#![forbid(unsafe_code)]
+{{/if}}
+
+{{#each modules}}
+pub mod {{ this }};
+{{/each}}
+
+/// Version of the crate, as in its `Cargo.toml`.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Glob-importable re-exports of the crate's most used items.
+pub mod prelude {
+{{#each prelude}}
+    pub use crate::{{ this }};
+{{/each}}
+}
+
+{{> partials/test-header}}
+
+    #[test]
+    fn test_crate_name_smoke() {
+        assert!(!VERSION.is_empty());
+    }
+
+    #[test]
+    fn test_crate_name_prelude_imports() {
+        #[allow(unused_imports)]
+        use crate::prelude::*;
+    }
+}
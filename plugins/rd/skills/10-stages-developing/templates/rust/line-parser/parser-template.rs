@@ -0,0 +1,285 @@
+//! [Brief description of what this module does]
+//!
+//! [`RecordNameParser`] reads `name,quantity,price` lines from any
+//! [`BufRead`] one at a time, so input of any size is parsed in constant
+//! memory. It yields each line as a [`RecordName`] or a [`ParseError`]
+//! carrying its line number, and carries on after a bad line;
+//! [`parse_all`] collects both, so a caller can report every problem in
+//! one pass. Blank lines and lines starting with `#` are skipped, and the
+//! first other line must be the header.
+
+use std::fmt;
+use std::io::{self, BufRead};
+
+/// The header line the input must start with.
+pub const HEADER: &str = "name,quantity,price";
+
+/// One parsed line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordName {
+    /// The item's name.
+    pub name: String,
+    /// How many there are.
+    pub quantity: u32,
+    /// Unit price, in cents.
+    pub price_cents: u64,
+}
+
+/// What was wrong with a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Reading failed; no further lines are read.
+    Io(String),
+    /// The first line was not [`HEADER`].
+    Header(String),
+    /// The line had the wrong number of fields.
+    FieldCount {
+        /// Fields a line must have.
+        expected: usize,
+        /// Fields the line had.
+        found: usize,
+    },
+    /// A field did not parse.
+    Invalid {
+        /// Name of the field, as in [`HEADER`].
+        field: &'static str,
+        /// Text of the field.
+        value: String,
+    },
+}
+
+/// A line that could not be parsed, numbered from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line number in the input.
+    pub line: usize,
+    /// What was wrong with it.
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ErrorKind::Io(message) => write!(f, "read failed: {message}"),
+            ErrorKind::Header(found) => write!(f, "expected header {HEADER:?}, found {found:?}"),
+            ErrorKind::FieldCount { expected, found } => {
+                write!(f, "expected {expected} fields, found {found}")
+            }
+            ErrorKind::Invalid { field, value } => write!(f, "invalid {field} {value:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses records from a reader, one line at a time.
+#[derive(Debug)]
+pub struct RecordNameParser<R> {
+    lines: io::Lines<R>,
+    line: usize,
+    header_seen: bool,
+    failed: bool,
+}
+
+impl<R: BufRead> RecordNameParser<R> {
+    /// Parses the lines of `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line: 0,
+            header_seen: false,
+            failed: false,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for RecordNameParser<R> {
+    type Item = Result<RecordName, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            self.line += 1;
+            let line = self.line;
+            let error = |kind| Some(Err(ParseError { line, kind }));
+            let text = match self.lines.next()? {
+                Ok(text) => text,
+                Err(err) => {
+                    self.failed = true;
+                    return error(ErrorKind::Io(err.to_string()));
+                }
+            };
+            let text = text.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+            if !self.header_seen {
+                self.header_seen = true;
+                if text == HEADER {
+                    continue;
+                }
+                return error(ErrorKind::Header(text.to_string()));
+            }
+            return match parse_record(text) {
+                Ok(record) => Some(Ok(record)),
+                Err(kind) => error(kind),
+            };
+        }
+        None
+    }
+}
+
+/// Records and errors of a whole input, each in line order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Parsed {
+    /// The lines that parsed.
+    pub records: Vec<RecordName>,
+    /// The lines that did not.
+    pub errors: Vec<ParseError>,
+}
+
+/// Parses every line of `reader`, collecting the records and the errors.
+pub fn parse_all(reader: impl BufRead) -> Parsed {
+    let mut parsed = Parsed::default();
+    for result in RecordNameParser::new(reader) {
+        match result {
+            Ok(record) => parsed.records.push(record),
+            Err(err) => parsed.errors.push(err),
+        }
+    }
+    parsed
+}
+
+fn parse_record(text: &str) -> Result<RecordName, ErrorKind> {
+    let fields: Vec<&str> = text.split(',').map(str::trim).collect();
+    let [name, quantity, price] = fields[..] else {
+        return Err(ErrorKind::FieldCount {
+            expected: 3,
+            found: fields.len(),
+        });
+    };
+    let invalid = |field, value: &str| ErrorKind::Invalid {
+        field,
+        value: value.to_string(),
+    };
+    if name.is_empty() {
+        return Err(invalid("name", name));
+    }
+    let Ok(quantity) = quantity.parse() else {
+        return Err(invalid("quantity", quantity));
+    };
+    let Some(price_cents) = parse_cents(price) else {
+        return Err(invalid("price", price));
+    };
+    Ok(RecordName {
+        name: name.to_string(),
+        quantity,
+        price_cents,
+    })
+}
+
+/// Reads a price such as `2.5` or `2.50` as 250 cents.
+fn parse_cents(text: &str) -> Option<u64> {
+    let (units, cents) = text.split_once('.').unwrap_or((text, "0"));
+    let all_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if !all_digits(units) || !all_digits(cents) || cents.len() > 2 {
+        return None;
+    }
+    let scale = if cents.len() == 1 { 10 } else { 1 };
+    let units: u64 = units.parse().ok()?;
+    let cents: u64 = cents.parse().ok()?;
+    units.checked_mul(100)?.checked_add(cents * scale)
+}
+
+{{> partials/test-header}}
+    use std::io::{BufReader, Read};
+
+    const FIXTURE: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/{{ module }}.csv"
+    ));
+
+    fn record(name: &str, quantity: u32, price_cents: u64) -> RecordName {
+        RecordName {
+            name: name.to_string(),
+            quantity,
+            price_cents,
+        }
+    }
+
+    fn invalid(line: usize, field: &'static str, value: &str) -> ParseError {
+        let value = value.to_string();
+        let kind = ErrorKind::Invalid { field, value };
+        ParseError { line, kind }
+    }
+
+    #[test]
+    fn test_parses_valid_input() {
+        let input = "name,quantity,price\r\nwidget, 4, 2.5\r\n\r\nbolt,10,0.05\r\n";
+        let parsed = parse_all(input.as_bytes());
+        let expected = vec![record("widget", 4, 250), record("bolt", 10, 5)];
+        assert_eq!(parsed.records, expected);
+        assert!(parsed.errors.is_empty());
+    }
+
+    #[test]
+    fn test_collects_errors_from_malformed_fixture() {
+        let parsed = parse_all(FIXTURE.as_bytes());
+        let expected = vec![record("widget", 4, 250), record("sprocket", 12, 75)];
+        assert_eq!(parsed.records, expected);
+        let field_count = |line, found| ParseError {
+            line,
+            kind: ErrorKind::FieldCount { expected: 3, found },
+        };
+        let lines: Vec<usize> = parsed.errors.iter().map(|err| err.line).collect();
+        assert_eq!(lines, [4, 7, 8, 9, 10]);
+        assert_eq!(parsed.errors[0], invalid(4, "quantity", ""));
+        assert_eq!(parsed.errors[1], field_count(7, 2));
+        assert_eq!(parsed.errors[2], invalid(8, "quantity", "two"));
+        assert_eq!(parsed.errors[3], field_count(9, 4));
+        assert_eq!(parsed.errors[4], invalid(10, "price", "abc"));
+    }
+
+    #[test]
+    fn test_reports_missing_header() {
+        let parsed = parse_all("widget,4,2.50\nbolt,1,1\n".as_bytes());
+        assert_eq!(parsed.records, [record("bolt", 1, 100)]);
+        let err = &parsed.errors[0];
+        assert_eq!(err.line, 1);
+        let message = "line 1: expected header \"name,quantity,price\", found \"widget,4,2.50\"";
+        assert_eq!(err.to_string(), message);
+    }
+
+    #[test]
+    fn test_rejects_malformed_prices() {
+        for price in ["", "1.234", ".5", "1.", "-1", "1.x"] {
+            assert_eq!(parse_cents(price), None, "{price:?}");
+        }
+        assert_eq!(parse_cents("12"), Some(1200));
+        assert_eq!(parse_cents("0.7"), Some(70));
+    }
+
+    /// Yields its bytes, then fails.
+    struct FailingReader(&'static [u8]);
+
+    impl Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::other("disk on fire"));
+            }
+            let n = self.0.read(buf)?;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_stops_after_read_error() {
+        let reader = BufReader::new(FailingReader(b"name,quantity,price\nnut,1,0.10\n"));
+        let results: Vec<_> = RecordNameParser::new(reader).collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0], Ok(record("nut", 1, 10)));
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.kind, ErrorKind::Io("disk on fire".to_string()));
+    }
+}
@@ -0,0 +1,255 @@
+//! [Brief description of what this module does]
+//!
+//! [`CacheName`] keeps at most `capacity` entries and, when full, evicts
+//! the one used least recently: a `get` or an `insert` makes an entry the
+//! most recent. With a time to live, entries also expire that long after
+//! they were inserted, and an expired entry counts as a miss.
+//!
+//! Recency is a counter stamped on each use, indexed by a `BTreeMap`, so
+//! the least recent entry is its first key. Lookups take `&mut self` to
+//! update it; share a cache between threads behind a `Mutex`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// Hit and miss counts of a [`CacheName`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// Lookups that found a live entry.
+    pub hits: u64,
+    /// Lookups that found nothing, or an expired entry.
+    pub misses: u64,
+    /// Entries evicted to make room.
+    pub evictions: u64,
+}
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: V,
+    used: u64,
+    inserted: Instant,
+}
+
+/// A least-recently-used cache with an optional time to live.
+#[derive(Debug)]
+pub struct CacheName<K, V> {
+    capacity: usize,
+    ttl: Option<Duration>,
+    entries: HashMap<K, Entry<V>>,
+    /// Keys by the counter value of their last use.
+    recency: BTreeMap<u64, K>,
+    counter: u64,
+    stats: CacheStats,
+}
+
+impl<K: Eq + Hash + Clone, V> CacheName<K, V> {
+    /// Creates an empty cache holding at most `capacity` entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "cache capacity must be positive");
+        Self {
+            capacity,
+            ttl: None,
+            entries: HashMap::with_capacity(capacity),
+            recency: BTreeMap::new(),
+            counter: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Expires entries `ttl` after they are inserted.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// The live value for `key`, made the most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.get_at(key, Instant::now())
+    }
+
+    /// Inserts `value` for `key` as the most recently used entry,
+    /// evicting the least recent one if the cache is full. Returns the
+    /// value it replaced, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_at(key, value, Instant::now())
+    }
+
+    /// Removes the entry for `key`, returning its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let entry = self.entries.remove(key)?;
+        self.recency.remove(&entry.used);
+        Some(entry.value)
+    }
+
+    /// Entries held, including expired ones not yet looked up.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the cache holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Hit, miss and eviction counts so far.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    fn get_at(&mut self, key: &K, now: Instant) -> Option<&V> {
+        let expired = match self.entries.get(key) {
+            None => {
+                self.stats.misses += 1;
+                return None;
+            }
+            Some(entry) => self.is_expired(entry, now),
+        };
+        if expired {
+            self.remove(key);
+            self.stats.misses += 1;
+            return None;
+        }
+        self.stats.hits += 1;
+        let used = self.next_use();
+        let entry = self.entries.get_mut(key)?;
+        let key = self.recency.remove(&entry.used)?;
+        self.recency.insert(used, key);
+        entry.used = used;
+        Some(&entry.value)
+    }
+
+    fn insert_at(&mut self, key: K, value: V, now: Instant) -> Option<V> {
+        let used = self.next_use();
+        let entry = Entry {
+            value,
+            used,
+            inserted: now,
+        };
+        let replaced = self.entries.insert(key.clone(), entry);
+        if let Some(old) = &replaced {
+            self.recency.remove(&old.used);
+        }
+        self.recency.insert(used, key);
+        if self.entries.len() > self.capacity {
+            if let Some((_, oldest)) = self.recency.pop_first() {
+                self.entries.remove(&oldest);
+                self.stats.evictions += 1;
+            }
+        }
+        replaced.map(|old| old.value)
+    }
+
+    fn is_expired(&self, entry: &Entry<V>, now: Instant) -> bool {
+        self.ttl
+            .is_some_and(|ttl| now.saturating_duration_since(entry.inserted) >= ttl)
+    }
+
+    fn next_use(&mut self) -> u64 {
+        self.counter += 1;
+        self.counter
+    }
+}
+
+{{> partials/test-header}}
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = CacheName::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        // Reading "a" makes "b" the least recent.
+        assert_eq!(cache.get(&"a"), Some(&1));
+        cache.insert("c", 3);
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.get(&"c"), Some(&3));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_insert_replaces_and_refreshes() {
+        let mut cache = CacheName::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        assert_eq!(cache.insert("a", 10), Some(1));
+        cache.insert("c", 3);
+        assert_eq!(cache.get(&"a"), Some(&10));
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.remove(&"a"), Some(10));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_entries_expire_after_ttl() {
+        let start = Instant::now();
+        let ttl = Duration::from_secs(60);
+        let mut cache = CacheName::new(4).with_ttl(ttl);
+        cache.insert_at("a", 1, start);
+        let later = start + Duration::from_secs(59);
+        assert_eq!(cache.get_at(&"a", later), Some(&1));
+        // A hit does not extend the time to live.
+        assert_eq!(cache.get_at(&"a", start + ttl), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_counts_hits_and_misses() {
+        let start = Instant::now();
+        let mut cache = CacheName::new(4).with_ttl(Duration::from_secs(1));
+        cache.insert_at(1, "one", start);
+        cache.get_at(&1, start);
+        cache.get_at(&2, start);
+        cache.get_at(&1, start + Duration::from_secs(2));
+        let expected = CacheStats {
+            hits: 1,
+            misses: 2,
+            evictions: 0,
+        };
+        assert_eq!(cache.stats(), expected);
+    }
+
+    #[test]
+    fn test_shared_behind_mutex() {
+        let cache = Arc::new(Mutex::new(CacheName::new(16)));
+        let workers: Vec<_> = (0..4)
+            .map(|worker| {
+                let cache = Arc::clone(&cache);
+                thread::spawn(move || {
+                    for n in 0..100 {
+                        let key = (worker * 100 + n) % 32;
+                        let mut cache = cache.lock().unwrap();
+                        if cache.get(&key).is_none() {
+                            cache.insert(key, key * 2);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        let mut cache = cache.lock().unwrap();
+        let stats = cache.stats();
+        assert_eq!(stats.hits + stats.misses, 400);
+        assert_eq!(cache.len(), 16);
+        for key in 0..32 {
+            if let Some(&value) = cache.get(&key) {
+                assert_eq!(value, key * 2);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cache capacity must be positive")]
+    fn test_new_rejects_zero_capacity() {
+        let _ = CacheName::<u32, u32>::new(0);
+    }
+}
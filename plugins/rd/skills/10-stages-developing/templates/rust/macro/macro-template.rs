@@ -0,0 +1,66 @@
+//! The `macro_name!` macro.
+
+/// [Brief description of what the macro does]
+///
+/// Builds a [`BTreeMap`](std::collections::BTreeMap) from `key => value`
+/// pairs; with no pairs it is an empty map. Keys alone are a compile
+/// error rather than the compiler's "no rules expected" message.
+///
+/// # Examples
+///
+/// ```
+/// use crate_name::macro_name;
+///
+/// let map = macro_name! { "a" => 1, "b" => 2 };
+/// assert_eq!(map["b"], 2);
+///
+/// let empty: std::collections::BTreeMap<&str, i32> = macro_name!();
+/// assert!(empty.is_empty());
+/// ```
+///
+/// ```compile_fail
+/// use crate_name::macro_name;
+///
+/// let map = macro_name! { "a", "b" };
+/// ```
+#[macro_export]
+macro_rules! macro_name {
+    () => {
+        ::std::collections::BTreeMap::new()
+    };
+    ($($key:expr => $value:expr),+ $(,)?) => {{
+        let mut map = ::std::collections::BTreeMap::new();
+        $(
+            map.insert($key, $value);
+        )+
+        map
+    }};
+    ($($key:expr),+ $(,)?) => {
+        ::std::compile_error!("macro_name! takes `key => value` pairs")
+    };
+}
+
+// `macro_name!` is in scope here by being defined above, so the tests
+// need no `use super::*`.
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_macro_name_pairs() {
+        let map = macro_name! { "a" => 1, "b" => 2, };
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["a"], 1);
+    }
+
+    #[test]
+    fn test_macro_name_later_pairs_win() {
+        let map = macro_name! { "a" => 1, "a" => 2 };
+        assert_eq!(map.len(), 1);
+        assert_eq!(map["a"], 2);
+    }
+
+    #[test]
+    fn test_macro_name_empty() {
+        let map: std::collections::BTreeMap<String, u8> = macro_name!();
+        assert!(map.is_empty());
+    }
+}
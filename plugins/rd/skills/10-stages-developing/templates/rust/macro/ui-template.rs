@@ -0,0 +1,10 @@
+//! Compile tests for `macro_name!`: what must build, and what must fail to
+//! with the expected message. After changing a message, regenerate the
+//! `.stderr` files with `TRYBUILD=overwrite cargo test`.
+
+#[test]
+fn macro_name_ui() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/macro_name/pass.rs");
+    cases.compile_fail("tests/ui/macro_name/missing_value.rs");
+}
@@ -0,0 +1,7 @@
+// Invoked under a short name, so that the expected output does not depend
+// on the macro's.
+use crate_name::macro_name as m;
+
+fn main() {
+    let _map = m! { "a", "b" };
+}
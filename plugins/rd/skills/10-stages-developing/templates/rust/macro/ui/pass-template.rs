@@ -0,0 +1,6 @@
+use crate_name::macro_name;
+
+fn main() {
+    let map = macro_name! { 1 => "one", 2 => "two" };
+    assert_eq!(map[&2], "two");
+}
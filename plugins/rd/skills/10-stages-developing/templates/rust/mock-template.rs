@@ -0,0 +1,106 @@
+// [Brief description of what this module does]
+//
+// `ServiceName` is given the `TraitName` it depends on rather than building
+// one, so its tests pass it a `MockTraitName`, which `mockall` generates
+// from the trait in test builds. Each test states the calls it expects and
+// what they return, and the mock fails the test on any other call. To mock
+// a trait defined elsewhere, put the same `#[cfg_attr(test,
+// mockall::automock)]` on it; `mockall` is then only a dev-dependency.
+
+/// [Brief description of what implementors provide]
+#[cfg_attr(test, mockall::automock)]
+pub trait TraitName {
+    /// The value stored under `key`, or `None` if there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error message if the value cannot be read.
+    fn method_name(&self, key: &str) -> Result<Option<String>, String>;
+}
+
+/// The code under test, working through whichever [`TraitName`] it is
+/// given.
+#[derive(Debug)]
+pub struct ServiceName<T> {
+    dependency: T,
+}
+
+impl<T: TraitName> ServiceName<T> {
+    /// Creates a service working through `dependency`.
+    pub fn new(dependency: T) -> Self {
+        Self { dependency }
+    }
+
+    /// The value under `key`, or `default` if there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error message, naming the key, if `key` is empty or the
+    /// dependency fails.
+    pub fn method_name_or(&self, key: &str, default: &str) -> Result<String, String> {
+        if key.is_empty() {
+            return Err("key cannot be empty".to_string());
+        }
+        let value = self
+            .dependency
+            .method_name(key)
+            .map_err(|err| format!("cannot read `{key}`: {err}"))?;
+        Ok(value.unwrap_or_else(|| default.to_string()))
+    }
+}
+
+{{> partials/test-header}}
+    use mockall::predicate::eq;
+
+    /// A mock expecting exactly one `method_name(key)` call, which returns
+    /// `response`.
+    fn expect_method_name(
+        key: &'static str,
+        response: Result<Option<String>, String>,
+    ) -> MockTraitName {
+        let mut mock = MockTraitName::new();
+        mock.expect_method_name()
+            .with(eq(key))
+            .times(1)
+            .return_once(move |_| response);
+        mock
+    }
+
+    #[test]
+    fn test_method_name_or_returns_stored_value() {
+        let service = ServiceName::new(expect_method_name("answer", Ok(Some("42".to_string()))));
+        assert_eq!(service.method_name_or("answer", "none").unwrap(), "42");
+    }
+
+    #[test]
+    fn test_method_name_or_falls_back_to_default() {
+        let service = ServiceName::new(expect_method_name("missing", Ok(None)));
+        assert_eq!(service.method_name_or("missing", "none").unwrap(), "none");
+    }
+
+    #[test]
+    fn test_method_name_or_names_key_on_failure() {
+        let service = ServiceName::new(expect_method_name("answer", Err("timed out".to_string())));
+        let err = service.method_name_or("answer", "none").unwrap_err();
+        assert_eq!(err, "cannot read `answer`: timed out");
+    }
+
+    #[test]
+    fn test_method_name_or_rejects_empty_key_without_calling() {
+        // No expectations: any call to the mock fails the test.
+        let service = ServiceName::new(MockTraitName::new());
+        assert!(service.method_name_or("", "none").is_err());
+    }
+
+    #[test]
+    fn test_method_name_or_calls_once_per_lookup() {
+        let mut mock = MockTraitName::new();
+        mock.expect_method_name()
+            .withf(|key| key.starts_with("user."))
+            .times(2)
+            .returning(|key| Ok(Some(key.to_uppercase())));
+        let service = ServiceName::new(mock);
+        assert_eq!(service.method_name_or("user.a", "").unwrap(), "USER.A");
+        assert_eq!(service.method_name_or("user.b", "").unwrap(), "USER.B");
+    }
+}
@@ -0,0 +1,241 @@
+//! [Brief description of what this module does]
+//!
+//! A [`TypeName`] can only be made through validation — [`TryFrom`],
+//! [`FromStr`] or, with the `serde` feature, deserialization — so every one
+//! in the program holds a valid address and code taking one need not check
+//! again. The rules are deliberately simple: one `@` between a non-empty
+//! local part and a dotted domain, no whitespace, and at most
+//! [`TypeName::MAX_LEN`] bytes.
+//!
+//! The feature is declared in `Cargo.toml` as:
+//!
+//! ```toml
+//! [dependencies]
+//! serde = { version = "1", features = ["derive"], optional = true }
+//!
+//! [features]
+//! serde = ["dep:serde"]
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A validated email address.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct TypeName(String);
+
+/// Why a string is not a valid [`TypeName`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeNameError {
+    /// The string is empty.
+    Empty,
+    /// The string is longer than [`TypeName::MAX_LEN`] bytes.
+    TooLong {
+        /// Length of the string, in bytes.
+        len: usize,
+    },
+    /// The string contains whitespace or control characters.
+    Whitespace,
+    /// The string has no `@`.
+    MissingAt,
+    /// Nothing comes before the `@`.
+    EmptyLocalPart,
+    /// What follows the `@` is not a domain such as `example.com`.
+    InvalidDomain,
+}
+
+impl fmt::Display for TypeNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("address is empty"),
+            Self::TooLong { len } => {
+                let max = TypeName::MAX_LEN;
+                write!(f, "address is {len} bytes, longer than {max}")
+            }
+            Self::Whitespace => f.write_str("address contains whitespace"),
+            Self::MissingAt => f.write_str("address has no '@'"),
+            Self::EmptyLocalPart => f.write_str("address has nothing before the '@'"),
+            Self::InvalidDomain => f.write_str("address has an invalid domain"),
+        }
+    }
+}
+
+impl std::error::Error for TypeNameError {}
+
+impl TypeName {
+    /// Longest valid value, in bytes.
+    pub const MAX_LEN: usize = LENGTH_LIMIT;
+
+    /// The address as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The part after the `@`.
+    pub fn domain(&self) -> &str {
+        self.0.split_once('@').map_or("", |(_, domain)| domain)
+    }
+
+    fn validate(value: &str) -> Result<(), TypeNameError> {
+        if value.is_empty() {
+            return Err(TypeNameError::Empty);
+        }
+        if value.len() > Self::MAX_LEN {
+            let len = value.len();
+            return Err(TypeNameError::TooLong { len });
+        }
+        if value.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(TypeNameError::Whitespace);
+        }
+        let Some((local, domain)) = value.split_once('@') else {
+            return Err(TypeNameError::MissingAt);
+        };
+        if local.is_empty() {
+            return Err(TypeNameError::EmptyLocalPart);
+        }
+        let labels_valid = domain.split('.').all(|label| !label.is_empty());
+        if !domain.contains('.') || domain.contains('@') || !labels_valid {
+            return Err(TypeNameError::InvalidDomain);
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for TypeName {
+    type Error = TypeNameError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::validate(&value)?;
+        Ok(Self(value))
+    }
+}
+
+impl TryFrom<&str> for TypeName {
+    type Error = TypeNameError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::validate(value)?;
+        Ok(Self(value.to_string()))
+    }
+}
+
+impl FromStr for TypeName {
+    type Err = TypeNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
+}
+
+impl fmt::Display for TypeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for TypeName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<TypeName> for String {
+    fn from(value: TypeName) -> Self {
+        value.0
+    }
+}
+
+{{> partials/test-header}}
+
+    type Error = TypeNameError;
+
+    fn parse(value: &str) -> Result<TypeName, Error> {
+        value.parse()
+    }
+
+    /// An address of exactly `len` bytes.
+    fn address_of_len(len: usize) -> String {
+        let domain = "@example.com";
+        format!("{}{domain}", "a".repeat(len - domain.len()))
+    }
+
+    #[test]
+    fn test_accepts_valid_values() {
+        let values = [
+            "a@b.co",
+            "first.last+tag@mail.example.org",
+            "x@sub.example.io",
+        ];
+        for value in values {
+            let parsed = parse(value).unwrap();
+            assert_eq!(parsed.as_str(), value);
+            assert_eq!(parsed.to_string(), value);
+        }
+        assert_eq!(parse("grace@example.com").unwrap().domain(), "example.com");
+    }
+
+    #[test]
+    fn test_length_boundary() {
+        let longest = address_of_len(TypeName::MAX_LEN);
+        assert!(parse(&longest).is_ok());
+        let len = TypeName::MAX_LEN + 1;
+        let too_long = address_of_len(len);
+        assert_eq!(parse(&too_long), Err(Error::TooLong { len }));
+    }
+
+    #[test]
+    fn test_rejects_invalid_values() {
+        let cases = [
+            ("", Error::Empty),
+            ("grace example.com", Error::Whitespace),
+            (" grace@example.com", Error::Whitespace),
+            ("grace@example.com\n", Error::Whitespace),
+            ("grace.example.com", Error::MissingAt),
+            ("@example.com", Error::EmptyLocalPart),
+            ("grace@", Error::InvalidDomain),
+            ("grace@localhost", Error::InvalidDomain),
+            ("grace@example..com", Error::InvalidDomain),
+            ("grace@.example.com", Error::InvalidDomain),
+            ("grace@example.com.", Error::InvalidDomain),
+            ("grace@a@example.com", Error::InvalidDomain),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(parse(value), Err(expected), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn test_conversions_agree() {
+        let value = "grace@example.com";
+        let parsed = parse(value).unwrap();
+        let from_str_ref = TypeName::try_from(value);
+        assert_eq!(from_str_ref.as_ref(), Ok(&parsed));
+        let from_string = TypeName::try_from(value.to_string());
+        assert_eq!(from_string.as_ref(), Ok(&parsed));
+        assert_eq!(String::from(parsed), value);
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let err = parse("grace").unwrap_err();
+        assert_eq!(err.to_string(), "address has no '@'");
+        let err = Error::TooLong { len: 300 };
+        let max = TypeName::MAX_LEN;
+        let expected = format!("address is 300 bytes, longer than {max}");
+        assert_eq!(err.to_string(), expected);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_validates_on_deserialize() {
+        let json = "\"grace@example.com\"";
+        let parsed: TypeName = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.as_str(), "grace@example.com");
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+        let result: Result<TypeName, _> = serde_json::from_str("\"grace\"");
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("address has no '@'"), "{err}");
+    }
+}
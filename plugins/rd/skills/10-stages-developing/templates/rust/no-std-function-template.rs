@@ -0,0 +1,181 @@
+//! [Brief description of what this module does]
+//!
+//! The crate is `no_std`: it uses `core` and fixed-capacity `heapless`
+//! collections, never an allocator, so it builds for bare-metal targets.
+//! Errors are an enum rather than `String` messages, and text is held in a
+//! `heapless::String` whose capacity is part of its type.
+//!
+//! The tests run on the host and link `std` for the test harness, but only
+//! under `cfg(test)`; everything else is compiled without it in every
+//! build, the test build included, so nothing from `std` can slip in. This
+//! file is a crate root, since `#![no_std]` applies to a whole crate:
+//! render it as `src/lib.rs`, or move the attribute and the
+//! `extern crate std` line to the crate's own root.
+//!
+//! Check a target build with, for example:
+//!
+//! ```sh
+//! rustup target add thumbv7em-none-eabihf
+//! cargo build --target thumbv7em-none-eabihf
+//! ```
+
+#![no_std]
+
+#[cfg(test)]
+extern crate std;
+
+use core::fmt::{self, Write as _};
+
+use heapless::String;
+
+/// Longest `key1` a [`FunctionResult`] holds, in bytes.
+pub const KEY1_CAPACITY: usize = MAX_KEY1_LEN;
+
+/// Result of function_name operation
+#[derive(Debug, PartialEq)]
+pub struct FunctionResult {
+    pub key1: String<KEY1_CAPACITY>,
+    pub key2: i32,
+}
+
+/// Why [`function_name`] rejected its input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionError {
+    /// `param1` was empty.
+    Empty,
+    /// `param2` was negative.
+    Negative,
+    /// The processed `param1` is longer than [`KEY1_CAPACITY`] bytes.
+    TooLong,
+    /// Doubling `param2` overflowed.
+    Overflow,
+}
+
+impl fmt::Display for FunctionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Empty => "param1 cannot be empty",
+            Self::Negative => "param2 must be non-negative",
+            Self::TooLong => "param1 is too long",
+            Self::Overflow => "param2 is too large",
+        })
+    }
+}
+
+impl core::error::Error for FunctionError {}
+
+/// [Brief description of what the function does]
+///
+/// # Arguments
+///
+/// * `param1` - [Description of param1]
+/// * `param2` - [Description of param2]
+///
+/// # Returns
+///
+/// * `Ok(FunctionResult)` - Processed results
+/// * `Err(FunctionError)` - Which check the input failed
+///
+/// # Examples
+///
+/// ```ignore
+/// // The function's path depends on the module it is rendered into.
+/// let result = function_name("example", 42)?;
+/// assert_eq!(result.key1, "processed_example");
+/// assert_eq!(result.key2, 84);
+/// ```
+pub fn function_name(param1: &str, param2: i32) -> Result<FunctionResult, FunctionError> {
+    // Input validation
+    if param1.is_empty() {
+        return Err(FunctionError::Empty);
+    }
+
+    if param2 < 0 {
+        return Err(FunctionError::Negative);
+    }
+
+    // Implementation; `write!` into a `heapless::String` fails instead of
+    // growing when the text does not fit.
+    let mut key1 = String::new();
+    write!(key1, "processed_{param1}").map_err(|_| FunctionError::TooLong)?;
+    let key2 = param2.checked_mul(2).ok_or(FunctionError::Overflow)?;
+
+    Ok(FunctionResult { key1, key2 })
+}
+
+{{> partials/test-header}}
+    // The crate is `no_std` even here, so there is no `std` prelude:
+    // anything from `std` is named through the `extern crate` above.
+    use std::string::ToString;
+
+    fn ok(key1: &str, key2: i32) -> Result<FunctionResult, FunctionError> {
+        let key1 = String::try_from(key1).unwrap();
+        Ok(FunctionResult { key1, key2 })
+    }
+
+    #[test]
+    fn test_function_name_valid_input() {
+        let result = function_name("hello", 42).unwrap();
+
+        assert_eq!(result.key1, "processed_hello");
+        assert_eq!(result.key2, 84);
+    }
+
+    #[test]
+    fn test_function_name_empty_string() {
+        let result = function_name("", 10);
+        assert_eq!(result, Err(FunctionError::Empty));
+    }
+
+    #[test]
+    fn test_function_name_negative_number() {
+        let result = function_name("test", -1);
+        assert_eq!(result, Err(FunctionError::Negative));
+    }
+
+    #[test]
+    fn test_function_name_zero() {
+        let result = function_name("test", 0).unwrap();
+        assert_eq!(result.key2, 0);
+    }
+
+    #[test]
+    fn test_function_name_capacity() {
+        let prefix = "processed_".len();
+        let fits = "x".repeat(KEY1_CAPACITY - prefix);
+        let result = function_name(&fits, 1).unwrap();
+        assert_eq!(result.key1.len(), KEY1_CAPACITY);
+
+        let longer = "x".repeat(KEY1_CAPACITY - prefix + 1);
+        let result = function_name(&longer, 1);
+        assert_eq!(result, Err(FunctionError::TooLong));
+    }
+
+    #[test]
+    fn test_function_name_overflow() {
+        let result = function_name("test", i32::MAX);
+        assert_eq!(result, Err(FunctionError::Overflow));
+    }
+
+    #[test]
+    fn test_function_name_error_messages() {
+        let message = FunctionError::Empty.to_string();
+        assert_eq!(message, "param1 cannot be empty");
+    }
+
+    #[test]
+    fn test_function_name_parametrized() {
+        let test_cases = [
+            ("hello", 10, ok("processed_hello", 20)),
+            ("world", 5, ok("processed_world", 10)),
+            ("test", 0, ok("processed_test", 0)),
+            ("", 10, Err(FunctionError::Empty)),
+            ("test", -1, Err(FunctionError::Negative)),
+        ];
+
+        for (input_str, input_num, expected) in test_cases {
+            let result = function_name(input_str, input_num);
+            assert_eq!(result, expected, "{input_str}, {input_num}");
+        }
+    }
+}
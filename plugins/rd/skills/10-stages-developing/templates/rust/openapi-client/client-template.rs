@@ -0,0 +1,16 @@
+{{#extends rest-client/client}}
+{{#block imports}}
+{{#each api.client_imports}}
+use super::{{ this }};
+{{/each}}
+{{/block}}
+{{#block groups}}
+{{#each api.groups}}
+    /// The `{{ tag }}` endpoints.
+    pub fn {{ module }}(&self) -> {{ type_name }}<'_> {
+        {{ type_name }}::new(self)
+    }
+
+{{/each}}
+{{/block}}
+{{/extends}}
@@ -0,0 +1,45 @@
+//! The `{{ group.tag }}` endpoints of {{ api.title }}.
+
+use reqwest::Method;
+
+use super::client::{self, ApiClient};
+use super::error::ApiError;
+{{#if group.imports}}
+use super::models::{{ group.imports }};
+{{/if}}
+
+{{#each group.doc}}
+{{ this }}
+{{/each}}
+/// The `{{ group.tag }}` endpoints, from [`ApiClient::{{ group.module }}`].
+///
+/// Each method returns the [`ApiError`] the request failed with.
+#[derive(Debug, Clone, Copy)]
+pub struct {{ group.type_name }}<'a> {
+    client: &'a ApiClient,
+}
+
+impl<'a> {{ group.type_name }}<'a> {
+    pub(super) fn new(client: &'a ApiClient) -> Self {
+        Self { client }
+    }
+{{#each group.operations}}
+
+{{#each doc}}
+    {{ this }}
+{{/each}}
+    pub async fn {{ name }}(&self{{ args }}) -> Result<{{ returns }}, ApiError> {
+{{#if optional_query}}
+        let mut request = self.client.request(Method::{{ method }}, {{ path }}){{#each required_query}}.query(&[({{ wire }}, {{ name }})]){{/each}}{{#if body}}.json({{ body }}){{/if}};
+{{#each optional_query}}
+        if let Some({{ name }}) = {{ name }} {
+            request = request.query(&[({{ wire }}, {{ name }})]);
+        }
+{{/each}}
+{{else}}
+        let request = self.client.request(Method::{{ method }}, {{ path }}){{#each required_query}}.query(&[({{ wire }}, {{ name }})]){{/each}}{{#if body}}.json({{ body }}){{/if}};
+{{/if}}
+        client::{{ decoder }}(request).await
+    }
+{{/each}}
+}
@@ -0,0 +1,25 @@
+{{#each api.doc}}
+{{ this }}
+{{/each}}
+//!
+//! Generated by `cc-scaffold from-openapi` from the service's OpenAPI
+//! document; regenerate it rather than editing it by hand.
+//!
+//! [`ApiClient`] holds what every request shares — the base URL, the
+//! credentials ([`Auth`]) and the connection pool — and is cheap to clone.
+//! Each tag of the document is a group of endpoints, returned by the method
+//! of [`ApiClient`] named after it, with one method per operation. The
+//! request and response bodies are in [`models`].
+//!
+//! Failures are an [`ApiError`] sorted by who has to act: the caller for a
+//! 4xx ([`ApiError::Client`], with the service's [`Problem`] if it sent
+//! one), the service for a 5xx ([`ApiError::Server`]), and the network for
+//! anything that never got an answer ([`ApiError::Transport`]).
+
+{{#each api.modules}}
+{{#if public}}pub {{/if}}mod {{ name }};
+{{/each}}
+
+{{#each api.exports}}
+pub use {{ this }};
+{{/each}}
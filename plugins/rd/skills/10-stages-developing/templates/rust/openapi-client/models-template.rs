@@ -0,0 +1,44 @@
+//! Request and response bodies of {{ api.title }}, one type per schema of
+//! its OpenAPI document.
+
+use serde::{Deserialize, Serialize};
+{{#each api.structs}}
+
+{{#each doc}}
+{{ this }}
+{{/each}}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct {{ name }} {
+{{#each fields}}
+{{#each doc}}
+    {{ this }}
+{{/each}}
+{{#if serde}}
+    #[serde({{ serde }})]
+{{/if}}
+    pub {{ name }}: {{ type }},
+{{/each}}
+}
+{{/each}}
+{{#each api.enums}}
+
+{{#each doc}}
+{{ this }}
+{{/each}}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum {{ name }} {
+{{#each variants}}
+{{#if rename}}
+    #[serde(rename = {{ rename }})]
+{{/if}}
+    {{ name }},
+{{/each}}
+}
+{{/each}}
+{{#each api.aliases}}
+
+{{#each doc}}
+{{ this }}
+{{/each}}
+pub type {{ name }} = {{ type }};
+{{/each}}
@@ -0,0 +1,218 @@
+//! [Brief description of what this module does]
+//!
+//! Pages are cut by key rather than by offset: a [`Cursor`] holds the key
+//! of the last item a page returned, and the next page starts after it, so
+//! items inserted or deleted meanwhile do not shift or repeat results.
+//!
+//! Clients get the cursor as an opaque string — the hex of a version tag
+//! and the key — and hand it back unchanged. The version lets the format
+//! change later, while cursors already issued fail with a
+//! [`CursorError`] instead of being misread.
+
+use std::fmt;
+
+/// Largest page [`paginate`] returns, whatever limit is asked for.
+pub const MAX_PAGE_SIZE: usize = MAX_LIMIT;
+
+const VERSION: &str = "v1";
+
+/// Where a page starts: after the item with key `after`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    /// Key of the last item of the previous page.
+    pub after: u64,
+}
+
+/// Why a cursor string could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CursorError {
+    /// The cursor is not hex-encoded UTF-8.
+    Encoding,
+    /// The cursor was issued in a format this version does not read.
+    Version(String),
+    /// The cursor does not hold a valid key.
+    Key,
+}
+
+impl fmt::Display for CursorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Encoding => f.write_str("cursor is not validly encoded"),
+            Self::Version(version) => write!(f, "unsupported cursor version {version:?}"),
+            Self::Key => f.write_str("cursor does not hold a valid key"),
+        }
+    }
+}
+
+impl std::error::Error for CursorError {}
+
+impl Cursor {
+    /// The opaque string handed to clients.
+    pub fn encode(&self) -> String {
+        let text = format!("{VERSION}:{}", self.after);
+        text.bytes().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// Reads a cursor string made by [`Cursor::encode`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CursorError`] if `encoded` is not such a string.
+    pub fn decode(encoded: &str) -> Result<Self, CursorError> {
+        if !encoded.len().is_multiple_of(2) || !encoded.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(CursorError::Encoding);
+        }
+        let bytes = (0..encoded.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&encoded[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| CursorError::Encoding)?;
+        let text = String::from_utf8(bytes).map_err(|_| CursorError::Encoding)?;
+        let (version, key) = text.split_once(':').ok_or(CursorError::Encoding)?;
+        if version != VERSION {
+            return Err(CursorError::Version(version.to_string()));
+        }
+        let after = key.parse().map_err(|_| CursorError::Key)?;
+        Ok(Self { after })
+    }
+}
+
+/// One page of results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<T> {
+    /// The items of the page, in key order.
+    pub items: Vec<T>,
+    /// Cursor for the next page; `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// The page of `items` after `cursor`, or the first page without one.
+///
+/// `items` must be sorted by `key`, which must be unique. At most `limit`
+/// items are returned, clamped to between 1 and [`MAX_PAGE_SIZE`].
+///
+/// # Errors
+///
+/// Returns a [`CursorError`] if `cursor` cannot be decoded.
+pub fn paginate<T: Clone>(
+    items: &[T],
+    key: impl Fn(&T) -> u64,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<Page<T>, CursorError> {
+    let limit = limit.clamp(1, MAX_PAGE_SIZE);
+    let start = match cursor {
+        Some(cursor) => {
+            let after = Cursor::decode(cursor)?.after;
+            items.partition_point(|item| key(item) <= after)
+        }
+        None => 0,
+    };
+    // Look one item past the page to learn whether another one follows.
+    let rest = &items[start..];
+    let page = &rest[..rest.len().min(limit)];
+    let next_cursor = match page.last() {
+        Some(last) if rest.len() > limit => {
+            let cursor = Cursor { after: key(last) };
+            Some(cursor.encode())
+        }
+        _ => None,
+    };
+    Ok(Page {
+        items: page.to_vec(),
+        next_cursor,
+    })
+}
+
+{{> partials/test-header}}
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Row {
+        id: u64,
+    }
+
+    fn rows(ids: impl IntoIterator<Item = u64>) -> Vec<Row> {
+        ids.into_iter().map(|id| Row { id }).collect()
+    }
+
+    fn page(items: &[Row], cursor: Option<&str>, limit: usize) -> Page<Row> {
+        paginate(items, |row| row.id, cursor, limit).unwrap()
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = Cursor { after: 42 };
+        let encoded = cursor.encode();
+        assert!(encoded.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(Cursor::decode(&encoded), Ok(cursor));
+    }
+
+    #[test]
+    fn test_follows_cursors_to_last_page() {
+        let items = rows([2, 3, 5, 7, 11]);
+        let first = page(&items, None, 2);
+        assert_eq!(first.items, rows([2, 3]));
+        let second = page(&items, first.next_cursor.as_deref(), 2);
+        assert_eq!(second.items, rows([5, 7]));
+        let last = page(&items, second.next_cursor.as_deref(), 2);
+        assert_eq!(last.items, rows([11]));
+        assert_eq!(last.next_cursor, None);
+    }
+
+    #[test]
+    fn test_full_last_page_has_no_cursor() {
+        let items = rows(1..=4);
+        let first = page(&items, None, 2);
+        let last = page(&items, first.next_cursor.as_deref(), 2);
+        assert_eq!(last.items, rows([3, 4]));
+        assert_eq!(last.next_cursor, None);
+    }
+
+    #[test]
+    fn test_empty_pages() {
+        let empty = page(&[], None, 10);
+        assert!(empty.items.is_empty());
+        assert_eq!(empty.next_cursor, None);
+
+        // A cursor past the end, as after the last items were deleted.
+        let items = rows(1..=3);
+        let past_end = Cursor { after: 3 }.encode();
+        let after_end = page(&items, Some(&past_end), 10);
+        assert!(after_end.items.is_empty());
+        assert_eq!(after_end.next_cursor, None);
+    }
+
+    #[test]
+    fn test_cursor_survives_deleted_item() {
+        let items = rows(1..=6);
+        let first = page(&items, None, 3);
+        // Item 3, the cursor's own key, is gone by the next request.
+        let remaining = rows([1, 2, 4, 5, 6]);
+        let next = page(&remaining, first.next_cursor.as_deref(), 3);
+        assert_eq!(next.items, rows([4, 5, 6]));
+    }
+
+    #[test]
+    fn test_limit_is_clamped() {
+        let items = rows(0..(MAX_PAGE_SIZE as u64 + 10));
+        assert_eq!(page(&items, None, 0).items.len(), 1);
+        assert_eq!(page(&items, None, usize::MAX).items.len(), MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_rejects_invalid_cursors() {
+        let decode = |cursor: &str| Cursor::decode(cursor).unwrap_err();
+        assert_eq!(decode("zz"), CursorError::Encoding);
+        assert_eq!(decode("abc"), CursorError::Encoding);
+        assert_eq!(decode("ff"), CursorError::Encoding);
+        // "v0:1" and "v1:x", hex-encoded.
+        assert_eq!(decode("76303a31"), CursorError::Version("v0".to_string()));
+        assert_eq!(decode("76313a78"), CursorError::Key);
+
+        let items = rows(1..=3);
+        let result = paginate(&items, |row| row.id, Some("not a cursor"), 2);
+        assert_eq!(result, Err(CursorError::Encoding));
+        let message = CursorError::Version("v0".to_string()).to_string();
+        assert_eq!(message, "unsupported cursor version \"v0\"");
+    }
+}
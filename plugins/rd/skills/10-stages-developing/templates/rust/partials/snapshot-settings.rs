@@ -0,0 +1,13 @@
+    /// insta settings for this module's snapshots: each is a `.snap` file
+    /// named after its test's snapshot name, in `snapshots/` beside this
+    /// file, and each of `redacted` is replaced with `[<field>]` wherever it
+    /// appears, for values that change from run to run such as IDs and
+    /// timestamps. Review new and changed snapshots with `cargo insta review`.
+    fn snapshot_settings(redacted: &[&str]) -> insta::Settings {
+        let mut settings = insta::Settings::clone_current();
+        settings.set_prepend_module_to_snapshot(false);
+        for field in redacted {
+            settings.add_redaction(&format!(".**.{field}"), format!("[{field}]"));
+        }
+        settings
+    }
@@ -0,0 +1,3 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
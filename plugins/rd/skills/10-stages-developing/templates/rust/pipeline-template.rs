@@ -0,0 +1,202 @@
+//! [Brief description of what this module does]
+//!
+//! [`pipeline_name`] runs a producer → transform → consumer pipeline. The
+//! producer and the transform each get a thread, and the consumer runs on
+//! the caller's. The stages are joined by `sync_channel`s of
+//! [`CHANNEL_CAPACITY`] slots, so a stage that runs ahead blocks once the
+//! next falls behind, rather than buffering without bound. With a single
+//! transform thread, outputs arrive in input order.
+//!
+//! Shutdown follows the channels. When the inputs run out, the producer
+//! drops its sender; the transform then drains the channel and drops its
+//! own, and the consumer returns after the last output. When the consumer
+//! breaks early, it drops its receiver instead; the next send upstream
+//! fails, and the earlier stages stop the same way. A stage that panics
+//! closes its channels too, and is reported as a [`PipelineError`].
+//!
+//! For async code, `tokio::sync::mpsc::channel` and `tokio::spawn` take
+//! the place of `sync_channel` and `thread::spawn`.
+
+use std::fmt;
+use std::ops::ControlFlow;
+use std::sync::mpsc;
+use std::thread;
+
+/// Items each channel holds before its sender blocks.
+pub const CHANNEL_CAPACITY: usize = CHANNEL_BOUND;
+
+/// A pipeline stage that panicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipelineError {
+    /// The producer panicked while yielding an input.
+    Producer,
+    /// The transform panicked on an input.
+    Transform,
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stage = match self {
+            Self::Producer => "producer",
+            Self::Transform => "transform",
+        };
+        write!(f, "the {stage} stage panicked")
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+/// Feeds `inputs` through `transform` into `consume` until the inputs run
+/// out or `consume` breaks, and waits for every stage to stop.
+///
+/// # Errors
+///
+/// Returns the first stage that panicked; the outputs consumed until
+/// then are kept by `consume`.
+pub fn pipeline_name<I, T, O>(
+    inputs: I,
+    mut transform: impl FnMut(T) -> O + Send + 'static,
+    mut consume: impl FnMut(O) -> ControlFlow<()>,
+) -> Result<(), PipelineError>
+where
+    I: IntoIterator<Item = T>,
+    I::IntoIter: Send + 'static,
+    T: Send + 'static,
+    O: Send + 'static,
+{
+    let inputs = inputs.into_iter();
+    let (input_tx, input_rx) = mpsc::sync_channel(CHANNEL_CAPACITY);
+    let (output_tx, output_rx) = mpsc::sync_channel(CHANNEL_CAPACITY);
+
+    let producer = thread::spawn(move || {
+        for input in inputs {
+            // The transform has stopped, so nothing will read the rest.
+            if input_tx.send(input).is_err() {
+                break;
+            }
+        }
+    });
+    let transformer = thread::spawn(move || {
+        for input in input_rx {
+            if output_tx.send(transform(input)).is_err() {
+                break;
+            }
+        }
+    });
+
+    for output in output_rx.iter() {
+        if consume(output).is_break() {
+            break;
+        }
+    }
+    // Closes the output channel if the consumer broke early.
+    drop(output_rx);
+
+    let transformed = transformer.join();
+    let produced = producer.join();
+    if produced.is_err() {
+        Err(PipelineError::Producer)
+    } else if transformed.is_err() {
+        Err(PipelineError::Transform)
+    } else {
+        Ok(())
+    }
+}
+
+{{> partials/test-header}}
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    type Outcome = (Vec<u32>, Result<(), PipelineError>);
+
+    /// Runs the pipeline over `inputs` with `transform`, and collects the
+    /// outputs.
+    fn collect<I>(inputs: I, transform: fn(u32) -> u32) -> Outcome
+    where
+        I: IntoIterator<Item = u32>,
+        I::IntoIter: Send + 'static,
+    {
+        let mut outputs = Vec::new();
+        let consume = |n| {
+            outputs.push(n);
+            ControlFlow::Continue(())
+        };
+        let result = pipeline_name(inputs, transform, consume);
+        (outputs, result)
+    }
+
+    #[test]
+    fn test_outputs_keep_input_order() {
+        let (outputs, result) = collect(0..500, |n| n * 2);
+        assert_eq!(result, Ok(()));
+        assert_eq!(outputs, (0..500).map(|n| n * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_empty_input_shuts_down() {
+        assert_eq!(collect(Vec::new(), |n| n), (Vec::new(), Ok(())));
+    }
+
+    #[test]
+    fn test_bounded_channels_hold_back_producer() {
+        let produced = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&produced);
+        let inputs = (0..1000).inspect(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        let mut seen = 0;
+        let consume = |_| {
+            if seen == 0 {
+                // Give the producer time to run ahead, which the full
+                // channels stop it doing.
+                thread::sleep(Duration::from_millis(50));
+                // One item in each channel slot, one held by each stage
+                // and the one being consumed.
+                let limit = 2 * CHANNEL_CAPACITY + 3;
+                assert!(produced.load(Ordering::SeqCst) <= limit);
+            }
+            seen += 1;
+            ControlFlow::Continue(())
+        };
+        assert_eq!(pipeline_name(inputs, |n: u32| n, consume), Ok(()));
+        assert_eq!(seen, 1000);
+    }
+
+    #[test]
+    fn test_consumer_break_stops_upstream() {
+        let mut outputs = Vec::new();
+        let consume = |n| {
+            outputs.push(n);
+            if outputs.len() == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        };
+        // The inputs never run out, so this only returns because the
+        // channels closed behind the consumer.
+        assert_eq!(pipeline_name(0.., |n: u64| n + 1, consume), Ok(()));
+        assert_eq!(outputs, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_transform_panic_is_reported() {
+        let (outputs, result) = collect(0..10, |n| {
+            assert!(n < 3, "cannot transform {n}");
+            n
+        });
+        assert_eq!(result, Err(PipelineError::Transform));
+        assert_eq!(outputs, [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_producer_panic_is_reported() {
+        let inputs = (0..10).inspect(|&n| assert!(n < 5, "cannot produce {n}"));
+        let (outputs, result) = collect(inputs, |n| n);
+        assert_eq!(result, Err(PipelineError::Producer));
+        assert_eq!(outputs, [0, 1, 2, 3, 4]);
+        let message = PipelineError::Producer.to_string();
+        assert_eq!(message, "the producer stage panicked");
+    }
+}
@@ -0,0 +1,220 @@
+//! [Brief description of what this module does]
+//!
+//! A [`PluginName`] is registered as a trait object in a
+//! [`PluginNameRegistry`], under the unique name it reports. The registry
+//! keeps its plugins ordered by [`priority`](PluginName::priority), highest
+//! first, with ties in registration order, and
+//! [`method_name`](PluginNameRegistry::method_name) runs them in that order.
+
+use std::fmt;
+
+/// [Brief description of what plugins provide]
+pub trait PluginName: Send + Sync {
+    /// Unique name the plugin is registered and looked up under.
+    fn name(&self) -> &str;
+
+    /// Position among the registered plugins: higher runs first.
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    /// [Brief description of what the method does]
+    fn method_name(&self, input: String) -> String;
+}
+
+/// Why a plugin could not be registered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// A plugin with this name is already registered.
+    Duplicate(String),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Duplicate(name) => write!(f, "plugin {name:?} is already registered"),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// The registered [`PluginName`]s, in priority order.
+#[derive(Default)]
+pub struct PluginNameRegistry {
+    plugins: Vec<Box<dyn PluginName>>,
+}
+
+impl PluginNameRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `plugin` after every plugin of the same or a higher priority.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegistryError::Duplicate`] if a plugin with its name is
+    /// already registered.
+    pub fn register<P>(&mut self, plugin: P) -> Result<(), RegistryError>
+    where
+        P: PluginName + 'static,
+    {
+        if self.get(plugin.name()).is_some() {
+            return Err(RegistryError::Duplicate(plugin.name().to_string()));
+        }
+        let priority = plugin.priority();
+        let index = self
+            .plugins
+            .partition_point(|existing| existing.priority() >= priority);
+        self.plugins.insert(index, Box::new(plugin));
+        Ok(())
+    }
+
+    /// Removes the plugin registered as `name`, returning it.
+    pub fn unregister(&mut self, name: &str) -> Option<Box<dyn PluginName>> {
+        let index = self.plugins.iter().position(|p| p.name() == name)?;
+        Some(self.plugins.remove(index))
+    }
+
+    /// The plugin registered as `name`.
+    pub fn get(&self, name: &str) -> Option<&dyn PluginName> {
+        self.plugins
+            .iter()
+            .find(|plugin| plugin.name() == name)
+            .map(|plugin| plugin.as_ref())
+    }
+
+    /// The registered plugins, in the order they run.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn PluginName> {
+        self.plugins.iter().map(|plugin| plugin.as_ref())
+    }
+
+    /// Names of the registered plugins, in the order they run.
+    pub fn names(&self) -> Vec<&str> {
+        self.iter().map(|plugin| plugin.name()).collect()
+    }
+
+    /// Passes `input` through every plugin in order, each getting the
+    /// output of the one before.
+    pub fn method_name(&self, input: String) -> String {
+        let mut value = input;
+        for plugin in self.iter() {
+            value = plugin.method_name(value);
+        }
+        value
+    }
+
+    /// Number of registered plugins.
+    pub fn len(&self) -> usize {
+        self.plugins.len()
+    }
+
+    /// Whether no plugins are registered.
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+}
+
+impl fmt::Debug for PluginNameRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PluginNameRegistry")
+            .field("plugins", &self.names())
+            .finish()
+    }
+}
+
+{{> partials/test-header}}
+
+    /// Upper-cases its input, ahead of default-priority plugins.
+    struct Shout;
+
+    impl PluginName for Shout {
+        fn name(&self) -> &str {
+            "shout"
+        }
+
+        fn priority(&self) -> i32 {
+            10
+        }
+
+        fn method_name(&self, input: String) -> String {
+            input.to_uppercase()
+        }
+    }
+
+    /// Appends a fixed suffix, at the default priority.
+    struct Suffix(&'static str);
+
+    impl PluginName for Suffix {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn method_name(&self, input: String) -> String {
+            input + self.0
+        }
+    }
+
+    #[test]
+    fn test_runs_plugins_by_priority() {
+        let mut registry = PluginNameRegistry::new();
+        registry.register(Suffix("!")).unwrap();
+        registry.register(Shout).unwrap();
+        assert_eq!(registry.names(), ["shout", "!"]);
+        // Shouting first leaves the suffix alone.
+        assert_eq!(registry.method_name("hi".to_string()), "HI!");
+    }
+
+    #[test]
+    fn test_equal_priorities_keep_registration_order() {
+        let mut registry = PluginNameRegistry::new();
+        for suffix in ["a", "b", "c"] {
+            registry.register(Suffix(suffix)).unwrap();
+        }
+        registry.register(Shout).unwrap();
+        assert_eq!(registry.names(), ["shout", "a", "b", "c"]);
+        assert_eq!(registry.method_name(String::new()), "abc");
+    }
+
+    #[test]
+    fn test_looks_up_by_name() {
+        let mut registry = PluginNameRegistry::new();
+        registry.register(Shout).unwrap();
+        let plugin = registry.get("shout").unwrap();
+        assert_eq!(plugin.priority(), 10);
+        assert_eq!(plugin.method_name("ok".to_string()), "OK");
+        assert!(registry.get("missing").is_none());
+    }
+
+    #[test]
+    fn test_rejects_duplicate_names() {
+        let mut registry = PluginNameRegistry::new();
+        registry.register(Suffix("x")).unwrap();
+        let err = registry.register(Suffix("x")).unwrap_err();
+        assert_eq!(err, RegistryError::Duplicate("x".to_string()));
+        assert_eq!(err.to_string(), "plugin \"x\" is already registered");
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_unregister_removes_plugin() {
+        let mut registry = PluginNameRegistry::new();
+        registry.register(Shout).unwrap();
+        registry.register(Suffix("?")).unwrap();
+        let removed = registry.unregister("shout").unwrap();
+        assert_eq!(removed.name(), "shout");
+        assert_eq!(registry.names(), ["?"]);
+        assert!(registry.unregister("shout").is_none());
+        let debug = format!("{registry:?}");
+        assert_eq!(debug, "PluginNameRegistry { plugins: [\"?\"] }");
+    }
+
+    #[test]
+    fn test_empty_registry_passes_input_through() {
+        let registry = PluginNameRegistry::new();
+        assert!(registry.is_empty());
+        assert_eq!(registry.method_name("same".to_string()), "same");
+    }
+}
@@ -0,0 +1,68 @@
+// [Brief description of what this module does]
+
+use std::num::ParseIntError;
+
+/// [Brief description of what the function does]
+///
+/// Values are written in decimal and separated by commas; an empty slice
+/// is the empty string.
+pub fn function_name(values: &[u32]) -> String {
+    values
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Reads back what [`function_name`] writes.
+///
+/// # Errors
+///
+/// Returns the parse error of the first item that is not a `u32`.
+pub fn inverse_name(text: &str) -> Result<Vec<u32>, ParseIntError> {
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+    text.split(',').map(str::parse).collect()
+}
+
+{{> partials/test-header}}
+    use proptest::prelude::*;
+
+    // Cases that once failed are saved to
+    // proptest-regressions/<module>.txt and replayed first on every run;
+    // check that file in. PROPTEST_CASES changes how many new cases run.
+
+    /// Inputs for `function_name`, shrinking towards short slices of
+    /// small values.
+    fn values() -> impl Strategy<Value = Vec<u32>> {
+        prop::collection::vec(any::<u32>(), 0..=MAX_LEN)
+    }
+
+    proptest! {
+        #[test]
+        fn test_function_name_roundtrip(values in values()) {
+            prop_assert_eq!(inverse_name(&function_name(&values)), Ok(values));
+        }
+
+        #[test]
+        fn test_function_name_separates_values(values in values()) {
+            let text = function_name(&values);
+            prop_assert_eq!(text.matches(',').count(), values.len().saturating_sub(1));
+            prop_assert!(text.chars().all(|c| c.is_ascii_digit() || c == ','));
+        }
+
+        #[test]
+        fn test_inverse_name_accepts_any_text(text in "\\PC*") {
+            // Garbage is an error, never a panic.
+            let _ = inverse_name(&text);
+        }
+    }
+
+    #[test]
+    fn test_function_name_examples() {
+        assert_eq!(function_name(&[]), "");
+        assert_eq!(function_name(&[1, 20, 300]), "1,20,300");
+        assert!(inverse_name("1,,2").is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! [Brief description of what this module does]
+//!
+//! [`TypeName`] is what the rest of the crate works with, and [`proto`]
+//! holds the message `build.rs` generates from `proto/{{ module }}.proto`.
+//! A `TypeName` converts into its message with `From` and back with
+//! `TryFrom`, which checks what the wire format cannot: prost decodes any
+//! well-formed buffer, leaving each field missing from it at its default,
+//! so an empty name or an unset or unknown status is rejected there.
+//! [`TypeName::encode`] and [`TypeName::decode`] go to and from bytes in
+//! one step.
+
+use std::fmt;
+
+use prost::Message;
+
+/// Code generated from `proto/{{ module }}.proto`.
+#[allow(clippy::all, missing_docs)]
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/proto_package.rs"));
+}
+
+/// A record, as the crate works with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeName {
+    /// Identifier.
+    pub id: u64,
+    /// Never empty.
+    pub name: String,
+    /// Where the record is in its life.
+    pub status: Status,
+    /// In the order they were added.
+    pub tags: Vec<String>,
+    /// Absent unless given.
+    pub note: Option<String>,
+}
+
+/// Where a [`TypeName`] is in its life.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// In use.
+    Active,
+    /// Kept, but no longer in use.
+    Archived,
+}
+
+/// Why bytes or a message could not be read as a [`TypeName`].
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The bytes are not a `proto_package.TypeName` message.
+    Wire(prost::DecodeError),
+    /// The message has no name.
+    MissingName,
+    /// The message's status is unset, or one this build does not know.
+    UnknownStatus(i32),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wire(err) => write!(f, "invalid TypeName message: {err}"),
+            Self::MissingName => f.write_str("TypeName message has no name"),
+            Self::UnknownStatus(status) => {
+                write!(f, "TypeName message has unknown status {status}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Wire(err) => Some(err),
+            Self::MissingName | Self::UnknownStatus(_) => None,
+        }
+    }
+}
+
+impl From<prost::DecodeError> for DecodeError {
+    fn from(err: prost::DecodeError) -> Self {
+        Self::Wire(err)
+    }
+}
+
+impl TypeName {
+    /// Encodes the record as a `proto_package.TypeName` message.
+    pub fn encode(&self) -> Vec<u8> {
+        proto::TypeName::from(self.clone()).encode_to_vec()
+    }
+
+    /// Decodes a record from a `proto_package.TypeName` message.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        proto::TypeName::decode(bytes)?.try_into()
+    }
+}
+
+impl From<TypeName> for proto::TypeName {
+    fn from(value: TypeName) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            status: proto::Status::from(value.status).into(),
+            tags: value.tags,
+            note: value.note,
+        }
+    }
+}
+
+impl TryFrom<proto::TypeName> for TypeName {
+    type Error = DecodeError;
+
+    fn try_from(message: proto::TypeName) -> Result<Self, Self::Error> {
+        if message.name.is_empty() {
+            return Err(DecodeError::MissingName);
+        }
+        let status = match proto::Status::try_from(message.status) {
+            Ok(proto::Status::Active) => Status::Active,
+            Ok(proto::Status::Archived) => Status::Archived,
+            Ok(proto::Status::Unspecified) | Err(_) => {
+                return Err(DecodeError::UnknownStatus(message.status));
+            }
+        };
+        Ok(Self {
+            id: message.id,
+            name: message.name,
+            status,
+            tags: message.tags,
+            note: message.note,
+        })
+    }
+}
+
+impl From<Status> for proto::Status {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Active => Self::Active,
+            Status::Archived => Self::Archived,
+        }
+    }
+}
+
+{{> partials/test-header}}
+
+    fn message() -> proto::TypeName {
+        proto::TypeName {
+            id: 7,
+            name: "first".to_string(),
+            status: proto::Status::Archived.into(),
+            tags: vec!["a".to_string(), "b".to_string()],
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_message_converts_both_ways() {
+        let value = TypeName::try_from(message()).unwrap();
+        assert_eq!(value.status, Status::Archived);
+        assert_eq!(value.tags, ["a", "b"]);
+        assert_eq!(proto::TypeName::from(value), message());
+    }
+
+    #[test]
+    fn test_try_from_rejects_empty_name() {
+        let message = proto::TypeName {
+            name: String::new(),
+            ..message()
+        };
+        assert!(matches!(
+            TypeName::try_from(message),
+            Err(DecodeError::MissingName)
+        ));
+    }
+
+    #[test]
+    fn test_try_from_rejects_unset_and_unknown_status() {
+        for status in [proto::Status::Unspecified.into(), 99] {
+            let message = proto::TypeName {
+                status,
+                ..message()
+            };
+            assert!(matches!(
+                TypeName::try_from(message),
+                Err(DecodeError::UnknownStatus(s)) if s == status
+            ));
+        }
+    }
+}
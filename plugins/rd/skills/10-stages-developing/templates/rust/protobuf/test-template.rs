@@ -0,0 +1,70 @@
+//! `TypeName` through its wire format, as another service would send and
+//! read it.
+
+use crate_name::{{ module }}::proto;
+use crate_name::{{ module }}::{DecodeError, Status, TypeName};
+use prost::Message;
+
+fn sample() -> TypeName {
+    TypeName {
+        id: 42,
+        name: "Ada".to_string(),
+        status: Status::Active,
+        tags: vec!["urgent".to_string(), "fragile".to_string()],
+        note: Some("Leave at the door".to_string()),
+    }
+}
+
+#[test]
+fn test_encode_decode_roundtrip() {
+    let value = sample();
+    assert_eq!(TypeName::decode(&value.encode()).unwrap(), value);
+}
+
+#[test]
+fn test_roundtrip_without_optional_fields() {
+    let value = TypeName {
+        tags: Vec::new(),
+        note: None,
+        ..sample()
+    };
+    assert_eq!(TypeName::decode(&value.encode()).unwrap(), value);
+}
+
+#[test]
+fn test_decode_message_from_another_writer() {
+    let message = proto::TypeName {
+        id: 42,
+        name: "Ada".to_string(),
+        status: proto::Status::Active.into(),
+        tags: vec!["urgent".to_string(), "fragile".to_string()],
+        note: Some("Leave at the door".to_string()),
+    };
+    let bytes = message.encode_to_vec();
+    assert_eq!(TypeName::decode(&bytes).unwrap(), sample());
+}
+
+#[test]
+fn test_decode_ignores_unknown_fields() {
+    let mut bytes = sample().encode();
+    // Field 15 as a varint holding 1, as a newer writer might add.
+    bytes.extend([15 << 3, 1]);
+    assert_eq!(TypeName::decode(&bytes).unwrap(), sample());
+}
+
+#[test]
+fn test_decode_rejects_truncated_input() {
+    let bytes = sample().encode();
+    let err = TypeName::decode(&bytes[..bytes.len() - 1]).unwrap_err();
+    assert!(matches!(err, DecodeError::Wire(_)), "{err:?}");
+}
+
+#[test]
+fn test_decode_rejects_message_without_status() {
+    let message = proto::TypeName {
+        status: proto::Status::Unspecified.into(),
+        ..proto::TypeName::from(sample())
+    };
+    let err = TypeName::decode(&message.encode_to_vec()).unwrap_err();
+    assert!(matches!(err, DecodeError::UnknownStatus(0)), "{err:?}");
+}
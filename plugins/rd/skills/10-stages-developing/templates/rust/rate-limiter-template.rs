@@ -0,0 +1,239 @@
+//! [Brief description of what this module does]
+//!
+//! [`LimiterName`] is a token bucket: it holds up to `capacity` tokens,
+//! gains one every `refill` and spends one per admitted call, so it allows
+//! bursts of `capacity` and a steady rate of one call per `refill`.
+//! [`LimiterName::try_acquire`] says how long until the next token;
+//! [`LimiterName::acquire`] blocks the thread for that long and
+//! [`LimiterName::acquire_async`] awaits it on the tokio timer.
+//!
+//! The bucket is kept as a `Duration` of credit, one `refill` per token, so
+//! the refill is exact integer arithmetic. Time goes through a [`Clock`],
+//! which tests replace with a virtual one.
+
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The time source of a [`LimiterName`].
+pub trait Clock {
+    /// The current instant.
+    fn now(&self) -> Instant;
+    /// Blocks for `duration`; used by [`LimiterName::acquire`].
+    fn sleep(&self, duration: Duration);
+}
+
+/// The real clock: [`Instant::now`] and [`thread::sleep`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Tokens held, as `refill` per token.
+    credit: Duration,
+    updated: Instant,
+}
+
+/// A token-bucket rate limiter.
+#[derive(Debug)]
+pub struct LimiterName<C = SystemClock> {
+    capacity: u32,
+    refill: Duration,
+    clock: C,
+    bucket: Mutex<Bucket>,
+}
+
+impl LimiterName {
+    /// Creates a full bucket of `capacity` tokens that gains one every
+    /// `refill`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero or `refill` is zero.
+    pub fn new(capacity: u32, refill: Duration) -> Self {
+        Self::with_clock(capacity, refill, SystemClock)
+    }
+}
+
+impl<C: Clock> LimiterName<C> {
+    /// Creates a full bucket, as [`LimiterName::new`], timed by `clock`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero or `refill` is zero.
+    pub fn with_clock(capacity: u32, refill: Duration, clock: C) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        assert!(!refill.is_zero(), "refill interval must be positive");
+        let bucket = Bucket {
+            credit: refill * capacity,
+            updated: clock.now(),
+        };
+        Self {
+            capacity,
+            refill,
+            clock,
+            bucket: Mutex::new(bucket),
+        }
+    }
+
+    /// Takes a token if one is available; otherwise returns how long until
+    /// one will be.
+    ///
+    /// # Errors
+    ///
+    /// Returns the time to wait before trying again.
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        let now = self.clock.now();
+        let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+        let elapsed = now.saturating_duration_since(bucket.updated);
+        bucket.credit = (bucket.credit + elapsed).min(self.refill * self.capacity);
+        bucket.updated = now;
+        match bucket.credit.checked_sub(self.refill) {
+            Some(credit) => {
+                bucket.credit = credit;
+                Ok(())
+            }
+            None => Err(self.refill - bucket.credit),
+        }
+    }
+
+    /// Takes a token, blocking the thread until one is available.
+    pub fn acquire(&self) {
+        while let Err(wait) = self.try_acquire() {
+            self.clock.sleep(wait);
+        }
+    }
+
+    /// Takes a token, waiting on the tokio timer until one is available.
+    pub async fn acquire_async(&self) {
+        while let Err(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+{{> partials/test-header}}
+    use std::cell::{Cell, RefCell};
+
+    /// A clock whose sleeps only move its time forward.
+    struct VirtualClock {
+        start: Instant,
+        now: Cell<Instant>,
+        sleeps: RefCell<Vec<Duration>>,
+    }
+
+    impl VirtualClock {
+        fn new() -> Self {
+            let start = Instant::now();
+            Self {
+                start,
+                now: Cell::new(start),
+                sleeps: RefCell::default(),
+            }
+        }
+
+        fn elapsed(&self) -> Duration {
+            self.now.get() - self.start
+        }
+
+        fn advance(&self, duration: Duration) {
+            self.now.set(self.now.get() + duration);
+        }
+    }
+
+    impl Clock for &VirtualClock {
+        fn now(&self) -> Instant {
+            self.now.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.advance(duration);
+            self.sleeps.borrow_mut().push(duration);
+        }
+    }
+
+    /// Reads tokio's clock, which a paused runtime only moves forward
+    /// when every task is waiting on a timer.
+    struct TokioClock;
+
+    impl Clock for TokioClock {
+        fn now(&self) -> Instant {
+            tokio::time::Instant::now().into_std()
+        }
+
+        fn sleep(&self, _duration: Duration) {
+            unreachable!("only acquire_async is used with the tokio clock");
+        }
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_admits_burst_then_refill_rate() {
+        let clock = VirtualClock::new();
+        let limiter = LimiterName::with_clock(3, ms(100), &clock);
+        let mut admitted = Vec::new();
+        for _ in 0..6 {
+            limiter.acquire();
+            admitted.push(clock.elapsed());
+        }
+        let expected = [0, 0, 0, 100, 200, 300].map(ms);
+        assert_eq!(admitted, expected);
+        assert_eq!(*clock.sleeps.borrow(), [ms(100); 3]);
+    }
+
+    #[test]
+    fn test_try_acquire_reports_wait() {
+        let clock = VirtualClock::new();
+        let limiter = LimiterName::with_clock(1, ms(100), &clock);
+        assert_eq!(limiter.try_acquire(), Ok(()));
+        assert_eq!(limiter.try_acquire(), Err(ms(100)));
+        clock.advance(ms(40));
+        assert_eq!(limiter.try_acquire(), Err(ms(60)));
+        clock.advance(ms(60));
+        assert_eq!(limiter.try_acquire(), Ok(()));
+    }
+
+    #[test]
+    fn test_idle_refill_stops_at_capacity() {
+        let clock = VirtualClock::new();
+        let limiter = LimiterName::with_clock(2, ms(100), &clock);
+        limiter.acquire();
+        limiter.acquire();
+        clock.advance(ms(10_000));
+        assert_eq!(limiter.try_acquire(), Ok(()));
+        assert_eq!(limiter.try_acquire(), Ok(()));
+        assert_eq!(limiter.try_acquire(), Err(ms(100)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_acquire_async_waits_for_tokens() {
+        let start = tokio::time::Instant::now();
+        let limiter = LimiterName::with_clock(2, ms(250), TokioClock);
+        let mut admitted = Vec::new();
+        for _ in 0..4 {
+            limiter.acquire_async().await;
+            admitted.push(start.elapsed());
+        }
+        let expected = [0, 0, 250, 500].map(ms);
+        assert_eq!(admitted, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "refill interval must be positive")]
+    fn test_new_rejects_zero_refill() {
+        let _ = LimiterName::new(1, Duration::ZERO);
+    }
+}
@@ -0,0 +1,465 @@
+//! [Brief description of what this module does]
+//!
+//! [`CacheName`] keeps JSON-encoded values under a key prefix, each with a
+//! time to live, so entries expire on their own; [`CacheName::touch`]
+//! restarts one's TTL, as a session store does on each request. It talks
+//! to a [`Store`]: [`RedisStore`] over a pool of Redis connections, or
+//! [`MemoryStore`] in tests and local runs without a server.
+//!
+//! ```ignore
+//! // Needs a running Redis server.
+//! let store = RedisStore::new("redis://127.0.0.1/")?;
+//! let sessions = CacheName::new(store, "session", Duration::from_secs(30 * 60));
+//! sessions.set(&token, &session).await?;
+//! let session: Option<Session> = sessions.get(&token).await?;
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use deadpool_redis::redis::{AsyncCommands, RedisError};
+use deadpool_redis::{Config, CreatePoolError, Pool, PoolError, Runtime};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::time::Instant;
+
+/// Why a cache call failed.
+#[derive(Debug)]
+pub enum CacheError {
+    /// No connection could be taken from the pool.
+    Pool(PoolError),
+    /// Redis rejected a command, or the connection failed.
+    Redis(RedisError),
+    /// A value could not be encoded as JSON, or a stored one decoded.
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pool(err) => write!(f, "no connection to the cache: {err}"),
+            Self::Redis(err) => write!(f, "cache command failed: {err}"),
+            Self::Json(err) => write!(f, "cache value is not valid: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Pool(err) => Some(err),
+            Self::Redis(err) => Some(err),
+            Self::Json(err) => Some(err),
+        }
+    }
+}
+
+impl From<PoolError> for CacheError {
+    fn from(err: PoolError) -> Self {
+        Self::Pool(err)
+    }
+}
+
+impl From<RedisError> for CacheError {
+    fn from(err: RedisError) -> Self {
+        Self::Redis(err)
+    }
+}
+
+impl From<serde_json::Error> for CacheError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+/// Result of a cache call.
+pub type Result<T> = std::result::Result<T, CacheError>;
+
+/// The commands a [`CacheName`] sends, on string keys and values. TTLs are
+/// kept in whole milliseconds, and are at least one.
+pub trait Store {
+    /// The value at `key`, unless there is none or it has expired.
+    fn get(&self, key: &str) -> impl Future<Output = Result<Option<String>>> + Send;
+
+    /// Stores `value` at `key` for `ttl`, replacing any value and TTL.
+    fn set(
+        &self,
+        key: &str,
+        value: String,
+        ttl: Duration,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Removes `key`; whether there was a value to remove.
+    fn delete(&self, key: &str) -> impl Future<Output = Result<bool>> + Send;
+
+    /// Restarts the TTL of `key` at `ttl`; whether there was a value.
+    fn expire(&self, key: &str, ttl: Duration) -> impl Future<Output = Result<bool>> + Send;
+}
+
+fn millis(ttl: Duration) -> u64 {
+    u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX).max(1)
+}
+
+/// A [`Store`] on a Redis server, through a connection pool; clones share
+/// the pool.
+#[derive(Clone)]
+pub struct RedisStore {
+    pool: Pool,
+}
+
+impl RedisStore {
+    /// A store with a default-sized pool of connections to `url`, e.g.
+    /// `redis://127.0.0.1/`. Connections are opened when first needed.
+    ///
+    /// # Errors
+    ///
+    /// [`CreatePoolError`] if `url` is not a Redis URL.
+    pub fn new(url: &str) -> std::result::Result<Self, CreatePoolError> {
+        let pool = Config::from_url(url).create_pool(Some(Runtime::Tokio1))?;
+        Ok(Self { pool })
+    }
+
+    /// A store using an already configured `pool`.
+    pub fn from_pool(pool: Pool) -> Self {
+        Self { pool }
+    }
+}
+
+impl fmt::Debug for RedisStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisStore")
+            .field("pool", &self.pool.status())
+            .finish()
+    }
+}
+
+impl Store for RedisStore {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        let mut conn = self.pool.get().await?;
+        Ok(conn.get(key).await?)
+    }
+
+    async fn set(&self, key: &str, value: String, ttl: Duration) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+        let () = conn.pset_ex(key, value, millis(ttl)).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let removed: usize = conn.del(key).await?;
+        Ok(removed > 0)
+    }
+
+    async fn expire(&self, key: &str, ttl: Duration) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let ttl = i64::try_from(millis(ttl)).unwrap_or(i64::MAX);
+        Ok(conn.pexpire(key, ttl).await?)
+    }
+}
+
+/// A [`Store`] in process memory, expiring entries as Redis does; clones
+/// share the entries. Time is `tokio`'s, so tests that pause it can move
+/// past a TTL at once.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    entries: Arc<Mutex<HashMap<String, (String, Instant)>>>,
+}
+
+impl MemoryStore {
+    /// A store with no entries.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `f` on the entries, with expired ones removed first.
+    fn with_entries<T>(&self, f: impl FnOnce(&mut HashMap<String, (String, Instant)>) -> T) -> T {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        entries.retain(|_, (_, expires)| *expires > now);
+        f(&mut entries)
+    }
+}
+
+/// When an entry stored now for `ttl` expires.
+fn expiry(ttl: Duration) -> Instant {
+    Instant::now() + Duration::from_millis(millis(ttl))
+}
+
+impl Store for MemoryStore {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.with_entries(|entries| entries.get(key).map(|(value, _)| value.clone())))
+    }
+
+    async fn set(&self, key: &str, value: String, ttl: Duration) -> Result<()> {
+        let entry = (value, expiry(ttl));
+        self.with_entries(|entries| entries.insert(key.to_string(), entry));
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        Ok(self.with_entries(|entries| entries.remove(key).is_some()))
+    }
+
+    async fn expire(&self, key: &str, ttl: Duration) -> Result<bool> {
+        Ok(self.with_entries(|entries| match entries.get_mut(key) {
+            Some((_, expires)) => {
+                *expires = expiry(ttl);
+                true
+            }
+            None => false,
+        }))
+    }
+}
+
+/// Typed values in a [`Store`], under `prefix:`, expiring after a TTL.
+///
+/// A stored value that no longer decodes as the type asked for is an
+/// error, not a miss: when a cached type changes incompatibly, change the
+/// prefix too, and the old entries expire unread.
+#[derive(Debug, Clone)]
+pub struct CacheName<S> {
+    store: S,
+    prefix: String,
+    ttl: Duration,
+}
+
+impl<S: Store> CacheName<S> {
+    /// A cache keeping values in `store` under `prefix:` for `ttl`, unless
+    /// [`set_with_ttl`](Self::set_with_ttl) says otherwise.
+    pub fn new(store: S, prefix: impl Into<String>, ttl: Duration) -> Self {
+        Self {
+            store,
+            prefix: prefix.into(),
+            ttl,
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}:{key}", self.prefix)
+    }
+
+    /// The value at `key`, unless there is none or it has expired.
+    ///
+    /// # Errors
+    ///
+    /// [`CacheError::Json`] if the stored value is not a `T`, and the
+    /// store's error if it fails.
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        match self.store.get(&self.key(key)).await? {
+            Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores `value` at `key` for the cache's TTL.
+    ///
+    /// # Errors
+    ///
+    /// [`CacheError::Json`] if `value` cannot be encoded, and the store's
+    /// error if it fails.
+    pub async fn set<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> Result<()> {
+        self.set_with_ttl(key, value, self.ttl).await
+    }
+
+    /// Stores `value` at `key` for `ttl`.
+    ///
+    /// # Errors
+    ///
+    /// As for [`set`](Self::set).
+    pub async fn set_with_ttl<T: Serialize + ?Sized>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> Result<()> {
+        let json = serde_json::to_string(value)?;
+        self.store.set(&self.key(key), json, ttl).await
+    }
+
+    /// Removes `key`; whether there was a value to remove.
+    ///
+    /// # Errors
+    ///
+    /// The store's error if it fails.
+    pub async fn remove(&self, key: &str) -> Result<bool> {
+        self.store.delete(&self.key(key)).await
+    }
+
+    /// Keeps the value at `key` for another full TTL from now; whether
+    /// there was one.
+    ///
+    /// # Errors
+    ///
+    /// The store's error if it fails.
+    pub async fn touch(&self, key: &str) -> Result<bool> {
+        self.store.expire(&self.key(key), self.ttl).await
+    }
+
+    /// The value at `key`, or else the one `load` returns, which is stored
+    /// there for the cache's TTL. A failed `load` stores nothing.
+    ///
+    /// # Errors
+    ///
+    /// `load`'s error, or the cache's, converted into `E`.
+    pub async fn get_or_try_insert_with<T, E, F, Fut>(
+        &self,
+        key: &str,
+        load: F,
+    ) -> std::result::Result<T, E>
+    where
+        T: Serialize + DeserializeOwned,
+        E: From<CacheError>,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = std::result::Result<T, E>>,
+    {
+        if let Some(value) = self.get(key).await? {
+            return Ok(value);
+        }
+        let value = load().await?;
+        self.set(key, &value).await?;
+        Ok(value)
+    }
+}
+
+{{> partials/test-header}}
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde::Deserialize;
+    use tokio::time;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Session {
+        user: String,
+        roles: Vec<String>,
+    }
+
+    fn session(user: &str) -> Session {
+        Session {
+            user: user.to_string(),
+            roles: vec!["reader".to_string()],
+        }
+    }
+
+    fn cache() -> CacheName<MemoryStore> {
+        CacheName::new(MemoryStore::new(), "test", TTL)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_set_then_get() {
+        let cache = cache();
+        cache.set("a", &session("ada")).await.unwrap();
+        let found: Option<Session> = cache.get("a").await.unwrap();
+        assert_eq!(found, Some(session("ada")));
+        let missing: Option<Session> = cache.get("b").await.unwrap();
+        assert_eq!(missing, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_values_expire_after_ttl() {
+        let cache = cache();
+        cache.set("a", &1).await.unwrap();
+        cache.set_with_ttl("b", &2, TTL * 2).await.unwrap();
+
+        time::advance(TTL).await;
+        assert_eq!(cache.get::<i32>("a").await.unwrap(), None);
+        assert_eq!(cache.get::<i32>("b").await.unwrap(), Some(2));
+
+        time::advance(TTL).await;
+        assert_eq!(cache.get::<i32>("b").await.unwrap(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_touch_restarts_ttl() {
+        let cache = cache();
+        cache.set("a", &1).await.unwrap();
+        time::advance(TTL / 2).await;
+        assert!(cache.touch("a").await.unwrap());
+        time::advance(TTL * 3 / 4).await;
+        assert_eq!(cache.get::<i32>("a").await.unwrap(), Some(1));
+        assert!(!cache.touch("missing").await.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_remove() {
+        let cache = cache();
+        cache.set("a", &1).await.unwrap();
+        assert!(cache.remove("a").await.unwrap());
+        assert!(!cache.remove("a").await.unwrap());
+        assert_eq!(cache.get::<i32>("a").await.unwrap(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_prefixes_keep_caches_apart() {
+        let store = MemoryStore::new();
+        let first = CacheName::new(store.clone(), "first", TTL);
+        let second = CacheName::new(store.clone(), "second", TTL);
+        first.set("a", &1).await.unwrap();
+        second.set("a", &2).await.unwrap();
+        assert_eq!(first.get::<i32>("a").await.unwrap(), Some(1));
+        assert_eq!(store.get("second:a").await.unwrap().as_deref(), Some("2"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_value_of_another_type_is_an_error() {
+        let store = MemoryStore::new();
+        let cache = CacheName::new(store.clone(), "test", TTL);
+        let json = "not json".to_string();
+        store.set("test:a", json, TTL).await.unwrap();
+        let result = cache.get::<Session>("a").await;
+        assert!(matches!(result, Err(CacheError::Json(_))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_get_or_try_insert_with_loads_once() {
+        let cache = cache();
+        let loads = AtomicUsize::new(0);
+        for _ in 0..3 {
+            let found = cache
+                .get_or_try_insert_with("a", || async {
+                    loads.fetch_add(1, Ordering::Relaxed);
+                    Ok::<_, LoadError>(session("ada"))
+                })
+                .await
+                .unwrap();
+            assert_eq!(found, session("ada"));
+        }
+        assert_eq!(loads.load(Ordering::Relaxed), 1);
+    }
+
+    /// What a loader fails with; any error that a [`CacheError`] converts
+    /// into will do.
+    type LoadError = Box<dyn std::error::Error + Send + Sync>;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failed_load_stores_nothing() {
+        let cache = cache();
+        let result = cache
+            .get_or_try_insert_with("a", || async {
+                Err::<i32, LoadError>("no such user".into())
+            })
+            .await;
+        assert_eq!(result.unwrap_err().to_string(), "no such user");
+        assert_eq!(cache.get::<i32>("a").await.unwrap(), None);
+    }
+
+    /// Runs against a real server: `REDIS_URL=redis://127.0.0.1/ cargo
+    /// test -- --ignored`.
+    #[tokio::test]
+    #[ignore = "needs Redis at REDIS_URL"]
+    async fn test_redis_store() {
+        let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".into());
+        let store = RedisStore::new(&url).unwrap();
+        let cache = CacheName::new(store, "cache-test", TTL);
+        cache.set("a", &session("ada")).await.unwrap();
+        assert_eq!(cache.get("a").await.unwrap(), Some(session("ada")));
+        assert!(cache.touch("a").await.unwrap());
+        assert!(cache.remove("a").await.unwrap());
+        assert_eq!(cache.get::<Session>("a").await.unwrap(), None);
+    }
+}
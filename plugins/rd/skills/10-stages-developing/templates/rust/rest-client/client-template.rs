@@ -0,0 +1,143 @@
+//! The client every endpoint group sends its requests through.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::{Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+
+{{#block imports}}
+use super::error::ApiError;
+use super::users::Users;
+{{/block}}
+
+/// Limit on each request, from connecting to reading the body.
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Credentials sent with every request.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Auth {
+    /// No credentials.
+    None,
+    /// `Authorization: Bearer <token>`.
+    Bearer(String),
+    /// The key in a header of its own, such as `x-api-key`.
+    ApiKey {
+        /// Name of the header.
+        header: &'static str,
+        /// The key.
+        key: String,
+    },
+}
+
+// Written by hand so credentials stay out of logs.
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => f.write_str("None"),
+            Self::Bearer(_) => f.write_str("Bearer(<redacted>)"),
+            Self::ApiKey { header, .. } => write!(f, "ApiKey({header}: <redacted>)"),
+        }
+    }
+}
+
+/// Client for the service, shared by its endpoint groups. Clones share the
+/// connection pool.
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    http: reqwest::Client,
+    base_url: String,
+    auth: Auth,
+}
+
+impl ApiClient {
+    /// A client for the service at `base_url`, e.g.
+    /// `https://api.example.com/v1`, sending `auth` with every request.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApiError::Transport`] if the HTTP client cannot be set up.
+    pub fn new(base_url: impl Into<String>, auth: Auth) -> Result<Self, ApiError> {
+        let http = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .map_err(ApiError::Transport)?;
+        let mut base_url = base_url.into();
+        base_url.truncate(base_url.trim_end_matches('/').len());
+        let inner = Inner {
+            http,
+            base_url,
+            auth,
+        };
+        Ok(Self {
+            inner: Arc::new(inner),
+        })
+    }
+
+{{#block groups}}
+    /// The `/users` endpoints.
+    pub fn users(&self) -> Users<'_> {
+        Users::new(self)
+    }
+
+{{/block}}
+    /// A request to `path` under the base URL, carrying the credentials.
+    pub(super) fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}{path}", self.inner.base_url);
+        let request = self.inner.http.request(method, url);
+        match &self.inner.auth {
+            Auth::None => request,
+            Auth::Bearer(token) => request.bearer_auth(token),
+            Auth::ApiKey { header, key } => request.header(*header, key),
+        }
+    }
+}
+
+/// Sends `request` and decodes the JSON body of a successful response.
+pub(super) async fn json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, ApiError> {
+    let response = send(request).await?;
+    response.json().await.map_err(ApiError::Decode)
+}
+
+/// Sends `request`, ignoring the body of a successful response.
+pub(super) async fn empty(request: RequestBuilder) -> Result<(), ApiError> {
+    send(request).await.map(drop)
+}
+
+/// `value` as one segment of a request path: every byte but the unreserved
+/// characters of RFC 3986 is percent-encoded, so a `/`, `?` or `#` in it
+/// stays part of the segment rather than reaching another endpoint.
+pub(super) fn segment(value: impl fmt::Display) -> String {
+    let mut encoded = String::new();
+    for byte in value.to_string().bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// `request`'s response if its status is a success, else the error it
+/// stands for.
+async fn send(request: RequestBuilder) -> Result<Response, ApiError> {
+    let response = request.send().await.map_err(ApiError::Transport)?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    if status.is_client_error() {
+        // Not every 4xx comes from the service itself, e.g. a proxy's.
+        let problem = response.json().await.ok();
+        return Err(ApiError::Client { status, problem });
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(ApiError::Server { status, body })
+}
@@ -0,0 +1,86 @@
+//! How a request can fail.
+
+use std::fmt;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+/// The body the service sends with a 4xx status.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Problem {
+    /// Machine-readable reason, such as `not_found`.
+    pub code: String,
+    /// Explanation for people.
+    pub message: String,
+}
+
+/// Why a request failed.
+#[derive(Debug)]
+pub enum ApiError {
+    /// The service rejected the request with a 4xx status. Retrying the
+    /// same request will fail the same way.
+    Client {
+        /// The status.
+        status: StatusCode,
+        /// What the service said was wrong, if its body said so.
+        problem: Option<Problem>,
+    },
+    /// The service failed with a 5xx status, or any other that is not a
+    /// success.
+    Server {
+        /// The status.
+        status: StatusCode,
+        /// The response body, for the logs.
+        body: String,
+    },
+    /// No response arrived: the connection failed or timed out.
+    Transport(reqwest::Error),
+    /// A successful response's body did not match the expected model.
+    Decode(reqwest::Error),
+}
+
+impl ApiError {
+    /// The response status, if there was a response.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Client { status, .. } | Self::Server { status, .. } => Some(*status),
+            Self::Transport(_) | Self::Decode(_) => None,
+        }
+    }
+
+    /// Whether the same request might succeed later: after a server failure
+    /// or a network problem, and after `429 Too Many Requests`.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Client { status, .. } => *status == StatusCode::TOO_MANY_REQUESTS,
+            Self::Server { .. } | Self::Transport(_) => true,
+            Self::Decode(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Client { status, problem } => {
+                write!(f, "request rejected with {status}")?;
+                match problem {
+                    Some(problem) => write!(f, ": {}: {}", problem.code, problem.message),
+                    None => Ok(()),
+                }
+            }
+            Self::Server { status, body } => write!(f, "service failed with {status}: {body}"),
+            Self::Transport(err) => write!(f, "no response from the service: {err}"),
+            Self::Decode(err) => write!(f, "unexpected response body: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport(err) | Self::Decode(err) => Some(err),
+            Self::Client { .. } | Self::Server { .. } => None,
+        }
+    }
+}
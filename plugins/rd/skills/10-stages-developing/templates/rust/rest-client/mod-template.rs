@@ -0,0 +1,26 @@
+//! [Brief description of what this module does]
+//!
+//! [`ApiClient`] holds what every request shares — the base URL, the
+//! credentials ([`Auth`]) and the connection pool — and is cheap to clone.
+//! The endpoints are grouped by resource, one method each, so
+//! `client.users().get(7)` reads like the `GET /users/7` it sends. A new
+//! endpoint is a method on its group; a new resource is a group like
+//! [`Users`] with a method on [`ApiClient`] returning it.
+//!
+//! Failures are an [`ApiError`] sorted by who has to act: the caller for a
+//! 4xx ([`ApiError::Client`], with the service's [`Problem`] if it sent
+//! one), the service for a 5xx ([`ApiError::Server`]), and the network for
+//! anything that never got an answer ([`ApiError::Transport`]).
+//! [`ApiError::is_retryable`] tells the two kinds worth retrying from the
+//! rest.
+
+mod client;
+mod error;
+/// The service's request and response bodies.
+pub mod models;
+mod users;
+
+pub use client::ApiClient;
+pub use client::{Auth, TIMEOUT};
+pub use error::{ApiError, Problem};
+pub use users::Users;
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// A user as the service returns it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct User {
+    pub id: u64,
+    pub name: String,
+    pub email: String,
+}
+
+/// Body of a request to create a user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NewUser {
+    pub name: String,
+    pub email: String,
+}
+
+/// One page of a listing.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Where the next page starts, or `None` on the last one.
+    pub next_cursor: Option<String>,
+}
@@ -0,0 +1,207 @@
+//! Tests of `{{ crate_name }}::{{ module }}` against a mock of the service.
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use wiremock::matchers::{body_json, header, method, path, query_param, query_param_is_missing};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use {{ crate_name }}::{{ module }}::models::NewUser;
+use {{ crate_name }}::{{ module }}::ApiClient;
+use {{ crate_name }}::{{ module }}::{ApiError, Auth, Problem};
+
+const TOKEN: &str = "test-token";
+
+fn client(server: &MockServer) -> ApiClient {
+    ApiClient::new(server.uri(), Auth::Bearer(TOKEN.to_string())).unwrap()
+}
+
+fn user(id: u64, name: &str) -> Value {
+    let email = format!("{}@example.com", name.to_lowercase());
+    json!({ "id": id, "name": name, "email": email })
+}
+
+fn respond(status: u16) -> ResponseTemplate {
+    ResponseTemplate::new(status)
+}
+
+#[tokio::test]
+async fn test_get_sends_bearer_token() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/users/7"))
+        .and(header("authorization", "Bearer test-token"))
+        .respond_with(respond(200).set_body_json(user(7, "Ada")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let found = client(&server).users().get(7).await.unwrap();
+    assert_eq!((found.id, found.name.as_str()), (7, "Ada"));
+}
+
+#[tokio::test]
+async fn test_api_key_goes_in_its_header() {
+    let server = MockServer::start().await;
+    Mock::given(method("DELETE"))
+        .and(path("/users/7"))
+        .and(header("x-api-key", "k-123"))
+        .respond_with(respond(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let key = "k-123".to_string();
+    let auth = Auth::ApiKey {
+        header: "x-api-key",
+        key,
+    };
+    // A trailing slash on the base URL is dropped.
+    let base_url = format!("{}/", server.uri());
+    let client = ApiClient::new(base_url, auth).unwrap();
+    client.users().delete(7).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_create_sends_model_as_json() {
+    let server = MockServer::start().await;
+    let body = json!({ "name": "Grace", "email": "grace@example.com" });
+    Mock::given(method("POST"))
+        .and(path("/users"))
+        .and(body_json(body))
+        .respond_with(respond(201).set_body_json(user(8, "Grace")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let new_user = NewUser {
+        name: "Grace".to_string(),
+        email: "grace@example.com".to_string(),
+    };
+    let created = client(&server).users().create(&new_user).await.unwrap();
+    assert_eq!(created.id, 8);
+}
+
+#[tokio::test]
+async fn test_list_follows_cursor() {
+    let server = MockServer::start().await;
+    let first = json!({ "items": [user(1, "Ada")], "next_cursor": "p2" });
+    Mock::given(method("GET"))
+        .and(path("/users"))
+        .and(query_param_is_missing("cursor"))
+        .respond_with(respond(200).set_body_json(first))
+        .mount(&server)
+        .await;
+    let last = json!({ "items": [user(2, "Grace")], "next_cursor": null });
+    Mock::given(method("GET"))
+        .and(path("/users"))
+        .and(query_param("cursor", "p2"))
+        .respond_with(respond(200).set_body_json(last))
+        .mount(&server)
+        .await;
+
+    let client = client(&server);
+    let users = client.users();
+    let mut names = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = users.list(cursor.as_deref()).await.unwrap();
+        names.extend(page.items.into_iter().map(|user| user.name));
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(names, ["Ada", "Grace"]);
+}
+
+#[tokio::test]
+async fn test_client_errors_carry_the_problem() {
+    let server = MockServer::start().await;
+    let problem = json!({ "code": "not_found", "message": "no user 7" });
+    Mock::given(method("GET"))
+        .respond_with(respond(404).set_body_json(problem))
+        .mount(&server)
+        .await;
+    let err = client(&server).users().get(7).await.unwrap_err();
+    let expected = Problem {
+        code: "not_found".to_string(),
+        message: "no user 7".to_string(),
+    };
+    assert!(
+        matches!(&err, ApiError::Client { problem: Some(p), .. } if *p == expected),
+        "{err:?}"
+    );
+    assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+    assert!(!err.is_retryable());
+    let message = "request rejected with 404 Not Found: not_found: no user 7";
+    assert_eq!(err.to_string(), message);
+}
+
+#[tokio::test]
+async fn test_client_errors_without_a_problem_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(respond(401).set_body_string("go away"))
+        .mount(&server)
+        .await;
+    let err = client(&server).users().get(7).await.unwrap_err();
+    assert!(
+        matches!(err, ApiError::Client { problem: None, .. }),
+        "{err:?}"
+    );
+    assert_eq!(err.to_string(), "request rejected with 401 Unauthorized");
+
+    server.reset().await;
+    Mock::given(method("GET"))
+        .respond_with(respond(429))
+        .mount(&server)
+        .await;
+    let err = client(&server).users().get(7).await.unwrap_err();
+    assert!(err.is_retryable(), "{err:?}");
+}
+
+#[tokio::test]
+async fn test_server_errors_keep_the_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(respond(503).set_body_string("maintenance"))
+        .mount(&server)
+        .await;
+    let err = client(&server).users().get(7).await.unwrap_err();
+    assert!(
+        matches!(&err, ApiError::Server { body, .. } if body == "maintenance"),
+        "{err:?}"
+    );
+    assert_eq!(err.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn test_unreachable_service_is_a_transport_error() {
+    // Nothing listens on the port once the listener is dropped.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let client = ApiClient::new(base_url, Auth::None).unwrap();
+    let err = client.users().get(7).await.unwrap_err();
+    assert!(matches!(err, ApiError::Transport(_)), "{err:?}");
+    assert_eq!(err.status(), None);
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn test_unexpected_body_is_a_decode_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(respond(200).set_body_json(json!({ "id": "seven" })))
+        .mount(&server)
+        .await;
+    let err = client(&server).users().get(7).await.unwrap_err();
+    assert!(matches!(err, ApiError::Decode(_)), "{err:?}");
+    assert!(!err.is_retryable());
+}
+
+#[test]
+fn test_debug_redacts_credentials() {
+    let auth = Auth::Bearer("secret".to_string());
+    assert_eq!(format!("{auth:?}"), "Bearer(<redacted>)");
+    let client = ApiClient::new("https://api.example.com", auth).unwrap();
+    assert!(!format!("{client:?}").contains("secret"));
+}
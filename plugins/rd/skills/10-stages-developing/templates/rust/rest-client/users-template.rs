@@ -0,0 +1,49 @@
+//! The `/users` endpoints.
+
+use reqwest::Method;
+
+use super::client::{self, ApiClient};
+use super::error::ApiError;
+use super::models::{NewUser, Page, User};
+
+/// The `/users` endpoints, from [`ApiClient::users`]. Each returns the
+/// [`ApiError`] the request failed with.
+#[derive(Debug, Clone, Copy)]
+pub struct Users<'a> {
+    client: &'a ApiClient,
+}
+
+impl<'a> Users<'a> {
+    pub(super) fn new(client: &'a ApiClient) -> Self {
+        Self { client }
+    }
+
+    /// `GET /users/{id}`.
+    pub async fn get(&self, id: u64) -> Result<User, ApiError> {
+        let path = format!("/users/{}", client::segment(id));
+        let request = self.client.request(Method::GET, &path);
+        client::json(request).await
+    }
+
+    /// `GET /users`: the page starting at `cursor`, or the first page.
+    pub async fn list(&self, cursor: Option<&str>) -> Result<Page<User>, ApiError> {
+        let mut request = self.client.request(Method::GET, "/users");
+        if let Some(cursor) = cursor {
+            request = request.query(&[("cursor", cursor)]);
+        }
+        client::json(request).await
+    }
+
+    /// `POST /users`, returning the user created.
+    pub async fn create(&self, user: &NewUser) -> Result<User, ApiError> {
+        let request = self.client.request(Method::POST, "/users").json(user);
+        client::json(request).await
+    }
+
+    /// `DELETE /users/{id}`.
+    pub async fn delete(&self, id: u64) -> Result<(), ApiError> {
+        let path = format!("/users/{}", client::segment(id));
+        let request = self.client.request(Method::DELETE, &path);
+        client::empty(request).await
+    }
+}
@@ -0,0 +1,331 @@
+//! [Brief description of what this module does]
+//!
+//! [`retry_with_backoff`] calls an operation until it succeeds, fails with
+//! an error that [`Retryable`] says is permanent, or runs out of attempts
+//! or time under a [`RetryPolicy`]. The delay doubles after each failure up
+//! to `max_delay`; with `jitter` on, each sleep is drawn from between zero
+//! and that delay, so clients failing together do not retry together.
+//!
+//! Time goes through a [`Clock`], so tests can swap [`SystemClock`] for one
+//! that records the sleeps instead of taking them.
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Classifies errors as worth retrying or not.
+pub trait Retryable {
+    /// Whether the operation may succeed if tried again.
+    fn is_retryable(&self) -> bool;
+}
+
+/// The time source of [`retry_with_backoff`].
+pub trait Clock {
+    /// The current instant.
+    fn now(&self) -> Instant;
+    /// Blocks for `duration`.
+    fn sleep(&self, duration: Duration);
+}
+
+/// The real clock: [`Instant::now`] and [`thread::sleep`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Limits and delays for [`retry_with_backoff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Calls made before giving up, counting the first.
+    pub max_attempts: u32,
+    /// Delay after the first failure, doubled after each further one.
+    pub initial_delay: Duration,
+    /// Longest delay between two attempts.
+    pub max_delay: Duration,
+    /// Time after the first call within which every attempt must start.
+    pub deadline: Option<Duration>,
+    /// Whether to sleep a random part of each delay.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: MAX_ATTEMPTS,
+            initial_delay: Duration::from_millis(INITIAL_DELAY_MS),
+            max_delay: Duration::from_secs(10),
+            deadline: None,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delay before attempt number `attempt + 1`, before jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+/// Why [`retry_with_backoff`] gave up, with the last error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RetryError<E> {
+    /// The error was not retryable.
+    Permanent(E),
+    /// Every allowed attempt failed.
+    Exhausted {
+        /// Attempts made.
+        attempts: u32,
+        /// Error of the last attempt.
+        last: E,
+    },
+    /// The next attempt would have started after the deadline.
+    DeadlineExceeded {
+        /// Attempts made.
+        attempts: u32,
+        /// Error of the last attempt.
+        last: E,
+    },
+}
+
+impl<E> RetryError<E> {
+    /// The error of the last attempt.
+    pub fn into_inner(self) -> E {
+        match self {
+            Self::Permanent(last)
+            | Self::Exhausted { last, .. }
+            | Self::DeadlineExceeded { last, .. } => last,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for RetryError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Permanent(last) => write!(f, "permanent error: {last}"),
+            Self::Exhausted { attempts, last } => {
+                write!(f, "gave up after {attempts} attempts: {last}")
+            }
+            Self::DeadlineExceeded { attempts, last } => {
+                write!(f, "deadline exceeded after {attempts} attempts: {last}")
+            }
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for RetryError<E> {}
+
+/// Calls `operation` with the attempt number, starting at 1, until it
+/// succeeds or `policy` says to stop.
+///
+/// # Errors
+///
+/// Returns the last error, wrapped in why no further attempt was made.
+pub fn retry_with_backoff<T, E, F>(
+    policy: &RetryPolicy,
+    clock: &impl Clock,
+    mut operation: F,
+) -> Result<T, RetryError<E>>
+where
+    E: Retryable,
+    F: FnMut(u32) -> Result<T, E>,
+{
+    let started = clock.now();
+    let random = RandomState::new();
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let last = match operation(attempt) {
+            Ok(value) => return Ok(value),
+            Err(err) if !err.is_retryable() => return Err(RetryError::Permanent(err)),
+            Err(err) => err,
+        };
+        if attempt >= policy.max_attempts {
+            return Err(RetryError::Exhausted {
+                attempts: attempt,
+                last,
+            });
+        }
+        let mut delay = policy.backoff(attempt);
+        if policy.jitter {
+            let mut hasher = random.build_hasher();
+            hasher.write_u32(attempt);
+            delay = delay.mul_f64(hasher.finish() as f64 / u64::MAX as f64);
+        }
+        let late = policy
+            .deadline
+            .is_some_and(|deadline| clock.now() + delay > started + deadline);
+        if late {
+            return Err(RetryError::DeadlineExceeded {
+                attempts: attempt,
+                last,
+            });
+        }
+        clock.sleep(delay);
+    }
+}
+
+{{> partials/test-header}}
+    use std::cell::{Cell, RefCell};
+
+    /// A clock whose sleeps only move its time forward, and are recorded.
+    struct MockClock {
+        now: Cell<Instant>,
+        sleeps: RefCell<Vec<Duration>>,
+    }
+
+    impl MockClock {
+        fn new() -> Self {
+            Self {
+                now: Cell::new(Instant::now()),
+                sleeps: RefCell::default(),
+            }
+        }
+
+        fn sleeps(&self) -> Vec<Duration> {
+            self.sleeps.borrow().clone()
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            self.now.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.now.set(self.now.get() + duration);
+            self.sleeps.borrow_mut().push(duration);
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum TestError {
+        Busy,
+        Invalid,
+    }
+
+    impl Retryable for TestError {
+        fn is_retryable(&self) -> bool {
+            *self == Self::Busy
+        }
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            deadline: None,
+            jitter: false,
+        }
+    }
+
+    fn always_busy(_attempt: u32) -> Result<(), TestError> {
+        Err(TestError::Busy)
+    }
+
+    fn always_invalid(_attempt: u32) -> Result<(), TestError> {
+        Err(TestError::Invalid)
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_succeeds_after_retryable_errors() {
+        let clock = MockClock::new();
+        let third_time_lucky = |attempt| {
+            if attempt < 3 {
+                Err(TestError::Busy)
+            } else {
+                Ok(attempt)
+            }
+        };
+        let result = retry_with_backoff(&policy(), &clock, third_time_lucky);
+        assert_eq!(result, Ok(3));
+        assert_eq!(clock.sleeps(), [ms(100), ms(200)]);
+    }
+
+    #[test]
+    fn test_stops_at_max_attempts_with_capped_delay() {
+        let clock = MockClock::new();
+        let result = retry_with_backoff(&policy(), &clock, always_busy);
+        let expected = RetryError::Exhausted {
+            attempts: 4,
+            last: TestError::Busy,
+        };
+        assert_eq!(result, Err(expected));
+        assert_eq!(clock.sleeps(), [ms(100), ms(200), ms(300)]);
+    }
+
+    #[test]
+    fn test_permanent_error_is_not_retried() {
+        let clock = MockClock::new();
+        let result = retry_with_backoff(&policy(), &clock, always_invalid);
+        assert_eq!(result, Err(RetryError::Permanent(TestError::Invalid)));
+        assert!(clock.sleeps().is_empty());
+    }
+
+    #[test]
+    fn test_deadline_stops_before_late_attempt() {
+        let clock = MockClock::new();
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            deadline: Some(ms(250)),
+            ..policy()
+        };
+        let result = retry_with_backoff(&policy, &clock, always_busy);
+        // Attempts start at 0ms and 100ms; the next would start at 300ms.
+        let expected = RetryError::DeadlineExceeded {
+            attempts: 2,
+            last: TestError::Busy,
+        };
+        assert_eq!(result, Err(expected));
+        assert_eq!(clock.sleeps(), [ms(100)]);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_backoff() {
+        let clock = MockClock::new();
+        let policy = RetryPolicy {
+            max_attempts: 8,
+            jitter: true,
+            ..policy()
+        };
+        let result = retry_with_backoff(&policy, &clock, always_busy);
+        let expected = RetryError::Exhausted {
+            attempts: 8,
+            last: TestError::Busy,
+        };
+        assert_eq!(result, Err(expected));
+        let sleeps = clock.sleeps();
+        assert_eq!(sleeps.len(), 7);
+        for (attempt, sleep) in (1..).zip(sleeps) {
+            assert!(sleep <= policy.backoff(attempt));
+        }
+    }
+
+    #[test]
+    fn test_error_display_and_inner() {
+        let err = RetryError::Exhausted {
+            attempts: 3,
+            last: "timed out",
+        };
+        assert_eq!(err.to_string(), "gave up after 3 attempts: timed out");
+        assert_eq!(err.into_inner(), "timed out");
+    }
+}
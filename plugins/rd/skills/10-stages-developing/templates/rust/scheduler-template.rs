@@ -0,0 +1,591 @@
+//! [Brief description of what this module does]
+//!
+//! [`SchedulerName`] runs [`Job`]s in-process on a [`Trigger`]: a fixed
+//! interval, or a five-field cron expression evaluated in UTC. It runs on
+//! the calling thread — [`SchedulerName::run_until`] sleeps until the next
+//! job is due, runs it, and repeats — so jobs run one at a time and a slow
+//! one delays the rest. Spawn a thread per job from inside `run` if that
+//! matters.
+//!
+//! When the scheduler falls behind, because the process was suspended or
+//! a job overran, the [`MissedTicks`] policy of each job decides whether
+//! the missed ticks all run or collapse into one. Time goes through a
+//! [`Clock`], so tests drive the schedule with a mock one.
+
+use std::fmt;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Longest [`SchedulerName::run_until`] sleeps before checking whether to
+/// stop.
+pub const MAX_SLEEP: Duration = Duration::from_secs(1);
+
+/// The time source of a [`SchedulerName`].
+pub trait Clock {
+    /// The current wall-clock time.
+    fn now(&self) -> SystemTime;
+    /// Blocks for `duration`.
+    fn sleep(&self, duration: Duration);
+}
+
+/// The real clock: [`SystemTime::now`] and [`thread::sleep`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// One firing of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    /// When the trigger said to run, which may be before now.
+    pub scheduled: SystemTime,
+    /// How many earlier ticks were folded into this one, by
+    /// [`MissedTicks::Skip`] or [`MissedTicks::Delay`].
+    pub missed: u32,
+}
+
+/// Work run by a [`SchedulerName`].
+pub trait Job: Send {
+    /// Does the work of one tick.
+    fn run(&mut self, tick: Tick);
+}
+
+impl<F: FnMut(Tick) + Send> Job for F {
+    fn run(&mut self, tick: Tick) {
+        self(tick);
+    }
+}
+
+/// What to do about ticks that came due while the scheduler was behind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissedTicks {
+    /// Run once per missed tick, back to back.
+    Burst,
+    /// Run once for all of them, then keep to the original schedule.
+    #[default]
+    Skip,
+    /// Run once for all of them, then start the schedule afresh from now.
+    Delay,
+}
+
+/// When a job runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    /// Every `Duration`, starting one interval after the job is added.
+    Every(Duration),
+    /// Whenever the cron expression matches.
+    Cron(CronExpr),
+}
+
+impl Trigger {
+    /// The first time after `after` this trigger fires, if it ever does.
+    pub fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        match self {
+            Self::Every(interval) => after.checked_add(*interval),
+            Self::Cron(cron) => cron.next_after(after),
+        }
+    }
+}
+
+/// Why a cron expression could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CronError {
+    /// The expression does not have five fields.
+    FieldCount(usize),
+    /// A field holds something other than numbers, ranges, steps and
+    /// lists in range.
+    Invalid {
+        /// Name of the field.
+        field: &'static str,
+        /// The part of the field that is wrong.
+        value: String,
+    },
+}
+
+impl fmt::Display for CronError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FieldCount(found) => write!(f, "expected 5 cron fields, found {found}"),
+            Self::Invalid { field, value } => write!(f, "invalid cron {field} {value:?}"),
+        }
+    }
+}
+
+impl std::error::Error for CronError {}
+
+/// A cron expression: minute, hour, day of month, month and day of week
+/// (0 or 7 is Sunday), each a `*`, a number, a range `a-b`, any of those
+/// with a step `/n`, or a comma-separated list of them.
+///
+/// As in most crons, when both day fields are restricted a day matches if
+/// either does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+/// How far ahead [`CronExpr::next_after`] looks before deciding an
+/// expression never fires, such as one for the 30th of February.
+const SEARCH_DAYS: u64 = 5 * 366;
+
+impl CronExpr {
+    /// Parses a five-field expression such as `*/15 9-17 * * 1-5`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CronError`] if `expr` is not a valid expression.
+    pub fn parse(expr: &str) -> Result<Self, CronError> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(CronError::FieldCount(fields.len()));
+        };
+        let weekdays = parse_field(weekday, "day of week", 0, 7)?;
+        Ok(Self {
+            minutes: parse_field(minute, "minute", 0, 59)?,
+            hours: parse_field(hour, "hour", 0, 23)?,
+            days: parse_field(day, "day of month", 1, 31)?,
+            months: parse_field(month, "month", 1, 12)?,
+            // Sunday is both 0 and 7.
+            weekdays: (weekdays | weekdays >> 7) & 0x7f,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    /// The first minute after `after` that the expression matches.
+    pub fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        let secs = after.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let start = (secs / 60 + 1) * 60;
+        let first_day = start / 86_400;
+        for day in first_day..first_day + SEARCH_DAYS {
+            if !self.matches_day(day) {
+                continue;
+            }
+            let from = (day == first_day).then_some(start % 86_400 / 60);
+            for minute in from.unwrap_or(0)..24 * 60 {
+                let matches = has(self.hours, minute / 60) && has(self.minutes, minute % 60);
+                if matches {
+                    let secs = day * 86_400 + minute * 60;
+                    return Some(UNIX_EPOCH + Duration::from_secs(secs));
+                }
+            }
+        }
+        None
+    }
+
+    /// Whether the day `days` after the Unix epoch matches.
+    fn matches_day(&self, days: u64) -> bool {
+        let (month, day) = month_and_day(days);
+        // 1970-01-01 was a Thursday.
+        let weekday = (days + 4) % 7;
+        let by_date = has(self.days, day);
+        let by_weekday = has(self.weekdays, weekday);
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (false, false) => by_date || by_weekday,
+            _ => by_date && by_weekday,
+        };
+        has(self.months, month) && day_matches
+    }
+}
+
+impl std::str::FromStr for CronExpr {
+    type Err = CronError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+fn has(set: u64, value: u64) -> bool {
+    set & (1 << value) != 0
+}
+
+/// The set of values, as bits, of one cron field ranging from `min` to
+/// `max`.
+fn parse_field(text: &str, field: &'static str, min: u64, max: u64) -> Result<u64, CronError> {
+    let mut set = 0;
+    for part in text.split(',') {
+        let invalid = || CronError::Invalid {
+            field,
+            value: part.to_string(),
+        };
+        let number = |s: &str| s.parse::<u64>().map_err(|_| invalid());
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, number(step)?),
+            None => (part, 1),
+        };
+        let (low, high) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((low, high)) => (number(low)?, number(high)?),
+            // `5/15` counts from 5 to the end of the range.
+            None if step > 1 => (number(range)?, max),
+            None => (number(range)?, number(range)?),
+        };
+        if step == 0 || low < min || high > max || low > high {
+            return Err(invalid());
+        }
+        for value in (low..=high).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+/// The month and day of month of the day `days` after the Unix epoch, by
+/// Howard Hinnant's `civil_from_days`.
+fn month_and_day(days: u64) -> (u64, u64) {
+    let days = days + 719_468;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    (month, day)
+}
+
+struct Entry {
+    job: Box<dyn Job>,
+    trigger: Trigger,
+    missed: MissedTicks,
+    next: Option<SystemTime>,
+}
+
+impl Entry {
+    /// Runs the job for the ticks due by `now`, returning how many times it
+    /// ran.
+    fn run_due(&mut self, now: SystemTime) -> usize {
+        let Some(first) = self.next.filter(|&next| next <= now) else {
+            return 0;
+        };
+        let due = |at: &SystemTime| *at <= now;
+        if self.missed == MissedTicks::Burst {
+            let mut runs = 0;
+            let mut tick = Some(first);
+            while let Some(scheduled) = tick.filter(due) {
+                self.job.run(Tick {
+                    scheduled,
+                    missed: 0,
+                });
+                runs += 1;
+                tick = self.trigger.next_after(scheduled);
+            }
+            self.next = tick;
+            return runs;
+        }
+        let mut latest = first;
+        let mut missed = 0;
+        while let Some(at) = self.trigger.next_after(latest).filter(due) {
+            latest = at;
+            missed += 1;
+        }
+        let (scheduled, next) = match self.missed {
+            MissedTicks::Delay => (first, self.trigger.next_after(now)),
+            _ => (latest, self.trigger.next_after(latest)),
+        };
+        self.job.run(Tick { scheduled, missed });
+        self.next = next;
+        1
+    }
+}
+
+/// Runs jobs when their triggers fire.
+pub struct SchedulerName<C = SystemClock> {
+    clock: C,
+    entries: Vec<Entry>,
+}
+
+impl SchedulerName {
+    /// A scheduler on the system clock.
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl Default for SchedulerName {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: fmt::Debug> fmt::Debug for SchedulerName<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("SchedulerName");
+        debug.field("clock", &self.clock);
+        debug.field("jobs", &self.entries.len());
+        debug.finish()
+    }
+}
+
+impl<C: Clock> SchedulerName<C> {
+    /// A scheduler reading time from `clock`.
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            clock,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Schedules `job` to run on `trigger`, with `missed` deciding what
+    /// happens to ticks missed while the scheduler was behind.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `trigger` is a zero interval.
+    pub fn add(&mut self, trigger: Trigger, missed: MissedTicks, job: impl Job + 'static) {
+        assert!(
+            trigger != Trigger::Every(Duration::ZERO),
+            "interval must be non-zero"
+        );
+        let next = trigger.next_after(self.clock.now());
+        self.entries.push(Entry {
+            job: Box::new(job),
+            trigger,
+            missed,
+            next,
+        });
+    }
+
+    /// When the next job is due, or `None` if no trigger will fire again.
+    pub fn next_due(&self) -> Option<SystemTime> {
+        self.entries.iter().filter_map(|entry| entry.next).min()
+    }
+
+    /// Runs every job that is due, returning how many runs there were.
+    pub fn run_pending(&mut self) -> usize {
+        let now = self.clock.now();
+        let runs = self.entries.iter_mut().map(|entry| entry.run_due(now));
+        runs.sum()
+    }
+
+    /// Runs jobs as they come due until `stop` returns true, or until no
+    /// trigger will fire again. `stop` is checked after each round of runs
+    /// and at least every [`MAX_SLEEP`].
+    pub fn run_until(&mut self, mut stop: impl FnMut() -> bool) {
+        loop {
+            self.run_pending();
+            if stop() {
+                return;
+            }
+            let Some(due) = self.next_due() else {
+                return;
+            };
+            let wait = due.duration_since(self.clock.now()).unwrap_or_default();
+            self.clock.sleep(wait.min(MAX_SLEEP));
+        }
+    }
+}
+
+{{> partials/test-header}}
+    use std::cell::Cell;
+    use std::sync::{Arc, Mutex};
+
+    /// Monday 2024-01-01 00:00 UTC.
+    const MONDAY: u64 = 1_704_067_200;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    /// `hour`:`minute` on the `day`th day after [`MONDAY`].
+    fn day_at(day: u64, hour: u64, minute: u64) -> SystemTime {
+        at(MONDAY + day * 86_400 + hour * 3600 + minute * 60)
+    }
+
+    /// Time moves only when told to, or when the scheduler sleeps.
+    #[derive(Debug)]
+    struct MockClock {
+        now: Cell<SystemTime>,
+    }
+
+    impl MockClock {
+        fn advance(&self, by: Duration) {
+            self.now.set(self.now.get() + by);
+        }
+    }
+
+    impl Clock for &MockClock {
+        fn now(&self) -> SystemTime {
+            self.now.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.advance(duration);
+        }
+    }
+
+    type Ticks = Arc<Mutex<Vec<Tick>>>;
+
+    fn recorder(ticks: &Ticks) -> impl Job + 'static {
+        let ticks = Arc::clone(ticks);
+        move |tick| ticks.lock().unwrap().push(tick)
+    }
+
+    fn scheduled(ticks: &Ticks) -> Vec<SystemTime> {
+        let ticks = ticks.lock().unwrap();
+        ticks.iter().map(|tick| tick.scheduled).collect()
+    }
+
+    fn cron(expr: &str) -> CronExpr {
+        expr.parse().unwrap()
+    }
+
+    #[test]
+    fn test_interval_fires_on_schedule() {
+        let clock = MockClock {
+            now: Cell::new(at(MONDAY)),
+        };
+        let mut scheduler = SchedulerName::with_clock(&clock);
+        let ticks = Ticks::default();
+        let every = Trigger::Every(Duration::from_secs(10));
+        scheduler.add(every, MissedTicks::Skip, recorder(&ticks));
+
+        let fired_three = || ticks.lock().unwrap().len() == 3;
+        scheduler.run_until(fired_three);
+        let expected = [10, 20, 30].map(|secs| at(MONDAY + secs));
+        assert_eq!(scheduled(&ticks), expected);
+        assert_eq!(clock.now.get(), at(MONDAY + 30));
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let workdays = cron("*/15 9-17 * * 1-5");
+        let next = |from| workdays.next_after(from);
+        assert_eq!(next(day_at(0, 8, 50)), Some(day_at(0, 9, 0)));
+        assert_eq!(next(day_at(0, 9, 0)), Some(day_at(0, 9, 15)));
+        assert_eq!(next(day_at(0, 17, 45)), Some(day_at(1, 9, 0)));
+        // From Friday evening to Monday morning.
+        assert_eq!(next(day_at(4, 18, 0)), Some(day_at(7, 9, 0)));
+
+        let new_year = cron("0 0 1 1 *");
+        let expected = at(1_735_689_600); // 2025-01-01 00:00 UTC
+        assert_eq!(new_year.next_after(day_at(0, 0, 0)), Some(expected));
+        let leap_day = cron("30 12 29 2 *");
+        assert_eq!(leap_day.next_after(at(MONDAY)), Some(day_at(59, 12, 30)));
+        assert_eq!(cron("0 0 30 2 *").next_after(at(MONDAY)), None);
+    }
+
+    #[test]
+    fn test_cron_day_fields() {
+        // The 13th, or any Friday.
+        let either = cron("0 0 13 * 5");
+        let next = |from| either.next_after(from);
+        assert_eq!(next(at(MONDAY)), Some(day_at(4, 0, 0)));
+        assert_eq!(next(day_at(4, 0, 0)), Some(day_at(11, 0, 0)));
+        assert_eq!(next(day_at(11, 0, 0)), Some(day_at(12, 0, 0)));
+        // Sundays, written as 7.
+        let sunday = cron("0 6 * * 7");
+        assert_eq!(sunday.next_after(at(MONDAY)), Some(day_at(6, 6, 0)));
+        assert_eq!(cron("0 6 * * 0"), sunday);
+    }
+
+    #[test]
+    fn test_rejects_invalid_cron() {
+        assert_eq!(CronExpr::parse("* * *"), Err(CronError::FieldCount(3)));
+        let cases = [
+            ("60 * * * *", "minute", "60"),
+            ("* 5-1 * * *", "hour", "5-1"),
+            ("* * 0 * *", "day of month", "0"),
+            ("* * * 1,13 *", "month", "13"),
+            ("*/0 * * * *", "minute", "*/0"),
+            ("* * * * mon", "day of week", "mon"),
+        ];
+        for (expr, field, value) in cases {
+            let value = value.to_string();
+            let expected = CronError::Invalid { field, value };
+            assert_eq!(CronExpr::parse(expr), Err(expected), "{expr}");
+        }
+        let err = CronExpr::parse("61 * * * *").unwrap_err();
+        assert_eq!(err.to_string(), "invalid cron minute \"61\"");
+    }
+
+    /// Adds a 10-second job under `missed`, then jumps the clock 35 seconds.
+    fn after_missed_ticks(missed: MissedTicks) -> (Vec<Tick>, Option<SystemTime>) {
+        let clock = MockClock {
+            now: Cell::new(at(MONDAY)),
+        };
+        let mut scheduler = SchedulerName::with_clock(&clock);
+        let ticks = Ticks::default();
+        let every = Trigger::Every(Duration::from_secs(10));
+        scheduler.add(every, missed, recorder(&ticks));
+        clock.advance(Duration::from_secs(35));
+        scheduler.run_pending();
+        let ticks = ticks.lock().unwrap().clone();
+        (ticks, scheduler.next_due())
+    }
+
+    #[test]
+    fn test_missed_tick_policies() {
+        let tick = |secs, missed| Tick {
+            scheduled: at(MONDAY + secs),
+            missed,
+        };
+        let (ticks, next) = after_missed_ticks(MissedTicks::Burst);
+        assert_eq!(ticks, [tick(10, 0), tick(20, 0), tick(30, 0)]);
+        assert_eq!(next, Some(at(MONDAY + 40)));
+
+        let (ticks, next) = after_missed_ticks(MissedTicks::Skip);
+        assert_eq!(ticks, [tick(30, 2)]);
+        assert_eq!(next, Some(at(MONDAY + 40)));
+
+        let (ticks, next) = after_missed_ticks(MissedTicks::Delay);
+        assert_eq!(ticks, [tick(10, 2)]);
+        assert_eq!(next, Some(at(MONDAY + 45)));
+    }
+
+    #[test]
+    fn test_cron_job_fires_at_matching_minutes() {
+        let clock = MockClock {
+            now: Cell::new(day_at(0, 8, 59)),
+        };
+        let mut scheduler = SchedulerName::with_clock(&clock);
+        let ticks = Ticks::default();
+        let hourly = Trigger::Cron(cron("0 * * * *"));
+        scheduler.add(hourly, MissedTicks::Skip, recorder(&ticks));
+
+        let until = day_at(0, 11, 30);
+        scheduler.run_until(|| clock.now.get() >= until);
+        let expected = [9, 10, 11].map(|hour| day_at(0, hour, 0));
+        assert_eq!(scheduled(&ticks), expected);
+    }
+
+    #[test]
+    fn test_stops_when_no_trigger_fires_again() {
+        let clock = MockClock {
+            now: Cell::new(at(MONDAY)),
+        };
+        let mut scheduler = SchedulerName::with_clock(&clock);
+        let never = Trigger::Cron(cron("0 0 31 4 *"));
+        scheduler.add(never, MissedTicks::Skip, |_tick| unreachable!());
+        assert_eq!(scheduler.next_due(), None);
+        scheduler.run_until(|| false);
+        assert_eq!(scheduler.run_pending(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "interval must be non-zero")]
+    fn test_zero_interval_panics() {
+        let mut scheduler = SchedulerName::new();
+        let zero = Trigger::Every(Duration::ZERO);
+        scheduler.add(zero, MissedTicks::Skip, |_tick| {});
+    }
+}
@@ -0,0 +1,147 @@
+//! [Brief description of what this module does]
+//!
+//! Fields are `camelCase` on the wire and [`Status`] variants
+//! `snake_case`. Optional fields may be left out of the input and are
+//! left out of the output when they hold their default.
+{{#if deny_unknown_fields}}
+//! Input with fields the model does not know is rejected.
+{{else}}
+//! Fields the model does not know are ignored on input.
+{{/if}}
+
+use serde::{Deserialize, Serialize};
+
+/// A record as exchanged in JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase"{{#if deny_unknown_fields}}, deny_unknown_fields{{/if}})]
+pub struct TypeName {
+    /// Identifier, required.
+    pub id: u64,
+    /// Required.
+    pub customer_name: String,
+    /// Defaults to [`Status::Pending`].
+    #[serde(default)]
+    pub status: Status,
+    /// Defaults to [`DEFAULT_QUANTITY`].
+    #[serde(default = "default_quantity")]
+    pub quantity: u32,
+    /// Absent unless given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// `quantity` when the input has none.
+pub const DEFAULT_QUANTITY: u32 = 1;
+
+fn default_quantity() -> u32 {
+    DEFAULT_QUANTITY
+}
+
+/// Where a [`TypeName`] is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Not yet handled.
+    #[default]
+    Pending,
+    /// On its way.
+    InTransit,
+    /// Arrived.
+    Delivered,
+}
+
+{{> partials/test-header}}
+{{#if snapshots}}
+    use serde_json::json;
+{{else}}
+    use serde_json::{json, Value};
+{{/if}}
+
+    const FIXTURE: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/{{ module }}.json"
+    ));
+
+{{#if snapshots}}
+{{> partials/snapshot-settings}}
+
+{{/if}}
+    fn sample() -> TypeName {
+        TypeName {
+            id: 42,
+            customer_name: "Ada Lovelace".to_string(),
+            status: Status::InTransit,
+            quantity: 3,
+            notes: Some("Leave at the door".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_fixture() {
+        let parsed: TypeName = serde_json::from_str(FIXTURE).unwrap();
+        assert_eq!(parsed, sample());
+    }
+
+    #[test]
+{{#if snapshots}}
+    fn test_serialize_snapshot() {
+        snapshot_settings(&["id"]).bind(|| {
+            insta::assert_json_snapshot!("{{ module }}_serialized", sample());
+        });
+    }
+{{else}}
+    fn test_serialize_matches_fixture() {
+        let expected: Value = serde_json::from_str(FIXTURE).unwrap();
+        assert_eq!(serde_json::to_value(sample()).unwrap(), expected);
+    }
+{{/if}}
+
+    #[test]
+    fn test_roundtrip() {
+        let text = serde_json::to_string(&sample()).unwrap();
+        let parsed: TypeName = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed, sample());
+    }
+
+    #[test]
+    fn test_optional_fields_default() {
+        let parsed: TypeName =
+            serde_json::from_value(json!({ "id": 7, "customerName": "Grace" })).unwrap();
+{{#if snapshots}}
+        snapshot_settings(&[]).bind(|| {
+            insta::assert_json_snapshot!("{{ module }}_defaults", parsed);
+        });
+{{else}}
+        assert_eq!(parsed.status, Status::Pending);
+        assert_eq!(parsed.quantity, DEFAULT_QUANTITY);
+        assert_eq!(parsed.notes, None);
+        let value = serde_json::to_value(&parsed).unwrap();
+        assert!(value.get("notes").is_none());
+{{/if}}
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        let input = json!({ "id": 7, "customerName": "Grace", "colour": "red" });
+        let parsed = serde_json::from_value::<TypeName>(input);
+{{#if deny_unknown_fields}}
+        let err = parsed.unwrap_err().to_string();
+        assert!(err.contains("unknown field `colour`"), "{err}");
+{{else}}
+        assert_eq!(parsed.unwrap().id, 7);
+{{/if}}
+    }
+
+    #[test]
+    fn test_missing_required_field() {
+        let parsed = serde_json::from_value::<TypeName>(json!({ "id": 7 }));
+        let err = parsed.unwrap_err().to_string();
+        assert!(err.contains("missing field `customerName`"), "{err}");
+    }
+
+    #[test]
+    fn test_unknown_status() {
+        let input = json!({ "id": 7, "customerName": "Grace", "status": "lost" });
+        assert!(serde_json::from_value::<TypeName>(input).is_err());
+    }
+}
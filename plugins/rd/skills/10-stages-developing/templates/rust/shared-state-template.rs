@@ -0,0 +1,236 @@
+//! [Brief description of what this module does]
+//!
+//! [`SharedName`] is state shared between threads: named counters behind
+//! an `Arc<RwLock<_>>`. Clones are handles to the same state. Callers never
+//! see the lock; each method takes it, does one complete change or read,
+//! and releases it before returning. So no caller can hold a guard across
+//! slow work or an `.await`, and changes that must happen together, like
+//! [`transfer`](SharedName::transfer), cannot be split by another thread.
+//! Reads copy values out, and [`snapshot`](SharedName::snapshot) copies a
+//! consistent view of everything.
+//!
+//! A lock is poisoned when a thread panics while holding it. The methods
+//! here check before they change anything, so the state is whole at every
+//! point where they could panic; a poisoned lock is therefore recovered
+//! rather than turned into a panic in every other thread.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A consistent copy of every counter, as returned by
+/// [`SharedName::snapshot`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Changes made before the copy was taken.
+    pub version: u64,
+    /// The counters, by name.
+    pub counts: BTreeMap<String, u64>,
+}
+
+/// Returned by [`SharedName::transfer`] when the source counter is too low,
+/// in which case nothing changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InsufficientError {
+    /// What the source counter held.
+    pub available: u64,
+}
+
+impl fmt::Display for InsufficientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "only {} available", self.available)
+    }
+}
+
+impl std::error::Error for InsufficientError {}
+
+#[derive(Debug, Default)]
+struct State {
+    counts: HashMap<String, u64>,
+    version: u64,
+}
+
+/// Named counters shared between threads.
+#[derive(Debug, Clone, Default)]
+pub struct SharedName {
+    state: Arc<RwLock<State>>,
+}
+
+impl SharedName {
+    /// Empty shared state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The value of counter `name`, if it exists.
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.read().counts.get(name).copied()
+    }
+
+    /// Changes made so far, counting every successful method call that
+    /// changed something.
+    pub fn version(&self) -> u64 {
+        self.read().version
+    }
+
+    /// A copy of every counter, all taken at the same moment.
+    pub fn snapshot(&self) -> Snapshot {
+        let state = self.read();
+        Snapshot {
+            version: state.version,
+            counts: state.counts.clone().into_iter().collect(),
+        }
+    }
+
+    /// Adds `by` to counter `name`, creating it at zero first if needed,
+    /// and returns the new value. Saturates at `u64::MAX`.
+    pub fn increment(&self, name: &str, by: u64) -> u64 {
+        let mut state = self.write();
+        let count = state.counts.entry(name.to_string()).or_default();
+        *count = count.saturating_add(by);
+        let value = *count;
+        state.version += 1;
+        value
+    }
+
+    /// Moves `amount` from counter `from` to counter `to`, creating `to`
+    /// if needed. No reader sees one changed without the other.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InsufficientError`], changing nothing, if `from` holds
+    /// less than `amount`.
+    pub fn transfer(&self, from: &str, to: &str, amount: u64) -> Result<(), InsufficientError> {
+        let mut state = self.write();
+        let available = state.counts.get(from).copied().unwrap_or(0);
+        if available < amount {
+            return Err(InsufficientError { available });
+        }
+        state.counts.insert(from.to_string(), available - amount);
+        let count = state.counts.entry(to.to_string()).or_default();
+        *count = count.saturating_add(amount);
+        state.version += 1;
+        Ok(())
+    }
+
+    /// Removes counter `name`, returning its value.
+    pub fn remove(&self, name: &str) -> Option<u64> {
+        let mut state = self.write();
+        let value = state.counts.remove(name)?;
+        state.version += 1;
+        Some(value)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, State> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, State> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+{{> partials/test-header}}
+    use std::sync::Barrier;
+    use std::thread;
+
+    const THREADS: usize = 8;
+    const ROUNDS: u64 = 1_000;
+
+    #[test]
+    fn test_methods_on_one_thread() {
+        let shared = SharedName::new();
+        assert_eq!(shared.increment("a", 5), 5);
+        assert_eq!(shared.increment("a", u64::MAX), u64::MAX);
+        assert_eq!(shared.get("b"), None);
+        shared.remove("a");
+        assert_eq!(shared.remove("a"), None);
+        assert_eq!(shared.version(), 3);
+    }
+
+    #[test]
+    fn test_concurrent_increments_are_not_lost() {
+        let shared = SharedName::new();
+        thread::scope(|scope| {
+            for _ in 0..THREADS {
+                let shared = shared.clone();
+                scope.spawn(move || {
+                    for _ in 0..ROUNDS {
+                        shared.increment("hits", 1);
+                    }
+                });
+            }
+        });
+        let total = THREADS as u64 * ROUNDS;
+        assert_eq!(shared.get("hits"), Some(total));
+        assert_eq!(shared.version(), total);
+    }
+
+    #[test]
+    fn test_readers_never_see_half_a_transfer() {
+        let shared = SharedName::new();
+        shared.increment("left", 100);
+        let barrier = Barrier::new(THREADS);
+        thread::scope(|scope| {
+            for writer in 0..THREADS / 2 {
+                let (shared, barrier) = (&shared, &barrier);
+                let (from, to) = if writer % 2 == 0 {
+                    ("left", "right")
+                } else {
+                    ("right", "left")
+                };
+                scope.spawn(move || {
+                    barrier.wait();
+                    for _ in 0..ROUNDS {
+                        // Running out is expected; it must change nothing.
+                        let _ = shared.transfer(from, to, 7);
+                    }
+                });
+            }
+            for _ in THREADS / 2..THREADS {
+                let (shared, barrier) = (&shared, &barrier);
+                scope.spawn(move || {
+                    barrier.wait();
+                    let mut last = 0;
+                    for _ in 0..ROUNDS {
+                        let snapshot = shared.snapshot();
+                        assert_eq!(snapshot.counts.values().sum::<u64>(), 100);
+                        assert!(snapshot.version >= last);
+                        last = snapshot.version;
+                    }
+                });
+            }
+        });
+        let snapshot = shared.snapshot();
+        assert_eq!(snapshot.counts.values().sum::<u64>(), 100);
+    }
+
+    #[test]
+    fn test_failed_transfer_changes_nothing() {
+        let shared = SharedName::new();
+        shared.increment("left", 3);
+        let before = shared.snapshot();
+        let err = shared.transfer("left", "right", 4).unwrap_err();
+        assert_eq!(err, InsufficientError { available: 3 });
+        assert_eq!(err.to_string(), "only 3 available");
+        assert_eq!(shared.snapshot(), before);
+    }
+
+    #[test]
+    fn test_recovers_from_poisoned_lock() {
+        let shared = SharedName::new();
+        shared.increment("a", 1);
+        let poisoner = shared.clone();
+        let panicked = thread::spawn(move || {
+            let _guard = poisoner.state.write().unwrap();
+            panic!("panicked while holding the lock");
+        })
+        .join();
+        assert!(panicked.is_err());
+        assert!(shared.state.is_poisoned());
+
+        assert_eq!(shared.get("a"), Some(1));
+        assert_eq!(shared.increment("a", 1), 2);
+        assert_eq!(shared.snapshot().counts.len(), 1);
+    }
+}
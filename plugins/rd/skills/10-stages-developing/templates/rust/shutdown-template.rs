@@ -0,0 +1,245 @@
+//! [Brief description of what this module does]
+//!
+//! [`ShutdownName`] spawns the program's long-running tasks and stops them
+//! together. It holds a root [`CancellationToken`]; each task gets a child
+//! of it, so cancelling the root is the shutdown broadcast every task sees,
+//! while a task cancelling its own token stops only itself.
+//!
+//! [`ShutdownName::shutdown`] cancels the root and waits up to a drain
+//! timeout for the tasks to finish their cleanup. Tasks still running at the
+//! deadline are aborted and named in the [`ShutdownReport`], so a hung task
+//! delays exit by the timeout at most and is easy to find.
+//!
+//! ```ignore
+//! // Runs until the process is asked to stop.
+//! let mut shutdown = ShutdownName::new();
+//! shutdown.spawn("worker", |token| async move {
+//!     while !token.is_cancelled() {
+//!         // ...
+//!     }
+//! });
+//! tokio::signal::ctrl_c().await?;
+//! let report = shutdown.shutdown(DRAIN_TIMEOUT).await;
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::task::{self, JoinSet};
+use tokio::time::{self, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// How long [`ShutdownName::shutdown`] is usually given to drain tasks.
+pub const DRAIN_TIMEOUT: Duration = Duration::from_millis(DRAIN_MS);
+
+/// How the tasks ended, as returned by [`ShutdownName::shutdown`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Tasks that finished by the deadline.
+    pub drained: usize,
+    /// Tasks that panicked, by name.
+    pub panicked: Vec<&'static str>,
+    /// Tasks still running at the deadline, which were aborted, by name.
+    pub abandoned: Vec<&'static str>,
+}
+
+impl ShutdownReport {
+    /// Whether every task finished by the deadline without panicking.
+    pub fn is_clean(&self) -> bool {
+        self.panicked.is_empty() && self.abandoned.is_empty()
+    }
+}
+
+/// Spawns tasks and shuts them down together.
+#[derive(Debug, Default)]
+pub struct ShutdownName {
+    token: CancellationToken,
+    tasks: JoinSet<()>,
+    names: HashMap<task::Id, &'static str>,
+}
+
+impl ShutdownName {
+    /// A coordinator with no tasks yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The root token. Cancelling it, say from a signal handler, tells
+    /// every task to stop, as [`shutdown`](Self::shutdown) does.
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Resolves once shutdown has been asked for.
+    pub async fn cancelled(&self) {
+        self.token.cancelled().await;
+    }
+
+    /// Spawns `task` with its own child token, which is cancelled when
+    /// shutdown begins. The task should then finish promptly.
+    pub fn spawn<F, Fut>(&mut self, name: &'static str, task: F)
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let future = task(self.token.child_token());
+        let handle = self.tasks.spawn(future);
+        self.names.insert(handle.id(), name);
+    }
+
+    /// Tells every task to stop and waits up to `drain` for them to
+    /// finish, aborting the ones that do not.
+    pub async fn shutdown(mut self, drain: Duration) -> ShutdownReport {
+        self.token.cancel();
+        let deadline = Instant::now() + drain;
+        let mut report = ShutdownReport::default();
+        loop {
+            let next = time::timeout_at(deadline, self.tasks.join_next_with_id());
+            match next.await {
+                Ok(Some(Ok((id, ())))) => {
+                    self.names.remove(&id);
+                    report.drained += 1;
+                }
+                Ok(Some(Err(err))) => {
+                    let name = self.names.remove(&err.id());
+                    report.panicked.extend(name);
+                }
+                Ok(None) => break,
+                Err(_elapsed) => {
+                    self.tasks.shutdown().await;
+                    report.abandoned.extend(self.names.into_values());
+                    report.abandoned.sort_unstable();
+                    break;
+                }
+            }
+        }
+        report
+    }
+}
+
+{{> partials/test-header}}
+    use std::sync::{Arc, Mutex};
+
+    /// When each task saw cancellation, by name.
+    type Observed = Arc<Mutex<Vec<(&'static str, Instant)>>>;
+
+    /// Works until cancelled, then records when it noticed and spends
+    /// `cleanup` tidying up.
+    async fn worker(
+        token: CancellationToken,
+        name: &'static str,
+        cleanup: Duration,
+        observed: Observed,
+    ) {
+        loop {
+            tokio::select! {
+                () = token.cancelled() => break,
+                () = time::sleep(Duration::from_millis(10)) => {}
+            }
+        }
+        observed.lock().unwrap().push((name, Instant::now()));
+        time::sleep(cleanup).await;
+    }
+
+    fn spawn_worker(
+        shutdown: &mut ShutdownName,
+        name: &'static str,
+        cleanup: Duration,
+        observed: &Observed,
+    ) {
+        let observed = Arc::clone(observed);
+        let work = move |token| worker(token, name, cleanup, observed);
+        shutdown.spawn(name, work);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tasks_observe_cancellation_within_deadline() {
+        let observed = Observed::default();
+        let mut shutdown = ShutdownName::new();
+        for name in ["accept", "flush", "metrics"] {
+            let cleanup = Duration::from_millis(50);
+            spawn_worker(&mut shutdown, name, cleanup, &observed);
+        }
+        time::sleep(Duration::from_millis(25)).await;
+
+        let started = Instant::now();
+        let drain = Duration::from_secs(1);
+        let report = shutdown.shutdown(drain).await;
+        assert!(report.is_clean(), "{report:?}");
+        assert_eq!(report.drained, 3);
+        let observed = observed.lock().unwrap();
+        assert_eq!(observed.len(), 3);
+        for &(name, at) in observed.iter() {
+            assert!(at - started < Duration::from_millis(1), "{name}");
+        }
+        // Cleanup ran to completion before shutdown returned.
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert!(started.elapsed() < drain);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_abandons_tasks_past_drain_timeout() {
+        let observed = Observed::default();
+        let mut shutdown = ShutdownName::new();
+        spawn_worker(&mut shutdown, "quick", Duration::ZERO, &observed);
+        let cleanup = Duration::from_secs(60);
+        spawn_worker(&mut shutdown, "slow", cleanup, &observed);
+        shutdown.spawn("stuck", |_token| std::future::pending());
+
+        let started = Instant::now();
+        let drain = Duration::from_millis(200);
+        let report = shutdown.shutdown(drain).await;
+        assert_eq!(report.drained, 1);
+        assert_eq!(report.abandoned, ["slow", "stuck"]);
+        assert!(!report.is_clean());
+        assert_eq!(started.elapsed(), drain);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reports_panicked_tasks() {
+        let mut shutdown = ShutdownName::new();
+        shutdown.spawn("fine", |token| async move { token.cancelled().await });
+        shutdown.spawn("broken", |token| async move {
+            token.cancelled().await;
+            panic!("cleanup failed");
+        });
+        let report = shutdown.shutdown(DRAIN_TIMEOUT).await;
+        assert_eq!(report.drained, 1);
+        assert_eq!(report.panicked, ["broken"]);
+        assert!(report.abandoned.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_root_token_triggers_shutdown() {
+        let observed = Observed::default();
+        let mut shutdown = ShutdownName::new();
+        spawn_worker(&mut shutdown, "server", Duration::ZERO, &observed);
+
+        // As a signal handler would, from elsewhere in the program.
+        let token = shutdown.token();
+        tokio::spawn(async move {
+            time::sleep(Duration::from_millis(30)).await;
+            token.cancel();
+        });
+        shutdown.cancelled().await;
+        let report = shutdown.shutdown(DRAIN_TIMEOUT).await;
+        assert!(report.is_clean(), "{report:?}");
+        assert_eq!(observed.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_task_token_cancels_only_that_task() {
+        let mut shutdown = ShutdownName::new();
+        let root = shutdown.token();
+        shutdown.spawn("short-lived", |token| async move { token.cancel() });
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        shutdown.spawn("watcher", |token| async move {
+            time::sleep(Duration::from_millis(10)).await;
+            tx.send(token.is_cancelled()).unwrap();
+        });
+        assert!(!rx.await.unwrap());
+        assert!(!root.is_cancelled());
+        assert!(shutdown.shutdown(DRAIN_TIMEOUT).await.is_clean());
+    }
+}
@@ -0,0 +1,215 @@
+//! [Brief description of what this module does]
+//!
+//! [`SignalName`] turns the operating system's stop requests into one
+//! stream of [`Signal`]s: `SIGINT` and `SIGTERM` on Unix, and on Windows
+//! Ctrl-C and Ctrl-Break, or the console closing and the system shutting
+//! down. [`SignalName::wait_for_shutdown`] cancels a [`CancellationToken`]
+//! on the first of them, which is how they reach the tasks — pass it the
+//! root token of the `rust/shutdown` coordinator. A second signal while the
+//! tasks drain usually means the user has lost patience; programs then
+//! exit at once with [`Signal::exit_code`].
+//!
+//! Signals come through a channel, so tests send them with
+//! [`SignalName::channel`] instead of signalling the test process.
+//!
+//! ```ignore
+//! // Waits for a signal sent to the process.
+//! let mut signals = SignalName::install()?;
+//! let token = shutdown.token();
+//! tokio::spawn(async move {
+//!     if signals.wait_for_shutdown(&token).await.is_some() {
+//!         if let Some(signal) = signals.recv().await {
+//!             std::process::exit(signal.exit_code());
+//!         }
+//!     }
+//! });
+//! ```
+
+use std::io;
+
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+/// A request from outside to stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// `SIGINT`, or Ctrl-C or Ctrl-Break on Windows.
+    Interrupt,
+    /// `SIGTERM`, or the console closing or the system shutting down on
+    /// Windows.
+    Terminate,
+}
+
+impl Signal {
+    /// The conventional exit code of a process stopped by this signal.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Interrupt => 130,
+            Self::Terminate => 143,
+        }
+    }
+}
+
+/// Stop requests, from the operating system or a test.
+#[derive(Debug)]
+pub struct SignalName {
+    signals: mpsc::Receiver<Signal>,
+}
+
+impl SignalName {
+    /// Starts listening for the operating system's signals. Must be called
+    /// inside a tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a signal handler cannot be registered.
+    pub fn install() -> io::Result<Self> {
+        let (sender, signals) = Self::channel();
+        os::listen(sender)?;
+        Ok(signals)
+    }
+
+    /// Signals delivered by sending on the returned sender, for tests.
+    pub fn channel() -> (mpsc::Sender<Signal>, Self) {
+        let (sender, signals) = mpsc::channel(8);
+        (sender, Self { signals })
+    }
+
+    /// The next signal, or `None` once no more can arrive.
+    pub async fn recv(&mut self) -> Option<Signal> {
+        self.signals.recv().await
+    }
+
+    /// Waits for a signal and cancels `token` when one arrives, returning
+    /// it. Returns `None` without waiting further if `token` is cancelled
+    /// some other way first, or if no signal can arrive.
+    pub async fn wait_for_shutdown(&mut self, token: &CancellationToken) -> Option<Signal> {
+        let signal = tokio::select! {
+            () = token.cancelled() => return None,
+            signal = self.recv() => signal?,
+        };
+        token.cancel();
+        Some(signal)
+    }
+}
+
+#[cfg(unix)]
+mod os {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    use super::*;
+
+    /// Forwards `SIGINT` and `SIGTERM` to `sender`.
+    pub fn listen(sender: mpsc::Sender<Signal>) -> io::Result<()> {
+        let mut interrupt = signal(SignalKind::interrupt())?;
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::spawn(async move {
+            loop {
+                let signal = tokio::select! {
+                    Some(()) = interrupt.recv() => Signal::Interrupt,
+                    Some(()) = terminate.recv() => Signal::Terminate,
+                    else => break,
+                };
+                if sender.send(signal).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod os {
+    use tokio::signal::windows::{ctrl_break, ctrl_c, ctrl_close, ctrl_shutdown};
+
+    use super::*;
+
+    /// Forwards the console's control events to `sender`.
+    pub fn listen(sender: mpsc::Sender<Signal>) -> io::Result<()> {
+        let mut ctrl_c = ctrl_c()?;
+        let mut ctrl_break = ctrl_break()?;
+        let mut close = ctrl_close()?;
+        let mut shutdown = ctrl_shutdown()?;
+        tokio::spawn(async move {
+            loop {
+                let signal = tokio::select! {
+                    Some(()) = ctrl_c.recv() => Signal::Interrupt,
+                    Some(()) = ctrl_break.recv() => Signal::Interrupt,
+                    Some(()) = close.recv() => Signal::Terminate,
+                    Some(()) = shutdown.recv() => Signal::Terminate,
+                    else => break,
+                };
+                if sender.send(signal).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+{{> partials/test-header}}
+    use std::time::Duration;
+
+    use tokio::time;
+
+    #[tokio::test]
+    async fn test_first_signal_cancels_token() {
+        let (deliver, mut signals) = SignalName::channel();
+        let token = CancellationToken::new();
+        let child = token.child_token();
+        deliver.send(Signal::Terminate).await.unwrap();
+        let signal = signals.wait_for_shutdown(&token).await;
+        assert_eq!(signal, Some(Signal::Terminate));
+        assert!(child.is_cancelled());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tasks_see_signal_delivered_later() {
+        let (deliver, mut signals) = SignalName::channel();
+        let token = CancellationToken::new();
+        let task_token = token.child_token();
+        let task = tokio::spawn(async move { task_token.cancelled().await });
+        tokio::spawn(async move {
+            time::sleep(Duration::from_secs(5)).await;
+            deliver.send(Signal::Interrupt).await.unwrap();
+        });
+
+        let signal = signals.wait_for_shutdown(&token).await;
+        assert_eq!(signal, Some(Signal::Interrupt));
+        time::timeout(Duration::from_millis(1), task)
+            .await
+            .expect("task saw the cancellation")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_second_signal_is_the_force_exit() {
+        let (deliver, mut signals) = SignalName::channel();
+        let token = CancellationToken::new();
+        deliver.send(Signal::Terminate).await.unwrap();
+        deliver.send(Signal::Interrupt).await.unwrap();
+        signals.wait_for_shutdown(&token).await.unwrap();
+        let force = signals.recv().await.unwrap();
+        assert_eq!(force.exit_code(), 130);
+        assert_eq!(Signal::Terminate.exit_code(), 143);
+    }
+
+    #[tokio::test]
+    async fn test_stops_waiting_when_cancelled_elsewhere() {
+        let (_deliver, mut signals) = SignalName::channel();
+        let token = CancellationToken::new();
+        token.cancel();
+        assert_eq!(signals.wait_for_shutdown(&token).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_closed_channel_does_not_cancel() {
+        let (deliver, mut signals) = SignalName::channel();
+        drop(deliver);
+        let token = CancellationToken::new();
+        assert_eq!(signals.wait_for_shutdown(&token).await, None);
+        assert!(!token.is_cancelled());
+    }
+}
@@ -0,0 +1,4 @@
+-- Reverts {{ version }}_{{ migration }}.up.sql, leaving records_table as the
+-- migrations before it made it.
+DROP INDEX records_table_new_column_idx;
+ALTER TABLE records_table DROP COLUMN new_column;
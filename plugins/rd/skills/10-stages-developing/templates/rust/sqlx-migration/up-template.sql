@@ -0,0 +1,9 @@
+-- [Brief description of what the migration changes]
+--
+-- sqlx applies this after every migration with a lower version, such as
+-- the sqlx bundle's 0001_create_records_table.sql, and the matching
+-- .down.sql reverts it. Once it has run anywhere, change the schema in a
+-- new migration rather than editing this one. A NOT NULL column needs a
+-- DEFAULT for the rows that already exist.
+ALTER TABLE records_table ADD COLUMN new_column TEXT;
+CREATE INDEX records_table_new_column_idx ON records_table (new_column);
@@ -0,0 +1,8 @@
+-- Table behind crate_name's `Record` repository. sqlx runs migrations in
+-- the order of their numbers; add later changes as new files rather than
+-- editing this one once it has run anywhere.
+CREATE TABLE records_table (
+    id BIGSERIAL PRIMARY KEY,
+    title TEXT NOT NULL UNIQUE,
+    body TEXT NOT NULL DEFAULT ''
+);
@@ -0,0 +1,171 @@
+//! [Brief description of what this module does]
+//!
+//! [`RecordRepository`] reads and writes the `records_table` table created
+//! by `migrations/0001_create_records_table.sql`. Queries are checked at
+//! run time, so building needs no database; switch to `sqlx::query_as!`
+//! for compile-time checking once one is available to the build.
+
+use std::fmt;
+
+use sqlx::{FromRow, PgPool};
+
+/// A row of `records_table`.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct Record {
+    pub id: i64,
+    pub title: String,
+    pub body: String,
+}
+
+/// Fields of a [`Record`] to insert or update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewRecord {
+    pub title: String,
+    pub body: String,
+}
+
+/// Why a repository call failed.
+#[derive(Debug)]
+pub enum RepositoryError {
+    /// No row has the id.
+    NotFound(i64),
+    /// A unique constraint rejected the row, e.g. a duplicate title.
+    Conflict(String),
+    /// Any other database or connection error.
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(id) => write!(f, "no record with id {id}"),
+            Self::Conflict(message) => write!(f, "conflicts with an existing record: {message}"),
+            Self::Database(err) => write!(f, "database error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Database(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for RepositoryError {
+    fn from(err: sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                Self::Conflict(db.message().to_string())
+            }
+            err => Self::Database(err),
+        }
+    }
+}
+
+/// Result of a repository call.
+pub type Result<T> = std::result::Result<T, RepositoryError>;
+
+/// CRUD access to `records_table`.
+#[derive(Debug, Clone)]
+pub struct RecordRepository {
+    pool: PgPool,
+}
+
+impl RecordRepository {
+    /// A repository using connections from `pool`.
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Inserts `new` and returns the stored row.
+    ///
+    /// # Errors
+    ///
+    /// [`RepositoryError::Conflict`] if the title is taken.
+    pub async fn create(&self, new: &NewRecord) -> Result<Record> {
+        let row = sqlx::query_as(
+            "INSERT INTO records_table (title, body) VALUES ($1, $2) RETURNING id, title, body",
+        )
+        .bind(&new.title)
+        .bind(&new.body)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// The row `id`.
+    ///
+    /// # Errors
+    ///
+    /// [`RepositoryError::NotFound`] if there is none.
+    pub async fn get(&self, id: i64) -> Result<Record> {
+        sqlx::query_as("SELECT id, title, body FROM records_table WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(RepositoryError::NotFound(id))
+    }
+
+    /// Every row, by id.
+    ///
+    /// # Errors
+    ///
+    /// [`RepositoryError::Database`] if the query fails.
+    pub async fn list(&self) -> Result<Vec<Record>> {
+        let rows = sqlx::query_as("SELECT id, title, body FROM records_table ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows)
+    }
+
+    /// Replaces the fields of row `id` with `new` and returns it.
+    ///
+    /// # Errors
+    ///
+    /// [`RepositoryError::NotFound`] if there is no such row, and
+    /// [`RepositoryError::Conflict`] if the title is taken.
+    pub async fn update(&self, id: i64, new: &NewRecord) -> Result<Record> {
+        sqlx::query_as(
+            "UPDATE records_table SET title = $2, body = $3 WHERE id = $1 \
+             RETURNING id, title, body",
+        )
+        .bind(id)
+        .bind(&new.title)
+        .bind(&new.body)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(RepositoryError::NotFound(id))
+    }
+
+    /// Deletes row `id`.
+    ///
+    /// # Errors
+    ///
+    /// [`RepositoryError::NotFound`] if there is none.
+    pub async fn delete(&self, id: i64) -> Result<()> {
+        let done = sqlx::query("DELETE FROM records_table WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if done.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(id));
+        }
+        Ok(())
+    }
+}
+
+{{> partials/test-header}}
+
+    #[test]
+    fn test_error_display() {
+        assert_eq!(
+            RepositoryError::NotFound(7).to_string(),
+            "no record with id 7"
+        );
+        let err = RepositoryError::from(sqlx::Error::RowNotFound);
+        assert!(matches!(err, RepositoryError::Database(_)));
+    }
+}
@@ -0,0 +1 @@
+Command::SubcommandName(args) => args.run(cli.verbose),
@@ -0,0 +1,28 @@
+/// Arguments of the `{{ subcommand | kebab_case }}` subcommand.
+#[derive(Debug, clap::Args)]
+struct SubcommandNameArgs {
+    /// File to read.
+    path: PathBuf,
+
+    /// How many lines to print.
+    #[arg(short = 'n', long, default_value_t = 10)]
+    lines: usize,
+}
+
+impl SubcommandNameArgs {
+    /// Prints the first lines of the file.
+    fn run(self, verbose: bool) -> Result<(), CliError> {
+        let Self { path, lines } = self;
+        if lines == 0 {
+            return Err(CliError::Usage("--lines must be at least 1".to_string()));
+        }
+        if verbose {
+            eprintln!("reading {}", path.display());
+        }
+        let text = fs::read_to_string(&path).map_err(|source| CliError::Io { path, source })?;
+        for line in text.lines().take(lines) {
+            println!("{line}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+#[test]
+fn test_{{ subcommand | snake_case }}_prints_first_lines() {
+    let command = "{{ subcommand | kebab_case }}";
+    let name = format!("{command}-{}.txt", std::process::id());
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, "one\ntwo\nthree\n").unwrap();
+    let assert = cli().args([command, "--lines", "2"]).arg(&path).assert();
+    std::fs::remove_file(&path).unwrap();
+    assert.success().stdout("one\ntwo\n");
+}
+
+#[test]
+fn test_{{ subcommand | snake_case }}_zero_lines_is_usage_error() {
+    let command = "{{ subcommand | kebab_case }}";
+    cli()
+        .args([command, "-n", "0", "unused.txt"])
+        .assert()
+        .code(64)
+        .stderr("error: --lines must be at least 1\n");
+}
+
+#[test]
+fn test_{{ subcommand | snake_case }}_missing_file_is_io_error() {
+    let command = "{{ subcommand | kebab_case }}";
+    cli()
+        .args([command, "--verbose", "does-not-exist.txt"])
+        .assert()
+        .code(74);
+}
@@ -0,0 +1,2 @@
+/// [Brief description of what the subcommand does]
+SubcommandName(SubcommandNameArgs),
@@ -0,0 +1,162 @@
+//! [Brief description of what this module does]
+//!
+//! Call [`init`] once, early in `main`. Which events are kept is decided by
+//! the `RUST_LOG` environment variable, e.g. `RUST_LOG=debug` or
+//! `RUST_LOG=warn,my_crate=trace`, and by [`DEFAULT_FILTER`] when it is
+//! unset. Events are written to stderr, readable or as one JSON object per
+//! line for log collectors.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Instant;
+
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::{SubscriberInitExt, TryInitError};
+use tracing_subscriber::EnvFilter;
+
+/// Filter directives used when `RUST_LOG` is unset.
+pub const DEFAULT_FILTER: &str = "{{ default_filter }}";
+
+/// How events are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Multi-line and colored, for people.
+{{#if default_format == "pretty"}}
+    #[default]
+{{/if}}
+    Pretty,
+    /// One JSON object per line, for machines.
+{{#if default_format == "json"}}
+    #[default]
+{{/if}}
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(Self::Pretty),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown log format `{s}`: expected pretty or json")),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pretty => "pretty",
+            Self::Json => "json",
+        })
+    }
+}
+
+/// The filter from `RUST_LOG`, or [`DEFAULT_FILTER`] if it is unset or
+/// invalid.
+pub fn filter() -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER))
+}
+
+/// Installs the global subscriber, writing events in `format` to stderr.
+///
+/// # Errors
+///
+/// Returns an error if a global subscriber is already installed.
+pub fn init(format: Format) -> Result<(), TryInitError> {
+    let registry = tracing_subscriber::registry().with(filter());
+    let layer = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+    match format {
+        Format::Pretty => registry.with(layer.pretty()).try_init(),
+        Format::Json => registry.with(layer.json()).try_init(),
+    }
+}
+
+/// Runs `f` in an `operation` span named `name`, logging how long it took
+/// at debug level when it returns. Events `f` logs carry the span.
+///
+/// ```ignore
+/// // `load_rows` stands for any operation of the caller's.
+/// let rows = telemetry::instrumented("load_rows", || load_rows(&path));
+/// ```
+pub fn instrumented<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let span = tracing::info_span!("operation", name);
+    let _entered = span.enter();
+    let start = Instant::now();
+    let result = f();
+    tracing::debug!(elapsed_us = start.elapsed().as_micros() as u64, "finished");
+    result
+}
+
+{{> partials/test-header}}
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    /// Collects what a subscriber writes.
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Runs `f` with a JSON subscriber at debug level and returns what it
+    /// wrote.
+    fn captured(f: impl FnOnce()) -> String {
+        let capture = Capture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, f);
+        let bytes = capture.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_instrumented_returns_result() {
+        assert_eq!(instrumented("add", || 2 + 2), 4);
+    }
+
+    #[test]
+    fn test_instrumented_events_carry_span() {
+        let output = captured(|| {
+            instrumented("load", || tracing::info!("loading"));
+        });
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2, "{output}");
+        assert!(lines[0].contains("\"message\":\"loading\""), "{output}");
+        assert!(lines[0].contains("\"name\":\"load\""), "{output}");
+        assert!(lines[1].contains("\"message\":\"finished\""), "{output}");
+        assert!(lines[1].contains("\"elapsed_us\":"), "{output}");
+    }
+
+    #[test]
+    fn test_format_parses_and_displays() {
+        assert_eq!("JSON".parse(), Ok(Format::Json));
+        assert_eq!("pretty".parse(), Ok(Format::Pretty));
+        assert!("xml".parse::<Format>().is_err());
+        assert_eq!(Format::Json.to_string(), "json");
+    }
+
+    #[test]
+    fn test_default_filter_is_valid() {
+        assert!(EnvFilter::try_new(DEFAULT_FILTER).is_ok());
+    }
+
+    #[test]
+    fn test_init_installs_once() {
+        assert!(init(Format::default()).is_ok());
+        assert!(init(Format::Json).is_err());
+    }
+}
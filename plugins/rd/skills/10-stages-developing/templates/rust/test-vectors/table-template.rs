@@ -0,0 +1,39 @@
+{{> partials/test-header}}
+
+    // Boundary values for `{{ function }}`, generated by `cc-scaffold
+    // test-vectors`: the first row holds a typical value of each parameter,
+    // and each row after it changes one of them to a boundary of its type.
+{{#if expect}}
+    // A row with `{{ expect }}` at `None` only checks that the call does not
+    // panic; set it to `Some(true)` or `Some(false)` once the row's outcome
+    // is decided.
+{{else}}
+    // Each row checks that the call does not panic.
+{{/if}}
+{{#if async}}
+    #[tokio::test]
+    async fn {{ test_name }}() {
+{{else}}
+    #[test]
+    fn {{ test_name }}() {
+{{/if}}
+        let test_cases = [
+{{#each rows}}
+            {{ this }},
+{{/each}}
+        ];
+
+{{#if expect}}
+        for (row, {{ pattern }}) in test_cases.into_iter().enumerate() {
+            let result = {{ call }};
+            if let Some({{ expect }}) = {{ expect }} {
+                assert_eq!(result.{{ check }}(), {{ expect }}, "row {row}");
+            }
+        }
+{{else}}
+        for {{ pattern }} in test_cases {
+            {{#if discard}}let _ = {{/if}}{{ call }};
+        }
+{{/if}}
+    }
+}
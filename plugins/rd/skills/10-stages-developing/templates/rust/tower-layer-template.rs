@@ -0,0 +1,170 @@
+//! [Brief description of what this module does]
+//!
+//! [`RequestIdLayer`] wraps a service in [`RequestId`], which makes sure
+//! every request carries a `{{ header }}` header — keeping the caller's, or
+//! assigning the next number — stores it in the request extensions as a
+//! [`RequestIdValue`] for handlers, and copies it onto the response.
+//!
+//! Two details are easy to get wrong in middleware like this. `poll_ready`
+//! must forward to the inner service, so that backpressure (a concurrency
+//! limit, a load shedder) reaches the caller. And `call` must call the
+//! inner service directly rather than from inside the returned future: the
+//! readiness `poll_ready` reserved belongs to this service, not to a clone
+//! that was never polled.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use http::{HeaderName, HeaderValue, Request, Response};
+use tower::{Layer, Service};
+
+/// The header holding the request id.
+pub static HEADER_NAME: HeaderName = HeaderName::from_static("{{ header }}");
+
+/// The request id, as stored in the request extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestIdValue(pub HeaderValue);
+
+/// Adds [`RequestId`] to a service stack.
+#[derive(Debug, Clone, Default)]
+pub struct RequestIdLayer {
+    next: Arc<AtomicU64>,
+}
+
+impl RequestIdLayer {
+    /// Creates a layer numbering requests from 1.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestId<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestId {
+            inner,
+            next: Arc::clone(&self.next),
+        }
+    }
+}
+
+/// Middleware giving every request and its response a request id.
+#[derive(Debug, Clone)]
+pub struct RequestId<S> {
+    inner: S,
+    next: Arc<AtomicU64>,
+}
+
+impl<S> RequestId<S> {
+    fn id_for<B>(&self, request: &Request<B>) -> HeaderValue {
+        if let Some(id) = request.headers().get(&HEADER_NAME) {
+            return id.clone();
+        }
+        let next = self.next.fetch_add(1, Ordering::Relaxed) + 1;
+        HeaderValue::from(next)
+    }
+}
+
+impl<S, B, R> Service<Request<B>> for RequestId<S>
+where
+    S: Service<Request<B>, Response = Response<R>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        let id = self.id_for(&request);
+        request.headers_mut().insert(&HEADER_NAME, id.clone());
+        let value = RequestIdValue(id.clone());
+        request.extensions_mut().insert(value);
+        // Called here, on the service `poll_ready` readied.
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let mut response = response.await?;
+            response.headers_mut().insert(&HEADER_NAME, id);
+            Ok(response)
+        })
+    }
+}
+
+{{> partials/test-header}}
+    use std::convert::Infallible;
+    use std::future::poll_fn;
+
+    use tower::{service_fn, ServiceBuilder, ServiceExt};
+
+    /// Answers with the request id the handler saw in the extensions.
+    async fn echo_id(request: Request<String>) -> Result<Response<String>, Infallible> {
+        let id: &RequestIdValue = request.extensions().get().unwrap();
+        let body = id.0.to_str().unwrap().to_string();
+        Ok(Response::new(body))
+    }
+
+    fn request(id: Option<&'static str>) -> Request<String> {
+        let mut builder = Request::builder().uri("/");
+        if let Some(id) = id {
+            builder = builder.header(&HEADER_NAME, id);
+        }
+        builder.body(String::new()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_assigns_id_to_request_and_response() {
+        let service = ServiceBuilder::new()
+            .layer(RequestIdLayer::new())
+            .service(service_fn(echo_id));
+        let response = service.oneshot(request(None)).await.unwrap();
+        assert_eq!(response.headers()[&HEADER_NAME], "1");
+        assert_eq!(response.body(), "1");
+    }
+
+    #[tokio::test]
+    async fn test_keeps_incoming_id() {
+        let service = ServiceBuilder::new()
+            .layer(RequestIdLayer::new())
+            .service(service_fn(echo_id));
+        let response = service.oneshot(request(Some("abc-123"))).await.unwrap();
+        assert_eq!(response.headers()[&HEADER_NAME], "abc-123");
+        assert_eq!(response.body(), "abc-123");
+    }
+
+    #[tokio::test]
+    async fn test_numbers_requests_across_clones() {
+        let layer = RequestIdLayer::new();
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let service = ServiceBuilder::new()
+                .layer(layer.clone())
+                .service(service_fn(echo_id));
+            let response = service.oneshot(request(None)).await.unwrap();
+            ids.push(response.into_body());
+        }
+        assert_eq!(ids, ["1", "2", "3"]);
+    }
+
+    #[tokio::test]
+    async fn test_poll_ready_forwards_backpressure() {
+        let mut service = ServiceBuilder::new()
+            .layer(RequestIdLayer::new())
+            .concurrency_limit(1)
+            .service(service_fn(echo_id));
+        let first = service.ready().await.unwrap().call(request(None));
+        // The limit is used up until the first response is done.
+        let ready = poll_fn(|cx| Poll::Ready(service.poll_ready(cx))).await;
+        assert!(ready.is_pending());
+
+        assert_eq!(first.await.unwrap().body(), "1");
+        let ready = poll_fn(|cx| Poll::Ready(service.poll_ready(cx))).await;
+        assert!(matches!(ready, Poll::Ready(Ok(()))));
+    }
+}
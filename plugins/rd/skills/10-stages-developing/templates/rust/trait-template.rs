@@ -0,0 +1,111 @@
+// [Brief description of what this module does]
+
+/// [Brief description of what implementors provide]
+///
+/// Implementors only write [`method_name`](TraitName::method_name); the
+/// other methods are provided in terms of it. The trait is object safe, so
+/// callers can hold any implementation as `Box<dyn TraitName>`.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Doctests link the crate as a dependency, where `crate::` paths do not resolve.
+/// use crate::trait_module::{ImplName, TraitName};
+///
+/// let imp: Box<dyn TraitName> = Box::new(ImplName::new("processed_"));
+/// assert_eq!(imp.method_name("example")?, "processed_example");
+/// ```
+pub trait TraitName {
+    /// [Brief description of what the method does]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error message if `input` cannot be handled.
+    fn method_name(&self, input: &str) -> Result<String, String>;
+
+    /// Calls [`method_name`](TraitName::method_name) on every item of
+    /// `inputs` in order, stopping at the first error.
+    ///
+    /// Override it when a batch can be handled more efficiently than one
+    /// item at a time.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error `method_name` returns.
+    fn method_name_all(&self, inputs: &[&str]) -> Result<Vec<String>, String> {
+        inputs.iter().map(|input| self.method_name(input)).collect()
+    }
+}
+
+/// [Brief description of the implementation]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImplName {
+    prefix: String,
+}
+
+impl ImplName {
+    /// Creates an implementation that prepends `prefix` to its input.
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+}
+
+impl TraitName for ImplName {
+    fn method_name(&self, input: &str) -> Result<String, String> {
+        if input.is_empty() {
+            return Err("input cannot be empty".to_string());
+        }
+        Ok(format!("{}{}", self.prefix, input))
+    }
+}
+
+{{> partials/test-header}}
+
+    fn boxed() -> Box<dyn TraitName> {
+        Box::new(ImplName::new("processed_"))
+    }
+
+    #[test]
+    fn test_method_name_through_trait_object() {
+        let imp = boxed();
+        assert_eq!(imp.method_name("hello").unwrap(), "processed_hello");
+    }
+
+    #[test]
+    fn test_method_name_empty_input() {
+        let imp = boxed();
+        let err = imp.method_name("").unwrap_err();
+        assert!(err.contains("empty"));
+    }
+
+    #[test]
+    fn test_method_name_all_provided() {
+        let imp = boxed();
+        assert_eq!(
+            imp.method_name_all(&["a", "b"]).unwrap(),
+            vec!["processed_a", "processed_b"]
+        );
+        assert_eq!(imp.method_name_all(&[]).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_method_name_all_stops_at_first_error() {
+        let imp = boxed();
+        assert!(imp.method_name_all(&["a", "", "b"]).is_err());
+    }
+
+    #[test]
+    fn test_trait_objects_share_a_collection() {
+        let imps: Vec<Box<dyn TraitName>> = vec![
+            Box::new(ImplName::new("x_")),
+            Box::new(ImplName::default()),
+        ];
+        let outputs: Vec<String> = imps
+            .iter()
+            .map(|imp| imp.method_name("item").unwrap())
+            .collect();
+        assert_eq!(outputs, vec!["x_item", "item"]);
+    }
+}
@@ -0,0 +1,279 @@
+//! [Brief description of what this module does]
+//!
+//! [`ScreenName`] is one terminal screen: its state, how keys change it,
+//! and how it is drawn. [`run`] draws it, then redraws after each event
+//! until the screen asks to quit. Drawing depends only on the state, so
+//! the tests render to a `TestBackend` and compare the buffer line by line.
+//!
+//! ```ignore
+//! // Takes over the terminal until the user quits.
+//! let mut screen = ScreenName::new(items);
+//! ratatui::run(|terminal| run(terminal, &mut screen, iter::repeat_with(event::read)))?;
+//! ```
+//!
+//! `ratatui::run` enters the alternate screen and raw mode, and restores the
+//! terminal afterwards, on a panic too.
+
+use std::error::Error;
+use std::io;
+
+use ratatui::backend::Backend;
+use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListState};
+use ratatui::{Frame, Terminal};
+
+/// A list of items to tick off, one of them selected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenName {
+    items: Vec<String>,
+    done: Vec<bool>,
+    selected: usize,
+    show_help: bool,
+    quit: bool,
+}
+
+impl ScreenName {
+    /// A screen listing `items`, none done, the first selected.
+    pub fn new(items: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let items: Vec<String> = items.into_iter().map(Into::into).collect();
+        Self {
+            done: vec![false; items.len()],
+            items,
+            selected: 0,
+            show_help: false,
+            quit: false,
+        }
+    }
+
+    /// The index of the selected item.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Whether item `index` is ticked off.
+    pub fn is_done(&self, index: usize) -> bool {
+        self.done.get(index).copied().unwrap_or(false)
+    }
+
+    /// Whether the screen is finished and [`run`] should return.
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Updates the state for `event`. Only key presses change it; a resize
+    /// needs nothing but the redraw that follows every event.
+    pub fn handle_event(&mut self, event: &Event) {
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                self.handle_key(*key);
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        let last = self.items.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('j') | KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('g') | KeyCode::Home => self.selected = 0,
+            KeyCode::Char('G') | KeyCode::End => self.selected = last,
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                if let Some(done) = self.done.get_mut(self.selected) {
+                    *done = !*done;
+                }
+            }
+            KeyCode::Char('?') => self.show_help = !self.show_help,
+            _ => {}
+        }
+    }
+
+    /// Draws the screen over the whole frame: the list in a border, and a
+    /// status line under it.
+    pub fn draw(&self, frame: &mut Frame) {
+        let [list_area, status_area] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+
+        let items = self.items.iter().zip(&self.done).map(|(item, &done)| {
+            let mark = if done { "[x]" } else { "[ ]" };
+            Line::from(format!("{mark} {item}"))
+        });
+        let list = List::new(items)
+            .block(Block::bordered().title(" Items "))
+            .highlight_symbol("> ")
+            .highlight_style(Style::new().reversed());
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(list, list_area, &mut state);
+
+        frame.render_widget(self.status(), status_area);
+    }
+
+    fn status(&self) -> Line<'static> {
+        if self.show_help {
+            return Line::from("j/k move  space tick  q quit");
+        }
+        let done = self.done.iter().filter(|&&done| done).count();
+        Line::from(format!("{done}/{} done  ? help", self.items.len()))
+    }
+}
+
+/// Draws `screen` on `terminal`, then handles `events` one at a time,
+/// redrawing after each, until the screen quits or the events run out.
+///
+/// # Errors
+///
+/// The first error reading an event or drawing.
+pub fn run<B>(
+    terminal: &mut Terminal<B>,
+    screen: &mut ScreenName,
+    events: impl IntoIterator<Item = io::Result<Event>>,
+) -> Result<(), Box<dyn Error>>
+where
+    B: Backend,
+    B::Error: 'static,
+{
+    terminal.draw(|frame| screen.draw(frame))?;
+    for event in events {
+        screen.handle_event(&event?);
+        if screen.should_quit() {
+            break;
+        }
+        terminal.draw(|frame| screen.draw(frame))?;
+    }
+    Ok(())
+}
+
+{{> partials/test-header}}
+    use ratatui::backend::TestBackend;
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+
+    const WIDTH: u16 = 32;
+
+    fn screen() -> ScreenName {
+        ScreenName::new(["write tests", "fix bug", "ship"])
+    }
+
+    fn key(code: KeyCode) -> io::Result<Event> {
+        Ok(Event::Key(KeyEvent::from(code)))
+    }
+
+    /// The screen as drawn on a fresh terminal of 6 rows.
+    fn render(screen: &ScreenName) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, 6)).unwrap();
+        terminal.draw(|frame| screen.draw(frame)).unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    /// `lines`, with the list's row `row` highlighted as selected.
+    fn expected(lines: [&str; 6], row: u16) -> Buffer {
+        let mut buffer = Buffer::with_lines(lines);
+        let highlighted = Rect::new(1, row + 1, WIDTH - 2, 1);
+        buffer.set_style(highlighted, Style::new().reversed());
+        buffer
+    }
+
+    #[test]
+    fn test_draws_list_and_status() {
+        let lines = [
+            "┌ Items ───────────────────────┐",
+            "│> [ ] write tests             │",
+            "│  [ ] fix bug                 │",
+            "│  [ ] ship                    │",
+            "└──────────────────────────────┘",
+            "0/3 done  ? help                ",
+        ];
+        assert_eq!(render(&screen()), expected(lines, 0));
+    }
+
+    #[test]
+    fn test_keys_move_and_tick() {
+        let mut screen = screen();
+        for code in [KeyCode::Down, KeyCode::Char(' '), KeyCode::Char('j')] {
+            screen.handle_event(&key(code).unwrap());
+        }
+        assert_eq!(screen.selected(), 2);
+        assert!(screen.is_done(1));
+
+        let lines = [
+            "┌ Items ───────────────────────┐",
+            "│  [ ] write tests             │",
+            "│  [x] fix bug                 │",
+            "│> [ ] ship                    │",
+            "└──────────────────────────────┘",
+            "1/3 done  ? help                ",
+        ];
+        assert_eq!(render(&screen), expected(lines, 2));
+    }
+
+    #[test]
+    fn test_selection_stays_in_bounds() {
+        let mut screen = screen();
+        screen.handle_event(&key(KeyCode::Up).unwrap());
+        assert_eq!(screen.selected(), 0);
+        screen.handle_event(&key(KeyCode::End).unwrap());
+        screen.handle_event(&key(KeyCode::Down).unwrap());
+        assert_eq!(screen.selected(), 2);
+
+        let mut empty = ScreenName::new(Vec::<String>::new());
+        for code in [KeyCode::Down, KeyCode::End, KeyCode::Char(' ')] {
+            empty.handle_event(&key(code).unwrap());
+        }
+        assert_eq!(empty.selected(), 0);
+    }
+
+    #[test]
+    fn test_help_replaces_status() {
+        let mut screen = screen();
+        screen.handle_event(&key(KeyCode::Char('?')).unwrap());
+        let buffer = render(&screen);
+        let status: String = (0..WIDTH).map(|x| buffer[(x, 5)].symbol()).collect();
+        assert_eq!(status.trim_end(), "j/k move  space tick  q quit");
+    }
+
+    #[test]
+    fn test_key_releases_are_ignored() {
+        let mut screen = screen();
+        let mut release = KeyEvent::from(KeyCode::Down);
+        release.kind = KeyEventKind::Release;
+        screen.handle_event(&Event::Key(release));
+        screen.handle_event(&Event::Resize(80, 24));
+        assert_eq!(screen, self::screen());
+    }
+
+    #[test]
+    fn test_run_until_quit() {
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, 6)).unwrap();
+        let mut screen = screen();
+        let events = [
+            key(KeyCode::Char(' ')),
+            key(KeyCode::Char('q')),
+            key(KeyCode::Char('j')),
+        ];
+        run(&mut terminal, &mut screen, events).unwrap();
+
+        assert!(screen.should_quit());
+        assert_eq!(screen.selected(), 0, "events after quitting are not read");
+        let lines = [
+            "┌ Items ───────────────────────┐",
+            "│> [x] write tests             │",
+            "│  [ ] fix bug                 │",
+            "│  [ ] ship                    │",
+            "└──────────────────────────────┘",
+            "1/3 done  ? help                ",
+        ];
+        terminal.backend().assert_buffer(&expected(lines, 0));
+    }
+
+    #[test]
+    fn test_run_stops_at_a_read_error() {
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, 6)).unwrap();
+        let events = std::iter::once(Err(io::Error::other("terminal closed")));
+        let err = run(&mut terminal, &mut screen(), events).unwrap_err();
+        assert_eq!(err.to_string(), "terminal closed");
+    }
+}
@@ -0,0 +1,155 @@
+//! [Brief description of what this module does]
+//!
+//! A [`TypeName`] goes from [`Idle`] to [`Running`] to [`Stopped`], and
+//! back to `Idle` when reset. Its state is a type parameter: each state is
+//! a zero-sized type, and each transition consumes the machine and returns
+//! it in the next state, so an invalid transition does not compile.
+//!
+//! ```
+//! use crate_name::typestate_module::TypeName;
+//!
+//! let mut running = TypeName::new("nightly").start();
+//! running.tick();
+//! let stopped = running.stop();
+//! assert_eq!(stopped.ticks(), 1);
+//! ```
+//!
+//! An idle machine cannot be stopped:
+//!
+//! ```compile_fail,E0599
+//! use crate_name::typestate_module::TypeName;
+//!
+//! let stopped = TypeName::new("nightly").stop();
+//! ```
+//!
+//! and a machine cannot be used in a state it has left:
+//!
+//! ```compile_fail,E0382
+//! use crate_name::typestate_module::TypeName;
+//!
+//! let running = TypeName::new("nightly").start();
+//! let stopped = running.stop();
+//! running.tick();
+//! ```
+
+use std::marker::PhantomData;
+
+mod sealed {
+    /// Implemented here only, so that no other crate can add a state.
+    pub trait Sealed {}
+}
+
+/// A state of a [`TypeName`]. Sealed: the states are the ones below.
+pub trait State: sealed::Sealed {}
+
+/// Created and not yet started.
+#[derive(Debug)]
+pub struct Idle;
+
+/// Started; counts ticks.
+#[derive(Debug)]
+pub struct Running;
+
+/// Stopped; the ticks can be read.
+#[derive(Debug)]
+pub struct Stopped;
+
+impl sealed::Sealed for Idle {}
+impl sealed::Sealed for Running {}
+impl sealed::Sealed for Stopped {}
+impl State for Idle {}
+impl State for Running {}
+impl State for Stopped {}
+
+/// A state machine in state `S`.
+#[derive(Debug)]
+pub struct TypeName<S: State> {
+    name: String,
+    ticks: u32,
+    state: PhantomData<S>,
+}
+
+impl<S: State> TypeName<S> {
+    /// The name the machine was created with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn into_state<T: State>(self) -> TypeName<T> {
+        TypeName {
+            name: self.name,
+            ticks: self.ticks,
+            state: PhantomData,
+        }
+    }
+}
+
+impl TypeName<Idle> {
+    /// Creates an idle machine.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ticks: 0,
+            state: PhantomData,
+        }
+    }
+
+    /// Starts the machine, with no ticks counted.
+    pub fn start(self) -> TypeName<Running> {
+        let mut running = self.into_state();
+        running.ticks = 0;
+        running
+    }
+}
+
+impl TypeName<Running> {
+    /// Counts one tick.
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    /// Stops the machine.
+    pub fn stop(self) -> TypeName<Stopped> {
+        self.into_state()
+    }
+}
+
+impl TypeName<Stopped> {
+    /// Ticks counted while the machine ran.
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+
+    /// Makes the machine idle again.
+    pub fn reset(self) -> TypeName<Idle> {
+        self.into_state()
+    }
+}
+
+{{> partials/test-header}}
+    use std::mem::size_of;
+
+    #[test]
+    fn test_transitions_in_order() {
+        let mut running = TypeName::new("nightly").start();
+        running.tick();
+        running.tick();
+        let stopped = running.stop();
+        assert_eq!(stopped.ticks(), 2);
+        assert_eq!(stopped.name(), "nightly");
+    }
+
+    #[test]
+    fn test_restart_counts_from_zero() {
+        let mut running = TypeName::new("nightly").start();
+        running.tick();
+        let running = running.stop().reset().start();
+        assert_eq!(running.stop().ticks(), 0);
+    }
+
+    #[test]
+    fn test_states_cost_nothing() {
+        assert_eq!(size_of::<Idle>(), 0);
+        assert_eq!(size_of::<TypeName<Idle>>(), size_of::<TypeName<Stopped>>());
+    }
+}
@@ -0,0 +1,142 @@
+//! [Brief description of what this module does]
+//!
+//! [`function_name_unchecked`] is the `unsafe` core and [`function_name`]
+//! the safe wrapper most callers should use. The wrapper checks what the
+//! unsafe function only assumes, so the checks and the assumptions sit
+//! side by side and are reviewed together.
+//!
+//! Unsafe code in this module follows these conventions:
+//!
+//! * every `unsafe fn` has a `# Safety` section listing what its caller
+//!   must guarantee, one invariant per bullet;
+//! * every `unsafe` block has a `// SAFETY:` comment that ticks off each of
+//!   those invariants and says why it holds at that point;
+//! * every invariant that is cheap to check is also a `debug_assert!`, so a
+//!   broken caller fails loudly in tests instead of corrupting memory;
+//! * the tests drive the unsafe code the way callers do, and are run under
+//!   `cargo +nightly miri test`, which reports the undefined behaviour —
+//!   out-of-bounds access, aliasing `&mut` — an ordinary test run misses.
+//!
+//! The lints below hold the module to the first two; move them to `lib.rs`
+//! to hold the whole crate to them.
+
+#![deny(unsafe_op_in_unsafe_fn)]
+#![warn(clippy::missing_safety_doc, clippy::undocumented_unsafe_blocks)]
+
+/// [Brief description of what the function does]
+///
+/// Returns mutable references to the elements at `a` and `b` of `slice`
+/// at the same time, or `None` if either index is out of bounds or both
+/// are the same.
+pub fn function_name<T>(slice: &mut [T], a: usize, b: usize) -> Option<(&mut T, &mut T)> {
+    let len = slice.len();
+    if a >= len || b >= len || a == b {
+        return None;
+    }
+    // SAFETY:
+    // - [x] in bounds: `a` and `b` were checked against `len` above.
+    // - [x] distinct: `a != b` was checked above.
+    Some(unsafe { function_name_unchecked(slice, a, b) })
+}
+
+/// [`function_name`] without the checks, for callers that have already
+/// established them.
+///
+/// # Safety
+///
+/// The caller must guarantee that:
+///
+/// - `a < slice.len()` and `b < slice.len()`;
+/// - `a != b`, so the two references never alias.
+///
+/// Debug builds check both and panic if either is broken.
+pub unsafe fn function_name_unchecked<T>(slice: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    let len = slice.len();
+    debug_assert!(a < len, "index out of bounds: {a} for length {len}");
+    debug_assert!(b < len, "index out of bounds: {b} for length {len}");
+    debug_assert_ne!(a, b, "indices must be distinct");
+    // One raw pointer for both elements: calling `as_mut_ptr` once per
+    // element would reborrow `slice` twice, and the second reborrow
+    // invalidates the first reference, which Miri reports.
+    let base = slice.as_mut_ptr();
+    // SAFETY:
+    // - [x] in bounds: the caller guarantees `a < len` and `b < len`, so
+    //   both offsets stay inside the slice `base` points to.
+    // - [x] distinct: the caller guarantees `a != b`, so the references are
+    //   to different elements and never alias.
+    // - [x] lifetime: both derive from `slice`, which stays mutably
+    //   borrowed for as long as either reference lives.
+    unsafe { (&mut *base.add(a), &mut *base.add(b)) }
+}
+
+{{> partials/test-header}}
+    /// Interpreting every access makes Miri orders of magnitude slower, so
+    /// the exhaustive test shrinks under it.
+    const LEN: usize = if cfg!(miri) { 6 } else { 64 };
+
+    #[test]
+    fn test_function_name_returns_both_elements() {
+        let mut values = [1, 2, 3, 4];
+        let (first, last) = function_name(&mut values, 0, 3).unwrap();
+        std::mem::swap(first, last);
+        assert_eq!(values, [4, 2, 3, 1]);
+    }
+
+    #[test]
+    fn test_function_name_rejects_what_unchecked_assumes() {
+        let mut values = [1, 2, 3];
+        assert!(function_name(&mut values, 1, 1).is_none());
+        assert!(function_name(&mut values, 0, 3).is_none());
+        assert!(function_name(&mut values, 3, 0).is_none());
+        assert!(function_name::<u8>(&mut [], 0, 1).is_none());
+    }
+
+    #[test]
+    fn test_references_are_independent() {
+        // Heap-owning elements, so Miri also checks nothing is dropped or
+        // read through a dangling pointer.
+        let mut names: Vec<String> = ["a", "b", "c"].map(String::from).into();
+        // SAFETY:
+        // - [x] in bounds: 0 and 2 are both below `names.len()`, 3.
+        // - [x] distinct: 0 != 2.
+        let (first, last) = unsafe { function_name_unchecked(&mut names, 0, 2) };
+        first.push('1');
+        last.push('3');
+        first.push_str(last);
+        assert_eq!(names, ["a1c3", "b", "c3"]);
+    }
+
+    #[test]
+    fn test_every_pair_of_indices() {
+        let mut values: Vec<Box<usize>> = (0..LEN).map(Box::new).collect();
+        for a in 0..LEN {
+            for b in (0..LEN).filter(|&b| b != a) {
+                let (x, y) = function_name(&mut values, a, b).unwrap();
+                assert_eq!((**x, **y), (a, b));
+                std::mem::swap(x, y);
+                std::mem::swap(x, y);
+            }
+        }
+        assert!(values.iter().enumerate().all(|(i, value)| **value == i));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "indices must be distinct")]
+    fn test_debug_assertions_catch_aliasing() {
+        let mut values = [1, 2, 3];
+        // SAFETY: deliberately broken — `a == b` — to show the debug
+        // assertion panics before any aliasing reference is made.
+        let _ = unsafe { function_name_unchecked(&mut values, 1, 1) };
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn test_debug_assertions_catch_out_of_bounds() {
+        let mut values = [1, 2, 3];
+        // SAFETY: deliberately broken — `b == len` — to show the debug
+        // assertion panics before the pointer leaves the slice.
+        let _ = unsafe { function_name_unchecked(&mut values, 0, 3) };
+    }
+}
@@ -0,0 +1,141 @@
+//! [Brief description of what this module does]
+//!
+//! [`function_name`] is plain Rust; `js_function_name` exports it to
+//! JavaScript as `{{ function_name | camel_case }}` and converts its
+//! [`ErrorName`] into a thrown JavaScript `Error`.
+//!
+//! Build with `wasm-pack build --target web` (or `--target nodejs`) given a
+//! `Cargo.toml` with:
+//!
+//! ```toml
+//! [lib]
+//! crate-type = ["cdylib", "rlib"]
+//!
+//! [dependencies]
+//! wasm-bindgen = "0.2"
+//!
+//! [dev-dependencies]
+//! js-sys = "0.3"
+//! wasm-bindgen-test = "0.3"
+//! ```
+//!
+//! The `#[test]`s run natively with `cargo test`; the `#[wasm_bindgen_test]`s
+//! need a JavaScript host and run with `wasm-pack test --node`, or with
+//! `cargo test --target wasm32-unknown-unknown` given a
+//! `.cargo/config.toml` with:
+//!
+//! ```toml
+//! [target.wasm32-unknown-unknown]
+//! runner = "wasm-bindgen-test-runner"
+//! ```
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+/// Longest input [`function_name`] accepts, in bytes.
+pub const MAX_INPUT: usize = MAX_LEN;
+
+/// Why [`function_name`] rejected its input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorName {
+    /// The input was empty.
+    Empty,
+    /// The input was longer than `max` bytes.
+    TooLong {
+        /// Length of the input, in bytes.
+        len: usize,
+        /// Longest input accepted, in bytes.
+        max: usize,
+    },
+}
+
+impl fmt::Display for ErrorName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("input cannot be empty"),
+            Self::TooLong { len, max } => {
+                write!(f, "input is {len} bytes, longer than {max}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ErrorName {}
+
+/// A JavaScript `Error` with the same message, which the exported function
+/// throws.
+impl From<ErrorName> for JsValue {
+    fn from(err: ErrorName) -> Self {
+        JsError::new(&err.to_string()).into()
+    }
+}
+
+/// [Brief description of what the function does]
+///
+/// # Errors
+///
+/// Returns [`ErrorName::Empty`] if `input` is empty, and
+/// [`ErrorName::TooLong`] if it is longer than [`MAX_INPUT`] bytes.
+pub fn function_name(input: &str) -> Result<u32, ErrorName> {
+    if input.is_empty() {
+        return Err(ErrorName::Empty);
+    }
+    if input.len() > MAX_INPUT {
+        return Err(ErrorName::TooLong {
+            len: input.len(),
+            max: MAX_INPUT,
+        });
+    }
+    Ok(input.split_whitespace().count() as u32)
+}
+
+/// JavaScript entry point for [`function_name`], which throws an `Error`
+/// where the Rust function returns one.
+///
+/// # Errors
+///
+/// Returns the [`ErrorName`] of [`function_name`] as a JavaScript `Error`.
+#[wasm_bindgen(js_name = {{ function_name | camel_case }})]
+pub fn js_function_name(input: &str) -> Result<u32, JsValue> {
+    Ok(function_name(input)?)
+}
+
+{{> partials/test-header}}
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[test]
+    fn test_function_name_success() {
+        assert_eq!(function_name("one two three"), Ok(3));
+    }
+
+    #[test]
+    fn test_function_name_rejects_empty_input() {
+        assert_eq!(function_name(""), Err(ErrorName::Empty));
+    }
+
+    #[test]
+    fn test_function_name_rejects_long_input() {
+        let len = MAX_INPUT + 1;
+        let err = function_name(&"a".repeat(len)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("input is {len} bytes, longer than {MAX_INPUT}")
+        );
+    }
+
+    // A `JsValue` only exists inside a JavaScript host, so these are
+    // skipped by a native `cargo test`.
+    #[wasm_bindgen_test]
+    fn test_exported_returns_result() {
+        assert_eq!(js_function_name("one two three").unwrap(), 3);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_exported_throws_error() {
+        let err = js_function_name("").unwrap_err();
+        let err = err.dyn_into::<js_sys::Error>().unwrap();
+        assert_eq!(String::from(err.message()), "input cannot be empty");
+    }
+}
@@ -0,0 +1,241 @@
+//! [Brief description of what this module does]
+//!
+//! [`PoolName`] runs jobs on a fixed set of worker threads. Jobs wait in a
+//! queue of [`QUEUE_CAPACITY`] slots, and [`PoolName::submit`] blocks
+//! while it is full, so a fast producer cannot run ahead of the workers.
+//! A job that panics is caught on its worker, which goes on to the next
+//! job; the panic is counted in the [`JoinReport`].
+//!
+//! [`PoolName::join`] closes the queue, lets the workers finish every job
+//! already submitted and waits for them to exit. Dropping the pool does
+//! the same, without the report.
+
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+
+/// Jobs that can wait for a free worker before `submit` blocks.
+pub const QUEUE_CAPACITY: usize = QUEUE_DEPTH;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// How the jobs of a pool ended, as returned by [`PoolName::join`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JoinReport {
+    /// Jobs that returned normally.
+    pub completed: usize,
+    /// Jobs that panicked.
+    pub panicked: usize,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    completed: AtomicUsize,
+    panicked: AtomicUsize,
+}
+
+/// A bounded pool of worker threads.
+#[derive(Debug)]
+pub struct PoolName {
+    sender: Option<SyncSender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+    counters: Arc<Counters>,
+}
+
+impl PoolName {
+    /// Spawns `workers` threads, named `<name>-<index>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first thread that cannot be spawned; the
+    /// threads already spawned are joined first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is zero.
+    pub fn new(name: &str, workers: usize) -> io::Result<Self> {
+        assert!(workers > 0, "a pool needs at least one worker");
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let mut pool = Self {
+            sender: Some(sender),
+            workers: Vec::with_capacity(workers),
+            counters: Arc::default(),
+        };
+        for index in 0..workers {
+            let receiver = Arc::clone(&receiver);
+            let counters = Arc::clone(&pool.counters);
+            let worker = thread::Builder::new()
+                .name(format!("{name}-{index}"))
+                .spawn(move || work(&receiver, &counters))?;
+            pool.workers.push(worker);
+        }
+        Ok(pool)
+    }
+
+    /// Queues `job` for the next free worker, blocking while the queue is
+    /// full.
+    pub fn submit<F>(&self, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let sender = self.sender.as_ref().expect("the pool is open");
+        // Workers catch panics and only exit once the sender is gone, so
+        // the receiver outlives every `submit`.
+        sender
+            .send(Box::new(job))
+            .expect("workers run until the pool is joined");
+    }
+
+    /// Closes the queue, waits for the submitted jobs to finish and
+    /// reports how they ended.
+    pub fn join(mut self) -> JoinReport {
+        self.shutdown();
+        JoinReport {
+            completed: self.counters.completed.load(Ordering::SeqCst),
+            panicked: self.counters.panicked.load(Ordering::SeqCst),
+        }
+    }
+
+    fn shutdown(&mut self) {
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            // Job panics are caught, so a worker only panics on a bug here.
+            worker.join().expect("worker thread panicked");
+        }
+    }
+}
+
+impl Drop for PoolName {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Runs jobs from `receiver` until the queue is closed and empty.
+fn work(receiver: &Mutex<Receiver<Job>>, counters: &Counters) {
+    loop {
+        // The guard is dropped at the end of the statement, so other
+        // workers can take jobs while this one runs its own.
+        let next = receiver
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .recv();
+        let Ok(job) = next else {
+            return;
+        };
+        let counter = match panic::catch_unwind(AssertUnwindSafe(job)) {
+            Ok(()) => &counters.completed,
+            Err(_) => &counters.panicked,
+        };
+        counter.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+{{> partials/test-header}}
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    fn pool(workers: usize) -> PoolName {
+        PoolName::new("test", workers).unwrap()
+    }
+
+    #[test]
+    fn test_runs_every_job_in_any_order() {
+        let pool = pool(4);
+        let (sender, receiver) = mpsc::channel();
+        for index in 0..100 {
+            let sender = sender.clone();
+            pool.submit(move || sender.send(index).unwrap());
+        }
+        drop(sender);
+        let report = pool.join();
+        let seen: HashSet<i32> = receiver.iter().collect();
+        assert_eq!(seen, (0..100).collect());
+        assert_eq!(
+            report,
+            JoinReport {
+                completed: 100,
+                panicked: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_runs_at_most_one_job_per_worker() {
+        let pool = pool(3);
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        for _ in 0..30 {
+            let active = Arc::clone(&active);
+            let peak = Arc::clone(&peak);
+            pool.submit(move || {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(2));
+                active.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+        pool.join();
+        assert!((1..=3).contains(&peak.load(Ordering::SeqCst)));
+    }
+
+    #[test]
+    fn test_worker_survives_panicking_job() {
+        let pool = pool(1);
+        let done = Arc::new(AtomicUsize::new(0));
+        pool.submit(|| panic!("job failed"));
+        let counter = Arc::clone(&done);
+        pool.submit(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        let report = pool.join();
+        assert_eq!(done.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            report,
+            JoinReport {
+                completed: 1,
+                panicked: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_join_waits_for_queued_jobs() {
+        let pool = pool(2);
+        let done = Arc::new(AtomicUsize::new(0));
+        for _ in 0..10 {
+            let done = Arc::clone(&done);
+            pool.submit(move || {
+                thread::sleep(Duration::from_millis(5));
+                done.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        assert_eq!(pool.join().completed, 10);
+        assert_eq!(done.load(Ordering::SeqCst), 10);
+    }
+
+    #[test]
+    fn test_drop_joins_workers() {
+        let done = Arc::new(AtomicUsize::new(0));
+        {
+            let pool = pool(2);
+            for _ in 0..10 {
+                let done = Arc::clone(&done);
+                pool.submit(move || {
+                    done.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+        }
+        assert_eq!(done.load(Ordering::SeqCst), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "a pool needs at least one worker")]
+    fn test_new_rejects_zero_workers() {
+        let _ = pool(0);
+    }
+}
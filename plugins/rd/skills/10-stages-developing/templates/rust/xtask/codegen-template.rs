@@ -0,0 +1,157 @@
+//! `cargo xtask codegen`: regenerates `{{ output }}` from the table below.
+//!
+//! The generated file is checked in, so building the workspace needs no
+//! generation step. CI runs `cargo xtask codegen --check`, which fails if
+//! the table changed without the file being regenerated, or the file was
+//! edited by hand.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::Result;
+
+/// The generated file, relative to the workspace root.
+const OUTPUT: &str = "{{ output }}";
+
+/// What the file is generated from; replace with the project's own source
+/// of truth, e.g. a grammar or a list of error codes.
+const KEYWORDS: &[&str] = &["select", "from", "where", "order", "by", "limit"];
+
+/// Whether the task writes the generated file or only checks it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Write,
+    Check,
+}
+
+impl Mode {
+    /// The mode `args` ask for: [`Mode::Check`] with `--check`.
+    ///
+    /// # Errors
+    ///
+    /// Any other argument.
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let mut mode = Mode::Write;
+        for arg in args {
+            match arg.as_str() {
+                "--check" => mode = Mode::Check,
+                _ => return Err(format!("unknown argument `{arg}` to codegen").into()),
+            }
+        }
+        Ok(mode)
+    }
+}
+
+/// Regenerates the file under the workspace `root`, or checks it is up to
+/// date.
+///
+/// # Errors
+///
+/// In [`Mode::Check`], a file that differs from what would be generated;
+/// otherwise a failure to read or write it.
+pub fn run(root: &Path, mode: Mode) -> Result<()> {
+    let path = root.join(OUTPUT);
+    let changed = ensure_file(&path, &generate(KEYWORDS), mode)?;
+    if changed {
+        println!("updated {}", path.display());
+    } else {
+        println!("{} is up to date", path.display());
+    }
+    Ok(())
+}
+
+/// The generated Rust source: the keywords sorted and deduplicated, so the
+/// generated lookup can binary search them.
+fn generate(keywords: &[&str]) -> String {
+    let mut keywords = keywords.to_vec();
+    keywords.sort_unstable();
+    keywords.dedup();
+
+    let mut out = String::from("//! Generated by `cargo xtask codegen`; do not edit.\n\n");
+    // One keyword a line, to keep diffs of the generated file small.
+    out.push_str("/// The keywords, sorted.\n#[rustfmt::skip]\n");
+    out.push_str("pub const KEYWORDS: &[&str] = &[\n");
+    for keyword in keywords {
+        out.push_str(&format!("    {keyword:?},\n"));
+    }
+    out.push_str(
+        "];\n\n\
+         /// Whether `word` is a keyword.\n\
+         pub fn is_keyword(word: &str) -> bool {\n    \
+         KEYWORDS.binary_search(&word).is_ok()\n\
+         }\n",
+    );
+    out
+}
+
+/// Makes `path` hold `contents`, returning whether it had to be written.
+/// In [`Mode::Check`] a file that differs is an error instead.
+fn ensure_file(path: &Path, contents: &str, mode: Mode) -> Result<bool> {
+    let with_path = |err: io::Error| format!("{}: {err}", path.display());
+    match fs::read_to_string(path) {
+        Ok(current) if current == contents => return Ok(false),
+        Ok(_) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(with_path(err).into()),
+    }
+    if mode == Mode::Check {
+        let stale = format!(
+            "{} is out of date; run `cargo xtask codegen`",
+            path.display()
+        );
+        return Err(stale.into());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(with_path)?;
+    }
+    fs::write(path, contents).map_err(with_path)?;
+    Ok(true)
+}
+
+{{> partials/test-header}}
+
+    #[test]
+    fn test_generate_sorts_and_dedups() {
+        let source = generate(&["where", "from", "where"]);
+        assert!(source.contains("&[\n    \"from\",\n    \"where\",\n];"));
+        assert!(source.starts_with("//! Generated by `cargo xtask codegen`"));
+    }
+
+    #[test]
+    fn test_write_then_check() {
+        let root = tempfile::tempdir().unwrap();
+        run(root.path(), Mode::Write).unwrap();
+        let generated = fs::read_to_string(root.path().join(OUTPUT)).unwrap();
+        assert_eq!(generated, generate(KEYWORDS));
+
+        run(root.path(), Mode::Check).unwrap();
+        assert!(!ensure_file(&root.path().join(OUTPUT), &generated, Mode::Write).unwrap());
+    }
+
+    #[test]
+    fn test_check_fails_on_stale_or_missing_file() {
+        let root = tempfile::tempdir().unwrap();
+        let err = run(root.path(), Mode::Check).unwrap_err().to_string();
+        assert!(err.ends_with("is out of date; run `cargo xtask codegen`"));
+        assert!(!root.path().join(OUTPUT).exists());
+
+        run(root.path(), Mode::Write).unwrap();
+        let path = root.path().join(OUTPUT);
+        fs::write(&path, "// edited by hand\n").unwrap();
+        assert!(run(root.path(), Mode::Check).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "// edited by hand\n");
+    }
+
+    fn mode(args: &[&str]) -> Result<Mode> {
+        let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+        Mode::from_args(&args)
+    }
+
+    #[test]
+    fn test_mode_from_args() {
+        assert_eq!(mode(&[]).unwrap(), Mode::Write);
+        assert_eq!(mode(&["--check"]).unwrap(), Mode::Check);
+        assert!(mode(&["--check", "--verbose"]).is_err());
+    }
+}
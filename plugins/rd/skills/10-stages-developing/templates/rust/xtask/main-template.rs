@@ -0,0 +1,110 @@
+//! Project automation for the workspace, run as `cargo xtask <task>`.
+//!
+//! Each task is a module with a `run` function; [`dispatch`] maps the task
+//! name on the command line to it. Tasks are plain Rust over `std`, so they
+//! run the same on every platform and build quickly.
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+mod codegen;
+
+/// What a task fails with; the message is all that is shown.
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const HELP: &str = "\
+Usage: cargo xtask <task> [args]
+
+Tasks:
+  codegen [--check]  Regenerate generated files, or with --check fail if they are stale
+  help               Show this message
+";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match dispatch(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs the task named by `args[0]` with the rest of `args`.
+fn dispatch(args: &[String]) -> Result<()> {
+    let Some((task, args)) = args.split_first() else {
+        print!("{HELP}");
+        return Ok(());
+    };
+    match task.as_str() {
+        "codegen" => {
+            let mode = codegen::Mode::from_args(args)?;
+            codegen::run(&workspace_root()?, mode)
+        }
+        "help" | "--help" | "-h" => {
+            print!("{HELP}");
+            Ok(())
+        }
+        _ => Err(format!("unknown task `{task}`; run `cargo xtask help`").into()),
+    }
+}
+
+/// The root of the workspace this crate belongs to: the nearest directory
+/// above it whose `Cargo.toml` has a `[workspace]` table. Tasks resolve
+/// paths against it, whatever directory `cargo xtask` was run from.
+fn workspace_root() -> Result<PathBuf> {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    manifest_dir
+        .ancestors()
+        .skip(1)
+        .find(|dir| is_workspace_root(dir))
+        .map(Path::to_path_buf)
+        .ok_or_else(|| format!("{} is not in a workspace", manifest_dir.display()).into())
+}
+
+fn is_workspace_root(dir: &Path) -> bool {
+    fs::read_to_string(dir.join("Cargo.toml"))
+        .is_ok_and(|manifest| manifest.lines().any(|line| line.trim() == "[workspace]"))
+}
+
+{{> partials/test-header}}
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_dispatch_without_task_shows_help() {
+        assert!(dispatch(&[]).is_ok());
+        assert!(dispatch(&args(&["help"])).is_ok());
+    }
+
+    #[test]
+    fn test_dispatch_rejects_unknown_task() {
+        let err = dispatch(&args(&["deploy"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown task `deploy`; run `cargo xtask help`"
+        );
+    }
+
+    #[test]
+    fn test_dispatch_rejects_unknown_task_argument() {
+        let err = dispatch(&args(&["codegen", "--force"])).unwrap_err();
+        assert_eq!(err.to_string(), "unknown argument `--force` to codegen");
+    }
+
+    #[test]
+    fn test_is_workspace_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        fs::write(&manifest, "[package]\nname = \"app\"\n").unwrap();
+        assert!(!is_workspace_root(dir.path()));
+        fs::write(&manifest, "[workspace]\nmembers = [\"xtask\"]\n").unwrap();
+        assert!(is_workspace_root(dir.path()));
+    }
+}